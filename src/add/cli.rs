@@ -139,7 +139,7 @@ pub(crate) struct AddArgs {
 
     /// Override the executable bit of the added files. The executable bit is only changed in the index, the files on disk are left
     /// unchanged.
-    #[arg(long)]
+    #[arg(long, allow_hyphen_values = true)]
     pub chmod: Option<ChmodFlag>,
 
     /// Pathspec is passed in <file> instead of commandline args. If <file> is exactly - then standard input is used. Pathspec elements
@@ -1,12 +1,13 @@
-use std::{
-    fs::{self, Metadata},
-    path::Path,
-};
+use std::fs::{self, Metadata};
 
 use crate::{
     command::GitCommand,
-    index::GitIndexEntry,
-    repo::{GitRepo, RepoState},
+    ignore::GitIgnore,
+    index::{GitIndexEntry, GitIndexMode, GitIndexStageFlag},
+    object::GitObjectType,
+    options::ChmodFlag,
+    pathspec::{read_pathspec_from_file, Pathspec, PathspecOptions},
+    repo::{GitRepo, GitRepoPath, RepoState},
     RustGitError,
 };
 
@@ -14,37 +15,122 @@ use super::cli::AddArgs;
 
 pub(crate) struct AddCommand {
     args: AddArgs,
+    pathspec_options: PathspecOptions,
     // TODO: add base args
 }
 
 impl AddCommand {
-    pub fn new(args: AddArgs) -> AddCommand {
-        AddCommand { args }
+    pub fn new(args: AddArgs, pathspec_options: PathspecOptions) -> AddCommand {
+        AddCommand {
+            args,
+            pathspec_options,
+        }
     }
-}
-
-fn process_path(path: &str, repo: &mut GitRepo) -> Result<(), RustGitError> {
-    let metadata = fs::metadata(path)?;
 
-    if metadata.is_dir() {
-        for dir_item in fs::read_dir(path)? {
-            process_path(dir_item?.path().to_str().unwrap(), repo)?
+    /// Returns the pathspec elements, given as arguments or read from --pathspec-from-file.
+    fn pathspec_elements(&self) -> Result<Vec<String>, RustGitError> {
+        match &self.args.pathspec_from_file {
+            Some(file) => read_pathspec_from_file(file, self.args.pathspec_file_nul),
+            None => Ok(self.args.pathspec.clone()),
         }
-    } else {
-        add_one_path(path, metadata, repo)?;
     }
 
-    Ok(())
+    /// Adds the files under the provided directory of the worktree which match the pathspec.
+    /// Untracked files which are ignored are skipped unless --force is set, and collected in
+    /// `ignored` when the pathspec names them exactly.
+    fn add_dir(
+        &self,
+        dir: &str,
+        repo: &mut GitRepo,
+        pathspec: &mut Pathspec,
+        ignore: &mut GitIgnore,
+        added: &mut Vec<GitRepoPath>,
+        ignored: &mut Vec<String>,
+    ) -> Result<(), RustGitError> {
+        let mut dir_entries = fs::read_dir(repo.root_dir.join(dir))?
+            .map(|dir_entry| Ok(dir_entry?.file_name()))
+            .collect::<Result<Vec<_>, RustGitError>>()?;
+        dir_entries.sort();
+
+        for name in dir_entries {
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            if name == ".git" {
+                continue;
+            }
+            let path = format!("{dir}{name}");
+            // Symbolic links are added as links, even when they point to directories.
+            let metadata = fs::symlink_metadata(repo.root_dir.join(&path))?;
+            let is_dir = metadata.is_dir();
+            if is_dir && !pathspec.may_match_under(&path) {
+                continue;
+            }
+            if !is_dir && pathspec.matches(&path).is_none() {
+                continue;
+            }
+
+            if !self.args.force && ignore.is_ignored(&path, is_dir)? && !repo.is_tracked(&path) {
+                if let Some(item) = pathspec.exact_item(&path) {
+                    ignored.push(item.original.clone());
+                } else if is_dir {
+                    // Files named explicitly inside an ignored directory are reported too.
+                    let dir_prefix = format!("{path}/");
+                    ignored.extend(
+                        pathspec
+                            .items
+                            .iter()
+                            .filter(|item| {
+                                !item.exclude
+                                    && !item.has_wildcards()
+                                    && item.path.starts_with(&dir_prefix)
+                                    && repo.root_dir.join(&item.path).exists()
+                            })
+                            .map(|item| item.original.clone()),
+                    );
+                }
+                continue;
+            }
+
+            if is_dir {
+                self.add_dir(&format!("{path}/"), repo, pathspec, ignore, added, ignored)?;
+            } else {
+                let repo_path = GitRepoPath::deserialize(path.as_bytes())?;
+                add_one_path(&repo_path, metadata, repo, self.args.intent_to_add)?;
+                added.push(repo_path);
+            }
+        }
+
+        Ok(())
+    }
 }
 
-fn add_one_path(path: &str, metadata: Metadata, repo: &mut GitRepo) -> Result<(), RustGitError> {
-    let path = Path::new(path);
-    // Write object file.
-    let obj_id = repo.index_path(&path, &metadata)?;
+fn add_one_path(
+    repo_path: &GitRepoPath,
+    metadata: Metadata,
+    repo: &mut GitRepo,
+    intent_to_add: bool,
+) -> Result<(), RustGitError> {
+    // Write object file. Paths added with --intent-to-add only record the empty blob, and are
+    // left alone if they're already in the index.
+    let obj_id = if intent_to_add {
+        if repo.is_tracked(&repo_path.as_string()) {
+            return Ok(());
+        }
+        repo.hash_obj(GitObjectType::Blob, String::new(), true)?
+    } else {
+        repo.index_path(&repo.root_dir.join(repo_path.as_string()), &metadata)?
+    };
 
-    // Make new index entry.
-    let repo_path = repo.path_to_git_repo_path(Path::new(path))?;
-    let index_entry = GitIndexEntry::new(&repo_path, &metadata, obj_id);
+    // Make new index entry, keeping the recorded mode where the filesystem can't represent it.
+    let existing_mode = repo
+        .index
+        .entry_position(repo_path, GitIndexStageFlag::RegularFileNoConflict)
+        .map(|position| repo.index.entry_at(position).mode.clone());
+    let mut index_entry = GitIndexEntry::new(repo_path, &metadata, obj_id);
+    index_entry.mode =
+        GitIndexMode::from_worktree(&metadata, existing_mode.as_ref(), &repo.config.core);
+    index_entry.extended_flags.intent_to_add = intent_to_add;
 
     // Update index.
     repo.index.add(index_entry);
@@ -52,16 +138,99 @@ fn add_one_path(path: &str, metadata: Metadata, repo: &mut GitRepo) -> Result<()
     Ok(())
 }
 
+/// Overrides the executable bit of the index entries of the provided paths, which must be
+/// regular files. Returns whether all the entries could be updated.
+fn chmod_paths(repo: &mut GitRepo, paths: &[GitRepoPath], chmod: &ChmodFlag) -> bool {
+    let (mode, flag) = match chmod {
+        ChmodFlag::Executable => (GitIndexMode::RegularFile0755, "+x"),
+        ChmodFlag::NonExecutable => (GitIndexMode::RegularFile0644, "-x"),
+    };
+
+    let mut all_updated = true;
+    for path in paths {
+        let position = repo
+            .index
+            .entry_position(path, GitIndexStageFlag::RegularFileNoConflict)
+            .filter(|&position| {
+                matches!(
+                    repo.index.entry_at(position).mode,
+                    GitIndexMode::RegularFile0644 | GitIndexMode::RegularFile0755
+                )
+            });
+        match position {
+            Some(position) => repo
+                .index
+                .update_entry_at(position, |entry| entry.mode = mode.clone()),
+            None => {
+                eprintln!("error: cannot chmod {flag} '{}'", path.as_string());
+                all_updated = false;
+            }
+        }
+    }
+    all_updated
+}
+
 impl GitCommand for AddCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
         let mut repo = repo_state.try_get()?;
+        repo.lock_index()?;
+
+        let mut pathspec = Pathspec::parse(&repo, &self.pathspec_elements()?, self.pathspec_options)?;
+        if pathspec.is_empty() {
+            eprintln!("Nothing specified, nothing added.");
+            eprintln!("hint: Maybe you wanted to say 'git add .'?");
+            eprintln!("hint: Turn this message off by running");
+            eprintln!("hint: \"git config advice.addEmptyPathspec false\"");
+            return Ok(());
+        }
 
-        for file_path in &self.args.pathspec {
-            process_path(file_path, &mut repo)?;
+        if self.args.refresh {
+            repo.refresh_index(false, |entry| {
+                pathspec.matches(&entry.path_name.as_string()).is_some()
+            })?;
+            repo.write_index()?;
+            return Ok(());
         }
 
+        let mut ignore = repo.standard_ignore()?;
+        let mut added = vec![];
+        let mut ignored = vec![];
+        self.add_dir("", &mut repo, &mut pathspec, &mut ignore, &mut added, &mut ignored)?;
+
+        // Items naming files which don't exist are errors, unlike those which only named
+        // ignored files.
+        for (item, _) in pathspec.seen().filter(|(item, seen)| {
+            seen.is_none() && !item.exclude && !item.has_wildcards() && !ignored.contains(&item.original)
+        }) {
+            if !repo.root_dir.join(&item.path).exists() {
+                return Err(RustGitError::new(format!(
+                    "fatal: pathspec '{}' did not match any files",
+                    item.original
+                )));
+            }
+        }
+
+        let chmod_updated = match &self.args.chmod {
+            Some(chmod) => chmod_paths(&mut repo, &added, chmod),
+            None => true,
+        };
+
         repo.write_index()?;
 
+        if !ignored.is_empty() {
+            eprintln!("The following paths are ignored by one of your .gitignore files:");
+            for path in &ignored {
+                eprintln!("{path}");
+            }
+            eprintln!("hint: Use -f if you really want to add them.");
+            eprintln!("hint: Turn this message off by running");
+            eprintln!("hint: \"git config advice.addIgnoredFile false\"");
+        }
+
+        // Like C Git, the added files are saved even if some were ignored or couldn't be chmodded.
+        if !chmod_updated || !ignored.is_empty() {
+            return Err(RustGitError::new(""));
+        }
         Ok(())
     }
 }
@@ -0,0 +1,164 @@
+// Attributes of paths read from .gitattributes files and $GIT_DIR/info/attributes, based on
+// C Git's implementation:
+// https://github.com/git/git/blob/master/attr.c
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::ignore::{parent_dirs, GitIgnorePattern};
+
+/// Name of the per-directory files holding attributes.
+pub(crate) const GITATTRIBUTES_FILE_NAME: &str = ".gitattributes";
+
+/// The state of an attribute for a path.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum GitAttrValue {
+    /// Set with `name`.
+    Set,
+    /// Unset with `-name`.
+    Unset,
+    /// Set to a value with `name=value`.
+    Value(String),
+    /// Not mentioned for the path, or reset with `!name`.
+    Unspecified,
+}
+
+impl GitAttrValue {
+    /// Parses an attribute as written in an attributes file, returning its name and value.
+    pub(crate) fn parse(attr: &str) -> (&str, GitAttrValue) {
+        if let Some(name) = attr.strip_prefix('-') {
+            (name, GitAttrValue::Unset)
+        } else if let Some(name) = attr.strip_prefix('!') {
+            (name, GitAttrValue::Unspecified)
+        } else if let Some((name, value)) = attr.split_once('=') {
+            (name, GitAttrValue::Value(String::from(value)))
+        } else {
+            (attr, GitAttrValue::Set)
+        }
+    }
+}
+
+/// A line of an attributes file: the attributes given to the paths matching a pattern.
+struct GitAttrRule {
+    pattern: GitIgnorePattern,
+    attrs: Vec<(String, GitAttrValue)>,
+}
+
+/// Parses the rules of an attributes file.
+fn parse_rules(contents: &str, source: &str, base: &str) -> Vec<GitAttrRule> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next()?;
+            // Negative patterns are ignored, and macros aren't supported.
+            if pattern.starts_with('!') || pattern.starts_with("[attr]") {
+                return None;
+            }
+            let pattern = GitIgnorePattern::parse(pattern, source, idx + 1, base)?;
+            let mut attrs = vec![];
+            for (name, value) in fields.map(GitAttrValue::parse) {
+                // The built-in "binary" macro unsets the attributes which treat files as text.
+                if name == "binary" && value == GitAttrValue::Set {
+                    for implied in ["diff", "merge", "text"] {
+                        attrs.push((String::from(implied), GitAttrValue::Unset));
+                    }
+                }
+                attrs.push((String::from(name), value));
+            }
+            Some(GitAttrRule { pattern, attrs })
+        })
+        .collect()
+}
+
+/// The attributes of the paths of a worktree. From highest to lowest precedence, attributes
+/// come from `info/attributes` in the git directory and the per-directory files, deeper
+/// directories first. Within a file, later lines override earlier ones.
+pub(crate) struct GitAttributes {
+    root_dir: PathBuf,
+    casefold: bool,
+    info: Vec<GitAttrRule>,
+    /// The rules of the per-directory files, keyed by their directory, read when first needed.
+    per_dir: HashMap<String, Vec<GitAttrRule>>,
+}
+
+impl GitAttributes {
+    pub(crate) fn new(root_dir: &Path, git_dir: &Path, casefold: bool) -> GitAttributes {
+        let info = fs::read_to_string(git_dir.join("info").join("attributes"))
+            .map(|contents| parse_rules(&contents, "info/attributes", ""))
+            .unwrap_or_default();
+        GitAttributes {
+            root_dir: root_dir.to_path_buf(),
+            casefold,
+            info,
+            per_dir: HashMap::new(),
+        }
+    }
+
+    /// Returns the value of the attribute for the path, which is relative to the root of the
+    /// repo.
+    pub(crate) fn get(&mut self, path: &str, name: &str) -> GitAttrValue {
+        for dir in parent_dirs(path) {
+            if !self.per_dir.contains_key(dir) {
+                let source = format!("{dir}{GITATTRIBUTES_FILE_NAME}");
+                let rules = fs::read_to_string(self.root_dir.join(&source))
+                    .map(|contents| parse_rules(&contents, &source, dir))
+                    .unwrap_or_default();
+                self.per_dir.insert(String::from(dir), rules);
+            }
+        }
+
+        let casefold = self.casefold;
+        std::iter::once(&self.info)
+            .chain(parent_dirs(path).filter_map(|dir| self.per_dir.get(dir)))
+            .flat_map(|rules| rules.iter().rev())
+            .filter(|rule| rule.pattern.matches(path, false, casefold))
+            .find_map(|rule| {
+                rule.attrs
+                    .iter()
+                    .rev()
+                    .find(|(attr_name, _)| attr_name == name)
+                    .map(|(_, value)| value.clone())
+            })
+            .unwrap_or(GitAttrValue::Unspecified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_prefer_later_and_deeper_rules() {
+        let temp_dir = std::env::temp_dir().join(format!("rust-git-attr-{}", std::process::id()));
+        fs::create_dir_all(temp_dir.join("sub")).unwrap();
+        fs::write(
+            temp_dir.join(GITATTRIBUTES_FILE_NAME),
+            "*.c diff=cpp text\n*.png binary\nlib.c -text\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("sub").join(GITATTRIBUTES_FILE_NAME),
+            "*.c !diff\n",
+        )
+        .unwrap();
+
+        let mut attributes = GitAttributes::new(&temp_dir, &temp_dir.join(".git"), false);
+        assert_eq!(
+            attributes.get("a.c", "diff"),
+            GitAttrValue::Value(String::from("cpp"))
+        );
+        assert_eq!(attributes.get("a.c", "text"), GitAttrValue::Set);
+        assert_eq!(attributes.get("lib.c", "text"), GitAttrValue::Unset);
+        assert_eq!(attributes.get("sub/a.c", "diff"), GitAttrValue::Unspecified);
+        assert_eq!(attributes.get("sub/a.c", "text"), GitAttrValue::Set);
+        assert_eq!(attributes.get("a.png", "diff"), GitAttrValue::Unset);
+        assert_eq!(attributes.get("a.txt", "diff"), GitAttrValue::Unspecified);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
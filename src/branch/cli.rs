@@ -0,0 +1,143 @@
+use clap::Args;
+
+use crate::color::ColorWhen;
+
+#[derive(Args, Debug)]
+#[command(about = "List, create, or delete branches")]
+#[command(long_about = "
+If --list is given, or if there are no non-option arguments, existing branches are listed; the current branch
+will be highlighted in green and marked with an asterisk. Option -r causes the remote-tracking branches to be
+listed, and option -a shows both local and remote branches.
+
+If a <pattern> is given, it is used as a shell wildcard to restrict the output to matching branches. If
+multiple patterns are given, a branch is shown if it matches any of the patterns.
+
+With --contains, shows only the branches that contain the named commit (in other words, the branches whose tip
+commits are descendants of the named commit), --no-contains inverts it. With --merged, only branches merged
+into the named commit (i.e. the branches whose tip commits are reachable from the named commit) will be
+listed. With --no-merged only branches not merged into the named commit will be listed. If the <commit>
+argument is missing it defaults to HEAD (i.e. the tip of the current branch).
+
+The command's second form creates a new branch head named <branchname> which points to the current HEAD, or
+<start-point> if given.
+
+With a -m or -M option, <oldbranch> will be renamed to <newbranch>. If <oldbranch> had a corresponding reflog,
+it is renamed to match <newbranch>, and a reflog entry is created to remember the branch renaming. If
+<newbranch> exists, -M must be used to force the rename to happen.
+
+The -c and -C options have the exact same semantics as -m and -M, except instead of the branch being renamed,
+it will be copied to a new name, along with its config and reflog.
+
+With a -d or -D option, <branchname> will be deleted. You may specify more than one branch for deletion. If
+the branch currently has a reflog then the reflog will also be deleted.
+")]
+pub(crate) struct BranchArgs {
+    /// Branch names or, when listing, patterns to filter branches by.
+    #[clap(value_name("branchname"))]
+    pub names: Vec<String>,
+
+    /// Delete a branch. The branch must be fully merged in its upstream branch, or in HEAD if no upstream was
+    /// set with --track or --set-upstream-to.
+    #[arg(short, long)]
+    pub delete: bool,
+
+    /// Shortcut for --delete --force.
+    #[arg(short = 'D')]
+    pub force_delete: bool,
+
+    /// Reset <branchname> to <start-point>, even if <branchname> exists already. Without -f, git branch refuses
+    /// to change an existing branch. In combination with -d (or --delete), allow deleting the branch
+    /// irrespective of its merged status. In combination with -m (or --move), allow renaming the branch even if
+    /// the new branch name already exists, the same applies for -c (or --copy).
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Move/rename a branch, together with its config and reflog.
+    #[arg(short, long = "move")]
+    pub move_branch: bool,
+
+    /// Shortcut for --move --force.
+    #[arg(short = 'M')]
+    pub force_move: bool,
+
+    /// Copy a branch, together with its config and reflog.
+    #[arg(short, long)]
+    pub copy: bool,
+
+    /// Shortcut for --copy --force.
+    #[arg(short = 'C')]
+    pub force_copy: bool,
+
+    /// List branches. With optional <pattern>..., e.g. git branch --list 'maint-*', list only the branches
+    /// that match the pattern(s).
+    #[arg(short, long)]
+    pub list: bool,
+
+    /// List or delete (if used with -d) the remote-tracking branches.
+    #[arg(short, long)]
+    pub remotes: bool,
+
+    /// List both remote-tracking branches and local branches.
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// When in list mode, show sha1 and commit subject line for each head, along with relationship to upstream
+    /// branch (if any). If given twice, print the path of the linked worktree (if any) and the name of the
+    /// upstream branch, as well.
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Be more quiet when creating or deleting a branch, suppressing non-error messages.
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Print the name of the current branch. In detached HEAD state, nothing is printed.
+    #[arg(long)]
+    pub show_current: bool,
+
+    /// Only list branches which contain the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub contains: Vec<String>,
+
+    /// Only list branches which don't contain the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub no_contains: Vec<String>,
+
+    /// Only list branches whose tips are reachable from the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub merged: Option<String>,
+
+    /// Only list branches whose tips are not reachable from the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub no_merged: Option<String>,
+
+    /// Set up <branchname>'s tracking information so <upstream> is considered <branchname>'s upstream branch.
+    /// If no <branchname> is specified, then it defaults to the current branch.
+    #[arg(short = 'u', long, value_name = "upstream")]
+    pub set_upstream_to: Option<String>,
+
+    /// Remove the upstream information for <branchname>. If no branch is specified it defaults to the current
+    /// branch.
+    #[arg(long)]
+    pub unset_upstream: bool,
+
+    /// Sorting and filtering branches are case insensitive.
+    #[arg(long)]
+    pub ignore_case: bool,
+
+    /// Color branches to highlight current, local, and remote-tracking branches. The value must be always (the
+    /// default when omitted), never, or auto.
+    #[arg(
+        long,
+        value_name = "when",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always",
+        overrides_with = "no_color"
+    )]
+    pub color: Option<ColorWhen>,
+
+    /// Turn off branch colors, even when the configuration file gives the default to color output.
+    #[arg(long, overrides_with = "color")]
+    pub no_color: bool,
+}
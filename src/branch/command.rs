@@ -0,0 +1,664 @@
+use crate::{
+    color::{BranchColor, ColorWhen, Palette},
+    command::GitCommand,
+    config::BranchConfig,
+    object::GitObjectId,
+    ref_filter::{
+        shorten_ref_name, AheadBehind, PatternMatch, RefDetails, RefFilter, RefFormat, RefItem,
+    },
+    refs::{is_valid_ref_name, GitHead, GitRef},
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
+
+use super::cli::BranchArgs;
+
+const HEADS_PREFIX: &str = "refs/heads/";
+const REMOTES_PREFIX: &str = "refs/remotes/";
+
+pub(crate) struct ListBranches {
+    patterns: Vec<String>,
+    local: bool,
+    remote: bool,
+    verbose: u8,
+    contains: Vec<String>,
+    no_contains: Vec<String>,
+    merged: Option<String>,
+    no_merged: Option<String>,
+    ignore_case: bool,
+    color: Option<ColorWhen>,
+}
+
+pub(crate) struct CreateBranch {
+    name: String,
+    start_point: Option<String>,
+    force: bool,
+    quiet: bool,
+}
+
+pub(crate) struct DeleteBranches {
+    names: Vec<String>,
+    force: bool,
+    remotes: bool,
+    quiet: bool,
+}
+
+pub(crate) struct MoveBranch {
+    old_name: Option<String>,
+    new_name: String,
+    force: bool,
+    copy: bool,
+}
+
+pub(crate) struct SetUpstream {
+    upstream: String,
+    branch: Option<String>,
+    quiet: bool,
+}
+
+pub(crate) struct UnsetUpstream {
+    branch: Option<String>,
+}
+
+pub(crate) enum BranchCommand {
+    List(ListBranches),
+    Create(CreateBranch),
+    Delete(DeleteBranches),
+    Move(MoveBranch),
+    SetUpstream(SetUpstream),
+    UnsetUpstream(UnsetUpstream),
+    ShowCurrent,
+}
+
+impl BranchCommand {
+    pub fn new(args: BranchArgs) -> Result<BranchCommand, RustGitError> {
+        let delete = args.delete || args.force_delete;
+        let move_branch = args.move_branch || args.force_move;
+        let copy = args.copy || args.force_copy;
+        let has_filter = !args.contains.is_empty()
+            || !args.no_contains.is_empty()
+            || args.merged.is_some()
+            || args.no_merged.is_some()
+            || args.verbose > 0;
+        let list = args.list || has_filter;
+
+        let modes = [
+            delete,
+            move_branch,
+            copy,
+            args.set_upstream_to.is_some(),
+            args.unset_upstream,
+            args.show_current,
+            list,
+        ];
+        if modes.iter().filter(|mode| **mode).count() > 1 {
+            return Err(RustGitError::new(
+                "fatal: options for listing, creating, deleting, renaming and copying branches cannot be combined",
+            ));
+        }
+
+        if delete {
+            if args.names.is_empty() {
+                return Err(RustGitError::new("fatal: branch name required"));
+            }
+            return Ok(BranchCommand::Delete(DeleteBranches {
+                names: args.names,
+                force: args.force_delete || args.force,
+                remotes: args.remotes,
+                quiet: args.quiet,
+            }));
+        }
+
+        if move_branch || copy {
+            let mut names = args.names.into_iter();
+            let (old_name, new_name) = match (names.next(), names.next(), names.next()) {
+                (Some(new_name), None, None) => (None, new_name),
+                (Some(old_name), Some(new_name), None) => (Some(old_name), new_name),
+                (None, _, _) => return Err(RustGitError::new("fatal: branch name required")),
+                _ => {
+                    let operation = if copy { "copy" } else { "rename" };
+                    return Err(RustGitError::new(format!(
+                        "fatal: too many arguments for a {operation} operation"
+                    )));
+                }
+            };
+            return Ok(BranchCommand::Move(MoveBranch {
+                old_name,
+                new_name,
+                force: args.force_move || args.force_copy || args.force,
+                copy,
+            }));
+        }
+
+        if let Some(upstream) = args.set_upstream_to {
+            if args.names.len() > 1 {
+                return Err(RustGitError::new(
+                    "fatal: too many arguments to set new upstream",
+                ));
+            }
+            return Ok(BranchCommand::SetUpstream(SetUpstream {
+                upstream,
+                branch: args.names.into_iter().next(),
+                quiet: args.quiet,
+            }));
+        }
+
+        if args.unset_upstream {
+            if args.names.len() > 1 {
+                return Err(RustGitError::new(
+                    "fatal: too many arguments to unset upstream",
+                ));
+            }
+            return Ok(BranchCommand::UnsetUpstream(UnsetUpstream {
+                branch: args.names.into_iter().next(),
+            }));
+        }
+
+        if args.show_current {
+            return Ok(BranchCommand::ShowCurrent);
+        }
+
+        if list || args.names.is_empty() {
+            return Ok(BranchCommand::List(ListBranches {
+                patterns: args.names,
+                local: !args.remotes || args.all,
+                remote: args.remotes || args.all,
+                verbose: args.verbose,
+                contains: args.contains,
+                no_contains: args.no_contains,
+                merged: args.merged,
+                no_merged: args.no_merged,
+                ignore_case: args.ignore_case,
+                color: match args.no_color {
+                    true => Some(ColorWhen::Never),
+                    false => args.color,
+                },
+            }));
+        }
+
+        if args.all || args.remotes {
+            return Err(RustGitError::new(
+                "fatal: the -a, and -r, options to 'git branch' do not take a branch name.\nDid you mean to use: -a|-r --list <pattern>?",
+            ));
+        }
+
+        let mut names = args.names.into_iter();
+        match (names.next(), names.next(), names.next()) {
+            (Some(name), start_point, None) => Ok(BranchCommand::Create(CreateBranch {
+                name,
+                start_point,
+                force: args.force,
+                quiet: args.quiet,
+            })),
+            _ => Err(RustGitError::new("fatal: too many arguments")),
+        }
+    }
+}
+
+fn validate_branch_name(name: &str) -> Result<(), RustGitError> {
+    if name.starts_with('-')
+        || name == "HEAD"
+        || !is_valid_ref_name(&format!("{HEADS_PREFIX}{name}"))
+    {
+        return Err(RustGitError::new(format!(
+            "fatal: '{name}' is not a valid branch name"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the short name of the branch HEAD is attached to, if any.
+fn current_branch(repo: &GitRepo) -> Result<Option<String>, RustGitError> {
+    Ok(repo.read_head()?.and_then(|head| {
+        head.branch()
+            .and_then(|branch| branch.strip_prefix(HEADS_PREFIX))
+            .map(|branch| branch.to_string())
+    }))
+}
+
+fn abbrev(id: &GitObjectId) -> String {
+    id.to_string()[..7].to_string()
+}
+
+/// Works out the branch config for tracking the provided upstream, which can be either a local
+/// branch or a remote-tracking branch. Also returns the short name of the upstream.
+fn upstream_config(repo: &GitRepo, upstream: &str) -> Result<(BranchConfig, String), RustGitError> {
+    let git_ref = repo
+        .refs
+        .dwim_ref(upstream)?
+        .filter(|git_ref| {
+            git_ref.name.starts_with(HEADS_PREFIX) || git_ref.name.starts_with(REMOTES_PREFIX)
+        })
+        .ok_or_else(|| {
+            RustGitError::new(format!(
+                "fatal: the requested upstream branch '{upstream}' does not exist"
+            ))
+        })?;
+
+    if let Some(branch) = git_ref.name.strip_prefix(HEADS_PREFIX) {
+        return Ok((
+            BranchConfig {
+                remote: Some(String::from(".")),
+                merge: Some(git_ref.name.clone()),
+                ..BranchConfig::default()
+            },
+            branch.to_string(),
+        ));
+    }
+
+    let remote_branch = git_ref.name.trim_start_matches(REMOTES_PREFIX);
+    match remote_branch.split_once('/') {
+        Some((remote, branch)) => Ok((
+            BranchConfig {
+                remote: Some(remote.to_string()),
+                merge: Some(format!("{HEADS_PREFIX}{branch}")),
+                ..BranchConfig::default()
+            },
+            remote_branch.to_string(),
+        )),
+        None => Err(RustGitError::new(format!(
+            "fatal: the requested upstream branch '{upstream}' does not exist"
+        ))),
+    }
+}
+
+fn tracking_info(item: &RefItem, verbose: u8, palette: &Palette<BranchColor>) -> String {
+    let Some(upstream) = &item.upstream else {
+        return String::new();
+    };
+    let upstream = palette.paint(BranchColor::Upstream, shorten_ref_name(upstream));
+
+    let status = match item.tracking {
+        None => Some(String::from("gone")),
+        Some(AheadBehind {
+            ahead: 0,
+            behind: 0,
+        }) => None,
+        Some(AheadBehind { ahead, behind: 0 }) => Some(format!("ahead {ahead}")),
+        Some(AheadBehind { ahead: 0, behind }) => Some(format!("behind {behind}")),
+        Some(AheadBehind { ahead, behind }) => Some(format!("ahead {ahead}, behind {behind}")),
+    };
+
+    match (status, verbose > 1) {
+        (Some(status), true) => format!("[{upstream}: {status}] "),
+        (None, true) => format!("[{upstream}] "),
+        (Some(status), false) => format!("[{status}] "),
+        (None, false) => String::new(),
+    }
+}
+
+impl ListBranches {
+    fn display_name(&self, item: &RefItem) -> String {
+        if item.name == "HEAD" {
+            return format!("(HEAD detached at {})", abbrev(&item.id));
+        }
+        match item.name.strip_prefix(REMOTES_PREFIX) {
+            Some(remote_branch) if self.local => format!("remotes/{remote_branch}"),
+            Some(remote_branch) => remote_branch.to_string(),
+            None => item.name.trim_start_matches(HEADS_PREFIX).to_string(),
+        }
+    }
+
+    fn execute(&self, repo: &GitRepo) -> Result<(), RustGitError> {
+        let head = repo.read_head()?;
+
+        let mut refs: Vec<GitRef> = Vec::new();
+        if let (Some(GitHead::Detached(id)), true, true) =
+            (&head, self.local, self.patterns.is_empty())
+        {
+            refs.push(GitRef {
+                name: String::from("HEAD"),
+                id: id.clone(),
+            });
+        }
+        refs.extend(repo.refs.list_refs()?.into_iter().filter(|git_ref| {
+            (self.local && git_ref.name.starts_with(HEADS_PREFIX))
+                || (self.remote && git_ref.name.starts_with(REMOTES_PREFIX))
+        }));
+
+        let mut filter = RefFilter::new(self.patterns.clone(), PatternMatch::ShortName);
+        filter.ignore_case = self.ignore_case;
+        filter.details = RefDetails {
+            head: true,
+            tracking: self.verbose > 0,
+        };
+        filter.merged = self
+            .merged
+            .as_deref()
+            .map(|revision| repo.resolve_commit(revision))
+            .transpose()?;
+        filter.no_merged = self
+            .no_merged
+            .as_deref()
+            .map(|revision| repo.resolve_commit(revision))
+            .transpose()?;
+        filter.contains = self
+            .contains
+            .iter()
+            .map(|revision| repo.resolve_commit(revision))
+            .collect::<Result<_, _>>()?;
+        filter.no_contains = self
+            .no_contains
+            .iter()
+            .map(|revision| repo.resolve_commit(revision))
+            .collect::<Result<_, _>>()?;
+
+        let items = filter.apply(repo, refs)?;
+        let when = self
+            .color
+            .unwrap_or_else(|| repo.config.color.when(&repo.config.color.branch));
+        let palette = Palette::new(when, repo.config.color.branch.slots())?;
+        let reset = palette.reset();
+
+        let subject_format: RefFormat = "%(subject)".parse()?;
+        let width = items
+            .iter()
+            .map(|item| self.display_name(item).len())
+            .max()
+            .unwrap_or(0);
+
+        for item in &items {
+            let is_current = item.is_head || item.name == "HEAD";
+            let marker = if is_current { '*' } else { ' ' };
+            let name = self.display_name(item);
+            let color = palette.get(if is_current {
+                BranchColor::Current
+            } else if item.name.starts_with(REMOTES_PREFIX) {
+                BranchColor::Remote
+            } else {
+                BranchColor::Local
+            });
+
+            // Names are padded inside of their color.
+            if self.verbose == 0 {
+                println!("{marker} {color}{name}{reset}");
+            } else {
+                println!(
+                    "{marker} {color}{name:<width$}{reset} {} {}{}",
+                    abbrev(&item.id),
+                    tracking_info(item, self.verbose, &palette),
+                    subject_format.format(item)?
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CreateBranch {
+    fn execute(&self, repo: &mut GitRepo) -> Result<(), RustGitError> {
+        validate_branch_name(&self.name)?;
+        let ref_name = format!("{HEADS_PREFIX}{}", self.name);
+
+        if repo.refs.resolve_ref(&ref_name)?.is_some() {
+            if !self.force {
+                return Err(RustGitError::new(format!(
+                    "fatal: a branch named '{}' already exists",
+                    self.name
+                )));
+            }
+            if current_branch(repo)?.as_deref() == Some(self.name.as_str()) {
+                return Err(RustGitError::new(
+                    "fatal: cannot force update the current branch.",
+                ));
+            }
+        }
+
+        let start_point = self.start_point.as_deref().unwrap_or("HEAD");
+        let commit_id = repo.resolve_commit(start_point).map_err(|_| {
+            RustGitError::new(format!("fatal: not a valid object name: '{start_point}'"))
+        })?;
+        repo.refs.set_ref(&ref_name, &commit_id)?;
+
+        // As with branch.autoSetupMerge, branching off a remote-tracking branch tracks it.
+        let start_ref = match &self.start_point {
+            Some(start_point) => repo.refs.dwim_ref(start_point)?,
+            None => None,
+        };
+        if let Some(start_ref) =
+            start_ref.filter(|git_ref| git_ref.name.starts_with(REMOTES_PREFIX))
+        {
+            let (config, upstream_name) = upstream_config(repo, &start_ref.name)?;
+            repo.config.branch.insert(self.name.clone(), config);
+            repo.config.write_branches(&repo.git_dir)?;
+            if !self.quiet {
+                println!("branch '{}' set up to track '{upstream_name}'.", self.name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DeleteBranches {
+    fn delete_branch(&self, repo: &mut GitRepo, name: &str) -> Result<(), RustGitError> {
+        let (ref_name, kind) = if self.remotes {
+            (format!("{REMOTES_PREFIX}{name}"), "remote-tracking branch")
+        } else {
+            (format!("{HEADS_PREFIX}{name}"), "branch")
+        };
+
+        let id = repo
+            .refs
+            .resolve_ref(&ref_name)?
+            .ok_or_else(|| RustGitError::new(format!("error: {kind} '{name}' not found.")))?;
+
+        if !self.remotes {
+            if current_branch(repo)?.as_deref() == Some(name) {
+                return Err(RustGitError::new(format!(
+                    "error: cannot delete branch '{name}' used by worktree at '{}'",
+                    repo.root_dir.display()
+                )));
+            }
+
+            if !self.force {
+                // A branch must be merged into its upstream if it has one, or HEAD otherwise.
+                let upstream_id = match repo
+                    .config
+                    .branch
+                    .get(name)
+                    .and_then(|config| config.upstream_ref())
+                {
+                    Some(upstream) => repo.refs.resolve_ref(&upstream)?,
+                    None => None,
+                };
+                let target_id = match upstream_id {
+                    Some(upstream_id) => Some(upstream_id),
+                    None => repo.read_head()?.and_then(|head| head.id().cloned()),
+                };
+
+                if let Some(target_id) = target_id {
+                    if !repo.is_ancestor(&id, &target_id)? {
+                        return Err(RustGitError::new(format!(
+                            "error: the branch '{name}' is not fully merged.\nIf you are sure you want to delete it, run 'git branch -D {name}'."
+                        )));
+                    }
+                }
+            }
+        }
+
+        repo.refs.delete_ref(&ref_name)?;
+        if !self.remotes && repo.config.branch.remove(name).is_some() {
+            repo.config.write_branches(&repo.git_dir)?;
+        }
+
+        if !self.quiet {
+            let kind = if self.remotes {
+                "remote-tracking branch"
+            } else {
+                "branch"
+            };
+            println!("Deleted {kind} {name} (was {}).", abbrev(&id));
+        }
+
+        Ok(())
+    }
+
+    fn execute(&self, repo: &mut GitRepo) -> Result<(), RustGitError> {
+        // As in C Git, failing to delete one branch doesn't stop the others from being deleted.
+        let mut errors = Vec::new();
+        for name in &self.names {
+            if let Err(err) = self.delete_branch(repo, name) {
+                errors.push(err.to_string());
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(RustGitError::new(errors.join("\n")))
+        }
+    }
+}
+
+impl MoveBranch {
+    fn execute(&self, repo: &mut GitRepo) -> Result<(), RustGitError> {
+        let current = current_branch(repo)?;
+        let old_name = match (&self.old_name, &current) {
+            (Some(old_name), _) => old_name.clone(),
+            (None, Some(current)) => current.clone(),
+            (None, None) => {
+                let operation = if self.copy { "copy" } else { "rename" };
+                return Err(RustGitError::new(format!(
+                    "fatal: cannot {operation} the current branch while not on any branch"
+                )));
+            }
+        };
+
+        validate_branch_name(&self.new_name)?;
+        let old_ref_name = format!("{HEADS_PREFIX}{old_name}");
+        let new_ref_name = format!("{HEADS_PREFIX}{}", self.new_name);
+        let is_current = current.as_deref() == Some(old_name.as_str());
+
+        if old_name != self.new_name
+            && !self.force
+            && repo.refs.resolve_ref(&new_ref_name)?.is_some()
+        {
+            return Err(RustGitError::new(format!(
+                "fatal: a branch named '{}' already exists",
+                self.new_name
+            )));
+        }
+
+        match repo.refs.resolve_ref(&old_ref_name)? {
+            Some(id) => {
+                if old_name == self.new_name {
+                    return Ok(());
+                }
+                repo.refs
+                    .rename_ref(&old_ref_name, &new_ref_name, &id, self.copy)?;
+            }
+            // The current branch can be renamed before it has any commits.
+            None if is_current && !self.copy => (),
+            None => {
+                return Err(RustGitError::new(format!(
+                    "fatal: no branch named '{old_name}'"
+                )))
+            }
+        }
+
+        if let Some(config) = repo.config.branch.get(&old_name).cloned() {
+            if !self.copy {
+                repo.config.branch.remove(&old_name);
+            }
+            repo.config.branch.insert(self.new_name.clone(), config);
+            repo.config.write_branches(&repo.git_dir)?;
+        }
+
+        if is_current && !self.copy {
+            repo.update_symbolic_ref("HEAD", &new_ref_name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the branch an upstream command applies to, defaulting to the current branch.
+fn target_branch(
+    repo: &GitRepo,
+    branch: &Option<String>,
+    detached_err: impl FnOnce() -> RustGitError,
+) -> Result<String, RustGitError> {
+    let branch = match branch {
+        Some(branch) => branch.clone(),
+        None => current_branch(repo)?.ok_or_else(detached_err)?,
+    };
+
+    if repo
+        .refs
+        .resolve_ref(&format!("{HEADS_PREFIX}{branch}"))?
+        .is_none()
+    {
+        return Err(RustGitError::new(format!(
+            "fatal: branch '{branch}' does not exist"
+        )));
+    }
+
+    Ok(branch)
+}
+
+impl GitCommand for BranchCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let mut repo = repo_state.try_get()?;
+
+        match self {
+            BranchCommand::List(list_cmd) => list_cmd.execute(&repo)?,
+            BranchCommand::Create(create_cmd) => create_cmd.execute(&mut repo)?,
+            BranchCommand::Delete(delete_cmd) => delete_cmd.execute(&mut repo)?,
+            BranchCommand::Move(move_cmd) => move_cmd.execute(&mut repo)?,
+            BranchCommand::SetUpstream(upstream_cmd) => {
+                let branch = target_branch(&repo, &upstream_cmd.branch, || {
+                    RustGitError::new(format!(
+                        "fatal: could not set upstream of HEAD to {} when it does not point to any branch",
+                        upstream_cmd.upstream
+                    ))
+                })?;
+                let (config, upstream_name) = upstream_config(&repo, &upstream_cmd.upstream)?;
+
+                // Only the upstream changes, the branch's other keys are kept.
+                let branch_config = repo.config.branch.entry(branch.clone()).or_default();
+                branch_config.remote = config.remote;
+                branch_config.merge = config.merge;
+                repo.config.write_branches(&repo.git_dir)?;
+
+                if !upstream_cmd.quiet {
+                    println!("branch '{branch}' set up to track '{upstream_name}'.");
+                }
+            }
+            BranchCommand::UnsetUpstream(unset_cmd) => {
+                let branch = target_branch(&repo, &unset_cmd.branch, || {
+                    RustGitError::new("fatal: HEAD does not point to a branch")
+                })?;
+
+                match repo.config.branch.get_mut(&branch) {
+                    Some(config) if config.upstream_ref().is_some() => {
+                        config.remote = None;
+                        config.merge = None;
+                        if *config == BranchConfig::default() {
+                            repo.config.branch.remove(&branch);
+                        }
+                        repo.config.write_branches(&repo.git_dir)?;
+                    }
+                    _ => {
+                        return Err(RustGitError::new(format!(
+                            "fatal: branch '{branch}' has no upstream information"
+                        )))
+                    }
+                }
+            }
+            BranchCommand::ShowCurrent => {
+                if let Some(branch) = current_branch(&repo)? {
+                    println!("{branch}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Only listing pages its output.
+    fn pages_by_default(&self) -> bool {
+        matches!(self, BranchCommand::List(_))
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use crate::object::GitObjectId;
+
+/// The tree objects last written for the directories of the index, stored in the TREE index
+/// extension, so that directories whose entries didn't change don't have to be written again.
+///
+/// See the "Cache tree" section of gitformat-index(5).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct GitCacheTree {
+    /// The tree written for this directory and the number of index entries it covers, unless an
+    /// entry in the directory changed since.
+    valid: Option<(GitObjectId, usize)>,
+    subtrees: BTreeMap<String, GitCacheTree>,
+}
+
+impl GitCacheTree {
+    /// Serializes the tree for the directory with the provided name, which is empty for the
+    /// root, followed by its subtrees.
+    pub(crate) fn serialize(cache_tree: &GitCacheTree, name: &str, bytes: &mut Vec<u8>) {
+        let entry_count = match &cache_tree.valid {
+            Some((_, entry_count)) => *entry_count as i64,
+            None => -1,
+        };
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(b'\0');
+        bytes
+            .extend_from_slice(format!("{entry_count} {}\n", cache_tree.subtrees.len()).as_bytes());
+        if let Some((id, _)) = &cache_tree.valid {
+            bytes.extend_from_slice(&GitObjectId::serialize(id));
+        }
+
+        // Like C git, shorter names are written first.
+        let mut subtrees = cache_tree.subtrees.iter().collect::<Vec<_>>();
+        subtrees.sort_by_key(|(name, _)| (name.len(), name.as_str()));
+        for (name, subtree) in subtrees {
+            GitCacheTree::serialize(subtree, name, bytes);
+        }
+    }
+
+    /// Deserializes a tree and its subtrees, returning it with its name and the number of bytes
+    /// read. Returns None if the data is malformed, in which case the cache should be dropped.
+    pub(crate) fn deserialize(bytes: &[u8]) -> Option<(GitCacheTree, String, usize)> {
+        let name_len = bytes.iter().position(|&b| b == b'\0')?;
+        let name = String::from_utf8(bytes[..name_len].to_vec()).ok()?;
+        let mut processed_bytes = name_len + 1;
+
+        let line_len = bytes[processed_bytes..].iter().position(|&b| b == b'\n')?;
+        let line = std::str::from_utf8(&bytes[processed_bytes..processed_bytes + line_len]).ok()?;
+        let (entry_count, subtree_count) = line.split_once(' ')?;
+        let entry_count = entry_count.parse::<i64>().ok()?;
+        let subtree_count = subtree_count.parse::<usize>().ok()?;
+        processed_bytes += line_len + 1;
+
+        let valid = if entry_count >= 0 {
+            let id =
+                GitObjectId::deserialize(bytes.get(processed_bytes..processed_bytes + 20)?).ok()?;
+            processed_bytes += 20;
+            Some((id, entry_count as usize))
+        } else {
+            None
+        };
+
+        let mut subtrees = BTreeMap::new();
+        for _ in 0..subtree_count {
+            let (subtree, subtree_name, subtree_bytes) =
+                GitCacheTree::deserialize(&bytes[processed_bytes..])?;
+            subtrees.insert(subtree_name, subtree);
+            processed_bytes += subtree_bytes;
+        }
+
+        Some((GitCacheTree { valid, subtrees }, name, processed_bytes))
+    }
+
+    /// Returns the tree written for this directory, if it still covers the provided number of
+    /// index entries.
+    pub(crate) fn valid_tree(&self, entry_count: usize) -> Option<&GitObjectId> {
+        match &self.valid {
+            Some((id, valid_entry_count)) if *valid_entry_count == entry_count => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Records the tree written for this directory.
+    pub(crate) fn set_valid_tree(&mut self, id: GitObjectId, entry_count: usize) {
+        self.valid = Some((id, entry_count));
+    }
+
+    /// Returns the cache for the subdirectory with the provided name, creating it if needed.
+    pub(crate) fn subtree_mut(&mut self, name: &str) -> &mut GitCacheTree {
+        self.subtrees.entry(name.to_string()).or_default()
+    }
+
+    /// Drops the caches for subdirectories which no longer exist.
+    pub(crate) fn retain_subtrees(&mut self, mut predicate: impl FnMut(&str) -> bool) {
+        self.subtrees.retain(|name, _| predicate(name));
+    }
+
+    /// Invalidates the trees of all directories containing the provided path, which is relative
+    /// to this directory.
+    pub(crate) fn invalidate_path(&mut self, path: &str) {
+        self.valid = None;
+        if let Some((dir, rest)) = path.split_once('/') {
+            if let Some(subtree) = self.subtrees.get_mut(dir) {
+                subtree.invalidate_path(rest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(hex_char: char) -> GitObjectId {
+        GitObjectId::new(hex_char.to_string().repeat(40))
+    }
+
+    fn get_test_cache_tree() -> GitCacheTree {
+        let mut cache_tree = GitCacheTree::default();
+        cache_tree.set_valid_tree(id('a'), 3);
+        cache_tree.subtree_mut("dir").set_valid_tree(id('b'), 2);
+        cache_tree
+            .subtree_mut("dir")
+            .subtree_mut("sub")
+            .set_valid_tree(id('c'), 1);
+        cache_tree.subtree_mut("other").set_valid_tree(id('d'), 1);
+        cache_tree
+    }
+
+    #[test]
+    fn should_roundtrip() {
+        let mut cache_tree = get_test_cache_tree();
+        cache_tree.invalidate_path("other/file.txt");
+
+        let mut bytes = vec![];
+        GitCacheTree::serialize(&cache_tree, "", &mut bytes);
+        assert_eq!(
+            GitCacheTree::deserialize(&bytes),
+            Some((cache_tree, String::new(), bytes.len()))
+        );
+        assert!(bytes.starts_with(b"\0-1 2\ndir\x002 1\n"));
+    }
+
+    #[test]
+    fn should_drop_malformed_cache_tree() {
+        assert_eq!(GitCacheTree::deserialize(b"\x001 0\n"), None);
+        assert_eq!(GitCacheTree::deserialize(b"\0x 0\n"), None);
+    }
+
+    #[test]
+    fn should_invalidate_directories_containing_path() {
+        let mut cache_tree = get_test_cache_tree();
+        cache_tree.invalidate_path("dir/file.txt");
+
+        assert_eq!(cache_tree.valid_tree(3), None);
+        assert_eq!(cache_tree.subtree_mut("dir").valid_tree(2), None);
+        assert_eq!(
+            cache_tree
+                .subtree_mut("dir")
+                .subtree_mut("sub")
+                .valid_tree(1),
+            Some(&id('c'))
+        );
+        assert_eq!(
+            cache_tree.subtree_mut("other").valid_tree(1),
+            Some(&id('d'))
+        );
+        assert_eq!(cache_tree.subtree_mut("other").valid_tree(2), None);
+    }
+}
@@ -0,0 +1,44 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Debug gitignore / exclude files")]
+#[command(long_about = "
+For each pathname given via the command-line or from a file via --stdin, check whether the file is excluded by .gitignore (or
+other input files to the exclude mechanism) and output the path if it is excluded.
+
+By default, tracked files are not shown at all since they are not subject to exclude rules; but see ‘--no-index’.
+")]
+pub(crate) struct CheckIgnoreArgs {
+    /// Don’t output anything, just set exit status. This is only valid with a single pathname.
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Instead of printing the paths that are excluded, for each path that matches an exclude pattern, print the exclude
+    /// pattern together with the path. (Matching an exclude pattern usually means the path is excluded, but if the pattern
+    /// begins with "!" then it is a negated pattern and matching it means the path is NOT excluded.)
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Read pathnames from the standard input, one per line, instead of from the command-line.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// The output format is modified to be machine-parsable. If --stdin is also given, input paths are separated with a NUL
+    /// character instead of a linefeed character.
+    #[arg(short = 'z')]
+    pub nul_terminated: bool,
+
+    /// Show given paths which don’t match any pattern. This only makes sense when --verbose is enabled, otherwise it would
+    /// not be possible to distinguish between paths which match a pattern and those which don’t.
+    #[arg(short, long)]
+    pub non_matching: bool,
+
+    /// Don’t look in the index when undertaking the checks. This can be used to debug why a path became tracked by e.g.
+    /// git add . and was not ignored by the rules as expected by the user or when developing patterns including negation
+    /// to match a path previously added with git add -f.
+    #[arg(long)]
+    pub no_index: bool,
+
+    /// Paths to check.
+    pub pathnames: Vec<String>,
+}
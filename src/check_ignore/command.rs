@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{
+    command::GitCommand,
+    ignore::{GitIgnore, GitIgnorePattern},
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
+
+use super::cli::CheckIgnoreArgs;
+
+pub(crate) struct CheckIgnoreCommand {
+    args: CheckIgnoreArgs,
+}
+
+impl CheckIgnoreCommand {
+    pub fn new(args: CheckIgnoreArgs) -> Result<CheckIgnoreCommand, RustGitError> {
+        if args.stdin {
+            if !args.pathnames.is_empty() {
+                return Err(RustGitError::new(
+                    "fatal: cannot specify pathnames with --stdin",
+                ));
+            }
+        } else {
+            if args.nul_terminated {
+                return Err(RustGitError::new("fatal: -z only makes sense with --stdin"));
+            }
+            if args.pathnames.is_empty() {
+                return Err(RustGitError::new("fatal: no path specified"));
+            }
+        }
+        if args.quiet {
+            if args.pathnames.len() > 1 {
+                return Err(RustGitError::new(
+                    "fatal: --quiet is only valid with a single pathname",
+                ));
+            }
+            if args.verbose {
+                return Err(RustGitError::new(
+                    "fatal: cannot have both --quiet and --verbose",
+                ));
+            }
+        }
+        if args.non_matching && !args.verbose {
+            return Err(RustGitError::new(
+                "fatal: --non-matching is only valid with --verbose",
+            ));
+        }
+
+        Ok(CheckIgnoreCommand { args })
+    }
+
+    /// Returns the pattern to report for the path, if any. Negative patterns are only reported
+    /// with --verbose, and tracked paths aren't checked unless --no-index is set.
+    fn check_path<'a>(
+        &self,
+        repo: &GitRepo,
+        ignore: &'a mut GitIgnore,
+        pathname: &str,
+    ) -> Result<Option<&'a GitIgnorePattern>, RustGitError> {
+        let path = repo.path_to_git_repo_path(Path::new(pathname))?.as_string();
+        if path.is_empty() || (!self.args.no_index && repo.is_tracked(&path)) {
+            return Ok(None);
+        }
+
+        let is_dir = pathname.ends_with('/')
+            || fs::symlink_metadata(repo.root_dir.join(&path))
+                .is_ok_and(|metadata| metadata.is_dir());
+        Ok(ignore
+            .matching_pattern(&path, is_dir)?
+            .filter(|pattern| self.args.verbose || !pattern.negative))
+    }
+
+    fn output(&self, pathname: &str, pattern: Option<&GitIgnorePattern>) {
+        match (self.args.verbose, self.args.nul_terminated, pattern) {
+            (false, false, _) => println!("{pathname}"),
+            (false, true, _) => print!("{pathname}\0"),
+            (true, false, Some(pattern)) => println!(
+                "{}:{}:{}\t{pathname}",
+                pattern.source, pattern.line, pattern.original
+            ),
+            (true, false, None) => println!("::\t{pathname}"),
+            (true, true, Some(pattern)) => print!(
+                "{}\0{}\0{}\0{pathname}\0",
+                pattern.source, pattern.line, pattern.original
+            ),
+            (true, true, None) => print!("\0\0\0{pathname}\0"),
+        }
+    }
+}
+
+impl GitCommand for CheckIgnoreCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+        let mut ignore = repo.standard_ignore()?;
+
+        let mut stdin = String::new();
+        if self.args.stdin {
+            io::stdin().read_to_string(&mut stdin)?;
+        }
+        let terminator = if self.args.nul_terminated { '\0' } else { '\n' };
+        let stdin_paths = stdin.split(terminator).filter(|path| !path.is_empty());
+
+        let mut num_ignored = 0;
+        for pathname in self
+            .args
+            .pathnames
+            .iter()
+            .map(String::as_str)
+            .chain(stdin_paths)
+        {
+            let pattern = self.check_path(&repo, &mut ignore, pathname)?;
+            if !self.args.quiet && (pattern.is_some() || self.args.non_matching) {
+                self.output(pathname, pattern);
+            }
+            if pattern.is_some() {
+                num_ignored += 1;
+            }
+        }
+
+        if num_ignored == 0 {
+            return Err(RustGitError::new(""));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+pub(crate) mod cli;
+pub(crate) mod command;
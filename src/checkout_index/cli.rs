@@ -0,0 +1,51 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Copy files from the index to the working tree")]
+#[command(long_about = "
+Will copy all files listed from the index to the working directory (not overwriting existing files).
+")]
+pub(crate) struct CheckoutIndexArgs {
+    /// update stat information for the checked out entries in the index file.
+    #[arg(short = 'u', long = "index")]
+    pub index: bool,
+
+    /// be quiet if files exist or are not in the index
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// forces overwrite of existing files
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// checks out all files in the index except for those with the skip-worktree bit set (see --ignore-skip-
+    /// worktree-bits). Cannot be used together with explicit <file>.
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// When creating files, prepend <string> (usually a directory including a trailing /)
+    #[arg(long, value_name = "string")]
+    pub prefix: Option<String>,
+
+    /// Instead of checking out unmerged entries, copy out the files from named stage. <number> must be
+    /// between 1 and 3. Note: --stage=all automatically implies --temp.
+    #[arg(long, value_name = "number", value_parser = ["1", "2", "3", "all"])]
+    pub stage: Option<String>,
+
+    /// Instead of copying the files to the working directory write the content to temporary files. The
+    /// temporary name associations will be written to stdout.
+    #[arg(long)]
+    pub temp: bool,
+
+    /// Instead of taking list of paths from the command line, read list of paths from the standard input.
+    /// Paths are separated by LF (i.e. one path per line) by default.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// Only meaningful with --stdin; paths are separated with NUL character instead of LF.
+    #[arg(short = 'z')]
+    pub nul_terminated: bool,
+
+    /// Files to checkout.
+    pub files: Vec<String>,
+}
@@ -0,0 +1,286 @@
+use std::{
+    fs::{self, Metadata},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    command::GitCommand,
+    index::{GitIndexEntry, GitIndexMode, GitIndexStageFlag},
+    repo::{GitRepo, RepoState},
+    tempfile::create_temp_file,
+    RustGitError,
+};
+
+use super::cli::CheckoutIndexArgs;
+
+/// The stage of the entries to check out.
+#[derive(Clone, Copy, PartialEq)]
+enum CheckoutStage {
+    Stage(GitIndexStageFlag),
+    /// Every unmerged stage, written to temporary files.
+    All,
+}
+
+impl CheckoutStage {
+    fn matches(&self, entry: &GitIndexEntry) -> bool {
+        match self {
+            CheckoutStage::Stage(stage) => entry.flags.stage == *stage,
+            CheckoutStage::All => entry.flags.stage != GitIndexStageFlag::RegularFileNoConflict,
+        }
+    }
+}
+
+/// The outcome of checking out an entry to the worktree.
+enum CheckoutResult {
+    Written(Metadata),
+    /// The file already matches the entry.
+    UpToDate,
+    /// A different file is in the way, and --force wasn't passed.
+    Exists,
+}
+
+pub(crate) struct CheckoutIndexCommand {
+    args: CheckoutIndexArgs,
+    stage: CheckoutStage,
+    to_temp: bool,
+}
+
+impl CheckoutIndexCommand {
+    pub fn new(args: CheckoutIndexArgs) -> Result<CheckoutIndexCommand, RustGitError> {
+        if args.all && !args.files.is_empty() {
+            return Err(RustGitError::new(
+                "fatal: git checkout-index: don't mix '--all' and explicit filenames",
+            ));
+        }
+        if args.stdin && !args.files.is_empty() {
+            return Err(RustGitError::new(
+                "fatal: git checkout-index: don't mix '--stdin' and explicit filenames",
+            ));
+        }
+
+        let stage = match args.stage.as_deref() {
+            Some("all") => CheckoutStage::All,
+            Some(number) => {
+                CheckoutStage::Stage(GitIndexStageFlag::from_number(number.parse()?).ok_or_else(
+                    || RustGitError::new("fatal: stage should be between 1 and 3 or all"),
+                )?)
+            }
+            None => CheckoutStage::Stage(GitIndexStageFlag::RegularFileNoConflict),
+        };
+        let to_temp = args.temp || stage == CheckoutStage::All;
+
+        Ok(CheckoutIndexCommand {
+            args,
+            stage,
+            to_temp,
+        })
+    }
+
+    /// Returns the positions of the index entries to check out for the provided file, or
+    /// reports why there are none.
+    fn entries_for_file(&self, repo: &GitRepo, file: &str) -> Result<Vec<usize>, RustGitError> {
+        let path = repo.path_to_git_repo_path(Path::new(file))?;
+        let entries = repo
+            .index
+            .iter_entries()
+            .enumerate()
+            .filter(|(_, entry)| entry.path_name == path)
+            .collect::<Vec<_>>();
+        let matching = entries
+            .iter()
+            .filter(|(_, entry)| self.stage.matches(entry))
+            .map(|(idx, _)| *idx)
+            .collect::<Vec<_>>();
+
+        if matching.is_empty() && !self.args.quiet {
+            let reason = match self.stage {
+                _ if entries.is_empty() => String::from("is not in the cache"),
+                CheckoutStage::Stage(GitIndexStageFlag::RegularFileNoConflict) => {
+                    String::from("is unmerged")
+                }
+                CheckoutStage::Stage(stage) => format!("does not exist at stage {stage}"),
+                CheckoutStage::All => String::from("is not unmerged"),
+            };
+            eprintln!("git checkout-index: {} {reason}", path.as_string());
+        }
+        Ok(matching)
+    }
+
+    /// Writes the provided entry to its file in the worktree, or under the prefix. Existing
+    /// files are only overwritten with --force, unless they already match the entry.
+    fn checkout_to_worktree(
+        &self,
+        repo: &GitRepo,
+        entry: &GitIndexEntry,
+    ) -> Result<CheckoutResult, RustGitError> {
+        let path = format!(
+            "{}{}",
+            self.args.prefix.as_deref().unwrap_or(""),
+            entry.path_name.as_string()
+        );
+        let target = repo.root_dir.join(&path);
+
+        if let Ok(metadata) = fs::symlink_metadata(&target) {
+            if !self.args.force {
+                if self.args.prefix.is_none()
+                    && !metadata.is_dir()
+                    && !repo.is_entry_modified(entry, &metadata)?
+                {
+                    return Ok(CheckoutResult::UpToDate);
+                }
+                if !self.args.quiet {
+                    eprintln!("{path} already exists, no checkout");
+                }
+                return Ok(CheckoutResult::Exists);
+            }
+            if metadata.is_dir() && entry.mode != GitIndexMode::GitLink {
+                fs::remove_dir_all(&target)?;
+            }
+        }
+
+        Ok(CheckoutResult::Written(
+            repo.checkout_entry_to(entry, &target)?,
+        ))
+    }
+
+    /// Writes the provided entry to a new temporary file at the root of the worktree, and
+    /// returns its name. As with the `mkstemp` of C Git, the file is only readable and writable
+    /// by its owner, and symbolic links are written as plain files holding their target.
+    fn checkout_to_temp_file(
+        &self,
+        repo: &GitRepo,
+        entry: &GitIndexEntry,
+    ) -> Result<String, RustGitError> {
+        if entry.mode == GitIndexMode::GitLink {
+            return Err(RustGitError::new(format!(
+                "error: cannot create temporary submodule {}",
+                entry.path_name.as_string()
+            )));
+        }
+
+        let obj = repo.obj_store.read_object(&entry.name)?.ok_or_else(|| {
+            RustGitError::new(format!(
+                "error: unable to read sha1 file of {} ({})",
+                entry.path_name.as_string(),
+                entry.name
+            ))
+        })?;
+        let prefix = if entry.mode == GitIndexMode::SymbolicLink {
+            ".merge_link_"
+        } else {
+            ".merge_file_"
+        };
+        let (mut file, path) = create_temp_file(&repo.root_dir, prefix, 0o600)?;
+        file.write_all(obj.content.as_bytes())?;
+
+        Ok(path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default())
+    }
+
+    /// Writes the provided entries, which all have the same path, to temporary files, and
+    /// prints the names of the files next to the path.
+    fn checkout_path_to_temp_files(
+        &self,
+        repo: &GitRepo,
+        entries: &[&GitIndexEntry],
+    ) -> Result<(), RustGitError> {
+        let names = if self.stage == CheckoutStage::All {
+            let mut names = [".", ".", "."].map(String::from);
+            for entry in entries {
+                let slot = match entry.flags.stage {
+                    GitIndexStageFlag::Base => 0,
+                    GitIndexStageFlag::Ours => 1,
+                    GitIndexStageFlag::Theirs => 2,
+                    GitIndexStageFlag::RegularFileNoConflict => continue,
+                };
+                names[slot] = self.checkout_to_temp_file(repo, entry)?;
+            }
+            names.join(" ")
+        } else {
+            self.checkout_to_temp_file(repo, entries[0])?
+        };
+
+        let terminator = if self.args.nul_terminated { '\0' } else { '\n' };
+        print!("{names}\t{}{terminator}", entries[0].path_name.as_string());
+        Ok(())
+    }
+}
+
+impl GitCommand for CheckoutIndexCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let mut repo = repo_state.try_get()?;
+        // The stat data of files written under a prefix doesn't describe the worktree, so only
+        // files checked out in place get refreshed in the index, which is then locked up front.
+        let refresh = self.args.index && self.args.prefix.is_none() && !self.to_temp;
+        if refresh {
+            repo.lock_index()?;
+        }
+        let mut has_errors = false;
+
+        let mut stdin = String::new();
+        if self.args.stdin {
+            io::stdin().read_to_string(&mut stdin)?;
+        }
+        let terminator = if self.args.nul_terminated { '\0' } else { '\n' };
+        let stdin_files = stdin.split(terminator).filter(|file| !file.is_empty());
+
+        let mut positions = vec![];
+        if self.args.all {
+            // Unmerged paths are skipped unless a stage is requested.
+            positions.extend(
+                repo.index
+                    .iter_entries()
+                    .enumerate()
+                    .filter(|(_, entry)| {
+                        !entry.extended_flags.skip_worktree && self.stage.matches(entry)
+                    })
+                    .map(|(idx, _)| idx),
+            );
+        }
+        for file in self
+            .args
+            .files
+            .iter()
+            .map(String::as_str)
+            .chain(stdin_files)
+        {
+            let matching = self.entries_for_file(&repo, file)?;
+            has_errors |= matching.is_empty();
+            positions.extend(matching);
+        }
+
+        if self.to_temp {
+            let entries = positions
+                .iter()
+                .map(|&idx| repo.index.entry_at(idx))
+                .collect::<Vec<_>>();
+            for path_entries in entries.chunk_by(|a, b| a.path_name == b.path_name) {
+                self.checkout_path_to_temp_files(&repo, path_entries)?;
+            }
+        } else {
+            let mut written = vec![];
+            for idx in positions {
+                match self.checkout_to_worktree(&repo, repo.index.entry_at(idx))? {
+                    CheckoutResult::Written(metadata) => written.push((idx, metadata)),
+                    CheckoutResult::UpToDate => (),
+                    CheckoutResult::Exists => has_errors = true,
+                }
+            }
+
+            if refresh {
+                for (idx, metadata) in written {
+                    repo.index.refresh_entry_stat_at(idx, &metadata);
+                }
+                repo.write_index()?;
+            }
+        }
+
+        if has_errors {
+            return Err(RustGitError::new(""));
+        }
+        Ok(())
+    }
+}
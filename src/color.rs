@@ -0,0 +1,479 @@
+// Colored output, based on C Git's color.c:
+// https://github.com/git/git/blob/master/color.c
+
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{self, IsTerminal},
+    marker::PhantomData,
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::RustGitError, pager::pager_in_use};
+
+/// The escape sequence turning every color and attribute off.
+pub(crate) const RESET: &str = "\x1b[m";
+
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// When output is colored, set with `--color` or the `color.*` config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ColorWhen {
+    Always,
+    Never,
+    /// Only color output going to a terminal, or to the pager.
+    Auto,
+}
+
+impl ColorWhen {
+    /// Returns whether output to stdout should be colored.
+    pub(crate) fn enabled(self) -> bool {
+        match self {
+            ColorWhen::Always => true,
+            ColorWhen::Never => false,
+            ColorWhen::Auto => {
+                (io::stdout().is_terminal() || pager_in_use())
+                    && env::var("TERM").is_ok_and(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+/// A color of a color specification, as the foreground or background.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Color {
+    Normal,
+    /// One of the 8 standard colors, their bright versions or the default color, as the offset
+    /// from the foreground code of black.
+    Ansi(u8),
+    Ansi256(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn parse(name: &str) -> Option<Color> {
+        let name = name.to_ascii_lowercase();
+        if name == "normal" {
+            return Some(Color::Normal);
+        }
+        if name == "default" {
+            return Some(Color::Ansi(9));
+        }
+        if let Some(hex) = name.strip_prefix('#').filter(|hex| hex.len() == 6) {
+            let component = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+            return Some(Color::Rgb(component(0)?, component(2)?, component(4)?));
+        }
+        if let Some(idx) = ANSI_NAMES.iter().position(|ansi| *ansi == name) {
+            return Some(Color::Ansi(idx as u8));
+        }
+        if let Some(idx) = name
+            .strip_prefix("bright")
+            .and_then(|bright| ANSI_NAMES.iter().position(|ansi| *ansi == bright))
+        {
+            return Some(Color::Ansi(60 + idx as u8));
+        }
+        // Numbers are colors of the 256 color mode, the first 16 of which are the standard ones.
+        match name.parse::<i32>().ok()? {
+            -1 => Some(Color::Normal),
+            value @ 0..=7 => Some(Color::Ansi(value as u8)),
+            value @ 8..=15 => Some(Color::Ansi(60 + value as u8 - 8)),
+            value @ 16..=255 => Some(Color::Ansi256(value as u8)),
+            _ => None,
+        }
+    }
+
+    fn code(self, background: bool) -> String {
+        let (offset, extended) = if background { (40, 4) } else { (30, 3) };
+        match self {
+            Color::Normal => String::new(),
+            Color::Ansi(value) => (offset + u16::from(value)).to_string(),
+            Color::Ansi256(value) => format!("{extended}8;5;{value}"),
+            Color::Rgb(red, green, blue) => format!("{extended}8;2;{red};{green};{blue}"),
+        }
+    }
+}
+
+/// Returns the code of an attribute like `bold`, or of its negation like `no-bold`.
+fn parse_attribute(name: &str) -> Option<u8> {
+    let (name, negate) = match name.strip_prefix("no") {
+        Some(name) => (name.strip_prefix('-').unwrap_or(name), true),
+        None => (name, false),
+    };
+    let (code, negated) = match name {
+        "bold" => (1, 22),
+        "dim" => (2, 22),
+        "italic" => (3, 23),
+        "ul" => (4, 24),
+        "blink" => (5, 25),
+        "reverse" => (7, 27),
+        "strike" => (9, 29),
+        _ => return None,
+    };
+    Some(if negate { negated } else { code })
+}
+
+/// Parses a color specification as in git-config(1), like `bold red` or `#ff0000 ul`, into the
+/// escape sequence setting it. The first color is the foreground and the second the background.
+pub(crate) fn parse_color(value: &str) -> Option<String> {
+    if value.trim() == "reset" {
+        return Some(String::from(RESET));
+    }
+
+    let (mut foreground, mut background) = (None, None);
+    let mut attributes = 0u64;
+    for word in value.split_ascii_whitespace() {
+        if let Some(color) = Color::parse(word) {
+            match (foreground, background) {
+                (None, _) => foreground = Some(color),
+                (Some(_), None) => background = Some(color),
+                (Some(_), Some(_)) => return None,
+            }
+        } else {
+            attributes |= 1 << parse_attribute(&word.to_ascii_lowercase())?;
+        }
+    }
+
+    let mut codes = (0..64)
+        .filter(|code| attributes & (1 << code) != 0)
+        .map(|code| code.to_string())
+        .collect::<Vec<_>>();
+    for (color, is_background) in [(foreground, false), (background, true)] {
+        match color {
+            None | Some(Color::Normal) => (),
+            Some(color) => codes.push(color.code(is_background)),
+        }
+    }
+    if codes.is_empty() {
+        Some(String::new())
+    } else {
+        Some(format!("\x1b[{}m", codes.join(";")))
+    }
+}
+
+/// A kind of output colored separately, configured with `color.<section>.<slot>`.
+pub(crate) trait ColorSlot: Copy + 'static {
+    /// The section of the config the slots are in, e.g. `diff` for `color.diff.<slot>`.
+    const SECTION: &'static str;
+    const ALL: &'static [Self];
+
+    /// The names of the slot in the config, in lowercase.
+    fn names(self) -> &'static [&'static str];
+
+    /// The escape sequence of the color used when none is configured.
+    fn default_color(self) -> &'static str;
+}
+
+/// The colors of each slot of some output, which are all empty when output isn't colored.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Palette<S> {
+    colors: Option<Vec<String>>,
+    slots: PhantomData<S>,
+}
+
+impl<S> Default for Palette<S> {
+    fn default() -> Palette<S> {
+        Palette {
+            colors: None,
+            slots: PhantomData,
+        }
+    }
+}
+
+impl<S: ColorSlot + PartialEq> Palette<S> {
+    /// Returns the palette used when output is colored, with the slots configured in `config`
+    /// overriding the default colors.
+    pub(crate) fn new(
+        when: ColorWhen,
+        config: &BTreeMap<String, String>,
+    ) -> Result<Palette<S>, RustGitError> {
+        if !when.enabled() {
+            return Ok(Palette::default());
+        }
+
+        let mut colors = S::ALL
+            .iter()
+            .map(|slot| String::from(slot.default_color()))
+            .collect::<Vec<_>>();
+        for (name, value) in config {
+            let Some(idx) = S::ALL
+                .iter()
+                .position(|slot| slot.names().contains(&name.to_ascii_lowercase().as_str()))
+            else {
+                continue;
+            };
+            colors[idx] = parse_color(value).ok_or_else(|| {
+                RustGitError::new(format!(
+                    "error: invalid color value: {value}\nfatal: bad config variable 'color.{}.{name}'",
+                    S::SECTION
+                ))
+            })?;
+        }
+        Ok(Palette {
+            colors: Some(colors),
+            slots: PhantomData,
+        })
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.colors.is_some()
+    }
+
+    /// Returns the escape sequence of the color of the slot.
+    pub(crate) fn get(&self, slot: S) -> &str {
+        let idx = S::ALL.iter().position(|other| *other == slot);
+        match (&self.colors, idx) {
+            (Some(colors), Some(idx)) => &colors[idx],
+            _ => "",
+        }
+    }
+
+    /// Returns the escape sequence ending colored text.
+    pub(crate) fn reset(&self) -> &str {
+        if self.is_enabled() {
+            RESET
+        } else {
+            ""
+        }
+    }
+
+    /// Returns the text in the color of the slot, leaving it as is when the color is empty.
+    pub(crate) fn paint(&self, slot: S, text: &str) -> String {
+        let color = self.get(slot);
+        if color.is_empty() {
+            String::from(text)
+        } else {
+            format!("{color}{text}{RESET}")
+        }
+    }
+}
+
+/// The slots of diffs, configured with `color.diff.<slot>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum DiffColor {
+    Context,
+    Meta,
+    Frag,
+    Func,
+    Old,
+    New,
+    Commit,
+    Whitespace,
+    OldMoved,
+    NewMoved,
+    OldMovedAlternative,
+    NewMovedAlternative,
+    OldMovedDimmed,
+    NewMovedDimmed,
+    OldMovedAlternativeDimmed,
+    NewMovedAlternativeDimmed,
+}
+
+impl ColorSlot for DiffColor {
+    const SECTION: &'static str = "diff";
+    const ALL: &'static [DiffColor] = &[
+        DiffColor::Context,
+        DiffColor::Meta,
+        DiffColor::Frag,
+        DiffColor::Func,
+        DiffColor::Old,
+        DiffColor::New,
+        DiffColor::Commit,
+        DiffColor::Whitespace,
+        DiffColor::OldMoved,
+        DiffColor::NewMoved,
+        DiffColor::OldMovedAlternative,
+        DiffColor::NewMovedAlternative,
+        DiffColor::OldMovedDimmed,
+        DiffColor::NewMovedDimmed,
+        DiffColor::OldMovedAlternativeDimmed,
+        DiffColor::NewMovedAlternativeDimmed,
+    ];
+
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            DiffColor::Context => &["context", "plain"],
+            DiffColor::Meta => &["meta"],
+            DiffColor::Frag => &["frag"],
+            DiffColor::Func => &["func"],
+            DiffColor::Old => &["old"],
+            DiffColor::New => &["new"],
+            DiffColor::Commit => &["commit"],
+            DiffColor::Whitespace => &["whitespace"],
+            DiffColor::OldMoved => &["oldmoved"],
+            DiffColor::NewMoved => &["newmoved"],
+            DiffColor::OldMovedAlternative => &["oldmovedalternative"],
+            DiffColor::NewMovedAlternative => &["newmovedalternative"],
+            DiffColor::OldMovedDimmed => &["oldmoveddimmed"],
+            DiffColor::NewMovedDimmed => &["newmoveddimmed"],
+            DiffColor::OldMovedAlternativeDimmed => &["oldmovedalternativedimmed"],
+            DiffColor::NewMovedAlternativeDimmed => &["newmovedalternativedimmed"],
+        }
+    }
+
+    fn default_color(self) -> &'static str {
+        match self {
+            DiffColor::Context | DiffColor::Func => "",
+            DiffColor::Meta => "\x1b[1m",
+            DiffColor::Frag => "\x1b[36m",
+            DiffColor::Old => "\x1b[31m",
+            DiffColor::New => "\x1b[32m",
+            DiffColor::Commit => "\x1b[33m",
+            DiffColor::Whitespace => "\x1b[41m",
+            DiffColor::OldMoved => "\x1b[1;35m",
+            DiffColor::NewMoved => "\x1b[1;36m",
+            DiffColor::OldMovedAlternative => "\x1b[1;34m",
+            DiffColor::NewMovedAlternative => "\x1b[1;33m",
+            DiffColor::OldMovedDimmed | DiffColor::NewMovedDimmed => "\x1b[2m",
+            DiffColor::OldMovedAlternativeDimmed | DiffColor::NewMovedAlternativeDimmed => {
+                "\x1b[2;3m"
+            }
+        }
+    }
+}
+
+/// The slots of `git status`, configured with `color.status.<slot>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum StatusColor {
+    Header,
+    Added,
+    Changed,
+    Untracked,
+    Ignored,
+    Branch,
+    NoBranch,
+    LocalBranch,
+    RemoteBranch,
+    Unmerged,
+}
+
+impl ColorSlot for StatusColor {
+    const SECTION: &'static str = "status";
+    const ALL: &'static [StatusColor] = &[
+        StatusColor::Header,
+        StatusColor::Added,
+        StatusColor::Changed,
+        StatusColor::Untracked,
+        StatusColor::Ignored,
+        StatusColor::Branch,
+        StatusColor::NoBranch,
+        StatusColor::LocalBranch,
+        StatusColor::RemoteBranch,
+        StatusColor::Unmerged,
+    ];
+
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            StatusColor::Header => &["header"],
+            StatusColor::Added => &["added", "updated"],
+            StatusColor::Changed => &["changed"],
+            StatusColor::Untracked => &["untracked"],
+            StatusColor::Ignored => &["ignored"],
+            StatusColor::Branch => &["branch"],
+            StatusColor::NoBranch => &["nobranch"],
+            StatusColor::LocalBranch => &["localbranch"],
+            StatusColor::RemoteBranch => &["remotebranch"],
+            StatusColor::Unmerged => &["unmerged"],
+        }
+    }
+
+    fn default_color(self) -> &'static str {
+        match self {
+            StatusColor::Header | StatusColor::Branch => "",
+            StatusColor::Added | StatusColor::LocalBranch => "\x1b[32m",
+            StatusColor::Changed
+            | StatusColor::Untracked
+            | StatusColor::Ignored
+            | StatusColor::NoBranch
+            | StatusColor::RemoteBranch
+            | StatusColor::Unmerged => "\x1b[31m",
+        }
+    }
+}
+
+/// The slots of `git branch`, configured with `color.branch.<slot>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BranchColor {
+    Plain,
+    Current,
+    Local,
+    Remote,
+    Upstream,
+}
+
+impl ColorSlot for BranchColor {
+    const SECTION: &'static str = "branch";
+    const ALL: &'static [BranchColor] = &[
+        BranchColor::Plain,
+        BranchColor::Current,
+        BranchColor::Local,
+        BranchColor::Remote,
+        BranchColor::Upstream,
+    ];
+
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            BranchColor::Plain => &["plain"],
+            BranchColor::Current => &["current"],
+            BranchColor::Local => &["local"],
+            BranchColor::Remote => &["remote"],
+            BranchColor::Upstream => &["upstream"],
+        }
+    }
+
+    fn default_color(self) -> &'static str {
+        match self {
+            BranchColor::Plain | BranchColor::Local => "",
+            BranchColor::Current => "\x1b[32m",
+            BranchColor::Remote => "\x1b[31m",
+            BranchColor::Upstream => "\x1b[34m",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_colors() {
+        assert_eq!(parse_color("red").unwrap(), "\x1b[31m");
+        assert_eq!(parse_color("bold red").unwrap(), "\x1b[1;31m");
+        assert_eq!(parse_color("red bold ul").unwrap(), "\x1b[1;4;31m");
+        assert_eq!(parse_color("normal blue").unwrap(), "\x1b[44m");
+        assert_eq!(parse_color("brightgreen 208").unwrap(), "\x1b[92;48;5;208m");
+        assert_eq!(
+            parse_color("#ff8000 no-italic").unwrap(),
+            "\x1b[23;38;2;255;128;0m"
+        );
+        assert_eq!(parse_color("12").unwrap(), "\x1b[94m");
+        assert_eq!(parse_color("normal").unwrap(), "");
+        assert_eq!(parse_color("reset").unwrap(), RESET);
+        assert_eq!(parse_color("red green blue"), None);
+        assert_eq!(parse_color("bolder"), None);
+    }
+
+    #[test]
+    fn should_override_default_colors_with_config() {
+        let config = BTreeMap::from([
+            (String::from("oldMoved"), String::from("yellow")),
+            (String::from("unknown"), String::from("green")),
+        ]);
+        let palette = Palette::<DiffColor>::new(ColorWhen::Always, &config).unwrap();
+        assert_eq!(palette.get(DiffColor::OldMoved), "\x1b[33m");
+        assert_eq!(palette.get(DiffColor::Meta), "\x1b[1m");
+        assert_eq!(palette.paint(DiffColor::Old, "-a"), "\x1b[31m-a\x1b[m");
+        assert_eq!(palette.paint(DiffColor::Context, " a"), " a");
+
+        let palette = Palette::<DiffColor>::new(ColorWhen::Never, &config).unwrap();
+        assert_eq!(palette.paint(DiffColor::Old, "-a"), "-a");
+        assert_eq!(palette.reset(), "");
+
+        let invalid = BTreeMap::from([(String::from("old"), String::from("reddish"))]);
+        assert!(Palette::<DiffColor>::new(ColorWhen::Always, &invalid).is_err());
+    }
+}
@@ -1,25 +1,50 @@
 use crate::{
     add::command::AddCommand,
+    branch::command::BranchCommand,
     cat_file::command::CatFileCommand,
+    check_ignore::command::CheckIgnoreCommand,
+    checkout_index::command::CheckoutIndexCommand,
     commit::command::CommitCommand,
     commit_tree::command::CommitTreeCommand,
+    diff::command::DiffCommand,
+    diff_files::command::DiffFilesCommand,
+    diff_index::command::DiffIndexCommand,
+    diff_tree::command::DiffTreeCommand,
     error::RustGitError,
+    for_each_ref::command::ForEachRefCommand,
     hash_object::command::HashObjectCommand,
     init::command::InitCommand,
+    log::command::LogCommand,
     ls_files::command::LsFilesCommand,
     mv::command::MvCommand,
+    notes::command::NotesCommand,
+    pathspec::PathspecOptions,
+    read_tree::command::ReadTreeCommand,
+    replace::command::ReplaceCommand,
     repo::RepoState,
     restore::command::RestoreCommand,
     rm::command::RmCommand,
+    show::command::ShowCommand,
+    show_ref::command::ShowRefCommand,
+    status::command::StatusCommand,
     symbolic_ref::{cli::SymbolicRefArgs, command::SymbolicRefCommand},
     tag::command::TagCommand,
+    update_index::command::UpdateIndexCommand,
     update_ref::command::UpdateRefCommand,
+    verify_commit::command::VerifyCommitCommand,
+    verify_tag::command::VerifyTagCommand,
     write_tree::command::WriteTreeCommand,
     Cli, CliCommand,
 };
 
 pub(crate) trait GitCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError>;
+
+    /// Whether the output goes through the pager unless `pager.<cmd>` or `--no-pager` say
+    /// otherwise, which is the case for commands whose output tends to be long.
+    fn pages_by_default(&self) -> bool {
+        false
+    }
 }
 
 // Here we have the mapping logic for converting a `CliCommand` to
@@ -28,21 +53,35 @@ pub(crate) trait GitCommand {
 // This allows us to only pass in the base options that each command
 // actually cares about.
 pub(crate) fn from_cli(value: Cli) -> Result<Box<dyn GitCommand>, RustGitError> {
+    let pathspec_options = || {
+        PathspecOptions::new(
+            value.literal_pathspecs,
+            value.glob_pathspecs,
+            value.noglob_pathspecs,
+            value.icase_pathspecs,
+        )
+    };
+
     match value.command {
         CliCommand::Init(args) => Ok(Box::new(InitCommand::new(
             args,
             value.git_dir,
             value.work_tree,
         ))),
-        CliCommand::Add(args) => Ok(Box::new(AddCommand::new(args))),
+        CliCommand::Add(args) => Ok(Box::new(AddCommand::new(args, pathspec_options()?))),
         CliCommand::HashObject(args) => Ok(Box::new(HashObjectCommand::new(args))),
         CliCommand::CatFile(args) => {
             CatFileCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
         }
-        CliCommand::LsFiles(args) => Ok(Box::new(LsFilesCommand::new(args))),
-        CliCommand::Rm(args) => Ok(Box::new(RmCommand::new(args))),
+        CliCommand::LsFiles(args) => {
+            LsFilesCommand::new(args, pathspec_options()?)
+                .map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::Rm(args) => Ok(Box::new(RmCommand::new(args, pathspec_options()?))),
         CliCommand::Mv(args) => Ok(Box::new(MvCommand::new(args))),
-        CliCommand::Restore(args) => Ok(Box::new(RestoreCommand::new(args))),
+        CliCommand::Restore(args) => {
+            Ok(Box::new(RestoreCommand::new(args, pathspec_options()?)))
+        }
         CliCommand::WriteTree(args) => Ok(Box::new(WriteTreeCommand::new(args))),
         CliCommand::Commit(args) => {
             CommitCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
@@ -55,5 +94,48 @@ pub(crate) fn from_cli(value: Cli) -> Result<Box<dyn GitCommand>, RustGitError>
         CliCommand::Tag(args) => {
             TagCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
         }
+        CliCommand::ForEachRef(args) => {
+            ForEachRefCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::ShowRef(args) => {
+            ShowRefCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::Branch(args) => {
+            BranchCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::VerifyCommit(args) => Ok(Box::new(VerifyCommitCommand::new(args))),
+        CliCommand::VerifyTag(args) => Ok(Box::new(VerifyTagCommand::new(args))),
+        CliCommand::Replace(args) => {
+            ReplaceCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::Notes(args) => {
+            NotesCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::Log(args) => Ok(Box::new(LogCommand::new(args))),
+        CliCommand::Show(args) => Ok(Box::new(ShowCommand::new(args))),
+        CliCommand::UpdateIndex(args) => {
+            UpdateIndexCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::ReadTree(args) => {
+            ReadTreeCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::CheckoutIndex(args) => {
+            CheckoutIndexCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::CheckIgnore(args) => {
+            CheckIgnoreCommand::new(args).map(|res| Box::new(res) as Box<dyn GitCommand>)
+        }
+        CliCommand::Status(args) => StatusCommand::new(args, pathspec_options()?)
+            .map(|res| Box::new(res) as Box<dyn GitCommand>),
+        CliCommand::Diff(args) => Ok(Box::new(DiffCommand::new(args, pathspec_options()?))),
+        CliCommand::DiffFiles(args) => {
+            Ok(Box::new(DiffFilesCommand::new(args, pathspec_options()?)))
+        }
+        CliCommand::DiffIndex(args) => {
+            Ok(Box::new(DiffIndexCommand::new(args, pathspec_options()?)))
+        }
+        CliCommand::DiffTree(args) => {
+            Ok(Box::new(DiffTreeCommand::new(args, pathspec_options()?)))
+        }
     }
 }
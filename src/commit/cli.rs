@@ -42,4 +42,23 @@ pub(crate) struct CommitArgs {
     /// The -m option is mutually exclusive with -c, -C, and -F.
     #[arg(short, long("message"), value_name = "msg")]
     pub messages: Vec<String>,
+
+    /// GPG-sign commits. The keyid argument is optional and defaults to the committer identity; if specified,
+    /// it must be given with an equals sign, e.g. -S=<keyid> or --gpg-sign=<keyid>. --no-gpg-sign is useful
+    /// to countermand both commit.gpgSign configuration variable, and earlier --gpg-sign.
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "keyid",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        overrides_with = "no_gpg_sign"
+    )]
+    pub gpg_sign: Option<String>,
+
+    /// Countermand commit.gpgSign configuration variable that is set to force each and every commit to be
+    /// signed.
+    #[arg(long, overrides_with = "gpg_sign")]
+    pub no_gpg_sign: bool,
 }
@@ -1,9 +1,11 @@
-use crate::{command::GitCommand, repo::RepoState, RustGitError};
+use crate::{command::GitCommand, gpg::resolve_signing_key, repo::RepoState, RustGitError};
 
 use super::cli::CommitArgs;
 
 pub(crate) struct CommitCommand {
     pub message: String,
+    pub gpg_sign: Option<String>,
+    pub no_gpg_sign: bool,
 }
 
 impl CommitCommand {
@@ -14,32 +16,43 @@ impl CommitCommand {
             return Err(RustGitError::new("commit message cannot be empty"));
         }
 
-        Ok(CommitCommand { message })
+        Ok(CommitCommand {
+            message,
+            gpg_sign: args.gpg_sign,
+            no_gpg_sign: args.no_gpg_sign,
+        })
     }
 }
 
 impl GitCommand for CommitCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
-        let repo = repo_state.try_get()?;
+        let mut repo = repo_state.try_get()?;
 
         // Write index as tree.
         let tree_id = repo.write_index_as_tree()?;
 
-        let (current_head_branch, current_head_ref) = repo.get_head_ref()?;
-
-        let current_head_branch = if let Some(current_head_branch) = current_head_branch {
-            current_head_branch
-        } else {
-            return Err(RustGitError::new("couldn't load HEAD"));
+        let head = match repo.read_head()? {
+            Some(head) => head,
+            None => return Err(RustGitError::new("couldn't load HEAD")),
         };
 
-        let parents = current_head_ref.map_or(vec![], |current_head| vec![current_head]);
+        let parents = head
+            .id()
+            .map_or(vec![], |current_head| vec![current_head.clone()]);
+
+        let signing_key = resolve_signing_key(
+            &repo.config,
+            self.gpg_sign.as_deref(),
+            repo.config.commit.gpgsign && !self.no_gpg_sign,
+        )?;
 
         // Create commit object with parent set to current HEAD.
-        let commit_id = repo.write_commit(&tree_id, &parents, &self.message)?;
+        let commit_id =
+            repo.write_commit(&tree_id, &parents, &self.message, signing_key.as_deref())?;
 
-        // Update HEAD branch to point to newly created commit.
-        repo.update_ref(&current_head_branch, &commit_id.to_string(), None)?;
+        // Update HEAD to point to newly created commit. If HEAD is attached to a branch, the
+        // branch is updated, otherwise the detached HEAD is moved.
+        repo.refs.update_head(&commit_id)?;
 
         println!("{commit_id}");
 
@@ -1,6 +1,9 @@
 use std::str::FromStr;
 
-use crate::{command::GitCommand, object::GitObjectId, repo::RepoState, RustGitError};
+use crate::{
+    command::GitCommand, gpg::resolve_signing_key, object::GitObjectId, repo::RepoState,
+    RustGitError,
+};
 
 use super::cli::CommitTreeArgs;
 
@@ -8,6 +11,8 @@ pub(crate) struct CommitTreeCommand {
     tree: GitObjectId,
     parents: Vec<GitObjectId>,
     message: String,
+    gpg_sign: Option<String>,
+    no_gpg_sign: bool,
 }
 
 impl CommitTreeCommand {
@@ -29,6 +34,8 @@ impl CommitTreeCommand {
             tree,
             parents,
             message,
+            gpg_sign: args.gpg_sign,
+            no_gpg_sign: args.no_gpg_sign,
         })
     }
 }
@@ -37,7 +44,18 @@ impl GitCommand for CommitTreeCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
         let repo = repo_state.try_get()?;
 
-        let object_id = repo.write_commit(&self.tree, &self.parents, &self.message)?;
+        let signing_key = resolve_signing_key(
+            &repo.config,
+            self.gpg_sign.as_deref(),
+            repo.config.commit.gpgsign && !self.no_gpg_sign,
+        )?;
+
+        let object_id = repo.write_commit(
+            &self.tree,
+            &self.parents,
+            &self.message,
+            signing_key.as_deref(),
+        )?;
 
         println!("{object_id}");
 
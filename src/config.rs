@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{Read, Write},
     path::Path,
@@ -6,7 +7,14 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use crate::{error::RustGitError, init::cli::HashAlgorithm};
+use crate::{
+    color::ColorWhen,
+    diff_format::ColorMovedMode,
+    error::RustGitError,
+    gpg::SignatureFormat,
+    init::cli::{HashAlgorithm, RefStorageFormat},
+    notes_tree::NotesMergeStrategy,
+};
 
 const CONFIG_FILE_NAME: &str = "config";
 
@@ -20,6 +28,31 @@ pub(crate) struct GitConfig {
     pub(crate) core: CoreConfig,
     pub(crate) user: UserConfig,
     pub(crate) extensions: ExtensionsConfig,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) branch: BTreeMap<String, BranchConfig>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) commit: CommitConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) tag: TagConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) gpg: GpgConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) notes: NotesConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) index: IndexConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) diff: DiffConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) status: StatusConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) color: ColorConfig,
+    /// Whether each command pages its output, or the pager it uses, under `[pager]`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) pager: BTreeMap<String, PagerConfig>,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -32,6 +65,20 @@ pub(crate) struct CoreConfig {
     pub(crate) ignorecase: bool,
     pub(crate) precomposeunicode: bool,
     pub(crate) symlinks: bool,
+    /// Whether ctime differences between the index and the worktree count as changes.
+    #[serde(alias = "trustCtime")]
+    pub(crate) trustctime: bool,
+    pub(crate) editor: Option<String>,
+    /// The notes ref read and written by default, instead of `refs/notes/commits`.
+    #[serde(alias = "notesRef")]
+    pub(crate) notesref: Option<String>,
+    /// The comma separated kinds of files flushed to disk when written, see [FsyncComponent].
+    pub(crate) fsync: Option<String>,
+    /// A file of ignore patterns read in addition to the `.gitignore` files and `info/exclude`.
+    #[serde(alias = "excludesFile")]
+    pub(crate) excludesfile: Option<String>,
+    /// The pager used by commands, unless overridden by `$GIT_PAGER`.
+    pub(crate) pager: Option<String>,
 }
 
 impl Default for CoreConfig {
@@ -44,14 +91,117 @@ impl Default for CoreConfig {
             ignorecase: false,
             precomposeunicode: true,
             symlinks: true,
+            trustctime: true,
+            editor: None,
+            notesref: None,
+            fsync: None,
+            excludesfile: None,
+            pager: None,
         }
     }
 }
 
+impl CoreConfig {
+    /// Returns whether files of the provided kind should be flushed to disk when written.
+    pub(crate) fn should_fsync(&self, component: FsyncComponent) -> bool {
+        let Some(fsync) = &self.fsync else {
+            return FsyncComponent::DEFAULT.contains(&component);
+        };
+
+        // Like C Git, components can be removed from the defaults with a leading "-", and "none"
+        // clears the defaults.
+        let mut current = FsyncComponent::DEFAULT.to_vec();
+        let (mut positive, mut negative) = (vec![], vec![]);
+        for name in fsync
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if name == "none" {
+                current.clear();
+                continue;
+            }
+            let (components, name) = match name.strip_prefix('-') {
+                Some(name) => (&mut negative, name),
+                None => (&mut positive, name),
+            };
+            match FsyncComponent::parse(name) {
+                Some(parsed) => components.extend_from_slice(parsed),
+                None => eprintln!("warning: ignoring unknown core.fsync component '{name}'"),
+            }
+        }
+
+        positive.contains(&component)
+            || (current.contains(&component) && !negative.contains(&component))
+    }
+}
+
+/// A kind of file which `core.fsync` can require to be flushed to disk when written.
+/// See `core.fsync` in git-config(1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FsyncComponent {
+    LooseObject,
+    Pack,
+    PackMetadata,
+    CommitGraph,
+    Index,
+    Reference,
+}
+
+impl FsyncComponent {
+    const DEFAULT: &'static [FsyncComponent] = &[
+        FsyncComponent::Pack,
+        FsyncComponent::PackMetadata,
+        FsyncComponent::Reference,
+    ];
+    const OBJECTS: &'static [FsyncComponent] = &[FsyncComponent::LooseObject, FsyncComponent::Pack];
+    const DERIVED_METADATA: &'static [FsyncComponent] =
+        &[FsyncComponent::PackMetadata, FsyncComponent::CommitGraph];
+    const COMMITTED: &'static [FsyncComponent] = &[
+        FsyncComponent::LooseObject,
+        FsyncComponent::Pack,
+        FsyncComponent::Reference,
+    ];
+    const ADDED: &'static [FsyncComponent] = &[
+        FsyncComponent::LooseObject,
+        FsyncComponent::Pack,
+        FsyncComponent::Reference,
+        FsyncComponent::Index,
+    ];
+    const ALL: &'static [FsyncComponent] = &[
+        FsyncComponent::LooseObject,
+        FsyncComponent::Pack,
+        FsyncComponent::PackMetadata,
+        FsyncComponent::CommitGraph,
+        FsyncComponent::Index,
+        FsyncComponent::Reference,
+    ];
+
+    /// Parses a component name, or the name of a group of components.
+    fn parse(name: &str) -> Option<&'static [FsyncComponent]> {
+        Some(match name {
+            "loose-object" => &[FsyncComponent::LooseObject],
+            "pack" => &[FsyncComponent::Pack],
+            "pack-metadata" => &[FsyncComponent::PackMetadata],
+            "commit-graph" => &[FsyncComponent::CommitGraph],
+            "index" => &[FsyncComponent::Index],
+            "reference" => &[FsyncComponent::Reference],
+            "objects" => FsyncComponent::OBJECTS,
+            "derived-metadata" => FsyncComponent::DERIVED_METADATA,
+            "committed" => FsyncComponent::COMMITTED,
+            "added" => FsyncComponent::ADDED,
+            "all" => FsyncComponent::ALL,
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(default)]
 pub(crate) struct ExtensionsConfig {
     pub(crate) objectformat: HashAlgorithm,
+    #[serde(alias = "refStorage", skip_serializing_if = "is_default")]
+    pub(crate) refstorage: RefStorageFormat,
 }
 
 impl GitConfig {
@@ -68,7 +218,7 @@ impl GitConfig {
         Ok(config)
     }
 
-    pub(crate) fn write(self, dir: &Path) -> Result<(), RustGitError> {
+    pub(crate) fn write(&self, dir: &Path) -> Result<(), RustGitError> {
         let config_file_path = dir.join(CONFIG_FILE_NAME);
         let mut config_file = File::create(config_file_path)?;
         // TODO: using TOML for ease of use, but the git config format isn't TOML
@@ -76,6 +226,26 @@ impl GitConfig {
         config_file.write_all(toml::to_string_pretty(&self)?.as_bytes())?;
         Ok(())
     }
+
+    /// Writes the `[branch.<name>]` sections to the config file, leaving every other section as
+    /// it is on disk, including the ones this config doesn't model.
+    pub(crate) fn write_branches(&self, dir: &Path) -> Result<(), RustGitError> {
+        let config_file_path = dir.join(CONFIG_FILE_NAME);
+        let mut config_s = String::new();
+        if config_file_path.exists() {
+            File::open(&config_file_path)?.read_to_string(&mut config_s)?;
+        }
+
+        let mut table: toml::Table = toml::de::from_str(&config_s)?;
+        if self.branch.is_empty() {
+            table.remove("branch");
+        } else {
+            table.insert(String::from("branch"), toml::Value::try_from(&self.branch)?);
+        }
+
+        File::create(config_file_path)?.write_all(toml::to_string_pretty(&table)?.as_bytes())?;
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -83,4 +253,265 @@ impl GitConfig {
 pub(crate) struct UserConfig {
     pub(crate) name: Option<String>,
     pub(crate) email: Option<String>,
+    /// The key to sign commits and tags with, if it can't be derived from the user's identity.
+    /// For SSH signatures, this is the path to a key file or a literal public key.
+    pub(crate) signingkey: Option<String>,
+}
+
+/// Configuration for `git commit`, stored under `[commit]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct CommitConfig {
+    /// Whether all commits should be signed.
+    pub(crate) gpgsign: bool,
+}
+
+/// Configuration for `git tag`, stored under `[tag]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct TagConfig {
+    /// Whether all annotated tags should be signed.
+    pub(crate) gpgsign: bool,
+}
+
+/// Configuration for signing and verifying signatures, stored under `[gpg]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct GpgConfig {
+    /// The format of the signatures to create.
+    pub(crate) format: SignatureFormat,
+    /// The program used for OpenPGP signatures, `gpg` by default.
+    pub(crate) program: Option<String>,
+    pub(crate) x509: GpgProgramConfig,
+    pub(crate) ssh: GpgSshConfig,
+}
+
+/// Configuration for X.509 signatures, stored under `[gpg.x509]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct GpgProgramConfig {
+    /// The program used for X.509 signatures, `gpgsm` by default.
+    pub(crate) program: Option<String>,
+}
+
+/// Configuration for SSH signatures, stored under `[gpg.ssh]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct GpgSshConfig {
+    /// The program used for SSH signatures, `ssh-keygen` by default.
+    pub(crate) program: Option<String>,
+    /// A file of public keys and the principals they belong to, which are trusted when verifying
+    /// SSH signatures. See the "ALLOWED SIGNERS" section of ssh-keygen(1).
+    #[serde(alias = "allowedSignersFile")]
+    pub(crate) allowedsignersfile: Option<String>,
+}
+
+/// Configuration for `git notes`, stored under `[notes]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct NotesConfig {
+    /// The strategy used by `git notes merge` when none is given.
+    #[serde(alias = "mergeStrategy")]
+    pub(crate) mergestrategy: Option<NotesMergeStrategy>,
+}
+
+/// Configuration for the index, stored under `[index]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct IndexConfig {
+    /// The version new indexes are written in.
+    pub(crate) version: Option<u32>,
+}
+
+/// Whether renames are detected, which is either a boolean or "copies" to detect copies too.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum RenamesConfig {
+    Enabled(bool),
+    Copies(CopiesKeyword),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CopiesKeyword {
+    #[serde(alias = "copy")]
+    Copies,
+}
+
+/// Configuration for diffs shown by porcelain commands, stored under `[diff]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct DiffConfig {
+    /// Whether renames are detected, which they are by default.
+    pub(crate) renames: Option<RenamesConfig>,
+    /// The maximum number of files considered for inexact rename detection.
+    #[serde(alias = "renameLimit")]
+    pub(crate) renamelimit: Option<usize>,
+    /// How moved lines are colored, either a boolean or the mode of `--color-moved`.
+    #[serde(alias = "colorMoved")]
+    pub(crate) colormoved: Option<ColorMovedConfig>,
+}
+
+/// Whether moved lines are colored, which is either a boolean or the mode used.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum ColorMovedConfig {
+    Enabled(bool),
+    Mode(ColorMovedMode),
+}
+
+impl ColorMovedConfig {
+    pub(crate) fn mode(self) -> ColorMovedMode {
+        match self {
+            ColorMovedConfig::Enabled(true) => ColorMovedMode::Default,
+            ColorMovedConfig::Enabled(false) => ColorMovedMode::No,
+            ColorMovedConfig::Mode(mode) => mode,
+        }
+    }
+}
+
+/// Configuration for `git status`, stored under `[status]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct StatusConfig {
+    /// Whether renames are detected, overriding diff.renames.
+    pub(crate) renames: Option<RenamesConfig>,
+    /// The maximum number of files considered for inexact rename detection, overriding
+    /// diff.renameLimit.
+    #[serde(alias = "renameLimit")]
+    pub(crate) renamelimit: Option<usize>,
+}
+
+/// When output is colored, which is either a boolean, where true means automatically, or a
+/// [ColorWhen].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum ColorSetting {
+    Enabled(bool),
+    When(ColorWhen),
+}
+
+impl ColorSetting {
+    pub(crate) fn when(self) -> ColorWhen {
+        match self {
+            ColorSetting::Enabled(true) => ColorWhen::Auto,
+            ColorSetting::Enabled(false) => ColorWhen::Never,
+            ColorSetting::When(when) => when,
+        }
+    }
+}
+
+/// The colors of a command, e.g. `color.diff`: either when its output is colored, or the colors
+/// of its slots. With slots, output is colored according to `color.ui`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum ColorSection {
+    Setting(ColorSetting),
+    Slots(BTreeMap<String, String>),
+}
+
+impl Default for ColorSection {
+    fn default() -> Self {
+        ColorSection::Slots(BTreeMap::new())
+    }
+}
+
+impl ColorSection {
+    /// Returns the colors configured for slots of the output.
+    pub(crate) fn slots(&self) -> &BTreeMap<String, String> {
+        static NO_SLOTS: BTreeMap<String, String> = BTreeMap::new();
+        match self {
+            ColorSection::Setting(_) => &NO_SLOTS,
+            ColorSection::Slots(slots) => slots,
+        }
+    }
+}
+
+/// Configuration for colored output, stored under `[color]`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct ColorConfig {
+    /// When the output of all commands is colored, unless configured for the command.
+    pub(crate) ui: Option<ColorSetting>,
+    pub(crate) diff: ColorSection,
+    pub(crate) status: ColorSection,
+    pub(crate) branch: ColorSection,
+}
+
+impl ColorConfig {
+    /// Returns when the output of a command with the provided colors is colored, falling back
+    /// to `color.ui`. Output is colored automatically by default.
+    pub(crate) fn when(&self, section: &ColorSection) -> ColorWhen {
+        match section {
+            ColorSection::Setting(setting) => setting.when(),
+            ColorSection::Slots(_) => self.ui.map_or(ColorWhen::Auto, ColorSetting::when),
+        }
+    }
+}
+
+/// Whether a command pages its output, or the pager it uses, configured with `pager.<cmd>`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum PagerConfig {
+    Enabled(bool),
+    Command(String),
+}
+
+/// Per-branch configuration, stored under `[branch.<name>]`.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct BranchConfig {
+    /// The remote to fetch from for this branch, or "." for a local upstream.
+    pub(crate) remote: Option<String>,
+    /// The full name of the upstream branch on the remote, e.g. `refs/heads/main`.
+    pub(crate) merge: Option<String>,
+    /// The keys this config doesn't model, like `description`, kept so they can be written back.
+    #[serde(flatten)]
+    pub(crate) other: toml::Table,
+}
+
+impl BranchConfig {
+    /// Returns the full name of the upstream ref of this branch, if it has one configured.
+    /// For remote upstreams, this is the remote-tracking ref, e.g. `refs/remotes/origin/main`.
+    pub(crate) fn upstream_ref(&self) -> Option<String> {
+        match (&self.remote, &self.merge) {
+            (Some(remote), Some(merge)) if remote == "." => Some(merge.clone()),
+            (Some(remote), Some(merge)) => Some(format!(
+                "refs/remotes/{remote}/{}",
+                merge.trim_start_matches("refs/heads/")
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core_config(fsync: Option<&str>) -> CoreConfig {
+        CoreConfig {
+            fsync: fsync.map(String::from),
+            ..CoreConfig::default()
+        }
+    }
+
+    #[test]
+    fn should_fsync_configured_components() {
+        let default = core_config(None);
+        assert!(default.should_fsync(FsyncComponent::Reference));
+        assert!(!default.should_fsync(FsyncComponent::Index));
+
+        let added = core_config(Some("added"));
+        assert!(added.should_fsync(FsyncComponent::Index));
+        assert!(added.should_fsync(FsyncComponent::LooseObject));
+
+        let without_reference = core_config(Some("-reference"));
+        assert!(!without_reference.should_fsync(FsyncComponent::Reference));
+        assert!(without_reference.should_fsync(FsyncComponent::Pack));
+
+        let none = core_config(Some("none, index"));
+        assert!(none.should_fsync(FsyncComponent::Index));
+        assert!(!none.should_fsync(FsyncComponent::Reference));
+    }
 }
@@ -0,0 +1,162 @@
+use crate::error::RustGitError;
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The date formats supported when displaying timestamps, see the `--date` option of git-log(1).
+///
+/// Timestamps are always stored in UTC for now (see `GitRepo::get_timestamp`), so every format
+/// uses a `+0000` offset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum DateFormat {
+    #[default]
+    Default,
+    Unix,
+    Raw,
+    Iso,
+    IsoStrict,
+    Rfc2822,
+    Short,
+}
+
+impl std::str::FromStr for DateFormat {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(DateFormat::Default),
+            "unix" => Ok(DateFormat::Unix),
+            "raw" => Ok(DateFormat::Raw),
+            "iso" | "iso8601" => Ok(DateFormat::Iso),
+            "iso-strict" | "iso8601-strict" => Ok(DateFormat::IsoStrict),
+            "rfc" | "rfc2822" => Ok(DateFormat::Rfc2822),
+            "short" => Ok(DateFormat::Short),
+            other => Err(RustGitError::new(format!("unknown date format {other}"))),
+        }
+    }
+}
+
+/// Broken-down UTC time.
+struct CivilTime {
+    year: i64,
+    month: usize,
+    day: u32,
+    hour: u64,
+    minute: u64,
+    second: u64,
+    weekday: usize,
+}
+
+// Converts days since the unix epoch to a (year, month, day) triple, using the algorithm from:
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, usize, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as usize;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn to_civil_time(seconds: u64) -> CivilTime {
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+    let (year, month, day) = civil_from_days(days);
+
+    CivilTime {
+        year,
+        month,
+        day,
+        hour: seconds_of_day / 3600,
+        minute: (seconds_of_day % 3600) / 60,
+        second: seconds_of_day % 60,
+        weekday: (days % 7) as usize,
+    }
+}
+
+/// Formats a timestamp, given in milliseconds since the unix epoch, in the provided format.
+pub(crate) fn format_timestamp(timestamp: u128, format: DateFormat) -> String {
+    let seconds = (timestamp / 1000) as u64;
+    let time = to_civil_time(seconds);
+    let month_name = MONTHS[time.month - 1];
+    let weekday_name = WEEKDAYS[time.weekday];
+
+    match format {
+        DateFormat::Default => format!(
+            "{weekday_name} {month_name} {} {:02}:{:02}:{:02} {} +0000",
+            time.day, time.hour, time.minute, time.second, time.year
+        ),
+        DateFormat::Unix => seconds.to_string(),
+        DateFormat::Raw => format!("{seconds} +0000"),
+        DateFormat::Iso => format!(
+            "{}-{:02}-{:02} {:02}:{:02}:{:02} +0000",
+            time.year, time.month, time.day, time.hour, time.minute, time.second
+        ),
+        DateFormat::IsoStrict => format!(
+            "{}-{:02}-{:02}T{:02}:{:02}:{:02}+00:00",
+            time.year, time.month, time.day, time.hour, time.minute, time.second
+        ),
+        DateFormat::Rfc2822 => format!(
+            "{weekday_name}, {} {month_name} {} {:02}:{:02}:{:02} +0000",
+            time.day, time.year, time.hour, time.minute, time.second
+        ),
+        DateFormat::Short => format!("{}-{:02}-{:02}", time.year, time.month, time.day),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_epoch() {
+        assert_eq!(
+            format_timestamp(0, DateFormat::Default),
+            "Thu Jan 1 00:00:00 1970 +0000"
+        );
+        assert_eq!(format_timestamp(0, DateFormat::Short), "1970-01-01");
+    }
+
+    #[test]
+    fn should_format_timestamp_in_all_formats() {
+        // 2024-02-29 13:14:15 UTC
+        let timestamp = 1709212455000;
+        assert_eq!(
+            format_timestamp(timestamp, DateFormat::Default),
+            "Thu Feb 29 13:14:15 2024 +0000"
+        );
+        assert_eq!(format_timestamp(timestamp, DateFormat::Unix), "1709212455");
+        assert_eq!(
+            format_timestamp(timestamp, DateFormat::Raw),
+            "1709212455 +0000"
+        );
+        assert_eq!(
+            format_timestamp(timestamp, DateFormat::Iso),
+            "2024-02-29 13:14:15 +0000"
+        );
+        assert_eq!(
+            format_timestamp(timestamp, DateFormat::IsoStrict),
+            "2024-02-29T13:14:15+00:00"
+        );
+        assert_eq!(
+            format_timestamp(timestamp, DateFormat::Rfc2822),
+            "Thu, 29 Feb 2024 13:14:15 +0000"
+        );
+    }
+
+    #[test]
+    fn should_parse_date_format() {
+        assert_eq!("iso".parse(), Ok(DateFormat::Iso));
+        assert_eq!("short".parse(), Ok(DateFormat::Short));
+        assert_eq!(
+            "invalid".parse::<DateFormat>(),
+            Err(RustGitError::new("unknown date format invalid"))
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use clap::Args;
+
+use crate::{
+    color::ColorWhen,
+    diff_format::{ColorMovedMode, WordDiffMode},
+    diffcore_rename::parse_rename_score,
+    xdiff::DiffAlgorithm,
+};
+
+/// The options controlling how diff commands compare files and show the changes.
+#[derive(Args, Debug)]
+pub(crate) struct DiffOptionArgs {
+    /// Generate patch.
+    #[arg(short = 'p', short_alias = 'u', long)]
+    pub patch: bool,
+
+    /// Suppress all output from the diff machinery.
+    #[arg(short = 's', long)]
+    pub no_patch: bool,
+
+    /// Generate diffs with <n> lines of context instead of the usual three. Implies --patch.
+    #[arg(short = 'U', long, value_name = "n")]
+    pub unified: Option<usize>,
+
+    /// Generate the diff in raw format.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Show only names of changed files.
+    #[arg(long, conflicts_with = "name_status")]
+    pub name_only: bool,
+
+    /// Show only names and status of changed files.
+    #[arg(long)]
+    pub name_status: bool,
+
+    /// Generate a diffstat. The width of the output defaults to 80 columns, and can be overridden by
+    /// --stat=<width>.
+    #[arg(
+        long,
+        value_name = "width",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0"
+    )]
+    pub stat: Option<usize>,
+
+    /// Similar to --stat, but shows number of added and deleted lines in decimal notation and pathname without
+    /// abbreviation, to make it more machine friendly. For binary files, outputs two - instead of saying 0 0.
+    #[arg(long)]
+    pub numstat: bool,
+
+    /// Output only the last line of the --stat format containing total number of modified files, as well as
+    /// number of added and deleted lines.
+    #[arg(long)]
+    pub shortstat: bool,
+
+    /// Output a condensed summary of extended header information such as creations, deletions and mode
+    /// changes.
+    #[arg(long)]
+    pub summary: bool,
+
+    /// Instead of showing the full 40-byte hexadecimal object name in diff-raw format output and diff-tree
+    /// header lines, show the shortest prefix that is at least <n> hexdigits long.
+    #[arg(
+        long,
+        value_name = "n",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "7"
+    )]
+    pub abbrev: Option<usize>,
+
+    /// Detect renames. If n is specified, it is a threshold on the similarity index (i.e. amount of addition/deletions
+    /// compared to the file's size). For example, -M=90% means Git should consider a delete/add pair to be a rename if
+    /// more than 90% of the file hasn't changed. Without a % sign, the number is to be read as a fraction, with a
+    /// decimal point before it. The default similarity index is 50%. If specified, n must be given with an equals
+    /// sign, e.g. -M=90% or --find-renames=90%.
+    #[arg(
+        short = 'M',
+        long,
+        value_name = "n",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_parser = parse_rename_score,
+        overrides_with_all = ["no_renames", "find_copies"]
+    )]
+    pub find_renames: Option<u32>,
+
+    /// Detect copies as well as renames. If n is specified, it has the same meaning as for -M.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "n",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_parser = parse_rename_score,
+        overrides_with_all = ["no_renames", "find_renames"]
+    )]
+    pub find_copies: Option<u32>,
+
+    /// For performance reasons, by default, -C option finds copies only if the original file of the copy was
+    /// modified in the same changeset. This flag makes the command inspect unmodified files as candidates for the
+    /// source of copy. Implies -C.
+    #[arg(long)]
+    pub find_copies_harder: bool,
+
+    /// Turn off rename detection, even when the configuration file gives the default to do so.
+    #[arg(long, overrides_with_all = ["find_renames", "find_copies"])]
+    pub no_renames: bool,
+
+    /// Prevent the exhaustive portion of rename/copy detection from running if the number of source/destination
+    /// files involved exceeds the specified number. Defaults to diff.renameLimit.
+    #[arg(short = 'l', value_name = "num")]
+    pub rename_limit: Option<usize>,
+
+    /// Spend extra time to make sure the smallest possible diff is produced.
+    #[arg(long)]
+    pub minimal: bool,
+
+    /// Generate a diff using the "patience diff" algorithm.
+    #[arg(long)]
+    pub patience: bool,
+
+    /// Generate a diff using the "histogram diff" algorithm.
+    #[arg(long)]
+    pub histogram: bool,
+
+    /// Choose a diff algorithm.
+    #[arg(long, value_name = "algorithm")]
+    pub diff_algorithm: Option<DiffAlgorithm>,
+
+    /// Ignore whitespace when comparing lines. This ignores differences even if one line has whitespace where
+    /// the other line has none.
+    #[arg(short = 'w', long)]
+    pub ignore_all_space: bool,
+
+    /// Ignore changes in amount of whitespace. This ignores whitespace at line end, and considers all other
+    /// sequences of one or more whitespace characters to be equivalent.
+    #[arg(short = 'b', long)]
+    pub ignore_space_change: bool,
+
+    /// Ignore changes in whitespace at EOL.
+    #[arg(long)]
+    pub ignore_space_at_eol: bool,
+
+    /// Ignore changes whose lines are all blank.
+    #[arg(long)]
+    pub ignore_blank_lines: bool,
+
+    /// Show the context between diff hunks, up to the specified number of lines, thereby fusing hunks that
+    /// are close to each other.
+    #[arg(long, value_name = "lines")]
+    pub inter_hunk_context: Option<usize>,
+
+    /// Show whole function as context lines for each change.
+    #[arg(short = 'W', long)]
+    pub function_context: bool,
+
+    /// Enable the heuristic that shifts diff hunk boundaries to make patches easier to read. This is the
+    /// default.
+    #[arg(long, overrides_with = "no_indent_heuristic")]
+    pub indent_heuristic: bool,
+
+    /// Disable the indent heuristic.
+    #[arg(long, overrides_with = "indent_heuristic")]
+    pub no_indent_heuristic: bool,
+
+    /// Show colored diff. The value must be always (the default when omitted), never, or auto.
+    #[arg(
+        long,
+        value_name = "when",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always",
+        overrides_with = "no_color"
+    )]
+    pub color: Option<ColorWhen>,
+
+    /// Turn off colored diff, even when the configuration file gives the default to color output.
+    #[arg(long, overrides_with = "color")]
+    pub no_color: bool,
+
+    /// Show a word diff, using the mode to delimit changed words. By default, words are delimited by
+    /// whitespace.
+    #[arg(
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "plain"
+    )]
+    pub word_diff: Option<WordDiffMode>,
+
+    /// Moved lines of code are colored differently. The mode defaults to zebra when the option is
+    /// given without one, and to diff.colorMoved otherwise.
+    #[arg(
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "default",
+        overrides_with = "no_color_moved"
+    )]
+    pub color_moved: Option<ColorMovedMode>,
+
+    /// Turn off move detection, even when the configuration file gives the default to do so.
+    #[arg(long, overrides_with = "color_moved")]
+    pub no_color_moved: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(about = "Show changes between commits, commit and working tree, etc")]
+#[command(long_about = "
+Show changes between the working tree and the index or a tree, changes between the index and a tree, changes
+between two trees, or changes between two files on disk.
+
+git diff [<options>] [--] [<path>...]
+    This form is to view the changes you made relative to the index (staging area for the next commit).
+
+git diff [<options>] --no-index [--] <path> <path>
+    This form is to compare the given two paths on the filesystem.
+
+git diff [<options>] --cached [<commit>] [--] [<path>...]
+    This form is to view the changes you staged for the next commit relative to the named <commit>, which
+    defaults to HEAD.
+
+git diff [<options>] <commit> [--] [<path>...]
+    This form is to view the changes you have in your working tree relative to the named <commit>.
+
+git diff [<options>] <commit> <commit> [--] [<path>...]
+    This is to view the changes between two arbitrary <commit>. The forms <commit>..<commit> and
+    <commit>...<commit>, which compares the merge base of both commits with the second one, are accepted
+    too.
+")]
+pub(crate) struct DiffArgs {
+    #[command(flatten)]
+    pub options: DiffOptionArgs,
+
+    /// View the changes staged for the next commit relative to the named commit, which defaults to HEAD.
+    #[arg(long, visible_alias = "staged")]
+    pub cached: bool,
+
+    /// Compare the two given paths on the filesystem, which may be outside of a repository.
+    #[arg(long)]
+    pub no_index: bool,
+
+    /// The commits to compare, followed by the paths to limit the diff to.
+    #[arg(value_name = "commit-or-path")]
+    pub args: Vec<String>,
+
+    /// The paths to limit the diff to.
+    #[arg(last = true, value_name = "path")]
+    pub pathspec: Vec<String>,
+}
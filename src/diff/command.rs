@@ -0,0 +1,392 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    color::{ColorWhen, Palette},
+    command::GitCommand,
+    config::{ColorMovedConfig, CopiesKeyword, GitConfig, RenamesConfig},
+    diff_format::{format_diff, ColorMovedMode, DiffOptions, DiffOutputFormat, WordDiffMode},
+    diffcore::{read_worktree_file, GitDiffPair, GitDiffSide},
+    diffcore_rename::{detect_renames, RenameOptions, DEFAULT_RENAME_LIMIT, DEFAULT_RENAME_SCORE},
+    index::GitIndexMode,
+    object::{GitObject, GitObjectId, GitObjectType},
+    pathspec::{Pathspec, PathspecOptions},
+    repo::{GitRepo, RepoState},
+    xdiff::DiffAlgorithm,
+    RustGitError,
+};
+
+use super::cli::{DiffArgs, DiffOptionArgs};
+
+/// Returns the options of the diff machinery set on the command line. Porcelain commands show
+/// patches with abbreviated object ids by default, while plumbing commands show the raw format
+/// with full object ids.
+pub(crate) fn diff_options(args: &DiffOptionArgs, porcelain: bool) -> DiffOptions {
+    let mut options = DiffOptions::default();
+
+    let mut output = DiffOutputFormat {
+        raw: args.raw,
+        numstat: args.numstat,
+        stat: args.stat.is_some(),
+        shortstat: args.shortstat,
+        summary: args.summary,
+        patch: args.patch || args.unified.is_some(),
+        ..DiffOutputFormat::default()
+    };
+    // Listing names replaces every other output.
+    if args.name_only || args.name_status {
+        output = DiffOutputFormat {
+            name_only: args.name_only,
+            name_status: args.name_status,
+            ..DiffOutputFormat::default()
+        };
+    } else if output == DiffOutputFormat::default() {
+        output.patch = porcelain;
+        output.raw = !porcelain;
+    }
+    if args.no_patch {
+        output = DiffOutputFormat::default();
+    }
+    options.output = output;
+
+    if let Some(width) = args.stat.filter(|width| *width > 0) {
+        options.stat_width = width;
+    }
+    options.abbrev_raw = porcelain || args.abbrev.is_some();
+    if let Some(abbrev) = args.abbrev {
+        options.abbrev = abbrev.clamp(4, 40);
+    }
+
+    let xdiff = &mut options.xdiff;
+    xdiff.algorithm = match args.diff_algorithm {
+        Some(algorithm) => algorithm,
+        None if args.histogram => DiffAlgorithm::Histogram,
+        None if args.patience => DiffAlgorithm::Patience,
+        None if args.minimal => DiffAlgorithm::Minimal,
+        None => DiffAlgorithm::Myers,
+    };
+    xdiff.ignore_all_space = args.ignore_all_space;
+    xdiff.ignore_space_change = args.ignore_space_change;
+    xdiff.ignore_space_at_eol = args.ignore_space_at_eol;
+    xdiff.ignore_blank_lines = args.ignore_blank_lines;
+    if let Some(context) = args.unified {
+        xdiff.context = context;
+    }
+    if let Some(interhunk_context) = args.inter_hunk_context {
+        xdiff.interhunk_context = interhunk_context;
+    }
+    xdiff.function_context = args.function_context;
+    xdiff.indent_heuristic = !args.no_indent_heuristic;
+
+    options.renames = rename_options(args, None, None);
+    // Word diffs in color mode are colored unless asked otherwise.
+    options.color = match args.no_color {
+        true => Some(ColorWhen::Never),
+        false if args.color.is_none() && args.word_diff == Some(WordDiffMode::Color) => {
+            Some(ColorWhen::Always)
+        }
+        false => args.color,
+    };
+    options.word_diff = args.word_diff.filter(|mode| *mode != WordDiffMode::None);
+    options.color_moved = match args.no_color_moved {
+        true => Some(ColorMovedMode::No),
+        false => args.color_moved,
+    };
+    options
+}
+
+/// Sets the colors of the output, and how moved lines are colored. Porcelain commands color
+/// their output according to `color.diff` and detect moved lines according to
+/// `diff.colorMoved`, while plumbing commands only do so when asked on the command line.
+pub(crate) fn color_options(
+    options: &mut DiffOptions,
+    config: &GitConfig,
+    porcelain: bool,
+) -> Result<(), RustGitError> {
+    let when = match options.color {
+        Some(when) => when,
+        None if porcelain => config.color.when(&config.color.diff),
+        None => ColorWhen::Never,
+    };
+    options.palette = Palette::new(when, config.color.diff.slots())?;
+    if options.color_moved.is_none() && porcelain {
+        options.color_moved = config.diff.colormoved.map(ColorMovedConfig::mode);
+    }
+    Ok(())
+}
+
+/// Returns how renames and copies are detected, if they are. Porcelain commands pass the
+/// configured `renames` setting and rename limit, which the command line overrides.
+pub(crate) fn rename_options(
+    args: &DiffOptionArgs,
+    renames: Option<RenamesConfig>,
+    limit: Option<usize>,
+) -> Option<RenameOptions> {
+    if args.no_renames {
+        return None;
+    }
+    let copies = args.find_copies.is_some()
+        || args.find_copies_harder
+        || (args.find_renames.is_none()
+            && renames == Some(RenamesConfig::Copies(CopiesKeyword::Copies)));
+    let detect =
+        copies || args.find_renames.is_some() || renames == Some(RenamesConfig::Enabled(true));
+    detect.then(|| RenameOptions {
+        copies,
+        find_copies_harder: args.find_copies_harder,
+        min_score: args
+            .find_copies
+            .or(args.find_renames)
+            .unwrap_or(DEFAULT_RENAME_SCORE),
+        limit: args.rename_limit.or(limit).unwrap_or(DEFAULT_RENAME_LIMIT),
+    })
+}
+
+/// Returns whether the argument can only be a pathspec, as it uses pathspec magic or wildcards.
+fn looks_like_pathspec(arg: &str) -> bool {
+    arg.starts_with(':') || arg.contains(['*', '?', '['])
+}
+
+fn ambiguous_argument(arg: &str) -> RustGitError {
+    RustGitError::new(format!(
+        "fatal: ambiguous argument '{arg}': unknown revision or path not in the working tree.
+Use '--' to separate paths from revisions, like this:
+'git <command> [<revision>...] -- [<file>...]'"
+    ))
+}
+
+/// Returns the version of a file or symbolic link outside of a repo, or None if it's missing.
+fn no_index_side(path: &Path) -> Result<Option<GitDiffSide>, RustGitError> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(None);
+    };
+    let contents = read_worktree_file(path)?;
+    Ok(Some(GitDiffSide {
+        path: path.to_string_lossy().into_owned(),
+        mode: GitIndexMode::from_metadata(&metadata).as_octal(),
+        id: Some(GitObject::new(GitObjectType::Blob, contents)?.id),
+    }))
+}
+
+/// Returns the paths of the files under the directory, relative to it, sorted.
+fn list_files(dir: &Path, prefix: &str, files: &mut BTreeSet<String>) -> Result<(), RustGitError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), &format!("{name}/"), files)?;
+        } else {
+            files.insert(name);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) struct DiffCommand {
+    args: DiffArgs,
+    options: DiffOptions,
+    pathspec_options: PathspecOptions,
+}
+
+impl DiffCommand {
+    pub fn new(args: DiffArgs, pathspec_options: PathspecOptions) -> DiffCommand {
+        let options = diff_options(&args.options, true);
+        DiffCommand {
+            args,
+            options,
+            pathspec_options,
+        }
+    }
+
+    /// Returns every path given on the command line, before and after `--`.
+    fn paths(&self) -> Vec<&String> {
+        self.args.args.iter().chain(&self.args.pathspec).collect()
+    }
+
+    /// Compares two files, or the files of two directories, outside of any repo. Like C Git,
+    /// the command fails when they differ.
+    fn diff_no_index(&self, config: &GitConfig) -> Result<(), RustGitError> {
+        let [old, new] = self.paths()[..] else {
+            return Err(RustGitError::new(
+                "usage: git diff --no-index [<options>] <path> <path>",
+            ));
+        };
+        let (old, mut new) = (PathBuf::from(old), PathBuf::from(new));
+        // A file is compared with the file of the same name in a directory.
+        if new.is_dir() && !old.is_dir() {
+            if let Some(name) = old.file_name() {
+                new = new.join(name);
+            }
+        }
+
+        let mut pairs = vec![];
+        if old.is_dir() && new.is_dir() {
+            let mut old_files = BTreeSet::new();
+            let mut new_files = BTreeSet::new();
+            list_files(&old, "", &mut old_files)?;
+            list_files(&new, "", &mut new_files)?;
+            for name in old_files.union(&new_files) {
+                pairs.extend(GitDiffPair::between(
+                    no_index_side(&old.join(name))?,
+                    no_index_side(&new.join(name))?,
+                ));
+            }
+        } else {
+            let old_side = no_index_side(&old)?;
+            let new_side = no_index_side(&new)?;
+            for (side, path) in [(&old_side, &old), (&new_side, &new)] {
+                if side.is_none() {
+                    return Err(RustGitError::new(format!(
+                        "error: Could not access '{}'",
+                        path.display()
+                    )));
+                }
+            }
+            pairs.extend(GitDiffPair::between(old_side, new_side));
+        }
+
+        let read = |side: &GitDiffSide| read_worktree_file(Path::new(&side.path));
+        let mut options = self.options.clone();
+        color_options(&mut options, config, true)?;
+        if let Some(renames) = &options.renames {
+            pairs = detect_renames(pairs, vec![], renames, read)?;
+        }
+        print!("{}", format_diff(&pairs, &options, read)?);
+        if pairs.is_empty() {
+            Ok(())
+        } else {
+            Err(RustGitError::new(""))
+        }
+    }
+
+    /// Splits the arguments before `--` into the trees of the revisions they start with and
+    /// the paths which follow. A range `A..B` names two revisions, and `A...B` names the merge
+    /// base of both and B.
+    fn parse_args(&self, repo: &GitRepo) -> Result<(Vec<GitObjectId>, Vec<String>), RustGitError> {
+        let mut trees = vec![];
+        let mut paths = self.args.pathspec.clone();
+        let mut args = self.args.args.iter();
+        let resolve = |revision: &str| {
+            let revision = if revision.is_empty() {
+                "HEAD"
+            } else {
+                revision
+            };
+            repo.resolve_revision(revision)
+        };
+
+        for (idx, arg) in args.by_ref().enumerate() {
+            if let Some((from, to)) = arg.split_once("...") {
+                let from_commit = repo.peel_to_commit(&resolve(from)?)?;
+                let to_commit = repo.peel_to_commit(&resolve(to)?)?;
+                let merge_base = repo
+                    .merge_base(&from_commit, &to_commit)?
+                    .ok_or_else(|| RustGitError::new(format!("fatal: {arg}: no merge base")))?;
+                trees.push(repo.peel_to_tree(&merge_base)?);
+                trees.push(repo.peel_to_tree(&to_commit)?);
+            } else if let Some((from, to)) = arg.split_once("..") {
+                trees.push(repo.peel_to_tree(&resolve(from)?)?);
+                trees.push(repo.peel_to_tree(&resolve(to)?)?);
+            } else if let Ok(id) = repo.resolve_revision(arg) {
+                trees.push(repo.peel_to_tree(&id)?);
+            } else {
+                let rest = self.args.args[idx..].to_vec();
+                for path in &rest {
+                    if !looks_like_pathspec(path) && fs::symlink_metadata(path).is_err() {
+                        return Err(ambiguous_argument(path));
+                    }
+                }
+                paths.splice(0..0, rest);
+                break;
+            }
+        }
+        Ok((trees, paths))
+    }
+}
+
+impl GitCommand for DiffCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        // Outside of a repo, two paths are compared as if --no-index was given.
+        let outside_repo = !matches!(repo_state, RepoState::Repo(_));
+        if self.args.no_index || (outside_repo && self.paths().len() == 2) {
+            return match &repo_state {
+                RepoState::Repo(repo) => self.diff_no_index(&repo.config),
+                _ => self.diff_no_index(&GitConfig::default()),
+            };
+        }
+        let mut repo = repo_state.try_get()?;
+
+        // Porcelain diffs detect renames unless configured otherwise.
+        let mut options = self.options.clone();
+        options.renames = rename_options(
+            &self.args.options,
+            Some(
+                repo.config
+                    .diff
+                    .renames
+                    .unwrap_or(RenamesConfig::Enabled(true)),
+            ),
+            repo.config.diff.renamelimit,
+        );
+        color_options(&mut options, &repo.config, true)?;
+
+        let (trees, paths) = self.parse_args(&repo)?;
+        let mut pathspec = Pathspec::parse(&repo, &paths, self.pathspec_options)?;
+        let (pairs, old_tree) = match (&trees[..], self.args.cached) {
+            ([], false) => {
+                repo.try_lock_index()?;
+                repo.refresh_index(false, |_| true)?;
+                repo.write_index_if_able()?;
+                (repo.diff_index_to_worktree(&mut pathspec)?, None)
+            }
+            ([], true) => {
+                let head = match repo.read_head()?.as_ref().and_then(|head| head.id()) {
+                    Some(id) => Some(repo.peel_to_tree(id)?),
+                    None => None,
+                };
+                let pairs = repo.diff_tree_to_index(head.as_ref(), &mut pathspec, true)?;
+                (pairs, head)
+            }
+            ([tree], cached) => {
+                if !cached {
+                    repo.try_lock_index()?;
+                    repo.refresh_index(false, |_| true)?;
+                    repo.write_index_if_able()?;
+                }
+                let pairs = repo.diff_tree_to_index(Some(tree), &mut pathspec, cached)?;
+                (pairs, Some(tree.clone()))
+            }
+            ([old, new], false) => {
+                let pairs = repo.diff_trees(Some(old), Some(new), &mut pathspec, true)?;
+                (pairs, Some(old.clone()))
+            }
+            _ => {
+                return Err(RustGitError::new(
+                    "usage: git diff [<options>] [<commit>] [--] [<path>...]",
+                ))
+            }
+        };
+
+        // Copies may come from any file of the old side, which is the index when comparing it
+        // with the worktree.
+        let unchanged = match &old_tree {
+            _ if !options.finds_copies_harder() => vec![],
+            _ if trees.is_empty() && !self.args.cached => repo.index_files(&mut pathspec),
+            Some(tree) => repo.tree_files(tree, &mut pathspec)?,
+            None => vec![],
+        };
+        let pairs = repo.detect_renames(pairs, unchanged, options.renames.as_ref())?;
+        print!(
+            "{}",
+            format_diff(&pairs, &options, |side| repo.read_diff_side(side))?
+        );
+        Ok(())
+    }
+
+    fn pages_by_default(&self) -> bool {
+        true
+    }
+}
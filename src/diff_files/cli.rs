@@ -0,0 +1,19 @@
+use clap::Args;
+
+use crate::diff::cli::DiffOptionArgs;
+
+#[derive(Args, Debug)]
+#[command(about = "Compares files in the working tree and the index")]
+#[command(long_about = "
+Compares the files in the working tree and the index. When paths are specified, compares only those named paths.
+Otherwise all entries in the index are compared. The output format is the same as for git diff-index and git
+diff-tree.
+")]
+pub(crate) struct DiffFilesArgs {
+    #[command(flatten)]
+    pub options: DiffOptionArgs,
+
+    /// Limits the paths compared to those matching the pathspec.
+    #[arg(value_name = "path")]
+    pub pathspec: Vec<String>,
+}
@@ -0,0 +1,48 @@
+use crate::{
+    command::GitCommand,
+    diff::command::{color_options, diff_options},
+    diff_format::{format_diff, DiffOptions},
+    pathspec::{Pathspec, PathspecOptions},
+    repo::RepoState,
+    RustGitError,
+};
+
+use super::cli::DiffFilesArgs;
+
+pub(crate) struct DiffFilesCommand {
+    pathspec: Vec<String>,
+    options: DiffOptions,
+    pathspec_options: PathspecOptions,
+}
+
+impl DiffFilesCommand {
+    pub fn new(args: DiffFilesArgs, pathspec_options: PathspecOptions) -> DiffFilesCommand {
+        DiffFilesCommand {
+            options: diff_options(&args.options, false),
+            pathspec: args.pathspec,
+            pathspec_options,
+        }
+    }
+}
+
+impl GitCommand for DiffFilesCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+        let mut options = self.options.clone();
+        color_options(&mut options, &repo.config, false)?;
+
+        let mut pathspec = Pathspec::parse(&repo, &self.pathspec, self.pathspec_options)?;
+        let pairs = repo.diff_index_to_worktree(&mut pathspec)?;
+        let unchanged = if options.finds_copies_harder() {
+            repo.index_files(&mut pathspec)
+        } else {
+            vec![]
+        };
+        let pairs = repo.detect_renames(pairs, unchanged, options.renames.as_ref())?;
+        print!(
+            "{}",
+            format_diff(&pairs, &options, |side| repo.read_diff_side(side))?
+        );
+        Ok(())
+    }
+}
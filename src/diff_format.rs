@@ -0,0 +1,1472 @@
+// Patches between two versions of a file, based on C Git's diff.c:
+// https://github.com/git/git/blob/master/diff.c
+
+use std::{borrow::Cow, collections::HashMap};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::{ColorWhen, DiffColor, Palette, RESET},
+    diffcore::{GitChangeType, GitDiffPair, GitDiffSide},
+    diffcore_rename::RenameOptions,
+    index::GitIndexMode,
+    object::{GitObject, GitObjectId, GitObjectType},
+    xdiff::{split_lines, Hunk, HunkLine, LineDiff, XdiffOptions},
+    RustGitError,
+};
+
+/// The number of bytes looked at to tell whether a file is binary, as in C Git.
+const FIRST_FEW_BYTES: usize = 8000;
+
+const DEFAULT_ABBREV_LEN: usize = 7;
+
+/// The width of the diffstat, which C Git takes from the terminal when there is one.
+const DEFAULT_STAT_WIDTH: usize = 80;
+
+/// Returns whether the contents look binary, i.e. have a NUL byte near the start.
+pub(crate) fn is_binary(contents: &str) -> bool {
+    contents
+        .as_bytes()
+        .iter()
+        .take(FIRST_FEW_BYTES)
+        .any(|byte| *byte == 0)
+}
+
+/// One side of a file pair.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GitDiffFile {
+    /// The path, relative to the root of the repo.
+    pub(crate) path: String,
+    pub(crate) mode: GitIndexMode,
+    pub(crate) id: GitObjectId,
+    pub(crate) contents: String,
+}
+
+impl GitDiffFile {
+    /// Returns the file with the provided contents for one side of a file pair, hashing the
+    /// contents of worktree files.
+    pub(crate) fn load(side: &GitDiffSide, contents: String) -> Result<GitDiffFile, RustGitError> {
+        let id = match &side.id {
+            Some(id) => id.clone(),
+            None => GitObject::new(GitObjectType::Blob, contents.clone())?.id,
+        };
+        Ok(GitDiffFile {
+            path: side.path.clone(),
+            mode: GitIndexMode::from_octal(side.mode),
+            id,
+            contents,
+        })
+    }
+}
+
+/// The outputs shown for a list of file pairs, which are shown in the order of the fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct DiffOutputFormat {
+    pub(crate) raw: bool,
+    pub(crate) name_only: bool,
+    pub(crate) name_status: bool,
+    pub(crate) numstat: bool,
+    pub(crate) stat: bool,
+    pub(crate) shortstat: bool,
+    pub(crate) summary: bool,
+    pub(crate) patch: bool,
+}
+
+impl DiffOutputFormat {
+    /// Returns whether an output other than the list of changed paths is shown, which requires
+    /// comparing every file rather than trees as a whole.
+    pub(crate) fn needs_files(&self) -> bool {
+        self.numstat || self.stat || self.shortstat || self.summary || self.patch
+    }
+}
+
+/// How patches are generated and shown.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DiffOptions {
+    pub(crate) output: DiffOutputFormat,
+    pub(crate) xdiff: XdiffOptions,
+    /// The number of hexadecimal digits object ids are abbreviated to.
+    pub(crate) abbrev: usize,
+    /// Whether object ids are abbreviated in the raw output, where they're shown in full
+    /// otherwise.
+    pub(crate) abbrev_raw: bool,
+    /// The number of columns the diffstat fits in.
+    pub(crate) stat_width: usize,
+    /// The prefix of paths of the old side, `a/` by default.
+    pub(crate) src_prefix: String,
+    /// The prefix of paths of the new side, `b/` by default.
+    pub(crate) dst_prefix: String,
+    /// How renames and copies are detected, if they are.
+    pub(crate) renames: Option<RenameOptions>,
+    /// When output is colored, if chosen on the command line.
+    pub(crate) color: Option<ColorWhen>,
+    /// The colors of the output, which are all empty when it isn't colored.
+    pub(crate) palette: Palette<DiffColor>,
+    /// How patches show changed words, if they do.
+    pub(crate) word_diff: Option<WordDiffMode>,
+    /// How moved lines are colored, if they are.
+    pub(crate) color_moved: Option<ColorMovedMode>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            output: DiffOutputFormat::default(),
+            xdiff: XdiffOptions::default(),
+            abbrev: DEFAULT_ABBREV_LEN,
+            abbrev_raw: false,
+            stat_width: DEFAULT_STAT_WIDTH,
+            src_prefix: String::from("a/"),
+            dst_prefix: String::from("b/"),
+            renames: None,
+            color: None,
+            palette: Palette::default(),
+            word_diff: None,
+            color_moved: None,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Returns whether unchanged files may be the source of copies, which then have to be listed.
+    pub(crate) fn finds_copies_harder(&self) -> bool {
+        self.renames
+            .as_ref()
+            .is_some_and(|renames| renames.find_copies_harder)
+    }
+
+    fn abbrev(&self, id: Option<&GitObjectId>) -> String {
+        let id = id.map_or_else(|| "0".repeat(40), |id| id.to_string());
+        id[..self.abbrev.min(id.len())].to_string()
+    }
+}
+
+/// Returns the extended header lines describing how a file pair was renamed or copied.
+fn format_metainfo(pair: &GitDiffPair) -> String {
+    let (Some(old), Some(new)) = (&pair.old, &pair.new) else {
+        return String::new();
+    };
+    let kind = match pair.status {
+        GitChangeType::Renamed => "rename",
+        GitChangeType::Copied => "copy",
+        _ => return String::new(),
+    };
+    format!(
+        "similarity index {}%\n{kind} from {}\n{kind} to {}\n",
+        pair.score, old.path, new.path
+    )
+}
+
+/// The kind of a line of a patch, which decides how it's colored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PatchLineKind {
+    /// A line of the header, like `diff --git a/a.txt b/a.txt`.
+    Meta,
+    /// A hunk header, like `@@ -1,3 +1,4 @@`.
+    Frag,
+    Context,
+    Removed,
+    Added,
+    /// The marker following a line without a line feed.
+    NoNewline,
+    /// Text which is shown as is, and may span several lines.
+    Plain,
+}
+
+/// The line is part of a block of lines moved elsewhere in the diff, see [mark_moved_lines].
+const MOVED_LINE: u8 = 1;
+/// The line is part of a moved block following another moved block.
+const MOVED_LINE_ALT: u8 = 2;
+/// The line is inside of a moved block, and dimmed with `--color-moved=dimmed-zebra`.
+const MOVED_LINE_DIMMED: u8 = 4;
+
+/// A line of a patch. The text of context, removed and added lines doesn't include their
+/// prefix, and the text of other lines doesn't include their line feed, except for plain text.
+struct PatchLine<'a> {
+    kind: PatchLineKind,
+    text: Cow<'a, str>,
+    /// The line is an added blank line at the end of the file, which is a whitespace error.
+    blank_at_eof: bool,
+    /// How the line was moved, as a combination of the `MOVED_LINE` flags.
+    moved: u8,
+}
+
+impl<'a> PatchLine<'a> {
+    fn new(kind: PatchLineKind, text: impl Into<Cow<'a, str>>) -> PatchLine<'a> {
+        PatchLine {
+            kind,
+            text: text.into(),
+            blank_at_eof: false,
+            moved: 0,
+        }
+    }
+
+    /// Returns the contents of a context, removed or added line, without the line feed.
+    fn contents(&self) -> &str {
+        self.text.strip_suffix('\n').unwrap_or(&self.text)
+    }
+}
+
+/// How words are marked in word diffs, see `--word-diff`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum WordDiffMode {
+    /// Highlight changed words using only colors.
+    Color,
+    /// Show words as [-removed-] and {+added+}.
+    Plain,
+    /// Use a special line-based format intended for script consumption.
+    Porcelain,
+    /// Disable word diff again.
+    None,
+}
+
+/// How moved lines are colored, see `--color-moved`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ColorMovedMode {
+    /// Moved lines are not highlighted.
+    No,
+    /// Is a synonym for zebra.
+    Default,
+    /// Any line that is added in one location and was removed in another location will be
+    /// colored with color.diff.newMoved.
+    Plain,
+    /// Blocks of moved text of at least 20 alphanumeric characters are detected greedily.
+    Blocks,
+    /// Blocks of moved text are detected as in blocks mode, and adjacent blocks are painted in
+    /// alternating colors.
+    Zebra,
+    /// Similar to zebra, but additional dimming of uninteresting parts of moved code is
+    /// performed.
+    DimmedZebra,
+}
+
+/// Returns the number of blank lines at the end of the contents, as C Git's
+/// count_trailing_blank: the first line isn't looked at when it is a single character.
+fn count_trailing_blank(contents: &str) -> usize {
+    let bytes = contents.as_bytes();
+    let Some(&last) = bytes.last() else {
+        return 0;
+    };
+    let mut end = bytes.len() as isize - 1;
+    if last == b'\n' {
+        end -= 1;
+    }
+    let mut count = 0;
+    while 0 < end {
+        let line_start = bytes[..=end as usize]
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |idx| idx + 1);
+        if !bytes[line_start..=end as usize]
+            .iter()
+            .all(u8::is_ascii_whitespace)
+        {
+            break;
+        }
+        count += 1;
+        end = line_start as isize - 2;
+    }
+    count
+}
+
+/// The lines of both files starting the blank lines added at their end, which are whitespace
+/// errors, as C Git's check_blank_at_eof. Lines are numbered as in hunk headers.
+fn blank_at_eof(old: &str, new: &str) -> Option<(usize, usize)> {
+    let (old_blank, new_blank) = (count_trailing_blank(old), count_trailing_blank(new));
+    if new_blank <= old_blank {
+        return None;
+    }
+    let count_lines = |contents: &str| split_lines(contents).len();
+    Some((
+        count_lines(old) - old_blank + 1,
+        count_lines(new) - new_blank + 1,
+    ))
+}
+
+/// Returns the lines of a hunk, flagging blank lines added at the end of the file.
+fn hunk_lines<'a>(hunk: &Hunk<'a>, blank_at_eof: Option<(usize, usize)>) -> Vec<PatchLine<'a>> {
+    let mut lines = vec![PatchLine::new(PatchLineKind::Frag, hunk.header())];
+    let (mut old_line, mut new_line) = (hunk.old_start, hunk.new_start);
+    for line in &hunk.lines {
+        let mut patch_line = match line {
+            HunkLine::Context(text) => {
+                old_line += 1;
+                new_line += 1;
+                PatchLine::new(PatchLineKind::Context, *text)
+            }
+            HunkLine::Removed(text) => {
+                old_line += 1;
+                PatchLine::new(PatchLineKind::Removed, *text)
+            }
+            HunkLine::Added(text) => {
+                new_line += 1;
+                PatchLine::new(PatchLineKind::Added, *text)
+            }
+        };
+        patch_line.blank_at_eof = patch_line.kind == PatchLineKind::Added
+            && blank_at_eof.is_some_and(|(old_start, new_start)| {
+                old_start <= old_line && new_start <= new_line
+            })
+            && patch_line.contents().trim_ascii().is_empty();
+        let incomplete = !line.text().ends_with('\n');
+        lines.push(patch_line);
+        if incomplete {
+            lines.push(PatchLine::new(PatchLineKind::NoNewline, ""));
+        }
+    }
+    lines
+}
+
+/// Appends text in the style of one side of a word diff to the output, marking each line of it
+/// separately, as C Git's fn_out_diff_words_write_helper.
+fn push_words(out: &mut String, text: &str, style: (&str, &str, &str), newline: &str) {
+    let (prefix, suffix, color) = style;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (segment, next) = match rest.split_once('\n') {
+            Some((segment, next)) => (segment, Some(next)),
+            None => (rest, None),
+        };
+        if !segment.is_empty() {
+            out.push_str(color);
+            out.push_str(prefix);
+            out.push_str(segment);
+            out.push_str(suffix);
+            if !color.is_empty() {
+                out.push_str(RESET);
+            }
+        }
+        let Some(next) = next else {
+            break;
+        };
+        out.push_str(newline);
+        rest = next;
+    }
+}
+
+/// Returns the start and end of the words of the text, which are separated by whitespace.
+fn split_words(text: &str) -> Vec<(usize, usize)> {
+    let is_space = |byte: &u8| byte.is_ascii_whitespace() || *byte == 0x0b;
+    let bytes = text.as_bytes();
+    let mut words = vec![];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if is_space(&bytes[idx]) {
+            idx += 1;
+            continue;
+        }
+        let start = idx;
+        while idx < bytes.len() && !is_space(&bytes[idx]) {
+            idx += 1;
+        }
+        words.push((start, idx));
+    }
+    words
+}
+
+/// Formats the word diff of a run of removed and added lines, as C Git's diff_words_show: the
+/// words of both sides are diffed, and the added text is shown with the words which changed.
+fn format_words(
+    removed: &str,
+    added: &str,
+    mode: WordDiffMode,
+    palette: &Palette<DiffColor>,
+) -> String {
+    let (old_style, new_style, context_style, newline) = match mode {
+        WordDiffMode::Porcelain => (("-", "\n"), ("+", "\n"), (" ", "\n"), "~\n"),
+        WordDiffMode::Plain => (("[-", "-]"), ("{+", "+}"), ("", ""), "\n"),
+        _ => (("", ""), ("", ""), ("", ""), "\n"),
+    };
+    let old_style = (old_style.0, old_style.1, palette.get(DiffColor::Old));
+    let new_style = (new_style.0, new_style.1, palette.get(DiffColor::New));
+    let context_style = (
+        context_style.0,
+        context_style.1,
+        palette.get(DiffColor::Context),
+    );
+
+    let mut out = String::new();
+    if added.is_empty() {
+        push_words(&mut out, removed, old_style, newline);
+        return out;
+    }
+
+    // Words are diffed as lines of their own.
+    let old_words = split_words(removed);
+    let new_words = split_words(added);
+    let word_lines = |text: &str, words: &[(usize, usize)]| {
+        words
+            .iter()
+            .map(|(start, end)| format!("{}\n", &text[*start..*end]))
+            .collect::<String>()
+    };
+    let old_text = word_lines(removed, &old_words);
+    let new_text = word_lines(added, &new_words);
+    let word_diff = LineDiff::new(&old_text, &new_text, &XdiffOptions::default());
+
+    // The range of text covered by words, which is empty after the previous word when there are
+    // none.
+    let span = |words: &[(usize, usize)], start: usize, len: usize| match len {
+        0 if start == 0 => (0, 0),
+        0 => (words[start - 1].1, words[start - 1].1),
+        _ => (words[start].0, words[start + len - 1].1),
+    };
+    let mut current = 0;
+    for change in word_diff.changes() {
+        let (old_start, old_end) = span(&old_words, change.old_start, change.old_len);
+        let (new_start, new_end) = span(&new_words, change.new_start, change.new_len);
+        if current != new_start {
+            push_words(&mut out, &added[current..new_start], context_style, newline);
+        }
+        if old_start != old_end {
+            push_words(&mut out, &removed[old_start..old_end], old_style, newline);
+        }
+        if new_start != new_end {
+            push_words(&mut out, &added[new_start..new_end], new_style, newline);
+        }
+        current = new_end;
+    }
+    if current != added.len() {
+        push_words(&mut out, &added[current..], context_style, newline);
+    }
+    out
+}
+
+/// Returns the lines of a hunk shown as a word diff, see `--word-diff`.
+fn word_diff_lines<'a>(
+    hunk: &Hunk<'a>,
+    mode: WordDiffMode,
+    palette: &Palette<DiffColor>,
+) -> Vec<PatchLine<'a>> {
+    let mut lines = vec![PatchLine::new(PatchLineKind::Frag, hunk.header())];
+    let mut out = String::new();
+    let (mut removed, mut added) = (String::new(), String::new());
+    let flush = |out: &mut String, removed: &mut String, added: &mut String| {
+        if !removed.is_empty() || !added.is_empty() {
+            out.push_str(&format_words(removed, added, mode, palette));
+            removed.clear();
+            added.clear();
+        }
+    };
+    for line in &hunk.lines {
+        // Lines without a line feed are compared as if they had one.
+        let (side, text) = match line {
+            HunkLine::Removed(text) => (&mut removed, text),
+            HunkLine::Added(text) => (&mut added, text),
+            HunkLine::Context(text) => {
+                flush(&mut out, &mut removed, &mut added);
+                let text = text.strip_suffix('\n').unwrap_or(text);
+                let (color, reset) = (palette.get(DiffColor::Context), palette.reset());
+                match mode {
+                    WordDiffMode::Porcelain => out.push_str(&format!("{color} {text}{reset}\n~\n")),
+                    _ if text.is_empty() => out.push('\n'),
+                    _ => out.push_str(&format!("{color}{text}{reset}\n")),
+                }
+                continue;
+            }
+        };
+        side.push_str(text);
+        if !text.ends_with('\n') {
+            side.push('\n');
+        }
+    }
+    flush(&mut out, &mut removed, &mut added);
+    lines.push(PatchLine::new(PatchLineKind::Plain, out));
+    lines
+}
+
+/// Returns the lines of the patch turning the old file into the new one, where a missing side
+/// means the file was added or deleted. The `metainfo` lines follow the mode lines of the
+/// header. No lines are returned for files without changes, which includes files whose only
+/// changes are ignored by the options.
+fn patch_lines<'a>(
+    old: Option<&'a GitDiffFile>,
+    new: Option<&'a GitDiffFile>,
+    metainfo: &str,
+    options: &DiffOptions,
+) -> Vec<PatchLine<'a>> {
+    let Some(any_side) = old.or(new) else {
+        return vec![];
+    };
+    // As in C Git, a leading slash, as found in absolute paths given to --no-index, is dropped
+    // so that it doesn't double up with the prefix.
+    let prefixed =
+        |prefix: &str, path: &str| format!("{prefix}{}", path.strip_prefix('/').unwrap_or(path));
+    let old_name = prefixed(&options.src_prefix, &old.unwrap_or(any_side).path);
+    let new_name = prefixed(&options.dst_prefix, &new.unwrap_or(any_side).path);
+    let meta = |text: String| PatchLine::new(PatchLineKind::Meta, text);
+
+    let mut header = vec![meta(format!("diff --git {old_name} {new_name}"))];
+    let mut has_header_changes = true;
+    match (old, new) {
+        (None, Some(new)) => header.push(meta(format!("new file mode {}", new.mode))),
+        (Some(old), None) => header.push(meta(format!("deleted file mode {}", old.mode))),
+        (Some(old), Some(new)) if old.mode != new.mode => {
+            header.push(meta(format!("old mode {}", old.mode)));
+            header.push(meta(format!("new mode {}", new.mode)));
+        }
+        _ => has_header_changes = false,
+    }
+    if !metainfo.is_empty() {
+        header.extend(metainfo.lines().map(|line| meta(String::from(line))));
+        has_header_changes = true;
+    }
+
+    let old_id = old.map(|file| &file.id);
+    let new_id = new.map(|file| &file.id);
+    if old_id == new_id {
+        return if has_header_changes { header } else { vec![] };
+    }
+    let mut index = format!(
+        "index {}..{}",
+        options.abbrev(old_id),
+        options.abbrev(new_id)
+    );
+    match (old, new) {
+        (Some(old), Some(new)) if old.mode == new.mode => index.push_str(&format!(" {}", old.mode)),
+        _ => (),
+    }
+    header.push(meta(index));
+
+    let old_label = old.map_or_else(|| String::from("/dev/null"), |_| old_name);
+    let new_label = new.map_or_else(|| String::from("/dev/null"), |_| new_name);
+    let old_contents = old.map_or("", |file| file.contents.as_str());
+    let new_contents = new.map_or("", |file| file.contents.as_str());
+    if is_binary(old_contents) || is_binary(new_contents) {
+        header.push(PatchLine::new(
+            PatchLineKind::Plain,
+            format!("Binary files {old_label} and {new_label} differ\n"),
+        ));
+        return header;
+    }
+
+    let line_diff = LineDiff::new(old_contents, new_contents, &options.xdiff);
+    if line_diff.is_empty() {
+        // Files whose changes are all ignored aren't shown.
+        let ignored = old.is_some() && new.is_some() && LineDiff::ignores_changes(&options.xdiff);
+        return if ignored && !has_header_changes {
+            vec![]
+        } else {
+            header
+        };
+    }
+
+    let mut lines = header;
+    lines.push(meta(format!("--- {old_label}")));
+    lines.push(meta(format!("+++ {new_label}")));
+    let blank_at_eof = blank_at_eof(old_contents, new_contents);
+    for hunk in line_diff.hunks(&options.xdiff) {
+        match options.word_diff {
+            Some(WordDiffMode::None) | None if !options.palette.is_enabled() => {
+                lines.push(PatchLine::new(PatchLineKind::Plain, hunk.format_unified()))
+            }
+            Some(WordDiffMode::None) | None => lines.extend(hunk_lines(&hunk, blank_at_eof)),
+            Some(mode) => lines.extend(word_diff_lines(&hunk, mode, &options.palette)),
+        }
+    }
+    lines
+}
+
+/// Formats the contents of an added line, highlighting whitespace errors as C Git's
+/// ws_check_emit does with the default `core.whitespace` rules: whitespace at the end of the
+/// line, and spaces before tabs in the indent.
+fn format_whitespace_errors(line: &str, set: &str, reset: &str, ws: &str) -> String {
+    let bytes = line.as_bytes();
+    let trailing = bytes.len()
+        - bytes
+            .iter()
+            .rev()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+    let mut out = String::new();
+    let mut written = 0;
+    for (idx, byte) in bytes[..trailing].iter().enumerate() {
+        match byte {
+            b' ' => continue,
+            b'\t' => (),
+            _ => break,
+        }
+        if written < idx {
+            out.push_str(&format!("{ws}{}{reset}\t", &line[written..idx]));
+        } else {
+            out.push_str(&line[written..=idx]);
+        }
+        written = idx + 1;
+    }
+    if written < trailing {
+        out.push_str(&format!("{set}{}{reset}", &line[written..trailing]));
+    }
+    if trailing < line.len() {
+        out.push_str(&format!("{ws}{}{reset}", &line[trailing..]));
+    }
+    out
+}
+
+/// Formats a hunk header, where the function name has its own color.
+fn format_hunk_header(header: &str, palette: &Palette<DiffColor>) -> String {
+    let reset = palette.reset();
+    let range_end = header
+        .get(2..)
+        .and_then(|rest| rest.find("@@"))
+        .map_or(header.len(), |idx| idx + 4);
+    let (range, rest) = header.split_at(range_end);
+    let mut out = format!("{}{range}{reset}", palette.get(DiffColor::Frag));
+    let function_name = rest.trim_start_matches([' ', '\t']);
+    let blank = &rest[..rest.len() - function_name.len()];
+    if !blank.is_empty() {
+        out.push_str(&format!(
+            "{}{blank}{reset}",
+            palette.get(DiffColor::Context)
+        ));
+    }
+    if !function_name.is_empty() {
+        out.push_str(&format!(
+            "{}{function_name}{reset}",
+            palette.get(DiffColor::Func)
+        ));
+    }
+    out.push('\n');
+    out
+}
+
+/// Returns the color of a removed or added line, which depends on how it was moved.
+fn line_color(line: &PatchLine) -> DiffColor {
+    let added = line.kind == PatchLineKind::Added;
+    let pick = |old, new| if added { new } else { old };
+    match line.moved {
+        flags if flags == MOVED_LINE | MOVED_LINE_ALT | MOVED_LINE_DIMMED => pick(
+            DiffColor::OldMovedAlternativeDimmed,
+            DiffColor::NewMovedAlternativeDimmed,
+        ),
+        flags if flags == MOVED_LINE | MOVED_LINE_ALT => pick(
+            DiffColor::OldMovedAlternative,
+            DiffColor::NewMovedAlternative,
+        ),
+        flags if flags == MOVED_LINE | MOVED_LINE_DIMMED => {
+            pick(DiffColor::OldMovedDimmed, DiffColor::NewMovedDimmed)
+        }
+        MOVED_LINE => pick(DiffColor::OldMoved, DiffColor::NewMoved),
+        _ => pick(DiffColor::Old, DiffColor::New),
+    }
+}
+
+/// Formats the lines of patches in the colors of the palette, as C Git's emit_diff_symbol.
+fn render_patch(lines: &[PatchLine], palette: &Palette<DiffColor>) -> String {
+    let reset = palette.reset();
+    let mut out = String::new();
+    for line in lines {
+        let text = line.contents();
+        match line.kind {
+            PatchLineKind::Meta => {
+                out.push_str(&format!("{}{text}{reset}\n", palette.get(DiffColor::Meta)))
+            }
+            PatchLineKind::Frag => out.push_str(&format_hunk_header(text, palette)),
+            PatchLineKind::Context => out.push_str(&format!(
+                "{} {text}{reset}\n",
+                palette.get(DiffColor::Context)
+            )),
+            PatchLineKind::Removed => out.push_str(&format!(
+                "{}-{text}{reset}\n",
+                palette.get(line_color(line))
+            )),
+            PatchLineKind::Added => {
+                let set = palette.get(line_color(line));
+                let ws = palette.get(DiffColor::Whitespace);
+                if ws.is_empty() {
+                    out.push_str(&format!("{set}+{text}{reset}\n"));
+                } else if line.blank_at_eof {
+                    out.push_str(&format!("{ws}+{text}{reset}\n"));
+                } else {
+                    out.push_str(&format!(
+                        "{set}+{reset}{}\n",
+                        format_whitespace_errors(text, set, reset, ws)
+                    ));
+                }
+            }
+            PatchLineKind::NoNewline => out.push_str(&format!(
+                "{}\\ No newline at end of file{reset}\n",
+                palette.get(DiffColor::Context)
+            )),
+            PatchLineKind::Plain => out.push_str(&line.text),
+        }
+    }
+    out
+}
+
+/// The minimum number of alphanumeric characters of a block of moved lines, as smaller blocks
+/// are too common to be interesting.
+const MOVED_MIN_ALNUM_COUNT: usize = 20;
+
+/// Unmarks the block of moved lines ending before `end` if it is too small to be interesting,
+/// returning whether it's kept. Blocks are never too small in plain mode.
+fn adjust_last_block(
+    lines: &mut [PatchLine],
+    end: usize,
+    block_length: usize,
+    mode: ColorMovedMode,
+) -> bool {
+    if mode == ColorMovedMode::Plain {
+        return block_length > 0;
+    }
+    let block = &mut lines[end - block_length..end];
+    let alnum_count = block
+        .iter()
+        .flat_map(|line| line.text.bytes())
+        .filter(u8::is_ascii_alphanumeric)
+        .count();
+    if alnum_count >= MOVED_MIN_ALNUM_COUNT {
+        return true;
+    }
+    for line in block {
+        line.moved &= !MOVED_LINE;
+    }
+    false
+}
+
+/// Marks the removed lines which were added elsewhere, and the added lines which were removed
+/// elsewhere, as C Git's mark_color_as_moved. Except in plain mode, moved lines are grouped in
+/// blocks, and adjacent blocks are told apart with alternate colors.
+fn mark_moved_lines(lines: &mut [PatchLine], mode: ColorMovedMode) {
+    let is_change =
+        |line: &PatchLine| matches!(line.kind, PatchLineKind::Removed | PatchLineKind::Added);
+    let mut ids = HashMap::new();
+    let line_ids = lines
+        .iter()
+        .map(|line| {
+            let next_id = ids.len();
+            is_change(line).then(|| *ids.entry(line.contents()).or_insert(next_id))
+        })
+        .collect::<Vec<_>>();
+    // Each line of a run of removed or added lines continues with the next one.
+    let next_line = (0..lines.len())
+        .map(|idx| {
+            Some(idx + 1).filter(|next| {
+                is_change(&lines[idx])
+                    && lines
+                        .get(*next)
+                        .is_some_and(|next| next.kind == lines[idx].kind)
+            })
+        })
+        .collect::<Vec<_>>();
+    // The removed and added lines with each id, in order.
+    let mut removed: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut added: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let Some(id) = line_ids[idx] else {
+            continue;
+        };
+        let side = match line.kind {
+            PatchLineKind::Removed => &mut removed,
+            _ => &mut added,
+        };
+        side.entry(id).or_default().push(idx);
+    }
+    // The lines each potential moved block has matched so far.
+    let mut blocks: Vec<usize> = vec![];
+    let mut moved_kind = None;
+    let mut flipped_block = false;
+    let mut block_length = 0;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let kind = lines[idx].kind;
+        let mut matches = match (kind, line_ids[idx]) {
+            (PatchLineKind::Added, Some(id)) => removed.get(&id),
+            (PatchLineKind::Removed, Some(id)) => added.get(&id),
+            _ => {
+                flipped_block = false;
+                None
+            }
+        };
+
+        if !blocks.is_empty() && (matches.is_none() || moved_kind != Some(kind)) {
+            if !adjust_last_block(lines, idx, block_length, mode) && block_length > 1 {
+                // Another block may start at the second line of this one.
+                matches = None;
+                idx -= block_length;
+            }
+            blocks.clear();
+            block_length = 0;
+            flipped_block = false;
+        }
+        let Some(matches) = matches else {
+            moved_kind = None;
+            idx += 1;
+            continue;
+        };
+
+        if mode == ColorMovedMode::Plain {
+            lines[idx].moved |= MOVED_LINE;
+            idx += 1;
+            continue;
+        }
+
+        blocks = blocks
+            .iter()
+            .filter_map(|matched| next_line[*matched])
+            .filter(|next| line_ids[*next] == line_ids[idx])
+            .collect();
+        if blocks.is_empty() {
+            let contiguous = adjust_last_block(lines, idx, block_length, mode);
+            if !contiguous && block_length > 1 {
+                idx -= block_length;
+            } else {
+                blocks = matches.clone();
+            }
+            flipped_block =
+                contiguous && !blocks.is_empty() && moved_kind == Some(kind) && !flipped_block;
+            moved_kind = (!blocks.is_empty()).then_some(kind);
+            block_length = 0;
+        }
+
+        if !blocks.is_empty() {
+            block_length += 1;
+            lines[idx].moved |= MOVED_LINE;
+            if flipped_block && mode != ColorMovedMode::Blocks {
+                lines[idx].moved |= MOVED_LINE_ALT;
+            }
+        }
+        idx += 1;
+    }
+    adjust_last_block(lines, idx, block_length, mode);
+
+    if mode == ColorMovedMode::DimmedZebra {
+        dim_moved_lines(lines);
+    }
+}
+
+/// Dims the moved lines which aren't at the boundary of two adjacent moved blocks, as C Git's
+/// dim_moved_lines.
+fn dim_moved_lines(lines: &mut [PatchLine]) {
+    let zebra = |line: &PatchLine| line.moved & (MOVED_LINE | MOVED_LINE_ALT);
+    for idx in 0..lines.len() {
+        let line = &lines[idx];
+        if !matches!(line.kind, PatchLineKind::Removed | PatchLineKind::Added)
+            || line.moved & MOVED_LINE == 0
+        {
+            continue;
+        }
+        // Lines other than removed and added lines are as good as missing.
+        let neighbour = |other: Option<&PatchLine>| {
+            other
+                .filter(|other| matches!(other.kind, PatchLineKind::Removed | PatchLineKind::Added))
+                .map(|other| other.moved)
+        };
+        let prev = neighbour(idx.checked_sub(1).map(|prev| &lines[prev]));
+        let next = neighbour(lines.get(idx + 1));
+
+        let inside_block = prev
+            .is_some_and(|prev| prev & (MOVED_LINE | MOVED_LINE_ALT) == zebra(line))
+            && next.is_some_and(|next| next & (MOVED_LINE | MOVED_LINE_ALT) == zebra(line));
+        let at_boundary = |other: Option<u8>| {
+            other.is_some_and(|other| {
+                other & MOVED_LINE != 0 && other & MOVED_LINE_ALT != line.moved & MOVED_LINE_ALT
+            })
+        };
+        if inside_block || !(at_boundary(prev) || at_boundary(next)) {
+            lines[idx].moved |= MOVED_LINE_DIMMED;
+        }
+    }
+}
+
+/// Returns the name of a file whose path changed, like `dir/{old => new}.txt`, sharing the
+/// leading directories and trailing components of both paths.
+fn pprint_rename(old: &str, new: &str) -> String {
+    let (a, b) = (old.as_bytes(), new.as_bytes());
+    let mut prefix_len = 0;
+    for (idx, (one, other)) in a.iter().zip(b).enumerate() {
+        if one != other {
+            break;
+        }
+        if *one == b'/' {
+            prefix_len = idx + 1;
+        }
+    }
+
+    // The common suffix starts at a slash, which may be the one ending the common prefix.
+    let byte_at = |bytes: &[u8], idx: usize| bytes.get(idx).copied().unwrap_or(0);
+    let min_idx = prefix_len.saturating_sub(1);
+    let (mut a_idx, mut b_idx) = (a.len(), b.len());
+    let mut suffix_len = 0;
+    while a_idx >= min_idx && b_idx >= min_idx && byte_at(a, a_idx) == byte_at(b, b_idx) {
+        if byte_at(a, a_idx) == b'/' {
+            suffix_len = a.len() - a_idx;
+        }
+        if a_idx == 0 || b_idx == 0 {
+            break;
+        }
+        a_idx -= 1;
+        b_idx -= 1;
+    }
+
+    let a_mid_end = a.len().saturating_sub(suffix_len).max(prefix_len);
+    let b_mid_end = b.len().saturating_sub(suffix_len).max(prefix_len);
+    let middle = format!(
+        "{} => {}",
+        &old[prefix_len..a_mid_end],
+        &new[prefix_len..b_mid_end]
+    );
+    if prefix_len + suffix_len == 0 {
+        middle
+    } else {
+        format!(
+            "{}{{{middle}}}{}",
+            &old[..prefix_len],
+            &old[old.len() - suffix_len..]
+        )
+    }
+}
+
+/// Returns the name shown for a file pair, which includes both paths if they differ.
+fn display_name(pair: &GitDiffPair) -> String {
+    match (&pair.old, &pair.new) {
+        (Some(old), Some(new)) if old.path != new.path => pprint_rename(&old.path, &new.path),
+        _ => String::from(pair.path()),
+    }
+}
+
+/// Formats the line of the raw, name-only or name-status output for a file pair.
+fn format_raw(pair: &GitDiffPair, options: &DiffOptions) -> String {
+    if options.output.name_only {
+        return format!("{}\n", pair.path());
+    }
+    // Renames and copies show the similarity and both paths.
+    let (status, paths) = match (&pair.old, pair.status) {
+        (Some(old), GitChangeType::Renamed | GitChangeType::Copied) => (
+            format!("{}{:03}", pair.status.letter(), pair.score),
+            format!("{}\t{}", old.path, pair.path()),
+        ),
+        _ => (pair.status.letter().to_string(), pair.path().to_string()),
+    };
+    if options.output.name_status {
+        return format!("{status}\t{paths}\n");
+    }
+    let mode = |side: Option<&GitDiffSide>| side.map_or(0, |side| side.mode);
+    let id = |side: Option<&GitDiffSide>| {
+        let id = side.and_then(|side| side.id.as_ref());
+        if options.abbrev_raw {
+            options.abbrev(id)
+        } else {
+            id.map_or_else(|| "0".repeat(40), |id| id.to_string())
+        }
+    };
+    format!(
+        ":{:06o} {:06o} {} {} {status}\t{paths}\n",
+        mode(pair.old.as_ref()),
+        mode(pair.new.as_ref()),
+        id(pair.old.as_ref()),
+        id(pair.new.as_ref()),
+    )
+}
+
+/// Formats the lines of the summary output for a file pair, which describe created, deleted,
+/// renamed and copied files and mode changes.
+fn format_summary(pair: &GitDiffPair) -> String {
+    let kind = match pair.status {
+        GitChangeType::Renamed => "rename",
+        GitChangeType::Copied => "copy",
+        _ => "",
+    };
+    if let (Some(old), Some(new), false) = (&pair.old, &pair.new, kind.is_empty()) {
+        let mut summary = format!(" {kind} {} ({}%)\n", display_name(pair), pair.score);
+        if old.mode != new.mode {
+            summary.push_str(&format!(
+                " mode change {:06o} => {:06o}\n",
+                old.mode, new.mode
+            ));
+        }
+        return summary;
+    }
+    match (&pair.old, &pair.new) {
+        (None, Some(new)) => format!(" create mode {:06o} {}\n", new.mode, new.path),
+        (Some(old), None) => format!(" delete mode {:06o} {}\n", old.mode, old.path),
+        (Some(old), Some(new))
+            if old.mode != new.mode && pair.status != GitChangeType::Unmerged =>
+        {
+            format!(
+                " mode change {:06o} => {:06o} {}\n",
+                old.mode,
+                new.mode,
+                display_name(pair)
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+/// How much a file pair changes, for the diffstat.
+struct FileStat {
+    name: String,
+    /// The number of lines added, or the size of the new file for binary files.
+    added: usize,
+    /// The number of lines removed, or the size of the old file for binary files.
+    deleted: usize,
+    binary: bool,
+    unmerged: bool,
+}
+
+impl FileStat {
+    fn new(
+        pair: &GitDiffPair,
+        old: Option<&GitDiffFile>,
+        new: Option<&GitDiffFile>,
+        options: &DiffOptions,
+    ) -> FileStat {
+        let mut stat = FileStat {
+            name: display_name(pair),
+            added: 0,
+            deleted: 0,
+            binary: false,
+            unmerged: pair.status == GitChangeType::Unmerged,
+        };
+        let old_contents = old.map_or("", |file| &file.contents);
+        let new_contents = new.map_or("", |file| &file.contents);
+        if stat.unmerged {
+            return stat;
+        }
+        if is_binary(old_contents) || is_binary(new_contents) {
+            stat.binary = true;
+            stat.added = new_contents.len();
+            stat.deleted = old_contents.len();
+        } else if old.map(|file| &file.id) != new.map(|file| &file.id) {
+            let (deleted, added) =
+                LineDiff::new(old_contents, new_contents, &options.xdiff).line_counts();
+            stat.added = added;
+            stat.deleted = deleted;
+        }
+        stat
+    }
+}
+
+fn decimal_width(number: usize) -> usize {
+    number.to_string().len()
+}
+
+/// Scales the number of changed lines to the width of the graph, keeping at least one column
+/// for any change.
+fn scale_linear(changes: usize, width: usize, max_change: usize) -> usize {
+    if changes == 0 {
+        0
+    } else {
+        1 + changes * (width - 1) / max_change
+    }
+}
+
+/// Formats the line summing up the diffstat, e.g. ` 2 files changed, 3 insertions(+)`.
+fn format_stat_summary(stats: &[FileStat]) -> String {
+    let mut files = 0;
+    let mut insertions = 0;
+    let mut deletions = 0;
+    for stat in stats.iter().filter(|stat| !stat.unmerged) {
+        files += 1;
+        // The sizes of binary files aren't lines.
+        if !stat.binary {
+            insertions += stat.added;
+            deletions += stat.deleted;
+        }
+    }
+    if files == 0 {
+        return String::from(" 0 files changed\n");
+    }
+
+    let plural = |count: usize, one: &str, many: &str| {
+        format!("{count} {}", if count == 1 { one } else { many })
+    };
+    let mut summary = format!(" {}", plural(files, "file changed", "files changed"));
+    if insertions > 0 || deletions == 0 {
+        summary.push_str(&format!(
+            ", {}",
+            plural(insertions, "insertion(+)", "insertions(+)")
+        ));
+    }
+    if deletions > 0 || insertions == 0 {
+        summary.push_str(&format!(
+            ", {}",
+            plural(deletions, "deletion(-)", "deletions(-)")
+        ));
+    }
+    summary.push('\n');
+    summary
+}
+
+/// Formats the diffstat, with a graph of the changes of each file fitting in `width` columns.
+/// Names which don't fit are shortened from the front.
+fn format_stat(stats: &[FileStat], width: usize, palette: &Palette<DiffColor>) -> String {
+    let mut max_len = 0;
+    let mut max_change = 0;
+    let mut number_width = 0;
+    let mut bin_width = 0;
+    for stat in stats {
+        max_len = max_len.max(stat.name.chars().count());
+        if stat.unmerged {
+            // "Unmerged" takes 8 columns.
+            bin_width = bin_width.max(8);
+        } else if stat.binary {
+            // "Bin XXX -> YYY bytes"
+            bin_width = bin_width.max(14 + decimal_width(stat.added) + decimal_width(stat.deleted));
+            // Counts are aligned with "Bin".
+            number_width = 3;
+        } else {
+            max_change = max_change.max(stat.added + stat.deleted);
+        }
+    }
+    let number_width = number_width.max(decimal_width(max_change)) as isize;
+
+    // The name and the graph are shrunk to fit, with at least 6 columns for the graph.
+    let width = (width as isize).max(16 + 6 + number_width);
+    let mut graph_width = if max_change + 4 > bin_width {
+        max_change as isize
+    } else {
+        bin_width as isize - 4
+    };
+    let mut name_width = max_len as isize;
+    if name_width + number_width + 6 + graph_width > width {
+        if graph_width > width * 3 / 8 - number_width - 6 {
+            graph_width = (width * 3 / 8 - number_width - 6).max(6);
+        }
+        if name_width > width - number_width - 6 - graph_width {
+            name_width = width - number_width - 6 - graph_width;
+        } else {
+            graph_width = width - number_width - 6 - name_width;
+        }
+    }
+    let (number_width, name_width, graph_width) = (
+        number_width as usize,
+        name_width.max(0) as usize,
+        graph_width.max(0) as usize,
+    );
+
+    let mut out = String::new();
+    for stat in stats {
+        let mut prefix = "";
+        let mut name = stat.name.as_str();
+        let mut len = name_width;
+        let name_len = name.chars().count();
+        if name_width < name_len {
+            prefix = "...";
+            len = len.saturating_sub(3);
+            let skipped = name_len.saturating_sub(len);
+            name = &name[name
+                .char_indices()
+                .nth(skipped)
+                .map_or(name.len(), |(idx, _)| idx)..];
+            if let Some(slash) = name.find('/') {
+                name = &name[slash..];
+            }
+        }
+        let padding = " ".repeat(len.saturating_sub(name.chars().count()));
+
+        if stat.unmerged {
+            out.push_str(&format!(
+                " {prefix}{name}{padding} | {:>number_width$}\n",
+                "Unmerged"
+            ));
+            continue;
+        }
+        if stat.binary {
+            out.push_str(&format!(
+                " {prefix}{name}{padding} | {:>number_width$}",
+                "Bin"
+            ));
+            if stat.added == 0 && stat.deleted == 0 {
+                out.push('\n');
+            } else {
+                out.push_str(&format!(
+                    " {} -> {} bytes\n",
+                    palette.paint(DiffColor::Old, &stat.deleted.to_string()),
+                    palette.paint(DiffColor::New, &stat.added.to_string())
+                ));
+            }
+            continue;
+        }
+
+        let total = stat.added + stat.deleted;
+        let (mut added, mut deleted) = (stat.added, stat.deleted);
+        if graph_width <= max_change {
+            let mut scaled_total = scale_linear(total, graph_width, max_change);
+            if scaled_total < 2 && added > 0 && deleted > 0 {
+                scaled_total = 2;
+            }
+            if added < deleted {
+                added = scale_linear(added, graph_width, max_change);
+                deleted = scaled_total - added;
+            } else {
+                deleted = scale_linear(deleted, graph_width, max_change);
+                added = scaled_total - deleted;
+            }
+        }
+        let graph = |slot, sign: &str, count| match count {
+            0 => String::new(),
+            _ => palette.paint(slot, &sign.repeat(count)),
+        };
+        out.push_str(&format!(
+            " {prefix}{name}{padding} | {total:>number_width$}{}{}{}\n",
+            if total > 0 { " " } else { "" },
+            graph(DiffColor::New, "+", added),
+            graph(DiffColor::Old, "-", deleted)
+        ));
+    }
+    out.push_str(&format_stat_summary(stats));
+    out
+}
+
+fn format_numstat(stats: &[FileStat]) -> String {
+    let mut out = String::new();
+    for stat in stats {
+        if stat.binary {
+            out.push_str(&format!("-\t-\t{}\n", stat.name));
+        } else {
+            out.push_str(&format!(
+                "{}\t{}\t{}\n",
+                stat.added, stat.deleted, stat.name
+            ));
+        }
+    }
+    out
+}
+
+type LoadedPair = (Option<GitDiffFile>, Option<GitDiffFile>);
+
+fn load_pair(
+    pair: &GitDiffPair,
+    read: &mut impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+) -> Result<LoadedPair, RustGitError> {
+    if pair.status == GitChangeType::Unmerged {
+        return Ok((None, None));
+    }
+    let mut load = |side: Option<&GitDiffSide>| {
+        side.map(|side| GitDiffFile::load(side, read(side)?))
+            .transpose()
+    };
+    Ok((load(pair.old.as_ref())?, load(pair.new.as_ref())?))
+}
+
+/// Formats the file pairs in the outputs chosen by the options, reading the contents of their
+/// sides with `read` when an output needs them.
+pub(crate) fn format_diff(
+    pairs: &[GitDiffPair],
+    options: &DiffOptions,
+    mut read: impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+) -> Result<String, RustGitError> {
+    let output = &options.output;
+    let mut out = String::new();
+    if pairs.is_empty() {
+        return Ok(out);
+    }
+    // Each output is separated from the patch by an empty line.
+    let mut separator = false;
+
+    if output.raw || output.name_only || output.name_status {
+        for pair in pairs {
+            out.push_str(&format_raw(pair, options));
+        }
+        separator = true;
+    }
+
+    let files = if output.numstat || output.stat || output.shortstat || output.patch {
+        pairs
+            .iter()
+            .map(|pair| load_pair(pair, &mut read))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![]
+    };
+
+    if output.numstat || output.stat || output.shortstat {
+        let stats = pairs
+            .iter()
+            .zip(&files)
+            .map(|(pair, (old, new))| FileStat::new(pair, old.as_ref(), new.as_ref(), options))
+            .collect::<Vec<_>>();
+        if output.numstat {
+            out.push_str(&format_numstat(&stats));
+        }
+        if output.stat {
+            out.push_str(&format_stat(&stats, options.stat_width, &options.palette));
+        }
+        if output.shortstat {
+            out.push_str(&format_stat_summary(&stats));
+        }
+        separator = true;
+    }
+
+    if output.summary {
+        let summary = pairs.iter().map(format_summary).collect::<String>();
+        if !summary.is_empty() {
+            out.push_str(&summary);
+            separator = true;
+        }
+    }
+
+    if output.patch {
+        if separator {
+            out.push('\n');
+        }
+        let mut lines = vec![];
+        for (pair, (old, new)) in pairs.iter().zip(&files) {
+            if pair.status == GitChangeType::Unmerged {
+                lines.push(PatchLine::new(
+                    PatchLineKind::Plain,
+                    format!("* Unmerged path {}\n", pair.path()),
+                ));
+            } else {
+                lines.extend(patch_lines(
+                    old.as_ref(),
+                    new.as_ref(),
+                    &format_metainfo(pair),
+                    options,
+                ));
+            }
+        }
+        // Moved lines may be moved to another file, so they're found in all patches at once.
+        match options.color_moved {
+            Some(ColorMovedMode::No) | None => (),
+            Some(_) if !options.palette.is_enabled() => (),
+            Some(mode) => mark_moved_lines(&mut lines, mode),
+        }
+        out.push_str(&render_patch(&lines, &options.palette));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::{GitObject, GitObjectType};
+
+    fn file(path: &str, mode: GitIndexMode, contents: &str) -> GitDiffFile {
+        let id = GitObject::new(GitObjectType::Blob, String::from(contents))
+            .unwrap()
+            .id;
+        GitDiffFile {
+            path: String::from(path),
+            mode,
+            id,
+            contents: String::from(contents),
+        }
+    }
+
+    fn format_patch(
+        old: Option<&GitDiffFile>,
+        new: Option<&GitDiffFile>,
+        metainfo: &str,
+        options: &DiffOptions,
+    ) -> String {
+        render_patch(&patch_lines(old, new, metainfo, options), &options.palette)
+    }
+
+    #[test]
+    fn should_format_modified_files() {
+        let old = file("a.txt", GitIndexMode::RegularFile0644, "a\nb\n");
+        let new = file("a.txt", GitIndexMode::RegularFile0644, "a\nc\n");
+        assert_eq!(
+            format_patch(Some(&old), Some(&new), "", &DiffOptions::default()),
+            "diff --git a/a.txt b/a.txt
+index 422c2b7..0f7bc76 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+ a
+-b
++c
+"
+        );
+    }
+
+    #[test]
+    fn should_format_added_deleted_and_mode_changes() {
+        let empty = file("empty", GitIndexMode::RegularFile0644, "");
+        assert_eq!(
+            format_patch(None, Some(&empty), "", &DiffOptions::default()),
+            "diff --git a/empty b/empty\nnew file mode 100644\nindex 0000000..e69de29\n"
+        );
+
+        let old = file("run.sh", GitIndexMode::RegularFile0644, "echo\n");
+        assert_eq!(
+            format_patch(Some(&old), None, "", &DiffOptions::default()),
+            "diff --git a/run.sh b/run.sh
+deleted file mode 100644
+index fa11a6a..0000000
+--- a/run.sh
++++ /dev/null
+@@ -1 +0,0 @@
+-echo
+"
+        );
+
+        let new = file("run.sh", GitIndexMode::RegularFile0755, "echo\n");
+        assert_eq!(
+            format_patch(Some(&old), Some(&new), "", &DiffOptions::default()),
+            "diff --git a/run.sh b/run.sh\nold mode 100644\nnew mode 100755\n"
+        );
+    }
+
+    #[test]
+    fn should_not_show_binary_contents_or_ignored_changes() {
+        let old = file("bin", GitIndexMode::RegularFile0644, "a\0b");
+        assert_eq!(
+            format_patch(None, Some(&old), "", &DiffOptions::default()),
+            "diff --git a/bin b/bin
+new file mode 100644
+index 0000000..20b5be9
+Binary files /dev/null and b/bin differ
+"
+        );
+
+        let old = file("a.txt", GitIndexMode::RegularFile0644, "a b\n");
+        let new = file("a.txt", GitIndexMode::RegularFile0644, "a  b\n");
+        let mut options = DiffOptions::default();
+        options.xdiff.ignore_space_change = true;
+        assert_eq!(format_patch(Some(&old), Some(&new), "", &options), "");
+    }
+
+    #[test]
+    fn should_abbreviate_renames_to_common_parts() {
+        assert_eq!(pprint_rename("a/b/c.txt", "a/d/c.txt"), "a/{b => d}/c.txt");
+        assert_eq!(pprint_rename("old.txt", "new.txt"), "old.txt => new.txt");
+        assert_eq!(pprint_rename("a/x.txt", "b/x.txt"), "{a => b}/x.txt");
+    }
+
+    #[test]
+    fn should_scale_and_truncate_stats_to_width() {
+        let stat = |name: &str, added, deleted, binary| FileStat {
+            name: String::from(name),
+            added,
+            deleted,
+            binary,
+            unmerged: false,
+        };
+        let stats = [
+            stat("a.txt", 2, 1, false),
+            stat("bin", 3, 0, true),
+            stat(&format!("{}x.txt", "long/".repeat(9)), 100, 0, false),
+        ];
+        assert_eq!(
+            format_stat(&stats, 40, &Palette::default()),
+            " a.txt                     |   3 +-
+ bin                       | Bin 0 -> 3 bytes
+ .../long/long/long/x.txt  | 100 ++++++
+ 3 files changed, 102 insertions(+), 1 deletion(-)
+"
+        );
+    }
+
+    #[test]
+    fn should_highlight_whitespace_errors() {
+        let (set, ws) = ("\x1b[32m", "\x1b[41m");
+        assert_eq!(
+            format_whitespace_errors(" \tx  ", set, RESET, ws),
+            "\x1b[41m \x1b[m\t\x1b[32mx\x1b[m\x1b[41m  \x1b[m"
+        );
+        assert_eq!(
+            format_whitespace_errors("\tx", set, RESET, ws),
+            "\t\x1b[32mx\x1b[m"
+        );
+
+        // Only the blank lines added at the end of the file are errors.
+        assert_eq!(count_trailing_blank("a\n\n \n"), 2);
+        assert_eq!(blank_at_eof("a\n\n", "a\n\n\n\n"), Some((2, 2)));
+        assert_eq!(blank_at_eof("a\n\n\n", "b\n\n"), None);
+    }
+}
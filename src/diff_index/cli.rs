@@ -0,0 +1,27 @@
+use clap::Args;
+
+use crate::diff::cli::DiffOptionArgs;
+
+#[derive(Args, Debug)]
+#[command(about = "Compare a tree to the working tree or index")]
+#[command(long_about = "
+Compares the content and mode of the blobs found in a tree object with the corresponding tracked files in the
+working tree, or with the corresponding paths in the index. When <path> arguments are present, compares only paths
+matching those patterns. Otherwise all tracked files are compared.
+")]
+pub(crate) struct DiffIndexArgs {
+    #[command(flatten)]
+    pub options: DiffOptionArgs,
+
+    /// Do not consider the on-disk file at all.
+    #[arg(long)]
+    pub cached: bool,
+
+    /// The id of a tree object to diff against.
+    #[arg(value_name = "tree-ish")]
+    pub tree_ish: String,
+
+    /// Limits the paths compared to those matching the pathspec.
+    #[arg(value_name = "path")]
+    pub pathspec: Vec<String>,
+}
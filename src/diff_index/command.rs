@@ -0,0 +1,49 @@
+use crate::{
+    command::GitCommand,
+    diff::command::{color_options, diff_options},
+    diff_format::{format_diff, DiffOptions},
+    pathspec::{Pathspec, PathspecOptions},
+    repo::RepoState,
+    RustGitError,
+};
+
+use super::cli::DiffIndexArgs;
+
+pub(crate) struct DiffIndexCommand {
+    args: DiffIndexArgs,
+    options: DiffOptions,
+    pathspec_options: PathspecOptions,
+}
+
+impl DiffIndexCommand {
+    pub fn new(args: DiffIndexArgs, pathspec_options: PathspecOptions) -> DiffIndexCommand {
+        DiffIndexCommand {
+            options: diff_options(&args.options, false),
+            args,
+            pathspec_options,
+        }
+    }
+}
+
+impl GitCommand for DiffIndexCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+        let mut options = self.options.clone();
+        color_options(&mut options, &repo.config, false)?;
+
+        let tree = repo.peel_to_tree(&repo.resolve_revision(&self.args.tree_ish)?)?;
+        let mut pathspec = Pathspec::parse(&repo, &self.args.pathspec, self.pathspec_options)?;
+        let pairs = repo.diff_tree_to_index(Some(&tree), &mut pathspec, self.args.cached)?;
+        let unchanged = if options.finds_copies_harder() {
+            repo.tree_files(&tree, &mut pathspec)?
+        } else {
+            vec![]
+        };
+        let pairs = repo.detect_renames(pairs, unchanged, options.renames.as_ref())?;
+        print!(
+            "{}",
+            format_diff(&pairs, &options, |side| repo.read_diff_side(side))?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use clap::Args;
+
+use crate::diff::cli::DiffOptionArgs;
+
+#[derive(Args, Debug)]
+#[command(about = "Compares the content and mode of blobs found via two tree objects")]
+#[command(long_about = "
+Compares the content and mode of the blobs found via two tree objects.
+
+If there is only one <tree-ish> given, the commit is compared with its parents (see --stdin below).
+
+Note that git diff-tree can use the tree encapsulated in a commit object.
+")]
+pub(crate) struct DiffTreeArgs {
+    #[command(flatten)]
+    pub options: DiffOptionArgs,
+
+    /// Recurse into sub-trees.
+    #[arg(short = 'r')]
+    pub recursive: bool,
+
+    /// When --root is specified the initial commit will be shown as a big creation event. This is equivalent to a
+    /// diff against the NULL tree.
+    #[arg(long)]
+    pub root: bool,
+
+    /// When --stdin is specified, the command does not take <tree-ish> arguments from the command line. Instead,
+    /// it reads lines containing either two <tree>, one <commit>, or a list of <commit> from its standard input.
+    /// (Use a single space as separator.)
+    ///
+    /// When two trees are given, it compares the first tree with the second. When a single commit is given, it
+    /// compares the commit with its parents. The remaining commits, when given, are used as if they are parents
+    /// of the first commit.
+    ///
+    /// When comparing two trees, the ID of both trees (separated by a space and terminated by a newline) is
+    /// printed before the difference. When comparing commits, the ID of the first (or only) commit, followed by
+    /// a newline, is printed.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// git diff-tree outputs a line with the commit ID when applicable. This flag suppressed the commit ID
+    /// output.
+    #[arg(long)]
+    pub no_commit_id: bool,
+
+    /// The tree-ish objects to compare, followed by the paths to limit the diff to.
+    #[arg(value_name = "tree-ish-or-path")]
+    pub args: Vec<String>,
+}
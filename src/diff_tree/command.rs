@@ -0,0 +1,153 @@
+use std::io::{self, BufRead};
+
+use crate::{
+    command::GitCommand,
+    diff::command::{color_options, diff_options},
+    diff_format::{format_diff, DiffOptions},
+    object::{GitObjectId, GitObjectType},
+    pathspec::{Pathspec, PathspecOptions},
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
+
+use super::cli::DiffTreeArgs;
+
+const USAGE: &str = "usage: git diff-tree [<options>] <tree-ish> [<tree-ish>] [<path>...]";
+
+pub(crate) struct DiffTreeCommand {
+    args: DiffTreeArgs,
+    options: DiffOptions,
+    pathspec_options: PathspecOptions,
+}
+
+impl DiffTreeCommand {
+    pub fn new(args: DiffTreeArgs, pathspec_options: PathspecOptions) -> DiffTreeCommand {
+        DiffTreeCommand {
+            options: diff_options(&args.options, false),
+            args,
+            pathspec_options,
+        }
+    }
+
+    /// Outputs other than the list of changed paths compare every file, so they imply -r.
+    fn recursive(&self) -> bool {
+        self.args.recursive || self.options.output.needs_files()
+    }
+
+    /// Prints the changes between two trees, preceded by the header if there are any.
+    fn show_diff(
+        &self,
+        repo: &GitRepo,
+        old: Option<&GitObjectId>,
+        new: &GitObjectId,
+        pathspec: &mut Pathspec,
+        header: Option<&str>,
+    ) -> Result<(), RustGitError> {
+        let mut options = self.options.clone();
+        color_options(&mut options, &repo.config, false)?;
+        let pairs = repo.diff_trees(old, Some(new), pathspec, self.recursive())?;
+        let unchanged = match old {
+            Some(old) if options.finds_copies_harder() => repo.tree_files(old, pathspec)?,
+            _ => vec![],
+        };
+        let pairs = repo.detect_renames(pairs, unchanged, options.renames.as_ref())?;
+        if pairs.is_empty() {
+            return Ok(());
+        }
+        if let Some(header) = header.filter(|_| !self.args.no_commit_id) {
+            println!("{header}");
+        }
+        print!(
+            "{}",
+            format_diff(&pairs, &options, |side| repo.read_diff_side(side))?
+        );
+        Ok(())
+    }
+
+    /// Prints the changes a commit made to its parent, which are the provided commits instead
+    /// if there are any. Root commits are only shown with --root, and merges aren't shown.
+    fn show_commit(
+        &self,
+        repo: &GitRepo,
+        commit_id: &GitObjectId,
+        parents: Option<Vec<GitObjectId>>,
+        pathspec: &mut Pathspec,
+    ) -> Result<(), RustGitError> {
+        let commit = repo.read_commit(commit_id)?;
+        let parents = parents.unwrap_or(commit.parents);
+        let old_tree = match &parents[..] {
+            [] if self.args.root => None,
+            [parent] => Some(repo.peel_to_tree(parent)?),
+            _ => return Ok(()),
+        };
+        self.show_diff(
+            repo,
+            old_tree.as_ref(),
+            &commit.tree,
+            pathspec,
+            Some(&commit_id.to_string()),
+        )
+    }
+
+    /// Handles a line of the standard input, which holds either two trees or a commit
+    /// followed by the parents to compare it with.
+    fn show_stdin_line(
+        &self,
+        repo: &GitRepo,
+        line: &str,
+        pathspec: &mut Pathspec,
+    ) -> Result<(), RustGitError> {
+        let ids = line
+            .split(' ')
+            .map(|id| repo.resolve_revision(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        match &ids[..] {
+            [] => Ok(()),
+            [old, new] if repo.object_type(old)? == GitObjectType::Tree => {
+                self.show_diff(repo, Some(old), new, pathspec, Some(line))
+            }
+            [commit, parents @ ..] => {
+                let commit_id = repo.peel_to_commit(commit)?;
+                let parents = (!parents.is_empty()).then(|| parents.to_vec());
+                self.show_commit(repo, &commit_id, parents, pathspec)
+            }
+        }
+    }
+}
+
+impl GitCommand for DiffTreeCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+
+        if self.args.stdin {
+            let mut pathspec = Pathspec::parse(&repo, &self.args.args, self.pathspec_options)?;
+            for line in io::stdin().lock().lines() {
+                let line = line?;
+                self.show_stdin_line(&repo, line.trim_end(), &mut pathspec)?;
+            }
+            return Ok(());
+        }
+
+        let Some((first, rest)) = self.args.args.split_first() else {
+            return Err(RustGitError::new(USAGE));
+        };
+        let first = repo.resolve_revision(first)?;
+        let second = rest.first().and_then(|arg| repo.resolve_revision(arg).ok());
+        let paths = &rest[usize::from(second.is_some())..];
+        let mut pathspec = Pathspec::parse(&repo, paths, self.pathspec_options)?;
+
+        match second {
+            Some(second) => self.show_diff(
+                &repo,
+                Some(&repo.peel_to_tree(&first)?),
+                &repo.peel_to_tree(&second)?,
+                &mut pathspec,
+                None,
+            ),
+            None => {
+                let commit_id = repo.peel_to_commit(&first)?;
+                self.show_commit(&repo, &commit_id, None, &mut pathspec)
+            }
+        }
+    }
+}
@@ -0,0 +1,511 @@
+// The file pairs which diff commands compare, collected from trees, the index and the worktree,
+// based on C Git's implementation:
+// https://github.com/git/git/blob/master/diff-lib.c
+// https://github.com/git/git/blob/master/tree-diff.c
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{
+    index::{GitIndexEntry, GitIndexMode, GitIndexStageFlag},
+    object::{GitObjectId, GitTreeEntry},
+    pathspec::Pathspec,
+    repo::GitRepo,
+    RustGitError,
+};
+
+const GITLINK_MODE: u32 = 0o160000;
+
+/// Returns whether the two modes describe the same kind of file, i.e. only the executable bit
+/// may differ.
+pub(crate) fn same_kind(one: u32, other: u32) -> bool {
+    one & 0o170000 == other & 0o170000
+}
+
+/// How a path changed between two versions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum GitChangeType {
+    Added,
+    Deleted,
+    Modified,
+    /// The path changed between a regular file, a symbolic link and a submodule.
+    TypeChanged,
+    Renamed,
+    /// The path was copied from another one, which may have changed too.
+    Copied,
+    /// The path has unmerged entries in the index.
+    Unmerged,
+}
+
+impl GitChangeType {
+    /// Returns the letter representing the change in short formats.
+    pub(crate) fn letter(&self) -> char {
+        match self {
+            GitChangeType::Added => 'A',
+            GitChangeType::Deleted => 'D',
+            GitChangeType::Modified => 'M',
+            GitChangeType::TypeChanged => 'T',
+            GitChangeType::Renamed => 'R',
+            GitChangeType::Copied => 'C',
+            GitChangeType::Unmerged => 'U',
+        }
+    }
+}
+
+/// One version of a path in a file pair.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GitDiffSide {
+    /// The path, relative to the root of the repo, or to the current directory for files
+    /// compared outside of a repo.
+    pub(crate) path: String,
+    pub(crate) mode: u32,
+    /// The id of the object, or None for worktree files, which are only hashed when their
+    /// contents are needed.
+    pub(crate) id: Option<GitObjectId>,
+}
+
+impl GitDiffSide {
+    fn from_tree_entry(path: String, entry: &GitTreeEntry) -> Result<GitDiffSide, RustGitError> {
+        Ok(GitDiffSide {
+            path,
+            mode: u32::from_str_radix(&entry.mode, 8)?,
+            id: Some(entry.obj_id.clone()),
+        })
+    }
+
+    fn from_index_entry(entry: &GitIndexEntry) -> GitDiffSide {
+        GitDiffSide {
+            path: entry.path_name.as_string(),
+            mode: entry.mode.as_octal(),
+            id: Some(entry.name.clone()),
+        }
+    }
+}
+
+/// A path which differs between the old and new versions being compared, where a missing side
+/// means the path was added or deleted.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GitDiffPair {
+    pub(crate) old: Option<GitDiffSide>,
+    pub(crate) new: Option<GitDiffSide>,
+    pub(crate) status: GitChangeType,
+    /// For renames and copies, the similarity of both sides as a percentage.
+    pub(crate) score: u32,
+}
+
+impl GitDiffPair {
+    /// Pairs the two versions of a path, unless they're the same.
+    pub(crate) fn between(
+        old: Option<GitDiffSide>,
+        new: Option<GitDiffSide>,
+    ) -> Option<GitDiffPair> {
+        let status = match (&old, &new) {
+            (None, None) => return None,
+            (None, Some(_)) => GitChangeType::Added,
+            (Some(_), None) => GitChangeType::Deleted,
+            (Some(old), Some(new))
+                if old.mode == new.mode && old.id.is_some() && old.id == new.id =>
+            {
+                return None
+            }
+            (Some(old), Some(new)) if !same_kind(old.mode, new.mode) => GitChangeType::TypeChanged,
+            (Some(_), Some(_)) => GitChangeType::Modified,
+        };
+        Some(GitDiffPair {
+            old,
+            new,
+            status,
+            score: 0,
+        })
+    }
+
+    /// Returns the pair reporting that the path is unmerged, with the mode of the worktree file
+    /// on the new side if it's compared.
+    fn unmerged(path: String, worktree_mode: Option<u32>) -> GitDiffPair {
+        let side = |mode| GitDiffSide {
+            path: path.clone(),
+            mode,
+            id: None,
+        };
+        GitDiffPair {
+            old: Some(side(0)),
+            new: Some(side(worktree_mode.unwrap_or(0))),
+            status: GitChangeType::Unmerged,
+            score: 0,
+        }
+    }
+
+    /// Returns the path of the new side, or of the old one for deleted paths.
+    pub(crate) fn path(&self) -> &str {
+        match (&self.new, &self.old) {
+            (Some(side), _) | (None, Some(side)) => &side.path,
+            (None, None) => "",
+        }
+    }
+}
+
+/// Reads the contents of a file, or the target of a symbolic link.
+pub(crate) fn read_worktree_file(path: &Path) -> Result<String, RustGitError> {
+    if fs::symlink_metadata(path)?.is_symlink() {
+        Ok(fs::read_link(path)?.to_string_lossy().into_owned())
+    } else {
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
+/// The entries of a tree, keyed so that they sort in tree order, where trees sort as if their
+/// names ended with a slash.
+fn tree_entries_by_key(
+    repo: &GitRepo,
+    tree: Option<&GitObjectId>,
+) -> Result<BTreeMap<String, GitTreeEntry>, RustGitError> {
+    let Some(tree) = tree else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(repo
+        .read_tree(tree)?
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let key = if entry.entry_type == "tree" {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            (key, entry)
+        })
+        .collect())
+}
+
+impl GitRepo {
+    /// Reads the contents of one side of a file pair, from the object store or the worktree.
+    /// Submodules are shown as the commit they're at.
+    pub(crate) fn read_diff_side(&self, side: &GitDiffSide) -> Result<String, RustGitError> {
+        if side.mode & 0o170000 == GITLINK_MODE {
+            let id = side
+                .id
+                .as_ref()
+                .map_or_else(|| "0".repeat(40), |id| id.to_string());
+            return Ok(format!("Subproject commit {id}\n"));
+        }
+        match &side.id {
+            Some(id) => match self.obj_store.read_object(id)? {
+                Some(obj) => Ok(obj.content),
+                None => Err(RustGitError::new(format!(
+                    "fatal: unable to read {id} ({})",
+                    side.path
+                ))),
+            },
+            None => read_worktree_file(&self.root_dir.join(&side.path)),
+        }
+    }
+
+    /// Compares two trees, either of which may be missing, for the paths matching the pathspec.
+    /// Unless `recursive` is set, only the entries of the top-level trees are compared, so that
+    /// changed subtrees are reported as a whole.
+    pub(crate) fn diff_trees(
+        &self,
+        old: Option<&GitObjectId>,
+        new: Option<&GitObjectId>,
+        pathspec: &mut Pathspec,
+        recursive: bool,
+    ) -> Result<Vec<GitDiffPair>, RustGitError> {
+        let mut pairs = vec![];
+        self.diff_subtrees(old, new, "", pathspec, recursive, &mut pairs)?;
+        Ok(pairs)
+    }
+
+    fn diff_subtrees(
+        &self,
+        old: Option<&GitObjectId>,
+        new: Option<&GitObjectId>,
+        prefix: &str,
+        pathspec: &mut Pathspec,
+        recursive: bool,
+        pairs: &mut Vec<GitDiffPair>,
+    ) -> Result<(), RustGitError> {
+        let mut entries = BTreeMap::<String, (Option<GitTreeEntry>, Option<GitTreeEntry>)>::new();
+        for (key, entry) in tree_entries_by_key(self, old)? {
+            entries.entry(key).or_default().0 = Some(entry);
+        }
+        for (key, entry) in tree_entries_by_key(self, new)? {
+            entries.entry(key).or_default().1 = Some(entry);
+        }
+
+        for (key, (old_entry, new_entry)) in entries {
+            if let (Some(old_entry), Some(new_entry)) = (&old_entry, &new_entry) {
+                if old_entry.obj_id == new_entry.obj_id && old_entry.mode == new_entry.mode {
+                    continue;
+                }
+            }
+            let Some(name) = key.strip_suffix('/') else {
+                let path = format!("{prefix}{key}");
+                if pathspec.matches(&path).is_none() {
+                    continue;
+                }
+                let old_side = old_entry
+                    .map(|entry| GitDiffSide::from_tree_entry(path.clone(), &entry))
+                    .transpose()?;
+                let new_side = new_entry
+                    .map(|entry| GitDiffSide::from_tree_entry(path.clone(), &entry))
+                    .transpose()?;
+                pairs.extend(GitDiffPair::between(old_side, new_side));
+                continue;
+            };
+
+            let path = format!("{prefix}{name}");
+            if !pathspec.may_match_under(&path) && pathspec.matches(&path).is_none() {
+                continue;
+            }
+            let old_id = old_entry.as_ref().map(|entry| &entry.obj_id);
+            let new_id = new_entry.as_ref().map(|entry| &entry.obj_id);
+            if recursive {
+                self.diff_subtrees(old_id, new_id, &format!("{path}/"), pathspec, true, pairs)?;
+                continue;
+            }
+
+            // Subtrees are only reported if they have changes matching the pathspec.
+            if !pathspec.is_empty() {
+                let mut subtree_pairs = vec![];
+                self.diff_subtrees(
+                    old_id,
+                    new_id,
+                    &format!("{path}/"),
+                    pathspec,
+                    true,
+                    &mut subtree_pairs,
+                )?;
+                if subtree_pairs.is_empty() {
+                    continue;
+                }
+            }
+            let old_side = old_entry
+                .map(|entry| GitDiffSide::from_tree_entry(path.clone(), &entry))
+                .transpose()?;
+            let new_side = new_entry
+                .map(|entry| GitDiffSide::from_tree_entry(path.clone(), &entry))
+                .transpose()?;
+            pairs.extend(GitDiffPair::between(old_side, new_side));
+        }
+        Ok(())
+    }
+
+    /// Returns the files of the tree with the provided id, or none if it's missing, by path.
+    fn tree_sides(
+        &self,
+        tree: Option<&GitObjectId>,
+    ) -> Result<BTreeMap<String, GitDiffSide>, RustGitError> {
+        let Some(tree) = tree else {
+            return Ok(BTreeMap::new());
+        };
+        Ok(self
+            .read_tree_entries(tree, "", GitIndexStageFlag::RegularFileNoConflict)?
+            .iter()
+            .map(|entry| {
+                (
+                    entry.path_name.as_string(),
+                    GitDiffSide::from_index_entry(entry),
+                )
+            })
+            .collect())
+    }
+
+    /// Returns the files of the tree with the provided id matching the pathspec, which copies may
+    /// come from when looking for copies harder.
+    pub(crate) fn tree_files(
+        &self,
+        tree: &GitObjectId,
+        pathspec: &mut Pathspec,
+    ) -> Result<Vec<GitDiffSide>, RustGitError> {
+        Ok(self
+            .tree_sides(Some(tree))?
+            .into_iter()
+            .filter(|(path, _)| pathspec.matches(path).is_some())
+            .map(|(_, side)| side)
+            .collect())
+    }
+
+    /// Returns the files of the index matching the pathspec, which copies may come from when
+    /// looking for copies harder.
+    pub(crate) fn index_files(&self, pathspec: &mut Pathspec) -> Vec<GitDiffSide> {
+        self.index
+            .iter_entries()
+            .filter(|entry| entry.flags.stage == GitIndexStageFlag::RegularFileNoConflict)
+            .filter(|entry| pathspec.matches(&entry.path_name.as_string()).is_some())
+            .map(GitDiffSide::from_index_entry)
+            .collect()
+    }
+
+    /// Returns the version of the path of the index entry in the worktree, which is the entry
+    /// itself if the file didn't change, or None if it's missing.
+    fn worktree_side(&self, entry: &GitIndexEntry) -> Result<Option<GitDiffSide>, RustGitError> {
+        if entry.extended_flags.skip_worktree {
+            return Ok(Some(GitDiffSide::from_index_entry(entry)));
+        }
+        let Some(metadata) = self.worktree_metadata(&entry.path_name)? else {
+            return Ok(None);
+        };
+        if !self.is_entry_modified(entry, &metadata)? {
+            return Ok(Some(GitDiffSide::from_index_entry(entry)));
+        }
+        let mode = GitIndexMode::from_worktree(&metadata, Some(&entry.mode), &self.config.core);
+        Ok(Some(GitDiffSide {
+            path: entry.path_name.as_string(),
+            mode: mode.as_octal(),
+            id: None,
+        }))
+    }
+
+    /// Returns the mode of the worktree file at the path, if it exists.
+    fn worktree_mode(&self, entry: &GitIndexEntry) -> Result<Option<u32>, RustGitError> {
+        Ok(self.worktree_metadata(&entry.path_name)?.map(|metadata| {
+            GitIndexMode::from_worktree(&metadata, Some(&entry.mode), &self.config.core).as_octal()
+        }))
+    }
+
+    /// Compares the tree with the provided id, or an empty tree, with the index if `cached` is
+    /// set, or else with the worktree, for the paths matching the pathspec. Files of the index
+    /// whose stat data show they didn't change are taken from the index instead of being read.
+    pub(crate) fn diff_tree_to_index(
+        &self,
+        tree: Option<&GitObjectId>,
+        pathspec: &mut Pathspec,
+        cached: bool,
+    ) -> Result<Vec<GitDiffPair>, RustGitError> {
+        let mut versions = BTreeMap::<String, (Option<GitDiffSide>, Option<usize>)>::new();
+        for (path, side) in self.tree_sides(tree)? {
+            versions.entry(path).or_default().0 = Some(side);
+        }
+        let mut unmerged = BTreeMap::<String, &GitIndexEntry>::new();
+        for (idx, entry) in self.index.iter_entries().enumerate() {
+            let path = entry.path_name.as_string();
+            if entry.flags.stage == GitIndexStageFlag::RegularFileNoConflict {
+                versions.entry(path).or_default().1 = Some(idx);
+            } else {
+                versions.entry(path.clone()).or_default();
+                unmerged.entry(path).or_insert(entry);
+            }
+        }
+
+        let mut pairs = vec![];
+        for (path, (tree_side, index_position)) in versions {
+            if pathspec.matches(&path).is_none() {
+                continue;
+            }
+            if let Some(entry) = unmerged.get(&path) {
+                let worktree_mode = if cached {
+                    None
+                } else {
+                    self.worktree_mode(entry)?
+                };
+                pairs.push(GitDiffPair::unmerged(path, worktree_mode));
+                continue;
+            }
+            let new_side = match index_position.map(|idx| self.index.entry_at(idx)) {
+                // Paths added with --intent-to-add aren't in the index yet.
+                Some(entry) if cached => (!entry.extended_flags.intent_to_add)
+                    .then(|| GitDiffSide::from_index_entry(entry)),
+                Some(entry) => self.worktree_side(entry)?,
+                None => None,
+            };
+            pairs.extend(GitDiffPair::between(tree_side, new_side));
+        }
+        Ok(pairs)
+    }
+
+    /// Compares the index with the worktree for the paths matching the pathspec. Unmerged paths
+    /// are reported as such, followed by the changes of the worktree file from our side.
+    pub(crate) fn diff_index_to_worktree(
+        &self,
+        pathspec: &mut Pathspec,
+    ) -> Result<Vec<GitDiffPair>, RustGitError> {
+        let mut pairs = vec![];
+        let mut last_unmerged_path = None;
+        for entry in self.index.iter_entries() {
+            let path = entry.path_name.as_string();
+            if pathspec.matches(&path).is_none() {
+                continue;
+            }
+
+            if entry.flags.stage != GitIndexStageFlag::RegularFileNoConflict {
+                if last_unmerged_path.as_ref() != Some(&path) {
+                    pairs.push(GitDiffPair::unmerged(
+                        path.clone(),
+                        self.worktree_mode(entry)?,
+                    ));
+                    last_unmerged_path = Some(path);
+                }
+                if entry.flags.stage == GitIndexStageFlag::Ours {
+                    pairs.extend(GitDiffPair::between(
+                        Some(GitDiffSide::from_index_entry(entry)),
+                        self.worktree_side(entry)?,
+                    ));
+                }
+                continue;
+            }
+
+            // Paths added with --intent-to-add aren't in the index yet.
+            let old_side =
+                (!entry.extended_flags.intent_to_add).then(|| GitDiffSide::from_index_entry(entry));
+            let new_side = match self.worktree_side(entry)? {
+                Some(mut side) if entry.extended_flags.intent_to_add => {
+                    side.id = None;
+                    Some(side)
+                }
+                new_side => new_side,
+            };
+            pairs.extend(GitDiffPair::between(old_side, new_side));
+        }
+        Ok(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(mode: u32, id: Option<&str>) -> GitDiffSide {
+        GitDiffSide {
+            path: String::from("a"),
+            mode,
+            id: id.map(|id| GitObjectId::new(id.repeat(40))),
+        }
+    }
+
+    #[test]
+    fn should_pair_versions_by_change() {
+        let status = |old, new| GitDiffPair::between(old, new).map(|pair| pair.status);
+        assert_eq!(
+            status(None, Some(side(0o100644, Some("a")))),
+            Some(GitChangeType::Added)
+        );
+        assert_eq!(
+            status(Some(side(0o100644, Some("a"))), None),
+            Some(GitChangeType::Deleted)
+        );
+        assert_eq!(
+            status(
+                Some(side(0o100644, Some("a"))),
+                Some(side(0o100644, Some("a")))
+            ),
+            None
+        );
+        assert_eq!(
+            status(
+                Some(side(0o100644, Some("a"))),
+                Some(side(0o100755, Some("a")))
+            ),
+            Some(GitChangeType::Modified)
+        );
+        assert_eq!(
+            status(Some(side(0o100644, Some("a"))), Some(side(0o100644, None))),
+            Some(GitChangeType::Modified)
+        );
+        assert_eq!(
+            status(
+                Some(side(0o100644, Some("a"))),
+                Some(side(0o120000, Some("b")))
+            ),
+            Some(GitChangeType::TypeChanged)
+        );
+    }
+}
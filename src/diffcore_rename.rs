@@ -0,0 +1,622 @@
+// Detection of renamed and copied files among the file pairs of a diff, based on C Git's
+// implementation:
+// https://github.com/git/git/blob/master/diffcore-rename.c
+// https://github.com/git/git/blob/master/diffcore-delta.c
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    diff_format::is_binary,
+    diffcore::{GitChangeType, GitDiffPair, GitDiffSide},
+    object::{GitObject, GitObjectId, GitObjectType},
+    repo::GitRepo,
+    RustGitError,
+};
+
+/// The score of identical files. Scores are kept as fractions of it, and shown as percentages.
+pub(crate) const MAX_SCORE: u32 = 60000;
+
+/// The minimum similarity of renamed files by default, i.e. 50%.
+pub(crate) const DEFAULT_RENAME_SCORE: u32 = 30000;
+
+/// The maximum number of sources or destinations of inexact renames by default.
+pub(crate) const DEFAULT_RENAME_LIMIT: usize = 1000;
+
+/// The number of best sources remembered for each destination.
+const NUM_CANDIDATE_PER_DST: usize = 4;
+
+/// The modulus of the hashes of the chunks files are split into.
+const HASHBASE: u32 = 107927;
+
+/// How renames and copies are detected.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RenameOptions {
+    /// Whether files copied from changed files are detected too.
+    pub(crate) copies: bool,
+    /// Whether copies may come from unchanged files too.
+    pub(crate) find_copies_harder: bool,
+    /// The minimum similarity of inexact renames and copies, as a fraction of MAX_SCORE.
+    pub(crate) min_score: u32,
+    /// The maximum number of sources or destinations of inexact renames.
+    pub(crate) limit: usize,
+}
+
+impl Default for RenameOptions {
+    fn default() -> RenameOptions {
+        RenameOptions {
+            copies: false,
+            find_copies_harder: false,
+            min_score: DEFAULT_RENAME_SCORE,
+            limit: DEFAULT_RENAME_LIMIT,
+        }
+    }
+}
+
+/// Parses a similarity score like `90%`, `0.9` or `9`, which all mean 90%, as a fraction of
+/// MAX_SCORE. Without a percent sign, the digits are read as a decimal fraction.
+pub(crate) fn parse_rename_score(arg: &str) -> Result<u32, String> {
+    if arg.is_empty() {
+        return Ok(DEFAULT_RENAME_SCORE);
+    }
+    let mut num: u64 = 0;
+    let mut scale: u64 = 1;
+    let mut dot = false;
+    let mut chars = arg.chars();
+    for ch in chars.by_ref() {
+        match ch {
+            '.' if !dot => {
+                scale = 1;
+                dot = true;
+            }
+            '%' => {
+                scale = if dot { scale * 100 } else { 100 };
+                break;
+            }
+            '0'..='9' => {
+                if scale < 100000 {
+                    scale *= 10;
+                    num = num * 10 + u64::from(ch as u8 - b'0');
+                }
+            }
+            _ => return Err(format!("invalid similarity score '{arg}'")),
+        }
+    }
+    if chars.next().is_some() {
+        return Err(format!("invalid similarity score '{arg}'"));
+    }
+    Ok(if num >= scale {
+        MAX_SCORE
+    } else {
+        (u64::from(MAX_SCORE) * num / scale) as u32
+    })
+}
+
+/// Returns the score as a percentage.
+pub(crate) fn similarity_index(score: u32) -> u32 {
+    score * 100 / MAX_SCORE
+}
+
+/// Returns whether the paths have the same file name.
+fn basename_same(src: &str, dst: &str) -> bool {
+    let src_name = src.rsplit('/').next().unwrap_or(src);
+    let dst_name = dst.rsplit('/').next().unwrap_or(dst);
+    src_name == dst_name
+}
+
+fn is_regular(mode: u32) -> bool {
+    mode & 0o170000 == 0o100000
+}
+
+/// Returns whether renames of files of this mode are detected, which excludes trees and
+/// submodules.
+fn is_file(mode: u32) -> bool {
+    is_regular(mode) || mode & 0o170000 == 0o120000
+}
+
+/// Splits the contents into lines, or chunks of 64 bytes for long lines, and returns the number
+/// of bytes in the chunks of each hash, sorted by hash. Carriage returns before line feeds are
+/// ignored in text files.
+fn hash_chunks(contents: &str) -> Vec<(u32, u64)> {
+    let bytes = contents.as_bytes();
+    let is_text = !is_binary(contents);
+    let mut counts = BTreeMap::<u32, u64>::new();
+    let (mut accum1, mut accum2) = (0u32, 0u32);
+    let mut n = 0;
+    for (idx, &byte) in bytes.iter().enumerate() {
+        if is_text && byte == b'\r' && bytes.get(idx + 1) == Some(&b'\n') {
+            continue;
+        }
+        let old_1 = accum1;
+        accum1 = (accum1 << 7) ^ (accum2 >> 25);
+        accum2 = (accum2 << 7) ^ (old_1 >> 25);
+        accum1 = accum1.wrapping_add(u32::from(byte));
+        n += 1;
+        if n < 64 && byte != b'\n' {
+            continue;
+        }
+        let hash = accum1.wrapping_add(accum2.wrapping_mul(0x61)) % HASHBASE;
+        *counts.entry(hash).or_default() += n;
+        n = 0;
+        accum1 = 0;
+        accum2 = 0;
+    }
+    if n > 0 {
+        let hash = accum1.wrapping_add(accum2.wrapping_mul(0x61)) % HASHBASE;
+        *counts.entry(hash).or_default() += n;
+    }
+    counts.into_iter().collect()
+}
+
+/// Returns the number of bytes of the destination found in the source, by comparing the chunks
+/// of both.
+fn count_copied_bytes(src: &[(u32, u64)], dst: &[(u32, u64)]) -> u64 {
+    let mut copied = 0;
+    let mut dst_iter = dst.iter().peekable();
+    for &(hash, src_count) in src {
+        while dst_iter.next_if(|(dst_hash, _)| *dst_hash < hash).is_some() {}
+        if let Some((_, dst_count)) = dst_iter.next_if(|(dst_hash, _)| *dst_hash == hash) {
+            copied += src_count.min(*dst_count);
+        }
+    }
+    copied
+}
+
+/// A file which may be the source or destination of a rename.
+struct RenameFile {
+    side: GitDiffSide,
+    contents: Option<String>,
+    chunks: Option<Vec<(u32, u64)>>,
+}
+
+impl RenameFile {
+    fn new(side: GitDiffSide) -> RenameFile {
+        RenameFile {
+            side,
+            contents: None,
+            chunks: None,
+        }
+    }
+
+    fn contents(
+        &mut self,
+        read: &mut impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+    ) -> Result<&str, RustGitError> {
+        if self.contents.is_none() {
+            self.contents = Some(read(&self.side)?);
+        }
+        Ok(self.contents.as_deref().unwrap_or_default())
+    }
+
+    /// Returns the id of the file, hashing the contents of worktree files.
+    fn id(
+        &mut self,
+        read: &mut impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+    ) -> Result<GitObjectId, RustGitError> {
+        if let Some(id) = &self.side.id {
+            return Ok(id.clone());
+        }
+        let contents = self.contents(read)?.to_string();
+        Ok(GitObject::new(GitObjectType::Blob, contents)?.id)
+    }
+
+    fn chunks(
+        &mut self,
+        read: &mut impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+    ) -> Result<&[(u32, u64)], RustGitError> {
+        if self.chunks.is_none() {
+            self.chunks = Some(hash_chunks(self.contents(read)?));
+        }
+        Ok(self.chunks.as_deref().unwrap_or_default())
+    }
+}
+
+/// A source of renames and copies.
+struct RenameSource {
+    file: RenameFile,
+    /// The number of pairs using the file, counting the pair of the file itself unless it was
+    /// deleted. The last rename of a deleted file is a rename, while the other ones are copies.
+    used: usize,
+}
+
+/// A possible rename of a source to a destination.
+#[derive(Clone, Copy)]
+struct RenameCandidate {
+    score: u32,
+    name_score: bool,
+    dst: usize,
+    src: usize,
+}
+
+/// Returns how similar the files are, as a fraction of MAX_SCORE, or 0 when they're too
+/// different in size to reach the minimum score.
+fn estimate_similarity(
+    src: &mut RenameFile,
+    dst: &mut RenameFile,
+    min_score: u32,
+    read: &mut impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+) -> Result<u32, RustGitError> {
+    if !is_regular(src.side.mode) || !is_regular(dst.side.mode) {
+        return Ok(0);
+    }
+    let src_size = src.contents(read)?.len() as u64;
+    let dst_size = dst.contents(read)?.len() as u64;
+    let max_size = src_size.max(dst_size);
+    let delta_size = max_size - src_size.min(dst_size);
+    if max_size * u64::from(MAX_SCORE - min_score) < delta_size * u64::from(MAX_SCORE) {
+        return Ok(0);
+    }
+    if dst_size == 0 {
+        return Ok(0);
+    }
+    let copied = count_copied_bytes(src.chunks(read)?, dst.chunks(read)?);
+    Ok((copied * u64::from(MAX_SCORE) / max_size) as u32)
+}
+
+/// Keeps the candidate if it's better than the worst of the best candidates of a destination.
+fn record_if_better(best: &mut Vec<RenameCandidate>, candidate: RenameCandidate) {
+    let key = |candidate: &RenameCandidate| (candidate.score, candidate.name_score);
+    if best.len() < NUM_CANDIDATE_PER_DST {
+        if candidate.score > 0 {
+            best.push(candidate);
+        }
+        return;
+    }
+    if let Some(worst) = best
+        .iter_mut()
+        .min_by_key(|best| key(best))
+        .filter(|worst| key(&candidate) > key(worst))
+    {
+        *worst = candidate;
+    }
+}
+
+/// Pairs added files with the deleted files they were renamed from, and with the changed files
+/// they were copied from if copies are detected. The pairs of renamed files are replaced by a
+/// single pair, while copied files stay. The `unchanged` files may be the source of copies too
+/// when looking for copies harder. Contents are read with `read`.
+pub(crate) fn detect_renames(
+    pairs: Vec<GitDiffPair>,
+    unchanged: Vec<GitDiffSide>,
+    options: &RenameOptions,
+    mut read: impl FnMut(&GitDiffSide) -> Result<String, RustGitError>,
+) -> Result<Vec<GitDiffPair>, RustGitError> {
+    let mut dsts = vec![];
+    let mut srcs = vec![];
+    // The source each deleted file is.
+    let mut deleted_srcs = BTreeMap::new();
+    for (idx, pair) in pairs.iter().enumerate() {
+        match (&pair.old, &pair.new, pair.status) {
+            (None, Some(new), _) if is_file(new.mode) => {
+                dsts.push((idx, RenameFile::new(new.clone())));
+            }
+            (Some(old), None, _) if is_file(old.mode) => {
+                deleted_srcs.insert(idx, srcs.len());
+                srcs.push(RenameSource {
+                    file: RenameFile::new(old.clone()),
+                    used: 0,
+                });
+            }
+            (Some(old), Some(_), GitChangeType::Modified | GitChangeType::TypeChanged)
+                if options.copies && is_file(old.mode) =>
+            {
+                srcs.push(RenameSource {
+                    file: RenameFile::new(old.clone()),
+                    used: 1,
+                });
+            }
+            _ => {}
+        }
+    }
+    if options.copies && options.find_copies_harder {
+        let changed = pairs
+            .iter()
+            .filter_map(|pair| pair.old.as_ref().map(|old| old.path.as_str()))
+            .collect::<HashSet<_>>();
+        for side in unchanged {
+            if is_file(side.mode) && !changed.contains(side.path.as_str()) {
+                srcs.push(RenameSource {
+                    file: RenameFile::new(side),
+                    used: 1,
+                });
+            }
+        }
+    }
+    if dsts.is_empty() || srcs.is_empty() {
+        return Ok(pairs);
+    }
+    // Sources are sorted by path, so that earlier ones are preferred.
+    let mut order = (0..srcs.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| srcs[*a].file.side.path.cmp(&srcs[*b].file.side.path));
+
+    // The source and score of each destination found to be a rename or copy.
+    let mut matches = vec![None; dsts.len()];
+
+    // Files with the same contents are paired first, preferring unused sources with the same
+    // file name.
+    let mut src_ids = vec![];
+    for src in srcs.iter_mut() {
+        src_ids.push(src.file.id(&mut read)?);
+    }
+    for (dst_idx, (_, dst)) in dsts.iter_mut().enumerate() {
+        let dst_id = dst.id(&mut read)?;
+        let mut best = None;
+        let mut best_score = 0;
+        for &src_idx in &order {
+            let src = &srcs[src_idx];
+            let (src_mode, dst_mode) = (src.file.side.mode, dst.side.mode);
+            if src_ids[src_idx] != dst_id
+                || ((!is_regular(src_mode) || !is_regular(dst_mode)) && src_mode != dst_mode)
+                || (src.used > 0 && !options.copies)
+            {
+                continue;
+            }
+            let score = u32::from(src.used == 0)
+                + u32::from(basename_same(&src.file.side.path, &dst.side.path));
+            if score > best_score || best.is_none() {
+                best = Some(src_idx);
+                best_score = score;
+                if score == 2 {
+                    break;
+                }
+            }
+        }
+        if let Some(src_idx) = best {
+            srcs[src_idx].used += 1;
+            matches[dst_idx] = Some((src_idx, MAX_SCORE));
+        }
+    }
+
+    // The remaining files are paired by similarity, trying renames before copies.
+    let remaining_dsts = (0..dsts.len())
+        .filter(|idx| matches[*idx].is_none())
+        .collect::<Vec<_>>();
+    let remaining_srcs = order
+        .iter()
+        .copied()
+        .filter(|idx| options.copies || srcs[*idx].used == 0)
+        .collect::<Vec<_>>();
+    let limit = if options.limit == 0 {
+        32767
+    } else {
+        options.limit
+    };
+    if !remaining_dsts.is_empty() && !remaining_srcs.is_empty() {
+        if remaining_dsts.len() > limit
+            || remaining_srcs.len() > limit
+            || remaining_dsts.len() * remaining_srcs.len() > limit * limit
+        {
+            eprintln!(
+                "warning: exhaustive rename detection was skipped due to too many files.
+warning: you may want to set your diff.renameLimit variable to at least {} and retry the command.",
+                remaining_dsts.len().max(remaining_srcs.len())
+            );
+        } else {
+            let mut candidates = vec![];
+            for &dst_idx in &remaining_dsts {
+                let mut best = vec![];
+                for &src_idx in &remaining_srcs {
+                    let score = estimate_similarity(
+                        &mut srcs[src_idx].file,
+                        &mut dsts[dst_idx].1,
+                        options.min_score,
+                        &mut read,
+                    )?;
+                    let candidate = RenameCandidate {
+                        score,
+                        name_score: basename_same(
+                            &srcs[src_idx].file.side.path,
+                            &dsts[dst_idx].1.side.path,
+                        ),
+                        dst: dst_idx,
+                        src: src_idx,
+                    };
+                    record_if_better(&mut best, candidate);
+                }
+                candidates.extend(best);
+            }
+            candidates.sort_by(|a, b| {
+                (b.score, b.name_score)
+                    .cmp(&(a.score, a.name_score))
+                    .then(a.dst.cmp(&b.dst))
+            });
+            for copies in [false, true] {
+                if copies && !options.copies {
+                    break;
+                }
+                for candidate in &candidates {
+                    if candidate.score < options.min_score {
+                        break;
+                    }
+                    if matches[candidate.dst].is_some() || (!copies && srcs[candidate.src].used > 0)
+                    {
+                        continue;
+                    }
+                    srcs[candidate.src].used += 1;
+                    matches[candidate.dst] = Some((candidate.src, candidate.score));
+                }
+            }
+        }
+    }
+
+    let renames = dsts
+        .iter()
+        .zip(&matches)
+        .filter_map(|((pair_idx, _), found)| found.map(|found| (*pair_idx, found)))
+        .collect::<BTreeMap<_, _>>();
+    // Deleted files which were renamed are only shown as the rename.
+    let renamed = deleted_srcs
+        .into_iter()
+        .filter(|(_, src_idx)| srcs[*src_idx].used > 0)
+        .map(|(idx, _)| idx)
+        .collect::<HashSet<_>>();
+    let mut out = vec![];
+    for (idx, mut pair) in pairs.into_iter().enumerate() {
+        if let Some(&(src_idx, score)) = renames.get(&idx) {
+            // Each file using a source counts down its uses, so that only the last one using
+            // a deleted file is a rename.
+            let src = &mut srcs[src_idx];
+            src.used -= 1;
+            pair.old = Some(src.file.side.clone());
+            pair.status = if src.used > 0 {
+                GitChangeType::Copied
+            } else {
+                GitChangeType::Renamed
+            };
+            pair.score = similarity_index(score);
+            out.push(pair);
+        } else if !renamed.contains(&idx) {
+            out.push(pair);
+        }
+    }
+    Ok(out)
+}
+
+impl GitRepo {
+    /// Detects renames and copies among the file pairs if the options are set, reading files
+    /// from the repo. The `unchanged` files are only used when looking for copies harder.
+    pub(crate) fn detect_renames(
+        &self,
+        pairs: Vec<GitDiffPair>,
+        unchanged: Vec<GitDiffSide>,
+        options: Option<&RenameOptions>,
+    ) -> Result<Vec<GitDiffPair>, RustGitError> {
+        match options {
+            Some(options) => {
+                detect_renames(pairs, unchanged, options, |side| self.read_diff_side(side))
+            }
+            None => Ok(pairs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(path: &str, contents: &str) -> GitDiffSide {
+        GitDiffSide {
+            path: String::from(path),
+            mode: 0o100644,
+            id: Some(
+                GitObject::new(GitObjectType::Blob, String::from(contents))
+                    .unwrap()
+                    .id,
+            ),
+        }
+    }
+
+    fn detect(
+        pairs: Vec<GitDiffPair>,
+        files: &[(&str, &str)],
+        options: &RenameOptions,
+    ) -> Vec<(GitChangeType, u32, String)> {
+        let contents = files
+            .iter()
+            .map(|(path, contents)| (path.to_string(), contents.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        detect_renames(pairs, vec![], options, |side| {
+            Ok(contents[&side.path].clone())
+        })
+        .unwrap()
+        .into_iter()
+        .map(|pair| {
+            let name = match (&pair.old, &pair.new) {
+                (Some(old), Some(new)) if old.path != new.path => {
+                    format!("{} -> {}", old.path, new.path)
+                }
+                _ => String::from(pair.path()),
+            };
+            (pair.status, pair.score, name)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn should_parse_rename_scores() {
+        assert_eq!(parse_rename_score(""), Ok(DEFAULT_RENAME_SCORE));
+        assert_eq!(parse_rename_score("90%"), Ok(54000));
+        assert_eq!(parse_rename_score("9"), Ok(54000));
+        assert_eq!(parse_rename_score("0.9"), Ok(54000));
+        assert_eq!(parse_rename_score("100%"), Ok(MAX_SCORE));
+        assert!(parse_rename_score("9x").is_err());
+    }
+
+    #[test]
+    fn should_pair_identical_files_first() {
+        let files = [
+            ("a.txt", "a\n"),
+            ("b.txt", "a\n"),
+            ("dir/a.txt", "a\n"),
+            ("c.txt", "c\n"),
+        ];
+        let pairs = ["a.txt", "b.txt", "c.txt"]
+            .iter()
+            .filter_map(|path| GitDiffPair::between(Some(side(path, files[0].1)), None))
+            .chain(GitDiffPair::between(None, Some(side("dir/a.txt", "a\n"))))
+            .collect();
+        assert_eq!(
+            detect(pairs, &files, &RenameOptions::default()),
+            vec![
+                (GitChangeType::Deleted, 0, String::from("b.txt")),
+                (GitChangeType::Deleted, 0, String::from("c.txt")),
+                (
+                    GitChangeType::Renamed,
+                    100,
+                    String::from("a.txt -> dir/a.txt")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_pair_similar_files_above_minimum_score() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        let files = [("old.txt", old), ("new.txt", new)];
+        let pairs = || {
+            vec![
+                GitDiffPair::between(None, Some(side("new.txt", new))).unwrap(),
+                GitDiffPair::between(Some(side("old.txt", old)), None).unwrap(),
+            ]
+        };
+        assert_eq!(
+            detect(pairs(), &files, &RenameOptions::default()),
+            vec![(
+                GitChangeType::Renamed,
+                81,
+                String::from("old.txt -> new.txt")
+            )]
+        );
+
+        let options = RenameOptions {
+            min_score: parse_rename_score("90%").unwrap(),
+            ..RenameOptions::default()
+        };
+        assert_eq!(detect(pairs(), &files, &options).len(), 2);
+    }
+
+    #[test]
+    fn should_detect_copies_of_changed_files() {
+        let files = [("a.txt", "a\nb\nc\n"), ("b.txt", "a\nb\nc\n")];
+        let pairs = vec![
+            GitDiffPair::between(Some(side("a.txt", "a\nb\nc\n")), Some(side("a.txt", "a\n")))
+                .unwrap(),
+            GitDiffPair::between(None, Some(side("b.txt", "a\nb\nc\n"))).unwrap(),
+        ];
+        assert_eq!(
+            detect(pairs.clone(), &files, &RenameOptions::default())[1].0,
+            GitChangeType::Added
+        );
+        let options = RenameOptions {
+            copies: true,
+            ..RenameOptions::default()
+        };
+        assert_eq!(
+            detect(pairs, &files, &options),
+            vec![
+                (GitChangeType::Modified, 0, String::from("a.txt")),
+                (GitChangeType::Copied, 100, String::from("a.txt -> b.txt")),
+            ]
+        );
+    }
+}
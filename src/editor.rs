@@ -0,0 +1,90 @@
+use std::{env, path::Path, process::Command};
+
+use crate::{config::GitConfig, error::RustGitError};
+
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Picks the editor to use, looking in the same places as C Git:
+/// https://github.com/git/git/blob/master/editor.c
+fn editor_command(config: &GitConfig) -> String {
+    env::var("GIT_EDITOR")
+        .ok()
+        .or_else(|| config.core.editor.clone())
+        .or_else(|| env::var("VISUAL").ok())
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Opens the file at the provided path in the user's editor, and waits for it to be closed.
+pub(crate) fn launch_editor(config: &GitConfig, path: &Path) -> Result<(), RustGitError> {
+    let editor = editor_command(config);
+    // ":" is the conventional way of not editing anything.
+    if editor == ":" {
+        return Ok(());
+    }
+
+    // The editor is run through the shell, as it may include arguments.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(path)
+        .status()
+        .map_err(|_| RustGitError::new(format!("error: unable to start editor '{editor}'")))?;
+
+    if !status.success() {
+        return Err(RustGitError::new(format!(
+            "error: there was a problem with the editor '{editor}'"
+        )));
+    }
+    Ok(())
+}
+
+/// Cleans up a message the way C Git's `stripspace` does: trailing whitespace is removed from
+/// every line, runs of empty lines are collapsed into one, leading and trailing empty lines are
+/// dropped, and a non-empty result ends with a newline. Lines starting with `#` are dropped too
+/// when `strip_comments` is set.
+pub(crate) fn strip_space(message: &str, strip_comments: bool) -> String {
+    let mut result = String::new();
+    let mut pending_empty_line = false;
+
+    for line in message.lines() {
+        if strip_comments && line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            pending_empty_line = !result.is_empty();
+            continue;
+        }
+
+        if pending_empty_line {
+            result.push('\n');
+            pending_empty_line = false;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_strip_space() {
+        assert_eq!(strip_space("", false), "");
+        assert_eq!(strip_space("\n  \n", false), "");
+        assert_eq!(
+            strip_space("\n\nfirst  \n\n\n\nsecond\n# comment\n\n", false),
+            "first\n\nsecond\n# comment\n"
+        );
+        assert_eq!(
+            strip_space("first\n# comment\nsecond", true),
+            "first\nsecond\n"
+        );
+    }
+}
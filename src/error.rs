@@ -12,11 +12,24 @@ use std::{
 #[derive(Debug, PartialEq)]
 pub(crate) struct RustGitError {
     error: String,
+    exit_code: u8,
 }
 
 impl RustGitError {
     pub fn new(s: impl Into<String>) -> RustGitError {
-        RustGitError { error: s.into() }
+        RustGitError {
+            error: s.into(),
+            exit_code: 1,
+        }
+    }
+
+    /// Sets the code the process exits with because of this error, instead of 1.
+    pub fn with_exit_code(self, exit_code: u8) -> RustGitError {
+        RustGitError { exit_code, ..self }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        self.exit_code
     }
 }
 
@@ -0,0 +1,60 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Output information on each ref")]
+#[command(long_about = "
+Iterate over all refs that match <pattern> and show them according to the given <format>, after sorting them
+according to the given set of <key>. If <count> is given, stop after showing that many refs. The interpolated
+values in <format> can optionally be quoted as string literals in the specified host language allowing their
+direct evaluation in that language.
+")]
+pub(crate) struct ForEachRefArgs {
+    /// If one or more patterns are given, only refs are shown that match against at least one pattern, either
+    /// using fnmatch(3) or literally, in the latter case matching completely or from the beginning up to a
+    /// slash.
+    #[clap(value_name("pattern"))]
+    pub patterns: Vec<String>,
+
+    /// By default the command shows all refs that match <pattern>. This option makes it stop after showing that
+    /// many refs.
+    #[arg(long, value_name = "count")]
+    pub count: Option<usize>,
+
+    /// A field name to sort on. Prefix - to sort in descending order of the value. When unspecified, refname is
+    /// used. You may use the --sort=<key> option multiple times, in which case the last key becomes the primary
+    /// key.
+    #[arg(long, value_name = "key")]
+    pub sort: Vec<String>,
+
+    /// A string that interpolates %(fieldname) from a ref being shown and the object it points at. In addition,
+    /// the string literal %% renders as % and %xx - where xx are hex digits - renders as the character with
+    /// hex code xx. For example, %00 interpolates to \0 (NUL), %09 to \t (TAB), and %0a to \n (LF).
+    ///
+    /// When unspecified, <format> defaults to %(objectname) SPC %(objecttype) TAB %(refname).
+    #[arg(long, value_name = "format")]
+    pub format: Option<String>,
+
+    /// Only list refs which points at the given object.
+    #[arg(long, value_name = "object")]
+    pub points_at: Vec<String>,
+
+    /// Only list refs whose tips are reachable from the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "object", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub merged: Option<String>,
+
+    /// Only list refs whose tips are not reachable from the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "object", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub no_merged: Option<String>,
+
+    /// Only list refs which contain the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "object", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub contains: Vec<String>,
+
+    /// Only list refs which don't contain the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "object", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub no_contains: Vec<String>,
+
+    /// Sorting and filtering refs are case insensitive.
+    #[arg(long)]
+    pub ignore_case: bool,
+}
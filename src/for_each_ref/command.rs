@@ -0,0 +1,96 @@
+use crate::{
+    command::GitCommand,
+    ref_filter::{sort_details, sort_refs, PatternMatch, RefFilter, RefFormat, SortKey},
+    repo::RepoState,
+    RustGitError,
+};
+
+use super::cli::ForEachRefArgs;
+
+const DEFAULT_FORMAT: &str = "%(objectname) %(objecttype)\t%(refname)";
+
+pub(crate) struct ForEachRefCommand {
+    patterns: Vec<String>,
+    count: Option<usize>,
+    sort_keys: Vec<SortKey>,
+    format: RefFormat,
+    points_at: Vec<String>,
+    merged: Option<String>,
+    no_merged: Option<String>,
+    contains: Vec<String>,
+    no_contains: Vec<String>,
+    ignore_case: bool,
+}
+
+impl ForEachRefCommand {
+    pub fn new(args: ForEachRefArgs) -> Result<ForEachRefCommand, RustGitError> {
+        let sort_keys = if args.sort.is_empty() {
+            vec!["refname".parse()?]
+        } else {
+            args.sort
+                .iter()
+                .map(|key| key.parse())
+                .collect::<Result<Vec<SortKey>, RustGitError>>()?
+        };
+
+        let format = args.format.as_deref().unwrap_or(DEFAULT_FORMAT).parse()?;
+
+        Ok(ForEachRefCommand {
+            patterns: args.patterns,
+            count: args.count,
+            sort_keys,
+            format,
+            points_at: args.points_at,
+            merged: args.merged,
+            no_merged: args.no_merged,
+            contains: args.contains,
+            no_contains: args.no_contains,
+            ignore_case: args.ignore_case,
+        })
+    }
+}
+
+impl GitCommand for ForEachRefCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+
+        let mut filter = RefFilter::new(self.patterns.clone(), PatternMatch::FullName);
+        filter.ignore_case = self.ignore_case;
+        filter.details = self.format.details().union(sort_details(&self.sort_keys));
+        filter.points_at = self
+            .points_at
+            .iter()
+            .map(|revision| repo.resolve_revision(revision))
+            .collect::<Result<_, _>>()?;
+        filter.merged = self
+            .merged
+            .as_deref()
+            .map(|revision| repo.resolve_commit(revision))
+            .transpose()?;
+        filter.no_merged = self
+            .no_merged
+            .as_deref()
+            .map(|revision| repo.resolve_commit(revision))
+            .transpose()?;
+        filter.contains = self
+            .contains
+            .iter()
+            .map(|revision| repo.resolve_commit(revision))
+            .collect::<Result<_, _>>()?;
+        filter.no_contains = self
+            .no_contains
+            .iter()
+            .map(|revision| repo.resolve_commit(revision))
+            .collect::<Result<_, _>>()?;
+
+        let mut items = filter.apply(&repo, repo.refs.list_refs()?)?;
+        sort_refs(&mut items, &self.sort_keys)?;
+
+        let count = self.count.unwrap_or(items.len());
+        for item in items.iter().take(count) {
+            println!("{}", self.format.format(item)?);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,484 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GitConfig,
+    error::RustGitError,
+    object::{split_commit_signature, GitObjectType},
+    repo::GitRepo,
+    tempfile::create_temp_file,
+};
+
+const PGP_SIGNATURE_MARKERS: [&str; 2] = [
+    "-----BEGIN PGP SIGNATURE-----",
+    "-----BEGIN PGP MESSAGE-----",
+];
+const X509_SIGNATURE_MARKERS: [&str; 1] = ["-----BEGIN SIGNED MESSAGE-----"];
+const SSH_SIGNATURE_MARKERS: [&str; 1] = ["-----BEGIN SSH SIGNATURE-----"];
+
+// The namespace SSH signatures are made in, so that signatures for git objects can't be reused
+// for other purposes (and vice versa).
+const SSH_NAMESPACE: &str = "git";
+
+const DEFAULT_OPENPGP_PROGRAM: &str = "gpg";
+const DEFAULT_X509_PROGRAM: &str = "gpgsm";
+const DEFAULT_SSH_PROGRAM: &str = "ssh-keygen";
+
+const GOOD_SIGNATURE_STATUS: &str = "[GNUPG:] GOODSIG ";
+const SIG_CREATED_STATUS: &str = "[GNUPG:] SIG_CREATED ";
+
+/// The kind of signature created or verified, configured with `gpg.format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SignatureFormat {
+    #[default]
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+impl SignatureFormat {
+    fn markers(&self) -> &'static [&'static str] {
+        match self {
+            SignatureFormat::OpenPgp => &PGP_SIGNATURE_MARKERS,
+            SignatureFormat::X509 => &X509_SIGNATURE_MARKERS,
+            SignatureFormat::Ssh => &SSH_SIGNATURE_MARKERS,
+        }
+    }
+
+    /// Detects the format of a signature from its armor, regardless of what's configured.
+    fn detect(signature: &str) -> Option<SignatureFormat> {
+        [
+            SignatureFormat::OpenPgp,
+            SignatureFormat::X509,
+            SignatureFormat::Ssh,
+        ]
+        .into_iter()
+        .find(|format| {
+            format
+                .markers()
+                .iter()
+                .any(|marker| signature.starts_with(marker))
+        })
+    }
+
+    fn program(&self, config: &GitConfig) -> String {
+        let (program, default) = match self {
+            SignatureFormat::OpenPgp => (&config.gpg.program, DEFAULT_OPENPGP_PROGRAM),
+            SignatureFormat::X509 => (&config.gpg.x509.program, DEFAULT_X509_PROGRAM),
+            SignatureFormat::Ssh => (&config.gpg.ssh.program, DEFAULT_SSH_PROGRAM),
+        };
+        program.clone().unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// Returns the offset of the signature appended to a tag message, if there is one. A signature
+/// must start at the beginning of a line.
+pub(crate) fn signature_start(message: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in message.split_inclusive('\n') {
+        if SignatureFormat::detect(line).is_some() {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The outcome of verifying a signature.
+pub(crate) struct SignatureCheck {
+    /// Whether the signature is good.
+    pub(crate) good: bool,
+    /// The human-readable output of the verification program.
+    pub(crate) output: String,
+    /// The raw machine-readable status of the verification program.
+    pub(crate) status: String,
+}
+
+/// A file in the temporary directory which is removed once dropped.
+struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    fn new(prefix: &str, contents: &str) -> Result<TempFile, RustGitError> {
+        let (mut file, path) = create_temp_file(&env::temp_dir(), prefix, 0o600)?;
+        // The file is removed when dropped, even if writing it fails.
+        let temp_file = TempFile { path };
+        file.write_all(contents.as_bytes())?;
+        Ok(temp_file)
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct ProgramOutput {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+/// Runs a signing program with the provided data on its standard input.
+fn run_program(program: &str, args: &[&str], input: &str) -> Result<ProgramOutput, RustGitError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| RustGitError::new(format!("error: cannot run {program}")))?;
+
+    // The program may exit without reading all of its input (e.g. when a key is missing), so a
+    // failure to write is reported through its exit status instead.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(input.as_bytes());
+    }
+
+    let output = child.wait_with_output()?;
+    Ok(ProgramOutput {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+/// Expands a leading `~/` in a configured path to the user's home directory.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+/// Returns the key to sign with when none is given on the command line: `user.signingKey` if it's
+/// set, otherwise the committer identity for GPG formats.
+fn default_signing_key(config: &GitConfig) -> Result<String, RustGitError> {
+    if let Some(signing_key) = &config.user.signingkey {
+        return Ok(signing_key.clone());
+    }
+
+    match (config.gpg.format, &config.user.name, &config.user.email) {
+        (SignatureFormat::Ssh, _, _) => Err(RustGitError::new(
+            "fatal: either user.signingkey or gpg.ssh.defaultKeyCommand needs to be configured",
+        )),
+        (_, Some(name), Some(email)) => Ok(format!("{name} <{email}>")),
+        _ => Err(RustGitError::new("fatal: no signing key configured")),
+    }
+}
+
+/// Resolves the key to sign with from a `-S[<keyid>]`-style option, where an empty key id means
+/// the default key. Without the option, the default key is used if `sign_by_default` is set, e.g.
+/// by `commit.gpgSign`.
+pub(crate) fn resolve_signing_key(
+    config: &GitConfig,
+    key_arg: Option<&str>,
+    sign_by_default: bool,
+) -> Result<Option<String>, RustGitError> {
+    match key_arg {
+        Some(key) if !key.is_empty() => Ok(Some(key.to_string())),
+        Some(_) => default_signing_key(config).map(Some),
+        None if sign_by_default => default_signing_key(config).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Signs the payload with the provided key, using the configured signature format, and returns
+/// the ASCII-armored signature.
+pub(crate) fn sign_buffer(
+    config: &GitConfig,
+    payload: &str,
+    signing_key: &str,
+) -> Result<String, RustGitError> {
+    let format = config.gpg.format;
+    let program = format.program(config);
+
+    let output = match format {
+        SignatureFormat::OpenPgp | SignatureFormat::X509 => {
+            let output = run_program(&program, &["--status-fd=2", "-bsau", signing_key], payload)?;
+            // gpg may exit successfully without having signed anything, so also check its status.
+            let created = output
+                .stderr
+                .lines()
+                .any(|line| line.starts_with(SIG_CREATED_STATUS));
+            if !output.success || !created {
+                return Err(RustGitError::new(format!(
+                    "{}error: gpg failed to sign the data",
+                    output.stderr
+                )));
+            }
+            output
+        }
+        SignatureFormat::Ssh => {
+            // A literal public key is signed with the matching private key from the ssh-agent.
+            let literal_key = signing_key
+                .strip_prefix("key::")
+                .or_else(|| signing_key.starts_with("ssh-").then_some(signing_key));
+            let key_file = literal_key
+                .map(|key| TempFile::new(".git_signing_key_tmp", key))
+                .transpose()?;
+
+            let key_path = match &key_file {
+                Some(key_file) => key_file.path().to_string_lossy().to_string(),
+                None => expand_home(signing_key),
+            };
+            let mut args = vec!["-Y", "sign", "-n", SSH_NAMESPACE, "-f", key_path.as_str()];
+            if key_file.is_some() {
+                args.push("-U");
+            }
+
+            let output = run_program(&program, &args, payload)?;
+            if !output.success {
+                let message = if output.stderr.contains("usage:") {
+                    String::from("ssh-keygen -Y sign is needed for ssh signing (available in openssh version 8.2p1+)")
+                } else {
+                    output.stderr.trim_end().to_string()
+                };
+                return Err(RustGitError::new(format!(
+                    "error: {message}\nerror: failed to sign the data"
+                )));
+            }
+            output
+        }
+    };
+
+    Ok(output.stdout)
+}
+
+/// Verifies a signature over the payload. The signature format is detected from the signature
+/// itself, and falls back to the configured format.
+pub(crate) fn verify_signed_buffer(
+    config: &GitConfig,
+    payload: &str,
+    signature: &str,
+) -> Result<SignatureCheck, RustGitError> {
+    let format = SignatureFormat::detect(signature).unwrap_or(config.gpg.format);
+    let program = format.program(config);
+    let signature_file = TempFile::new(".git_vtag_tmp", signature)?;
+    let signature_path = signature_file.path().to_string_lossy().to_string();
+
+    match format {
+        SignatureFormat::OpenPgp | SignatureFormat::X509 => {
+            let output = run_program(
+                &program,
+                &[
+                    "--keyid-format=long",
+                    "--status-fd=1",
+                    "--verify",
+                    &signature_path,
+                    "-",
+                ],
+                payload,
+            )?;
+            let good = output
+                .stdout
+                .lines()
+                .any(|line| line.starts_with(GOOD_SIGNATURE_STATUS));
+            Ok(SignatureCheck {
+                good: output.success && good,
+                output: output.stderr,
+                status: output.stdout,
+            })
+        }
+        SignatureFormat::Ssh => {
+            verify_ssh_signed_buffer(config, &program, payload, &signature_path)
+        }
+    }
+}
+
+fn verify_ssh_signed_buffer(
+    config: &GitConfig,
+    program: &str,
+    payload: &str,
+    signature_path: &str,
+) -> Result<SignatureCheck, RustGitError> {
+    let allowed_signers = config
+        .gpg
+        .ssh
+        .allowedsignersfile
+        .as_deref()
+        .map(expand_home)
+        .filter(|path| Path::new(path).exists())
+        .ok_or_else(|| {
+            RustGitError::new(
+                "error: gpg.ssh.allowedSignersFile needs to be configured and exist for ssh signature verification",
+            )
+        })?;
+
+    let principals = run_program(
+        program,
+        &[
+            "-Y",
+            "find-principals",
+            "-f",
+            &allowed_signers,
+            "-s",
+            signature_path,
+        ],
+        "",
+    )?;
+
+    if !principals.success || principals.stdout.trim().is_empty() {
+        // Without a matching principal, the signature itself can still be checked and reported,
+        // but the key it was made with isn't trusted, so it never counts as good.
+        let output = run_program(
+            program,
+            &[
+                "-Y",
+                "check-novalidate",
+                "-n",
+                SSH_NAMESPACE,
+                "-s",
+                signature_path,
+            ],
+            payload,
+        )?;
+        let output_text = format!("{}{}No principal matched.\n", output.stdout, output.stderr);
+        return Ok(SignatureCheck {
+            good: false,
+            status: output_text.clone(),
+            output: output_text,
+        });
+    }
+
+    let mut last_output = String::new();
+    for principal in principals.stdout.lines().filter(|line| !line.is_empty()) {
+        let output = run_program(
+            program,
+            &[
+                "-Y",
+                "verify",
+                "-n",
+                SSH_NAMESPACE,
+                "-f",
+                &allowed_signers,
+                "-I",
+                principal,
+                "-s",
+                signature_path,
+            ],
+            payload,
+        )?;
+        last_output = format!("{}{}", output.stdout, output.stderr);
+        if output.success {
+            return Ok(SignatureCheck {
+                good: true,
+                status: last_output.clone(),
+                output: last_output,
+            });
+        }
+    }
+
+    Ok(SignatureCheck {
+        good: false,
+        status: last_output.clone(),
+        output: last_output,
+    })
+}
+
+impl GitRepo {
+    /// Verifies the signature of the named commit or tag, which must be of the expected type. If
+    /// `verbose` is set, the signed payload is printed. The output of the verification program, or
+    /// its raw status if `raw` is set, is printed to stderr. Returns whether the signature is good.
+    pub(crate) fn verify_object_signature(
+        &self,
+        name: &str,
+        expected_type: GitObjectType,
+        verbose: bool,
+        raw: bool,
+    ) -> Result<bool, RustGitError> {
+        let object = match self.resolve_revision(name) {
+            Ok(id) => self.obj_store.read_object(&id)?,
+            Err(_) => None,
+        };
+        let object = match object {
+            Some(object) => object,
+            None => {
+                eprintln!("error: {expected_type} '{name}' not found.");
+                return Ok(false);
+            }
+        };
+
+        if object.header.obj_type != expected_type {
+            eprintln!(
+                "error: {name}: cannot verify a non-{expected_type} object of type {}.",
+                object.header.obj_type
+            );
+            return Ok(false);
+        }
+
+        let (payload, signature) = match expected_type {
+            GitObjectType::Tag => match signature_start(&object.content) {
+                Some(start) => (
+                    object.content[..start].to_string(),
+                    Some(object.content[start..].to_string()),
+                ),
+                None => (object.content.clone(), None),
+            },
+            _ => split_commit_signature(&object.content),
+        };
+
+        if verbose {
+            print!("{payload}");
+        }
+
+        let signature = match signature {
+            Some(signature) => signature,
+            // As in C Git, an unsigned commit fails verification silently.
+            None if expected_type == GitObjectType::Commit => return Ok(false),
+            None => {
+                eprintln!("error: no signature found");
+                return Ok(false);
+            }
+        };
+
+        let check = verify_signed_buffer(&self.config, &payload, &signature)?;
+        eprint!("{}", if raw { &check.status } else { &check.output });
+
+        Ok(check.good)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_signature_at_start_of_line() {
+        let message =
+            "Release\n\nNot a -----BEGIN PGP SIGNATURE-----\n-----BEGIN SSH SIGNATURE-----\nabc\n";
+        assert_eq!(signature_start(message), Some(45));
+        assert_eq!(&message[45..48], "---");
+        assert_eq!(signature_start("Release\n"), None);
+    }
+
+    #[test]
+    fn should_detect_signature_format() {
+        assert_eq!(
+            SignatureFormat::detect("-----BEGIN SSH SIGNATURE-----\n"),
+            Some(SignatureFormat::Ssh)
+        );
+        assert_eq!(
+            SignatureFormat::detect("-----BEGIN PGP SIGNATURE-----\n"),
+            Some(SignatureFormat::OpenPgp)
+        );
+        assert_eq!(
+            SignatureFormat::detect("-----BEGIN SIGNED MESSAGE-----\n"),
+            Some(SignatureFormat::X509)
+        );
+        assert_eq!(SignatureFormat::detect("Release\n"), None);
+    }
+}
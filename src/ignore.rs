@@ -0,0 +1,493 @@
+// Ignore rules read from .gitignore files, $GIT_DIR/info/exclude and core.excludesFile,
+// based on C Git's implementation:
+// https://github.com/git/git/blob/master/dir.c
+
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    repo::{GitRepo, GitRepoPath},
+    wildmatch::{wildmatch, WildmatchFlags},
+    RustGitError,
+};
+
+/// Name of the per-directory files holding ignore patterns.
+pub(crate) const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+/// A single pattern of an ignore file.
+#[derive(Debug)]
+pub(crate) struct GitIgnorePattern {
+    /// The pattern as it was written, used when reporting which pattern matched.
+    pub(crate) original: String,
+    /// Where the pattern was read from, e.g. `dir/.gitignore`.
+    pub(crate) source: String,
+    /// The 1-based line of the pattern in its source.
+    pub(crate) line: usize,
+    /// Whether the pattern starts with '!', re-including paths excluded by earlier patterns.
+    pub(crate) negative: bool,
+    /// The glob, without the '!' prefix, leading '/' or trailing '/'.
+    pattern: String,
+    /// Whether the pattern ends with '/', so it only matches directories.
+    must_be_dir: bool,
+    /// Whether the pattern has no '/', so it's matched against the basename of paths at any
+    /// depth rather than against the path relative to `base`.
+    basename_only: bool,
+    /// The directory holding the source of the pattern, relative to the root of the repo and
+    /// with a trailing '/', or empty for the root.
+    base: String,
+}
+
+impl GitIgnorePattern {
+    /// Parses a single line of an ignore file, returning None for blank lines and comments.
+    pub(crate) fn parse(
+        line: &str,
+        source: &str,
+        line_number: usize,
+        base: &str,
+    ) -> Option<GitIgnorePattern> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.starts_with('#') {
+            return None;
+        }
+        let original = trim_trailing_spaces(line);
+        if original.is_empty() {
+            return None;
+        }
+
+        let (negative, pattern) = match original.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, original),
+        };
+        let (must_be_dir, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let basename_only = !pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+        Some(GitIgnorePattern {
+            original: String::from(original),
+            source: String::from(source),
+            line: line_number,
+            negative,
+            pattern: String::from(pattern),
+            must_be_dir,
+            basename_only,
+            base: String::from(base),
+        })
+    }
+
+    /// Returns whether the pattern matches the provided path, which is relative to the root of
+    /// the repo.
+    pub(crate) fn matches(&self, path: &str, is_dir: bool, casefold: bool) -> bool {
+        if self.must_be_dir && !is_dir {
+            return false;
+        }
+        let flags = WildmatchFlags {
+            pathname: true,
+            casefold,
+        };
+
+        if self.basename_only {
+            let basename = path.rsplit('/').next().unwrap_or(path);
+            return wildmatch(&self.pattern, basename, flags);
+        }
+
+        let in_base = path.len() >= self.base.len()
+            && path.is_char_boundary(self.base.len())
+            && if casefold {
+                path[..self.base.len()].eq_ignore_ascii_case(&self.base)
+            } else {
+                path.starts_with(&self.base)
+            };
+        in_base && wildmatch(&self.pattern, &path[self.base.len()..], flags)
+    }
+}
+
+/// Removes the trailing spaces of a line, unless they are escaped with a backslash.
+fn trim_trailing_spaces(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let mut end = bytes.len();
+    while end > 0 && bytes[end - 1] == b' ' {
+        // Count the backslashes before the space, an odd number escapes it.
+        let backslashes = bytes[..end - 1]
+            .iter()
+            .rev()
+            .take_while(|&&b| b == b'\\')
+            .count();
+        if backslashes % 2 == 1 {
+            break;
+        }
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Parses all the patterns of an ignore file.
+fn parse_patterns(contents: &str, source: &str, base: &str) -> Vec<GitIgnorePattern> {
+    contents
+        .split('\n')
+        .enumerate()
+        .filter_map(|(idx, line)| GitIgnorePattern::parse(line, source, idx + 1, base))
+        .collect()
+}
+
+/// Returns the last pattern of the list which matches the path, since later patterns override
+/// earlier ones.
+fn last_matching<'a>(
+    patterns: &'a [GitIgnorePattern],
+    path: &str,
+    is_dir: bool,
+    casefold: bool,
+) -> Option<&'a GitIgnorePattern> {
+    patterns
+        .iter()
+        .rev()
+        .find(|pattern| pattern.matches(path, is_dir, casefold))
+}
+
+/// Returns the directories containing the path, deepest first, each with a trailing '/'
+/// except for the root which is empty.
+pub(crate) fn parent_dirs(path: &str) -> impl Iterator<Item = &str> {
+    path.rmatch_indices('/')
+        .map(move |(idx, _)| &path[..=idx])
+        .chain(std::iter::once(""))
+}
+
+/// The ignore rules of a worktree. From highest to lowest precedence, patterns come from the
+/// command line, the per-directory files (deeper directories first), and then the exclude
+/// files, the last added first.
+pub(crate) struct GitIgnore {
+    root_dir: PathBuf,
+    casefold: bool,
+    command_line: Vec<GitIgnorePattern>,
+    /// Name of the per-directory files read, if any.
+    per_dir_file_name: Option<String>,
+    /// The patterns of the per-directory files, keyed by their directory, read when first needed.
+    per_dir: HashMap<String, Vec<GitIgnorePattern>>,
+    files: Vec<Vec<GitIgnorePattern>>,
+}
+
+impl GitIgnore {
+    /// Creates ignore rules for the worktree at the provided root, without any patterns.
+    pub(crate) fn new(root_dir: &Path, casefold: bool) -> GitIgnore {
+        GitIgnore {
+            root_dir: root_dir.to_path_buf(),
+            casefold,
+            command_line: vec![],
+            per_dir_file_name: None,
+            per_dir: HashMap::new(),
+            files: vec![],
+        }
+    }
+
+    /// Adds a pattern given on the command line.
+    pub(crate) fn add_command_line_pattern(&mut self, pattern: &str) {
+        let line = self.command_line.len() + 1;
+        self.command_line.extend(GitIgnorePattern::parse(
+            pattern,
+            "--exclude option",
+            line,
+            "",
+        ));
+    }
+
+    /// Adds the patterns of the provided file, failing if it can't be read.
+    pub(crate) fn add_exclude_file(
+        &mut self,
+        path: &Path,
+        source: &str,
+    ) -> Result<(), RustGitError> {
+        let contents = fs::read_to_string(path).map_err(|_| {
+            RustGitError::new(format!(
+                "fatal: cannot use {} as an exclude file",
+                path.display()
+            ))
+        })?;
+        self.files.push(parse_patterns(&contents, source, ""));
+        Ok(())
+    }
+
+    /// Reads patterns from the files with the provided name in each directory of the worktree.
+    pub(crate) fn set_per_directory_file(&mut self, file_name: &str) {
+        self.per_dir_file_name = Some(String::from(file_name));
+        self.per_dir.clear();
+    }
+
+    /// Reads the per-directory files of the directories containing the path, unless they were
+    /// already read.
+    fn load_parent_dirs(&mut self, path: &str) -> Result<(), RustGitError> {
+        let Some(file_name) = &self.per_dir_file_name else {
+            return Ok(());
+        };
+        for dir in parent_dirs(path) {
+            if self.per_dir.contains_key(dir) {
+                continue;
+            }
+            let source = format!("{dir}{file_name}");
+            let patterns = match fs::read_to_string(self.root_dir.join(&source)) {
+                Ok(contents) => parse_patterns(&contents, &source, dir),
+                Err(_) => vec![],
+            };
+            self.per_dir.insert(String::from(dir), patterns);
+        }
+        Ok(())
+    }
+
+    /// Returns the pattern deciding whether the path is ignored, considering only the path
+    /// itself and not its parent directories.
+    fn find_pattern(&self, path: &str, is_dir: bool) -> Option<&GitIgnorePattern> {
+        last_matching(&self.command_line, path, is_dir, self.casefold)
+            .or_else(|| {
+                parent_dirs(path).find_map(|dir| {
+                    last_matching(self.per_dir.get(dir)?, path, is_dir, self.casefold)
+                })
+            })
+            .or_else(|| {
+                self.files
+                    .iter()
+                    .rev()
+                    .find_map(|patterns| last_matching(patterns, path, is_dir, self.casefold))
+            })
+    }
+
+    /// Returns the pattern deciding whether the path, relative to the root of the repo, is
+    /// ignored. A negative pattern means the path was re-included. Paths inside an ignored
+    /// directory are always ignored, as git doesn't look inside ignored directories.
+    pub(crate) fn matching_pattern(
+        &mut self,
+        path: &str,
+        is_dir: bool,
+    ) -> Result<Option<&GitIgnorePattern>, RustGitError> {
+        self.load_parent_dirs(path)?;
+
+        let ignored_parent = path
+            .match_indices('/')
+            .map(|(idx, _)| &path[..idx])
+            .find(|dir| {
+                self.find_pattern(dir, true)
+                    .is_some_and(|pattern| !pattern.negative)
+            });
+        Ok(match ignored_parent {
+            Some(dir) => self.find_pattern(dir, true),
+            None => self.find_pattern(path, is_dir),
+        })
+    }
+
+    /// Returns whether the path, relative to the root of the repo, is ignored.
+    pub(crate) fn is_ignored(&mut self, path: &str, is_dir: bool) -> Result<bool, RustGitError> {
+        Ok(self
+            .matching_pattern(path, is_dir)?
+            .is_some_and(|pattern| !pattern.negative))
+    }
+}
+
+/// The files of the worktree which aren't in the index.
+#[derive(Default)]
+pub(crate) struct GitUntrackedFiles {
+    /// Untracked files which aren't ignored, sorted by path.
+    pub(crate) others: Vec<GitRepoPath>,
+    /// Untracked files which are ignored, sorted by path. Only collected when requested.
+    pub(crate) ignored: Vec<GitRepoPath>,
+}
+
+impl GitRepo {
+    /// Returns the ignore rules git uses by default: the `.gitignore` files of the worktree,
+    /// `info/exclude` in the git directory and the file set by core.excludesFile, which
+    /// defaults to `$XDG_CONFIG_HOME/git/ignore`.
+    pub(crate) fn standard_ignore(&self) -> Result<GitIgnore, RustGitError> {
+        let mut ignore = GitIgnore::new(&self.root_dir, self.config.core.ignorecase);
+
+        let excludes_file = match &self.config.core.excludesfile {
+            Some(path) => match path.strip_prefix("~/") {
+                Some(rest) => env::var_os("HOME").map(|home| PathBuf::from(home).join(rest)),
+                None => Some(PathBuf::from(path)),
+            },
+            None => env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+                .map(|dir| dir.join("git").join("ignore")),
+        };
+        if let Some(path) = excludes_file.filter(|path| path.is_file()) {
+            ignore.add_exclude_file(&path, &path.display().to_string())?;
+        }
+
+        let info_exclude = self.git_dir.join("info").join("exclude");
+        if info_exclude.is_file() {
+            let source = info_exclude
+                .strip_prefix(&self.root_dir)
+                .unwrap_or(&info_exclude)
+                .display()
+                .to_string();
+            ignore.add_exclude_file(&info_exclude, &source)?;
+        }
+
+        ignore.set_per_directory_file(GITIGNORE_FILE_NAME);
+        Ok(ignore)
+    }
+
+    /// Returns whether the index has entries for the path or for files under it.
+    pub(crate) fn is_tracked(&self, path: &str) -> bool {
+        self.index.iter_entries().any(|entry| {
+            let entry_path = entry.path_name.as_string();
+            entry_path == path
+                || path.is_empty()
+                || entry_path
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Walks the worktree to find the files which aren't in the index. Ignored directories are
+    /// only walked when `collect_ignored` is set.
+    pub(crate) fn untracked_files(
+        &self,
+        ignore: &mut GitIgnore,
+        collect_ignored: bool,
+    ) -> Result<GitUntrackedFiles, RustGitError> {
+        let tracked = self
+            .index
+            .iter_entries()
+            .map(|entry| entry.path_name.as_string())
+            .collect::<HashSet<_>>();
+        let mut untracked = GitUntrackedFiles::default();
+        self.walk_untracked("", false, &tracked, ignore, collect_ignored, &mut untracked)?;
+        untracked.others.sort();
+        untracked.ignored.sort();
+        Ok(untracked)
+    }
+
+    fn walk_untracked(
+        &self,
+        dir: &str,
+        dir_ignored: bool,
+        tracked: &HashSet<String>,
+        ignore: &mut GitIgnore,
+        collect_ignored: bool,
+        untracked: &mut GitUntrackedFiles,
+    ) -> Result<(), RustGitError> {
+        for dir_entry in fs::read_dir(self.root_dir.join(dir))? {
+            let dir_entry = dir_entry?;
+            let Some(name) = dir_entry.file_name().to_str().map(String::from) else {
+                continue;
+            };
+            if name == ".git" {
+                continue;
+            }
+            let path = format!("{dir}{name}");
+            let is_dir = dir_entry.file_type()?.is_dir();
+            let ignored = dir_ignored || ignore.is_ignored(&path, is_dir)?;
+
+            if is_dir {
+                if !ignored || collect_ignored {
+                    let dir = format!("{path}/");
+                    self.walk_untracked(
+                        &dir,
+                        ignored,
+                        tracked,
+                        ignore,
+                        collect_ignored,
+                        untracked,
+                    )?;
+                }
+            } else if !tracked.contains(&path) {
+                let repo_path = GitRepoPath::deserialize(path.as_bytes())?;
+                if !ignored {
+                    untracked.others.push(repo_path);
+                } else if collect_ignored {
+                    untracked.ignored.push(repo_path);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(line: &str, base: &str) -> GitIgnorePattern {
+        GitIgnorePattern::parse(line, ".gitignore", 1, base).unwrap()
+    }
+
+    #[test]
+    fn should_skip_comments_and_blank_lines() {
+        let patterns = parse_patterns("# comment\n\n   \n\\#hash\nfoo \\ \n", ".gitignore", "");
+        let originals = patterns
+            .iter()
+            .map(|pattern| pattern.original.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(originals, vec!["\\#hash", "foo \\ "]);
+        assert_eq!(patterns[1].line, 5);
+    }
+
+    #[test]
+    fn should_match_basename_at_any_depth_unless_anchored() {
+        assert!(pattern("*.o", "").matches("a/b/c.o", false, false));
+        assert!(pattern("c.o", "").matches("a/b/c.o", false, false));
+        assert!(!pattern("/c.o", "").matches("a/c.o", false, false));
+        assert!(pattern("/c.o", "").matches("c.o", false, false));
+        assert!(pattern("a/c.o", "").matches("a/c.o", false, false));
+        assert!(!pattern("a/c.o", "").matches("b/a/c.o", false, false));
+        assert!(pattern("c.o", "sub/").matches("x/c.o", false, false));
+        assert!(pattern("/c.o", "sub/").matches("sub/c.o", false, false));
+        assert!(!pattern("/c.o", "sub/").matches("c.o", false, false));
+        assert!(!pattern("*.O", "").matches("c.o", false, false));
+        assert!(pattern("*.O", "").matches("c.o", false, true));
+    }
+
+    #[test]
+    fn should_match_directories_and_double_asterisks() {
+        assert!(pattern("build/", "").matches("a/build", true, false));
+        assert!(!pattern("build/", "").matches("a/build", false, false));
+        assert!(pattern("**/foo", "").matches("a/b/foo", false, false));
+        assert!(pattern("**/foo", "").matches("foo", false, false));
+        assert!(pattern("a/**/b", "").matches("a/b", false, false));
+        assert!(pattern("a/**/b", "").matches("a/x/y/b", false, false));
+        assert!(pattern("a/**", "").matches("a/x/y", false, false));
+        assert!(!pattern("a/*", "").matches("a/x/y", false, false));
+    }
+
+    #[test]
+    fn should_prefer_later_and_deeper_patterns() {
+        let temp_dir = std::env::temp_dir().join(format!("rust-git-ignore-{}", std::process::id()));
+        fs::create_dir_all(temp_dir.join("sub")).unwrap();
+        fs::write(temp_dir.join(".gitignore"), "*.log\n!keep.log\nout/\n").unwrap();
+        fs::write(temp_dir.join("sub/.gitignore"), "!*.log\n").unwrap();
+
+        let mut ignore = GitIgnore::new(&temp_dir, false);
+        ignore.set_per_directory_file(GITIGNORE_FILE_NAME);
+        assert!(ignore.is_ignored("a.log", false).unwrap());
+        assert!(!ignore.is_ignored("keep.log", false).unwrap());
+        assert!(!ignore.is_ignored("sub/a.log", false).unwrap());
+        assert_eq!(
+            ignore
+                .matching_pattern("sub/a.log", false)
+                .unwrap()
+                .unwrap()
+                .source,
+            "sub/.gitignore"
+        );
+        // Files can't be re-included from inside an ignored directory.
+        assert!(ignore.is_ignored("out/keep.log", false).unwrap());
+        assert_eq!(
+            ignore
+                .matching_pattern("out/keep.log", false)
+                .unwrap()
+                .unwrap()
+                .original,
+            "out/"
+        );
+
+        ignore.add_command_line_pattern("a.log");
+        ignore.add_command_line_pattern("!a.log");
+        assert!(!ignore.is_ignored("a.log", false).unwrap());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
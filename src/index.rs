@@ -1,15 +1,30 @@
-use crate::{error::RustGitError, hash::Hasher, object::GitObjectId, repo::GitRepoPath};
+use crate::{
+    cache_tree::GitCacheTree,
+    config::{CoreConfig, GitConfig},
+    error::RustGitError,
+    hash::Hasher,
+    lockfile::GitLockFile,
+    object::GitObjectId,
+    repo::GitRepoPath,
+    resolve_undo::GitResolveUndo,
+};
 use sha1::{Digest, Sha1};
 /// All binary numbers are in network byte order.
 use std::{
+    env,
     fmt::Display,
-    fs::{self, File, Metadata},
-    io::Write,
+    fs::{self, Metadata},
     os::unix::fs::{MetadataExt, PermissionsExt},
-    path::{Path, PathBuf},
+    path::Path,
 };
 
 const INDEX_HEADER_SIGNATURE: &[u8; 4] = b"DIRC";
+const CACHE_TREE_SIGNATURE: &[u8; 4] = b"TREE";
+const RESOLVE_UNDO_SIGNATURE: &[u8; 4] = b"REUC";
+// These extensions describe the layout of the index file itself, so they're stale once the index
+// is written again.
+const END_OF_INDEX_ENTRY_SIGNATURE: &[u8; 4] = b"EOIE";
+const INDEX_ENTRY_OFFSET_TABLE_SIGNATURE: &[u8; 4] = b"IEOT";
 
 fn as_u32_be(array: &[u8; 4]) -> u32 {
     ((array[0] as u32) << 24)
@@ -22,30 +37,126 @@ fn as_u16_be(array: &[u8; 2]) -> u16 {
     ((array[0] as u16) << 8) + ((array[1] as u16) << 0)
 }
 
-const DEFAULT_INDEX_NAME: &str = "index";
+pub(crate) const DEFAULT_INDEX_NAME: &str = "index";
 
-// Only supporting V2 for now.
-#[derive(Debug, PartialEq)]
+/// Encodes a number in the variable-length format used for v4 path prefixes, where each byte
+/// holds 7 bits, most significant first, and the MSB marks that another byte follows. One is
+/// subtracted from all but the last byte so every number has a single encoding.
+fn encode_varint(mut value: usize) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    while value >> 7 != 0 {
+        value = (value >> 7) - 1;
+        bytes.push(0x80 | (value & 0x7F) as u8);
+    }
+    bytes.reverse();
+    bytes
+}
+
+/// Decodes a number encoded by `encode_varint`, returning it with the number of bytes read.
+fn decode_varint(bytes: &[u8]) -> Result<(usize, usize), RustGitError> {
+    let mut bytes_iter = bytes.iter();
+    let mut next = || {
+        bytes_iter
+            .next()
+            .ok_or_else(|| RustGitError::new("truncated path prefix in index entry"))
+    };
+
+    let mut byte = next()?;
+    let mut value = (byte & 0x7F) as usize;
+    let mut processed_bytes = 1;
+    while byte & 0x80 != 0 {
+        byte = next()?;
+        value = ((value + 1) << 7) + (byte & 0x7F) as usize;
+        processed_bytes += 1;
+    }
+    Ok((value, processed_bytes))
+}
+
+/// Returns whether one of the provided paths is a leading directory of the other.
+fn is_file_directory_conflict(path: &GitRepoPath, other: &GitRepoPath) -> bool {
+    let (path, other) = (path.as_path_buf(), other.as_path_buf());
+    path != other && (path.starts_with(&other) || other.starts_with(&path))
+}
+
+/// Version 3 adds extended flags to entries, and version 4 prefix-compresses their path names.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub(crate) enum GitIndexVersion {
+    #[default]
     V2,
+    V3,
+    V4,
+}
+
+impl Display for GitIndexVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.number())
+    }
 }
 
 impl GitIndexVersion {
     pub(crate) fn serialize(index_version: &GitIndexVersion) -> [u8; 4] {
-        match index_version {
-            GitIndexVersion::V2 => (2 as u32).to_be_bytes(),
-        }
+        index_version.number().to_be_bytes()
     }
 
     pub(crate) fn deserialize(bytes: &[u8]) -> Result<GitIndexVersion, RustGitError> {
         let bytes: &[u8; 4] = bytes.try_into()?;
         match as_u32_be(bytes) {
-            2 => Ok(GitIndexVersion::V2),
+            version @ 2..=4 => Ok(GitIndexVersion::from_number(version).unwrap()),
             other => Err(RustGitError::new(format!(
                 "unsupported index version {other}"
             ))),
         }
     }
+
+    /// Returns the version with the provided number, if it's supported.
+    pub(crate) fn from_number(number: u32) -> Option<GitIndexVersion> {
+        match number {
+            2 => Some(GitIndexVersion::V2),
+            3 => Some(GitIndexVersion::V3),
+            4 => Some(GitIndexVersion::V4),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn number(&self) -> u32 {
+        match self {
+            GitIndexVersion::V2 => 2,
+            GitIndexVersion::V3 => 3,
+            GitIndexVersion::V4 => 4,
+        }
+    }
+
+    /// Returns the version new indexes are written in, as set by GIT_INDEX_VERSION or the
+    /// "index.version" configuration. Invalid values fall back to version 3, which is written as
+    /// version 2 unless entries need extended flags.
+    pub(crate) fn default_for(config: &GitConfig) -> GitIndexVersion {
+        let (source, version) = match env::var("GIT_INDEX_VERSION") {
+            Ok(version) => ("GIT_INDEX_VERSION", version.parse().ok()),
+            Err(_) => match config.index.version {
+                Some(version) => ("index.version", Some(version)),
+                None => return GitIndexVersion::V3,
+            },
+        };
+
+        version
+            .and_then(GitIndexVersion::from_number)
+            .unwrap_or_else(|| {
+                eprintln!("warning: {source} set, but the value is invalid.\nUsing version 3");
+                GitIndexVersion::V3
+            })
+    }
+
+    /// Returns the version an index with the provided entries is actually written in. Versions 2
+    /// and 3 only differ in extended flags, so the oldest version which can hold the entries is used.
+    fn for_entries(&self, entries: &[GitIndexEntry]) -> GitIndexVersion {
+        match self {
+            GitIndexVersion::V4 => GitIndexVersion::V4,
+            _ if entries.iter().any(|entry| !entry.extended_flags.is_empty()) => {
+                GitIndexVersion::V3
+            }
+            _ => GitIndexVersion::V2,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -78,7 +189,8 @@ impl GitIndexHeader {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Timestamps are ordered by seconds, then nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct GitIndexTimestamp {
     pub seconds: u32,
     pub nanoseconds: u32,
@@ -91,6 +203,22 @@ impl Display for GitIndexTimestamp {
 }
 
 impl GitIndexTimestamp {
+    /// Returns the time the file with the provided metadata was last modified.
+    pub(crate) fn mtime(metadata: &Metadata) -> GitIndexTimestamp {
+        GitIndexTimestamp {
+            seconds: metadata.mtime() as u32,
+            nanoseconds: metadata.mtime_nsec() as u32,
+        }
+    }
+
+    /// Returns the time the metadata of the file with the provided metadata last changed.
+    pub(crate) fn ctime(metadata: &Metadata) -> GitIndexTimestamp {
+        GitIndexTimestamp {
+            seconds: metadata.ctime() as u32,
+            nanoseconds: metadata.ctime_nsec() as u32,
+        }
+    }
+
     pub(crate) fn serialize(timestamp: &GitIndexTimestamp) -> [u8; 8] {
         let mut bytes: [u8; 8] = [0; 8];
         bytes[0..4].copy_from_slice(&timestamp.seconds.to_be_bytes());
@@ -110,7 +238,7 @@ impl GitIndexTimestamp {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum GitIndexMode {
     RegularFile0755,
     RegularFile0644,
@@ -130,6 +258,62 @@ impl Display for GitIndexMode {
 }
 
 impl GitIndexMode {
+    /// Returns the mode of the file with the provided metadata.
+    pub(crate) fn from_metadata(metadata: &Metadata) -> GitIndexMode {
+        if metadata.is_symlink() {
+            GitIndexMode::SymbolicLink
+        } else if metadata.is_dir() {
+            GitIndexMode::GitLink
+        } else {
+            // If file is executable by owner, we set 755, otherwise 644.
+            if metadata.permissions().mode() & 0b01000000 != 0 {
+                GitIndexMode::RegularFile0755
+            } else {
+                GitIndexMode::RegularFile0644
+            }
+        }
+    }
+
+    /// Returns the mode to record for the worktree file with the provided metadata, whose path
+    /// has the provided mode in the index, if any. Like C Git, modes which the filesystem can't
+    /// represent are kept: the executable bit is only trusted with core.filemode, and symbolic
+    /// links written as plain files because of core.symlinks=false stay symbolic links.
+    pub(crate) fn from_worktree(
+        metadata: &Metadata,
+        existing: Option<&GitIndexMode>,
+        core: &CoreConfig,
+    ) -> GitIndexMode {
+        if !metadata.is_file() {
+            return GitIndexMode::from_metadata(metadata);
+        }
+        match existing {
+            Some(GitIndexMode::SymbolicLink) if !core.symlinks => GitIndexMode::SymbolicLink,
+            Some(mode @ (GitIndexMode::RegularFile0644 | GitIndexMode::RegularFile0755))
+                if !core.filemode =>
+            {
+                mode.clone()
+            }
+            _ if !core.filemode => GitIndexMode::RegularFile0644,
+            _ => GitIndexMode::from_metadata(metadata),
+        }
+    }
+
+    /// Returns the mode for the provided number, e.g. 0o100644. Like C Git, any number is
+    /// accepted, and falls back to a regular file, executable if the owner can execute it.
+    pub(crate) fn from_octal(mode: u32) -> GitIndexMode {
+        match mode & 0o170000 {
+            0o120000 => GitIndexMode::SymbolicLink,
+            0o160000 | 0o040000 => GitIndexMode::GitLink,
+            _ if mode & 0o100 != 0 => GitIndexMode::RegularFile0755,
+            _ => GitIndexMode::RegularFile0644,
+        }
+    }
+
+    /// Returns the mode as a number, e.g. 0o100644.
+    pub(crate) fn as_octal(&self) -> u32 {
+        as_u32_be(&GitIndexMode::serialize(self))
+    }
+
     pub(crate) fn serialize(mode: &GitIndexMode) -> [u8; 4] {
         match &mode {
             GitIndexMode::RegularFile0755 => [0b00000000, 0b00000000, 0b10000001, 0b11101101],
@@ -155,7 +339,7 @@ impl GitIndexMode {
 
 // Value for each case is important here,
 // to maintian consistent ordering of index entries.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub(crate) enum GitIndexStageFlag {
     RegularFileNoConflict = 0,
     Base = 1,
@@ -163,6 +347,19 @@ pub(crate) enum GitIndexStageFlag {
     Theirs = 3,
 }
 
+impl GitIndexStageFlag {
+    /// Returns the stage with the provided number, if it's valid.
+    pub(crate) fn from_number(number: u8) -> Option<GitIndexStageFlag> {
+        match number {
+            0 => Some(GitIndexStageFlag::RegularFileNoConflict),
+            1 => Some(GitIndexStageFlag::Base),
+            2 => Some(GitIndexStageFlag::Ours),
+            3 => Some(GitIndexStageFlag::Theirs),
+            _ => None,
+        }
+    }
+}
+
 impl Display for GitIndexStageFlag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -174,7 +371,7 @@ impl Display for GitIndexStageFlag {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct GitIndexFlags {
     pub assume_valid: bool,
     pub extended: bool,
@@ -209,15 +406,7 @@ impl GitIndexFlags {
         let bytes: &[u8; 2] = bytes.try_into()?;
         let assume_valid = bytes[0] & 0b10000000 != 0;
         let extended = bytes[0] & 0b01000000 != 0;
-        let stage = if bytes[0] & 0b00110000 != 0 {
-            GitIndexStageFlag::Theirs
-        } else if bytes[0] & 0b00100000 != 0 {
-            GitIndexStageFlag::Ours
-        } else if bytes[0] & 0b00010000 != 0 {
-            GitIndexStageFlag::Base
-        } else {
-            GitIndexStageFlag::RegularFileNoConflict
-        };
+        let stage = GitIndexStageFlag::from_number((bytes[0] & 0b00110000) >> 4).unwrap();
         let name_length = as_u16_be(&[bytes[0] & 0b00001111, bytes[1]]);
 
         Ok(GitIndexFlags {
@@ -229,7 +418,51 @@ impl GitIndexFlags {
     }
 }
 
-#[derive(Debug, Eq)]
+/// The flags only stored by version 3 and later indexes, in an extra 16-bit field following
+/// the flags of entries whose extended flag is set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct GitIndexExtendedFlags {
+    /// The entry is not checked out, so its worktree file is never looked at.
+    pub skip_worktree: bool,
+    /// The entry was added with `git add -N`, recording that its path will be added later.
+    pub intent_to_add: bool,
+}
+
+impl GitIndexExtendedFlags {
+    pub(crate) fn serialize(flags: &GitIndexExtendedFlags) -> [u8; 2] {
+        let mut first_byte: u8 = 0;
+        if flags.skip_worktree {
+            first_byte |= 0b01000000;
+        }
+        if flags.intent_to_add {
+            first_byte |= 0b00100000;
+        }
+
+        [first_byte, 0]
+    }
+
+    pub(crate) fn deserialize(bytes: &[u8]) -> Result<GitIndexExtendedFlags, RustGitError> {
+        let bytes: &[u8; 2] = bytes.try_into()?;
+        // The remaining bits are reserved for future use.
+        if bytes[0] & 0b10011111 != 0 || bytes[1] != 0 {
+            return Err(RustGitError::new(format!(
+                "unknown index entry format 0x{:08x}",
+                (as_u16_be(bytes) as u32) << 16
+            )));
+        }
+
+        Ok(GitIndexExtendedFlags {
+            skip_worktree: bytes[0] & 0b01000000 != 0,
+            intent_to_add: bytes[0] & 0b00100000 != 0,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == GitIndexExtendedFlags::default()
+    }
+}
+
+#[derive(Clone, Debug, Eq)]
 pub(crate) struct GitIndexEntry {
     pub last_metadata_update: GitIndexTimestamp,
     pub last_data_update: GitIndexTimestamp,
@@ -241,6 +474,7 @@ pub(crate) struct GitIndexEntry {
     pub file_size: u32,
     pub name: GitObjectId,
     pub flags: GitIndexFlags,
+    pub extended_flags: GitIndexExtendedFlags,
 
     /// Entry path name (variable length) relative to top level directory
     /// (without leading slash). '/' is used as path separator. The special
@@ -250,41 +484,61 @@ pub(crate) struct GitIndexEntry {
 }
 
 impl GitIndexEntry {
-    pub(crate) fn serialize(entry: &GitIndexEntry) -> Vec<u8> {
-        let mut fixed: [u8; 62] = [0; 62];
-        fixed[0..8].copy_from_slice(&GitIndexTimestamp::serialize(&entry.last_metadata_update));
-        fixed[8..16].copy_from_slice(&GitIndexTimestamp::serialize(&entry.last_data_update));
-        fixed[16..20].copy_from_slice(&entry.dev.to_be_bytes());
-        fixed[20..24].copy_from_slice(&entry.ino.to_be_bytes());
-        fixed[24..28].copy_from_slice(&GitIndexMode::serialize(&entry.mode));
-        fixed[28..32].copy_from_slice(&entry.uid.to_be_bytes());
-        fixed[32..36].copy_from_slice(&entry.gid.to_be_bytes());
-        fixed[36..40].copy_from_slice(&entry.file_size.to_be_bytes());
-        fixed[40..60].copy_from_slice(&GitObjectId::serialize(&entry.name));
-        fixed[60..62].copy_from_slice(&GitIndexFlags::serialize(&entry.flags));
-
-        let path_name_bytes = entry.path_name.as_string().as_bytes().to_vec();
-        let padding_byte_count = {
-            let remainder = (62 + path_name_bytes.len()) % 8;
-            if remainder == 0 {
-                8
-            } else {
-                8 - remainder
-            }
+    /// Serializes the entry as stored in an index of the provided version. Version 4 indexes store
+    /// path names relative to the path name of the previous entry.
+    pub(crate) fn serialize(
+        entry: &GitIndexEntry,
+        version: GitIndexVersion,
+        previous_path: &str,
+    ) -> Vec<u8> {
+        let extended = !entry.extended_flags.is_empty();
+        let flags = GitIndexFlags {
+            extended,
+            ..entry.flags
         };
 
-        let padding_bytes: Vec<u8> = std::iter::repeat(b'\0').take(padding_byte_count).collect();
-
         let mut bytes = vec![];
+        bytes.extend_from_slice(&GitIndexTimestamp::serialize(&entry.last_metadata_update));
+        bytes.extend_from_slice(&GitIndexTimestamp::serialize(&entry.last_data_update));
+        bytes.extend_from_slice(&entry.dev.to_be_bytes());
+        bytes.extend_from_slice(&entry.ino.to_be_bytes());
+        bytes.extend_from_slice(&GitIndexMode::serialize(&entry.mode));
+        bytes.extend_from_slice(&entry.uid.to_be_bytes());
+        bytes.extend_from_slice(&entry.gid.to_be_bytes());
+        bytes.extend_from_slice(&entry.file_size.to_be_bytes());
+        bytes.extend_from_slice(&GitObjectId::serialize(&entry.name));
+        bytes.extend_from_slice(&GitIndexFlags::serialize(&flags));
+        if extended {
+            bytes.extend_from_slice(&GitIndexExtendedFlags::serialize(&entry.extended_flags));
+        }
 
-        bytes.extend_from_slice(&fixed);
-        bytes.extend_from_slice(&path_name_bytes);
-        bytes.extend_from_slice(&padding_bytes);
+        let path_name = entry.path_name.as_string();
+        if version == GitIndexVersion::V4 {
+            // The number of bytes to remove from the end of the previous path name, followed by
+            // the NUL-terminated bytes to append to it.
+            let common_prefix_len = previous_path
+                .bytes()
+                .zip(path_name.bytes())
+                .take_while(|(previous, current)| previous == current)
+                .count();
+            bytes.extend_from_slice(&encode_varint(previous_path.len() - common_prefix_len));
+            bytes.extend_from_slice(&path_name.as_bytes()[common_prefix_len..]);
+            bytes.push(b'\0');
+            return bytes;
+        }
+
+        bytes.extend_from_slice(path_name.as_bytes());
+        let padding_byte_count = 8 - bytes.len() % 8;
+        bytes.extend(std::iter::repeat_n(b'\0', padding_byte_count));
 
         bytes
     }
 
-    pub(crate) fn deserialize(bytes: &[u8]) -> Result<(GitIndexEntry, usize), RustGitError> {
+    pub(crate) fn deserialize(
+        bytes: &[u8],
+        version: GitIndexVersion,
+        previous_path: &str,
+    ) -> Result<(GitIndexEntry, usize), RustGitError> {
         let last_metadata_update = GitIndexTimestamp::deserialize(&bytes[0..8])?;
         let last_data_update = GitIndexTimestamp::deserialize(&bytes[8..16])?;
         let dev = as_u32_be(&bytes[16..20].try_into()?);
@@ -296,32 +550,54 @@ impl GitIndexEntry {
         let name = GitObjectId::deserialize(&bytes[40..60])?;
         let flags = GitIndexFlags::deserialize(&bytes[60..62])?;
 
-        let path_name_bytes = {
-            if flags.name_length < 0xFFF {
-                Ok(&bytes[62..(62 + flags.name_length as usize)])
-            } else {
-                if let Some(null_index) = &bytes[62..].iter().position(|&b| b == b'\0') {
-                    Ok(&bytes[62..62 + null_index])
+        let (extended_flags, fixed_len) = if flags.extended {
+            if version == GitIndexVersion::V2 {
+                return Err(RustGitError::new("extended flags in version 2 index entry"));
+            }
+            (GitIndexExtendedFlags::deserialize(&bytes[62..64])?, 64)
+        } else {
+            (GitIndexExtendedFlags::default(), 62)
+        };
+
+        let (path_name, processed_bytes) = if version == GitIndexVersion::V4 {
+            let (strip_len, varint_len) = decode_varint(&bytes[fixed_len..])?;
+            let prefix_len = previous_path
+                .len()
+                .checked_sub(strip_len)
+                .ok_or_else(|| RustGitError::new("malformed name field in the index"))?;
+            let suffix_start = fixed_len + varint_len;
+            let suffix_len = bytes[suffix_start..]
+                .iter()
+                .position(|&b| b == b'\0')
+                .ok_or_else(|| RustGitError::new("missing null byte for path name"))?;
+
+            let mut path_name_bytes = previous_path.as_bytes()[..prefix_len].to_vec();
+            path_name_bytes.extend_from_slice(&bytes[suffix_start..suffix_start + suffix_len]);
+            (
+                GitRepoPath::deserialize(&path_name_bytes)?,
+                suffix_start + suffix_len + 1,
+            )
+        } else {
+            let path_name_bytes = {
+                if flags.name_length < 0xFFF {
+                    Ok(&bytes[fixed_len..(fixed_len + flags.name_length as usize)])
+                } else if let Some(null_index) =
+                    &bytes[fixed_len..].iter().position(|&b| b == b'\0')
+                {
+                    Ok(&bytes[fixed_len..fixed_len + null_index])
                 } else {
                     Err(RustGitError::new("missing null byte for path name"))
                 }
-            }
-        }?;
-
-        let path_name = GitRepoPath::deserialize(path_name_bytes)?;
-
-        let processed_bytes = 62 + path_name_bytes.len();
-        let padding = {
-            let remainder = processed_bytes % 8;
-            if remainder == 0 {
-                8
-            } else {
-                8 - remainder
-            }
+            }?;
+
+            let processed_bytes = fixed_len + path_name_bytes.len();
+            let padding = 8 - processed_bytes % 8;
+            (
+                GitRepoPath::deserialize(path_name_bytes)?,
+                processed_bytes + padding,
+            )
         };
 
-        let padded_processed_bytes = processed_bytes + padding;
-
         Ok((
             GitIndexEntry {
                 last_metadata_update,
@@ -334,56 +610,109 @@ impl GitIndexEntry {
                 file_size,
                 name,
                 flags,
+                extended_flags,
                 path_name,
             },
-            padded_processed_bytes,
+            processed_bytes,
         ))
     }
 
+    /// Returns the flags of the entry as C git holds them in memory, which `ls-files --debug`
+    /// prints: the on-disk flags without the name length, and any extended flags above them.
+    pub(crate) fn debug_flags(&self) -> u32 {
+        let flags = GitIndexFlags::serialize(&GitIndexFlags {
+            name_length: 0,
+            ..self.flags
+        });
+        let extended_flags = GitIndexExtendedFlags::serialize(&self.extended_flags);
+        ((as_u16_be(&extended_flags) as u32) << 16) | as_u16_be(&flags) as u32
+    }
+
     pub(crate) fn new(
         path: &GitRepoPath,
         metadata: &Metadata,
         obj_id: GitObjectId,
     ) -> GitIndexEntry {
-        let mode = if metadata.is_symlink() {
-            GitIndexMode::SymbolicLink
-        } else if metadata.is_dir() {
-            GitIndexMode::GitLink
-        } else {
-            // If file is executable by owner, we set 755, otherwise 644.
-            if metadata.permissions().mode() & 0b01000000 != 0 {
-                GitIndexMode::RegularFile0755
-            } else {
-                GitIndexMode::RegularFile0644
-            }
-        };
-
         GitIndexEntry {
-            last_metadata_update: GitIndexTimestamp {
-                seconds: metadata.ctime() as u32,
-                nanoseconds: metadata.ctime_nsec() as u32,
-            },
-            last_data_update: GitIndexTimestamp {
-                seconds: metadata.mtime() as u32,
-                nanoseconds: metadata.mtime_nsec() as u32,
-            },
+            last_metadata_update: GitIndexTimestamp::ctime(metadata),
+            last_data_update: GitIndexTimestamp::mtime(metadata),
             dev: metadata.dev() as u32,
             ino: metadata.ino() as u32,
             uid: metadata.uid() as u32,
             gid: metadata.gid() as u32,
             file_size: metadata.size() as u32,
             name: obj_id,
-            mode: mode,
+            mode: GitIndexMode::from_metadata(metadata),
             flags: GitIndexFlags {
                 assume_valid: false,
                 extended: false,
                 stage: GitIndexStageFlag::RegularFileNoConflict,
                 name_length: path.as_string().len() as u16,
             },
+            extended_flags: GitIndexExtendedFlags::default(),
+            path_name: path.clone(),
+        }
+    }
+
+    /// Creates an entry for an object which isn't read from the worktree. Without stat data, the
+    /// entry is compared to its file by contents until it's refreshed.
+    pub(crate) fn from_object(
+        path: &GitRepoPath,
+        mode: GitIndexMode,
+        obj_id: GitObjectId,
+        stage: GitIndexStageFlag,
+    ) -> GitIndexEntry {
+        let no_timestamp = GitIndexTimestamp {
+            seconds: 0,
+            nanoseconds: 0,
+        };
+        GitIndexEntry {
+            last_metadata_update: no_timestamp,
+            last_data_update: no_timestamp,
+            dev: 0,
+            ino: 0,
+            mode,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            name: obj_id,
+            flags: GitIndexFlags {
+                assume_valid: false,
+                extended: false,
+                stage,
+                name_length: path.as_string().len() as u16,
+            },
+            extended_flags: GitIndexExtendedFlags::default(),
             path_name: path.clone(),
         }
     }
 
+    /// Returns whether the stat data recorded for the entry still matches the file with the
+    /// provided metadata, in which case the file is assumed not to have changed. As in C Git, the
+    /// device isn't compared, as it isn't stable on all filesystems.
+    /// See https://git-scm.com/docs/racy-git for when the stat data can't be trusted.
+    pub(crate) fn matches_stat(&self, metadata: &Metadata, core: &CoreConfig) -> bool {
+        self.mode == GitIndexMode::from_worktree(metadata, Some(&self.mode), core)
+            && self.last_data_update == GitIndexTimestamp::mtime(metadata)
+            && (!core.trustctime || self.last_metadata_update == GitIndexTimestamp::ctime(metadata))
+            && self.ino == metadata.ino() as u32
+            && self.uid == metadata.uid()
+            && self.gid == metadata.gid()
+            && self.file_size == metadata.size() as u32
+    }
+
+    /// Records the stat data of the file with the provided metadata, whose contents match the
+    /// entry.
+    pub(crate) fn refresh_stat(&mut self, metadata: &Metadata) {
+        self.last_metadata_update = GitIndexTimestamp::ctime(metadata);
+        self.last_data_update = GitIndexTimestamp::mtime(metadata);
+        self.dev = metadata.dev() as u32;
+        self.ino = metadata.ino() as u32;
+        self.uid = metadata.uid();
+        self.gid = metadata.gid();
+        self.file_size = metadata.size() as u32;
+    }
+
     /// Creates a copy of the provided index with a new name.
     pub(crate) fn with_updated_name(self, new_name: &GitRepoPath) -> GitIndexEntry {
         GitIndexEntry {
@@ -427,6 +756,13 @@ impl PartialEq for GitIndexEntry {
 pub(crate) struct GitIndex {
     header: GitIndexHeader,
     entries: Vec<GitIndexEntry>,
+    cache_tree: Option<GitCacheTree>,
+    resolve_undo: GitResolveUndo,
+    /// Optional extensions which aren't understood, written back as they were read.
+    unknown_extensions: Vec<([u8; 4], Vec<u8>)>,
+    /// The modification time of the index file when it was read. Entries for files modified at
+    /// or after this time may have changed without their stat data showing it.
+    timestamp: Option<GitIndexTimestamp>,
 }
 
 impl Default for GitIndex {
@@ -437,23 +773,51 @@ impl Default for GitIndex {
                 num_entries: 0,
             },
             entries: vec![],
+            cache_tree: None,
+            resolve_undo: GitResolveUndo::default(),
+            unknown_extensions: vec![],
+            timestamp: None,
         }
     }
 }
 
+/// Appends an extension with the provided signature and data to the serialized index.
+fn serialize_extension(bytes: &mut Vec<u8>, signature: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(signature);
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(data);
+}
+
 impl GitIndex {
     // Reference for Git index binary format: https://git-scm.com/docs/index-format
     pub(crate) fn serialize(index: &GitIndex) -> Vec<u8> {
         let mut bytes = vec![];
-        let header_bytes = GitIndexHeader::serialize(&index.header);
+        let version = index.header.version.for_entries(&index.entries);
+        let header_bytes = GitIndexHeader::serialize(&GitIndexHeader {
+            version,
+            num_entries: index.header.num_entries,
+        });
         bytes.extend_from_slice(&header_bytes);
 
+        let mut previous_path = String::new();
         for entry in &index.entries {
-            let entry_bytes = GitIndexEntry::serialize(entry);
+            let entry_bytes = GitIndexEntry::serialize(entry, version, &previous_path);
             bytes.extend_from_slice(&entry_bytes);
+            previous_path = entry.path_name.as_string();
         }
 
-        // Skipping extensions for now.
+        if let Some(cache_tree) = &index.cache_tree {
+            let mut data = vec![];
+            GitCacheTree::serialize(cache_tree, "", &mut data);
+            serialize_extension(&mut bytes, CACHE_TREE_SIGNATURE, &data);
+        }
+        if !index.resolve_undo.is_empty() {
+            let data = GitResolveUndo::serialize(&index.resolve_undo);
+            serialize_extension(&mut bytes, RESOLVE_UNDO_SIGNATURE, &data);
+        }
+        for (signature, data) in &index.unknown_extensions {
+            serialize_extension(&mut bytes, signature, data);
+        }
 
         let mut hasher = Sha1::new();
         hasher.update(&bytes);
@@ -468,13 +832,51 @@ impl GitIndex {
 
         let mut entries = Vec::new();
         let mut entry_start = 12;
+        let mut previous_path = String::new();
         for _ in 0..header.num_entries {
-            let (entry, processed_bytes) = GitIndexEntry::deserialize(&bytes[entry_start..])?;
+            let (entry, processed_bytes) =
+                GitIndexEntry::deserialize(&bytes[entry_start..], header.version, &previous_path)?;
+            previous_path = entry.path_name.as_string();
             entries.push(entry);
             entry_start += processed_bytes;
         }
 
-        // Skipping extensions for now.
+        // Extensions fill the rest of the index up to the checksum, each starting with a 4-byte
+        // signature and a 4-byte size.
+        let mut cache_tree = None;
+        let mut resolve_undo = GitResolveUndo::default();
+        let mut unknown_extensions = vec![];
+        let extensions_end = bytes.len() - 20;
+        while entry_start + 8 <= extensions_end {
+            let signature: [u8; 4] = bytes[entry_start..entry_start + 4].try_into()?;
+            let size = as_u32_be(&bytes[entry_start + 4..entry_start + 8].try_into()?) as usize;
+            let data = bytes
+                .get(entry_start + 8..entry_start + 8 + size)
+                .filter(|_| entry_start + 8 + size <= extensions_end)
+                .ok_or_else(|| RustGitError::new("index file corrupt"))?;
+
+            match &signature {
+                // Malformed cache trees are dropped, as they're only a cache.
+                CACHE_TREE_SIGNATURE => {
+                    cache_tree = GitCacheTree::deserialize(data)
+                        .filter(|(_, _, processed_bytes)| *processed_bytes == data.len())
+                        .map(|(cache_tree, _, _)| cache_tree)
+                }
+                RESOLVE_UNDO_SIGNATURE => resolve_undo = GitResolveUndo::deserialize(data)?,
+                END_OF_INDEX_ENTRY_SIGNATURE | INDEX_ENTRY_OFFSET_TABLE_SIGNATURE => (),
+                // Extensions whose signature starts with an uppercase letter are optional.
+                signature if signature[0].is_ascii_uppercase() => {
+                    unknown_extensions.push((*signature, data.to_vec()))
+                }
+                signature => {
+                    return Err(RustGitError::new(format!(
+                        "error: index uses {} extension, which we do not understand\nfatal: index file corrupt",
+                        String::from_utf8_lossy(signature)
+                    )))
+                }
+            }
+            entry_start += 8 + size;
+        }
 
         // Checksum will always be last 20 bytes
         // TODO: use separate SHA type
@@ -490,7 +892,14 @@ impl GitIndex {
             )));
         }
 
-        Ok(GitIndex { header, entries })
+        Ok(GitIndex {
+            header,
+            entries,
+            cache_tree,
+            resolve_undo,
+            unknown_extensions,
+            timestamp: None,
+        })
     }
 
     /// Creates a new, empty index.
@@ -498,36 +907,69 @@ impl GitIndex {
         GitIndex::default()
     }
 
-    /// Loads the index from the provided git directory.
-    pub(crate) fn open(git_dir: &Path) -> Result<GitIndex, RustGitError> {
-        let index_file_path: PathBuf = git_dir.join(DEFAULT_INDEX_NAME);
-
+    /// Loads the index from the provided file.
+    /// If there is no index yet, an empty one is returned which will be written in the version
+    /// set by the provided config.
+    pub(crate) fn open(
+        index_file_path: &Path,
+        config: &GitConfig,
+    ) -> Result<GitIndex, RustGitError> {
         if !index_file_path.exists() {
-            return Ok(Self::new());
+            let mut index = Self::new();
+            index.set_version(GitIndexVersion::default_for(config));
+            return Ok(index);
         }
 
         let index_file_bytes = fs::read(index_file_path)?;
 
-        let git_index: GitIndex = GitIndex::deserialize(&index_file_bytes)?;
+        let mut git_index: GitIndex = GitIndex::deserialize(&index_file_bytes)?;
+        git_index.timestamp = Some(GitIndexTimestamp::mtime(&fs::metadata(index_file_path)?));
 
         Ok(git_index)
     }
 
-    /// Writes the index to the provided git directory.
-    pub(crate) fn write(&mut self, git_dir: &Path) -> Result<(), RustGitError> {
-        let mut index_file = File::create(&git_dir.join(DEFAULT_INDEX_NAME))?;
+    /// Writes the index through the provided lock on the index file, which it replaces.
+    /// The index is flushed to disk first if `fsync` is set.
+    pub(crate) fn write(&mut self, lock: GitLockFile, fsync: bool) -> Result<(), RustGitError> {
+        let serialized = GitIndex::serialize(self);
+        self.header.version = self.header.version.for_entries(&self.entries);
 
-        let serialized = GitIndex::serialize(&self);
+        lock.commit(&serialized, fsync)
+    }
 
-        index_file.write(&serialized)?;
+    /// Sets the version the index is written in. Versions 2 and 3 are interchangeable, as the
+    /// index is written in version 3 exactly when its entries have extended flags.
+    pub(crate) fn set_version(&mut self, version: GitIndexVersion) {
+        self.header.version = version;
+    }
 
-        Ok(())
+    /// Invalidates the cached trees of the directories containing the provided path, and records
+    /// the stages of the path if it's being removed from the index while unmerged.
+    fn entry_changed(&mut self, entry: &GitIndexEntry, removed: bool) {
+        if let Some(cache_tree) = &mut self.cache_tree {
+            cache_tree.invalidate_path(&entry.path_name.as_string());
+        }
+        if removed {
+            self.resolve_undo.record(entry);
+        }
     }
 
     /// Inserts the provided entry into the index.
     /// An existing entry with the same path_name and stage will be replaced, otherwise
     /// a new entry will be inserted such that the entry list remains sorted.
+    /// Inserting a merged entry resolves the path, replacing its unmerged entries.
     pub(crate) fn add(&mut self, index_entry: GitIndexEntry) {
+        let is_unmerged_stage = |entry: &GitIndexEntry| {
+            entry.path_name == index_entry.path_name
+                && entry.flags.stage != GitIndexStageFlag::RegularFileNoConflict
+        };
+        if index_entry.flags.stage == GitIndexStageFlag::RegularFileNoConflict
+            && self.entries.iter().any(is_unmerged_stage)
+        {
+            self.filter_entries(|entry| !is_unmerged_stage(entry));
+        }
+
+        self.entry_changed(&index_entry, false);
         let existing_entry = self.entries.binary_search(&index_entry);
 
         match existing_entry {
@@ -539,6 +981,23 @@ impl GitIndex {
         }
     }
 
+    /// Replaces all the entries of the index, as when reading trees into it. The cached trees
+    /// are discarded, as they no longer match the entries, and so are the recorded stages of
+    /// resolved paths.
+    pub(crate) fn replace_entries(&mut self, mut entries: Vec<GitIndexEntry>) {
+        entries.sort();
+        self.header.num_entries = entries.len() as u32;
+        self.entries = entries;
+        self.cache_tree = None;
+        self.resolve_undo = GitResolveUndo::default();
+    }
+
+    /// Returns whether the index was never written: it has no entries and wasn't read from a
+    /// file.
+    pub(crate) fn is_unborn(&self) -> bool {
+        self.entries.is_empty() && self.timestamp.is_none()
+    }
+
     /// Returns an iterator over the index entries.
     pub(crate) fn iter_entries(&self) -> impl Iterator<Item = &GitIndexEntry> {
         self.entries.iter()
@@ -550,16 +1009,19 @@ impl GitIndex {
         &mut self,
         mut predicate: impl FnMut(&GitIndexEntry) -> bool,
     ) -> Vec<GitRepoPath> {
-        let mut removed_paths = Vec::new();
-        self.entries.retain(|entry| {
-            let result = predicate(entry);
-            if !result {
-                self.header.num_entries -= 1;
-                removed_paths.push(entry.path_name.clone());
-            }
-            result
-        });
-        removed_paths
+        let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| predicate(entry));
+        self.entries = kept;
+        self.header.num_entries -= removed.len() as u32;
+
+        removed
+            .into_iter()
+            .map(|entry| {
+                self.entry_changed(&entry, true);
+                entry.path_name
+            })
+            .collect()
     }
 
     /// Tries to find an index entry with the provided path.
@@ -569,11 +1031,30 @@ impl GitIndex {
             .map_or(None, |idx| Some((idx, &self.entries[idx])))
     }
 
+    /// Returns the entry at the provided index.
+    /// Assumes that the provided index is valid.
+    pub(crate) fn entry_at(&self, index: usize) -> &GitIndexEntry {
+        &self.entries[index]
+    }
+
+    /// Returns the position of the entry with the provided path and stage, if there is one.
+    pub(crate) fn entry_position(
+        &self,
+        path: &GitRepoPath,
+        stage: GitIndexStageFlag,
+    ) -> Option<usize> {
+        self.entries
+            .binary_search_by(|entry| (&entry.path_name, entry.flags.stage).cmp(&(path, stage)))
+            .ok()
+    }
+
     /// Removes the index entry at the provided index.
     /// Assumes that the provided index is valid.
     pub(crate) fn remove_entry_at(&mut self, index: usize) -> GitIndexEntry {
         self.header.num_entries -= 1;
-        self.entries.remove(index)
+        let entry = self.entries.remove(index);
+        self.entry_changed(&entry, true);
+        entry
     }
 
     /// Updates the path name of the index entry with the provided current path name.
@@ -586,8 +1067,69 @@ impl GitIndex {
             let new_entry = self.remove_entry_at(index).with_updated_name(new_name);
             self.add(new_entry);
         }
-        // TODO: refresh index entry
-        // https://github.com/git/git/blob/master/read-cache.c#L165-L171
+    }
+
+    /// Updates the entry at the provided index, which must keep its path and stage.
+    /// Assumes that the provided index is valid.
+    pub(crate) fn update_entry_at(
+        &mut self,
+        index: usize,
+        update: impl FnOnce(&mut GitIndexEntry),
+    ) {
+        let entry = &mut self.entries[index];
+        update(entry);
+        if let Some(cache_tree) = &mut self.cache_tree {
+            cache_tree.invalidate_path(&entry.path_name.as_string());
+        }
+    }
+
+    /// Returns whether the provided path is a leading directory of an entry, or an entry is a
+    /// leading directory of the path, so that both can't be in the index.
+    pub(crate) fn has_file_directory_conflict(&self, path: &GitRepoPath) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| is_file_directory_conflict(&entry.path_name, path))
+    }
+
+    /// Removes the entries conflicting with the provided path, see `has_file_directory_conflict`.
+    pub(crate) fn remove_file_directory_conflicts(&mut self, path: &GitRepoPath) {
+        self.filter_entries(|entry| !is_file_directory_conflict(&entry.path_name, path));
+    }
+
+    /// Returns whether the provided entry is racily clean: its file was modified no earlier than
+    /// the index was written, so a later change in the same timestamp tick wouldn't show in its
+    /// stat data.
+    pub(crate) fn is_racy(&self, entry: &GitIndexEntry) -> bool {
+        self.timestamp
+            .is_some_and(|timestamp| timestamp <= entry.last_data_update)
+    }
+
+    /// Records the stat data of the file of the entry at the provided index, whose contents
+    /// match the entry. Assumes that the provided index is valid.
+    pub(crate) fn refresh_entry_stat_at(&mut self, index: usize, metadata: &Metadata) {
+        self.entries[index].refresh_stat(metadata);
+    }
+
+    /// Clears the recorded size of the entry at the provided index, whose file changed without
+    /// its stat data showing it, so that later commands don't trust the stat data.
+    /// Assumes that the provided index is valid.
+    pub(crate) fn smudge_entry_at(&mut self, index: usize) {
+        self.entries[index].file_size = 0;
+    }
+
+    /// Removes the cached trees, so they can be updated while reading the entries.
+    pub(crate) fn take_cache_tree(&mut self) -> Option<GitCacheTree> {
+        self.cache_tree.take()
+    }
+
+    /// Replaces the cached trees.
+    pub(crate) fn set_cache_tree(&mut self, cache_tree: GitCacheTree) {
+        self.cache_tree = Some(cache_tree);
+    }
+
+    /// Returns the stages recorded for paths whose conflicts were resolved.
+    pub(crate) fn resolve_undo(&self) -> &GitResolveUndo {
+        &self.resolve_undo
     }
 
     // Returns the range of entries which have the provided path prefix.
@@ -605,10 +1147,11 @@ impl GitIndex {
 
 #[cfg(test)]
 mod tests {
-    use crate::{object::GitObjectId, repo::GitRepoPath};
+    use crate::{cache_tree::GitCacheTree, object::GitObjectId, repo::GitRepoPath};
 
     use super::{
-        GitIndex, GitIndexEntry, GitIndexFlags, GitIndexMode, GitIndexStageFlag, GitIndexTimestamp,
+        GitIndex, GitIndexEntry, GitIndexExtendedFlags, GitIndexFlags, GitIndexMode,
+        GitIndexStageFlag, GitIndexTimestamp,
     };
 
     fn get_test_index_entry() -> GitIndexEntry {
@@ -634,6 +1177,7 @@ mod tests {
                 stage: GitIndexStageFlag::RegularFileNoConflict,
                 name_length: 8,
             },
+            extended_flags: GitIndexExtendedFlags::default(),
             path_name: GitRepoPath::deserialize(b"test.txt").unwrap(),
         }
     }
@@ -682,6 +1226,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_format_debug_flags() {
+        let mut entry = get_test_index_entry();
+        assert_eq!(entry.debug_flags(), 0);
+
+        entry.flags.stage = GitIndexStageFlag::Ours;
+        assert_eq!(entry.debug_flags(), 0x2000);
+
+        entry.flags = GitIndexFlags {
+            extended: true,
+            stage: GitIndexStageFlag::RegularFileNoConflict,
+            ..entry.flags
+        };
+        entry.extended_flags.skip_worktree = true;
+        assert_eq!(entry.debug_flags(), 0x40004000);
+    }
+
+    #[test]
+    fn should_record_resolve_undo_when_resolving() {
+        let mut index = GitIndex::new();
+        for stage in [
+            GitIndexStageFlag::Base,
+            GitIndexStageFlag::Ours,
+            GitIndexStageFlag::Theirs,
+        ] {
+            index.add(GitIndexEntry {
+                flags: GitIndexFlags {
+                    stage,
+                    ..get_test_index_entry().flags
+                },
+                ..get_test_index_entry()
+            });
+        }
+        assert!(index.resolve_undo().is_empty());
+
+        index.add(get_test_index_entry());
+        assert_eq!(index.entries, vec![get_test_index_entry()]);
+        assert_eq!(index.header.num_entries, 1);
+
+        let recorded = index.resolve_undo().iter().collect::<Vec<_>>();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].0.as_string(), "test.txt");
+        assert!(recorded[0]
+            .1
+            .iter()
+            .all(|stage| stage
+                == &Some((GitIndexMode::RegularFile0644, get_test_index_entry().name))));
+    }
+
+    #[test]
+    fn should_detect_racily_clean_entries() {
+        let mut index = GitIndex::new();
+        let entry = get_test_index_entry();
+        // Without a timestamp, the index was never written, so no entry is racy.
+        assert!(!index.is_racy(&entry));
+
+        for (seconds, nanoseconds, expected) in [
+            (100, 199, true),
+            (100, 200, true),
+            (100, 201, false),
+            (99, 999, true),
+        ] {
+            index.timestamp = Some(GitIndexTimestamp {
+                seconds,
+                nanoseconds,
+            });
+            assert_eq!(index.is_racy(&entry), expected);
+        }
+    }
+
+    #[test]
+    fn should_invalidate_cached_trees_of_changed_paths() {
+        let mut index = GitIndex::new();
+        let tree_id = GitObjectId::new(String::from("303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9"));
+        let mut cache_tree = GitCacheTree::default();
+        cache_tree.set_valid_tree(tree_id.clone(), 0);
+        cache_tree
+            .subtree_mut("dir")
+            .set_valid_tree(tree_id.clone(), 0);
+        cache_tree
+            .subtree_mut("other")
+            .set_valid_tree(tree_id.clone(), 0);
+        index.set_cache_tree(cache_tree);
+
+        index.add(GitIndexEntry {
+            path_name: GitRepoPath::deserialize(b"dir/test.txt").unwrap(),
+            ..get_test_index_entry()
+        });
+
+        let mut cache_tree = index.take_cache_tree().unwrap();
+        assert_eq!(cache_tree.valid_tree(0), None);
+        assert_eq!(cache_tree.subtree_mut("dir").valid_tree(0), None);
+        assert_eq!(
+            cache_tree.subtree_mut("other").valid_tree(0),
+            Some(&tree_id)
+        );
+
+        index.set_cache_tree(cache_tree.clone());
+        cache_tree.set_valid_tree(tree_id.clone(), 1);
+        index.set_cache_tree(cache_tree);
+        index.remove_entry_at(0);
+        assert_eq!(index.take_cache_tree().unwrap().valid_tree(1), None);
+    }
+
     mod serialization {
         use std::iter;
 
@@ -698,11 +1346,16 @@ mod tests {
         }
 
         mod git_index {
+            use sha1::{Digest, Sha1};
+
             use crate::{
+                cache_tree::GitCacheTree,
                 error::RustGitError,
+                hash::Hasher,
                 index::{
-                    GitIndex, GitIndexEntry, GitIndexFlags, GitIndexHeader, GitIndexMode,
-                    GitIndexStageFlag, GitIndexTimestamp, GitIndexVersion,
+                    tests::get_test_index_entry, GitIndex, GitIndexEntry, GitIndexExtendedFlags,
+                    GitIndexFlags, GitIndexHeader, GitIndexMode, GitIndexStageFlag,
+                    GitIndexTimestamp, GitIndexVersion,
                 },
                 object::GitObjectId,
                 repo::GitRepoPath,
@@ -716,6 +1369,7 @@ mod tests {
                         num_entries: 0,
                     },
                     entries: vec![],
+                    ..GitIndex::default()
                 };
 
                 let result = GitIndex::deserialize(&GitIndex::serialize(&test_index));
@@ -754,6 +1408,7 @@ mod tests {
                                 stage: GitIndexStageFlag::RegularFileNoConflict,
                                 name_length: 8,
                             },
+                            extended_flags: GitIndexExtendedFlags::default(),
                             path_name: GitRepoPath::deserialize(b"test.txt").unwrap(),
                         },
                         GitIndexEntry {
@@ -780,15 +1435,148 @@ mod tests {
                                 stage: GitIndexStageFlag::RegularFileNoConflict,
                                 name_length: 9,
                             },
+                            extended_flags: GitIndexExtendedFlags::default(),
                             path_name: GitRepoPath::deserialize(b"test2.txt").unwrap(),
                         },
                     ],
+                    ..GitIndex::default()
                 };
 
                 let result = GitIndex::deserialize(&GitIndex::serialize(&test_index));
                 assert_eq!(result, Ok(test_index));
             }
 
+            fn get_test_index(version: GitIndexVersion) -> GitIndex {
+                let paths = [
+                    "dir/file.txt",
+                    "dir/file2.txt",
+                    "dir/sub/file.txt",
+                    "other.txt",
+                ];
+                let entries = paths
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, path)| GitIndexEntry {
+                        flags: GitIndexFlags {
+                            extended: idx == 1,
+                            name_length: path.len() as u16,
+                            ..get_test_index_entry().flags
+                        },
+                        extended_flags: GitIndexExtendedFlags {
+                            skip_worktree: idx == 1,
+                            intent_to_add: false,
+                        },
+                        path_name: GitRepoPath::deserialize(path.as_bytes()).unwrap(),
+                        ..get_test_index_entry()
+                    })
+                    .collect();
+
+                GitIndex {
+                    header: GitIndexHeader {
+                        version,
+                        num_entries: paths.len() as u32,
+                    },
+                    entries,
+                    ..GitIndex::default()
+                }
+            }
+
+            #[test]
+            fn should_roundtrip_extended_flags() {
+                let test_index = get_test_index(GitIndexVersion::V3);
+                let result = GitIndex::deserialize(&GitIndex::serialize(&test_index)).unwrap();
+                assert_eq!(result, test_index);
+                assert_eq!(
+                    result.entries[1].extended_flags,
+                    GitIndexExtendedFlags {
+                        skip_worktree: true,
+                        intent_to_add: false,
+                    }
+                );
+            }
+
+            #[test]
+            fn should_write_version_3_only_for_extended_flags() {
+                let mut test_index = get_test_index(GitIndexVersion::V2);
+                assert_eq!(GitIndex::serialize(&test_index)[4..8], [0, 0, 0, 3]);
+
+                test_index.entries[1].extended_flags = GitIndexExtendedFlags::default();
+                test_index.header.version = GitIndexVersion::V3;
+                assert_eq!(GitIndex::serialize(&test_index)[4..8], [0, 0, 0, 2]);
+            }
+
+            #[test]
+            fn should_roundtrip_prefix_compressed_paths() {
+                let test_index = get_test_index(GitIndexVersion::V4);
+                let serialized = GitIndex::serialize(&test_index);
+                let result = GitIndex::deserialize(&serialized).unwrap();
+                assert_eq!(result, test_index);
+                assert_eq!(
+                    result
+                        .entries
+                        .iter()
+                        .map(|entry| entry.path_name.as_string())
+                        .collect::<Vec<_>>(),
+                    [
+                        "dir/file.txt",
+                        "dir/file2.txt",
+                        "dir/sub/file.txt",
+                        "other.txt"
+                    ]
+                );
+
+                // Only the suffix differing from the previous path is stored.
+                let second_entry = &serialized[12 + 62 + 1 + 13..];
+                assert_eq!(&second_entry[62..62 + 2 + 1 + 6], b"\x40\x00\x042.txt\0");
+            }
+
+            /// Returns the serialized empty index with the provided extension data appended.
+            fn with_extension_bytes(extension_bytes: &[u8]) -> Vec<u8> {
+                let serialized = GitIndex::serialize(&GitIndex::default());
+                let mut bytes = serialized[..serialized.len() - 20].to_vec();
+                bytes.extend_from_slice(extension_bytes);
+                let mut hasher = Sha1::new();
+                hasher.update(&bytes);
+                bytes.extend_from_slice(&GitObjectId::serialize(&hasher.final_oid_fn()));
+                bytes
+            }
+
+            #[test]
+            fn should_roundtrip_extensions() {
+                let mut test_index = get_test_index(GitIndexVersion::V3);
+                let mut cache_tree = GitCacheTree::default();
+                cache_tree.set_valid_tree(get_test_index_entry().name, 4);
+                test_index.set_cache_tree(cache_tree);
+                test_index.resolve_undo.record(&GitIndexEntry {
+                    flags: GitIndexFlags {
+                        stage: GitIndexStageFlag::Theirs,
+                        ..get_test_index_entry().flags
+                    },
+                    ..get_test_index_entry()
+                });
+                test_index
+                    .unknown_extensions
+                    .push((*b"ZZZZ", b"unknown".to_vec()));
+
+                let result = GitIndex::deserialize(&GitIndex::serialize(&test_index));
+                assert_eq!(result, Ok(test_index));
+            }
+
+            #[test]
+            fn should_drop_stale_and_malformed_extensions() {
+                let bytes = with_extension_bytes(b"EOIE\0\0\0\x04\0\0\0\x0cTREE\0\0\0\x03bad");
+                assert_eq!(GitIndex::deserialize(&bytes), Ok(GitIndex::default()));
+            }
+
+            #[test]
+            fn should_fail_for_unknown_required_extension() {
+                let bytes = with_extension_bytes(b"link\0\0\0\0");
+                assert_eq!(
+                    GitIndex::deserialize(&bytes),
+                    Err(RustGitError::new("error: index uses link extension, which we do not understand\nfatal: index file corrupt"))
+                );
+            }
+
             #[test]
             fn should_fail_for_invalid_checksum() {
                 let test_index = GitIndex {
@@ -797,6 +1585,7 @@ mod tests {
                         num_entries: 0,
                     },
                     entries: vec![],
+                    ..GitIndex::default()
                 };
 
                 let mut serialized_bytes = GitIndex::serialize(&test_index);
@@ -815,15 +1604,20 @@ mod tests {
             use std::iter;
 
             use crate::{
-                index::{tests::get_test_index_entry, GitIndexEntry, GitIndexFlags},
+                index::{
+                    tests::get_test_index_entry, GitIndexEntry, GitIndexFlags, GitIndexVersion,
+                },
                 repo::GitRepoPath,
             };
 
             #[test]
             fn should_roundtrip() {
                 let git_index_entry = get_test_index_entry();
-                let result =
-                    GitIndexEntry::deserialize(&GitIndexEntry::serialize(&git_index_entry));
+                let result = GitIndexEntry::deserialize(
+                    &GitIndexEntry::serialize(&git_index_entry, GitIndexVersion::V2, ""),
+                    GitIndexVersion::V2,
+                    "",
+                );
                 assert_eq!(result, Ok((git_index_entry, 72)));
             }
 
@@ -844,8 +1638,11 @@ mod tests {
                     .unwrap(),
                     ..test_index_entry
                 };
-                let result =
-                    GitIndexEntry::deserialize(&GitIndexEntry::serialize(&git_index_entry));
+                let result = GitIndexEntry::deserialize(
+                    &GitIndexEntry::serialize(&git_index_entry, GitIndexVersion::V2, ""),
+                    GitIndexVersion::V2,
+                    "",
+                );
                 assert_eq!(result, Ok((git_index_entry, 65584)));
             }
         }
@@ -867,12 +1664,74 @@ mod tests {
                 assert_eq!(result, Ok(git_index_flags));
             }
 
+            #[test]
+            fn should_roundtrip_stages() {
+                for stage in 0..=3 {
+                    let git_index_flags = GitIndexFlags {
+                        assume_valid: true,
+                        extended: false,
+                        stage: super::GitIndexStageFlag::from_number(stage).unwrap(),
+                        name_length: 5,
+                    };
+
+                    let result =
+                        GitIndexFlags::deserialize(&GitIndexFlags::serialize(&git_index_flags));
+                    assert_eq!(result, Ok(git_index_flags));
+                }
+            }
+
             #[test]
             fn should_fail_for_incorrect_number_of_bytes() {
                 assert_incorrect_bytes_err(2, GitIndexFlags::deserialize)
             }
         }
 
+        mod git_index_extended_flags {
+            use crate::error::RustGitError;
+
+            use super::{assert_incorrect_bytes_err, GitIndexExtendedFlags};
+
+            #[test]
+            fn should_roundtrip() {
+                let git_index_extended_flags = GitIndexExtendedFlags {
+                    skip_worktree: true,
+                    intent_to_add: true,
+                };
+
+                let result = GitIndexExtendedFlags::deserialize(&GitIndexExtendedFlags::serialize(
+                    &git_index_extended_flags,
+                ));
+                assert_eq!(result, Ok(git_index_extended_flags));
+            }
+
+            #[test]
+            fn should_fail_for_incorrect_number_of_bytes() {
+                assert_incorrect_bytes_err(2, GitIndexExtendedFlags::deserialize)
+            }
+
+            #[test]
+            fn should_fail_for_reserved_flags() {
+                assert_eq!(
+                    GitIndexExtendedFlags::deserialize(&[0b10000000, 0]),
+                    Err(RustGitError::new("unknown index entry format 0x80000000"))
+                );
+            }
+        }
+
+        mod varint {
+            use super::{decode_varint, encode_varint};
+
+            #[test]
+            fn should_roundtrip() {
+                for value in [0, 1, 127, 128, 255, 16511, 16512, 1 << 20] {
+                    let encoded = encode_varint(value);
+                    assert_eq!(decode_varint(&encoded), Ok((value, encoded.len())));
+                }
+                assert_eq!(encode_varint(128), [0x80, 0x00]);
+                assert_eq!(encode_varint(16511), [0xFF, 0x7F]);
+            }
+        }
+
         mod git_index_mode {
             use crate::error::RustGitError;
 
@@ -984,11 +1843,16 @@ mod tests {
 
             #[test]
             fn should_roundtrip() {
-                let git_index_version = GitIndexVersion::V2;
-
-                let result =
-                    GitIndexVersion::deserialize(&GitIndexVersion::serialize(&git_index_version));
-                assert_eq!(result, Ok(git_index_version));
+                for git_index_version in [
+                    GitIndexVersion::V2,
+                    GitIndexVersion::V3,
+                    GitIndexVersion::V4,
+                ] {
+                    let result = GitIndexVersion::deserialize(&GitIndexVersion::serialize(
+                        &git_index_version,
+                    ));
+                    assert_eq!(result, Ok(git_index_version));
+                }
             }
 
             #[test]
@@ -998,10 +1862,10 @@ mod tests {
 
             #[test]
             fn should_fail_for_invalid_version() {
-                let result = GitIndexVersion::deserialize(&(3 as u32).to_be_bytes());
+                let result = GitIndexVersion::deserialize(&(5 as u32).to_be_bytes());
                 assert_eq!(
                     result,
-                    Err(RustGitError::new("unsupported index version 3"))
+                    Err(RustGitError::new("unsupported index version 5"))
                 );
             }
         }
@@ -20,6 +20,23 @@ impl Display for HashAlgorithm {
     }
 }
 
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RefStorageFormat {
+    #[default]
+    Files,
+    Reftable
+}
+
+impl Display for RefStorageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefStorageFormat::Files => write!(f, "files"),
+            RefStorageFormat::Reftable => write!(f, "reftable"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum InitPermissionFlag {
     Umask,
@@ -86,6 +103,15 @@ pub(crate) struct InitArgs {
     #[arg(long, value_name="format", default_value="sha1")]
     pub object_format: HashAlgorithm,
 
+    /// Specify the given ref storage format for the repository. The valid values are:
+    ///     files
+    ///         for loose files with packed-refs. This is the default.
+    ///
+    ///     reftable
+    ///         for the reftable format. This format is experimental and its internals are subject to change.
+    #[arg(long, value_name="format", default_value="files")]
+    pub ref_format: RefStorageFormat,
+
     /// Use the specified name for the initial branch in the newly created repository. If not specified, fall back to the default name (currently master, but this is subject to change in the
     /// future; the name can be customized via the init.defaultBranch configuration variable).
     #[arg(short='b', long, value_name="branch-name", default_value="main")]
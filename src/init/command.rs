@@ -1,7 +1,7 @@
 use std::{fs::{DirBuilder, self, File}, path::{PathBuf, Path}, io::Write};
-use crate::{command::GitCommand, config::{CoreConfig, ExtensionsConfig, GitConfig}, repo::RepoState, RustGitError};
+use crate::{command::GitCommand, config::{CoreConfig, ExtensionsConfig, GitConfig}, refs::{RefBackend, RefUpdate, RefValue}, reftable::ReftableBackend, repo::RepoState, RustGitError};
 
-use super::cli::{InitArgs, InitPermissionFlag, HashAlgorithm};
+use super::cli::{InitArgs, InitPermissionFlag, HashAlgorithm, RefStorageFormat};
 
 const DEFAULT_GIT_DIR: &str = ".git";
 
@@ -37,6 +37,9 @@ impl InitCommand {
 ///     - refs
 ///         - heads
 ///         - tags
+///
+/// With the reftable ref format, refs/heads is a file instead, so that older Git versions don't
+/// mistake the repository for one using loose refs, and refs are stored under reftable/.
 fn create_git_repo(cmd: &InitCommand, project_dir: &PathBuf, git_dir: &PathBuf) -> Result<PathBuf, RustGitError>
 {
     let mut git_repo_dir = project_dir.to_path_buf();
@@ -69,8 +72,17 @@ fn create_git_repo(cmd: &InitCommand, project_dir: &PathBuf, git_dir: &PathBuf)
     dir_builder.create(info_dir)?;
     dir_builder.create(hooks_dir)?;
     dir_builder.create(refs_dir)?;
-    dir_builder.create(refs_heads_dir)?;
-    dir_builder.create(refs_tags_dir)?;
+
+    match cmd.args.ref_format {
+        RefStorageFormat::Files => {
+            dir_builder.create(refs_heads_dir)?;
+            dir_builder.create(refs_tags_dir)?;
+        }
+        RefStorageFormat::Reftable => {
+            fs::write(refs_heads_dir, "this repository uses the reftable format\n")?;
+            dir_builder.create(git_repo_dir.join("reftable"))?;
+        }
+    }
 
     let head_file_path = git_repo_dir.join("HEAD");
     let mut head_file = 
@@ -83,15 +95,27 @@ fn create_git_repo(cmd: &InitCommand, project_dir: &PathBuf, git_dir: &PathBuf)
     // There's also a more complex implementation of working with the HEAD file,
     // for now we'll keep it simple and re-visit when we start doing actual operations on it.
     // https://github.com/git/git/blob/master/refs/reftable-backend.c#L2230
-    let initial_branch_ref = format!("ref: refs/heads/{}", &cmd.args.initial_branch);
+    let initial_branch = format!("refs/heads/{}", &cmd.args.initial_branch);
 
-    head_file.write_all(initial_branch_ref.as_bytes())
+    // With reftable, the HEAD file only exists so that the directory is still recognized as a
+    // repository, and points at an invalid branch. The actual HEAD is stored in the tables.
+    let head_file_ref = match cmd.args.ref_format {
+        RefStorageFormat::Files => format!("ref: {initial_branch}"),
+        RefStorageFormat::Reftable => String::from("ref: refs/heads/.invalid\n"),
+    };
+
+    head_file.write_all(head_file_ref.as_bytes())
              .map_err(|err| format!("Failed to write initial branch to HEAD file: ({err})"))?;
 
     let config = init_config(cmd, &git_repo_dir);
     config.write(&git_repo_dir)
           .map_err(|err| format!("Failed to write config: ({err})"))?;
 
+    if cmd.args.ref_format == RefStorageFormat::Reftable {
+        ReftableBackend::new(&git_repo_dir, &config)
+            .commit(&[RefUpdate::Set { name: String::from("HEAD"), value: RefValue::Symbolic(initial_branch) }])?;
+    }
+
     let canonical_git_repo = 
         fs::canonicalize(&git_repo_dir)
         .map_err(|err| format!("Failed to canonicalize git repo {git_repo_dir:?} ({err})"))?;
@@ -105,7 +129,7 @@ fn init_config(cmd: &InitCommand, git_repo_dir: &PathBuf) -> GitConfig {
     // version will get adjusted by git-clone(1) once it has learned about
     // the remote repository's format.
     let repo_version =
-        if cmd.args.object_format != HashAlgorithm::Sha1 {
+        if cmd.args.object_format != HashAlgorithm::Sha1 && cmd.args.ref_format == RefStorageFormat::Files {
             0
         } else {
             1
@@ -129,7 +153,7 @@ fn init_config(cmd: &InitCommand, git_repo_dir: &PathBuf) -> GitConfig {
         },
         extensions: ExtensionsConfig {
             objectformat: cmd.args.object_format,
-            ..Default::default()
+            refstorage: cmd.args.ref_format,
         },
         ..Default::default()
     }
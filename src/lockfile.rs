@@ -0,0 +1,121 @@
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::error::RustGitError;
+
+pub(crate) const LOCK_SUFFIX: &str = ".lock";
+
+// Same advice as C Git:
+// https://github.com/git/git/blob/master/lockfile.c#L170
+const LOCK_HELD_ADVICE: &str = "Another git process seems to be running in this repository, e.g.
+an editor opened by 'git commit'. Please make sure all processes
+are terminated then try again. If it still fails, a git process
+may have crashed in this repository earlier:
+remove the file manually to continue.";
+
+/// An exclusive lock on a file, held by creating `<file>.lock` next to it.
+/// The new contents of the file are written to the lock file, which then replaces the file, so
+/// readers only ever see the old or the new contents. The lock is released if dropped without
+/// being committed.
+pub(crate) struct GitLockFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    file: File,
+    committed: bool,
+}
+
+impl GitLockFile {
+    /// Takes the lock for the provided file, failing if another process holds it.
+    pub(crate) fn acquire(path: &Path) -> Result<GitLockFile, RustGitError> {
+        match GitLockFile::try_acquire(path)? {
+            Some(lock) => Ok(lock),
+            None => Err(RustGitError::new(format!(
+                "fatal: Unable to create '{}{LOCK_SUFFIX}': File exists.\n\n{LOCK_HELD_ADVICE}\n",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Takes the lock for the provided file, returning None if another process holds it.
+    pub(crate) fn try_acquire(path: &Path) -> Result<Option<GitLockFile>, RustGitError> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(LOCK_SUFFIX);
+        let lock_path = PathBuf::from(lock_path);
+
+        match File::options()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(file) => Ok(Some(GitLockFile {
+                path: path.to_path_buf(),
+                lock_path,
+                file,
+                committed: false,
+            })),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Replaces the locked file with the provided contents, flushing them to disk first if
+    /// `fsync` is set.
+    pub(crate) fn commit(mut self, contents: &[u8], fsync: bool) -> Result<(), RustGitError> {
+        self.file.write_all(contents)?;
+        if fsync {
+            self.file.sync_all()?;
+        }
+        fs::rename(&self.lock_path, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for GitLockFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::TempDir;
+
+    #[test]
+    fn should_replace_file_on_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("index");
+        fs::write(&path, "old").unwrap();
+
+        let lock = GitLockFile::acquire(&path).unwrap();
+        assert!(temp_dir.path().join("index.lock").exists());
+        assert!(GitLockFile::try_acquire(&path).unwrap().is_none());
+
+        lock.commit(b"new", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!temp_dir.path().join("index.lock").exists());
+    }
+
+    #[test]
+    fn should_release_lock_when_dropped() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("index");
+
+        drop(GitLockFile::acquire(&path).unwrap());
+        assert!(!temp_dir.path().join("index.lock").exists());
+        assert!(!path.exists());
+
+        let _lock = GitLockFile::acquire(&path).unwrap();
+        let err = GitLockFile::acquire(&path).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("Another git process seems to be running in this repository"));
+    }
+}
@@ -0,0 +1,74 @@
+use clap::Args;
+
+use crate::color::ColorWhen;
+
+#[derive(Args, Debug)]
+#[command(about = "Show commit logs")]
+#[command(long_about = "
+Shows the commit logs.
+
+List commits that are reachable by following the parent links from the given commit(s). The commits are
+listed in reverse chronological order.
+
+The command takes options applicable to the git rev-list command to control what is shown and how, and options
+applicable to the git diff-* commands to control how the changes each commit introduces are shown.
+")]
+pub(crate) struct LogArgs {
+    /// Show only commits reachable from the given revisions. When no <revision-range> is specified, it defaults
+    /// to HEAD (i.e. the whole history leading to the current commit).
+    #[arg(value_name = "revision-range")]
+    pub revisions: Vec<String>,
+
+    /// Limit the number of commits to output.
+    #[arg(short = 'n', long, value_name = "number")]
+    pub max_count: Option<usize>,
+
+    /// This is a shorthand for "--pretty=oneline --abbrev-commit" used together.
+    #[arg(long)]
+    pub oneline: bool,
+
+    /// Show the notes (see git-notes(1)) that annotate the commit, when showing the commit log message. This is
+    /// the default for git log, git show and git whatchanged commands when there is no --pretty, --format, or
+    /// --oneline option given on the command line.
+    ///
+    /// By default, the notes shown are from the notes refs listed in the core.notesRef and notes.displayRef
+    /// variables (or corresponding environment overrides). See git-config(1) for more details.
+    ///
+    /// With an optional <ref> argument, use the ref to find the notes to display. The ref can specify the full
+    /// refname when it begins with refs/notes/; when it begins with notes/, refs/ and otherwise refs/notes/ is
+    /// prefixed to form the full name of the ref.
+    ///
+    /// Multiple --notes options can be combined to control which notes are being displayed. Examples:
+    /// "--notes=foo" will show only notes from "refs/notes/foo"; "--notes=foo --notes" will show both notes
+    /// from "refs/notes/foo" and from the default notes ref(s).
+    #[arg(
+        long,
+        value_name = "ref",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        overrides_with = "no_notes"
+    )]
+    pub notes: Vec<String>,
+
+    /// Do not show notes. This negates the above --notes option, by resetting the list of notes refs from which
+    /// notes are shown. Options are parsed in the order given on the command line, so e.g. "--notes --notes=foo
+    /// --no-notes --notes=bar" will only show notes from "refs/notes/bar".
+    #[arg(long, overrides_with = "notes")]
+    pub no_notes: bool,
+
+    /// Show colored output. The value must be always (the default when omitted), never, or auto.
+    #[arg(
+        long,
+        value_name = "when",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always",
+        overrides_with = "no_color"
+    )]
+    pub color: Option<ColorWhen>,
+
+    /// Turn off colored output, even when the configuration file gives the default to color output.
+    #[arg(long, overrides_with = "color")]
+    pub no_color: bool,
+}
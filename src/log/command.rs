@@ -0,0 +1,84 @@
+use crate::{
+    color::ColorWhen,
+    command::GitCommand,
+    notes_tree::load_display_notes,
+    pretty::{format_log_entry, log_palette, CommitFormat},
+    refs::GitHead,
+    repo::RepoState,
+    RustGitError,
+};
+
+use super::cli::LogArgs;
+
+pub(crate) struct LogCommand {
+    revisions: Vec<String>,
+    max_count: Option<usize>,
+    format: CommitFormat,
+    notes: Vec<String>,
+    no_notes: bool,
+    color: Option<ColorWhen>,
+}
+
+impl LogCommand {
+    pub fn new(args: LogArgs) -> LogCommand {
+        LogCommand {
+            revisions: args.revisions,
+            max_count: args.max_count,
+            format: if args.oneline {
+                CommitFormat::Oneline
+            } else {
+                CommitFormat::Medium
+            },
+            notes: args.notes,
+            no_notes: args.no_notes,
+            color: match args.no_color {
+                true => Some(ColorWhen::Never),
+                false => args.color,
+            },
+        }
+    }
+}
+
+impl GitCommand for LogCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+
+        let starts = if self.revisions.is_empty() {
+            match repo.read_head()? {
+                Some(GitHead::Symbolic { ref_name, id: None }) => {
+                    return Err(RustGitError::new(format!(
+                        "fatal: your current branch '{}' does not have any commits yet",
+                        ref_name.trim_start_matches("refs/heads/")
+                    )))
+                }
+                _ => vec![repo.resolve_commit("HEAD")?],
+            }
+        } else {
+            self.revisions
+                .iter()
+                .map(|revision| repo.resolve_commit(revision))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let notes = load_display_notes(&repo, &self.notes, self.no_notes)?;
+        let palette = log_palette(&repo.config, self.color)?;
+        let commits = repo.walk_commits(&starts)?;
+        let max_count = self.max_count.unwrap_or(commits.len());
+
+        for (i, (id, commit)) in commits.iter().take(max_count).enumerate() {
+            if i > 0 && self.format == CommitFormat::Medium {
+                println!();
+            }
+            print!(
+                "{}",
+                format_log_entry(&repo, id, commit, self.format, &notes, &palette)?
+            );
+        }
+
+        Ok(())
+    }
+
+    fn pages_by_default(&self) -> bool {
+        true
+    }
+}
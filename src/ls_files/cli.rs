@@ -8,6 +8,33 @@ pub(crate) struct LsFilesArgs {
     #[arg(short, long)]
     pub cached: bool,
 
+    /// Show other (i.e. untracked) files in the output
+    #[arg(short, long)]
+    pub others: bool,
+
+    /// Show only ignored files in the output. When showing files in the index, print only those matched by an exclude
+    /// pattern. When showing "other" files, show only those matched by an exclude pattern. Standard ignore rules are
+    /// not automatically activated, therefore at least one of the --exclude* options is required.
+    #[arg(short, long)]
+    pub ignored: bool,
+
+    /// Skip untracked files matching pattern. Note that pattern is a shell wildcard pattern.
+    #[arg(short = 'x', long, value_name = "pattern")]
+    pub exclude: Vec<String>,
+
+    /// Read exclude patterns from <file>; 1 per line.
+    #[arg(short = 'X', long, value_name = "file")]
+    pub exclude_from: Vec<String>,
+
+    /// Read additional exclude patterns that apply only to the directory and its subdirectories in <file>.
+    #[arg(long, value_name = "file")]
+    pub exclude_per_directory: Option<String>,
+
+    /// Add the standard Git exclusions: .git/info/exclude, .gitignore in each directory, and the user's global
+    /// exclusion file.
+    #[arg(long)]
+    pub exclude_standard: bool,
+
     /// Show staged contents' mode bits, object name and stage number in the output.
     #[arg(short, long)]
     pub stage: bool,
@@ -16,4 +43,12 @@ pub(crate) struct LsFilesArgs {
     /// inspection; the exact format may change at any time.
     #[arg(long)]
     pub debug: bool,
+
+    /// Show files having resolve-undo information in the index together with their unmerged stages, i.e.
+    /// the stages recorded when their conflicts were resolved.
+    #[arg(long)]
+    pub resolve_undo: bool,
+
+    /// Files to show. If no files are given all files which match the other specified criteria are shown.
+    pub files: Vec<String>,
 }
\ No newline at end of file
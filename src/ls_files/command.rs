@@ -1,30 +1,90 @@
-use crate::{command::GitCommand, repo::RepoState, RustGitError};
+use std::path::Path;
+
+use crate::{
+    command::GitCommand,
+    ignore::GitIgnore,
+    pathspec::{Pathspec, PathspecOptions},
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
 
 use super::cli::LsFilesArgs;
 
 pub(crate) struct LsFilesCommand {
     args: LsFilesArgs,
-
+    pathspec_options: PathspecOptions,
 }
 
 impl LsFilesCommand {
-    pub fn new(args: LsFilesArgs) -> LsFilesCommand {
+    pub fn new(
+        args: LsFilesArgs,
+        pathspec_options: PathspecOptions,
+    ) -> Result<LsFilesCommand, RustGitError> {
+        let has_excludes = args.exclude_standard
+            || !args.exclude.is_empty()
+            || !args.exclude_from.is_empty()
+            || args.exclude_per_directory.is_some();
+        if args.ignored && !has_excludes {
+            return Err(RustGitError::new("ls-files: --ignored needs some exclude pattern"));
+        }
+
         // With no flags, we default to showing the cached files
-        if !args.stage {
-            LsFilesCommand { args: LsFilesArgs { cached: true, ..args } }
+        if !args.stage && !args.resolve_undo && !args.others {
+            Ok(LsFilesCommand {
+                args: LsFilesArgs { cached: true, ..args },
+                pathspec_options,
+            })
+        } else if args.ignored && !args.others && !args.cached {
+            Err(RustGitError::new("fatal: ls-files -i must be used with either -o or -c"))
         } else {
-            LsFilesCommand {
+            Ok(LsFilesCommand {
                 args,
-            }
+                pathspec_options,
+            })
+        }
+    }
+
+    /// Returns the ignore rules set by the --exclude* options.
+    fn ignore(&self, repo: &GitRepo) -> Result<GitIgnore, RustGitError> {
+        let mut ignore = if self.args.exclude_standard {
+            repo.standard_ignore()?
+        } else {
+            GitIgnore::new(&repo.root_dir, repo.config.core.ignorecase)
+        };
+        for pattern in &self.args.exclude {
+            ignore.add_command_line_pattern(pattern);
+        }
+        for file in &self.args.exclude_from {
+            ignore.add_exclude_file(Path::new(file), file)?;
+        }
+        if let Some(file_name) = &self.args.exclude_per_directory {
+            ignore.set_per_directory_file(file_name);
         }
+        Ok(ignore)
     }
 }
 
 impl GitCommand for LsFilesCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
         let repo = repo_state.try_get()?;
+        let mut ignore = self.ignore(&repo)?;
+        let mut pathspec = Pathspec::parse(&repo, &self.args.files, self.pathspec_options)?;
+
+        if self.args.others {
+            let untracked = repo.untracked_files(&mut ignore, self.args.ignored)?;
+            let files = if self.args.ignored { untracked.ignored } else { untracked.others };
+            for path in files.iter().filter(|path| pathspec.matches(&path.as_string()).is_some()) {
+                println!("{}", path.as_string());
+            }
+        }
 
         for index_entry in repo.index.iter_entries() {
+            if pathspec.matches(&index_entry.path_name.as_string()).is_none() {
+                continue;
+            }
+            if self.args.ignored && !ignore.is_ignored(&index_entry.path_name.as_string(), false)? {
+                continue;
+            }
             if self.args.cached || self.args.stage {
                 if self.args.stage {
                     print!("{} {} {}\t", index_entry.mode, index_entry.name, index_entry.flags.stage)
@@ -33,11 +93,22 @@ impl GitCommand for LsFilesCommand {
                 println!("{}", index_entry.path_name.as_string());
 
                 if self.args.debug {
-                    println!("  ctime: {}", index_entry.last_data_update);
-                    println!("  mtime: {}", index_entry.last_metadata_update);
+                    println!("  ctime: {}", index_entry.last_metadata_update);
+                    println!("  mtime: {}", index_entry.last_data_update);
                     println!("  dev: {}\tino: {}", index_entry.dev, index_entry.ino);
                     println!("  uid: {}\tgid: {}", index_entry.uid, index_entry.gid);
-                    println!("  size: {}\tflags: {}", index_entry.file_size, index_entry.flags.stage);
+                    println!("  size: {}\tflags: {:x}", index_entry.file_size, index_entry.debug_flags());
+                }
+            }
+        }
+
+        if self.args.resolve_undo {
+            for (path, stages) in repo.index.resolve_undo().iter() {
+                if pathspec.matches(&path.as_string()).is_none() {
+                    continue;
+                }
+                for (stage, (mode, id)) in stages.iter().enumerate().filter_map(|(idx, stage)| Some((idx + 1, stage.as_ref()?))) {
+                    println!("{mode} {id} {stage}\t{}", path.as_string());
                 }
             }
         }
@@ -1,49 +1,110 @@
+mod attr;
+mod cache_tree;
+mod color;
 mod command;
 mod config;
+mod date;
+mod diff_format;
+mod diffcore;
+mod diffcore_rename;
+mod editor;
 mod error;
+mod gpg;
 mod hash;
+mod ignore;
 mod index;
+mod lockfile;
+mod notes_tree;
 mod object;
 mod object_store;
 mod options;
+mod pager;
+mod pathspec;
+mod pretty;
+mod ref_filter;
 mod refs;
+mod reftable;
 mod repo;
+mod resolve_undo;
+mod revision;
+mod tempfile;
+mod wildmatch;
+mod wt_status;
+mod xdiff;
 
 mod add;
+mod branch;
 mod cat_file;
+mod check_ignore;
+mod checkout_index;
 mod commit;
 mod commit_tree;
+mod diff;
+mod diff_files;
+mod diff_index;
+mod diff_tree;
+mod for_each_ref;
 mod hash_object;
 mod init;
+mod log;
 mod ls_files;
 mod mv;
+mod notes;
+mod read_tree;
+mod replace;
 mod restore;
 mod rm;
+mod show;
+mod show_ref;
+mod status;
 mod symbolic_ref;
 mod tag;
+mod update_index;
 mod update_ref;
+mod verify_commit;
+mod verify_tag;
 mod write_tree;
 
-use std::{path::PathBuf, process::ExitCode};
+use std::{env, path::PathBuf, process::ExitCode};
 
 use add::cli::AddArgs;
+use branch::cli::BranchArgs;
 use cat_file::cli::CatFileArgs;
-use clap::{Parser, Subcommand};
+use check_ignore::cli::CheckIgnoreArgs;
+use checkout_index::cli::CheckoutIndexArgs;
+use clap::{builder::FalseyValueParser, CommandFactory, FromArgMatches, Parser, Subcommand};
 
 use command::from_cli;
 use commit::cli::CommitArgs;
 use commit_tree::cli::CommitTreeArgs;
+use config::GitConfig;
+use diff::cli::DiffArgs;
+use diff_files::cli::DiffFilesArgs;
+use diff_index::cli::DiffIndexArgs;
+use diff_tree::cli::DiffTreeArgs;
 use error::RustGitError;
+use for_each_ref::cli::ForEachRefArgs;
 use hash_object::cli::HashObjectArgs;
 use init::cli::InitArgs;
+use log::cli::LogArgs;
 use ls_files::cli::LsFilesArgs;
 use mv::cli::MvArgs;
-use repo::GitRepo;
+use notes::cli::NotesArgs;
+use pager::Pager;
+use read_tree::cli::ReadTreeArgs;
+use replace::cli::ReplaceArgs;
+use repo::{GitRepo, RepoState};
 use restore::cli::RestoreArgs;
 use rm::cli::RmArgs;
+use show::cli::ShowArgs;
+use show_ref::cli::ShowRefArgs;
+use status::cli::StatusArgs;
 use symbolic_ref::cli::SymbolicRefArgs;
 use tag::cli::TagArgs;
+use update_index::cli::UpdateIndexArgs;
 use update_ref::cli::UpdateRefArgs;
+use verify_commit::cli::VerifyCommitArgs;
+use verify_tag::cli::VerifyTagArgs;
 use write_tree::cli::WriteTreeArgs;
 
 fn parse_config_override(s: &str) -> Result<(String, String), String> {
@@ -185,8 +246,8 @@ struct Cli {
 
     /// Set the Git namespace. See gitnamespaces(7) for more details.
     /// Equivalent to setting the GIT_NAMESPACE environment variable.
-    #[arg(long, value_name = "path", env = "GIT_NAMESPACE")]
-    namespace: Option<PathBuf>,
+    #[arg(long, value_name = "name", env = "GIT_NAMESPACE")]
+    namespace: Option<String>,
 
     /// Currently for internal use only. Set a prefix which gives a path
     /// from above a repository down to its root. One use is to give
@@ -200,8 +261,9 @@ struct Cli {
     bare: bool,
 
     /// Do not use replacement refs to replace Git objects. See git-
-    /// replace(1) for more information.
-    #[arg(long)]
+    /// replace(1) for more information. This is equivalent to setting the
+    /// GIT_NO_REPLACE_OBJECTS environment variable.
+    #[arg(long, env = "GIT_NO_REPLACE_OBJECTS", value_parser = FalseyValueParser::new())]
     no_replace_objects: bool,
 
     /// Treat pathspecs literally (i.e. no globbing, no pathspec magic).
@@ -230,7 +292,7 @@ struct Cli {
 
     /// Do not perform optional operations that require locks. This is
     /// equivalent to setting the GIT_OPTIONAL_LOCKS to 0.
-    #[arg(long, env = "GIT_OPTIONAL_LOCKS")]
+    #[arg(long)]
     no_optional_locks: bool,
 
     /// List commands by group. This is an internal/experimental option and
@@ -266,23 +328,77 @@ enum CliCommand {
     UpdateRef(UpdateRefArgs),
     SymbolicRef(SymbolicRefArgs),
     Tag(TagArgs),
+    ForEachRef(ForEachRefArgs),
+    ShowRef(ShowRefArgs),
+    Branch(BranchArgs),
+    VerifyCommit(VerifyCommitArgs),
+    VerifyTag(VerifyTagArgs),
+    Replace(ReplaceArgs),
+    Notes(NotesArgs),
+    Log(LogArgs),
+    Show(ShowArgs),
+    UpdateIndex(UpdateIndexArgs),
+    ReadTree(ReadTreeArgs),
+    CheckoutIndex(CheckoutIndexArgs),
+    CheckIgnore(CheckIgnoreArgs),
+    Status(StatusArgs),
+    Diff(DiffArgs),
+    DiffFiles(DiffFilesArgs),
+    DiffIndex(DiffIndexArgs),
+    DiffTree(DiffTreeArgs),
+}
+
+/// Reads GIT_OPTIONAL_LOCKS, which allows optional locks unless set to a false value. As it has
+/// the opposite meaning of --no-optional-locks, it can't be bound to the flag directly.
+fn optional_locks_from_env() -> bool {
+    match env::var("GIT_OPTIONAL_LOCKS") {
+        Ok(value) => !matches!(
+            value.to_lowercase().as_str(),
+            "" | "0" | "n" | "no" | "f" | "false" | "off"
+        ),
+        Err(_) => true,
+    }
 }
 
-fn load_repo_and_execute(cli: Cli) -> Result<(), RustGitError> {
-    let repo = GitRepo::new(&cli.git_dir)?;
+fn load_repo_and_execute(cli: Cli, command_name: &str) -> Result<(), RustGitError> {
+    let repo = GitRepo::new(
+        &cli.git_dir,
+        cli.namespace.as_deref(),
+        !cli.no_replace_objects,
+        !cli.no_optional_locks && optional_locks_from_env(),
+    )?;
 
+    let (paginate, no_pager) = (cli.paginate, cli.no_pager);
     let command = from_cli(cli)?;
+    // The pager is kept until the command is done writing to it.
+    let _pager = {
+        let default_config = GitConfig::default();
+        let config = match &repo {
+            RepoState::Repo(repo) => &repo.config,
+            _ => &default_config,
+        };
+        Pager::setup(
+            config,
+            command_name,
+            command.pages_by_default(),
+            paginate,
+            no_pager,
+        )
+    };
     command.execute(repo)
 }
 
 fn main() -> ExitCode {
-    let cli = Cli::parse();
+    // The name of the command is needed to look up its pager.
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let command_name = matches.subcommand_name().unwrap_or_default().to_string();
 
-    match load_repo_and_execute(cli) {
+    match load_repo_and_execute(cli, &command_name) {
         Ok(_) => ExitCode::SUCCESS,
         Err(err) => {
-            eprint!("{}", err.to_string());
-            ExitCode::FAILURE
+            eprint!("{err}");
+            ExitCode::from(err.exit_code())
         }
     }
 }
@@ -185,10 +185,12 @@ impl GitCommand for MvCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError>
     {
         let mut repo = repo_state.try_get()?;
+        repo.lock_index()?;
 
         let (sources, destination) = get_src_and_dst(&self.args.files)?;
 
         let destination_repo_path = repo.path_to_git_repo_path(Path::new(destination))?;
+        let mut moved_paths = Vec::new();
 
         for source in sources {
             let source_repo_path = repo.path_to_git_repo_path(Path::new(source))?;
@@ -213,6 +215,7 @@ impl GitCommand for MvCommand {
 
                             if action.update_index {
                                 repo.index.rename_entry_by_path(&action.source, &action.destination);
+                                moved_paths.push(action.destination.clone());
                             }
                         }
                     }
@@ -228,6 +231,11 @@ impl GitCommand for MvCommand {
         // TODO: cleanup empty source dirs:
         // https://github.com/git/git/blob/master/builtin/mv.c#L539
 
+        // Renaming a file changes its ctime, so the stat data of the moved entries is refreshed
+        // to spare later commands from hashing them. Files which changed are left for those
+        // commands to report.
+        repo.refresh_index(false, |entry| moved_paths.contains(&entry.path_name))?;
+
         repo.write_index()?;
 
         Ok(())
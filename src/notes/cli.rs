@@ -0,0 +1,217 @@
+use clap::{ArgGroup, Args, Subcommand};
+
+use crate::notes_tree::NotesMergeStrategy;
+
+#[derive(Args, Debug)]
+#[command(about = "Add or inspect object notes")]
+#[command(long_about = "
+Adds, removes, or reads notes attached to objects, without touching the objects themselves.
+
+By default, notes are saved to and read from refs/notes/commits, but this default can be overridden. See the
+OPTIONS, CONFIGURATION, and ENVIRONMENT sections below. If this ref does not exist, it will be quietly created
+when it is first needed to store a note.
+
+A typical use of notes is to supplement a commit message without changing the commit itself. Notes can be
+shown by git log along with the original commit message. To distinguish these notes from the message stored in
+the commit object, the notes are indented like the message, after an unindented line saying \"Notes
+(<refname>):\" (or \"Notes:\" for refs/notes/commits).
+
+Notes can also be added to patches prepared with git format-patch by using the --notes option. Such notes are
+added as a patch commentary after a three dash separator line.
+
+To change which notes are shown by git log, see the \"notes.displayRef\" discussion in the section called
+\"CONFIGURATION\".
+
+See the \"notes.rewrite.<command>\" configuration for a way to carry notes across commands that rewrite
+commits.
+")]
+pub(crate) struct NotesArgs {
+    /// Manipulate the notes tree in <ref>. This overrides GIT_NOTES_REF and the "core.notesRef"
+    /// configuration. The ref specifies the full refname when it begins with refs/notes/; when it begins with
+    /// notes/, refs/ and otherwise refs/notes/ is prefixed to form a full name of the ref.
+    #[arg(long = "ref", value_name = "ref", global = true)]
+    pub notes_ref: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<NotesSubcommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum NotesSubcommand {
+    /// List the notes object for a given object. If no object is given, show a list of all note objects and
+    /// the objects they annotate (in the format "<note-object> <annotated-object>"). This is the default
+    /// subcommand if no subcommand is given.
+    List {
+        #[arg(value_name = "object")]
+        object: Option<String>,
+    },
+
+    /// Add notes for a given object (defaults to HEAD). Abort if the object already has notes (use -f to
+    /// overwrite existing notes). However, if you're using add interactively (using an editor to supply the
+    /// notes contents), then - instead of aborting - the existing notes will be opened in the editor (like the
+    /// edit subcommand).
+    Add {
+        #[command(flatten)]
+        message: NotesMessageArgs,
+
+        /// When adding notes to an object that already has notes, overwrite the existing notes (instead of
+        /// aborting).
+        #[arg(short, long)]
+        force: bool,
+
+        #[arg(value_name = "object")]
+        object: Option<String>,
+    },
+
+    /// Copy the notes for the first object onto the second object (defaults to HEAD). Abort if the second
+    /// object already has notes, or if the first object has none (use -f to overwrite existing notes to the
+    /// second object). This subcommand is equivalent to: git notes add [-f] -C $(git notes list <from-object>)
+    /// <to-object>
+    ///
+    /// In --stdin mode, take lines in the format
+    ///
+    ///     <from-object> SP <to-object> [ SP <rest> ] LF
+    ///
+    /// on standard input, and copy the notes from each <from-object> to its corresponding <to-object>. (The
+    /// optional <rest> is ignored so that the command can read the input given to the post-rewrite hook.)
+    Copy {
+        /// When adding notes to an object that already has notes, overwrite the existing notes (instead of
+        /// aborting).
+        #[arg(short, long)]
+        force: bool,
+
+        /// Also read the object names to copy notes from and to from the standard input.
+        #[arg(long)]
+        stdin: bool,
+
+        #[arg(value_name = "object")]
+        objects: Vec<String>,
+    },
+
+    /// Append new message(s) given by -m or -F options to an existing note, or add them as a new note if one
+    /// does not exist, for the object (defaults to HEAD). When appending to an existing note, a blank line is
+    /// added before each new message as an inter-paragraph separator.
+    Append {
+        #[command(flatten)]
+        message: NotesMessageArgs,
+
+        #[arg(value_name = "object")]
+        object: Option<String>,
+    },
+
+    /// Edit the notes for a given object (defaults to HEAD).
+    Edit {
+        /// Allow an empty note object to be stored. The default behavior is to automatically remove empty
+        /// notes.
+        #[arg(long)]
+        allow_empty: bool,
+
+        #[arg(value_name = "object")]
+        object: Option<String>,
+    },
+
+    /// Show the notes for a given object (defaults to HEAD).
+    Show {
+        #[arg(value_name = "object")]
+        object: Option<String>,
+    },
+
+    /// Merge the given notes ref into the current notes ref. This will try to merge the changes made by the
+    /// given notes ref (called "remote") since the merge-base (if any) into the current notes ref (called
+    /// "local").
+    ///
+    /// If conflicts arise and a strategy for automatically resolving conflicting notes (see the "NOTES MERGE
+    /// STRATEGIES" section) is not given, the "manual" resolver is used. This resolver checks out the
+    /// conflicting notes in a special worktree (.git/NOTES_MERGE_WORKTREE), and instructs the user to manually
+    /// resolve the conflicts there. When done, the user can either finalize the merge with git notes merge
+    /// --commit, or abort the merge with git notes merge --abort.
+    #[command(group(ArgGroup::new("finish").args(["commit", "abort"])))]
+    Merge {
+        /// When merging notes, resolve notes conflicts using the given strategy. The following strategies
+        /// are recognized: "manual" (default), "ours", "theirs", "union" and "cat_sort_uniq". This option
+        /// overrides the "notes.mergeStrategy" configuration setting.
+        #[arg(short, long, value_name = "strategy")]
+        strategy: Option<NotesMergeStrategy>,
+
+        /// Finalize an in-progress git notes merge. Use this option when you have resolved the conflicts that
+        /// git notes merge stored in .git/NOTES_MERGE_WORKTREE. This amends the partial merge commit created by
+        /// git notes merge (stored in .git/NOTES_MERGE_PARTIAL) by adding the notes in
+        /// .git/NOTES_MERGE_WORKTREE. The notes ref stored in the .git/NOTES_MERGE_REF symref is updated to the
+        /// resulting commit.
+        #[arg(long)]
+        commit: bool,
+
+        /// Abort/reset an in-progress git notes merge, i.e. a notes merge with conflicts. This simply removes
+        /// all files related to the notes merge.
+        #[arg(long)]
+        abort: bool,
+
+        #[arg(value_name = "notes-ref", conflicts_with = "finish")]
+        remote_ref: Option<String>,
+    },
+
+    /// Remove the notes for given objects (defaults to HEAD). When giving zero or one object from the command
+    /// line, this is equivalent to specifying an empty note message to the edit subcommand.
+    Remove {
+        /// Do not consider it an error to request removing notes from an object that does not have notes
+        /// attached to it.
+        #[arg(long)]
+        ignore_missing: bool,
+
+        /// Also read the object names to remove notes from the standard input (there is no reason you cannot
+        /// combine this with object names from the command line).
+        #[arg(long)]
+        stdin: bool,
+
+        #[arg(value_name = "object")]
+        objects: Vec<String>,
+    },
+
+    /// Remove all notes for non-existing/unreachable objects.
+    Prune {
+        /// Do not remove anything; just report the object names whose notes would be removed.
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Report all object names whose notes are removed.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Print the current notes ref. This provides an easy way to retrieve the current notes ref (e.g. from
+    /// scripts).
+    GetRef,
+}
+
+/// The options shared by `git notes add` and `git notes append`.
+#[derive(Args, Debug)]
+pub(crate) struct NotesMessageArgs {
+    /// Use the given note message (instead of prompting). If multiple -m options are given, their values are
+    /// concatenated as separate paragraphs. Lines starting with # and empty lines other than a single line
+    /// between paragraphs will be stripped out.
+    #[arg(short, long = "message", value_name = "msg")]
+    pub messages: Vec<String>,
+
+    /// Take the note message from the given file. Use - to read the note message from the standard input.
+    /// Lines starting with # and empty lines other than a single line between paragraphs will be stripped out.
+    #[arg(short = 'F', long = "file", value_name = "file")]
+    pub files: Vec<String>,
+
+    /// Take the given blob object (for example, another note) as the note message. (Use git notes copy
+    /// <object> instead to copy notes between objects.)
+    #[arg(short = 'C', long, value_name = "object")]
+    pub reuse_message: Option<String>,
+
+    /// Like -C, but with -c the editor is invoked, so that the user can further edit the note message.
+    #[arg(
+        short = 'c',
+        long,
+        value_name = "object",
+        conflicts_with = "reuse_message"
+    )]
+    pub reedit_message: Option<String>,
+
+    /// Allow an empty note object to be stored. The default behavior is to automatically remove empty notes.
+    #[arg(long)]
+    pub allow_empty: bool,
+}
@@ -0,0 +1,775 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, BufRead, Read},
+};
+
+use crate::{
+    color::Palette,
+    command::GitCommand,
+    editor::{launch_editor, strip_space},
+    notes_tree::{
+        combine_notes, default_notes_ref, expand_notes_ref, GitNotes, NotesMergeStrategy,
+    },
+    object::{GitObjectId, GitObjectType},
+    pretty::{format_commit, CommitFormat},
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
+
+use super::cli::{NotesArgs, NotesMessageArgs, NotesSubcommand};
+
+const EDIT_FILE: &str = "NOTES_EDITMSG";
+const MERGE_WORKTREE: &str = "NOTES_MERGE_WORKTREE";
+const MERGE_PARTIAL: &str = "NOTES_MERGE_PARTIAL";
+const MERGE_REF: &str = "NOTES_MERGE_REF";
+
+/// The contents of a note given on the command line, rather than written in an editor.
+struct NoteMessage {
+    /// The paragraphs given by -m and -F, already cleaned up.
+    message: Option<String>,
+    /// The blob given by -C or -c to take the note from.
+    reuse: Option<String>,
+    /// Whether the note should still be edited, because -c was given.
+    edit: bool,
+    allow_empty: bool,
+}
+
+enum MergeAction {
+    Merge {
+        remote_ref: String,
+        strategy: Option<NotesMergeStrategy>,
+    },
+    Commit,
+    Abort,
+}
+
+enum NotesAction {
+    List {
+        object: Option<String>,
+    },
+    Add {
+        message: NoteMessage,
+        force: bool,
+        object: String,
+    },
+    Append {
+        message: NoteMessage,
+        object: String,
+    },
+    Copy {
+        force: bool,
+        pairs: Vec<(String, String)>,
+    },
+    Edit {
+        allow_empty: bool,
+        object: String,
+    },
+    Show {
+        object: String,
+    },
+    Merge(MergeAction),
+    Remove {
+        ignore_missing: bool,
+        objects: Vec<String>,
+    },
+    Prune {
+        dry_run: bool,
+        verbose: bool,
+    },
+    GetRef,
+}
+
+pub(crate) struct NotesCommand {
+    notes_ref: Option<String>,
+    action: NotesAction,
+}
+
+fn read_message_file(file: &str) -> Result<String, RustGitError> {
+    if file == "-" {
+        let mut message = String::new();
+        io::stdin().read_to_string(&mut message)?;
+        Ok(message)
+    } else {
+        fs::read_to_string(file)
+            .map_err(|_| RustGitError::new(format!("fatal: could not open or read '{file}'")))
+    }
+}
+
+/// Reads the whitespace separated object names given on standard input, one group per line.
+fn read_stdin_objects() -> Result<Vec<Vec<String>>, RustGitError> {
+    io::stdin()
+        .lock()
+        .lines()
+        .map(|line| {
+            Ok(line?
+                .split_whitespace()
+                .map(|object| object.to_string())
+                .collect())
+        })
+        .collect()
+}
+
+impl NoteMessage {
+    fn new(args: NotesMessageArgs) -> Result<NoteMessage, RustGitError> {
+        // Like C Git, each -m and -F is a paragraph of its own.
+        let mut paragraphs = Vec::new();
+        for message in args.messages {
+            paragraphs.push(strip_space(&message, false));
+        }
+        for file in args.files {
+            paragraphs.push(strip_space(&read_message_file(&file)?, false));
+        }
+
+        let message = (!paragraphs.is_empty()).then(|| {
+            let paragraphs = paragraphs
+                .into_iter()
+                .filter(|paragraph| !paragraph.is_empty())
+                .collect::<Vec<_>>();
+            paragraphs.join("\n")
+        });
+
+        Ok(NoteMessage {
+            message,
+            edit: args.reedit_message.is_some(),
+            reuse: args.reuse_message.or(args.reedit_message),
+            allow_empty: args.allow_empty,
+        })
+    }
+
+    /// Whether the note is fully given, so that no editor needs to be started.
+    fn is_given(&self) -> bool {
+        (self.message.is_some() || self.reuse.is_some()) && !self.edit
+    }
+
+    /// Returns the note given by -C or -c, followed by the paragraphs given by -m and -F.
+    fn contents(&self, repo: &GitRepo) -> Result<String, RustGitError> {
+        let mut contents = String::new();
+        if let Some(reuse) = &self.reuse {
+            let blob = resolve_object(repo, reuse)?;
+            if repo.object_type(&blob)? != GitObjectType::Blob {
+                return Err(RustGitError::new(format!(
+                    "fatal: cannot read note data from non-blob object '{reuse}'."
+                )));
+            }
+            contents = GitNotes::read_note(repo, &blob)?;
+        }
+        if let Some(message) = &self.message {
+            if !contents.is_empty() && !message.is_empty() {
+                contents.push('\n');
+            }
+            contents.push_str(message);
+        }
+        Ok(contents)
+    }
+}
+
+impl NotesCommand {
+    pub fn new(args: NotesArgs) -> Result<NotesCommand, RustGitError> {
+        let head = || String::from("HEAD");
+
+        let action = match args.command {
+            None => NotesAction::List { object: None },
+            Some(NotesSubcommand::List { object }) => NotesAction::List { object },
+            Some(NotesSubcommand::Add {
+                message,
+                force,
+                object,
+            }) => NotesAction::Add {
+                message: NoteMessage::new(message)?,
+                force,
+                object: object.unwrap_or_else(head),
+            },
+            Some(NotesSubcommand::Append { message, object }) => NotesAction::Append {
+                message: NoteMessage::new(message)?,
+                object: object.unwrap_or_else(head),
+            },
+            Some(NotesSubcommand::Copy {
+                force,
+                stdin,
+                mut objects,
+            }) => {
+                let pairs = if stdin {
+                    if !objects.is_empty() {
+                        return Err(RustGitError::new("error: too many arguments"));
+                    }
+                    read_stdin_objects()?
+                        .into_iter()
+                        .map(|objects| match objects.as_slice() {
+                            [from, to, ..] => Ok((from.clone(), to.clone())),
+                            _ => Err(RustGitError::new("fatal: malformed input line")),
+                        })
+                        .collect::<Result<_, _>>()?
+                } else {
+                    match objects.len() {
+                        0 => return Err(RustGitError::new("error: too few arguments")),
+                        1 => objects.push(head()),
+                        2 => (),
+                        _ => return Err(RustGitError::new("error: too many arguments")),
+                    }
+                    let to = objects.pop().unwrap();
+                    vec![(objects.pop().unwrap(), to)]
+                };
+                NotesAction::Copy { force, pairs }
+            }
+            Some(NotesSubcommand::Edit {
+                allow_empty,
+                object,
+            }) => NotesAction::Edit {
+                allow_empty,
+                object: object.unwrap_or_else(head),
+            },
+            Some(NotesSubcommand::Show { object }) => NotesAction::Show {
+                object: object.unwrap_or_else(head),
+            },
+            Some(NotesSubcommand::Merge {
+                strategy,
+                commit,
+                abort,
+                remote_ref,
+            }) => NotesAction::Merge(match (commit, abort, remote_ref) {
+                (true, _, _) => MergeAction::Commit,
+                (_, true, _) => MergeAction::Abort,
+                (_, _, Some(remote_ref)) => MergeAction::Merge {
+                    remote_ref: expand_notes_ref(&remote_ref),
+                    strategy,
+                },
+                (_, _, None) => {
+                    return Err(RustGitError::new(
+                        "error: must specify a notes ref to merge",
+                    ))
+                }
+            }),
+            Some(NotesSubcommand::Remove {
+                ignore_missing,
+                stdin,
+                mut objects,
+            }) => {
+                if stdin {
+                    objects.extend(read_stdin_objects()?.into_iter().flatten());
+                } else if objects.is_empty() {
+                    objects.push(head());
+                }
+                NotesAction::Remove {
+                    ignore_missing,
+                    objects,
+                }
+            }
+            Some(NotesSubcommand::Prune { dry_run, verbose }) => {
+                NotesAction::Prune { dry_run, verbose }
+            }
+            Some(NotesSubcommand::GetRef) => NotesAction::GetRef,
+        };
+
+        Ok(NotesCommand {
+            notes_ref: args.notes_ref.as_deref().map(expand_notes_ref),
+            action,
+        })
+    }
+}
+
+fn resolve_object(repo: &GitRepo, name: &str) -> Result<GitObjectId, RustGitError> {
+    repo.resolve_revision(name).map_err(|_| {
+        RustGitError::new(format!("fatal: failed to resolve '{name}' as a valid ref."))
+    })
+}
+
+fn no_note_found(object: &GitObjectId) -> RustGitError {
+    RustGitError::new(format!("error: no note found for object {object}."))
+}
+
+/// Lets the user write a note for an object in their editor, starting from the provided note.
+/// The result is cleaned up, dropping the comment lines describing the object.
+fn edit_note(repo: &GitRepo, object: &GitObjectId, initial: &str) -> Result<String, RustGitError> {
+    let mut contents = initial.to_string();
+    contents.push_str("\n#\n# Write/edit the notes for the following object:\n#\n");
+    match repo.object_type(object)? {
+        GitObjectType::Commit => {
+            let commit = repo.read_commit(object)?;
+            for line in
+                format_commit(object, &commit, CommitFormat::Medium, &Palette::default()).lines()
+            {
+                contents.push_str(format!("# {line}").trim_end());
+                contents.push('\n');
+            }
+        }
+        obj_type => contents.push_str(&format!("# {obj_type} {object}\n")),
+    }
+
+    let path = repo.git_dir.join(EDIT_FILE);
+    fs::write(&path, contents)?;
+    launch_editor(&repo.config, &path).map_err(|err| {
+        RustGitError::new(format!(
+            "{err}\nPlease supply the note contents using either -m or -F option"
+        ))
+    })?;
+    let edited = fs::read_to_string(&path)?;
+    fs::remove_file(&path)?;
+
+    Ok(strip_space(&edited, true))
+}
+
+/// Stores a note for an object, or removes the object's note if it's empty and empty notes
+/// aren't allowed, then commits the change.
+fn save_note(
+    repo: &GitRepo,
+    notes: &mut GitNotes,
+    object: &GitObjectId,
+    contents: &str,
+    allow_empty: bool,
+    subcommand: &str,
+) -> Result<(), RustGitError> {
+    if contents.is_empty() && !allow_empty {
+        eprintln!("Removing note for object {object}");
+        notes.remove(object);
+        notes.commit(repo, &format!("Notes removed by 'git notes {subcommand}'"))?;
+    } else {
+        let note = GitNotes::write_note(repo, contents)?;
+        notes.set(object, note);
+        notes.commit(repo, &format!("Notes added by 'git notes {subcommand}'"))?;
+    }
+    Ok(())
+}
+
+impl NotesCommand {
+    fn add(
+        repo: &GitRepo,
+        notes: &mut GitNotes,
+        message: &NoteMessage,
+        force: bool,
+        object_name: &str,
+    ) -> Result<(), RustGitError> {
+        notes.check_writable("add")?;
+        let object = resolve_object(repo, object_name)?;
+
+        let existing = notes.get(&object).cloned();
+        if let Some(existing) = &existing {
+            if !force {
+                // Without a message, the existing note is edited instead, as `notes edit` does.
+                if !message.is_given() && message.reuse.is_none() {
+                    let contents = GitNotes::read_note(repo, existing)?;
+                    let contents = edit_note(repo, &object, &contents)?;
+                    return save_note(repo, notes, &object, &contents, message.allow_empty, "add");
+                }
+                return Err(RustGitError::new(format!("error: Cannot add notes. Found existing notes for object {object}. Use '-f' to overwrite existing notes")));
+            }
+            eprintln!("Overwriting existing notes for object {object}");
+        }
+
+        let mut contents = message.contents(repo)?;
+        if !message.is_given() {
+            contents = edit_note(repo, &object, &contents)?;
+        }
+        save_note(repo, notes, &object, &contents, message.allow_empty, "add")
+    }
+
+    fn append(
+        repo: &GitRepo,
+        notes: &mut GitNotes,
+        message: &NoteMessage,
+        object_name: &str,
+    ) -> Result<(), RustGitError> {
+        notes.check_writable("append")?;
+        let object = resolve_object(repo, object_name)?;
+
+        let mut contents = message.contents(repo)?;
+        if !message.is_given() {
+            contents = edit_note(repo, &object, &contents)?;
+        }
+
+        // The new note becomes a paragraph of its own after the existing note.
+        if let Some(existing) = notes.get(&object) {
+            let existing = GitNotes::read_note(repo, existing)?;
+            if !contents.is_empty() && !existing.is_empty() {
+                contents = format!("{existing}\n{contents}");
+            } else if contents.is_empty() {
+                contents = existing;
+            }
+        }
+
+        save_note(
+            repo,
+            notes,
+            &object,
+            &contents,
+            message.allow_empty,
+            "append",
+        )
+    }
+
+    fn edit(
+        repo: &GitRepo,
+        notes: &mut GitNotes,
+        allow_empty: bool,
+        object_name: &str,
+    ) -> Result<(), RustGitError> {
+        notes.check_writable("edit")?;
+        let object = resolve_object(repo, object_name)?;
+
+        let existing = match notes.get(&object) {
+            Some(note) => GitNotes::read_note(repo, note)?,
+            None => String::new(),
+        };
+        let contents = edit_note(repo, &object, &existing)?;
+        save_note(repo, notes, &object, &contents, allow_empty, "edit")
+    }
+
+    fn copy(
+        repo: &GitRepo,
+        notes: &mut GitNotes,
+        force: bool,
+        pairs: &[(String, String)],
+    ) -> Result<(), RustGitError> {
+        notes.check_writable("copy")?;
+
+        for (from_name, to_name) in pairs {
+            let from = resolve_object(repo, from_name)?;
+            let to = resolve_object(repo, to_name)?;
+
+            if notes.get(&to).is_some() {
+                if !force {
+                    return Err(RustGitError::new(format!("error: Cannot copy notes. Found existing notes for object {to}. Use '-f' to overwrite existing notes")));
+                }
+                eprintln!("Overwriting existing notes for object {to}");
+            }
+
+            let note = notes.get(&from).cloned().ok_or_else(|| {
+                RustGitError::new(format!(
+                    "error: missing notes on source object {from}. Cannot copy."
+                ))
+            })?;
+            notes.set(&to, note);
+        }
+
+        notes.commit(repo, "Notes added by 'git notes copy'")?;
+        Ok(())
+    }
+
+    fn remove(
+        repo: &GitRepo,
+        notes: &mut GitNotes,
+        ignore_missing: bool,
+        objects: &[String],
+    ) -> Result<(), RustGitError> {
+        notes.check_writable("remove")?;
+
+        // As in C Git, every object is processed before reporting missing notes.
+        let mut errors = Vec::new();
+        for object_name in objects {
+            let object = resolve_object(repo, object_name)?;
+            if notes.remove(&object) {
+                eprintln!("Removing note for object {object_name}");
+            } else {
+                eprintln!("Object {object_name} has no note");
+                if !ignore_missing {
+                    errors.push(object_name);
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(RustGitError::new(""));
+        }
+        notes.commit(repo, "Notes removed by 'git notes remove'")?;
+        Ok(())
+    }
+
+    fn prune(
+        repo: &GitRepo,
+        notes: &mut GitNotes,
+        dry_run: bool,
+        verbose: bool,
+    ) -> Result<(), RustGitError> {
+        notes.check_writable("prune")?;
+
+        let pruned = notes
+            .iter()
+            .map(|(object, _)| object.clone())
+            .filter(|object| !repo.obj_store.is_valid_object_id(object))
+            .collect::<Vec<_>>();
+
+        for object in &pruned {
+            if verbose || dry_run {
+                println!("{object}");
+            }
+            notes.remove(object);
+        }
+
+        if !dry_run {
+            notes.commit(repo, "Notes removed by 'git notes prune'")?;
+        }
+        Ok(())
+    }
+
+    /// Removes the state of an in-progress notes merge.
+    fn abort_merge(repo: &GitRepo) -> Result<(), RustGitError> {
+        let worktree = repo.git_dir.join(MERGE_WORKTREE);
+        if worktree.exists() {
+            fs::remove_dir_all(worktree)?;
+        }
+        repo.refs.delete_ref(MERGE_PARTIAL)?;
+        repo.refs.delete_ref(MERGE_REF)
+    }
+
+    /// Finishes a notes merge which stopped because of conflicts, by adding the notes left in the
+    /// merge worktree to the partial merge commit.
+    fn commit_merge(repo: &GitRepo) -> Result<(), RustGitError> {
+        let partial_id = repo.refs.resolve_ref(MERGE_PARTIAL)?.ok_or_else(|| {
+            RustGitError::new(format!("fatal: failed to read ref {MERGE_PARTIAL}"))
+        })?;
+        let local_ref = repo
+            .get_symbolic_ref(MERGE_REF)?
+            .and_then(|target| {
+                target
+                    .strip_prefix("ref: ")
+                    .map(|ref_name| ref_name.to_string())
+            })
+            .ok_or_else(|| RustGitError::new(format!("fatal: failed to resolve {MERGE_REF}")))?;
+        let partial = repo.read_commit(&partial_id)?;
+
+        let mut notes = GitNotes::load_commit(repo, &local_ref, Some(&partial_id))?;
+        let worktree = repo.git_dir.join(MERGE_WORKTREE);
+        if worktree.exists() {
+            for entry in fs::read_dir(&worktree)? {
+                let entry = entry?;
+                let object = GitObjectId::new(entry.file_name().to_string_lossy().to_string());
+                let note = GitNotes::write_note(repo, &fs::read_to_string(entry.path())?)?;
+                notes.set(&object, note);
+            }
+        }
+
+        // The notes ref is expected to be where it was when the merge started.
+        notes.tip = partial.parents.first().cloned();
+        notes.commit_with_parents(repo, &partial.parents, &partial.message)?;
+        Self::abort_merge(repo)
+    }
+
+    fn merge(
+        repo: &GitRepo,
+        local: &mut GitNotes,
+        remote_ref: &str,
+        strategy: Option<NotesMergeStrategy>,
+    ) -> Result<(), RustGitError> {
+        local.check_writable("merge")?;
+
+        if repo.refs.ref_exists(MERGE_PARTIAL)? || repo.refs.ref_exists(MERGE_REF)? {
+            return Err(RustGitError::new("fatal: You have not concluded your previous notes merge (.git/NOTES_MERGE_* exists).\nPlease, use 'git notes merge --commit' or 'git notes merge --abort' to commit/abort the previous merge before you start a new notes merge."));
+        }
+
+        let strategy = strategy
+            .or(repo.config.notes.mergestrategy)
+            .unwrap_or_default();
+
+        let remote_id = repo.refs.resolve_ref(remote_ref)?;
+        let (local_id, remote_id) = match (local.tip.clone(), remote_id) {
+            (None, None) => {
+                return Err(RustGitError::new(format!(
+                    "fatal: Cannot merge empty notes ref ({remote_ref}) into empty notes ref ({})",
+                    local.ref_name
+                )))
+            }
+            (_, None) => {
+                println!("Already up to date.");
+                return Ok(());
+            }
+            (None, Some(remote_id)) => {
+                println!("Fast-forward");
+                return local.update_ref(repo, &remote_id);
+            }
+            (Some(local_id), Some(remote_id)) => (local_id, remote_id),
+        };
+
+        let base_id = repo.merge_base(&local_id, &remote_id)?;
+        if base_id.as_ref() == Some(&remote_id) {
+            println!("Already up to date.");
+            return Ok(());
+        }
+        if base_id.as_ref() == Some(&local_id) {
+            println!("Fast-forward");
+            return local.update_ref(repo, &remote_id);
+        }
+
+        let base = GitNotes::load_commit(repo, &local.ref_name, base_id.as_ref())?;
+        let remote = GitNotes::load_commit(repo, remote_ref, Some(&remote_id))?;
+
+        let objects = base
+            .iter()
+            .chain(local.iter())
+            .chain(remote.iter())
+            .map(|(object, _)| object.clone())
+            .collect::<BTreeSet<_>>();
+
+        let mut conflicts = Vec::new();
+        for object in objects {
+            let base_note = base.get(&object);
+            let local_note = local.get(&object).cloned();
+            let remote_note = remote.get(&object).cloned();
+
+            if local_note == remote_note || remote_note.as_ref() == base_note {
+                continue;
+            }
+            if local_note.as_ref() == base_note {
+                match remote_note {
+                    Some(note) => local.set(&object, note),
+                    None => {
+                        local.remove(&object);
+                    }
+                }
+                continue;
+            }
+
+            // Both sides changed the note differently.
+            let local_contents = match &local_note {
+                Some(note) => Some(GitNotes::read_note(repo, note)?),
+                None => None,
+            };
+            let remote_contents = match &remote_note {
+                Some(note) => Some(GitNotes::read_note(repo, note)?),
+                None => None,
+            };
+
+            let combined = match (&local_contents, &remote_contents) {
+                (Some(ours), Some(theirs)) => combine_notes(strategy, ours, theirs),
+                _ => match strategy {
+                    NotesMergeStrategy::Manual => None,
+                    NotesMergeStrategy::Ours => local_contents.clone(),
+                    _ => local_contents.clone().or(remote_contents.clone()),
+                },
+            };
+
+            match strategy {
+                NotesMergeStrategy::Manual => (),
+                NotesMergeStrategy::Ours => println!("Using local notes for {object}"),
+                NotesMergeStrategy::Theirs => println!("Using remote notes for {object}"),
+                NotesMergeStrategy::Union => {
+                    println!("Concatenating local and remote notes for {object}")
+                }
+                NotesMergeStrategy::CatSortUniq => {
+                    println!("Concatenating unique lines in local and remote notes for {object}")
+                }
+            }
+
+            match combined {
+                Some(contents) => {
+                    let note = GitNotes::write_note(repo, &contents)?;
+                    local.set(&object, note);
+                }
+                None if strategy != NotesMergeStrategy::Manual => {
+                    local.remove(&object);
+                }
+                None => {
+                    let contents = match (&local_contents, &remote_contents) {
+                        (Some(ours), Some(theirs)) => {
+                            println!("Auto-merging notes for {object}");
+                            println!(
+                                "CONFLICT (content): Merge conflict in notes for object {object}"
+                            );
+                            format!(
+                                "<<<<<<< {}\n{ours}=======\n{theirs}>>>>>>> {remote_ref}\n",
+                                local.ref_name
+                            )
+                        }
+                        (Some(ours), None) => {
+                            println!("CONFLICT (delete/modify): Notes for object {object} deleted in {remote_ref} and modified in {}. Version from {} left in tree.", local.ref_name, local.ref_name);
+                            ours.clone()
+                        }
+                        (None, Some(theirs)) => {
+                            println!("CONFLICT (delete/modify): Notes for object {object} deleted in {} and modified in {remote_ref}. Version from {remote_ref} left in tree.", local.ref_name);
+                            theirs.clone()
+                        }
+                        (None, None) => unreachable!("notes can't conflict if both were deleted"),
+                    };
+
+                    let worktree = repo.git_dir.join(MERGE_WORKTREE);
+                    fs::create_dir_all(&worktree)?;
+                    fs::write(worktree.join(object.to_string()), contents)?;
+                    // Conflicting notes are left out of the partial merge commit.
+                    local.remove(&object);
+                    conflicts.push(object);
+                }
+            }
+        }
+
+        let mut message = format!("Merged notes from {remote_ref} into {}", local.ref_name);
+        let parents = vec![local_id, remote_id];
+        if conflicts.is_empty() {
+            local.commit_with_parents(repo, &parents, &message)?;
+            return Ok(());
+        }
+
+        message.push_str("\n\nConflicts:\n");
+        for object in &conflicts {
+            message.push_str(&format!("\t{object}\n"));
+        }
+        let tree_id = local.write_tree(repo)?;
+        let partial_id = repo.write_commit(&tree_id, &parents, &message, None)?;
+        repo.refs.set_ref(MERGE_PARTIAL, &partial_id)?;
+        repo.update_symbolic_ref(MERGE_REF, &local.ref_name)?;
+
+        Err(RustGitError::new(format!("Automatic notes merge failed. Fix conflicts in .git/{MERGE_WORKTREE} and commit the result with 'git notes merge --commit', or abort the merge with 'git notes merge --abort'.")))
+    }
+}
+
+impl GitCommand for NotesCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+        let notes_ref = self
+            .notes_ref
+            .clone()
+            .unwrap_or_else(|| default_notes_ref(&repo.config));
+        let mut notes = GitNotes::load(&repo, &notes_ref)?;
+
+        match &self.action {
+            NotesAction::List { object: None } => {
+                for (object, note) in notes.iter() {
+                    println!("{note} {object}");
+                }
+                Ok(())
+            }
+            NotesAction::List {
+                object: Some(object_name),
+            } => {
+                let object = resolve_object(&repo, object_name)?;
+                let note = notes.get(&object).ok_or_else(|| no_note_found(&object))?;
+                println!("{note}");
+                Ok(())
+            }
+            NotesAction::Show { object } => {
+                let object = resolve_object(&repo, object)?;
+                let note = notes.get(&object).ok_or_else(|| no_note_found(&object))?;
+                print!("{}", GitNotes::read_note(&repo, note)?);
+                Ok(())
+            }
+            NotesAction::Add {
+                message,
+                force,
+                object,
+            } => Self::add(&repo, &mut notes, message, *force, object),
+            NotesAction::Append { message, object } => {
+                Self::append(&repo, &mut notes, message, object)
+            }
+            NotesAction::Edit {
+                allow_empty,
+                object,
+            } => Self::edit(&repo, &mut notes, *allow_empty, object),
+            NotesAction::Copy { force, pairs } => Self::copy(&repo, &mut notes, *force, pairs),
+            NotesAction::Remove {
+                ignore_missing,
+                objects,
+            } => Self::remove(&repo, &mut notes, *ignore_missing, objects),
+            NotesAction::Prune { dry_run, verbose } => {
+                Self::prune(&repo, &mut notes, *dry_run, *verbose)
+            }
+            NotesAction::Merge(MergeAction::Merge {
+                remote_ref,
+                strategy,
+            }) => Self::merge(&repo, &mut notes, remote_ref, *strategy),
+            NotesAction::Merge(MergeAction::Commit) => Self::commit_merge(&repo),
+            NotesAction::Merge(MergeAction::Abort) => Self::abort_merge(&repo),
+            NotesAction::GetRef => {
+                println!("{notes_ref}");
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::{collections::BTreeMap, env, fmt::Display};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GitConfig,
+    error::RustGitError,
+    object::{GitBlobObject, GitObjectId, GitTreeEntry, GitTreeObject},
+    repo::GitRepo,
+};
+
+pub(crate) const NOTES_REFS_PREFIX: &str = "refs/notes/";
+const DEFAULT_NOTES_REF: &str = "refs/notes/commits";
+
+// Notes are spread over subtrees named after the first two hex digits of the annotated object
+// (and the next two at each deeper level) once a tree would hold more than this many entries.
+const FANOUT_THRESHOLD: usize = 255;
+const MAX_FANOUT: usize = 19;
+
+/// How `git notes merge` resolves notes which were changed on both sides, see the "NOTES MERGE
+/// STRATEGIES" section of git-notes(1).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NotesMergeStrategy {
+    #[default]
+    Manual,
+    Ours,
+    Theirs,
+    Union,
+    #[value(name = "cat_sort_uniq")]
+    CatSortUniq,
+}
+
+impl Display for NotesMergeStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            NotesMergeStrategy::Manual => "manual",
+            NotesMergeStrategy::Ours => "ours",
+            NotesMergeStrategy::Theirs => "theirs",
+            NotesMergeStrategy::Union => "union",
+            NotesMergeStrategy::CatSortUniq => "cat_sort_uniq",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Expands a notes ref given on the command line to its full name, so that `foo` and
+/// `notes/foo` both name `refs/notes/foo`.
+pub(crate) fn expand_notes_ref(name: &str) -> String {
+    if name.starts_with(NOTES_REFS_PREFIX) {
+        name.to_string()
+    } else if name.starts_with("notes/") {
+        format!("refs/{name}")
+    } else {
+        format!("{NOTES_REFS_PREFIX}{name}")
+    }
+}
+
+/// Returns the notes ref used when none is given, which is taken from `GIT_NOTES_REF`, then
+/// `core.notesRef`, before falling back to `refs/notes/commits`.
+pub(crate) fn default_notes_ref(config: &GitConfig) -> String {
+    env::var("GIT_NOTES_REF")
+        .ok()
+        .or_else(|| config.core.notesref.clone())
+        .unwrap_or_else(|| DEFAULT_NOTES_REF.to_string())
+}
+
+/// Loads the notes shown by `log` and `show`. Each `--notes=<ref>` option shows the notes of that
+/// ref, with a bare `--notes` (given as an empty ref) standing for the default notes ref, which
+/// is also shown when no option is given at all.
+pub(crate) fn load_display_notes(
+    repo: &GitRepo,
+    notes_refs: &[String],
+    no_notes: bool,
+) -> Result<Vec<GitNotes>, RustGitError> {
+    if no_notes {
+        return Ok(Vec::new());
+    }
+
+    let mut ref_names: Vec<String> = Vec::new();
+    if notes_refs.is_empty() {
+        ref_names.push(default_notes_ref(&repo.config));
+    }
+    for notes_ref in notes_refs {
+        let ref_name = match notes_ref.as_str() {
+            "" => default_notes_ref(&repo.config),
+            notes_ref => expand_notes_ref(notes_ref),
+        };
+        if !ref_names.contains(&ref_name) {
+            ref_names.push(ref_name);
+        }
+    }
+
+    ref_names
+        .iter()
+        .map(|ref_name| GitNotes::load(repo, ref_name))
+        .collect()
+}
+
+fn is_hex(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The notes stored at the tip of a notes ref, mapping annotated objects to the blobs holding
+/// their notes.
+pub(crate) struct GitNotes {
+    pub(crate) ref_name: String,
+    /// The commit the notes were read from, which becomes the parent of the next notes commit.
+    pub(crate) tip: Option<GitObjectId>,
+    notes: BTreeMap<GitObjectId, GitObjectId>,
+    /// Entries of the top-level tree which aren't notes, and are kept as they are.
+    others: Vec<GitTreeEntry>,
+}
+
+impl GitNotes {
+    /// Reads the notes at the tip of the provided notes ref, which may not exist yet.
+    pub(crate) fn load(repo: &GitRepo, ref_name: &str) -> Result<GitNotes, RustGitError> {
+        let tip = repo.refs.resolve_ref(ref_name)?;
+        Self::load_commit(repo, ref_name, tip.as_ref())
+    }
+
+    /// Reads the notes recorded by the provided notes commit, if there is one.
+    pub(crate) fn load_commit(
+        repo: &GitRepo,
+        ref_name: &str,
+        commit_id: Option<&GitObjectId>,
+    ) -> Result<GitNotes, RustGitError> {
+        let mut notes = GitNotes {
+            ref_name: ref_name.to_string(),
+            tip: commit_id.cloned(),
+            notes: BTreeMap::new(),
+            others: Vec::new(),
+        };
+
+        if let Some(commit_id) = commit_id {
+            let tree_id = repo.read_commit(commit_id)?.tree;
+            notes.load_tree(repo, &tree_id, "")?;
+        }
+
+        Ok(notes)
+    }
+
+    fn load_tree(
+        &mut self,
+        repo: &GitRepo,
+        tree_id: &GitObjectId,
+        prefix: &str,
+    ) -> Result<(), RustGitError> {
+        for entry in repo.read_tree(tree_id)?.entries {
+            let path = format!("{prefix}{}", entry.name);
+            match entry.entry_type.as_str() {
+                "blob" if path.len() == 40 && is_hex(&path) => {
+                    self.notes
+                        .insert(GitObjectId::new(path.to_lowercase()), entry.obj_id);
+                }
+                "tree" if entry.name.len() == 2 && path.len() < 40 && is_hex(&path) => {
+                    self.load_tree(repo, &entry.obj_id, &path)?;
+                }
+                _ if prefix.is_empty() => self.others.push(entry),
+                // Like C Git, anything else nested in the fanout is dropped.
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails unless the notes ref can be written, which C Git only allows under refs/notes/.
+    pub(crate) fn check_writable(&self, action: &str) -> Result<(), RustGitError> {
+        if !self.ref_name.starts_with(NOTES_REFS_PREFIX) {
+            return Err(RustGitError::new(format!(
+                "fatal: refusing to {action} notes in {} (outside of refs/notes/)",
+                self.ref_name
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, object: &GitObjectId) -> Option<&GitObjectId> {
+        self.notes.get(object)
+    }
+
+    /// Attaches the provided note blob to an object, replacing any note it already has.
+    pub(crate) fn set(&mut self, object: &GitObjectId, note: GitObjectId) {
+        self.notes.insert(object.clone(), note);
+    }
+
+    /// Removes the note of an object, returning false if it didn't have one.
+    pub(crate) fn remove(&mut self, object: &GitObjectId) -> bool {
+        self.notes.remove(object).is_some()
+    }
+
+    /// Iterates over the annotated objects and their note blobs, ordered by object.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&GitObjectId, &GitObjectId)> {
+        self.notes.iter()
+    }
+
+    /// Writes a note's contents as a blob, returning its id.
+    pub(crate) fn write_note(repo: &GitRepo, contents: &str) -> Result<GitObjectId, RustGitError> {
+        repo.obj_store.write_object(GitBlobObject {
+            contents: contents.to_string(),
+        })
+    }
+
+    /// Reads the contents of a note blob.
+    pub(crate) fn read_note(repo: &GitRepo, note: &GitObjectId) -> Result<String, RustGitError> {
+        match repo.obj_store.read_object(note)? {
+            Some(obj) => Ok(obj.content),
+            None => Err(RustGitError::new(format!("fatal: bad object {note}"))),
+        }
+    }
+
+    /// Formats the note of an object for `log` and `show`, indented under a "Notes:" header that
+    /// names the notes ref unless it's the default one.
+    pub(crate) fn format_note(
+        &self,
+        repo: &GitRepo,
+        object: &GitObjectId,
+    ) -> Result<Option<String>, RustGitError> {
+        let note = match self.get(object) {
+            Some(note) => Self::read_note(repo, note)?,
+            None => return Ok(None),
+        };
+
+        let mut formatted = match self.ref_name.as_str() {
+            DEFAULT_NOTES_REF => String::from("\nNotes:\n"),
+            ref_name => format!(
+                "\nNotes ({}):\n",
+                ref_name.strip_prefix(NOTES_REFS_PREFIX).unwrap_or(ref_name)
+            ),
+        };
+        for line in note.trim_end_matches('\n').split('\n') {
+            formatted.push_str(&format!("    {line}\n"));
+        }
+
+        Ok(Some(formatted))
+    }
+
+    fn fanout(&self) -> usize {
+        let mut fanout = 0;
+        let mut capacity = FANOUT_THRESHOLD;
+        while self.notes.len() > capacity && fanout < MAX_FANOUT {
+            fanout += 1;
+            capacity = capacity.saturating_mul(256);
+        }
+        fanout
+    }
+
+    fn write_subtree(
+        repo: &GitRepo,
+        notes: &[(String, &GitObjectId)],
+        depth: usize,
+        fanout: usize,
+        mut entries: Vec<GitTreeEntry>,
+    ) -> Result<GitObjectId, RustGitError> {
+        let offset = depth * 2;
+        if depth == fanout {
+            for (hex, note) in notes {
+                entries.push(GitTreeEntry {
+                    mode: "100644".to_string(),
+                    entry_type: "blob".to_string(),
+                    obj_id: (*note).clone(),
+                    name: hex[offset..].to_string(),
+                });
+            }
+        } else {
+            // The notes are sorted, so the notes of each subtree are next to each other.
+            for group in
+                notes.chunk_by(|(a, _), (b, _)| a[offset..offset + 2] == b[offset..offset + 2])
+            {
+                let subtree_id = Self::write_subtree(repo, group, depth + 1, fanout, Vec::new())?;
+                entries.push(GitTreeEntry {
+                    mode: "040000".to_string(),
+                    entry_type: "tree".to_string(),
+                    obj_id: subtree_id,
+                    name: group[0].0[offset..offset + 2].to_string(),
+                });
+            }
+        }
+
+        // Trees are sorted by name, with subtrees sorted as if their names ended with a slash.
+        entries.sort_by_cached_key(|entry| match entry.entry_type.as_str() {
+            "tree" => format!("{}/", entry.name),
+            _ => entry.name.clone(),
+        });
+        repo.obj_store.write_object(GitTreeObject { entries })
+    }
+
+    /// Writes the notes as a tree, fanned out according to how many notes there are.
+    pub(crate) fn write_tree(&self, repo: &GitRepo) -> Result<GitObjectId, RustGitError> {
+        let notes = self
+            .notes
+            .iter()
+            .map(|(object, note)| (object.to_string(), note))
+            .collect::<Vec<_>>();
+        Self::write_subtree(repo, &notes, 0, self.fanout(), self.others.clone())
+    }
+
+    /// Records the notes as a new commit on the notes ref, with the provided parents, and moves
+    /// the ref to it as long as nothing else has moved it since the notes were read.
+    pub(crate) fn commit_with_parents(
+        &mut self,
+        repo: &GitRepo,
+        parents: &Vec<GitObjectId>,
+        message: &str,
+    ) -> Result<GitObjectId, RustGitError> {
+        let tree_id = self.write_tree(repo)?;
+        let commit_id = repo.write_commit(&tree_id, parents, message, None)?;
+        self.update_ref(repo, &commit_id)?;
+        Ok(commit_id)
+    }
+
+    /// Records the notes as a new commit on top of the notes ref.
+    pub(crate) fn commit(
+        &mut self,
+        repo: &GitRepo,
+        message: &str,
+    ) -> Result<GitObjectId, RustGitError> {
+        let parents = self.tip.iter().cloned().collect();
+        self.commit_with_parents(repo, &parents, message)
+    }
+
+    /// Moves the notes ref to the provided commit, as long as it still points at the tip the
+    /// notes were read from.
+    pub(crate) fn update_ref(
+        &mut self,
+        repo: &GitRepo,
+        commit_id: &GitObjectId,
+    ) -> Result<(), RustGitError> {
+        let old_value = self
+            .tip
+            .as_ref()
+            .map(|tip| tip.to_string())
+            .unwrap_or_default();
+        repo.update_ref(
+            &self.ref_name,
+            &commit_id.to_string(),
+            Some(&old_value),
+            false,
+        )?;
+        self.tip = Some(commit_id.clone());
+        Ok(())
+    }
+}
+
+/// Combines two versions of a note with one of the automatic merge strategies, or returns None
+/// when the strategy leaves conflicts to be resolved by hand.
+pub(crate) fn combine_notes(
+    strategy: NotesMergeStrategy,
+    ours: &str,
+    theirs: &str,
+) -> Option<String> {
+    let combined = match strategy {
+        NotesMergeStrategy::Manual => return None,
+        NotesMergeStrategy::Ours => ours.to_string(),
+        NotesMergeStrategy::Theirs => theirs.to_string(),
+        NotesMergeStrategy::Union if ours.is_empty() => theirs.to_string(),
+        NotesMergeStrategy::Union if theirs.is_empty() => ours.to_string(),
+        NotesMergeStrategy::Union => format!("{ours}\n{theirs}"),
+        NotesMergeStrategy::CatSortUniq => {
+            let mut lines = ours
+                .lines()
+                .chain(theirs.lines())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>();
+            lines.sort();
+            lines.dedup();
+            lines.iter().map(|line| format!("{line}\n")).collect()
+        }
+    };
+    Some(combined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_expand_notes_ref() {
+        assert_eq!(expand_notes_ref("commits"), "refs/notes/commits");
+        assert_eq!(expand_notes_ref("notes/ci"), "refs/notes/ci");
+        assert_eq!(expand_notes_ref("refs/notes/ci"), "refs/notes/ci");
+        assert_eq!(
+            expand_notes_ref("refs/heads/ci"),
+            "refs/notes/refs/heads/ci"
+        );
+    }
+
+    #[test]
+    fn should_combine_notes() {
+        let combine = |strategy| combine_notes(strategy, "b\na\n", "c\n\na\n");
+        assert_eq!(combine(NotesMergeStrategy::Manual), None);
+        assert_eq!(combine(NotesMergeStrategy::Ours).unwrap(), "b\na\n");
+        assert_eq!(combine(NotesMergeStrategy::Theirs).unwrap(), "c\n\na\n");
+        assert_eq!(
+            combine(NotesMergeStrategy::Union).unwrap(),
+            "b\na\n\nc\n\na\n"
+        );
+        assert_eq!(
+            combine(NotesMergeStrategy::CatSortUniq).unwrap(),
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn should_fan_out_with_many_notes() {
+        let mut notes = GitNotes {
+            ref_name: DEFAULT_NOTES_REF.to_string(),
+            tip: None,
+            notes: BTreeMap::new(),
+            others: Vec::new(),
+        };
+        let note = GitObjectId::new("0".repeat(40));
+        for i in 0..FANOUT_THRESHOLD {
+            notes.set(&GitObjectId::new(format!("{i:040x}")), note.clone());
+        }
+        assert_eq!(notes.fanout(), 0);
+
+        notes.set(&GitObjectId::new("f".repeat(40)), note);
+        assert_eq!(notes.fanout(), 1);
+    }
+}
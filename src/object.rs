@@ -4,7 +4,7 @@ use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 
-use crate::{error::RustGitError, hash::Hasher};
+use crate::{error::RustGitError, gpg::signature_start, hash::Hasher};
 
 const MAX_HEADER_LEN: usize = 32;
 
@@ -44,7 +44,7 @@ impl FromStr for GitObjectType {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub(crate) struct GitObjectId(String);
 
 impl GitObjectId {
@@ -204,6 +204,7 @@ impl TryFrom<GitBlobObject> for GitObject {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct GitCommitObject {
     pub(crate) tree: GitObjectId,
     pub(crate) parents: Vec<GitObjectId>,
@@ -213,6 +214,123 @@ pub(crate) struct GitCommitObject {
     pub(crate) committer_name: String,
     pub(crate) committer_email: String,
     pub(crate) timestamp: u128,
+    pub(crate) signature: Option<String>,
+}
+
+/// Parses the value of an identity header (e.g. `author`, `tagger`), which has the form
+/// `<name> <<email>> <timestamp> [<timezone>]`.
+fn parse_identity(value: &str) -> Result<(String, String, u128), RustGitError> {
+    let invalid = || RustGitError::new(format!("invalid identity '{value}'"));
+
+    let email_start = value.find('<').ok_or_else(invalid)?;
+    let email_end = value.find('>').ok_or_else(invalid)?;
+    if email_end < email_start {
+        return Err(invalid());
+    }
+
+    let name = value[..email_start].trim_end().to_string();
+    let email = value[email_start + 1..email_end].to_string();
+    let timestamp = value[email_end + 1..]
+        .split_whitespace()
+        .next()
+        .ok_or_else(invalid)?
+        .parse::<u128>()?;
+
+    Ok((name, email, timestamp))
+}
+
+/// Splits object content into its header lines and message, which are separated by the first
+/// empty line.
+fn split_headers(content: &str) -> (Vec<(&str, &str)>, &str) {
+    let (headers, message) = content.split_once("\n\n").unwrap_or((content, ""));
+
+    let headers = headers
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .collect();
+
+    (headers, message)
+}
+
+/// Splits the `gpgsig` header out of commit content, returning the payload the signature was made
+/// over and the signature itself. The signature header's continuation lines are indented by a
+/// space.
+pub(crate) fn split_commit_signature(content: &str) -> (String, Option<String>) {
+    let (headers, message) = match content.split_once("\n\n") {
+        Some((headers, message)) => (headers, Some(message)),
+        None => (content, None),
+    };
+
+    let mut payload_lines = Vec::new();
+    let mut signature_lines = Vec::new();
+    let mut in_signature = false;
+
+    for line in headers.split('\n') {
+        match (line.strip_prefix("gpgsig "), line.strip_prefix(' ')) {
+            (Some(first_line), _) => {
+                signature_lines.push(first_line);
+                in_signature = true;
+            }
+            (None, Some(continuation)) if in_signature => signature_lines.push(continuation),
+            _ => {
+                payload_lines.push(line);
+                in_signature = false;
+            }
+        }
+    }
+
+    let mut payload = payload_lines.join("\n");
+    if let Some(message) = message {
+        payload.push_str("\n\n");
+        payload.push_str(message);
+    }
+
+    let signature = (!signature_lines.is_empty()).then(|| signature_lines.join("\n") + "\n");
+
+    (payload, signature)
+}
+
+impl FromStr for GitCommitObject {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (payload, signature) = split_commit_signature(s);
+        let (headers, message) = split_headers(&payload);
+
+        let mut tree = None;
+        let mut parents = Vec::new();
+        let mut author = None;
+        let mut committer = None;
+
+        for (key, value) in headers {
+            match key {
+                "tree" => tree = Some(value.parse::<GitObjectId>()?),
+                "parent" => parents.push(value.parse::<GitObjectId>()?),
+                "author" => author = Some(parse_identity(value)?),
+                "committer" => committer = Some(parse_identity(value)?),
+                _ => (),
+            }
+        }
+
+        match (tree, author, committer) {
+            (
+                Some(tree),
+                Some((author_name, author_email, timestamp)),
+                Some((committer_name, committer_email, _)),
+            ) => Ok(GitCommitObject {
+                tree,
+                parents,
+                message: message.to_string(),
+                author_name,
+                author_email,
+                committer_name,
+                committer_email,
+                timestamp,
+                signature,
+            }),
+            _ => Err(RustGitError::new("malformed commit object")),
+        }
+    }
 }
 
 impl TryFrom<GitCommitObject> for GitObject {
@@ -232,15 +350,25 @@ impl TryFrom<GitCommitObject> for GitObject {
             value.author_name, value.author_email, value.timestamp
         ));
         content.push_str(&format!(
-            "committer {} <{}> {}\n\n",
+            "committer {} <{}> {}\n",
             value.committer_name, value.committer_email, value.timestamp
         ));
+
+        if let Some(signature) = value.signature {
+            content.push_str(&format!(
+                "gpgsig {}\n",
+                signature.trim_end_matches('\n').replace('\n', "\n ")
+            ));
+        }
+
+        content.push('\n');
         content.push_str(&value.message);
 
         return GitObject::new(GitObjectType::Commit, content);
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct GitTreeEntry {
     pub(crate) mode: String,
     pub(crate) entry_type: String,
@@ -266,10 +394,38 @@ impl TryFrom<GitTreeObject> for GitObject {
     }
 }
 
+#[derive(Debug, PartialEq)]
 pub(crate) struct GitTreeObject {
     pub(crate) entries: Vec<GitTreeEntry>,
 }
 
+impl FromStr for GitTreeObject {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entries = s
+            .lines()
+            .map(|line| {
+                let malformed = || RustGitError::new(format!("malformed tree entry '{line}'"));
+                let (meta, name) = line.split_once('\t').ok_or_else(malformed)?;
+                let mut meta = meta.split(' ');
+                match (meta.next(), meta.next(), meta.next(), meta.next()) {
+                    (Some(mode), Some(entry_type), Some(obj_id), None) => Ok(GitTreeEntry {
+                        mode: mode.to_string(),
+                        entry_type: entry_type.to_string(),
+                        obj_id: obj_id.parse()?,
+                        name: name.to_string(),
+                    }),
+                    _ => Err(malformed()),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(GitTreeObject { entries })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct GitTagObject {
     pub(crate) tag_name: String,
     pub(crate) object_id: GitObjectId,
@@ -278,6 +434,7 @@ pub(crate) struct GitTagObject {
     pub(crate) tagger_email: String,
     pub(crate) timestamp: u128,
     pub(crate) message: String,
+    pub(crate) signature: Option<String>,
 }
 
 impl TryFrom<GitTagObject> for GitObject {
@@ -295,10 +452,61 @@ impl TryFrom<GitTagObject> for GitObject {
         ));
         contents.push_str(&value.message);
 
+        // Unlike commits, tags carry their signature at the end of the message.
+        if let Some(signature) = value.signature {
+            contents.push_str(&signature);
+        }
+
         return GitObject::new(GitObjectType::Tag, contents);
     }
 }
 
+impl FromStr for GitTagObject {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (headers, message) = split_headers(s);
+        let (message, signature) = match signature_start(message) {
+            Some(start) => (&message[..start], Some(message[start..].to_string())),
+            None => (message, None),
+        };
+
+        let mut object_id = None;
+        let mut object_type = None;
+        let mut tag_name = None;
+        let mut tagger = None;
+
+        for (key, value) in headers {
+            match key {
+                "object" => object_id = Some(value.parse::<GitObjectId>()?),
+                "type" => object_type = Some(value.parse::<GitObjectType>()?),
+                "tag" => tag_name = Some(value.to_string()),
+                "tagger" => tagger = Some(parse_identity(value)?),
+                _ => (),
+            }
+        }
+
+        match (object_id, object_type, tag_name, tagger) {
+            (
+                Some(object_id),
+                Some(object_type),
+                Some(tag_name),
+                Some((tagger_name, tagger_email, timestamp)),
+            ) => Ok(GitTagObject {
+                tag_name,
+                object_id,
+                object_type,
+                tagger_name,
+                tagger_email,
+                timestamp,
+                message: message.to_string(),
+                signature,
+            }),
+            _ => Err(RustGitError::new("malformed tag object")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     mod git_object_type {
@@ -426,4 +634,192 @@ mod tests {
             );
         }
     }
+
+    mod git_tree_object {
+        use super::super::*;
+
+        #[test]
+        fn should_roundtrip_tree_object() {
+            let tree = GitTreeObject {
+                entries: vec![
+                    GitTreeEntry {
+                        mode: String::from("040000"),
+                        entry_type: String::from("tree"),
+                        obj_id: GitObjectId::new(String::from(
+                            "303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9",
+                        )),
+                        name: String::from("dir"),
+                    },
+                    GitTreeEntry {
+                        mode: String::from("100644"),
+                        entry_type: String::from("blob"),
+                        obj_id: GitObjectId::new(String::from(
+                            "30d74d258442c7c65512eafab474568dd706c430",
+                        )),
+                        name: String::from("file with spaces.txt"),
+                    },
+                ],
+            };
+
+            let obj: GitObject = GitTreeObject {
+                entries: tree.entries.clone(),
+            }
+            .try_into()
+            .unwrap();
+            assert_eq!(obj.content.content.parse::<GitTreeObject>(), Ok(tree));
+            assert_eq!("".parse::<GitTreeObject>(), Ok(GitTreeObject { entries: vec![] }));
+        }
+
+        #[test]
+        fn should_fail_to_parse_malformed_tree_entry() {
+            assert!("100644 blob 303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9"
+                .parse::<GitTreeObject>()
+                .is_err());
+        }
+    }
+
+    mod git_commit_object {
+        use super::super::*;
+
+        #[test]
+        fn should_roundtrip_commit_object() {
+            let commit = GitCommitObject {
+                tree: GitObjectId::new(String::from("303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9")),
+                parents: vec![GitObjectId::new(String::from(
+                    "30d74d258442c7c65512eafab474568dd706c430",
+                ))],
+                message: String::from("Test commit\n\nWith a body"),
+                author_name: String::from("Test User"),
+                author_email: String::from("test@user.com"),
+                committer_name: String::from("Test User"),
+                committer_email: String::from("test@user.com"),
+                timestamp: 1709212455000,
+                signature: None,
+            };
+
+            let obj: GitObject = commit.clone().try_into().unwrap();
+            assert_eq!(obj.content.content.parse::<GitCommitObject>(), Ok(commit));
+        }
+
+        #[test]
+        fn should_roundtrip_signed_commit_object() {
+            let commit = GitCommitObject {
+                tree: GitObjectId::new(String::from("303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9")),
+                parents: vec![],
+                message: String::from("Test commit"),
+                author_name: String::from("Test User"),
+                author_email: String::from("test@user.com"),
+                committer_name: String::from("Test User"),
+                committer_email: String::from("test@user.com"),
+                timestamp: 1709212455000,
+                signature: Some(String::from(
+                    "-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----\n",
+                )),
+            };
+
+            let obj: GitObject = commit.clone().try_into().unwrap();
+            assert_eq!(
+                obj.content.content,
+                "tree 303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9
+author Test User <test@user.com> 1709212455000
+committer Test User <test@user.com> 1709212455000
+gpgsig -----BEGIN SSH SIGNATURE-----
+ U1NIU0lH
+ -----END SSH SIGNATURE-----
+
+Test commit"
+            );
+            assert_eq!(
+                obj.content.content.parse::<GitCommitObject>(),
+                Ok(commit.clone())
+            );
+
+            let unsigned: GitObject = GitCommitObject {
+                signature: None,
+                ..commit
+            }
+            .try_into()
+            .unwrap();
+            assert_eq!(
+                split_commit_signature(&obj.content.content).0,
+                unsigned.content.content
+            );
+        }
+
+        #[test]
+        fn should_parse_commit_with_timezone_and_extra_headers() {
+            let content = "tree 303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9
+author A U Thor <author@example.com> 1112911993 +0200
+committer C O Mitter <committer@example.com> 1112912000 -0700
+encoding UTF-8
+
+Subject line";
+            let commit = content.parse::<GitCommitObject>().unwrap();
+            assert_eq!(commit.parents, vec![]);
+            assert_eq!(commit.author_name, "A U Thor");
+            assert_eq!(commit.committer_email, "committer@example.com");
+            assert_eq!(commit.timestamp, 1112911993);
+            assert_eq!(commit.message, "Subject line");
+        }
+
+        #[test]
+        fn should_fail_to_parse_commit_without_tree() {
+            assert_eq!(
+                "author A <a@b.c> 0\ncommitter A <a@b.c> 0\n\nmsg".parse::<GitCommitObject>(),
+                Err(RustGitError::new("malformed commit object"))
+            );
+        }
+    }
+
+    mod git_tag_object {
+        use super::super::*;
+
+        #[test]
+        fn should_parse_tag_object() {
+            let content = "object 30d74d258442c7c65512eafab474568dd706c430
+type commit
+tag v1.0
+tagger Test User <test@user.com> 1709212455000
+
+Release v1.0";
+            assert_eq!(
+                content.parse::<GitTagObject>(),
+                Ok(GitTagObject {
+                    tag_name: String::from("v1.0"),
+                    object_id: GitObjectId::new(String::from(
+                        "30d74d258442c7c65512eafab474568dd706c430"
+                    )),
+                    object_type: GitObjectType::Commit,
+                    tagger_name: String::from("Test User"),
+                    tagger_email: String::from("test@user.com"),
+                    timestamp: 1709212455000,
+                    message: String::from("Release v1.0"),
+                    signature: None,
+                })
+            );
+        }
+
+        #[test]
+        fn should_parse_signature_of_signed_tag_object() {
+            let content = "object 30d74d258442c7c65512eafab474568dd706c430
+type commit
+tag v1.0
+tagger Test User <test@user.com> 1709212455000
+
+Release v1.0
+-----BEGIN PGP SIGNATURE-----
+iQEzBAABCAAdFiEE
+-----END PGP SIGNATURE-----
+";
+            let tag = content.parse::<GitTagObject>().unwrap();
+            assert_eq!(tag.message, "Release v1.0\n");
+            assert_eq!(
+                tag.signature.as_deref(),
+                Some("-----BEGIN PGP SIGNATURE-----\niQEzBAABCAAdFiEE\n-----END PGP SIGNATURE-----\n")
+            );
+
+            let obj: GitObject = tag.try_into().unwrap();
+            assert_eq!(obj.content.content, content);
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use std::fs::create_dir_all;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_dir};
 use std::path::{Path, PathBuf};
 
 use crate::error::RustGitError;
@@ -11,16 +12,47 @@ use std::io::{Read, Write};
 
 const OBJECTS_FOLDER: &str = "objects";
 
+// Replacements can themselves be replaced; C Git gives up after 5 levels:
+// https://github.com/git/git/blob/master/replace-object.c
+const MAX_REPLACE_DEPTH: usize = 5;
+
 pub(crate) struct GitObjectStore {
     /// Path to object store folder.
     obj_dir: PathBuf,
+    /// Objects replaced by other objects through refs/replace/, keyed by the replaced object.
+    replacements: HashMap<GitObjectId, GitObjectId>,
 }
 
 impl GitObjectStore {
     pub(crate) fn new(git_dir: &Path) -> GitObjectStore {
         GitObjectStore {
             obj_dir: git_dir.join(OBJECTS_FOLDER),
+            replacements: HashMap::new(),
+        }
+    }
+
+    /// Sets the replacements which reads transparently substitute, see git-replace(1).
+    pub(crate) fn set_replacements(&mut self, replacements: HashMap<GitObjectId, GitObjectId>) {
+        self.replacements = replacements;
+    }
+
+    /// Returns the object that should be read in place of the provided one, following chains
+    /// of replacements.
+    pub(crate) fn lookup_replacement(
+        &self,
+        obj_id: &GitObjectId,
+    ) -> Result<GitObjectId, RustGitError> {
+        let mut id = obj_id;
+        for _ in 0..MAX_REPLACE_DEPTH {
+            match self.replacements.get(id) {
+                Some(replacement) => id = replacement,
+                None => return Ok(id.clone()),
+            }
         }
+
+        Err(RustGitError::new(format!(
+            "fatal: replace depth too high for object {obj_id}"
+        )))
     }
 
     pub(crate) fn loose_object_path(&self, obj_id: &GitObjectId) -> (PathBuf, PathBuf) {
@@ -65,10 +97,12 @@ impl GitObjectStore {
         Ok(())
     }
 
+    /// Reads an object, or the object replacing it if there is one.
     pub(crate) fn read_object(
         &self,
         obj_id: &GitObjectId,
     ) -> Result<Option<GitObjectContents>, RustGitError> {
+        let obj_id = self.lookup_replacement(obj_id)?;
         let (obj_folder, obj_file_name) = self.loose_object_path(&obj_id);
         let obj_file_path = obj_folder.join(obj_file_name);
 
@@ -86,6 +120,33 @@ impl GitObjectStore {
         Ok(Some(obj))
     }
 
+    /// Returns the ids of all objects whose hex id starts with the provided prefix.
+    pub(crate) fn find_objects_by_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<GitObjectId>, RustGitError> {
+        if prefix.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let (folder_name, file_prefix) = prefix.split_at(2);
+        let obj_folder = self.obj_dir.join(folder_name);
+        if !obj_folder.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut matches = Vec::new();
+        for dir_entry in read_dir(obj_folder)? {
+            if let Some(file_name) = dir_entry?.file_name().to_str() {
+                if file_name.starts_with(file_prefix) {
+                    matches.push(GitObjectId::new(format!("{folder_name}{file_name}")));
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
     /// Returns true if the provided object id exists in the repo.
     pub(crate) fn is_valid_object_id(&self, obj_id: &GitObjectId) -> bool {
         let (obj_folder, obj_file_name) = self.loose_object_path(&obj_id);
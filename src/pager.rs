@@ -0,0 +1,155 @@
+// Paging of output, based on C Git's pager.c:
+// https://github.com/git/git/blob/master/pager.c
+
+use std::{
+    env,
+    fs::File,
+    io::{self, IsTerminal, Write},
+    os::fd::{AsFd, AsRawFd, OwnedFd},
+    process::{Child, Command, Stdio},
+};
+
+use crate::config::{GitConfig, PagerConfig};
+
+const DEFAULT_PAGER: &str = "less";
+
+/// Set for the commands run while the pager is in use, so that they color their output as if it
+/// went to a terminal.
+const PAGER_IN_USE_ENV: &str = "GIT_PAGER_IN_USE";
+
+/// Returns whether output goes to the pager, which may have been started by a parent process.
+pub(crate) fn pager_in_use() -> bool {
+    env::var(PAGER_IN_USE_ENV).is_ok_and(|value| {
+        !matches!(
+            value.to_lowercase().as_str(),
+            "" | "0" | "n" | "no" | "f" | "false" | "off"
+        )
+    })
+}
+
+/// Picks the pager to use, looking in the same places as C Git, where `command_pager` is the
+/// pager configured for the command with `pager.<cmd>`. No pager is used when the pager is empty
+/// or `cat`.
+fn pager_command(
+    config: &GitConfig,
+    command_pager: Option<&str>,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let pager = env_var("GIT_PAGER")
+        .or_else(|| command_pager.map(String::from))
+        .or_else(|| config.core.pager.clone())
+        .or_else(|| env_var("PAGER"))
+        .unwrap_or_else(|| String::from(DEFAULT_PAGER));
+    (!pager.is_empty() && pager != "cat").then_some(pager)
+}
+
+/// A pager whose input is the output of this process.
+pub(crate) struct Pager {
+    child: Child,
+    /// The stderr this process had before it went to the pager too.
+    stderr: Option<OwnedFd>,
+}
+
+impl Pager {
+    /// Starts the pager for the command, if output should go through one. Commands page their
+    /// output when `pager.<cmd>` says so, or by default when `pages_by_default` is set, which
+    /// `--paginate` and `--no-pager` override. Output is only paged when it goes to a terminal.
+    pub(crate) fn setup(
+        config: &GitConfig,
+        command: &str,
+        pages_by_default: bool,
+        paginate: bool,
+        no_pager: bool,
+    ) -> Option<Pager> {
+        let (enabled, command_pager) = match config.pager.get(command) {
+            Some(PagerConfig::Enabled(enabled)) => (*enabled, None),
+            Some(PagerConfig::Command(pager)) => (true, Some(pager.as_str())),
+            None => (pages_by_default, None),
+        };
+        if no_pager || !(paginate || enabled) || !io::stdout().is_terminal() {
+            return None;
+        }
+        let pager = pager_command(config, command_pager, |name| env::var(name).ok())?;
+        Pager::start(&pager)
+    }
+
+    fn start(pager: &str) -> Option<Pager> {
+        let mut command = Command::new("sh");
+        // The pager is run through the shell, as it may include arguments.
+        command.arg("-c").arg(pager).stdin(Stdio::piped());
+        // Like C Git, less quits when the output fits on the screen and shows colors, and lv
+        // shows colors too.
+        if env::var_os("LESS").is_none() {
+            command.env("LESS", "FRX");
+        }
+        if env::var_os("LV").is_none() {
+            command.env("LV", "-c");
+        }
+        let mut child = command.spawn().ok()?;
+        let input = child.stdin.take()?;
+
+        io::stdout().flush().ok()?;
+        let stderr = if io::stderr().is_terminal() {
+            let stderr = io::stderr().as_fd().try_clone_to_owned().ok();
+            redirect(&input, libc::STDERR_FILENO);
+            stderr
+        } else {
+            None
+        };
+        redirect(&input, libc::STDOUT_FILENO);
+        env::set_var(PAGER_IN_USE_ENV, "true");
+        // As C Git, stop quietly instead of failing to write when the pager is quit early.
+        unsafe {
+            libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        }
+
+        Some(Pager { child, stderr })
+    }
+}
+
+/// Makes the file descriptor refer to the provided file instead.
+fn redirect(file: &impl AsRawFd, fd: libc::c_int) {
+    unsafe {
+        libc::dup2(file.as_raw_fd(), fd);
+    }
+}
+
+impl Drop for Pager {
+    /// Waits for the pager to be quit, once all the output has been written to it.
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        // The pager only sees the end of its input once every copy of the pipe is closed.
+        if let Ok(null) = File::open("/dev/null") {
+            redirect(&null, libc::STDOUT_FILENO);
+        }
+        if let Some(stderr) = self.stderr.take() {
+            redirect(&stderr, libc::STDERR_FILENO);
+        }
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pick_pager_like_git() {
+        let mut config = GitConfig::default();
+        let no_env = |_: &str| None;
+        assert_eq!(pager_command(&config, None, no_env).unwrap(), "less");
+
+        let env = |name: &str| (name == "PAGER").then(|| String::from("more"));
+        assert_eq!(pager_command(&config, None, env).unwrap(), "more");
+
+        config.core.pager = Some(String::from("less -S"));
+        assert_eq!(pager_command(&config, None, env).unwrap(), "less -S");
+        assert_eq!(pager_command(&config, Some("most"), env).unwrap(), "most");
+
+        let env = |name: &str| (name == "GIT_PAGER").then(|| String::from("cat"));
+        assert_eq!(pager_command(&config, Some("most"), env), None);
+
+        config.core.pager = Some(String::new());
+        assert_eq!(pager_command(&config, None, no_env), None);
+    }
+}
@@ -0,0 +1,500 @@
+// Pathspecs select the paths commands operate on, based on C Git's implementation:
+// https://github.com/git/git/blob/master/pathspec.c
+// https://github.com/git/git/blob/master/dir.c
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{
+    attr::{GitAttrValue, GitAttributes},
+    repo::GitRepo,
+    wildmatch::{wildmatch, WildmatchFlags},
+    RustGitError,
+};
+
+/// The magic applied to every pathspec, set by the global --*-pathspecs options.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PathspecOptions {
+    literal: bool,
+    glob: bool,
+    noglob: bool,
+    icase: bool,
+}
+
+impl PathspecOptions {
+    pub(crate) fn new(
+        literal: bool,
+        glob: bool,
+        noglob: bool,
+        icase: bool,
+    ) -> Result<PathspecOptions, RustGitError> {
+        if glob && noglob {
+            return Err(RustGitError::new(
+                "fatal: global 'glob' and 'noglob' pathspec settings are incompatible",
+            ));
+        }
+        if literal && (glob || noglob || icase) {
+            return Err(RustGitError::new(
+                "fatal: global 'literal' pathspec setting is incompatible with all other global pathspec settings",
+            ));
+        }
+        Ok(PathspecOptions {
+            literal,
+            glob,
+            noglob,
+            icase,
+        })
+    }
+}
+
+/// How a path matched a pathspec item. Better matches compare greater.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum PathspecMatch {
+    /// The path is inside the directory named by the item.
+    Recursively,
+    /// The path matches the wildcards of the item.
+    Glob,
+    /// The path is the one named by the item.
+    Exactly,
+}
+
+/// Returns the length of the leading part of the pattern without wildcards.
+fn nowildcard_len(pattern: &str) -> usize {
+    pattern.find(['*', '?', '[', '\\']).unwrap_or(pattern.len())
+}
+
+/// Returns whether the text starts with the prefix, optionally ignoring ASCII case.
+fn starts_with(text: &str, prefix: &str, icase: bool) -> bool {
+    text.len() >= prefix.len()
+        && text.is_char_boundary(prefix.len())
+        && if icase {
+            text[..prefix.len()].eq_ignore_ascii_case(prefix)
+        } else {
+            text.starts_with(prefix)
+        }
+}
+
+/// A single element of a pathspec, like `src/*.rs` or `:(exclude,icase)*.md`.
+#[derive(Debug)]
+pub(crate) struct PathspecItem {
+    /// The element as it was given.
+    pub(crate) original: String,
+    /// The path to match, relative to the root of the repo.
+    pub(crate) path: String,
+    /// Length of the leading part of `path` without wildcards.
+    nowildcard_len: usize,
+    /// Whether wildcards match '/' only as part of `**`.
+    glob: bool,
+    icase: bool,
+    pub(crate) exclude: bool,
+    /// The attributes paths must have to match, from `:(attr:...)`.
+    attrs: Vec<(String, GitAttrValue)>,
+}
+
+impl PathspecItem {
+    fn parse(
+        repo: &GitRepo,
+        element: &str,
+        options: PathspecOptions,
+    ) -> Result<PathspecItem, RustGitError> {
+        let mut top = false;
+        let mut literal = false;
+        let mut glob = false;
+        let mut icase = options.icase;
+        let mut exclude = false;
+        let mut attrs = vec![];
+
+        let mut path = element;
+        if options.literal {
+            // No magic is parsed at all.
+        } else if let Some(long_magic) = element.strip_prefix(":(") {
+            let Some((magic, rest)) = long_magic.split_once(')') else {
+                return Err(RustGitError::new(format!(
+                    "fatal: Missing ')' at the end of pathspec magic in '{element}'"
+                )));
+            };
+            for name in magic.split(',').filter(|name| !name.is_empty()) {
+                match name {
+                    "top" => top = true,
+                    "literal" => literal = true,
+                    "glob" => glob = true,
+                    "icase" => icase = true,
+                    "exclude" => exclude = true,
+                    _ => match name.strip_prefix("attr:") {
+                        Some(_) if !attrs.is_empty() => {
+                            return Err(RustGitError::new(
+                                "fatal: Only one 'attr:' specification is allowed.",
+                            ))
+                        }
+                        Some(spec) => {
+                            attrs = spec
+                                .split_whitespace()
+                                .map(GitAttrValue::parse)
+                                .map(|(name, value)| (String::from(name), value))
+                                .collect();
+                            if attrs.is_empty() {
+                                return Err(RustGitError::new(
+                                    "fatal: attr spec must not be empty",
+                                ));
+                            }
+                        }
+                        None => {
+                            return Err(RustGitError::new(format!(
+                                "fatal: Invalid pathspec magic '{name}' in '{element}'"
+                            )))
+                        }
+                    },
+                }
+            }
+            if literal && glob {
+                return Err(RustGitError::new(format!(
+                    "fatal: {element}: 'literal' and 'glob' are incompatible"
+                )));
+            }
+            path = rest;
+        } else if let Some(short_magic) = element.strip_prefix(':') {
+            path = short_magic;
+            while let Some(c) = path.chars().next() {
+                match c {
+                    '/' => top = true,
+                    '!' | '^' => exclude = true,
+                    ':' => {
+                        path = &path[1..];
+                        break;
+                    }
+                    _ => break,
+                }
+                path = &path[1..];
+            }
+        }
+
+        // The global options only apply to items without their own literal or glob magic.
+        let (literal, glob) = (
+            literal || options.literal || (options.noglob && !glob),
+            glob || (options.glob && !literal),
+        );
+
+        let normalized = if top {
+            repo.normalize_path(Path::new(path))
+                .map(|path| path.to_string_lossy().into_owned())
+                .ok_or_else(|| RustGitError::new(format!("path {path:?} is outside of repo")))?
+        } else {
+            repo.path_to_git_repo_path(Path::new(path))?.as_string()
+        };
+        // A trailing slash means the item only matches directories.
+        let path = if path.ends_with('/') && !normalized.is_empty() {
+            format!("{normalized}/")
+        } else {
+            normalized
+        };
+
+        Ok(PathspecItem {
+            original: String::from(element),
+            nowildcard_len: if literal {
+                path.len()
+            } else {
+                nowildcard_len(&path)
+            },
+            path,
+            glob,
+            icase,
+            exclude,
+            attrs,
+        })
+    }
+
+    /// Returns whether the item has wildcards.
+    pub(crate) fn has_wildcards(&self) -> bool {
+        self.nowildcard_len < self.path.len()
+    }
+
+    /// Returns how the path, relative to the root of the repo, matches the item, ignoring the
+    /// attributes it requires.
+    fn match_path(&self, name: &str) -> Option<PathspecMatch> {
+        let item = self.path.as_str();
+        if item.is_empty() {
+            return Some(PathspecMatch::Recursively);
+        }
+
+        if starts_with(name, item, self.icase) {
+            if name.len() == item.len() {
+                return Some(PathspecMatch::Exactly);
+            }
+            if item.ends_with('/') || name.as_bytes()[item.len()] == b'/' {
+                return Some(PathspecMatch::Recursively);
+            }
+        }
+
+        let flags = WildmatchFlags {
+            pathname: self.glob,
+            casefold: self.icase,
+        };
+        if self.has_wildcards()
+            && starts_with(name, &item[..self.nowildcard_len], self.icase)
+            && wildmatch(item, name, flags)
+        {
+            return Some(PathspecMatch::Glob);
+        }
+        None
+    }
+
+    /// Returns whether paths under the directory, relative to the root of the repo, could match
+    /// the item.
+    fn may_match_under(&self, dir: &str) -> bool {
+        let prefix = &self.path[..self.nowildcard_len];
+        let dir = format!("{dir}/");
+        starts_with(prefix, &dir, self.icase) || starts_with(&dir, prefix, self.icase)
+    }
+}
+
+/// A list of pathspec items. A path matches when it matches any item which isn't an exclude
+/// item, and none of the exclude items. Without any item, every path matches.
+pub(crate) struct Pathspec {
+    pub(crate) items: Vec<PathspecItem>,
+    /// The best match of each item so far.
+    seen: Vec<Option<PathspecMatch>>,
+    attributes: Option<GitAttributes>,
+}
+
+impl Pathspec {
+    /// Parses the provided elements, which are relative to the current directory unless they
+    /// have the `top` magic.
+    pub(crate) fn parse(
+        repo: &GitRepo,
+        elements: &[String],
+        options: PathspecOptions,
+    ) -> Result<Pathspec, RustGitError> {
+        let items = elements
+            .iter()
+            .map(|element| PathspecItem::parse(repo, element, options))
+            .collect::<Result<Vec<_>, _>>()?;
+        let attributes = items.iter().any(|item| !item.attrs.is_empty()).then(|| {
+            GitAttributes::new(&repo.root_dir, &repo.git_dir, repo.config.core.ignorecase)
+        });
+        Ok(Pathspec {
+            seen: vec![None; items.len()],
+            items,
+            attributes,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn item_matches(
+        item: &PathspecItem,
+        attributes: &mut Option<GitAttributes>,
+        name: &str,
+    ) -> Option<PathspecMatch> {
+        let matched = item.match_path(name)?;
+        if let Some(attributes) = attributes {
+            if !item
+                .attrs
+                .iter()
+                .all(|(attr, value)| attributes.get(name, attr) == *value)
+            {
+                return None;
+            }
+        }
+        Some(matched)
+    }
+
+    /// Returns how the path, relative to the root of the repo, matches the pathspec, and
+    /// records which items it matched.
+    pub(crate) fn matches(&mut self, name: &str) -> Option<PathspecMatch> {
+        if self.items.is_empty() {
+            return Some(PathspecMatch::Recursively);
+        }
+
+        let mut best = None;
+        let mut matched_items = vec![];
+        let mut has_positive = false;
+        for (idx, item) in self.items.iter().enumerate() {
+            if item.exclude {
+                continue;
+            }
+            has_positive = true;
+            if let Some(matched) = Self::item_matches(item, &mut self.attributes, name) {
+                matched_items.push((idx, matched));
+                best = best.max(Some(matched));
+            }
+        }
+        // Exclude items alone apply to every path.
+        if !has_positive {
+            best = Some(PathspecMatch::Recursively);
+        }
+        best?;
+
+        for item in self.items.iter().filter(|item| item.exclude) {
+            if Self::item_matches(item, &mut self.attributes, name).is_some() {
+                return None;
+            }
+        }
+
+        for (idx, matched) in matched_items {
+            self.seen[idx] = self.seen[idx].max(Some(matched));
+        }
+        best
+    }
+
+    /// Returns whether paths under the directory, relative to the root of the repo, could
+    /// match the pathspec, so that it's worth walking it.
+    pub(crate) fn may_match_under(&self, dir: &str) -> bool {
+        let mut positive = self.items.iter().filter(|item| !item.exclude).peekable();
+        positive.peek().is_none() || positive.any(|item| item.may_match_under(dir))
+    }
+
+    /// Returns the item which names exactly the path, relative to the root of the repo.
+    pub(crate) fn exact_item(&self, name: &str) -> Option<&PathspecItem> {
+        self.items.iter().find(|item| {
+            !item.exclude
+                && !item.has_wildcards()
+                && item.path.trim_end_matches('/').len() == name.len()
+                && starts_with(&item.path, name, item.icase)
+        })
+    }
+
+    /// Returns the best match of each item by the paths matched so far.
+    pub(crate) fn seen(&self) -> impl Iterator<Item = (&PathspecItem, Option<PathspecMatch>)> {
+        self.items.iter().zip(self.seen.iter().copied())
+    }
+}
+
+/// Reads pathspec elements from the provided file, or the standard input for `-`. Elements are
+/// separated by NUL characters if `nul` is set, or else by line endings, in which case they may
+/// be quoted.
+pub(crate) fn read_pathspec_from_file(file: &Path, nul: bool) -> Result<Vec<String>, RustGitError> {
+    let mut contents = String::new();
+    if file == Path::new("-") {
+        io::stdin().read_to_string(&mut contents)?;
+    } else {
+        contents = fs::read_to_string(file).map_err(|_| {
+            RustGitError::new(format!(
+                "fatal: could not open '{}' for reading",
+                file.display()
+            ))
+        })?;
+    }
+
+    if nul {
+        return Ok(contents
+            .split('\0')
+            .filter(|element| !element.is_empty())
+            .map(String::from)
+            .collect());
+    }
+    contents
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix('"') {
+            Some(quoted) => unquote(quoted)
+                .ok_or_else(|| RustGitError::new(format!("fatal: line is badly quoted: {line}"))),
+            None => Ok(String::from(line)),
+        })
+        .collect()
+}
+
+/// Unquotes a C-style quoted string, without its opening quote.
+fn unquote(quoted: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut chars = quoted.bytes();
+    loop {
+        match chars.next()? {
+            b'"' => break,
+            b'\\' => {
+                let escaped = match chars.next()? {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'v' => 0x0b,
+                    c @ (b'\\' | b'"') => c,
+                    c @ b'0'..=b'3' => {
+                        let mut value = c - b'0';
+                        for _ in 0..2 {
+                            let digit = chars.next()?;
+                            if !(b'0'..=b'7').contains(&digit) {
+                                return None;
+                            }
+                            value = value * 8 + (digit - b'0');
+                        }
+                        value
+                    }
+                    _ => return None,
+                };
+                bytes.push(escaped);
+            }
+            c => bytes.push(c),
+        }
+    }
+    // Nothing may follow the closing quote.
+    if chars.next().is_some() {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, glob: bool, icase: bool) -> PathspecItem {
+        PathspecItem {
+            original: String::from(path),
+            path: String::from(path),
+            nowildcard_len: nowildcard_len(path),
+            glob,
+            icase,
+            exclude: false,
+            attrs: vec![],
+        }
+    }
+
+    #[test]
+    fn should_match_paths_and_leading_directories() {
+        let dir = item("src", false, false);
+        assert_eq!(dir.match_path("src"), Some(PathspecMatch::Exactly));
+        assert_eq!(dir.match_path("src/a.rs"), Some(PathspecMatch::Recursively));
+        assert_eq!(dir.match_path("srcs/a.rs"), None);
+        assert_eq!(
+            item("", false, false).match_path("a"),
+            Some(PathspecMatch::Recursively)
+        );
+        assert_eq!(
+            item("SRC", false, true).match_path("src/a"),
+            Some(PathspecMatch::Recursively)
+        );
+    }
+
+    #[test]
+    fn should_match_wildcards_across_directories_unless_glob() {
+        assert_eq!(
+            item("*.rs", false, false).match_path("src/a.rs"),
+            Some(PathspecMatch::Glob)
+        );
+        assert_eq!(item("*.rs", true, false).match_path("src/a.rs"), None);
+        assert_eq!(
+            item("**/*.rs", true, false).match_path("src/a.rs"),
+            Some(PathspecMatch::Glob)
+        );
+        assert_eq!(item("src/*.rs", false, false).match_path("lib/a.rs"), None);
+        assert!(item("src/*.rs", false, false).may_match_under("src"));
+        assert!(!item("src/*.rs", false, false).may_match_under("lib"));
+    }
+
+    #[test]
+    fn should_unquote_c_style_strings() {
+        assert_eq!(
+            unquote("a\\tb\\\"c\\303\\251\""),
+            Some(String::from("a\tb\"cé"))
+        );
+        assert_eq!(unquote("unterminated"), None);
+        assert_eq!(unquote("a\"b"), None);
+    }
+}
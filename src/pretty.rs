@@ -0,0 +1,170 @@
+use crate::{
+    color::{ColorWhen, DiffColor, Palette},
+    config::GitConfig,
+    date::{format_timestamp, DateFormat},
+    error::RustGitError,
+    notes_tree::GitNotes,
+    object::{GitCommitObject, GitObjectId},
+    repo::GitRepo,
+};
+
+const ABBREV_LEN: usize = 7;
+
+/// The formats commits can be printed in by `log` and `show`, see the "PRETTY FORMATS" section of
+/// git-log(1).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum CommitFormat {
+    #[default]
+    Medium,
+    Oneline,
+}
+
+fn abbrev(id: &GitObjectId) -> String {
+    id.to_string()[..ABBREV_LEN].to_string()
+}
+
+/// Returns the title line of a commit message.
+fn subject(message: &str) -> &str {
+    message
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("")
+}
+
+/// Returns the colors of `log` and `show`, which color their output as `color.diff` says unless
+/// `--color` was given.
+pub(crate) fn log_palette(
+    config: &GitConfig,
+    color: Option<ColorWhen>,
+) -> Result<Palette<DiffColor>, RustGitError> {
+    let when = color.unwrap_or_else(|| config.color.when(&config.color.diff));
+    Palette::new(when, config.color.diff.slots())
+}
+
+/// Formats a commit in the provided format, with the commit id in the color of the palette.
+/// Unlike medium commits, oneline commits don't end with a newline.
+pub(crate) fn format_commit(
+    id: &GitObjectId,
+    commit: &GitCommitObject,
+    format: CommitFormat,
+    palette: &Palette<DiffColor>,
+) -> String {
+    match format {
+        CommitFormat::Oneline => format!(
+            "{} {}",
+            palette.paint(DiffColor::Commit, &abbrev(id)),
+            subject(&commit.message)
+        ),
+        CommitFormat::Medium => {
+            let mut formatted = format!(
+                "{}\n",
+                palette.paint(DiffColor::Commit, &format!("commit {id}"))
+            );
+            if commit.parents.len() > 1 {
+                let parents = commit.parents.iter().map(abbrev).collect::<Vec<_>>();
+                formatted.push_str(&format!("Merge: {}\n", parents.join(" ")));
+            }
+            formatted.push_str(&format!(
+                "Author: {} <{}>\n",
+                commit.author_name, commit.author_email
+            ));
+            formatted.push_str(&format!(
+                "Date:   {}\n\n",
+                format_timestamp(commit.timestamp, DateFormat::Default)
+            ));
+
+            // Leading and trailing empty lines of the message aren't shown.
+            let message = commit.message.trim_start_matches('\n').trim_end();
+            for line in message.lines() {
+                formatted.push_str(&format!("    {line}\n"));
+            }
+            formatted
+        }
+    }
+}
+
+/// Formats a commit as `log` and `show` print it, followed by its notes from each of the provided
+/// notes refs.
+pub(crate) fn format_log_entry(
+    repo: &GitRepo,
+    id: &GitObjectId,
+    commit: &GitCommitObject,
+    format: CommitFormat,
+    notes: &[GitNotes],
+    palette: &Palette<DiffColor>,
+) -> Result<String, RustGitError> {
+    let mut formatted = format_commit(id, commit, format, palette);
+    if format == CommitFormat::Oneline {
+        formatted.push('\n');
+    }
+
+    for notes in notes {
+        if let Some(note) = notes.format_note(repo, id)? {
+            // Oneline commits are directly followed by their notes.
+            let note = match format {
+                CommitFormat::Oneline => note.trim_start_matches('\n'),
+                CommitFormat::Medium => &note,
+            };
+            formatted.push_str(note);
+        }
+    }
+
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(parents: Vec<GitObjectId>) -> GitCommitObject {
+        GitCommitObject {
+            tree: GitObjectId::new(String::from("303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9")),
+            parents,
+            message: String::from("Test commit\n\nWith a body\n"),
+            author_name: String::from("Test User"),
+            author_email: String::from("test@user.com"),
+            committer_name: String::from("Test User"),
+            committer_email: String::from("test@user.com"),
+            timestamp: 1709212455000,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn should_format_commit() {
+        let id = GitObjectId::new(String::from("30d74d258442c7c65512eafab474568dd706c430"));
+        assert_eq!(
+            format_commit(
+                &id,
+                &commit(vec![]),
+                CommitFormat::Medium,
+                &Palette::default()
+            ),
+            "commit 30d74d258442c7c65512eafab474568dd706c430\n\
+             Author: Test User <test@user.com>\n\
+             Date:   Thu Feb 29 13:14:15 2024 +0000\n\
+             \n    Test commit\n    \n    With a body\n"
+        );
+        assert_eq!(
+            format_commit(
+                &id,
+                &commit(vec![]),
+                CommitFormat::Oneline,
+                &Palette::default()
+            ),
+            "30d74d2 Test commit"
+        );
+
+        let parents = vec![
+            GitObjectId::new(String::from("303a1b0cbbaeaf5ad4b4150e053126e3b103e4d9")),
+            GitObjectId::new(String::from("30d74d258442c7c65512eafab474568dd706c430")),
+        ];
+        assert!(format_commit(
+            &id,
+            &commit(parents),
+            CommitFormat::Medium,
+            &Palette::default()
+        )
+        .contains("\nMerge: 303a1b0 30d74d2\n"));
+    }
+}
@@ -0,0 +1,48 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Reads tree information into the index")]
+#[command(long_about = "
+Reads the tree information given by <tree-ish> into the index, but does not actually update any of the files it
+\"caches\". (see: git-checkout-index(1))
+
+Optionally, it can merge a tree into the index, perform a fast-forward (i.e. 2-way) merge, or a 3-way merge,
+with the -m flag. When used with -m, the -u flag causes it to also update the files in the work tree with the
+result of the merge.
+
+Only trivial merges are done by git read-tree itself. Only conflicting paths will be in unmerged state when
+git read-tree returns.
+")]
+pub(crate) struct ReadTreeArgs {
+    /// Perform a merge, not just a read. The command will refuse to run if your index file has unmerged
+    /// entries, indicating that you have not finished a previous merge you started.
+    #[arg(short = 'm')]
+    pub merge: bool,
+
+    /// Same as -m, except that unmerged entries are discarded instead of failing. When used with -u, updates
+    /// leading to loss of working tree changes or untracked files or directories will not abort the
+    /// operation.
+    #[arg(long)]
+    pub reset: bool,
+
+    /// After a successful merge, update the files in the work tree with the result of the merge.
+    #[arg(short = 'u')]
+    pub update: bool,
+
+    /// Usually a three-way merge by git read-tree resolves the merge for really trivial cases and leaves
+    /// other cases unresolved in the index. This option makes the command resolve a few more cases
+    /// internally: when one side removes a path and the other side leaves the path unmodified, and when
+    /// both sides remove a path.
+    #[arg(long)]
+    pub aggressive: bool,
+
+    /// Keep the current index contents, and read the contents of the named tree-ish under the directory at
+    /// <prefix>. The command will refuse to overwrite entries that already existed in the original index
+    /// file.
+    #[arg(long, value_name = "prefix")]
+    pub prefix: Option<String>,
+
+    /// The id of the tree object(s) to be read/merged.
+    #[arg(required = true, num_args = 1..=3, value_name = "tree-ish")]
+    pub trees: Vec<String>,
+}
@@ -0,0 +1,454 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    command::GitCommand,
+    index::{GitIndexEntry, GitIndexStageFlag},
+    repo::{GitRepo, GitRepoPath, RepoState},
+    RustGitError,
+};
+
+use super::cli::ReadTreeArgs;
+
+/// Returns whether both entries are missing, or both exist with the same mode and object.
+fn same(a: Option<&GitIndexEntry>, b: Option<&GitIndexEntry>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a.mode == b.mode && a.name == b.name,
+        _ => false,
+    }
+}
+
+fn with_stage(entry: &GitIndexEntry, stage: GitIndexStageFlag) -> GitIndexEntry {
+    let mut entry = entry.clone();
+    entry.flags.stage = stage;
+    entry
+}
+
+/// Computes the index resulting from merging trees into it, following the rules of
+/// https://git-scm.com/docs/git-read-tree#_merging, and the changes to make to the worktree.
+/// Like C Git, every path which can't be merged is reported before giving up.
+struct TreeMerger<'a> {
+    repo: &'a GitRepo,
+    update: bool,
+    reset: bool,
+    aggressive: bool,
+    entries: Vec<GitIndexEntry>,
+    /// Positions in `entries` of the entries to write to the worktree.
+    checkouts: Vec<usize>,
+    /// Paths to remove from the worktree.
+    removals: Vec<GitRepoPath>,
+    errors: Vec<String>,
+}
+
+impl<'a> TreeMerger<'a> {
+    fn new(repo: &'a GitRepo, args: &ReadTreeArgs) -> TreeMerger<'a> {
+        TreeMerger {
+            repo,
+            update: args.update,
+            reset: args.reset,
+            aggressive: args.aggressive,
+            entries: vec![],
+            checkouts: vec![],
+            removals: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn keep_entry(&mut self, entry: &GitIndexEntry) {
+        self.entries.push(entry.clone());
+    }
+
+    fn checkout_entry(&mut self, entry: &GitIndexEntry) {
+        self.checkouts.push(self.entries.len());
+        self.entries.push(entry.clone());
+    }
+
+    /// Records `merge` as the merged entry of its path, which `old` had in the index.
+    fn merged_entry(
+        &mut self,
+        merge: &GitIndexEntry,
+        old: Option<&GitIndexEntry>,
+    ) -> Result<(), RustGitError> {
+        match old {
+            // The stat data of the old entry still holds.
+            Some(old) if same(Some(old), Some(merge)) => self.keep_entry(old),
+            Some(old) => {
+                if self.verify_uptodate(old)? {
+                    self.checkout_entry(merge);
+                }
+            }
+            None => {
+                if self.verify_absent(&merge.path_name)? {
+                    self.checkout_entry(merge);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the path of `old` from the index.
+    fn deleted_entry(&mut self, old: Option<&GitIndexEntry>) -> Result<(), RustGitError> {
+        if let Some(old) = old {
+            if self.verify_uptodate(old)? {
+                self.removals.push(old.path_name.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn reject_merge(&mut self, path: &GitRepoPath) {
+        self.errors.push(format!(
+            "error: Entry '{}' would be overwritten by merge. Cannot merge.",
+            path.as_string()
+        ));
+    }
+
+    /// Returns whether the worktree file of the provided index entry is missing or differs from
+    /// it.
+    fn is_modified(&self, entry: &GitIndexEntry) -> Result<bool, RustGitError> {
+        match self.repo.worktree_metadata(&entry.path_name)? {
+            Some(metadata) => self.repo.is_entry_modified(entry, &metadata),
+            None => Ok(true),
+        }
+    }
+
+    /// Returns whether the worktree file of the provided index entry is missing or matches it,
+    /// so that it can be replaced. Local changes are only overwritten with --reset.
+    fn verify_uptodate(&mut self, entry: &GitIndexEntry) -> Result<bool, RustGitError> {
+        if self.reset || entry.extended_flags.skip_worktree {
+            return Ok(true);
+        }
+        let Some(metadata) = self.repo.worktree_metadata(&entry.path_name)? else {
+            return Ok(true);
+        };
+        if self.repo.is_entry_modified(entry, &metadata)? {
+            self.errors.push(format!(
+                "error: Entry '{}' not uptodate. Cannot merge.",
+                entry.path_name.as_string()
+            ));
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Returns whether there is no untracked file at the provided path which updating the
+    /// worktree would overwrite.
+    fn verify_absent(&mut self, path: &GitRepoPath) -> Result<bool, RustGitError> {
+        if !self.update || self.reset {
+            return Ok(true);
+        }
+        match self.repo.worktree_metadata(path)? {
+            Some(metadata) if !metadata.is_dir() => {
+                self.errors.push(format!(
+                    "error: Untracked working tree file '{}' would be overwritten by merge.",
+                    path.as_string()
+                ));
+                Ok(false)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    /// Reads a single tree into the index, keeping the stat data of unchanged entries.
+    fn one_way(
+        &mut self,
+        index: Option<&GitIndexEntry>,
+        tree: Option<&GitIndexEntry>,
+    ) -> Result<(), RustGitError> {
+        let Some(tree) = tree else {
+            return self.deleted_entry(index);
+        };
+        match index {
+            Some(index) if same(Some(index), Some(tree)) => {
+                // Local changes to the file are discarded by --reset -u.
+                if self.reset && self.update && self.is_modified(index)? {
+                    self.checkout_entry(index);
+                } else {
+                    self.keep_entry(index);
+                }
+                Ok(())
+            }
+            _ => self.merged_entry(tree, index),
+        }
+    }
+
+    /// Moves the index from tree `head` to tree `merge`, keeping the changes made in the index
+    /// and refusing to lose them, as for a fast-forward.
+    fn two_way(
+        &mut self,
+        index: Option<&GitIndexEntry>,
+        head: Option<&GitIndexEntry>,
+        merge: Option<&GitIndexEntry>,
+    ) -> Result<(), RustGitError> {
+        if let Some(index) = index {
+            if head.is_none() && merge.is_none()
+                || head.is_none() && same(Some(index), merge)
+                || head.is_some() && merge.is_some() && same(head, merge)
+                || head.is_some() && merge.is_some() && same(Some(index), merge)
+            {
+                self.keep_entry(index);
+            } else if head.is_some() && merge.is_none() && same(Some(index), head) {
+                self.deleted_entry(Some(index))?;
+            } else if let (Some(_), Some(merge)) = (head, merge) {
+                if same(Some(index), head) {
+                    self.merged_entry(merge, Some(index))?;
+                } else {
+                    self.reject_merge(&index.path_name);
+                }
+            } else {
+                self.reject_merge(&index.path_name);
+            }
+            return Ok(());
+        }
+
+        match (head, merge) {
+            // The removal of the path was staged, unless the index is being created.
+            (Some(head), Some(merge)) if !self.repo.index.is_unborn() => {
+                if !same(Some(head), Some(merge)) {
+                    self.reject_merge(&head.path_name);
+                }
+                Ok(())
+            }
+            (_, Some(merge)) => self.merged_entry(merge, None),
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// Merges the changes from tree `base` to trees `head` and `remote`, leaving the paths
+    /// which can't be trivially merged unmerged, with their entries from each tree at stages
+    /// 1, 2 and 3.
+    fn three_way(
+        &mut self,
+        index: Option<&GitIndexEntry>,
+        base: Option<&GitIndexEntry>,
+        head: Option<&GitIndexEntry>,
+        remote: Option<&GitIndexEntry>,
+    ) -> Result<(), RustGitError> {
+        let head_match = base.is_some() && same(base, head);
+        let remote_match = base.is_some() && same(base, remote);
+
+        // Only the remote side changed the path; the index may already match the result.
+        if let (Some(remote), true, false) = (remote, head_match, remote_match) {
+            if index.is_some() && !same(index, Some(remote)) && !same(index, head) {
+                self.reject_merge(&remote.path_name);
+                return Ok(());
+            }
+            return self.merged_entry(remote, index);
+        }
+
+        if let Some(index) = index {
+            if !same(Some(index), head) {
+                self.reject_merge(&index.path_name);
+                return Ok(());
+            }
+        }
+
+        if let Some(head) = head {
+            // Both sides made the same change, or only the head side changed the path.
+            if same(Some(head), remote) || remote_match && !head_match {
+                return self.merged_entry(head, index);
+            }
+        }
+
+        if self.aggressive
+            && (head.is_none() && remote.is_none()
+                || head.is_none() && remote_match
+                || remote.is_none() && head_match)
+        {
+            return self.deleted_entry(index);
+        }
+
+        // The path is left unmerged, so its file must not have local changes which the
+        // conflict resolution would overwrite.
+        if let Some(index) = index {
+            if !self.verify_uptodate(index)? {
+                return Ok(());
+            }
+        }
+        if let Some(base) = base {
+            self.keep_entry(&with_stage(base, GitIndexStageFlag::Base));
+        }
+        if let Some(head) = head {
+            self.keep_entry(&with_stage(head, GitIndexStageFlag::Ours));
+        }
+        if let Some(remote) = remote {
+            self.keep_entry(&with_stage(remote, GitIndexStageFlag::Theirs));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct ReadTreeCommand {
+    args: ReadTreeArgs,
+    /// The directory to read the tree into, ending with a slash.
+    prefix: Option<String>,
+}
+
+impl ReadTreeCommand {
+    pub fn new(args: ReadTreeArgs) -> Result<ReadTreeCommand, RustGitError> {
+        if args.update && !args.merge && !args.reset && args.prefix.is_none() {
+            return Err(RustGitError::new(
+                "fatal: -u is meaningless without -m, --reset, or --prefix",
+            ));
+        }
+
+        let prefix = match &args.prefix {
+            Some(_) if args.trees.len() > 1 => {
+                return Err(RustGitError::new(
+                    "fatal: --prefix can only be used with a single tree",
+                ))
+            }
+            Some(prefix) if prefix.starts_with('/') => {
+                return Err(RustGitError::new(format!("fatal: invalid path '{prefix}'")))
+            }
+            Some(prefix) if prefix.is_empty() || prefix.ends_with('/') => Some(prefix.clone()),
+            Some(prefix) => Some(format!("{prefix}/")),
+            None => None,
+        };
+
+        Ok(ReadTreeCommand { args, prefix })
+    }
+
+    /// Reads the entries of the tree-ish with the provided name, with paths under the prefix.
+    fn read_tree_ish(
+        &self,
+        repo: &GitRepo,
+        name: &str,
+    ) -> Result<Vec<GitIndexEntry>, RustGitError> {
+        let failed = || RustGitError::new(format!("fatal: failed to unpack tree object {name}"));
+        let id = repo.resolve_revision(name).map_err(|_| failed())?;
+        let tree_id = repo.peel_to_tree(&id).map_err(|_| failed())?;
+        repo.read_tree_entries(
+            &tree_id,
+            self.prefix.as_deref().unwrap_or(""),
+            GitIndexStageFlag::RegularFileNoConflict,
+        )
+    }
+
+    /// Adds the entries of a tree to the index under the prefix, where there must be no
+    /// entries yet.
+    fn bind(&self, repo: &mut GitRepo, entries: Vec<GitIndexEntry>) -> Result<(), RustGitError> {
+        let errors = entries
+            .iter()
+            .filter(|entry| repo.index.entry_by_path(&entry.path_name).is_some())
+            .map(|entry| {
+                let path = entry.path_name.as_string();
+                format!("error: Entry '{path}' overlaps with '{path}'.  Cannot bind.")
+            })
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(RustGitError::new(errors.join("\n")));
+        }
+
+        for mut entry in entries {
+            if self.args.update {
+                let metadata = repo.checkout_entry(&entry)?;
+                entry.refresh_stat(&metadata);
+            }
+            repo.index.add(entry);
+        }
+        Ok(())
+    }
+
+    /// Merges the provided trees into the index, and updates the worktree with -u.
+    fn merge(
+        &self,
+        repo: &mut GitRepo,
+        trees: Vec<Vec<GitIndexEntry>>,
+    ) -> Result<(), RustGitError> {
+        let has_unmerged_entries = repo
+            .index
+            .iter_entries()
+            .any(|entry| entry.flags.stage != GitIndexStageFlag::RegularFileNoConflict);
+        if has_unmerged_entries && !self.args.reset {
+            return Err(RustGitError::new(
+                "fatal: You need to resolve your current index first",
+            ));
+        }
+
+        // The entries of each path in the index and in each tree. With --reset, unmerged
+        // entries are discarded.
+        let mut paths: BTreeMap<
+            GitRepoPath,
+            (Option<&GitIndexEntry>, Vec<Option<&GitIndexEntry>>),
+        > = BTreeMap::new();
+        for entry in repo
+            .index
+            .iter_entries()
+            .filter(|entry| entry.flags.stage == GitIndexStageFlag::RegularFileNoConflict)
+        {
+            paths
+                .entry(entry.path_name.clone())
+                .or_insert_with(|| (None, vec![None; trees.len()]))
+                .0 = Some(entry);
+        }
+        for (tree_idx, tree) in trees.iter().enumerate() {
+            for entry in tree {
+                paths
+                    .entry(entry.path_name.clone())
+                    .or_insert_with(|| (None, vec![None; trees.len()]))
+                    .1[tree_idx] = Some(entry);
+            }
+        }
+
+        let mut merger = TreeMerger::new(repo, &self.args);
+        for (index, tree_entries) in paths.values() {
+            match tree_entries.as_slice() {
+                [tree] => merger.one_way(*index, *tree)?,
+                [head, merge] => merger.two_way(*index, *head, *merge)?,
+                [base, head, remote] => merger.three_way(*index, *base, *head, *remote)?,
+                _ => unreachable!("at most three trees are read"),
+            }
+        }
+        if !merger.errors.is_empty() {
+            return Err(RustGitError::new(merger.errors.join("\n")));
+        }
+
+        let TreeMerger {
+            mut entries,
+            checkouts,
+            removals,
+            ..
+        } = merger;
+        if self.args.update {
+            for path in &removals {
+                repo.remove_worktree_file(path)?;
+            }
+            for idx in checkouts {
+                let metadata = repo.checkout_entry(&entries[idx])?;
+                entries[idx].refresh_stat(&metadata);
+            }
+        }
+        repo.index.replace_entries(entries);
+        Ok(())
+    }
+}
+
+impl GitCommand for ReadTreeCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let mut repo = repo_state.try_get()?;
+        repo.lock_index()?;
+
+        let trees = self
+            .args
+            .trees
+            .iter()
+            .map(|name| self.read_tree_ish(&repo, name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if self.prefix.is_some() {
+            self.bind(&mut repo, trees.into_iter().flatten().collect())?;
+        } else if self.args.merge || self.args.reset {
+            self.merge(&mut repo, trees)?;
+        } else {
+            // Without merging, the trees replace the index, with later trees taking precedence.
+            let mut entries = BTreeMap::new();
+            for entry in trees.into_iter().flatten() {
+                entries.insert(entry.path_name.clone(), entry);
+            }
+            repo.index.replace_entries(entries.into_values().collect());
+        }
+
+        repo.write_index()
+    }
+}
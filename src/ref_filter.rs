@@ -0,0 +1,1120 @@
+// Shared machinery for listing refs, used by for-each-ref, tag and branch.
+// Loosely modelled on C Git's ref-filter:
+// https://github.com/git/git/blob/master/ref-filter.c
+
+use std::{cmp::Ordering, collections::HashSet, str::FromStr};
+
+use crate::{
+    date::{format_timestamp, DateFormat},
+    error::RustGitError,
+    object::{GitCommitObject, GitObjectId, GitObjectType, GitTagObject},
+    refs::GitRef,
+    repo::GitRepo,
+    wildmatch::{wildmatch, WildmatchFlags},
+};
+
+const DEFAULT_ABBREV_LEN: usize = 7;
+
+/// Shortens a full ref name to the name users usually type, e.g. `refs/heads/main` to `main`.
+pub(crate) fn shorten_ref_name(ref_name: &str) -> &str {
+    for prefix in ["refs/heads/", "refs/tags/", "refs/remotes/", "refs/"] {
+        if let Some(short_name) = ref_name.strip_prefix(prefix) {
+            return short_name;
+        }
+    }
+    ref_name
+}
+
+/// Compares two strings, treating runs of digits as numbers, so that e.g. `v1.9` sorts before
+/// `v1.10`.
+pub(crate) fn version_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_ch), Some(b_ch)) if a_ch.is_ascii_digit() && b_ch.is_ascii_digit() => {
+                let mut a_num = String::new();
+                while let Some(ch) = a_chars.next_if(|c| c.is_ascii_digit()) {
+                    a_num.push(ch);
+                }
+                let mut b_num = String::new();
+                while let Some(ch) = b_chars.next_if(|c| c.is_ascii_digit()) {
+                    b_num.push(ch);
+                }
+
+                let a_trimmed = a_num.trim_start_matches('0');
+                let b_trimmed = b_num.trim_start_matches('0');
+                let ordering = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(a_ch), Some(b_ch)) => {
+                let ordering = a_ch.cmp(b_ch);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Formats a ref name for the `refname` and `symref` atoms, applying their `short`, `lstrip` and
+/// `rstrip` modifiers.
+fn format_ref_name(
+    ref_name: &str,
+    atom_name: &str,
+    modifier: Option<&str>,
+) -> Result<String, RustGitError> {
+    let value = match modifier {
+        None => ref_name.to_string(),
+        Some("short") => shorten_ref_name(ref_name).to_string(),
+        Some(modifier) => {
+            let (direction, count) = match modifier.split_once('=') {
+                Some(("lstrip" | "strip", count)) => ("lstrip", count),
+                Some(("rstrip", count)) => ("rstrip", count),
+                _ => {
+                    return Err(RustGitError::new(format!(
+                        "fatal: unrecognized %({atom_name}) argument: {modifier}"
+                    )))
+                }
+            };
+            // C Git names `refname` here whatever the atom.
+            let count = count.parse::<isize>().map_err(|_| {
+                RustGitError::new(format!(
+                    "fatal: Integer value expected refname:{direction}={count}"
+                ))
+            })?;
+            let components: Vec<&str> = ref_name.split('/').collect();
+            // A negative count keeps that many components from the other end instead.
+            let stripped = if count < 0 {
+                components.len().saturating_sub(count.unsigned_abs())
+            } else {
+                count.unsigned_abs().min(components.len())
+            };
+            if direction == "lstrip" {
+                components[stripped..].join("/")
+            } else {
+                components[..components.len() - stripped].join("/")
+            }
+        }
+    };
+
+    Ok(value)
+}
+
+/// Splits a message into its subject (first paragraph, joined into one line) and body.
+fn split_message(message: &str) -> (String, String) {
+    let message = message.trim_start_matches('\n');
+    let (subject, body) = message.split_once("\n\n").unwrap_or((message, ""));
+    let subject = subject.lines().collect::<Vec<&str>>().join(" ");
+    (subject, body.to_string())
+}
+
+/// The number of commits on each side of a branch and its upstream.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct AheadBehind {
+    pub(crate) ahead: usize,
+    pub(crate) behind: usize,
+}
+
+impl GitRepo {
+    /// Counts the commits reachable from `local` but not `upstream` (ahead), and vice versa
+    /// (behind).
+    pub(crate) fn ahead_behind(
+        &self,
+        local: &GitObjectId,
+        upstream: &GitObjectId,
+    ) -> Result<AheadBehind, RustGitError> {
+        let local_ancestors = self.ancestors(local)?;
+        let upstream_ancestors = self.ancestors(upstream)?;
+
+        Ok(AheadBehind {
+            ahead: local_ancestors.difference(&upstream_ancestors).count(),
+            behind: upstream_ancestors.difference(&local_ancestors).count(),
+        })
+    }
+}
+
+/// A ref along with the details of the object it points to, which are needed to filter, sort
+/// and format it.
+pub(crate) struct RefItem {
+    pub(crate) name: String,
+    pub(crate) id: GitObjectId,
+    pub(crate) obj_type: GitObjectType,
+    obj_size: usize,
+    commit: Option<GitCommitObject>,
+    tag: Option<GitTagObject>,
+    /// For tags, the object that the tag ultimately points to.
+    peeled: Option<(GitObjectId, GitObjectType)>,
+    peeled_commit: Option<GitCommitObject>,
+    /// The commit this ref points to, either directly or through tags.
+    pub(crate) commit_id: Option<GitObjectId>,
+    pub(crate) upstream: Option<String>,
+    pub(crate) tracking: Option<AheadBehind>,
+    pub(crate) is_head: bool,
+    /// For symbolic refs, the full name of the ref they point to.
+    symref: Option<String>,
+}
+
+impl RefItem {
+    /// Loads the provided ref. `head_branch` is the full name of the branch HEAD points to, and
+    /// how far branches are from their upstream is only computed when `details.tracking` is set.
+    pub(crate) fn load(
+        repo: &GitRepo,
+        git_ref: &GitRef,
+        head_branch: Option<&str>,
+        details: RefDetails,
+    ) -> Result<RefItem, RustGitError> {
+        let obj = repo.obj_store.read_object(&git_ref.id)?.ok_or_else(|| {
+            RustGitError::new(format!(
+                "fatal: missing object {} for {}",
+                git_ref.id, git_ref.name
+            ))
+        })?;
+        let obj_type = obj.header.obj_type;
+
+        let mut commit = None;
+        let mut tag = None;
+        let mut peeled = None;
+        let mut peeled_commit = None;
+        let mut commit_id = None;
+
+        match obj_type {
+            GitObjectType::Commit => {
+                commit = Some(obj.content.parse::<GitCommitObject>()?);
+                commit_id = Some(git_ref.id.clone());
+            }
+            GitObjectType::Tag => {
+                tag = Some(obj.content.parse::<GitTagObject>()?);
+                let (peeled_id, peeled_type) = repo.peel(&git_ref.id)?;
+                if peeled_type == GitObjectType::Commit {
+                    peeled_commit = Some(repo.read_commit(&peeled_id)?);
+                    commit_id = Some(peeled_id.clone());
+                }
+                peeled = Some((peeled_id, peeled_type));
+            }
+            _ => (),
+        }
+
+        let is_head = head_branch == Some(git_ref.name.as_str());
+
+        let symref = repo
+            .refs
+            .get_symbolic_ref(&git_ref.name)?
+            .and_then(|value| value.strip_prefix("ref: ").map(str::to_string));
+
+        let mut upstream = None;
+        let mut tracking = None;
+        if let Some(branch_name) = git_ref.name.strip_prefix("refs/heads/") {
+            if let Some(upstream_ref) = repo
+                .config
+                .branch
+                .get(branch_name)
+                .and_then(|branch_config| branch_config.upstream_ref())
+            {
+                if details.tracking {
+                    if let (Some(local), Some(upstream_id)) =
+                        (&commit_id, repo.refs.resolve_ref(&upstream_ref)?)
+                    {
+                        tracking = Some(repo.ahead_behind(local, &upstream_id)?);
+                    }
+                }
+                upstream = Some(upstream_ref);
+            }
+        }
+
+        Ok(RefItem {
+            name: git_ref.name.clone(),
+            id: git_ref.id.clone(),
+            obj_type,
+            obj_size: obj.header.size,
+            commit,
+            tag,
+            peeled,
+            peeled_commit,
+            commit_id,
+            upstream,
+            tracking,
+            is_head,
+            symref,
+        })
+    }
+}
+
+/// How the patterns of a `RefFilter` are matched against ref names.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PatternMatch {
+    /// Patterns match a full ref name, either as a glob or as a leading path
+    /// (as in for-each-ref).
+    FullName,
+    /// Patterns are globs matched against the shortened ref name (as in tag and branch).
+    ShortName,
+}
+
+/// Details about refs that are costly to look up, only loaded when something asks for them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct RefDetails {
+    /// Whether each ref is the branch HEAD points to.
+    pub(crate) head: bool,
+    /// How far each branch is ahead of and behind its upstream.
+    pub(crate) tracking: bool,
+}
+
+impl RefDetails {
+    /// Combines the details needed by either side.
+    pub(crate) fn union(self, other: RefDetails) -> RefDetails {
+        RefDetails {
+            head: self.head || other.head,
+            tracking: self.tracking || other.tracking,
+        }
+    }
+}
+
+/// Criteria used to select which refs get listed.
+#[derive(Debug)]
+pub(crate) struct RefFilter {
+    pub(crate) patterns: Vec<String>,
+    pub(crate) pattern_match: PatternMatch,
+    pub(crate) ignore_case: bool,
+    pub(crate) points_at: Vec<GitObjectId>,
+    pub(crate) merged: Option<GitObjectId>,
+    pub(crate) no_merged: Option<GitObjectId>,
+    pub(crate) contains: Vec<GitObjectId>,
+    pub(crate) no_contains: Vec<GitObjectId>,
+    /// The costly details to load for each listed ref.
+    pub(crate) details: RefDetails,
+}
+
+impl RefFilter {
+    pub(crate) fn new(patterns: Vec<String>, pattern_match: PatternMatch) -> RefFilter {
+        RefFilter {
+            patterns,
+            pattern_match,
+            ignore_case: false,
+            points_at: Vec::new(),
+            merged: None,
+            no_merged: None,
+            contains: Vec::new(),
+            no_contains: Vec::new(),
+            details: RefDetails::default(),
+        }
+    }
+
+    fn matches_name(&self, ref_name: &str) -> bool {
+        if self.patterns.is_empty() {
+            return true;
+        }
+
+        let flags = WildmatchFlags {
+            pathname: self.pattern_match == PatternMatch::FullName,
+            casefold: self.ignore_case,
+        };
+
+        self.patterns
+            .iter()
+            .any(|pattern| match self.pattern_match {
+                PatternMatch::FullName => {
+                    let is_leading_path = ref_name
+                        .strip_prefix(pattern.as_str())
+                        .map(|rest| {
+                            rest.is_empty() || rest.starts_with('/') || pattern.ends_with('/')
+                        })
+                        .unwrap_or(false);
+                    is_leading_path || wildmatch(pattern, ref_name, flags)
+                }
+                PatternMatch::ShortName => wildmatch(pattern, shorten_ref_name(ref_name), flags),
+            })
+    }
+
+    /// Loads the refs matching this filter.
+    pub(crate) fn apply(
+        &self,
+        repo: &GitRepo,
+        refs: Vec<GitRef>,
+    ) -> Result<Vec<RefItem>, RustGitError> {
+        let merged_ancestors = self
+            .merged
+            .as_ref()
+            .map(|id| repo.ancestors(id))
+            .transpose()?;
+        let no_merged_ancestors = self
+            .no_merged
+            .as_ref()
+            .map(|id| repo.ancestors(id))
+            .transpose()?;
+
+        let head = if self.details.head {
+            repo.read_head()?
+        } else {
+            None
+        };
+        let head_branch = head.as_ref().and_then(|head| head.branch());
+
+        let mut items = Vec::new();
+        for git_ref in refs {
+            if !self.matches_name(&git_ref.name) {
+                continue;
+            }
+
+            let item = RefItem::load(repo, &git_ref, head_branch, self.details)?;
+
+            if !self.points_at.is_empty()
+                && !self.points_at.iter().any(|id| {
+                    *id == item.id || item.peeled.as_ref().is_some_and(|(peeled, _)| peeled == id)
+                })
+            {
+                continue;
+            }
+
+            let needs_commit = merged_ancestors.is_some()
+                || no_merged_ancestors.is_some()
+                || !self.contains.is_empty()
+                || !self.no_contains.is_empty();
+            if needs_commit {
+                let Some(commit_id) = &item.commit_id else {
+                    continue;
+                };
+
+                if let Some(ancestors) = &merged_ancestors {
+                    if !ancestors.contains(commit_id) {
+                        continue;
+                    }
+                }
+                if let Some(ancestors) = &no_merged_ancestors {
+                    if ancestors.contains(commit_id) {
+                        continue;
+                    }
+                }
+                if !self.contains.is_empty() || !self.no_contains.is_empty() {
+                    let ref_ancestors: HashSet<GitObjectId> = repo.ancestors(commit_id)?;
+                    if !self.contains.is_empty()
+                        && !self.contains.iter().any(|id| ref_ancestors.contains(id))
+                    {
+                        continue;
+                    }
+                    if self.no_contains.iter().any(|id| ref_ancestors.contains(id)) {
+                        continue;
+                    }
+                }
+            }
+
+            items.push(item);
+        }
+
+        Ok(items)
+    }
+}
+
+/// A field of a ref, as referenced by `%(<name>[:<modifier>])` in a format, or by a sort key.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Atom {
+    /// Whether the atom applies to the object a tag points to (`%(*<name>)`).
+    deref: bool,
+    name: String,
+    modifier: Option<String>,
+}
+
+impl FromStr for Atom {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (deref, s) = match s.strip_prefix('*') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (name, modifier) = match s.split_once(':') {
+            Some((name, modifier)) => (name, Some(modifier.to_string())),
+            None => (s, None),
+        };
+
+        const KNOWN_ATOMS: [&str; 30] = [
+            "refname",
+            "objectname",
+            "objecttype",
+            "objectsize",
+            "tree",
+            "parent",
+            "numparent",
+            "object",
+            "type",
+            "tag",
+            "author",
+            "authorname",
+            "authoremail",
+            "authordate",
+            "committer",
+            "committername",
+            "committeremail",
+            "committerdate",
+            "tagger",
+            "taggername",
+            "taggeremail",
+            "taggerdate",
+            "creator",
+            "creatordate",
+            "subject",
+            "body",
+            "contents",
+            "upstream",
+            "HEAD",
+            "symref",
+        ];
+        if !KNOWN_ATOMS.contains(&name) {
+            return Err(RustGitError::new(format!(
+                "fatal: unknown field name: {name}"
+            )));
+        }
+
+        Ok(Atom {
+            deref,
+            name: name.to_string(),
+            modifier,
+        })
+    }
+}
+
+/// Value of an atom, used for sorting.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortValue {
+    Number(u128),
+    Text(String),
+}
+
+impl Atom {
+    /// The costly details this atom needs to be loaded for each ref.
+    fn details(&self) -> RefDetails {
+        RefDetails {
+            head: self.name == "HEAD",
+            tracking: self.name == "upstream"
+                && matches!(self.modifier.as_deref(), Some("track" | "trackshort")),
+        }
+    }
+
+    fn date_format(&self) -> Result<DateFormat, RustGitError> {
+        match &self.modifier {
+            Some(modifier) => modifier.parse(),
+            None => Ok(DateFormat::Default),
+        }
+    }
+
+    fn identity<'a>(
+        &self,
+        role: &str,
+        commit: Option<&'a GitCommitObject>,
+        tag: Option<&'a GitTagObject>,
+    ) -> Option<(&'a str, &'a str, u128)> {
+        match (role, commit, tag) {
+            ("author", Some(commit), _) | ("creator", Some(commit), _) => {
+                Some((&commit.author_name, &commit.author_email, commit.timestamp))
+            }
+            ("committer", Some(commit), _) => Some((
+                &commit.committer_name,
+                &commit.committer_email,
+                commit.timestamp,
+            )),
+            ("tagger", _, Some(tag)) | ("creator", _, Some(tag)) => {
+                Some((&tag.tagger_name, &tag.tagger_email, tag.timestamp))
+            }
+            _ => None,
+        }
+    }
+
+    fn timestamp(&self, item: &RefItem) -> Option<u128> {
+        let (commit, tag) = self.objects(item);
+        let role = self.name.trim_end_matches("date");
+        self.identity(role, commit, tag)
+            .map(|(_, _, timestamp)| timestamp)
+    }
+
+    fn objects<'a>(
+        &self,
+        item: &'a RefItem,
+    ) -> (Option<&'a GitCommitObject>, Option<&'a GitTagObject>) {
+        if self.deref {
+            (item.peeled_commit.as_ref(), None)
+        } else {
+            (item.commit.as_ref(), item.tag.as_ref())
+        }
+    }
+
+    fn sort_value(&self, item: &RefItem) -> Result<SortValue, RustGitError> {
+        match self.name.as_str() {
+            "authordate" | "committerdate" | "taggerdate" | "creatordate" => {
+                Ok(SortValue::Number(self.timestamp(item).unwrap_or(0)))
+            }
+            "objectsize" | "numparent" => {
+                Ok(SortValue::Number(self.value(item)?.parse().unwrap_or(0)))
+            }
+            _ => Ok(SortValue::Text(self.value(item)?)),
+        }
+    }
+
+    /// Computes the formatted value of this atom for the provided ref.
+    fn value(&self, item: &RefItem) -> Result<String, RustGitError> {
+        let modifier = self.modifier.as_deref();
+
+        if self.deref && item.peeled.is_none() {
+            return Ok(String::new());
+        }
+
+        let (obj_id, obj_type) = match (&item.peeled, self.deref) {
+            (Some((peeled_id, peeled_type)), true) => (peeled_id, *peeled_type),
+            _ => (&item.id, item.obj_type),
+        };
+        let (commit, tag) = self.objects(item);
+
+        let value = match self.name.as_str() {
+            "refname" => format_ref_name(&item.name, "refname", modifier)?,
+            "symref" => match &item.symref {
+                Some(target) => format_ref_name(target, "symref", modifier)?,
+                None => String::new(),
+            },
+            "objectname" => match modifier {
+                None => obj_id.to_string(),
+                Some("short") => obj_id.to_string()[..DEFAULT_ABBREV_LEN].to_string(),
+                Some(modifier) => match modifier.strip_prefix("short=") {
+                    Some(len) => {
+                        let id = obj_id.to_string();
+                        let len = len.parse::<usize>()?.clamp(4, id.len());
+                        id[..len].to_string()
+                    }
+                    None => {
+                        return Err(RustGitError::new(format!(
+                            "fatal: unrecognized %(objectname) argument: {modifier}"
+                        )))
+                    }
+                },
+            },
+            "objecttype" => obj_type.to_string(),
+            "objectsize" if !self.deref => item.obj_size.to_string(),
+            "tree" => commit.map(|c| c.tree.to_string()).unwrap_or_default(),
+            "parent" => commit
+                .map(|c| {
+                    c.parents
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                })
+                .unwrap_or_default(),
+            "numparent" => commit
+                .map(|c| c.parents.len().to_string())
+                .unwrap_or_default(),
+            "object" => tag.map(|t| t.object_id.to_string()).unwrap_or_default(),
+            "type" => tag.map(|t| t.object_type.to_string()).unwrap_or_default(),
+            "tag" => tag.map(|t| t.tag_name.clone()).unwrap_or_default(),
+            "author" | "committer" | "tagger" | "creator" => self
+                .identity(&self.name, commit, tag)
+                .map(|(name, email, timestamp)| {
+                    format!(
+                        "{name} <{email}> {}",
+                        format_timestamp(timestamp, DateFormat::Raw)
+                    )
+                })
+                .unwrap_or_default(),
+            "authorname" | "committername" | "taggername" => self
+                .identity(self.name.trim_end_matches("name"), commit, tag)
+                .map(|(name, _, _)| name.to_string())
+                .unwrap_or_default(),
+            "authoremail" | "committeremail" | "taggeremail" => self
+                .identity(self.name.trim_end_matches("email"), commit, tag)
+                .map(|(_, email, _)| match modifier {
+                    Some("trim") => email.to_string(),
+                    Some("localpart") => email.split('@').next().unwrap_or("").to_string(),
+                    _ => format!("<{email}>"),
+                })
+                .unwrap_or_default(),
+            "authordate" | "committerdate" | "taggerdate" | "creatordate" => {
+                let date_format = self.date_format()?;
+                self.timestamp(item)
+                    .map(|timestamp| format_timestamp(timestamp, date_format))
+                    .unwrap_or_default()
+            }
+            "subject" | "body" | "contents" => {
+                let message = match (commit, tag) {
+                    (Some(commit), _) => commit.message.as_str(),
+                    (_, Some(tag)) => tag.message.as_str(),
+                    _ => "",
+                };
+                let (subject, body) = split_message(message);
+                match (self.name.as_str(), modifier) {
+                    ("subject", _) | ("contents", Some("subject")) => subject,
+                    ("body", _) | ("contents", Some("body")) => body,
+                    ("contents", Some("signature")) => tag
+                        .and_then(|tag| tag.signature.clone())
+                        .unwrap_or_default(),
+                    ("contents", Some(modifier)) if modifier.starts_with("lines=") => {
+                        let count = modifier.trim_start_matches("lines=").parse::<usize>()?;
+                        // As in C Git, continuation lines are indented.
+                        message
+                            .trim_start_matches('\n')
+                            .lines()
+                            .take(count)
+                            .collect::<Vec<&str>>()
+                            .join("\n    ")
+                    }
+                    // The complete message of a signed tag includes its signature.
+                    _ => match tag.and_then(|tag| tag.signature.as_ref()) {
+                        Some(signature) if commit.is_none() => format!("{message}{signature}"),
+                        _ => message.to_string(),
+                    },
+                }
+            }
+            "upstream" => match (&item.upstream, modifier) {
+                (None, _) => String::new(),
+                (Some(upstream), None) => upstream.clone(),
+                (Some(upstream), Some("short")) => shorten_ref_name(upstream).to_string(),
+                (Some(_), Some("track")) => match item.tracking {
+                    None => String::from("[gone]"),
+                    Some(AheadBehind {
+                        ahead: 0,
+                        behind: 0,
+                    }) => String::new(),
+                    Some(AheadBehind { ahead, behind: 0 }) => format!("[ahead {ahead}]"),
+                    Some(AheadBehind { ahead: 0, behind }) => format!("[behind {behind}]"),
+                    Some(AheadBehind { ahead, behind }) => {
+                        format!("[ahead {ahead}, behind {behind}]")
+                    }
+                },
+                (Some(_), Some("trackshort")) => match item.tracking {
+                    None => String::new(),
+                    Some(AheadBehind {
+                        ahead: 0,
+                        behind: 0,
+                    }) => String::from("="),
+                    Some(AheadBehind {
+                        ahead: _,
+                        behind: 0,
+                    }) => String::from(">"),
+                    Some(AheadBehind {
+                        ahead: 0,
+                        behind: _,
+                    }) => String::from("<"),
+                    Some(_) => String::from("<>"),
+                },
+                (Some(_), Some(modifier)) => {
+                    return Err(RustGitError::new(format!(
+                        "fatal: unrecognized %(upstream) argument: {modifier}"
+                    )))
+                }
+            },
+            "HEAD" => String::from(if item.is_head { "*" } else { " " }),
+            _ => String::new(),
+        };
+
+        Ok(value)
+    }
+}
+
+/// A key to sort refs by, as given to `--sort=<key>`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SortKey {
+    atom: Atom,
+    reverse: bool,
+    version: bool,
+}
+
+impl FromStr for SortKey {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (reverse, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (version, s) = match s.strip_prefix("version:").or_else(|| s.strip_prefix("v:")) {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+
+        Ok(SortKey {
+            atom: s.parse()?,
+            reverse,
+            version,
+        })
+    }
+}
+
+/// The costly details the provided sort keys need to be loaded for each ref.
+pub(crate) fn sort_details(keys: &[SortKey]) -> RefDetails {
+    keys.iter().fold(RefDetails::default(), |details, key| {
+        details.union(key.atom.details())
+    })
+}
+
+/// Sorts refs by the provided keys. As in C Git, the last key is the primary one, and ties are
+/// broken by ref name.
+pub(crate) fn sort_refs(items: &mut [RefItem], keys: &[SortKey]) -> Result<(), RustGitError> {
+    let mut error = None;
+    items.sort_by(|a, b| match compare_refs(a, b, keys) {
+        Ok(ordering) => ordering,
+        Err(err) => {
+            error.get_or_insert(err);
+            Ordering::Equal
+        }
+    });
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+fn compare_refs(a: &RefItem, b: &RefItem, keys: &[SortKey]) -> Result<Ordering, RustGitError> {
+    for key in keys.iter().rev() {
+        let ordering = match (key.atom.sort_value(a)?, key.atom.sort_value(b)?) {
+            (SortValue::Text(a), SortValue::Text(b)) if key.version => version_cmp(&a, &b),
+            (a, b) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        };
+        let ordering = if key.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        if ordering != Ordering::Equal {
+            return Ok(ordering);
+        }
+    }
+
+    Ok(a.name.cmp(&b.name))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum IfCondition {
+    NotEmpty,
+    Equals(String),
+    NotEquals(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FormatNode {
+    Literal(String),
+    Atom(Atom),
+    If {
+        condition: IfCondition,
+        test: Vec<FormatNode>,
+        then: Vec<FormatNode>,
+        otherwise: Vec<FormatNode>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+enum FormatToken {
+    Literal(String),
+    Atom(Atom),
+    If(IfCondition),
+    Then,
+    Else,
+    End,
+}
+
+/// A parsed `--format` string, as described in git-for-each-ref(1).
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RefFormat {
+    nodes: Vec<FormatNode>,
+}
+
+fn tokenize_format(format: &str) -> Result<Vec<FormatToken>, RustGitError> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut rest = format;
+
+    while let Some(percent_idx) = rest.find('%') {
+        literal.push_str(&rest[..percent_idx]);
+        rest = &rest[percent_idx + 1..];
+
+        if let Some(after) = rest.strip_prefix('%') {
+            literal.push('%');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('(') {
+            let close = after.find(')').ok_or_else(|| {
+                RustGitError::new(format!("fatal: malformed format string {format}"))
+            })?;
+            let atom = &after[..close];
+            rest = &after[close + 1..];
+
+            if !literal.is_empty() {
+                tokens.push(FormatToken::Literal(std::mem::take(&mut literal)));
+            }
+
+            let token = match atom {
+                "if" => FormatToken::If(IfCondition::NotEmpty),
+                "then" => FormatToken::Then,
+                "else" => FormatToken::Else,
+                "end" => FormatToken::End,
+                _ => {
+                    if let Some(value) = atom.strip_prefix("if:equals=") {
+                        FormatToken::If(IfCondition::Equals(value.to_string()))
+                    } else if let Some(value) = atom.strip_prefix("if:notequals=") {
+                        FormatToken::If(IfCondition::NotEquals(value.to_string()))
+                    } else {
+                        FormatToken::Atom(atom.parse()?)
+                    }
+                }
+            };
+            tokens.push(token);
+        } else if rest.len() >= 2
+            && rest.is_char_boundary(2)
+            && u8::from_str_radix(&rest[..2], 16).is_ok()
+        {
+            // %xx is interpreted as a hex escape.
+            literal.push(u8::from_str_radix(&rest[..2], 16)? as char);
+            rest = &rest[2..];
+        } else {
+            literal.push('%');
+        }
+    }
+    literal.push_str(rest);
+
+    if !literal.is_empty() {
+        tokens.push(FormatToken::Literal(literal));
+    }
+
+    Ok(tokens)
+}
+
+/// Parses tokens into nodes until reaching one of the provided terminators, which is returned
+/// along with the parsed nodes.
+fn parse_nodes<'a>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = FormatToken>>,
+    terminators: &[&'a str],
+) -> Result<(Vec<FormatNode>, Option<&'a str>), RustGitError> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        let token_name = match &token {
+            FormatToken::Then => Some("then"),
+            FormatToken::Else => Some("else"),
+            FormatToken::End => Some("end"),
+            _ => None,
+        };
+        if let Some(token_name) = token_name {
+            return match terminators
+                .iter()
+                .find(|terminator| **terminator == token_name)
+            {
+                Some(terminator) => Ok((nodes, Some(*terminator))),
+                None => Err(RustGitError::new(format!(
+                    "fatal: format: %({token_name}) atom used without a matching %(if) atom"
+                ))),
+            };
+        }
+
+        match token {
+            FormatToken::Literal(literal) => nodes.push(FormatNode::Literal(literal)),
+            FormatToken::Atom(atom) => nodes.push(FormatNode::Atom(atom)),
+            FormatToken::If(condition) => {
+                let (test, terminator) = parse_nodes(tokens, &["then"])?;
+                if terminator.is_none() {
+                    return Err(RustGitError::new(
+                        "fatal: format: %(if) atom used without a %(then) atom",
+                    ));
+                }
+                let (then, terminator) = parse_nodes(tokens, &["else", "end"])?;
+                let otherwise = match terminator {
+                    Some("else") => {
+                        let (otherwise, terminator) = parse_nodes(tokens, &["end"])?;
+                        if terminator.is_none() {
+                            return Err(RustGitError::new("fatal: format: %(end) atom missing"));
+                        }
+                        otherwise
+                    }
+                    Some(_) => Vec::new(),
+                    None => return Err(RustGitError::new("fatal: format: %(end) atom missing")),
+                };
+                nodes.push(FormatNode::If {
+                    condition,
+                    test,
+                    then,
+                    otherwise,
+                });
+            }
+            FormatToken::Then | FormatToken::Else | FormatToken::End => unreachable!(),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+impl FromStr for RefFormat {
+    type Err = RustGitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize_format(s)?.into_iter().peekable();
+        let (nodes, _) = parse_nodes(&mut tokens, &[])?;
+        Ok(RefFormat { nodes })
+    }
+}
+
+impl RefFormat {
+    fn nodes_details(nodes: &[FormatNode]) -> RefDetails {
+        nodes
+            .iter()
+            .fold(RefDetails::default(), |details, node| match node {
+                FormatNode::Literal(_) => details,
+                FormatNode::Atom(atom) => details.union(atom.details()),
+                FormatNode::If {
+                    test,
+                    then,
+                    otherwise,
+                    ..
+                } => details
+                    .union(Self::nodes_details(test))
+                    .union(Self::nodes_details(then))
+                    .union(Self::nodes_details(otherwise)),
+            })
+    }
+
+    /// The costly details this format needs to be loaded for each ref.
+    pub(crate) fn details(&self) -> RefDetails {
+        Self::nodes_details(&self.nodes)
+    }
+
+    fn format_nodes(nodes: &[FormatNode], item: &RefItem) -> Result<String, RustGitError> {
+        let mut output = String::new();
+
+        for node in nodes {
+            match node {
+                FormatNode::Literal(literal) => output.push_str(literal),
+                FormatNode::Atom(atom) => output.push_str(&atom.value(item)?),
+                FormatNode::If {
+                    condition,
+                    test,
+                    then,
+                    otherwise,
+                } => {
+                    let test_output = Self::format_nodes(test, item)?;
+                    let is_true = match condition {
+                        IfCondition::NotEmpty => !test_output.trim().is_empty(),
+                        IfCondition::Equals(value) => test_output == *value,
+                        IfCondition::NotEquals(value) => test_output != *value,
+                    };
+                    let branch = if is_true { then } else { otherwise };
+                    output.push_str(&Self::format_nodes(branch, item)?);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Formats the provided ref.
+    pub(crate) fn format(&self, item: &RefItem) -> Result<String, RustGitError> {
+        Self::format_nodes(&self.nodes, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_shorten_ref_names() {
+        assert_eq!(shorten_ref_name("refs/heads/main"), "main");
+        assert_eq!(shorten_ref_name("refs/tags/release/v1"), "release/v1");
+        assert_eq!(shorten_ref_name("refs/remotes/origin/main"), "origin/main");
+        assert_eq!(shorten_ref_name("refs/notes/commits"), "notes/commits");
+        assert_eq!(shorten_ref_name("HEAD"), "HEAD");
+    }
+
+    #[test]
+    fn should_compare_versions() {
+        assert_eq!(version_cmp("v1.9", "v1.10"), Ordering::Less);
+        assert_eq!(version_cmp("v1.10", "v1.9"), Ordering::Greater);
+        assert_eq!(version_cmp("v2.0", "v2.0"), Ordering::Equal);
+        assert_eq!(version_cmp("v2", "v2.0"), Ordering::Less);
+        assert_eq!(version_cmp("a", "b"), Ordering::Less);
+    }
+
+    #[test]
+    fn should_parse_sort_keys() {
+        assert_eq!(
+            "-version:refname".parse::<SortKey>(),
+            Ok(SortKey {
+                atom: "refname".parse().unwrap(),
+                reverse: true,
+                version: true,
+            })
+        );
+        assert_eq!(
+            "creatordate".parse::<SortKey>(),
+            Ok(SortKey {
+                atom: "creatordate".parse().unwrap(),
+                reverse: false,
+                version: false,
+            })
+        );
+        assert_eq!(
+            "invalid".parse::<SortKey>(),
+            Err(RustGitError::new("fatal: unknown field name: invalid"))
+        );
+    }
+
+    #[test]
+    fn should_parse_format_with_conditionals() {
+        let format =
+            "%(if)%(upstream)%(then)[%(upstream:short)]%(else)none%(end) %%".parse::<RefFormat>();
+        assert_eq!(
+            format,
+            Ok(RefFormat {
+                nodes: vec![
+                    FormatNode::If {
+                        condition: IfCondition::NotEmpty,
+                        test: vec![FormatNode::Atom("upstream".parse().unwrap())],
+                        then: vec![
+                            FormatNode::Literal(String::from("[")),
+                            FormatNode::Atom("upstream:short".parse().unwrap()),
+                            FormatNode::Literal(String::from("]")),
+                        ],
+                        otherwise: vec![FormatNode::Literal(String::from("none"))],
+                    },
+                    FormatNode::Literal(String::from(" %")),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn should_fail_to_parse_unbalanced_conditionals() {
+        assert_eq!(
+            "%(if)%(refname)".parse::<RefFormat>(),
+            Err(RustGitError::new(
+                "fatal: format: %(if) atom used without a %(then) atom"
+            ))
+        );
+        assert_eq!(
+            "%(if)%(refname)%(then)x".parse::<RefFormat>(),
+            Err(RustGitError::new("fatal: format: %(end) atom missing"))
+        );
+        assert_eq!(
+            "%(end)".parse::<RefFormat>(),
+            Err(RustGitError::new(
+                "fatal: format: %(end) atom used without a matching %(if) atom"
+            ))
+        );
+    }
+
+    #[test]
+    fn should_parse_hex_escapes() {
+        assert_eq!(
+            "%(refname)%09%(objectname)".parse::<RefFormat>(),
+            Ok(RefFormat {
+                nodes: vec![
+                    FormatNode::Atom("refname".parse().unwrap()),
+                    FormatNode::Literal(String::from("\t")),
+                    FormatNode::Atom("objectname".parse().unwrap()),
+                ]
+            })
+        );
+    }
+}
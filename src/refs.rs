@@ -1,47 +1,447 @@
 use std::{
+    fmt::Display,
     fs,
     path::{Path, PathBuf},
 };
 
-use crate::{error::RustGitError, object::GitObjectId};
+use crate::{
+    config::GitConfig, error::RustGitError, init::cli::RefStorageFormat, object::GitObjectId,
+    reftable::ReftableBackend,
+};
 
 const REFS_FOLDER: &str = "refs";
 const HEADS_FOLDER: &str = "heads";
 const TAGS_FOLDER: &str = "tags";
+const PACKED_REFS_FILE: &str = "packed-refs";
+const HEAD: &str = "HEAD";
+const LOGS_FOLDER: &str = "logs";
+const NAMESPACES_FOLDER: &str = "namespaces";
+const PACKED_REFS_HEADER: &str = "# pack-refs with: peeled fully-peeled sorted \n";
 
-pub(crate) struct GitRefs {
+// Symbolic refs can point at other symbolic refs; C Git gives up after 5 levels:
+// https://github.com/git/git/blob/master/refs.h#L66
+const MAX_SYMREF_DEPTH: usize = 5;
+
+/// A ref along with the object id it resolves to.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GitRef {
+    /// Full name of the ref, e.g. `refs/heads/main`.
+    pub(crate) name: String,
+    pub(crate) id: GitObjectId,
+}
+
+/// Checks that a full ref name follows the rules described in git-check-ref-format(1).
+pub(crate) fn is_valid_ref_name(ref_name: &str) -> bool {
+    if ref_name.is_empty()
+        || ref_name == "@"
+        || ref_name.starts_with('/')
+        || ref_name.ends_with('/')
+        || ref_name.ends_with('.')
+        || ref_name.contains("..")
+        || ref_name.contains("//")
+        || ref_name.contains("@{")
+    {
+        return false;
+    }
+
+    if ref_name
+        .chars()
+        .any(|c| c.is_ascii_control() || " ~^:?*[\\".contains(c))
+    {
+        return false;
+    }
+
+    ref_name
+        .split('/')
+        .all(|component| !component.starts_with('.') && !component.ends_with(".lock"))
+}
+
+/// The state of HEAD, which is either attached to a branch or detached at an object.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum GitHead {
+    /// HEAD is a symbolic ref to a branch. The branch has no id if it's unborn, i.e. it has no
+    /// commits yet.
+    Symbolic {
+        ref_name: String,
+        id: Option<GitObjectId>,
+    },
+    Detached(GitObjectId),
+}
+
+impl GitHead {
+    /// Returns the object HEAD currently points to, if any.
+    pub(crate) fn id(&self) -> Option<&GitObjectId> {
+        match self {
+            GitHead::Symbolic { id, .. } => id.as_ref(),
+            GitHead::Detached(id) => Some(id),
+        }
+    }
+
+    /// Returns the full name of the branch HEAD is attached to, if any.
+    pub(crate) fn branch(&self) -> Option<&str> {
+        match self {
+            GitHead::Symbolic { ref_name, .. } => Some(ref_name),
+            GitHead::Detached(_) => None,
+        }
+    }
+}
+
+/// An entry in the packed-refs file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PackedRef {
+    pub(crate) name: String,
+    pub(crate) id: GitObjectId,
+    /// For annotated tags, the id of the object the tag ultimately points at (the `^<id>` line).
+    pub(crate) peeled: Option<GitObjectId>,
+}
+
+/// The value stored for a ref, before any symbolic refs are followed.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RefValue {
+    Direct(GitObjectId),
+    /// A symbolic ref, holding the stored name of the ref it points at.
+    Symbolic(String),
+}
+
+impl RefValue {
+    /// Parses the contents of a loose ref file, which is either an object id or `ref: <name>`.
+    fn parse(value: &str) -> RefValue {
+        let value = value.trim();
+        match value.strip_prefix("ref: ") {
+            Some(target) => RefValue::Symbolic(target.to_string()),
+            None => RefValue::Direct(GitObjectId::new(value.to_string())),
+        }
+    }
+}
+
+impl Display for RefValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefValue::Direct(id) => write!(f, "{id}"),
+            RefValue::Symbolic(target) => write!(f, "ref: {target}"),
+        }
+    }
+}
+
+/// A single change in a ref transaction. Names are the stored names of refs, i.e. they include
+/// the prefix of the active namespace.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RefUpdate {
+    Set {
+        name: String,
+        value: RefValue,
+    },
+    /// Deletes the ref along with its reflog.
+    Delete {
+        name: String,
+    },
+    CopyReflog {
+        from: String,
+        to: String,
+    },
+}
+
+/// A way of storing refs, selected by the `extensions.refStorage` config. Backends only deal with
+/// stored ref names and raw values: namespaces and symbolic ref resolution are handled by
+/// `GitRefs` on top of them.
+pub(crate) trait RefBackend {
+    /// Reads the value of the ref with the provided name, without following symbolic refs.
+    fn read_ref(&self, name: &str) -> Result<Option<RefValue>, RustGitError>;
+
+    /// Lists the refs whose names start with the provided prefix, sorted by name.
+    fn list_refs(&self, prefix: &str) -> Result<Vec<(String, RefValue)>, RustGitError>;
+
+    /// Applies a list of updates, in order. Backends which support it apply them atomically.
+    fn commit(&self, updates: &[RefUpdate]) -> Result<(), RustGitError>;
+}
+
+/// The `files` backend, which stores refs as loose files under the git dir, along with the
+/// packed-refs file.
+struct FilesBackend {
     git_dir: PathBuf,
-    refs_dir: PathBuf,
-    heads_dir: PathBuf,
-    tags_dir: PathBuf,
+}
+
+impl FilesBackend {
+    fn new(git_dir: &Path) -> Result<FilesBackend, RustGitError> {
+        fs::create_dir_all(git_dir.join(REFS_FOLDER).join(HEADS_FOLDER))?;
+        fs::create_dir_all(git_dir.join(REFS_FOLDER).join(TAGS_FOLDER))?;
+
+        Ok(FilesBackend {
+            git_dir: git_dir.to_path_buf(),
+        })
+    }
+
+    fn write_ref(&self, ref_path: &Path, new_value: &str) -> Result<(), RustGitError> {
+        // Hierarchical refs (e.g. refs/remotes/origin/main) may need their parent folders created.
+        if let Some(parent) = ref_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(fs::write(ref_path, new_value)?)
+    }
+
+    /// Deletes a ref, whether it's loose, packed or both, along with its reflog.
+    fn delete_ref(&self, name: &str) -> Result<(), RustGitError> {
+        let ref_path = self.git_dir.join(name);
+        if ref_path.is_file() {
+            fs::remove_file(&ref_path)?;
+            self.remove_empty_parents(&ref_path, &self.git_dir)?;
+        }
+
+        let packed_refs = self.read_packed_refs()?;
+        if packed_refs.iter().any(|packed_ref| packed_ref.name == name) {
+            let remaining: Vec<PackedRef> = packed_refs
+                .into_iter()
+                .filter(|packed_ref| packed_ref.name != name)
+                .collect();
+            self.write_packed_refs(&remaining)?;
+        }
+
+        let reflog_path = self.reflog_path(name);
+        if reflog_path.is_file() {
+            fs::remove_file(&reflog_path)?;
+            self.remove_empty_parents(&reflog_path, &self.git_dir.join(LOGS_FOLDER))?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the now empty folders containing a deleted file, up to (not including) `root`.
+    fn remove_empty_parents(&self, path: &Path, root: &Path) -> Result<(), RustGitError> {
+        let mut dir = path.parent();
+        while let Some(parent) = dir {
+            // The refs, heads and tags folders always exist, as in C Git.
+            if parent == root
+                || parent.ends_with(REFS_FOLDER)
+                || parent.ends_with(Path::new(REFS_FOLDER).join(HEADS_FOLDER))
+                || parent.ends_with(Path::new(REFS_FOLDER).join(TAGS_FOLDER))
+                || fs::read_dir(parent)?.next().is_some()
+            {
+                break;
+            }
+            fs::remove_dir(parent)?;
+            dir = parent.parent();
+        }
+        Ok(())
+    }
+
+    fn reflog_path(&self, name: &str) -> PathBuf {
+        self.git_dir.join(LOGS_FOLDER).join(name)
+    }
+
+    fn copy_reflog(&self, from: &str, to: &str) -> Result<(), RustGitError> {
+        let old_path = self.reflog_path(from);
+        if !old_path.is_file() {
+            return Ok(());
+        }
+
+        let new_path = self.reflog_path(to);
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&old_path, &new_path)?;
+
+        Ok(())
+    }
+
+    fn write_packed_refs(&self, packed_refs: &[PackedRef]) -> Result<(), RustGitError> {
+        let mut contents = String::from(PACKED_REFS_HEADER);
+        for packed_ref in packed_refs {
+            contents.push_str(&format!("{} {}\n", packed_ref.id, packed_ref.name));
+            if let Some(peeled) = &packed_ref.peeled {
+                contents.push_str(&format!("^{peeled}\n"));
+            }
+        }
+
+        Ok(fs::write(self.git_dir.join(PACKED_REFS_FILE), contents)?)
+    }
+
+    /// Reads all entries of the packed-refs file, if there is one. Refs of all namespaces are
+    /// packed in the same file.
+    ///
+    /// Format reference: https://git-scm.com/docs/gitrepository-layout#Documentation/gitrepository-layout.txt-packed-refs
+    fn read_packed_refs(&self) -> Result<Vec<PackedRef>, RustGitError> {
+        let packed_refs_path = self.git_dir.join(PACKED_REFS_FILE);
+        if !fs::exists(&packed_refs_path)? {
+            return Ok(Vec::new());
+        }
+
+        let mut packed_refs: Vec<PackedRef> = Vec::new();
+        for line in fs::read_to_string(&packed_refs_path)?.lines() {
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+
+            if let Some(peeled) = line.strip_prefix('^') {
+                if let Some(last) = packed_refs.last_mut() {
+                    last.peeled = Some(GitObjectId::new(peeled.to_string()));
+                }
+                continue;
+            }
+
+            match line.split_once(' ') {
+                Some((id, name)) => packed_refs.push(PackedRef {
+                    name: name.to_string(),
+                    id: GitObjectId::new(id.to_string()),
+                    peeled: None,
+                }),
+                None => {
+                    return Err(RustGitError::new(format!(
+                        "unexpected line in packed-refs: '{line}'"
+                    )))
+                }
+            }
+        }
+
+        Ok(packed_refs)
+    }
+
+    fn collect_loose_refs(
+        &self,
+        dir: &Path,
+        refs: &mut Vec<(String, RefValue)>,
+    ) -> Result<(), RustGitError> {
+        // A namespace has no refs folder until a ref gets written in it.
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        for dir_entry in fs::read_dir(dir)? {
+            let path = dir_entry?.path();
+            if path.is_dir() {
+                self.collect_loose_refs(&path, refs)?;
+                continue;
+            }
+
+            let name = path
+                .strip_prefix(&self.git_dir)?
+                .to_str()
+                .ok_or_else(|| RustGitError::new(format!("invalid ref name {path:?}")))?
+                .to_string();
+            let value = RefValue::parse(&fs::read_to_string(&path)?);
+            refs.push((name, value));
+        }
+
+        Ok(())
+    }
+}
+
+impl RefBackend for FilesBackend {
+    /// Loose refs take precedence over packed refs.
+    fn read_ref(&self, name: &str) -> Result<Option<RefValue>, RustGitError> {
+        let ref_path = self.git_dir.join(name);
+        if ref_path.is_file() {
+            return Ok(Some(RefValue::parse(&fs::read_to_string(&ref_path)?)));
+        }
+
+        Ok(self
+            .read_packed_refs()?
+            .into_iter()
+            .find(|packed_ref| packed_ref.name == name)
+            .map(|packed_ref| RefValue::Direct(packed_ref.id)))
+    }
+
+    fn list_refs(&self, prefix: &str) -> Result<Vec<(String, RefValue)>, RustGitError> {
+        // The prefix ends with a slash, which is dropped to get the folder to list.
+        let mut refs = Vec::new();
+        self.collect_loose_refs(&self.git_dir.join(prefix.trim_end_matches('/')), &mut refs)?;
+        refs.retain(|(name, _)| name.starts_with(prefix));
+
+        for packed_ref in self.read_packed_refs()? {
+            if packed_ref.name.starts_with(prefix)
+                && !refs.iter().any(|(name, _)| *name == packed_ref.name)
+            {
+                refs.push((packed_ref.name, RefValue::Direct(packed_ref.id)));
+            }
+        }
+
+        refs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(refs)
+    }
+
+    /// Loose files can't be updated atomically, so updates are simply applied one by one.
+    fn commit(&self, updates: &[RefUpdate]) -> Result<(), RustGitError> {
+        for update in updates {
+            match update {
+                RefUpdate::Set { name, value } => {
+                    self.write_ref(&self.git_dir.join(name), &value.to_string())?
+                }
+                RefUpdate::Delete { name } => self.delete_ref(name)?,
+                RefUpdate::CopyReflog { from, to } => self.copy_reflog(from, to)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands a namespace (e.g. `foo/bar`) into the prefix its refs are stored under (e.g.
+/// `refs/namespaces/foo/refs/namespaces/bar/`), as described in gitnamespaces(7).
+fn namespace_prefix(namespace: &str) -> String {
+    namespace
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .map(|component| format!("{REFS_FOLDER}/{NAMESPACES_FOLDER}/{component}/"))
+        .collect()
+}
+
+pub(crate) struct GitRefs {
+    backend: Box<dyn RefBackend>,
+    /// The prefix of the active namespace, or an empty string if there is none. Ref names are
+    /// always given relative to the namespace, and only mapped to their stored names when
+    /// reading or writing refs.
+    namespace_prefix: String,
 }
 
 impl GitRefs {
-    pub(crate) fn new(git_dir: &Path) -> Result<GitRefs, RustGitError> {
-        let refs_dir = git_dir.join(REFS_FOLDER);
-        let heads_dir = refs_dir.join(HEADS_FOLDER);
-        let tags_dir = refs_dir.join(TAGS_FOLDER);
+    pub(crate) fn new(
+        git_dir: &Path,
+        namespace: Option<&str>,
+        config: &GitConfig,
+    ) -> Result<GitRefs, RustGitError> {
+        let backend: Box<dyn RefBackend> = match config.extensions.refstorage {
+            RefStorageFormat::Files => Box::new(FilesBackend::new(git_dir)?),
+            RefStorageFormat::Reftable => Box::new(ReftableBackend::new(git_dir, config)),
+        };
 
-        fs::create_dir_all(&heads_dir)?;
-        fs::create_dir_all(&tags_dir)?;
+        // The refs of a namespace are only stored once refs get written in it.
+        let namespace_prefix = namespace.map(namespace_prefix).unwrap_or_default();
 
         Ok(GitRefs {
-            git_dir: git_dir.to_path_buf(),
-            refs_dir,
-            heads_dir,
-            tags_dir,
+            backend,
+            namespace_prefix,
         })
     }
 
-    pub(crate) fn try_read_ref(&self, ref_path: &Path) -> Result<Option<String>, RustGitError> {
-        if fs::exists(&ref_path)? {
-            return Ok(Some(fs::read_to_string(&ref_path)?));
+    /// Maps a ref name to the name it's stored under, which is prefixed by the active namespace.
+    /// Only HEAD and refs under refs/ are namespaced.
+    fn storage_name(&self, ref_name: &str) -> String {
+        if ref_name == HEAD || ref_name.starts_with("refs/") {
+            format!("{}{ref_name}", self.namespace_prefix)
+        } else {
+            ref_name.to_string()
         }
-        Ok(None)
     }
 
-    pub(crate) fn write_ref(&self, ref_path: &Path, new_value: &str) -> Result<(), RustGitError> {
-        Ok(fs::write(&ref_path, new_value)?)
+    /// Maps the stored name of a ref back to its name within the active namespace, or None if
+    /// it's outside of the namespace.
+    fn namespaced_name<'a>(&self, storage_name: &'a str) -> Option<&'a str> {
+        storage_name.strip_prefix(&self.namespace_prefix)
+    }
+
+    /// Maps the target of a symbolic ref back to a name within the active namespace. Targets
+    /// outside of the namespace are left as they are.
+    fn symref_target<'a>(&self, storage_name: &'a str) -> &'a str {
+        self.namespaced_name(storage_name).unwrap_or(storage_name)
+    }
+
+    fn read_ref(&self, ref_name: &str) -> Result<Option<RefValue>, RustGitError> {
+        self.backend.read_ref(&self.storage_name(ref_name))
+    }
+
+    fn write_ref(&self, ref_name: &str, value: RefValue) -> Result<(), RustGitError> {
+        self.backend.commit(&[RefUpdate::Set {
+            name: self.storage_name(ref_name),
+            value,
+        }])
     }
 
     pub(crate) fn update_ref(
@@ -50,33 +450,26 @@ impl GitRefs {
         new_value: &str,
         old_value: Option<&str>,
     ) -> Result<(), RustGitError> {
-        let ref_path = self.refs_dir.join(git_ref);
-        match old_value {
-            Some(old_value) => {
-                let existing_value = self.try_read_ref(&ref_path)?;
-                let existing_value = existing_value.unwrap_or(String::new());
-
-                if old_value != existing_value {
-                    return Err(RustGitError::new(format!("existing value '{existing_value}' for refs/{git_ref} doesn't match expected value '{old_value}'")));
-                } else {
-                    return Ok(self.write_ref(&ref_path, new_value)?);
-                }
-            }
-            None => {
-                return Ok(self.write_ref(&ref_path, new_value)?);
+        let ref_name = format!("{REFS_FOLDER}/{git_ref}");
+        if let Some(old_value) = old_value {
+            let existing_value = self
+                .read_ref(&ref_name)?
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+
+            if old_value != existing_value {
+                return Err(RustGitError::new(format!("existing value '{existing_value}' for refs/{git_ref} doesn't match expected value '{old_value}'")));
             }
         }
+
+        self.write_ref(&ref_name, RefValue::parse(new_value))
     }
 
     pub(crate) fn get_symbolic_ref(&self, ref_name: &str) -> Result<Option<String>, RustGitError> {
-        let path = self.git_dir.join(ref_name);
-        if !fs::exists(&path)? {
-            return Ok(None);
-        }
-
-        let value = fs::read_to_string(&path)?;
-
-        return Ok(Some(value));
+        Ok(self.read_ref(ref_name)?.map(|value| match value {
+            RefValue::Symbolic(target) => format!("ref: {}", self.symref_target(&target)),
+            RefValue::Direct(id) => id.to_string(),
+        }))
     }
 
     pub(crate) fn update_symbolic_ref(
@@ -84,54 +477,55 @@ impl GitRefs {
         ref_name: &str,
         new_value: &str,
     ) -> Result<(), RustGitError> {
-        let path = self.git_dir.join(ref_name);
-        fs::write(path, format!("ref: {new_value}"))?;
-        Ok(())
+        self.write_ref(ref_name, RefValue::Symbolic(self.storage_name(new_value)))
     }
 
     pub(crate) fn delete_symbolic_ref(&self, ref_name: &str) -> Result<(), RustGitError> {
-        let path = self.git_dir.join(ref_name);
-        if !fs::exists(&path)? {
-            return Ok(());
+        match self.read_ref(ref_name)? {
+            None => Ok(()),
+            Some(RefValue::Symbolic(_)) => self.backend.commit(&[RefUpdate::Delete {
+                name: self.storage_name(ref_name),
+            }]),
+            Some(RefValue::Direct(_)) => Err(RustGitError::new(format!(
+                "cannot delete detached symbolic-ref '{ref_name}'"
+            ))),
         }
-
-        let value = fs::read_to_string(&path)?;
-
-        if value.starts_with("ref: ") {
-            fs::remove_file(&path)?;
-            Ok(())
-        } else {
-            Err(RustGitError::new(format!(
-                "cannot delete detatched symbolic-ref '{ref_name}'"
-            )))
-        }
-    }
-
-    pub(crate) fn tag_path(&self, tag_name: &str) -> PathBuf {
-        self.tags_dir.join(tag_name)
     }
 
-    pub(crate) fn try_read_tag(&self, tag_name: &str) -> Result<Option<String>, RustGitError> {
-        let tag_path = self.tag_path(tag_name);
-        self.try_read_ref(&tag_path)
+    /// Reads the object id the tag with the provided name points at, whether it's loose or packed.
+    pub(crate) fn try_read_tag(&self, tag_name: &str) -> Result<Option<GitObjectId>, RustGitError> {
+        self.resolve_ref(&format!("{REFS_FOLDER}/{TAGS_FOLDER}/{tag_name}"))
     }
 
-    pub(crate) fn get_head_ref(
-        &self,
-    ) -> Result<(Option<String>, Option<GitObjectId>), RustGitError> {
-        if let Some(head_ref_value) = self.get_symbolic_ref("HEAD")? {
-            if !head_ref_value.starts_with("ref: ") {
-                return Err(RustGitError::new("HEAD is in detatched state"));
+    /// Reads the current state of HEAD, or None if there's no HEAD.
+    pub(crate) fn read_head(&self) -> Result<Option<GitHead>, RustGitError> {
+        let head = match self.read_ref(HEAD)? {
+            Some(RefValue::Symbolic(target)) => {
+                let ref_name = self.symref_target(&target).to_string();
+                GitHead::Symbolic {
+                    id: self.resolve_ref(&ref_name)?,
+                    ref_name,
+                }
             }
+            Some(RefValue::Direct(id)) => GitHead::Detached(id),
+            None => return Ok(None),
+        };
 
-            let head_ref_branch = head_ref_value.trim_start_matches("ref: ");
+        Ok(Some(head))
+    }
 
-            let ref_path = self.git_dir.join(head_ref_branch);
+    /// Points HEAD directly at the provided object, detaching it from any branch.
+    pub(crate) fn detach_head(&self, id: &GitObjectId) -> Result<(), RustGitError> {
+        self.write_ref(HEAD, RefValue::Direct(id.clone()))
+    }
 
-            let ref_id = self.try_read_ref(&ref_path)?.map(GitObjectId::new);
-            Ok((Some(head_ref_branch.to_string()), ref_id))
-        } else {
-            Ok((None, None))
+    /// Moves HEAD to the provided object: the branch HEAD is attached to gets updated, or HEAD
+    /// itself if it's detached.
+    pub(crate) fn update_head(&self, id: &GitObjectId) -> Result<(), RustGitError> {
+        match self.read_head()? {
+            Some(GitHead::Symbolic { ref_name, .. }) => self.set_ref(&ref_name, id),
+            Some(GitHead::Detached(_)) => self.detach_head(id),
+            None => Err(RustGitError::new("couldn't load HEAD")),
         }
     }
 
@@ -140,31 +534,173 @@ impl GitRefs {
         tag_name: &str,
         object_id: &GitObjectId,
     ) -> Result<(), RustGitError> {
-        let tag_path = self.tag_path(tag_name);
-        self.write_ref(&tag_path, &object_id.to_string())
+        self.set_ref(
+            &format!("{REFS_FOLDER}/{TAGS_FOLDER}/{tag_name}"),
+            object_id,
+        )
     }
 
     pub(crate) fn delete_tag(&self, tag_name: &str) -> Result<(), RustGitError> {
-        let tag_path = self.tag_path(tag_name);
-        if !fs::exists(&tag_path)? {
-            return Err(RustGitError::new(format!("no tag {tag_name}")));
+        self.delete_ref(&format!("{REFS_FOLDER}/{TAGS_FOLDER}/{tag_name}"))
+    }
+
+    /// Points the ref with the provided full name (e.g. `refs/heads/main`) at an object.
+    pub(crate) fn set_ref(&self, ref_name: &str, id: &GitObjectId) -> Result<(), RustGitError> {
+        self.write_ref(ref_name, RefValue::Direct(id.clone()))
+    }
+
+    /// Deletes the ref with the provided full name, whether it's loose, packed or both, along
+    /// with its reflog.
+    pub(crate) fn delete_ref(&self, ref_name: &str) -> Result<(), RustGitError> {
+        self.backend.commit(&[RefUpdate::Delete {
+            name: self.storage_name(ref_name),
+        }])
+    }
+
+    /// Renames (or copies) a ref pointing at the provided object, along with its reflog, in a
+    /// single transaction.
+    pub(crate) fn rename_ref(
+        &self,
+        old_ref_name: &str,
+        new_ref_name: &str,
+        id: &GitObjectId,
+        copy: bool,
+    ) -> Result<(), RustGitError> {
+        let old_name = self.storage_name(old_ref_name);
+        let new_name = self.storage_name(new_ref_name);
+
+        let mut updates = vec![
+            RefUpdate::Set {
+                name: new_name.clone(),
+                value: RefValue::Direct(id.clone()),
+            },
+            RefUpdate::CopyReflog {
+                from: old_name.clone(),
+                to: new_name,
+            },
+        ];
+        if !copy {
+            updates.push(RefUpdate::Delete { name: old_name });
+        }
+
+        self.backend.commit(&updates)
+    }
+
+    /// Resolves the ref with the provided full name (e.g. `HEAD`, `refs/heads/main`) to an
+    /// object id, following symbolic refs.
+    pub(crate) fn resolve_ref(&self, ref_name: &str) -> Result<Option<GitObjectId>, RustGitError> {
+        let mut ref_name = ref_name.to_string();
+
+        for _ in 0..MAX_SYMREF_DEPTH {
+            match self.read_ref(&ref_name)? {
+                Some(RefValue::Symbolic(target)) => {
+                    ref_name = self.symref_target(&target).to_string()
+                }
+                Some(RefValue::Direct(id)) => return Ok(Some(id)),
+                None => return Ok(None),
+            }
         }
 
-        return Ok(fs::remove_file(&tag_path)?);
+        Err(RustGitError::new(format!(
+            "symbolic ref {ref_name} nested too deeply"
+        )))
+    }
+
+    /// Returns true if a ref with the provided full name exists, either as a loose or packed ref.
+    /// Symbolic refs are not followed, so a dangling symbolic ref still exists.
+    pub(crate) fn ref_exists(&self, ref_name: &str) -> Result<bool, RustGitError> {
+        Ok(self.read_ref(ref_name)?.is_some())
     }
 
-    pub(crate) fn list_tags(&self) -> Result<Vec<String>, RustGitError> {
-        let tag_files = fs::read_dir(&self.tags_dir)?;
+    /// Finds the full name of the ref that a short name (e.g. `main`, `v1.0`, `tags/v1.0`)
+    /// refers to, using the same lookup rules as C Git:
+    /// https://git-scm.com/docs/gitrevisions#Documentation/gitrevisions.txt-emltrefnamegtemegmasterheadsmasterrefsheadsmaster
+    pub(crate) fn dwim_ref(&self, short_name: &str) -> Result<Option<GitRef>, RustGitError> {
+        let candidates = [
+            short_name.to_string(),
+            format!("refs/{short_name}"),
+            format!("refs/tags/{short_name}"),
+            format!("refs/heads/{short_name}"),
+            format!("refs/remotes/{short_name}"),
+            format!("refs/remotes/{short_name}/HEAD"),
+        ];
 
-        let mut tags = Vec::new();
-        for tag_file in tag_files {
-            if let Some(tag_name) = tag_file?.file_name().to_str() {
-                tags.push(tag_name.to_string())
-            } else {
-                return Err(RustGitError::new("invalid tag name"));
+        for candidate in candidates {
+            // Only HEAD-like names are looked up outside of refs/.
+            if !candidate.starts_with("refs/")
+                && !candidate
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c == '_')
+            {
+                continue;
+            }
+
+            if let Some(id) = self.resolve_ref(&candidate)? {
+                return Ok(Some(GitRef {
+                    name: candidate,
+                    id,
+                }));
             }
         }
 
-        Ok(tags)
+        Ok(None)
+    }
+
+    /// Lists all refs under refs/, sorted by name.
+    pub(crate) fn list_refs(&self) -> Result<Vec<GitRef>, RustGitError> {
+        self.list_refs_with_prefix("refs/")
+    }
+
+    /// Lists the refs whose names start with the provided prefix, which ends with a slash, sorted
+    /// by name. Only the refs under the prefix are read.
+    pub(crate) fn list_refs_with_prefix(&self, prefix: &str) -> Result<Vec<GitRef>, RustGitError> {
+        let mut refs = Vec::new();
+        for (storage_name, value) in self.backend.list_refs(&self.storage_name(prefix))? {
+            let name = self
+                .namespaced_name(&storage_name)
+                .unwrap_or(&storage_name)
+                .to_string();
+
+            let id = match value {
+                RefValue::Direct(id) => Some(id),
+                RefValue::Symbolic(_) => self.resolve_ref(&name)?,
+            };
+            if let Some(id) = id {
+                refs.push(GitRef { name, id });
+            }
+        }
+
+        Ok(refs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_validate_ref_names() {
+        assert!(is_valid_ref_name("refs/heads/main"));
+        assert!(is_valid_ref_name("refs/heads/feature/nested-name_1"));
+        assert!(!is_valid_ref_name("refs/heads/.hidden"));
+        assert!(!is_valid_ref_name("refs/heads/a..b"));
+        assert!(!is_valid_ref_name("refs/heads/a b"));
+        assert!(!is_valid_ref_name("refs/heads/a~1"));
+        assert!(!is_valid_ref_name("refs/heads/a:b"));
+        assert!(!is_valid_ref_name("refs/heads/a.lock"));
+        assert!(!is_valid_ref_name("refs/heads/a/"));
+        assert!(!is_valid_ref_name("refs/heads/a@{1}"));
+        assert!(!is_valid_ref_name("refs/heads//a"));
+        assert!(!is_valid_ref_name("refs/heads/a."));
+    }
+
+    #[test]
+    fn should_expand_nested_namespaces() {
+        assert_eq!(namespace_prefix("foo"), "refs/namespaces/foo/");
+        assert_eq!(
+            namespace_prefix("foo/bar/"),
+            "refs/namespaces/foo/refs/namespaces/bar/"
+        );
+        assert_eq!(namespace_prefix(""), "");
     }
 }
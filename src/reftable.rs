@@ -0,0 +1,1210 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{bufread::ZlibDecoder, write::ZlibEncoder, Compression, Crc};
+use memmap2::Mmap;
+
+use crate::{
+    config::{FsyncComponent, GitConfig},
+    error::RustGitError,
+    lockfile::GitLockFile,
+    object::GitObjectId,
+    refs::{RefBackend, RefUpdate, RefValue},
+};
+
+// Format reference: https://git-scm.com/docs/reftable
+
+const REFTABLE_FOLDER: &str = "reftable";
+const TABLES_LIST_FILE: &str = "tables.list";
+
+const MAGIC: &[u8; 4] = b"REFT";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 24;
+const FOOTER_SIZE: usize = 68;
+const BLOCK_SIZE: usize = 4096;
+// Every 16th record is stored with its full key, as in C Git:
+// https://github.com/git/git/blob/master/reftable/writer.c#L137
+const RESTART_INTERVAL: usize = 16;
+const HASH_SIZE: usize = 20;
+
+const BLOCK_TYPE_REF: u8 = b'r';
+const BLOCK_TYPE_LOG: u8 = b'g';
+
+const VALUE_DELETION: u8 = 0;
+const VALUE_ID: u8 = 1;
+const VALUE_PEELED: u8 = 2;
+const VALUE_SYMREF: u8 = 3;
+
+const LOG_DELETION: u8 = 0;
+const LOG_UPDATE: u8 = 1;
+
+/// The value of a ref record. Deletions hide the ref in older tables of the stack.
+#[derive(Clone, Debug, PartialEq)]
+enum RefRecordValue {
+    Deletion,
+    Id(GitObjectId),
+    /// An annotated tag, along with the object it ultimately points at.
+    Peeled(GitObjectId, GitObjectId),
+    Symref(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RefRecord {
+    name: String,
+    update_index: u64,
+    value: RefRecordValue,
+}
+
+/// A single reflog entry.
+#[derive(Clone, Debug, PartialEq)]
+struct LogEntry {
+    old_id: GitObjectId,
+    new_id: GitObjectId,
+    name: String,
+    email: String,
+    /// Seconds since the unix epoch.
+    time: u64,
+    /// Offset from UTC, in minutes.
+    tz_offset: i16,
+    message: String,
+}
+
+/// A log record, which holds no entry if it's a deletion. Log records are keyed by ref name and
+/// update index, so a deletion only hides the entry with the same update index in older tables.
+#[derive(Clone, Debug, PartialEq)]
+struct LogRecord {
+    name: String,
+    update_index: u64,
+    entry: Option<LogEntry>,
+}
+
+impl LogRecord {
+    /// The key of a log record is the ref name followed by the reversed update index, so that
+    /// the newest entries of a ref come first.
+    fn key(&self) -> Vec<u8> {
+        log_key(&self.name, self.update_index)
+    }
+}
+
+fn log_key(name: &str, update_index: u64) -> Vec<u8> {
+    let mut key = name.as_bytes().to_vec();
+    key.push(0);
+    key.extend_from_slice(&(u64::MAX - update_index).to_be_bytes());
+    key
+}
+
+fn zero_id() -> GitObjectId {
+    GitObjectId::new("0".repeat(HASH_SIZE * 2))
+}
+
+fn corrupt_table() -> RustGitError {
+    RustGitError::new("fatal: reftable: corrupt table")
+}
+
+/// Writes a variable-length integer, using the same "offset" encoding as pack files, where each
+/// continuation byte also adds one to the value so that every value has a single encoding.
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    while value >> 7 != 0 {
+        value = (value >> 7) - 1;
+        bytes.push(0x80 | (value & 0x7f) as u8);
+    }
+    bytes.reverse();
+    buf.extend(bytes);
+}
+
+fn get_varint(data: &[u8], pos: &mut usize) -> Result<u64, RustGitError> {
+    let mut byte = *data.get(*pos).ok_or_else(corrupt_table)?;
+    *pos += 1;
+    let mut value = (byte & 0x7f) as u64;
+    while byte & 0x80 != 0 {
+        byte = *data.get(*pos).ok_or_else(corrupt_table)?;
+        *pos += 1;
+        value = ((value + 1) << 7) | (byte & 0x7f) as u64;
+    }
+    Ok(value)
+}
+
+fn put_u24(buf: &mut Vec<u8>, value: usize) {
+    buf.extend_from_slice(&(value as u32).to_be_bytes()[1..]);
+}
+
+fn get_u24(data: &[u8], pos: usize) -> Result<usize, RustGitError> {
+    let bytes = data.get(pos..pos + 3).ok_or_else(corrupt_table)?;
+    Ok(((bytes[0] as usize) << 16) | ((bytes[1] as usize) << 8) | bytes[2] as usize)
+}
+
+fn get_u64(data: &[u8], pos: usize) -> Result<u64, RustGitError> {
+    let bytes = data.get(pos..pos + 8).ok_or_else(corrupt_table)?;
+    Ok(u64::from_be_bytes(bytes.try_into()?))
+}
+
+fn get_bytes<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], RustGitError> {
+    let bytes = data.get(*pos..*pos + len).ok_or_else(corrupt_table)?;
+    *pos += len;
+    Ok(bytes)
+}
+
+fn get_string(data: &[u8], pos: &mut usize) -> Result<String, RustGitError> {
+    let len = get_varint(data, pos)? as usize;
+    Ok(String::from_utf8(get_bytes(data, pos, len)?.to_vec())?)
+}
+
+fn get_id(data: &[u8], pos: &mut usize) -> Result<GitObjectId, RustGitError> {
+    GitObjectId::deserialize(get_bytes(data, pos, HASH_SIZE)?)
+}
+
+fn put_id(buf: &mut Vec<u8>, id: &GitObjectId) -> Result<(), RustGitError> {
+    let bytes = hex::decode(id.to_string())?;
+    if bytes.len() != HASH_SIZE {
+        return Err(RustGitError::new(format!("fatal: invalid object id {id}")));
+    }
+    buf.extend(bytes);
+    Ok(())
+}
+
+fn put_string(buf: &mut Vec<u8>, s: &str) {
+    put_varint(buf, s.len() as u64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn encode_header(min_update_index: u64, max_update_index: u64) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    put_u24(&mut header, BLOCK_SIZE);
+    header.extend_from_slice(&min_update_index.to_be_bytes());
+    header.extend_from_slice(&max_update_index.to_be_bytes());
+    header
+}
+
+/// Accumulates the records of a single block. Keys are prefix compressed against the previous
+/// record, except at restart points, which store the full key so readers can binary search them.
+struct BlockWriter {
+    block_type: u8,
+    /// The block contents, starting at the block's position in the file. The first block of a
+    /// table also holds the file header.
+    data: Vec<u8>,
+    header_offset: usize,
+    restarts: Vec<usize>,
+    last_key: Vec<u8>,
+    entries: usize,
+}
+
+impl BlockWriter {
+    fn new(block_type: u8, file_header: Option<&[u8]>) -> BlockWriter {
+        let mut data = file_header
+            .map(|header| header.to_vec())
+            .unwrap_or_default();
+        let header_offset = data.len();
+        data.push(block_type);
+        // Placeholder for the block length, filled in when the block is finished.
+        put_u24(&mut data, 0);
+
+        BlockWriter {
+            block_type,
+            data,
+            header_offset,
+            restarts: Vec::new(),
+            last_key: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// Adds a record to the block, returning false if it doesn't fit. A block always accepts its
+    /// first record, even if it's larger than the block size.
+    fn add(&mut self, key: &[u8], value_type: u8, value: &[u8]) -> bool {
+        let restart = self.entries.is_multiple_of(RESTART_INTERVAL);
+        let prefix_len = if restart {
+            0
+        } else {
+            self.last_key
+                .iter()
+                .zip(key)
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+
+        let mut record = Vec::new();
+        put_varint(&mut record, prefix_len as u64);
+        put_varint(
+            &mut record,
+            (((key.len() - prefix_len) as u64) << 3) | value_type as u64,
+        );
+        record.extend_from_slice(&key[prefix_len..]);
+        record.extend_from_slice(value);
+
+        let restart_count = self.restarts.len() + usize::from(restart);
+        if self.entries > 0 && self.data.len() + record.len() + 3 * restart_count + 2 > BLOCK_SIZE {
+            return false;
+        }
+
+        if restart {
+            self.restarts.push(self.data.len());
+        }
+        self.data.extend(record);
+        self.last_key = key.to_vec();
+        self.entries += 1;
+        true
+    }
+
+    /// Appends the restart table and returns the finished block. Log blocks get compressed, with
+    /// their length field still holding the uncompressed length.
+    fn finish(mut self) -> Result<Vec<u8>, RustGitError> {
+        for restart in &self.restarts {
+            put_u24(&mut self.data, *restart);
+        }
+        self.data
+            .extend_from_slice(&(self.restarts.len() as u16).to_be_bytes());
+
+        let block_len = self.data.len();
+        let len_bytes = &(block_len as u32).to_be_bytes()[1..];
+        self.data[self.header_offset + 1..self.header_offset + 4].copy_from_slice(len_bytes);
+
+        if self.block_type != BLOCK_TYPE_LOG {
+            return Ok(self.data);
+        }
+
+        let records_start = self.header_offset + 4;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.data[records_start..])?;
+        let mut block = self.data[..records_start].to_vec();
+        block.extend(encoder.finish()?);
+        Ok(block)
+    }
+}
+
+fn encode_ref_value(
+    record: &RefRecord,
+    min_update_index: u64,
+) -> Result<(u8, Vec<u8>), RustGitError> {
+    let mut value = Vec::new();
+    put_varint(&mut value, record.update_index - min_update_index);
+    let value_type = match &record.value {
+        RefRecordValue::Deletion => VALUE_DELETION,
+        RefRecordValue::Id(id) => {
+            put_id(&mut value, id)?;
+            VALUE_ID
+        }
+        RefRecordValue::Peeled(id, peeled) => {
+            put_id(&mut value, id)?;
+            put_id(&mut value, peeled)?;
+            VALUE_PEELED
+        }
+        RefRecordValue::Symref(target) => {
+            put_string(&mut value, target);
+            VALUE_SYMREF
+        }
+    };
+    Ok((value_type, value))
+}
+
+fn encode_log_value(record: &LogRecord) -> Result<(u8, Vec<u8>), RustGitError> {
+    let entry = match &record.entry {
+        Some(entry) => entry,
+        None => return Ok((LOG_DELETION, Vec::new())),
+    };
+
+    let mut value = Vec::new();
+    put_id(&mut value, &entry.old_id)?;
+    put_id(&mut value, &entry.new_id)?;
+    put_string(&mut value, &entry.name);
+    put_string(&mut value, &entry.email);
+    put_varint(&mut value, entry.time);
+    value.extend_from_slice(&entry.tz_offset.to_be_bytes());
+    put_string(&mut value, &entry.message);
+    Ok((LOG_UPDATE, value))
+}
+
+/// Writes the blocks of one section of a table, given its records as sorted (key, type, value)
+/// triples.
+fn write_blocks(
+    table: &mut Vec<u8>,
+    file_header: &[u8],
+    block_type: u8,
+    records: Vec<(Vec<u8>, u8, Vec<u8>)>,
+) -> Result<(), RustGitError> {
+    let new_block = |table: &Vec<u8>| {
+        // The first block of the table also holds the file header.
+        BlockWriter::new(block_type, table.is_empty().then_some(file_header))
+    };
+
+    let mut block: Option<BlockWriter> = None;
+    for (key, value_type, value) in records {
+        let writer = block.get_or_insert_with(|| new_block(table));
+        if writer.add(&key, value_type, &value) {
+            continue;
+        }
+
+        flush_block(table, block.take(), block_type)?;
+        let mut writer = new_block(table);
+        writer.add(&key, value_type, &value);
+        block = Some(writer);
+    }
+
+    flush_block(table, block, block_type)
+}
+
+/// Ref blocks are padded to the block size, so that readers can binary search them by position.
+/// Log blocks are compressed, and never padded.
+fn flush_block(
+    table: &mut Vec<u8>,
+    block: Option<BlockWriter>,
+    block_type: u8,
+) -> Result<(), RustGitError> {
+    if let Some(block) = block {
+        let mut data = block.finish()?;
+        if block_type != BLOCK_TYPE_LOG && data.len() < BLOCK_SIZE {
+            data.resize(BLOCK_SIZE, 0);
+        }
+        table.extend(data);
+    }
+    Ok(())
+}
+
+/// Serializes a table holding the provided records, which must be sorted by key. Indexes and
+/// object blocks are optional in the format, and not written: lookups binary search the padded
+/// ref blocks instead.
+fn write_table(
+    min_update_index: u64,
+    max_update_index: u64,
+    refs: &[RefRecord],
+    logs: &[LogRecord],
+) -> Result<Vec<u8>, RustGitError> {
+    let header = encode_header(min_update_index, max_update_index);
+    let mut table = Vec::new();
+
+    let ref_records = refs
+        .iter()
+        .map(|record| {
+            let (value_type, value) = encode_ref_value(record, min_update_index)?;
+            Ok((record.name.as_bytes().to_vec(), value_type, value))
+        })
+        .collect::<Result<Vec<_>, RustGitError>>()?;
+    write_blocks(&mut table, &header, BLOCK_TYPE_REF, ref_records)?;
+
+    let log_position = if logs.is_empty() { 0 } else { table.len() };
+    let log_records = logs
+        .iter()
+        .map(|record| {
+            let (value_type, value) = encode_log_value(record)?;
+            Ok((record.key(), value_type, value))
+        })
+        .collect::<Result<Vec<_>, RustGitError>>()?;
+    write_blocks(&mut table, &header, BLOCK_TYPE_LOG, log_records)?;
+
+    if table.is_empty() {
+        table.extend_from_slice(&header);
+    }
+
+    // The footer repeats the header, followed by the positions of the (unused) ref index, object
+    // blocks and object index, the log blocks and the (unused) log index.
+    let mut footer = header;
+    for position in [0, 0, 0, log_position as u64, 0] {
+        footer.extend_from_slice(&position.to_be_bytes());
+    }
+    let mut crc = Crc::new();
+    crc.update(&footer);
+    footer.extend_from_slice(&crc.sum().to_be_bytes());
+
+    table.extend(footer);
+    Ok(table)
+}
+
+/// A decoded block, starting at the block's position in the file.
+struct Block {
+    data: Vec<u8>,
+    records_start: usize,
+    records_end: usize,
+    restarts: Vec<usize>,
+    /// The position of the following block in the file.
+    next: usize,
+}
+
+impl Block {
+    /// Decodes the key of the record at the provided position, given the key of the previous
+    /// record. Returns the record's value type, leaving `pos` at the start of its value.
+    fn read_key(&self, pos: &mut usize, key: &mut Vec<u8>) -> Result<u8, RustGitError> {
+        let prefix_len = get_varint(&self.data, pos)? as usize;
+        let suffix_and_type = get_varint(&self.data, pos)?;
+        let suffix = get_bytes(&self.data, pos, (suffix_and_type >> 3) as usize)?;
+        if prefix_len > key.len() {
+            return Err(corrupt_table());
+        }
+        key.truncate(prefix_len);
+        key.extend_from_slice(suffix);
+        Ok((suffix_and_type & 0x7) as u8)
+    }
+
+    fn restart_key(&self, restart: usize) -> Result<Vec<u8>, RustGitError> {
+        let mut key = Vec::new();
+        self.read_key(&mut self.restarts[restart].clone(), &mut key)?;
+        Ok(key)
+    }
+}
+
+/// A single table of the stack, mapped into memory so that only the blocks which get looked at
+/// are read.
+struct Table {
+    name: String,
+    data: Mmap,
+    block_size: usize,
+    min_update_index: u64,
+    max_update_index: u64,
+    /// The ref blocks span from the start of the file up to here.
+    ref_end: usize,
+    log_start: Option<usize>,
+    log_end: usize,
+}
+
+impl Table {
+    fn open(dir: &Path, name: &str) -> Result<Table, RustGitError> {
+        let file = File::open(dir.join(name))?;
+        if (file.metadata()?.len() as usize) < HEADER_SIZE + FOOTER_SIZE {
+            return Err(corrupt_table());
+        }
+        // SAFETY: tables are never modified once written, only replaced in tables.list and then
+        // removed, which leaves existing mappings intact.
+        let data = unsafe { Mmap::map(&file)? };
+        if &data[..4] != MAGIC || data[4] != VERSION {
+            return Err(corrupt_table());
+        }
+
+        let footer_start = data.len() - FOOTER_SIZE;
+        let footer = &data[footer_start..];
+        let mut crc = Crc::new();
+        crc.update(&footer[..FOOTER_SIZE - 4]);
+        if crc.sum().to_be_bytes() != footer[FOOTER_SIZE - 4..] {
+            return Err(corrupt_table());
+        }
+
+        let ref_index_position = get_u64(footer, HEADER_SIZE)? as usize;
+        let obj_position = (get_u64(footer, HEADER_SIZE + 8)? >> 5) as usize;
+        let obj_index_position = get_u64(footer, HEADER_SIZE + 16)? as usize;
+        let log_position = get_u64(footer, HEADER_SIZE + 24)? as usize;
+        let log_index_position = get_u64(footer, HEADER_SIZE + 32)? as usize;
+
+        // Sections are laid out in this order, and any of them may be missing.
+        let ref_end = [
+            ref_index_position,
+            obj_position,
+            obj_index_position,
+            log_position,
+            log_index_position,
+        ]
+        .into_iter()
+        .filter(|position| *position != 0)
+        .min()
+        .unwrap_or(footer_start);
+        let ref_end = if ref_end > HEADER_SIZE { ref_end } else { 0 };
+
+        // A table with logs but no refs starts with a log block, whose position is then 0.
+        let first_block_type = data.get(HEADER_SIZE).copied();
+        let (ref_end, log_start) = if log_position != 0 {
+            (ref_end, Some(log_position))
+        } else if first_block_type == Some(BLOCK_TYPE_LOG) && footer_start > HEADER_SIZE {
+            (0, Some(0))
+        } else {
+            (ref_end, None)
+        };
+        let log_end = if log_index_position != 0 {
+            log_index_position
+        } else {
+            footer_start
+        };
+
+        Ok(Table {
+            name: name.to_string(),
+            block_size: get_u24(&data, 5)?,
+            min_update_index: get_u64(&data, 8)?,
+            max_update_index: get_u64(&data, 16)?,
+            data,
+            ref_end,
+            log_start,
+            log_end,
+        })
+    }
+
+    fn read_block(&self, offset: usize, expected_type: u8) -> Result<Block, RustGitError> {
+        let header_offset = if offset == 0 { HEADER_SIZE } else { 0 };
+        let records_start = header_offset + 4;
+        let block_type = *self
+            .data
+            .get(offset + header_offset)
+            .ok_or_else(corrupt_table)?;
+        let block_len = get_u24(&self.data, offset + header_offset + 1)?;
+        if block_type != expected_type || block_len < records_start + 2 {
+            return Err(corrupt_table());
+        }
+
+        let (data, next) = if block_type == BLOCK_TYPE_LOG {
+            let compressed = self
+                .data
+                .get(offset + records_start..self.log_end)
+                .ok_or_else(corrupt_table)?;
+            let mut decoder = ZlibDecoder::new(compressed);
+            let mut data = self.data[offset..offset + records_start].to_vec();
+            decoder.read_to_end(&mut data)?;
+            let next = offset + records_start + decoder.total_in() as usize;
+            (data, next)
+        } else {
+            let data = self
+                .data
+                .get(offset..offset + block_len)
+                .ok_or_else(corrupt_table)?
+                .to_vec();
+            (data, self.ref_block_end(offset, block_len))
+        };
+
+        if data.len() != block_len {
+            return Err(corrupt_table());
+        }
+
+        let restart_count = u16::from_be_bytes(data[block_len - 2..].try_into()?) as usize;
+        let records_end = block_len
+            .checked_sub(2 + 3 * restart_count)
+            .filter(|records_end| *records_end >= records_start)
+            .ok_or_else(corrupt_table)?;
+        let restarts = (0..restart_count)
+            .map(|i| get_u24(&data, records_end + 3 * i))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Block {
+            data,
+            records_start,
+            records_end,
+            restarts,
+            next,
+        })
+    }
+
+    /// Finds the position of the block following the ref block at `offset`. Padding is all
+    /// zeroes, whereas an unpadded block is directly followed by the type of the next block.
+    fn ref_block_end(&self, offset: usize, block_len: usize) -> usize {
+        let padded = block_len < self.block_size && self.data.get(offset + block_len) == Some(&0);
+        offset + if padded { self.block_size } else { block_len }
+    }
+
+    /// Finds the positions of all ref blocks from their headers alone. When the table is padded,
+    /// these are simply multiples of the block size.
+    fn ref_block_offsets(&self) -> Result<Vec<usize>, RustGitError> {
+        if self.ref_end == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut offsets = Vec::new();
+        let mut next = 0;
+        while next < self.ref_end {
+            let header_offset = if next == 0 { HEADER_SIZE } else { 0 };
+            if self.data.get(next + header_offset) != Some(&BLOCK_TYPE_REF) {
+                return Err(corrupt_table());
+            }
+            let block_len = get_u24(&self.data, next + header_offset + 1)?;
+            offsets.push(next);
+
+            let end = self.ref_block_end(next, block_len);
+            if next == 0 && end == self.block_size {
+                offsets.extend((self.block_size..self.ref_end).step_by(self.block_size));
+                break;
+            }
+            next = end;
+        }
+        Ok(offsets)
+    }
+
+    fn read_ref_record(
+        &self,
+        block: &Block,
+        pos: &mut usize,
+        key: &mut Vec<u8>,
+    ) -> Result<RefRecord, RustGitError> {
+        let value_type = block.read_key(pos, key)?;
+        let data = &block.data;
+        let update_index = self.min_update_index + get_varint(data, pos)?;
+        let value = match value_type {
+            VALUE_DELETION => RefRecordValue::Deletion,
+            VALUE_ID => RefRecordValue::Id(get_id(data, pos)?),
+            VALUE_PEELED => RefRecordValue::Peeled(get_id(data, pos)?, get_id(data, pos)?),
+            VALUE_SYMREF => RefRecordValue::Symref(get_string(data, pos)?),
+            _ => return Err(corrupt_table()),
+        };
+
+        Ok(RefRecord {
+            name: String::from_utf8(key.clone())?,
+            update_index,
+            value,
+        })
+    }
+
+    fn read_log_record(
+        block: &Block,
+        pos: &mut usize,
+        key: &mut Vec<u8>,
+    ) -> Result<LogRecord, RustGitError> {
+        let log_type = block.read_key(pos, key)?;
+        if key.len() < 9 || key[key.len() - 9] != 0 {
+            return Err(corrupt_table());
+        }
+        let name = String::from_utf8(key[..key.len() - 9].to_vec())?;
+        let update_index = u64::MAX - get_u64(key, key.len() - 8)?;
+
+        let data = &block.data;
+        let entry = match log_type {
+            LOG_DELETION => None,
+            LOG_UPDATE => Some(LogEntry {
+                old_id: get_id(data, pos)?,
+                new_id: get_id(data, pos)?,
+                name: get_string(data, pos)?,
+                email: get_string(data, pos)?,
+                time: get_varint(data, pos)?,
+                tz_offset: i16::from_be_bytes(get_bytes(data, pos, 2)?.try_into()?),
+                message: get_string(data, pos)?,
+            }),
+            _ => return Err(corrupt_table()),
+        };
+
+        Ok(LogRecord {
+            name,
+            update_index,
+            entry,
+        })
+    }
+
+    /// Reads the refs in order, starting from the first one not before `start`, for as long as
+    /// `visit` returns true. The starting point is found by binary searching first the blocks and
+    /// then the restart points of the block that may hold it.
+    fn scan_refs(
+        &self,
+        start: &str,
+        mut visit: impl FnMut(RefRecord) -> bool,
+    ) -> Result<(), RustGitError> {
+        let target = start.as_bytes();
+        let offsets = self.ref_block_offsets()?;
+
+        // Find the last block whose first key isn't past the start.
+        let (mut low, mut high) = (0, offsets.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let block = self.read_block(offsets[mid], BLOCK_TYPE_REF)?;
+            if block.restart_key(0)?.as_slice() <= target {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let first_block = low.saturating_sub(1);
+
+        for (block_idx, offset) in offsets.into_iter().enumerate().skip(first_block) {
+            let block = self.read_block(offset, BLOCK_TYPE_REF)?;
+            let mut pos = block.records_start;
+            if block_idx == first_block {
+                let (mut low, mut high) = (0, block.restarts.len());
+                while low < high {
+                    let mid = (low + high) / 2;
+                    if block.restart_key(mid)?.as_slice() <= target {
+                        low = mid + 1;
+                    } else {
+                        high = mid;
+                    }
+                }
+                pos = block.restarts[low.saturating_sub(1)];
+            }
+
+            let mut key = Vec::new();
+            while pos < block.records_end {
+                let record = self.read_ref_record(&block, &mut pos, &mut key)?;
+                if key.as_slice() >= target && !visit(record) {
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks up a single ref.
+    fn seek_ref(&self, name: &str) -> Result<Option<RefRecord>, RustGitError> {
+        let mut found = None;
+        self.scan_refs(name, |record| {
+            if record.name == name {
+                found = Some(record);
+            }
+            false
+        })?;
+        Ok(found)
+    }
+
+    fn refs(&self) -> Result<Vec<RefRecord>, RustGitError> {
+        let mut refs = Vec::new();
+        self.scan_refs("", |record| {
+            refs.push(record);
+            true
+        })?;
+        Ok(refs)
+    }
+
+    /// Reads the logs in order, for as long as `visit` returns true. Log blocks are compressed
+    /// as a stream, so they can only be read one after the other.
+    fn scan_logs(&self, mut visit: impl FnMut(LogRecord) -> bool) -> Result<(), RustGitError> {
+        let mut offset = match self.log_start {
+            Some(log_start) => log_start,
+            None => return Ok(()),
+        };
+
+        while offset < self.log_end {
+            let block = self.read_block(offset, BLOCK_TYPE_LOG)?;
+            let mut pos = block.records_start;
+            let mut key = Vec::new();
+            while pos < block.records_end {
+                if !visit(Self::read_log_record(&block, &mut pos, &mut key)?) {
+                    return Ok(());
+                }
+            }
+            offset = block.next;
+        }
+        Ok(())
+    }
+
+    fn logs(&self) -> Result<Vec<LogRecord>, RustGitError> {
+        let mut logs = Vec::new();
+        self.scan_logs(|record| {
+            logs.push(record);
+            true
+        })?;
+        Ok(logs)
+    }
+
+    /// Reads the log records of a single ref, which are stored next to each other.
+    fn ref_logs(&self, name: &str) -> Result<Vec<LogRecord>, RustGitError> {
+        let mut logs = Vec::new();
+        self.scan_logs(|record| match record.name.as_str().cmp(name) {
+            Ordering::Less => true,
+            Ordering::Equal => {
+                logs.push(record);
+                true
+            }
+            Ordering::Greater => false,
+        })?;
+        Ok(logs)
+    }
+}
+
+/// Looks up a ref in the stack. Newer tables take precedence, so the first table holding the
+/// ref has its value.
+fn find_ref(tables: &[Table], name: &str) -> Result<Option<RefRecord>, RustGitError> {
+    for table in tables.iter().rev() {
+        if let Some(record) = table.seek_ref(name)? {
+            return Ok(Some(record));
+        }
+    }
+    Ok(None)
+}
+
+/// Collects the log records of a single ref from the stack, newer tables taking precedence.
+fn find_logs(tables: &[Table], name: &str) -> Result<BTreeMap<Vec<u8>, LogRecord>, RustGitError> {
+    let mut logs = BTreeMap::new();
+    for table in tables {
+        for record in table.ref_logs(name)? {
+            logs.insert(record.key(), record);
+        }
+    }
+    Ok(logs)
+}
+
+/// Merges the refs of consecutive tables, newer tables taking precedence. Deletions are kept,
+/// as they still need to hide refs of any older tables.
+fn merge_refs(tables: &[Table]) -> Result<BTreeMap<String, RefRecord>, RustGitError> {
+    let mut refs = BTreeMap::new();
+    for table in tables {
+        for record in table.refs()? {
+            refs.insert(record.name.clone(), record);
+        }
+    }
+    Ok(refs)
+}
+
+fn merge_logs(tables: &[Table]) -> Result<BTreeMap<Vec<u8>, LogRecord>, RustGitError> {
+    let mut logs = BTreeMap::new();
+    for table in tables {
+        for record in table.logs()? {
+            logs.insert(record.key(), record);
+        }
+    }
+    Ok(logs)
+}
+
+/// Picks the tables to compact after adding a table to the stack: starting from the newest
+/// table, older tables get merged in while they're less than twice the size of the merged ones,
+/// keeping the table sizes geometric so that the stack stays logarithmic in length.
+fn compaction_start(sizes: &[usize]) -> Option<usize> {
+    let mut start = sizes.len().checked_sub(1)?;
+    let mut total = sizes[start];
+    while start > 0 && sizes[start - 1] <= 2 * total {
+        start -= 1;
+        total += sizes[start];
+    }
+    (start + 1 < sizes.len()).then_some(start)
+}
+
+/// The `reftable` backend, which stores refs and reflogs in a stack of tables listed in
+/// `reftable/tables.list`, oldest first. Every transaction adds a table to the stack, and
+/// tables get merged back together as the stack grows.
+pub(crate) struct ReftableBackend {
+    reftable_dir: PathBuf,
+    log_updates: bool,
+    committer_name: String,
+    committer_email: String,
+    /// Whether `tables.list` is flushed to disk before replacing it, as set by `core.fsync`.
+    fsync: bool,
+}
+
+impl ReftableBackend {
+    pub(crate) fn new(git_dir: &Path, config: &GitConfig) -> ReftableBackend {
+        ReftableBackend {
+            reftable_dir: git_dir.join(REFTABLE_FOLDER),
+            log_updates: config.core.logallrefupdates,
+            committer_name: config.user.name.clone().unwrap_or_default(),
+            committer_email: config.user.email.clone().unwrap_or_default(),
+            fsync: config.core.should_fsync(FsyncComponent::Reference),
+        }
+    }
+
+    fn tables_list_path(&self) -> PathBuf {
+        self.reftable_dir.join(TABLES_LIST_FILE)
+    }
+
+    fn read_tables(&self) -> Result<Vec<Table>, RustGitError> {
+        let tables_list = match fs::read_to_string(self.tables_list_path()) {
+            Ok(tables_list) => tables_list,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        tables_list
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|name| Table::open(&self.reftable_dir, name))
+            .collect()
+    }
+
+    /// Writes a new table to the reftable folder, returning its name.
+    fn add_table(
+        &self,
+        min_update_index: u64,
+        max_update_index: u64,
+        refs: &[RefRecord],
+        logs: &[LogRecord],
+    ) -> Result<Table, RustGitError> {
+        let data = write_table(min_update_index, max_update_index, refs, logs)?;
+
+        // The random suffix keeps tables with the same update indexes apart.
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.subsec_nanos();
+        let suffix = nanos ^ process::id().rotate_left(16);
+        let name = format!("0x{min_update_index:012x}-0x{max_update_index:012x}-{suffix:08x}.ref");
+
+        fs::write(self.reftable_dir.join(&name), &data)?;
+        Table::open(&self.reftable_dir, &name)
+    }
+
+    /// Merges the tables from `start` onwards into a single one. Deletions can only be dropped
+    /// when there are no older tables left for them to hide refs in.
+    fn compact(&self, tables: &mut Vec<Table>, start: usize) -> Result<(), RustGitError> {
+        let keep_deletions = start > 0;
+        let segment = &tables[start..];
+        let refs: Vec<RefRecord> = merge_refs(segment)?
+            .into_values()
+            .filter(|record| keep_deletions || record.value != RefRecordValue::Deletion)
+            .collect();
+        let logs: Vec<LogRecord> = merge_logs(segment)?
+            .into_values()
+            .filter(|record| keep_deletions || record.entry.is_some())
+            .collect();
+
+        let min_update_index = segment[0].min_update_index;
+        let max_update_index = segment[segment.len() - 1].max_update_index;
+        let compacted = self.add_table(min_update_index, max_update_index, &refs, &logs)?;
+
+        tables.truncate(start);
+        tables.push(compacted);
+        Ok(())
+    }
+
+    fn new_log_entry(
+        &self,
+        old_id: GitObjectId,
+        new_id: GitObjectId,
+    ) -> Result<LogEntry, RustGitError> {
+        Ok(LogEntry {
+            old_id,
+            new_id,
+            name: self.committer_name.clone(),
+            email: self.committer_email.clone(),
+            time: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            tz_offset: 0,
+            message: String::new(),
+        })
+    }
+}
+
+impl RefBackend for ReftableBackend {
+    fn read_ref(&self, name: &str) -> Result<Option<RefValue>, RustGitError> {
+        Ok(
+            find_ref(&self.read_tables()?, name)?.and_then(|record| match record.value {
+                RefRecordValue::Deletion => None,
+                RefRecordValue::Id(id) | RefRecordValue::Peeled(id, _) => {
+                    Some(RefValue::Direct(id))
+                }
+                RefRecordValue::Symref(target) => Some(RefValue::Symbolic(target)),
+            }),
+        )
+    }
+
+    fn list_refs(&self, prefix: &str) -> Result<Vec<(String, RefValue)>, RustGitError> {
+        // Only the refs under the prefix get read, with newer tables taking precedence.
+        let mut refs = BTreeMap::new();
+        for table in self.read_tables()? {
+            table.scan_refs(prefix, |record| {
+                let in_prefix = record.name.starts_with(prefix);
+                if in_prefix {
+                    refs.insert(record.name.clone(), record);
+                }
+                in_prefix
+            })?;
+        }
+
+        Ok(refs
+            .into_values()
+            .filter_map(|record| match record.value {
+                RefRecordValue::Deletion => None,
+                RefRecordValue::Id(id) | RefRecordValue::Peeled(id, _) => {
+                    Some((record.name, RefValue::Direct(id)))
+                }
+                RefRecordValue::Symref(target) => Some((record.name, RefValue::Symbolic(target))),
+            })
+            .collect())
+    }
+
+    /// All updates are written to a single new table, which is only added to the stack once
+    /// it's complete, so a transaction is applied either fully or not at all.
+    fn commit(&self, updates: &[RefUpdate]) -> Result<(), RustGitError> {
+        fs::create_dir_all(&self.reftable_dir)?;
+        let lock = GitLockFile::acquire(&self.tables_list_path())?;
+
+        let mut tables = self.read_tables()?;
+        let min_update_index = tables.last().map_or(1, |table| table.max_update_index + 1);
+
+        // Copied reflog entries get update indexes of their own, ahead of the transaction's
+        // updates, so that they stay older than the entries those updates add.
+        let mut update_index = min_update_index;
+        let mut copied_logs = Vec::new();
+        for update in updates {
+            if let RefUpdate::CopyReflog { from, to } = update {
+                for record in find_logs(&tables, from)?.into_values().rev() {
+                    if record.entry.is_some() {
+                        copied_logs.push(LogRecord {
+                            name: to.clone(),
+                            update_index,
+                            entry: record.entry,
+                        });
+                        update_index += 1;
+                    }
+                }
+            }
+        }
+
+        let mut refs: BTreeMap<String, RefRecord> = BTreeMap::new();
+        let mut logs: BTreeMap<Vec<u8>, LogRecord> = BTreeMap::new();
+        for record in copied_logs {
+            logs.insert(record.key(), record);
+        }
+
+        for update in updates {
+            match update {
+                RefUpdate::Set { name, value } => {
+                    let record_value = match value {
+                        RefValue::Direct(id) => RefRecordValue::Id(id.clone()),
+                        RefValue::Symbolic(target) => RefRecordValue::Symref(target.clone()),
+                    };
+
+                    if let (true, RefValue::Direct(new_id)) = (self.log_updates, value) {
+                        let old_id = match find_ref(&tables, name)?.map(|record| record.value) {
+                            Some(RefRecordValue::Id(id)) | Some(RefRecordValue::Peeled(id, _)) => {
+                                id
+                            }
+                            _ => zero_id(),
+                        };
+                        let record = LogRecord {
+                            name: name.clone(),
+                            update_index,
+                            entry: Some(self.new_log_entry(old_id, new_id.clone())?),
+                        };
+                        logs.insert(record.key(), record);
+                    }
+
+                    refs.insert(
+                        name.clone(),
+                        RefRecord {
+                            name: name.clone(),
+                            update_index,
+                            value: record_value,
+                        },
+                    );
+                }
+                RefUpdate::Delete { name } => {
+                    refs.insert(
+                        name.clone(),
+                        RefRecord {
+                            name: name.clone(),
+                            update_index,
+                            value: RefRecordValue::Deletion,
+                        },
+                    );
+
+                    // The reflog goes away with the ref, which takes a deletion for each entry.
+                    logs.retain(|_, record| record.name != *name);
+                    for record in find_logs(&tables, name)?.into_values() {
+                        if record.entry.is_some() {
+                            let deletion = LogRecord {
+                                entry: None,
+                                ..record
+                            };
+                            logs.insert(deletion.key(), deletion);
+                        }
+                    }
+                }
+                RefUpdate::CopyReflog { .. } => (),
+            }
+        }
+
+        let refs: Vec<RefRecord> = refs.into_values().collect();
+        let logs: Vec<LogRecord> = logs.into_values().collect();
+        tables.push(self.add_table(min_update_index, update_index, &refs, &logs)?);
+
+        let old_names: Vec<String> = tables.iter().map(|table| table.name.clone()).collect();
+        let sizes: Vec<usize> = tables.iter().map(|table| table.data.len()).collect();
+        if let Some(start) = compaction_start(&sizes) {
+            self.compact(&mut tables, start)?;
+        }
+
+        let tables_list: String = tables
+            .iter()
+            .map(|table| format!("{}\n", table.name))
+            .collect();
+        lock.commit(tables_list.as_bytes(), self.fsync)?;
+
+        // Compacted tables are no longer referenced, and can be removed.
+        for name in old_names {
+            if !tables.iter().any(|table| table.name == name) {
+                fs::remove_file(self.reftable_dir.join(name))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: usize) -> GitObjectId {
+        GitObjectId::new(format!("{n:040x}"))
+    }
+
+    fn open_table(name: &str, data: Vec<u8>) -> Table {
+        let dir = std::env::temp_dir().join(format!("reftable-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), data).unwrap();
+        let table = Table::open(&dir, name).unwrap();
+        fs::remove_file(dir.join(name)).unwrap();
+        table
+    }
+
+    #[test]
+    fn should_round_trip_varints() {
+        for value in [0, 1, 127, 128, 255, 16383, 16384, 1 << 35, u64::MAX >> 1] {
+            let mut buf = Vec::new();
+            put_varint(&mut buf, value);
+            let mut pos = 0;
+            assert_eq!(get_varint(&buf, &mut pos).unwrap(), value);
+            assert_eq!(pos, buf.len());
+        }
+
+        let mut buf = Vec::new();
+        put_varint(&mut buf, 128);
+        assert_eq!(buf, vec![0x80, 0x00]);
+    }
+
+    #[test]
+    fn should_read_back_written_table() {
+        let refs: Vec<RefRecord> = (0..1000)
+            .map(|n| RefRecord {
+                name: format!("refs/heads/branch-{n:04}"),
+                update_index: 1 + n as u64 % 3,
+                value: match n % 4 {
+                    0 => RefRecordValue::Deletion,
+                    1 => RefRecordValue::Peeled(id(n), id(n + 1)),
+                    2 => RefRecordValue::Symref(String::from("refs/heads/main")),
+                    _ => RefRecordValue::Id(id(n)),
+                },
+            })
+            .collect();
+        let logs: Vec<LogRecord> = (0..300)
+            .map(|n| LogRecord {
+                name: format!("refs/heads/branch-{:04}", n / 3),
+                update_index: 3 - n as u64 % 3,
+                entry: (n % 5 != 0).then(|| LogEntry {
+                    old_id: id(n),
+                    new_id: id(n + 1),
+                    name: String::from("Test User"),
+                    email: String::from("test@example.com"),
+                    time: 1700000000 + n as u64,
+                    tz_offset: -120,
+                    message: format!("update {n}"),
+                }),
+            })
+            .collect();
+
+        let table = open_table("full.ref", write_table(1, 3, &refs, &logs).unwrap());
+        assert!(table.ref_block_offsets().unwrap().len() > 1);
+        assert_eq!(table.refs().unwrap(), refs);
+        assert_eq!(table.logs().unwrap(), logs);
+
+        for record in &refs {
+            assert_eq!(table.seek_ref(&record.name).unwrap().as_ref(), Some(record));
+        }
+        assert_eq!(table.seek_ref("refs/heads/a").unwrap(), None);
+        assert_eq!(table.seek_ref("refs/heads/branch-0500x").unwrap(), None);
+        assert_eq!(table.seek_ref("refs/tags/v1").unwrap(), None);
+
+        assert_eq!(
+            table.ref_logs("refs/heads/branch-0042").unwrap(),
+            logs[126..129].to_vec()
+        );
+        assert_eq!(table.ref_logs("refs/heads/branch-0100").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn should_read_back_empty_table() {
+        let table = open_table("empty.ref", write_table(1, 1, &[], &[]).unwrap());
+        assert_eq!(table.refs().unwrap(), Vec::new());
+        assert_eq!(table.logs().unwrap(), Vec::new());
+        assert_eq!(table.seek_ref("HEAD").unwrap(), None);
+    }
+
+    #[test]
+    fn should_keep_table_sizes_geometric() {
+        assert_eq!(compaction_start(&[]), None);
+        assert_eq!(compaction_start(&[100]), None);
+        assert_eq!(compaction_start(&[100, 100]), Some(0));
+        assert_eq!(compaction_start(&[1000, 100]), None);
+        assert_eq!(compaction_start(&[1000, 100, 100]), Some(1));
+        assert_eq!(compaction_start(&[300, 100, 100]), Some(0));
+    }
+}
@@ -0,0 +1,79 @@
+use clap::{ArgGroup, Args};
+
+#[derive(Args, Debug)]
+#[command(about = "Create, list, delete refs to replace objects")]
+#[command(long_about = "
+Adds a replace reference in refs/replace/ namespace.
+
+The name of the replace reference is the SHA-1 of the object that is replaced. The content of the replace
+reference is the SHA-1 of the replacement object.
+
+The replaced object and the replacement object must be of the same type. This restriction can be bypassed using
+-f.
+
+Unless -f is given, the replace reference must not yet exist.
+
+There is no other restriction on the replaced and replacement objects. Merge commits can be replaced by
+non-merge commits and vice versa.
+
+Replacement references will be used by default by all Git commands except those doing reachability traversal
+(prune, pack transfer and fsck).
+
+It is possible to disable the use of replacement references for any command using the --no-replace-objects
+option just after git.
+")]
+#[command(group(ArgGroup::new("mode").args(["list", "delete", "edit", "graft"])))]
+pub(crate) struct ReplaceArgs {
+    /// The objects to replace or delete replace refs for, followed by the replacement or parents if needed,
+    /// or a pattern when listing.
+    #[arg(value_name = "object")]
+    pub args: Vec<String>,
+
+    /// If an existing replace ref for the same object exists, it will be overwritten (instead of failing).
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Delete existing replace refs for the given objects.
+    #[arg(short, long)]
+    pub delete: bool,
+
+    /// Edit an object’s content interactively. The existing content for <object> is pretty-printed into a
+    /// temporary file, an editor is launched on the file, and the result is parsed to create a new object of the
+    /// same type as <object>. A replacement ref is then created to replace <object> with the newly created
+    /// object. See git-var(1) for details about how the editor will be chosen.
+    #[arg(short, long)]
+    pub edit: bool,
+
+    /// When editing, provide the raw object contents rather than pretty-printed ones. Currently this only
+    /// affects trees, which will be shown in their binary form. This is harder to work with, but can help when
+    /// repairing a tree that is so corrupted it cannot be pretty-printed. Note that you may need to configure
+    /// your editor to cleanly read and write binary data.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Create a graft commit. A new commit is created with the same content as <commit> except that its
+    /// parents will be [<parent>...] instead of <commit>'s parents. A replacement ref is then created to
+    /// replace <commit> with the newly created commit. Use --convert-graft-file to convert a
+    /// $GIT_DIR/info/grafts file and use replace refs instead.
+    #[arg(short, long)]
+    pub graft: bool,
+
+    /// List replace refs for objects that match the given pattern (or all if no pattern is given). Typing "git
+    /// replace" without arguments, also lists all replace refs.
+    #[arg(short, long)]
+    pub list: bool,
+
+    /// When listing, use the specified <format>, which can be one of short, medium and long. When omitted, the
+    /// format defaults to short.
+    ///
+    ///     short
+    ///         <replaced-sha1>
+    ///
+    ///     medium
+    ///         <replaced-sha1> -> <replacement-sha1>
+    ///
+    ///     long
+    ///         <replaced-sha1> (<replaced-type>) -> <replacement-sha1> (<replacement-type>)
+    #[arg(long, value_name = "format")]
+    pub format: Option<String>,
+}
@@ -0,0 +1,373 @@
+use std::{collections::HashMap, fs};
+
+use crate::{
+    command::GitCommand,
+    editor::launch_editor,
+    object::{split_commit_signature, GitObjectId, GitObjectType},
+    repo::{GitRepo, RepoState, REPLACE_REFS_PREFIX},
+    wildmatch::{wildmatch, WildmatchFlags},
+    RustGitError,
+};
+
+use super::cli::ReplaceArgs;
+
+const EDIT_FILE: &str = "REPLACE_EDITOBJ";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ListFormat {
+    Short,
+    Medium,
+    Long,
+}
+
+pub(crate) enum ReplaceCommand {
+    Replace {
+        object: String,
+        replacement: String,
+        force: bool,
+    },
+    Edit {
+        object: String,
+        force: bool,
+    },
+    Graft {
+        commit: String,
+        parents: Vec<String>,
+        force: bool,
+    },
+    Delete {
+        objects: Vec<String>,
+    },
+    List {
+        pattern: Option<String>,
+        format: ListFormat,
+    },
+}
+
+impl ReplaceCommand {
+    pub fn new(args: ReplaceArgs) -> Result<ReplaceCommand, RustGitError> {
+        // Without any mode, objects get replaced, or replace refs listed if there's no object.
+        let list = args.list || !(args.delete || args.edit || args.graft || !args.args.is_empty());
+
+        if args.format.is_some() && !list {
+            return Err(RustGitError::new(
+                "fatal: --format cannot be used when not listing",
+            ));
+        }
+        if args.force && (list || args.delete) {
+            return Err(RustGitError::new(
+                "fatal: -f only makes sense when writing a replacement",
+            ));
+        }
+        if args.raw && !args.edit {
+            return Err(RustGitError::new(
+                "fatal: --raw only makes sense with --edit",
+            ));
+        }
+
+        let mut objects = args.args;
+        if list {
+            if objects.len() > 1 {
+                return Err(RustGitError::new(
+                    "fatal: only one pattern can be given with -l",
+                ));
+            }
+
+            let format = match args.format.as_deref() {
+                None | Some("short") => ListFormat::Short,
+                Some("medium") => ListFormat::Medium,
+                Some("long") => ListFormat::Long,
+                Some(format) => {
+                    return Err(RustGitError::new(format!(
+                        "error: invalid replace format '{format}'\nvalid formats are 'short', 'medium' and 'long'"
+                    )))
+                }
+            };
+            return Ok(ReplaceCommand::List {
+                pattern: objects.pop(),
+                format,
+            });
+        }
+
+        if args.delete {
+            if objects.is_empty() {
+                return Err(RustGitError::new("fatal: -d needs at least one argument"));
+            }
+            return Ok(ReplaceCommand::Delete { objects });
+        }
+
+        if args.edit {
+            if objects.len() != 1 {
+                return Err(RustGitError::new("fatal: -e needs exactly one argument"));
+            }
+            return Ok(ReplaceCommand::Edit {
+                object: objects.remove(0),
+                force: args.force,
+            });
+        }
+
+        if args.graft {
+            if objects.is_empty() {
+                return Err(RustGitError::new("fatal: -g needs at least one argument"));
+            }
+            let commit = objects.remove(0);
+            return Ok(ReplaceCommand::Graft {
+                commit,
+                parents: objects,
+                force: args.force,
+            });
+        }
+
+        if objects.len() != 2 {
+            return Err(RustGitError::new("fatal: bad number of arguments"));
+        }
+        let replacement = objects.remove(1);
+        Ok(ReplaceCommand::Replace {
+            object: objects.remove(0),
+            replacement,
+            force: args.force,
+        })
+    }
+}
+
+fn replace_ref_name(object: &GitObjectId) -> String {
+    format!("{REPLACE_REFS_PREFIX}{object}")
+}
+
+fn resolve(repo: &GitRepo, name: &str) -> Result<GitObjectId, RustGitError> {
+    repo.resolve_revision(name)
+        .map_err(|_| RustGitError::new(format!("error: failed to resolve '{name}' as a valid ref")))
+}
+
+fn resolve_object_name(repo: &GitRepo, name: &str) -> Result<GitObjectId, RustGitError> {
+    repo.resolve_revision(name)
+        .map_err(|_| RustGitError::new(format!("error: not a valid object name: '{name}'")))
+}
+
+/// Fails if the object already has a replacement and it isn't being forced over.
+fn check_ref_valid(repo: &GitRepo, object: &GitObjectId, force: bool) -> Result<(), RustGitError> {
+    let ref_name = replace_ref_name(object);
+    if !force && repo.refs.ref_exists(&ref_name)? {
+        return Err(RustGitError::new(format!(
+            "error: replace ref '{ref_name}' already exists"
+        )));
+    }
+    Ok(())
+}
+
+fn replace_object(
+    repo: &GitRepo,
+    object_ref: &str,
+    object: &GitObjectId,
+    replacement_ref: &str,
+    replacement: &GitObjectId,
+    force: bool,
+) -> Result<(), RustGitError> {
+    let obj_type = repo.object_type(object)?;
+    let replacement_type = repo.object_type(replacement)?;
+    if !force && obj_type != replacement_type {
+        return Err(RustGitError::new(format!(
+            "error: Objects must be of the same type.
+'{object_ref}' points to a replaced object of type '{obj_type}'
+while '{replacement_ref}' points to a replacement object of type '{replacement_type}'."
+        )));
+    }
+
+    check_ref_valid(repo, object, force)?;
+    repo.refs.set_ref(&replace_ref_name(object), replacement)
+}
+
+/// Swaps the `parent` headers of raw commit content for the provided parents, which go right
+/// after the `tree` header as in C Git's `replace_parents`.
+fn replace_parents(content: &str, parents: &[GitObjectId]) -> String {
+    let (headers, message) = match content.split_once("\n\n") {
+        Some((headers, message)) => (headers, Some(message)),
+        None => (content, None),
+    };
+
+    let mut lines = Vec::new();
+    for line in headers.split('\n') {
+        if line.starts_with("parent ") {
+            continue;
+        }
+        lines.push(line.to_string());
+        if line.starts_with("tree ") {
+            lines.extend(parents.iter().map(|parent| format!("parent {parent}")));
+        }
+    }
+
+    let mut content = lines.join("\n");
+    if let Some(message) = message {
+        content.push_str("\n\n");
+        content.push_str(message);
+    }
+    content
+}
+
+impl ReplaceCommand {
+    fn edit(repo: &GitRepo, object_ref: &str, force: bool) -> Result<(), RustGitError> {
+        let object = resolve_object_name(repo, object_ref)?;
+        let obj_type = repo.object_type(&object)?;
+        check_ref_valid(repo, &object, force)?;
+
+        // The object's current replacement gets edited if it already has one.
+        let current = repo
+            .refs
+            .resolve_ref(&replace_ref_name(&object))?
+            .unwrap_or_else(|| object.clone());
+        let contents = repo
+            .obj_store
+            .read_object(&current)?
+            .ok_or_else(|| RustGitError::new(format!("fatal: bad object {current}")))?
+            .content;
+
+        let path = repo.git_dir.join(EDIT_FILE);
+        fs::write(&path, contents)?;
+        let edited = launch_editor(&repo.config, &path)
+            .map_err(|err| RustGitError::new(format!("{err}\nerror: editing object file failed")))
+            .and_then(|_| Ok(fs::read_to_string(&path)?));
+        fs::remove_file(&path)?;
+
+        let new_id = repo.hash_obj(obj_type, edited?, true)?;
+        if new_id == object {
+            return Err(RustGitError::new(format!(
+                "error: new object is the same as the old one: '{object}'"
+            )));
+        }
+
+        replace_object(repo, object_ref, &object, "replacement", &new_id, force)
+    }
+
+    fn graft(
+        repo: &GitRepo,
+        commit_ref: &str,
+        parent_refs: &[String],
+        force: bool,
+    ) -> Result<(), RustGitError> {
+        let commit_id = resolve_object_name(repo, commit_ref)?;
+        let commit_id = repo
+            .peel_to_commit(&commit_id)
+            .map_err(|_| RustGitError::new(format!("error: could not parse {commit_ref}")))?;
+        let content = repo
+            .obj_store
+            .read_object(&commit_id)?
+            .ok_or_else(|| RustGitError::new(format!("fatal: bad object {commit_id}")))?
+            .content;
+
+        let parents = parent_refs
+            .iter()
+            .map(|parent_ref| {
+                let parent = resolve_object_name(repo, parent_ref)?;
+                repo.peel_to_commit(&parent).map_err(|_| {
+                    RustGitError::new(format!("error: could not parse {parent_ref} as a commit"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Only the parents change, so that every other header of the commit is kept as is.
+        let (content, signature) = split_commit_signature(&replace_parents(&content, &parents));
+        if signature.is_some() {
+            eprintln!("warning: the original commit '{commit_ref}' has a gpg signature");
+            eprintln!("warning: the signature will be removed in the replacement commit!");
+        }
+
+        let new_id = repo.hash_obj(GitObjectType::Commit, content, true)?;
+        if new_id == commit_id {
+            eprintln!("warning: graft for '{commit_id}' unnecessary");
+            return Ok(());
+        }
+
+        replace_object(repo, commit_ref, &commit_id, "replacement", &new_id, force)
+    }
+
+    fn delete(repo: &GitRepo, objects: &[String]) -> Result<(), RustGitError> {
+        // As in C Git, failing to delete one replace ref doesn't stop the others from being deleted.
+        let mut errors = Vec::new();
+        for name in objects {
+            let object = match repo.resolve_revision(name) {
+                Ok(object) => object,
+                Err(_) => {
+                    errors.push(format!("error: failed to resolve '{name}' as a valid ref"));
+                    continue;
+                }
+            };
+
+            let ref_name = replace_ref_name(&object);
+            if repo.refs.resolve_ref(&ref_name)?.is_none() {
+                errors.push(format!("error: replace ref '{object}' not found"));
+                continue;
+            }
+
+            repo.refs.delete_ref(&ref_name)?;
+            println!("Deleted replace ref '{object}'");
+        }
+
+        if !errors.is_empty() {
+            return Err(RustGitError::new(errors.join("\n")));
+        }
+        Ok(())
+    }
+
+    fn list(repo: &GitRepo, pattern: Option<&str>, format: ListFormat) -> Result<(), RustGitError> {
+        for git_ref in repo.refs.list_refs_with_prefix(REPLACE_REFS_PREFIX)? {
+            let replaced = git_ref.name.trim_start_matches(REPLACE_REFS_PREFIX);
+            if !pattern
+                .is_none_or(|pattern| wildmatch(pattern, replaced, WildmatchFlags::default()))
+            {
+                continue;
+            }
+
+            match format {
+                ListFormat::Short => println!("{replaced}"),
+                ListFormat::Medium => println!("{replaced} -> {}", git_ref.id),
+                ListFormat::Long => {
+                    let replaced_type =
+                        repo.object_type(&GitObjectId::new(replaced.to_string()))?;
+                    let replacement_type = repo.object_type(&git_ref.id)?;
+                    println!(
+                        "{replaced} ({replaced_type}) -> {} ({replacement_type})",
+                        git_ref.id
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GitCommand for ReplaceCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let mut repo = repo_state.try_get()?;
+        // As in C Git, this command always deals with objects themselves, never their replacements.
+        repo.obj_store.set_replacements(HashMap::new());
+
+        match self {
+            ReplaceCommand::Replace {
+                object,
+                replacement,
+                force,
+            } => {
+                let object_id = resolve(&repo, object)?;
+                let replacement_id = resolve(&repo, replacement)?;
+                replace_object(
+                    &repo,
+                    object,
+                    &object_id,
+                    replacement,
+                    &replacement_id,
+                    *force,
+                )
+            }
+            ReplaceCommand::Edit { object, force } => Self::edit(&repo, object, *force),
+            ReplaceCommand::Graft {
+                commit,
+                parents,
+                force,
+            } => Self::graft(&repo, commit, parents, *force),
+            ReplaceCommand::Delete { objects } => Self::delete(&repo, objects),
+            ReplaceCommand::List { pattern, format } => {
+                Self::list(&repo, pattern.as_deref(), *format)
+            }
+        }
+    }
+}
@@ -1,23 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 use std::fs::Metadata;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 
-use crate::index::{GitIndex, GitIndexEntry};
+use crate::cache_tree::GitCacheTree;
+use crate::config::FsyncComponent;
+use crate::gpg::sign_buffer;
+use crate::index::{GitIndex, GitIndexEntry, GitIndexMode, GitIndexStageFlag, DEFAULT_INDEX_NAME};
+use crate::lockfile::GitLockFile;
 use crate::object::{
     GitBlobObject, GitCommitObject, GitObject, GitObjectId, GitObjectType, GitTagObject,
     GitTreeEntry, GitTreeObject,
 };
 use crate::object_store::GitObjectStore;
-use crate::refs::GitRefs;
+use crate::refs::{GitHead, GitRefs};
 use crate::{config::GitConfig, error::RustGitError};
 
-use std::fs::File;
-use std::io::Read;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::fs::{symlink, OpenOptionsExt};
 
 const DEFAULT_GIT_DIR_NAME: &str = ".git";
+pub(crate) const REPLACE_REFS_PREFIX: &str = "refs/replace/";
 
 const IDENTITY_ERR: &str = "*** Please tell me who you are.
 
@@ -49,6 +55,17 @@ impl RepoState {
     }
 }
 
+/// An index entry which couldn't be refreshed, see [GitRepo::refresh_index].
+#[derive(Debug, PartialEq)]
+pub(crate) enum GitRefreshFailure {
+    /// The file of the entry differs from it.
+    NeedsUpdate(GitRepoPath),
+    /// The file of the entry is missing.
+    Missing(GitRepoPath),
+    /// The path has unmerged entries.
+    NeedsMerge(GitRepoPath),
+}
+
 /// Represents a path which is relative to the root of the git repository.
 #[derive(Clone, Debug, Eq)]
 pub(crate) struct GitRepoPath(PathBuf);
@@ -103,6 +120,13 @@ impl Display for GitRepoPath {
 pub(crate) struct GitRepo {
     pub(crate) config: GitConfig,
     pub(crate) index: GitIndex,
+    /// Path to the index file, which is `index` in the git directory unless overridden by
+    /// GIT_INDEX_FILE.
+    index_file: PathBuf,
+    /// Whether optional operations which take locks, like saving a refreshed index, are allowed.
+    use_optional_locks: bool,
+    /// The index lock, when held by a command which modifies the index. See `lock_index`.
+    index_lock: Option<GitLockFile>,
     /// Absolute path to root directory of the repo.
     pub(crate) root_dir: PathBuf,
     /// Path to working directory relative to root of repo.
@@ -163,7 +187,16 @@ impl GitRepo {
     /// Creates a new GitRepo.
     /// If git_dir is provided, it will be used to find the git directory for the repo.
     /// Otherwise, we will search for the git directory from the current working directory.
-    pub(crate) fn new(git_dir: &Option<PathBuf>) -> Result<RepoState, RustGitError> {
+    /// If a namespace is provided, all refs are read from and written to that namespace.
+    /// Unless `use_replace_refs` is false, objects are replaced as described by refs/replace/.
+    /// Unless `use_optional_locks` is false, the index may be written by commands which only
+    /// read it, to save work for later commands.
+    pub(crate) fn new(
+        git_dir: &Option<PathBuf>,
+        namespace: Option<&str>,
+        use_replace_refs: bool,
+        use_optional_locks: bool,
+    ) -> Result<RepoState, RustGitError> {
         let current_dir = env::current_dir()?;
         let resolved_git_dir = match git_dir {
             Some(git_dir) => {
@@ -180,19 +213,32 @@ impl GitRepo {
         };
 
         let config = GitConfig::new(&resolved_git_dir)?;
-        // Loading the index on every repo initialization is inefficient, as it's not always needed
-        // by the command, but it's simple for now.
-        let index = GitIndex::open(&resolved_git_dir)?;
 
         let root_dir = resolved_git_dir.parent().unwrap().canonicalize()?;
         let abs_root_dir = root_dir.canonicalize()?;
+
+        // Like C Git, a relative GIT_INDEX_FILE is relative to the root of the repo.
+        let index_file = match env::var_os("GIT_INDEX_FILE") {
+            Some(index_file) => abs_root_dir.join(index_file),
+            None => resolved_git_dir.join(DEFAULT_INDEX_NAME),
+        };
+        // Loading the index on every repo initialization is inefficient, as it's not always needed
+        // by the command, but it's simple for now.
+        let index = GitIndex::open(&index_file, &config)?;
+
         let working_dir = current_dir.strip_prefix(&abs_root_dir)?.to_path_buf();
-        let obj_store = GitObjectStore::new(&resolved_git_dir);
-        let refs = GitRefs::new(&resolved_git_dir)?;
+        let mut obj_store = GitObjectStore::new(&resolved_git_dir);
+        let refs = GitRefs::new(&resolved_git_dir, namespace, &config)?;
+        if use_replace_refs {
+            obj_store.set_replacements(Self::read_replacements(&refs)?);
+        }
 
         Ok(RepoState::Repo(GitRepo {
             config,
             index,
+            index_file,
+            use_optional_locks,
+            index_lock: None,
             root_dir: abs_root_dir,
             working_dir,
             git_dir: resolved_git_dir,
@@ -201,6 +247,22 @@ impl GitRepo {
         }))
     }
 
+    /// Reads the replacements recorded under refs/replace/, where each ref is named after the
+    /// object it replaces and points at its replacement.
+    fn read_replacements(
+        refs: &GitRefs,
+    ) -> Result<HashMap<GitObjectId, GitObjectId>, RustGitError> {
+        let mut replacements = HashMap::new();
+        for git_ref in refs.list_refs_with_prefix(REPLACE_REFS_PREFIX)? {
+            let replaced = git_ref.name.trim_start_matches(REPLACE_REFS_PREFIX);
+            // Refs which aren't named after an object can't replace anything.
+            if replaced.len() == 40 && replaced.chars().all(|c| c.is_ascii_hexdigit()) {
+                replacements.insert(GitObjectId::new(replaced.to_lowercase()), git_ref.id);
+            }
+        }
+        Ok(replacements)
+    }
+
     pub fn hash_obj(
         &self,
         obj_type: GitObjectType,
@@ -228,7 +290,9 @@ impl GitRepo {
             let blob_obj = GitBlobObject { contents };
             return self.obj_store.write_object(blob_obj);
         } else if metadata.is_symlink() {
-            todo!("handle links");
+            // The blob of a symbolic link holds its target.
+            let contents = fs::read_link(path)?.to_string_lossy().into_owned();
+            return self.obj_store.write_object(GitBlobObject { contents });
         } else if metadata.is_dir() {
             todo!("handle dirs")
         }
@@ -241,14 +305,148 @@ impl GitRepo {
         self.root_dir.join(path.as_string())
     }
 
-    /// Write file relative to root of repo.
-    pub(crate) fn write_file(
+    /// Returns the metadata of the worktree file at the provided path, or None if it's missing.
+    /// Symbolic links aren't followed.
+    pub(crate) fn worktree_metadata(
         &self,
         path: &GitRepoPath,
-        contents: impl AsRef<[u8]>,
-    ) -> Result<(), RustGitError> {
+    ) -> Result<Option<Metadata>, RustGitError> {
+        match fs::symlink_metadata(self.path_in_repo(path)) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) => {
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the id of the blob holding the contents of the worktree file at the provided
+    /// path, which is only written to the repo if `write` is set.
+    pub(crate) fn hash_worktree_file(
+        &self,
+        path: &GitRepoPath,
+        write: bool,
+    ) -> Result<GitObjectId, RustGitError> {
         let path_in_repo = self.path_in_repo(path);
-        Ok(fs::write(path_in_repo, contents)?)
+        let contents = if fs::symlink_metadata(&path_in_repo)?.is_symlink() {
+            fs::read_link(path_in_repo)?.to_string_lossy().into_owned()
+        } else {
+            fs::read_to_string(path_in_repo)?
+        };
+        self.hash_obj(GitObjectType::Blob, contents, write)
+    }
+
+    /// Returns whether the worktree file of the provided entry, which has the provided metadata,
+    /// differs from the entry. The stat data recorded for the entry is trusted when it matches,
+    /// so that files are only hashed when their stat data changed without their size changing,
+    /// or when the entry is racily clean. Entries assumed valid or not checked out are never
+    /// modified.
+    pub(crate) fn is_entry_modified(
+        &self,
+        entry: &GitIndexEntry,
+        metadata: &Metadata,
+    ) -> Result<bool, RustGitError> {
+        if entry.flags.assume_valid || entry.extended_flags.skip_worktree {
+            return Ok(false);
+        }
+        self.is_entry_content_modified(entry, metadata)
+    }
+
+    fn is_entry_content_modified(
+        &self,
+        entry: &GitIndexEntry,
+        metadata: &Metadata,
+    ) -> Result<bool, RustGitError> {
+        if entry.extended_flags.intent_to_add
+            || entry.mode
+                != GitIndexMode::from_worktree(metadata, Some(&entry.mode), &self.config.core)
+        {
+            return Ok(true);
+        }
+        // A size of 0 is recorded for smudged entries, so those are compared by contents.
+        if entry.file_size != 0 && entry.file_size != metadata.len() as u32 {
+            return Ok(true);
+        }
+        if entry.matches_stat(metadata, &self.config.core) && !self.index.is_racy(entry) {
+            return Ok(false);
+        }
+        Ok(self.hash_worktree_file(&entry.path_name, false)? != entry.name)
+    }
+
+    /// Records the current stat data of index entries accepted by `filter` whose files didn't
+    /// change, so that later commands can trust it instead of hashing the files. Entries which
+    /// couldn't be refreshed are returned, with unmerged paths reported once. Entries assumed
+    /// valid are only checked if `really` is set.
+    pub(crate) fn refresh_index(
+        &mut self,
+        really: bool,
+        mut filter: impl FnMut(&GitIndexEntry) -> bool,
+    ) -> Result<Vec<GitRefreshFailure>, RustGitError> {
+        let mut failures = vec![];
+        let mut refreshed = vec![];
+        let mut last_unmerged_path = None;
+        for (idx, entry) in self.index.iter_entries().enumerate() {
+            if !filter(entry) || entry.extended_flags.skip_worktree {
+                continue;
+            }
+            if entry.flags.stage != GitIndexStageFlag::RegularFileNoConflict {
+                if last_unmerged_path != Some(&entry.path_name) {
+                    failures.push(GitRefreshFailure::NeedsMerge(entry.path_name.clone()));
+                    last_unmerged_path = Some(&entry.path_name);
+                }
+                continue;
+            }
+            if entry.flags.assume_valid && !really {
+                continue;
+            }
+
+            let Some(metadata) = self.worktree_metadata(&entry.path_name)? else {
+                failures.push(GitRefreshFailure::Missing(entry.path_name.clone()));
+                continue;
+            };
+            if entry.matches_stat(&metadata, &self.config.core) && !self.index.is_racy(entry) {
+                continue;
+            }
+            if self.is_entry_content_modified(entry, &metadata)? {
+                failures.push(GitRefreshFailure::NeedsUpdate(entry.path_name.clone()));
+            } else {
+                refreshed.push((idx, metadata));
+            }
+        }
+
+        for (idx, metadata) in refreshed {
+            self.index.refresh_entry_stat_at(idx, &metadata);
+        }
+        Ok(failures)
+    }
+
+    /// Clears the recorded size of racily clean entries whose files changed, as once the index is
+    /// written with a later timestamp, their stat data would no longer show the change.
+    /// See https://git-scm.com/docs/racy-git.
+    fn smudge_racily_clean_entries(&mut self) -> Result<(), RustGitError> {
+        let mut smudged = vec![];
+        for (idx, entry) in self.index.iter_entries().enumerate() {
+            if entry.file_size == 0
+                || entry.extended_flags.skip_worktree
+                || !self.index.is_racy(entry)
+            {
+                continue;
+            }
+            let Some(metadata) = self.worktree_metadata(&entry.path_name)? else {
+                continue;
+            };
+            // Otherwise, the stat data already shows the file changed.
+            if entry.matches_stat(&metadata, &self.config.core)
+                && self.hash_worktree_file(&entry.path_name, false)? != entry.name
+            {
+                smudged.push(idx);
+            }
+        }
+
+        for idx in smudged {
+            self.index.smudge_entry_at(idx);
+        }
+        Ok(())
     }
 
     /// Remove file relative to root of repo.
@@ -257,12 +455,112 @@ impl GitRepo {
         Ok(fs::remove_file(path_in_repo)?)
     }
 
+    /// Writes the contents of the provided index entry to the worktree, creating its leading
+    /// directories, and returns the metadata of the written file.
+    pub(crate) fn checkout_entry(&self, entry: &GitIndexEntry) -> Result<Metadata, RustGitError> {
+        self.checkout_entry_to(entry, &self.path_in_repo(&entry.path_name))
+    }
+
+    /// Writes the contents of the provided index entry to the provided path, replacing the file
+    /// there, and returns the metadata of the written file. Executable files get the executable
+    /// bits, and symbolic links are recreated, unless core.symlinks is false, in which case a
+    /// plain file holding the link target is written. Submodules only get an empty directory.
+    pub(crate) fn checkout_entry_to(
+        &self,
+        entry: &GitIndexEntry,
+        path: &Path,
+    ) -> Result<Metadata, RustGitError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if entry.mode == GitIndexMode::GitLink {
+            fs::create_dir_all(path)?;
+            return Ok(fs::symlink_metadata(path)?);
+        }
+
+        let obj = self.obj_store.read_object(&entry.name)?.ok_or_else(|| {
+            RustGitError::new(format!(
+                "error: unable to read sha1 file of {} ({})",
+                entry.path_name.as_string(),
+                entry.name
+            ))
+        })?;
+
+        // The file is recreated so that it gets the mode of the entry.
+        match fs::remove_file(path) {
+            Ok(()) => (),
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        }
+        match entry.mode {
+            GitIndexMode::SymbolicLink if self.config.core.symlinks => {
+                symlink(&obj.content, path)?;
+            }
+            _ => {
+                // Like C Git, the permissions are left to the umask.
+                let permissions = if entry.mode == GitIndexMode::RegularFile0755 {
+                    0o777
+                } else {
+                    0o666
+                };
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(permissions)
+                    .open(path)?
+                    .write_all(obj.content.as_bytes())?;
+            }
+        }
+        Ok(fs::symlink_metadata(path)?)
+    }
+
+    /// Removes the worktree file at the provided path if it exists, along with the directories
+    /// which are left empty.
+    pub(crate) fn remove_worktree_file(&self, path: &GitRepoPath) -> Result<(), RustGitError> {
+        match fs::remove_file(self.path_in_repo(path)) {
+            Ok(()) => (),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        }
+
+        let mut dir = path.as_path_buf();
+        while dir.pop() && !dir.as_os_str().is_empty() {
+            if fs::remove_dir(self.root_dir.join(&dir)).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn update_ref(
         &self,
         git_ref: &str,
         new_value: &str,
         old_value: Option<&str>,
+        no_deref: bool,
     ) -> Result<(), RustGitError> {
+        if git_ref == "HEAD" {
+            let head = self.read_head()?;
+            if let (Some(GitHead::Symbolic { ref_name, .. }), false) = (&head, no_deref) {
+                return self.update_ref(ref_name, new_value, old_value, no_deref);
+            }
+
+            // HEAD is (or is about to be) detached, so it gets overwritten directly.
+            if let Some(old_value) = old_value {
+                let existing_value = head
+                    .as_ref()
+                    .and_then(|head| head.id())
+                    .map(|id| id.to_string())
+                    .unwrap_or_default();
+                if old_value != existing_value {
+                    return Err(RustGitError::new(format!("existing value '{existing_value}' for HEAD doesn't match expected value '{old_value}'")));
+                }
+            }
+            return self
+                .refs
+                .detach_head(&GitObjectId::new(new_value.to_string()));
+        }
+
         if !git_ref.starts_with("refs/") {
             // TODO: C Git supports other things here (e.g. symbolic links as refs),
             // but for now we just support simple refs
@@ -289,12 +587,22 @@ impl GitRepo {
         Ok(fs::symlink_metadata(path_in_repo)?)
     }
 
-    pub(crate) fn write_index_as_tree_internal(
+    /// Writes the tree for the provided index entries, which all start with the same directory
+    /// path of the provided length. Trees which are still valid in the provided cache are reused,
+    /// and the cache is updated with the trees which are written.
+    fn write_index_as_tree_internal(
         &self,
         entries: &Vec<&GitIndexEntry>,
         offset: usize,
+        cache_tree: &mut GitCacheTree,
     ) -> Result<GitObjectId, RustGitError> {
-        let mut subtrees: HashMap<String, Vec<&GitIndexEntry>> = HashMap::new();
+        if let Some(tree_id) = cache_tree.valid_tree(entries.len()) {
+            if self.obj_store.is_valid_object_id(tree_id) {
+                return Ok(tree_id.clone());
+            }
+        }
+
+        let mut subtrees: BTreeMap<String, Vec<&GitIndexEntry>> = BTreeMap::new();
         let mut objects: Vec<&GitIndexEntry> = Vec::new();
 
         // Identify sub-trees and objects to add to create tree.
@@ -316,11 +624,23 @@ impl GitRepo {
         }
 
         let mut tree_entries = Vec::new();
+        cache_tree.retain_subtrees(|name| subtrees.contains_key(name));
 
         // Recursively compute sub-trees and add contents.
         for (name, entries) in subtrees {
-            let subtree_id =
-                self.write_index_as_tree_internal(&entries, offset + name.len() + 1)?;
+            // Directories which only contain entries intended to be added aren't written yet.
+            if entries
+                .iter()
+                .all(|entry| entry.extended_flags.intent_to_add)
+            {
+                continue;
+            }
+
+            let subtree_id = self.write_index_as_tree_internal(
+                &entries,
+                offset + name.len() + 1,
+                cache_tree.subtree_mut(&name),
+            )?;
             let entry = GitTreeEntry {
                 mode: "040000".to_string(),
                 entry_type: "tree".to_string(),
@@ -329,8 +649,11 @@ impl GitRepo {
             };
             tree_entries.push(entry);
         }
-        // Add blob object contents.
+        // Add blob object contents, except for entries which are only intended to be added.
         for object in objects {
+            if object.extended_flags.intent_to_add {
+                continue;
+            }
             let name = object.path_name.as_string()[offset..].to_string();
             let entry = GitTreeEntry {
                 mode: object.mode.to_string(),
@@ -345,18 +668,86 @@ impl GitRepo {
             entries: tree_entries,
         };
 
-        return self.obj_store.write_object(tree_obj);
+        let tree_id = self.obj_store.write_object(tree_obj)?;
+        // Like C git, trees missing entries which are intended to be added aren't cached.
+        if !entries
+            .iter()
+            .any(|entry| entry.extended_flags.intent_to_add)
+        {
+            cache_tree.set_valid_tree(tree_id.clone(), entries.len());
+        }
+        Ok(tree_id)
     }
 
     /// Saves the current index as a tree object in the repo.
-    pub(crate) fn write_index_as_tree(&self) -> Result<GitObjectId, RustGitError> {
+    /// The trees written are cached in the index, which is written if the cache changed.
+    pub(crate) fn write_index_as_tree(&mut self) -> Result<GitObjectId, RustGitError> {
+        self.try_lock_index()?;
+        let previous_cache_tree = self.index.take_cache_tree();
+        let mut cache_tree = previous_cache_tree.clone().unwrap_or_default();
+
         let all_index_entries = &self.index.iter_entries().collect();
+        let tree_id = self.write_index_as_tree_internal(all_index_entries, 0, &mut cache_tree)?;
 
-        self.write_index_as_tree_internal(all_index_entries, 0)
+        let cache_tree_changed = previous_cache_tree.as_ref() != Some(&cache_tree);
+        self.index.set_cache_tree(cache_tree);
+        if cache_tree_changed {
+            self.write_index_if_able()?;
+        }
+
+        Ok(tree_id)
     }
 
+    /// Takes the index lock and reads the index again under it, as commands which modify the
+    /// index do in C Git with `repo_hold_locked_index`, so that changes written by other
+    /// processes since the repo was opened aren't lost. The lock is held until the index is
+    /// written, and released if the repo is dropped first.
+    pub(crate) fn lock_index(&mut self) -> Result<(), RustGitError> {
+        let lock = GitLockFile::acquire(&self.index_file)?;
+        self.index = GitIndex::open(&self.index_file, &self.config)?;
+        self.index_lock = Some(lock);
+        Ok(())
+    }
+
+    /// Writes the index through the lock taken by `lock_index`, or else through a new lock,
+    /// failing if another process holds it.
     pub(crate) fn write_index(&mut self) -> Result<(), RustGitError> {
-        self.index.write(&self.git_dir)
+        let lock = match self.index_lock.take() {
+            Some(lock) => lock,
+            None => GitLockFile::acquire(&self.index_file)?,
+        };
+        self.smudge_racily_clean_entries()?;
+        let fsync = self.config.core.should_fsync(FsyncComponent::Index);
+        self.index.write(lock, fsync)
+    }
+
+    /// Takes the index lock if optional locks are enabled and no other process holds it, and reads
+    /// the index again under it, so that changes which only save work for later commands can be
+    /// written with `write_index_if_able` without losing changes made by other processes.
+    /// Nothing happens if the lock is already held.
+    pub(crate) fn try_lock_index(&mut self) -> Result<(), RustGitError> {
+        if !self.use_optional_locks || self.index_lock.is_some() {
+            return Ok(());
+        }
+        if let Some(lock) = GitLockFile::try_acquire(&self.index_file)? {
+            self.index = GitIndex::open(&self.index_file, &self.config)?;
+            self.index_lock = Some(lock);
+        }
+        Ok(())
+    }
+
+    /// Writes the index for changes which only save work for later commands, such as cached
+    /// trees, through the lock taken by `try_lock_index` before they were made. Nothing is written
+    /// if the lock couldn't be taken, as the changes can be recomputed later.
+    pub(crate) fn write_index_if_able(&mut self) -> Result<(), RustGitError> {
+        match self.index_lock.take() {
+            Some(lock) => {
+                self.smudge_racily_clean_entries()?;
+                let fsync = self.config.core.should_fsync(FsyncComponent::Index);
+                self.index.write(lock, fsync)
+            }
+            None => Ok(()),
+        }
     }
 
     // TODO:
@@ -367,12 +758,13 @@ impl GitRepo {
         return Ok(now.duration_since(UNIX_EPOCH)?.as_millis());
     }
 
-    /// Writes a commit object to the repo.
+    /// Writes a commit object to the repo, signing it with the provided key if there is one.
     pub(crate) fn write_commit(
         &self,
         tree: &GitObjectId,
         parents: &Vec<GitObjectId>,
         message: &str,
+        signing_key: Option<&str>,
     ) -> Result<GitObjectId, RustGitError> {
         if !self.obj_store.is_valid_object_id(&tree) {
             return Err(RustGitError::new(format!(
@@ -396,7 +788,7 @@ impl GitRepo {
         match (&self.config.user.name, &self.config.user.email) {
             (Some(user_name), Some(user_email)) => {
                 let timestamp = self.get_timestamp()?;
-                let commit_obj = GitCommitObject {
+                let mut commit_obj = GitCommitObject {
                     tree: tree.clone(),
                     parents: parents.clone(),
                     message: message.to_string(),
@@ -405,8 +797,23 @@ impl GitRepo {
                     committer_name: user_name.to_string(),
                     committer_email: user_email.to_string(),
                     timestamp: timestamp,
+                    signature: None,
                 };
 
+                if let Some(signing_key) = signing_key {
+                    // The signature covers the commit as it would be written without it.
+                    let payload: GitObject = commit_obj.clone().try_into()?;
+                    let signature = sign_buffer(
+                        &self.config,
+                        &payload.content.content,
+                        signing_key,
+                    )
+                    .map_err(|err| {
+                        RustGitError::new(format!("{err}\nfatal: failed to write commit object"))
+                    })?;
+                    commit_obj.signature = Some(signature);
+                }
+
                 return self.obj_store.write_object(commit_obj);
             }
             _ => Err(RustGitError::new(IDENTITY_ERR)),
@@ -429,7 +836,7 @@ impl GitRepo {
         self.refs.delete_symbolic_ref(ref_name)
     }
 
-    pub(crate) fn read_tag(&self, tag_name: &str) -> Result<Option<String>, RustGitError> {
+    pub(crate) fn read_tag(&self, tag_name: &str) -> Result<Option<GitObjectId>, RustGitError> {
         self.refs.try_read_tag(tag_name)
     }
 
@@ -438,12 +845,13 @@ impl GitRepo {
         tag_name: &str,
         object_id: &GitObjectId,
         message: &str,
+        signing_key: Option<&str>,
     ) -> Result<(), RustGitError> {
         if let Some(target_object) = self.obj_store.read_object(&object_id)? {
             match (&self.config.user.name, &self.config.user.email) {
                 (Some(user_name), Some(user_email)) => {
                     let timestamp = self.get_timestamp()?;
-                    let commit_obj = GitTagObject {
+                    let mut commit_obj = GitTagObject {
                         tag_name: tag_name.to_string(),
                         object_id: object_id.clone(),
                         object_type: target_object.header.obj_type,
@@ -451,8 +859,26 @@ impl GitRepo {
                         tagger_email: user_email.to_string(),
                         timestamp,
                         message: message.to_string(),
+                        signature: None,
                     };
 
+                    if let Some(signing_key) = signing_key {
+                        // The signature is appended to the message, so it has to start on its own
+                        // line.
+                        if !commit_obj.message.is_empty() && !commit_obj.message.ends_with('\n') {
+                            commit_obj.message.push('\n');
+                        }
+                        let payload: GitObject = commit_obj.clone().try_into()?;
+                        let signature =
+                            sign_buffer(&self.config, &payload.content.content, signing_key)
+                                .map_err(|err| {
+                                    RustGitError::new(format!(
+                                        "{err}\nerror: unable to sign the tag"
+                                    ))
+                                })?;
+                        commit_obj.signature = Some(signature);
+                    }
+
                     let tag_obj_id = self.obj_store.write_object(commit_obj)?;
                     self.refs.create_tag(tag_name, &tag_obj_id)
                 }
@@ -475,13 +901,7 @@ impl GitRepo {
         self.refs.delete_tag(tag_name)
     }
 
-    pub(crate) fn list_tags(&self) -> Result<Vec<String>, RustGitError> {
-        self.refs.list_tags()
-    }
-
-    pub(crate) fn get_head_ref(
-        &self,
-    ) -> Result<(Option<String>, Option<GitObjectId>), RustGitError> {
-        self.refs.get_head_ref()
+    pub(crate) fn read_head(&self) -> Result<Option<GitHead>, RustGitError> {
+        self.refs.read_head()
     }
 }
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::RustGitError,
+    index::{GitIndexEntry, GitIndexMode, GitIndexStageFlag},
+    object::GitObjectId,
+    repo::GitRepoPath,
+};
+
+/// The stages of a path whose conflict was resolved, indexed by stage minus one. Each stage
+/// which existed holds its mode and object.
+pub(crate) type GitResolveUndoStages = [Option<(GitIndexMode, GitObjectId)>; 3];
+
+/// The conflicted stages of paths which were resolved in the index, stored in the REUC index
+/// extension, so that the conflicts can be recreated later.
+///
+/// See the "Resolve undo" section of gitformat-index(5).
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct GitResolveUndo {
+    entries: BTreeMap<GitRepoPath, GitResolveUndoStages>,
+}
+
+impl GitResolveUndo {
+    pub(crate) fn serialize(resolve_undo: &GitResolveUndo) -> Vec<u8> {
+        let mut bytes = vec![];
+        for (path, stages) in &resolve_undo.entries {
+            bytes.extend_from_slice(path.as_string().as_bytes());
+            bytes.push(b'\0');
+            for stage in stages {
+                let mode = stage.as_ref().map_or(0, |(mode, _)| mode.as_octal());
+                bytes.extend_from_slice(format!("{mode:o}").as_bytes());
+                bytes.push(b'\0');
+            }
+            for (_, id) in stages.iter().flatten() {
+                bytes.extend_from_slice(&GitObjectId::serialize(id));
+            }
+        }
+        bytes
+    }
+
+    pub(crate) fn deserialize(bytes: &[u8]) -> Result<GitResolveUndo, RustGitError> {
+        let malformed = || RustGitError::new("malformed REUC extension in index");
+        let next_field = |processed_bytes: &mut usize| {
+            let field_len = bytes[*processed_bytes..]
+                .iter()
+                .position(|&b| b == b'\0')
+                .ok_or_else(malformed)?;
+            let field = &bytes[*processed_bytes..*processed_bytes + field_len];
+            *processed_bytes += field_len + 1;
+            Ok::<&[u8], RustGitError>(field)
+        };
+
+        let mut entries = BTreeMap::new();
+        let mut processed_bytes = 0;
+        while processed_bytes < bytes.len() {
+            let path = GitRepoPath::deserialize(next_field(&mut processed_bytes)?)?;
+
+            let mut modes = [0; 3];
+            for mode in modes.iter_mut() {
+                let field = std::str::from_utf8(next_field(&mut processed_bytes)?)
+                    .map_err(|_| malformed())?;
+                *mode = u32::from_str_radix(field, 8).map_err(|_| malformed())?;
+            }
+
+            let mut stages: GitResolveUndoStages = Default::default();
+            for (stage, mode) in stages.iter_mut().zip(modes) {
+                if mode != 0 {
+                    let id_bytes = bytes
+                        .get(processed_bytes..processed_bytes + 20)
+                        .ok_or_else(malformed)?;
+                    *stage = Some((
+                        GitIndexMode::deserialize(&mode.to_be_bytes())?,
+                        GitObjectId::deserialize(id_bytes)?,
+                    ));
+                    processed_bytes += 20;
+                }
+            }
+
+            entries.insert(path, stages);
+        }
+
+        Ok(GitResolveUndo { entries })
+    }
+
+    /// Records the stage of an unmerged entry which is being removed from the index.
+    pub(crate) fn record(&mut self, entry: &GitIndexEntry) {
+        let stage_idx = match entry.flags.stage {
+            GitIndexStageFlag::RegularFileNoConflict => return,
+            GitIndexStageFlag::Base => 0,
+            GitIndexStageFlag::Ours => 1,
+            GitIndexStageFlag::Theirs => 2,
+        };
+        let stages = self.entries.entry(entry.path_name.clone()).or_default();
+        stages[stage_idx] = Some((entry.mode.clone(), entry.name.clone()));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the recorded paths and their stages, ordered by path.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&GitRepoPath, &GitResolveUndoStages)> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_roundtrip() {
+        let mut stages: GitResolveUndoStages = Default::default();
+        stages[1] = Some((
+            GitIndexMode::RegularFile0644,
+            GitObjectId::new("a".repeat(40)),
+        ));
+        stages[2] = Some((
+            GitIndexMode::RegularFile0755,
+            GitObjectId::new("b".repeat(40)),
+        ));
+        let resolve_undo = GitResolveUndo {
+            entries: BTreeMap::from([(GitRepoPath::deserialize(b"dir/file.txt").unwrap(), stages)]),
+        };
+
+        let bytes = GitResolveUndo::serialize(&resolve_undo);
+        assert!(bytes.starts_with(b"dir/file.txt\x000\x00100644\x00100755\x00"));
+        assert_eq!(bytes.len(), 13 + 2 + 7 + 7 + 40);
+        assert_eq!(GitResolveUndo::deserialize(&bytes), Ok(resolve_undo));
+    }
+
+    #[test]
+    fn should_fail_for_truncated_entry() {
+        assert_eq!(
+            GitResolveUndo::deserialize(b"file.txt\x00100644\x000\x000\x00"),
+            Err(RustGitError::new("malformed REUC extension in index"))
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Args;
 
 #[derive(Args, Debug)]
@@ -16,7 +18,17 @@ See \"Reset, restore and revert\" in git(1) for the differences between the thre
 
 THIS COMMAND IS EXPERIMENTAL. THE BEHAVIOR MAY CHANGE.")]
 pub(crate) struct RestoreArgs {
-    // TODO: make this a pathspec
     /// Limits the paths affected by this operation.
-    pub(crate) files: Vec<String>,
+    pub(crate) pathspec: Vec<String>,
+
+    /// Pathspec is passed in <file> instead of commandline args. If <file> is exactly - then standard input is used. Pathspec
+    /// elements are separated by LF or CR/LF. Pathspec elements can be quoted as explained for the configuration variable
+    /// core.quotePath (see git-config(1)). See also --pathspec-file-nul and global --literal-pathspecs.
+    #[arg(long, value_name = "file", conflicts_with = "pathspec")]
+    pub(crate) pathspec_from_file: Option<PathBuf>,
+
+    /// Only meaningful with --pathspec-from-file. Pathspec elements are separated with NUL character and all other characters
+    /// are taken literally (including newlines and quotes).
+    #[arg(long, requires = "pathspec_from_file")]
+    pub(crate) pathspec_file_nul: bool,
 }
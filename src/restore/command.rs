@@ -1,44 +1,93 @@
-use std::path::Path;
-
-use crate::{command::GitCommand, repo::RepoState, RustGitError};
+use crate::{
+    command::GitCommand,
+    pathspec::{read_pathspec_from_file, Pathspec, PathspecOptions},
+    repo::RepoState,
+    RustGitError,
+};
 
 use super::cli::RestoreArgs;
 
 pub(crate) struct RestoreCommand {
     args: RestoreArgs,
+    pathspec_options: PathspecOptions,
 }
 
 impl RestoreCommand {
-    pub fn new(args: RestoreArgs) -> RestoreCommand {
-        RestoreCommand { args }
+    pub fn new(args: RestoreArgs, pathspec_options: PathspecOptions) -> RestoreCommand {
+        RestoreCommand {
+            args,
+            pathspec_options,
+        }
     }
 }
 
 impl GitCommand for RestoreCommand {
     // TODO: implement full restore functionality after implementing commits and branches
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
-        let repo = repo_state.try_get()?;
-
-        for file in self.args.files.iter() {
-            println!("restoring {file}");
-            let file_repo_path = repo.path_to_git_repo_path(Path::new(file))?;
-
-            let index_entries = repo.index.entry_range_by_path(&file_repo_path);
-
-            for index_entry in index_entries {
-                let obj = repo.obj_store.read_object(&index_entry.name)?;
-
-                match obj {
-                    Some(obj) => {
-                        repo.write_file(&index_entry.path_name, obj.content)?;
-                        println!("restored {}", index_entry.path_name);
-                    }
-                    None => println!(
-                        "obj {} missing for {}",
-                        index_entry.name, index_entry.path_name
-                    ),
+        let mut repo = repo_state.try_get()?;
+        repo.lock_index()?;
+
+        let elements = match &self.args.pathspec_from_file {
+            Some(file) => read_pathspec_from_file(file, self.args.pathspec_file_nul)?,
+            None => self.args.pathspec.clone(),
+        };
+        if elements.is_empty() {
+            return Err(RustGitError::new(
+                "fatal: you must specify path(s) to restore",
+            ));
+        }
+        let mut pathspec = Pathspec::parse(&repo, &elements, self.pathspec_options)?;
+
+        let positions = repo
+            .index
+            .iter_entries()
+            .enumerate()
+            .filter(|(_, entry)| pathspec.matches(&entry.path_name.as_string()).is_some())
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+
+        let mut written = vec![];
+        for idx in positions {
+            let index_entry = repo.index.entry_at(idx);
+            // Files which already match their entry are left alone.
+            if let Some(metadata) = repo.worktree_metadata(&index_entry.path_name)? {
+                if !metadata.is_dir() && !repo.is_entry_modified(index_entry, &metadata)? {
+                    continue;
                 }
             }
+            // Symbolic links and executable bits are restored along with the contents.
+            match repo.obj_store.read_object(&index_entry.name)? {
+                Some(_) => {
+                    written.push((idx, repo.checkout_entry(index_entry)?));
+                    println!("restored {}", index_entry.path_name);
+                }
+                None => println!(
+                    "obj {} missing for {}",
+                    index_entry.name, index_entry.path_name
+                ),
+            }
+        }
+
+        // The rewritten files get their new stat data recorded, so they aren't hashed again.
+        if !written.is_empty() {
+            for (idx, metadata) in written {
+                repo.index.refresh_entry_stat_at(idx, &metadata);
+            }
+            repo.write_index()?;
+        }
+
+        let unmatched = pathspec
+            .seen()
+            .filter(|(item, seen)| !item.exclude && seen.is_none())
+            .map(|(item, _)| {
+                format!(
+                    "error: pathspec '{}' did not match any file(s) known to git\n",
+                    item.original
+                )
+            })
+            .collect::<String>();
+        if !unmatched.is_empty() {
+            return Err(RustGitError::new(unmatched));
         }
 
         Ok(())
@@ -0,0 +1,337 @@
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::{
+    error::RustGitError,
+    index::{GitIndexEntry, GitIndexMode, GitIndexStageFlag},
+    object::{GitCommitObject, GitObjectId, GitObjectType, GitTagObject, GitTreeObject},
+    repo::{GitRepo, GitRepoPath},
+};
+
+// C Git refuses to look up abbreviated object names shorter than this:
+// https://github.com/git/git/blob/master/hash.h#L119
+const MIN_ABBREV_LEN: usize = 4;
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl GitRepo {
+    /// Returns the type of the object with the provided id.
+    pub(crate) fn object_type(&self, id: &GitObjectId) -> Result<GitObjectType, RustGitError> {
+        match self.obj_store.read_object(id)? {
+            Some(obj) => Ok(obj.header.obj_type),
+            None => Err(RustGitError::new(format!("fatal: bad object {id}"))),
+        }
+    }
+
+    /// Reads and parses the commit object with the provided id.
+    pub(crate) fn read_commit(&self, id: &GitObjectId) -> Result<GitCommitObject, RustGitError> {
+        match self.obj_store.read_object(id)? {
+            Some(obj) if obj.header.obj_type == GitObjectType::Commit => obj.content.parse(),
+            Some(obj) => Err(RustGitError::new(format!(
+                "fatal: object {id} is a {}, not a commit",
+                obj.header.obj_type
+            ))),
+            None => Err(RustGitError::new(format!("fatal: bad object {id}"))),
+        }
+    }
+
+    /// Reads and parses the tag object with the provided id.
+    pub(crate) fn read_tag_object(&self, id: &GitObjectId) -> Result<GitTagObject, RustGitError> {
+        match self.obj_store.read_object(id)? {
+            Some(obj) if obj.header.obj_type == GitObjectType::Tag => obj.content.parse(),
+            Some(obj) => Err(RustGitError::new(format!(
+                "fatal: object {id} is a {}, not a tag",
+                obj.header.obj_type
+            ))),
+            None => Err(RustGitError::new(format!("fatal: bad object {id}"))),
+        }
+    }
+
+    /// Reads and parses the tree object with the provided id.
+    pub(crate) fn read_tree(&self, id: &GitObjectId) -> Result<GitTreeObject, RustGitError> {
+        match self.obj_store.read_object(id)? {
+            Some(obj) if obj.header.obj_type == GitObjectType::Tree => obj.content.parse(),
+            Some(obj) => Err(RustGitError::new(format!(
+                "fatal: object {id} is a {}, not a tree",
+                obj.header.obj_type
+            ))),
+            None => Err(RustGitError::new(format!("fatal: bad object {id}"))),
+        }
+    }
+
+    /// Reads the files of the tree with the provided id and its subtrees as index entries at the
+    /// provided stage, with their paths under `prefix`, which must be empty or end with a slash.
+    pub(crate) fn read_tree_entries(
+        &self,
+        id: &GitObjectId,
+        prefix: &str,
+        stage: GitIndexStageFlag,
+    ) -> Result<Vec<GitIndexEntry>, RustGitError> {
+        let mut entries = vec![];
+        for tree_entry in self.read_tree(id)?.entries {
+            let path = format!("{prefix}{}", tree_entry.name);
+            if tree_entry.entry_type == "tree" {
+                entries.extend(self.read_tree_entries(
+                    &tree_entry.obj_id,
+                    &format!("{path}/"),
+                    stage,
+                )?);
+            } else {
+                entries.push(GitIndexEntry::from_object(
+                    &GitRepoPath::deserialize(path.as_bytes())?,
+                    GitIndexMode::from_octal(u32::from_str_radix(&tree_entry.mode, 8)?),
+                    tree_entry.obj_id,
+                    stage,
+                ));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Follows tag objects until reaching an object which is not a tag.
+    pub(crate) fn peel(
+        &self,
+        id: &GitObjectId,
+    ) -> Result<(GitObjectId, GitObjectType), RustGitError> {
+        let mut id = id.clone();
+        loop {
+            match self.object_type(&id)? {
+                GitObjectType::Tag => id = self.read_tag_object(&id)?.object_id,
+                obj_type => return Ok((id, obj_type)),
+            }
+        }
+    }
+
+    /// Peels the provided object to a commit, following tags.
+    pub(crate) fn peel_to_commit(&self, id: &GitObjectId) -> Result<GitObjectId, RustGitError> {
+        match self.peel(id)? {
+            (commit_id, GitObjectType::Commit) => Ok(commit_id),
+            (_, obj_type) => Err(RustGitError::new(format!(
+                "fatal: object {id} is a {obj_type}, not a commit"
+            ))),
+        }
+    }
+
+    /// Peels the provided object to a tree, following tags and commits.
+    pub(crate) fn peel_to_tree(&self, id: &GitObjectId) -> Result<GitObjectId, RustGitError> {
+        match self.peel(id)? {
+            (commit_id, GitObjectType::Commit) => Ok(self.read_commit(&commit_id)?.tree),
+            (tree_id, GitObjectType::Tree) => Ok(tree_id),
+            (_, obj_type) => Err(RustGitError::new(format!(
+                "fatal: object {id} is a {obj_type}, not a tree"
+            ))),
+        }
+    }
+
+    fn resolve_revision_base(&self, name: &str) -> Result<GitObjectId, RustGitError> {
+        let unknown_revision = || {
+            RustGitError::new(format!(
+                "fatal: ambiguous argument '{name}': unknown revision or path not in the working tree."
+            ))
+        };
+
+        if name.is_empty() {
+            return Err(unknown_revision());
+        }
+
+        // A full object id always wins over a ref with the same name.
+        if name.len() == 40 && is_hex(name) {
+            return Ok(GitObjectId::new(name.to_lowercase()));
+        }
+
+        if name == "@" {
+            return self.resolve_revision_base("HEAD");
+        }
+
+        if let Some(git_ref) = self.refs.dwim_ref(name)? {
+            return Ok(git_ref.id);
+        }
+
+        if name.len() >= MIN_ABBREV_LEN && is_hex(name) {
+            let matches = self
+                .obj_store
+                .find_objects_by_prefix(&name.to_lowercase())?;
+            return match matches.as_slice() {
+                [id] => Ok(id.clone()),
+                [] => Err(unknown_revision()),
+                _ => Err(RustGitError::new(format!(
+                    "error: short object ID {name} is ambiguous"
+                ))),
+            };
+        }
+
+        Err(unknown_revision())
+    }
+
+    /// Resolves a revision, as described in gitrevisions(7), to an object id.
+    ///
+    /// Supported forms are object ids (full or abbreviated), ref names, and the `^`, `^<n>`,
+    /// `~<n>` and `^{<type>}` suffixes.
+    pub(crate) fn resolve_revision(&self, revision: &str) -> Result<GitObjectId, RustGitError> {
+        let base_end = revision.find(['^', '~']).unwrap_or(revision.len());
+        let mut id = self.resolve_revision_base(&revision[..base_end])?;
+
+        let mut rest = &revision[base_end..];
+        while !rest.is_empty() {
+            if let Some(peel_suffix) = rest.strip_prefix("^{") {
+                let close = peel_suffix.find('}').ok_or_else(|| {
+                    RustGitError::new(format!("fatal: invalid revision '{revision}'"))
+                })?;
+                id = match &peel_suffix[..close] {
+                    "" => self.peel(&id)?.0,
+                    "commit" => self.peel_to_commit(&id)?,
+                    "tree" => self.peel_to_tree(&id)?,
+                    "object" => id,
+                    "tag" => match self.object_type(&id)? {
+                        GitObjectType::Tag => id,
+                        obj_type => {
+                            return Err(RustGitError::new(format!(
+                                "fatal: object {id} is a {obj_type}, not a tag"
+                            )))
+                        }
+                    },
+                    "blob" => match self.peel(&id)? {
+                        (blob_id, GitObjectType::Blob) => blob_id,
+                        (_, obj_type) => {
+                            return Err(RustGitError::new(format!(
+                                "fatal: object {id} is a {obj_type}, not a blob"
+                            )))
+                        }
+                    },
+                    other => {
+                        return Err(RustGitError::new(format!(
+                            "fatal: invalid object type '{other}' in revision '{revision}'"
+                        )))
+                    }
+                };
+                rest = &peel_suffix[close + 1..];
+                continue;
+            }
+
+            let operator = rest.as_bytes()[0];
+            let digits_len = rest[1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len() - 1);
+            let count = if digits_len == 0 {
+                1
+            } else {
+                rest[1..1 + digits_len].parse::<usize>()?
+            };
+            rest = &rest[1 + digits_len..];
+
+            let commit_id = self.peel_to_commit(&id)?;
+            id = if operator == b'^' {
+                if count == 0 {
+                    commit_id
+                } else {
+                    let commit = self.read_commit(&commit_id)?;
+                    commit.parents.get(count - 1).cloned().ok_or_else(|| {
+                        RustGitError::new(format!("fatal: invalid revision '{revision}'"))
+                    })?
+                }
+            } else {
+                let mut ancestor = commit_id;
+                for _ in 0..count {
+                    let commit = self.read_commit(&ancestor)?;
+                    ancestor = commit.parents.first().cloned().ok_or_else(|| {
+                        RustGitError::new(format!("fatal: invalid revision '{revision}'"))
+                    })?;
+                }
+                ancestor
+            };
+        }
+
+        if !self.obj_store.is_valid_object_id(&id) {
+            return Err(RustGitError::new(format!(
+                "fatal: not a valid object name {revision}"
+            )));
+        }
+
+        Ok(id)
+    }
+
+    /// Resolves a revision and peels it to a commit.
+    pub(crate) fn resolve_commit(&self, revision: &str) -> Result<GitObjectId, RustGitError> {
+        let id = self.resolve_revision(revision)?;
+        self.peel_to_commit(&id)
+            .map_err(|_| RustGitError::new(format!("fatal: '{revision}' is not a commit")))
+    }
+
+    /// Returns the set of commits reachable from the provided commit, including itself.
+    pub(crate) fn ancestors(
+        &self,
+        commit_id: &GitObjectId,
+    ) -> Result<HashSet<GitObjectId>, RustGitError> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([commit_id.clone()]);
+
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            for parent in self.read_commit(&id)?.parents {
+                queue.push_back(parent);
+            }
+        }
+
+        Ok(seen)
+    }
+
+    /// Returns true if `ancestor` is reachable from `descendant`.
+    pub(crate) fn is_ancestor(
+        &self,
+        ancestor: &GitObjectId,
+        descendant: &GitObjectId,
+    ) -> Result<bool, RustGitError> {
+        Ok(self.ancestors(descendant)?.contains(ancestor))
+    }
+
+    /// Walks the history reachable from the provided commits, newest commit first, as
+    /// `git rev-list` does by default.
+    pub(crate) fn walk_commits(
+        &self,
+        starts: &[GitObjectId],
+    ) -> Result<Vec<(GitObjectId, GitCommitObject)>, RustGitError> {
+        // Commits are ordered by timestamp, then id to keep the walk deterministic.
+        let mut pending = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for start in starts {
+            if !pending.contains_key(start) {
+                let commit = self.read_commit(start)?;
+                queue.push((commit.timestamp, start.to_string()));
+                pending.insert(start.clone(), Some(commit));
+            }
+        }
+
+        let mut commits = Vec::new();
+        while let Some((_, id)) = queue.pop() {
+            let id = GitObjectId::new(id);
+            let commit = pending.get_mut(&id).and_then(Option::take).unwrap();
+            for parent in &commit.parents {
+                if !pending.contains_key(parent) {
+                    let parent_commit = self.read_commit(parent)?;
+                    queue.push((parent_commit.timestamp, parent.to_string()));
+                    pending.insert(parent.clone(), Some(parent_commit));
+                }
+            }
+            commits.push((id, commit));
+        }
+
+        Ok(commits)
+    }
+
+    /// Returns the most recent common ancestor of the two commits, if they share any history.
+    pub(crate) fn merge_base(
+        &self,
+        one: &GitObjectId,
+        other: &GitObjectId,
+    ) -> Result<Option<GitObjectId>, RustGitError> {
+        let other_ancestors = self.ancestors(other)?;
+        Ok(self
+            .walk_commits(std::slice::from_ref(one))?
+            .into_iter()
+            .map(|(id, _)| id)
+            .find(|id| other_ancestors.contains(id)))
+    }
+}
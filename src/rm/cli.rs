@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Args;
 
 #[derive(Args, Debug)]
@@ -38,4 +40,15 @@ pub(crate) struct RmArgs {
     /// git rm normally outputs one line (in the form of an rm command) for each file removed. This option suppresses that output.
     #[arg(short, long)]
     pub quiet: bool,
+
+    /// Pathspec is passed in <file> instead of commandline args. If <file> is exactly - then standard input is used. Pathspec elements
+    /// are separated by LF or CR/LF. Pathspec elements can be quoted as explained for the configuration variable core.quotePath (see
+    /// git-config(1)). See also --pathspec-file-nul and global --literal-pathspecs.
+    #[arg(long, value_name="file", conflicts_with="files")]
+    pub pathspec_from_file: Option<PathBuf>,
+
+    /// Only meaningful with --pathspec-from-file. Pathspec elements are separated with NUL character and all other characters are
+    /// taken literally (including newlines and quotes).
+    #[arg(long, requires="pathspec_from_file")]
+    pub pathspec_file_nul: bool,
 }
@@ -1,20 +1,28 @@
 
-use std::path::Path;
+use std::collections::HashSet;
 
-use crate::{command::GitCommand, repo::RepoState, RustGitError};
+use crate::{
+    command::GitCommand,
+    index::GitIndexStageFlag,
+    pathspec::{read_pathspec_from_file, Pathspec, PathspecMatch, PathspecOptions},
+    repo::RepoState,
+    RustGitError,
+};
 
 use super::cli::RmArgs;
 
 pub(crate) struct RmCommand {
     args: RmArgs,
+    pathspec_options: PathspecOptions,
 
     // TODO: add base args
 }
 
 impl RmCommand {
-    pub fn new(args: RmArgs) -> RmCommand {
+    pub fn new(args: RmArgs, pathspec_options: PathspecOptions) -> RmCommand {
         RmCommand {
-            args
+            args,
+            pathspec_options,
         }
     }
 }
@@ -22,14 +30,36 @@ impl RmCommand {
 impl GitCommand for RmCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError>
     {
-        if self.args.files.is_empty() {
+        let elements = match &self.args.pathspec_from_file {
+            Some(file) => read_pathspec_from_file(file, self.args.pathspec_file_nul)?,
+            None => self.args.files.clone(),
+        };
+        if elements.is_empty() {
             return Err(RustGitError::new("No pathspec was given. Which files should I remove?"));
         }
 
         let mut repo = repo_state.try_get()?;
-
-        // TODO: refresh index
-        // https://github.com/git/git/blob/master/read-cache.c#L1527
+        repo.lock_index()?;
+
+        let mut pathspec = Pathspec::parse(&repo, &elements, self.pathspec_options)?;
+        let file_repo_paths = repo
+            .index
+            .iter_entries()
+            .map(|entry| entry.path_name.as_string())
+            .filter(|path| pathspec.matches(path).is_some())
+            .collect::<HashSet<_>>();
+
+        if !self.args.recursive {
+            if let Some((item, _)) = pathspec
+                .seen()
+                .find(|(_, seen)| *seen == Some(PathspecMatch::Recursively))
+            {
+                return Err(RustGitError::new(format!(
+                    "fatal: not removing '{}' recursively without -r",
+                    item.original
+                )));
+            }
+        }
 
         // If not forced, the file, the index and the HEAD (if exists)
         // must match; but the file can already been removed, since
@@ -39,16 +69,33 @@ impl GitCommand for RmCommand {
         //  
         // Further, if HEAD commit exists, "diff-index --cached" must
         // report no changes unless forced.
-        if !self.args.force {
-            // TODO: add check for local modifications here, after adding support for commits
+        if !self.args.force && !self.args.cached {
+            // TODO: add check for changes staged since HEAD here
             // https://github.com/git/git/blob/master/builtin/rm.c#L99
-            ()
-        }
+            let mut modified_paths = Vec::new();
+            for entry in repo.index.iter_entries() {
+                if entry.flags.stage != GitIndexStageFlag::RegularFileNoConflict
+                    || !file_repo_paths.contains(&entry.path_name.as_string()) {
+                    continue;
+                }
+                if let Some(metadata) = repo.worktree_metadata(&entry.path_name)? {
+                    if repo.is_entry_modified(entry, &metadata)? {
+                        modified_paths.push(entry.path_name.as_string());
+                    }
+                }
+            }
 
-        let mut file_repo_paths = Vec::new();
-        
-        for file in self.args.files.iter() {
-            file_repo_paths.push(repo.path_to_git_repo_path(Path::new(file))?.as_string());
+            if !modified_paths.is_empty() {
+                let header = if modified_paths.len() == 1 {
+                    "the following file has local modifications:"
+                } else {
+                    "the following files have local modifications:"
+                };
+                let paths: String = modified_paths.iter().map(|path| format!("    {path}\n")).collect();
+                return Err(RustGitError::new(format!(
+                    "error: {header}\n{paths}(use --cached to keep the file, or -f to force removal)\n"
+                )));
+            }
         }
 
         // First remove the names from the index: we won't commit
@@ -0,0 +1,59 @@
+use clap::Args;
+
+use crate::color::ColorWhen;
+
+#[derive(Args, Debug)]
+#[command(about = "Show various types of objects")]
+#[command(long_about = "
+Shows one or more objects (blobs, trees, tags and commits).
+
+For commits it shows the log message and textual diff. It also presents the merge commit in a special format as
+produced by git diff-tree --cc.
+
+For tags, it shows the tag message and the referenced objects.
+
+For trees, it shows the names (equivalent to git ls-tree with --name-only).
+
+For plain blobs, it shows the plain contents.
+")]
+pub(crate) struct ShowArgs {
+    /// The names of objects to show (defaults to HEAD). For a more complete list of ways to spell object names,
+    /// see "SPECIFYING REVISIONS" section in gitrevisions(7).
+    #[arg(value_name = "object")]
+    pub objects: Vec<String>,
+
+    /// This is a shorthand for "--pretty=oneline --abbrev-commit" used together.
+    #[arg(long)]
+    pub oneline: bool,
+
+    /// Show the notes (see git-notes(1)) that annotate the commit, when showing the commit log message. With an
+    /// optional <ref> argument, use the ref to find the notes to display, instead of the default notes ref(s).
+    #[arg(
+        long,
+        value_name = "ref",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        overrides_with = "no_notes"
+    )]
+    pub notes: Vec<String>,
+
+    /// Do not show notes.
+    #[arg(long, overrides_with = "notes")]
+    pub no_notes: bool,
+
+    /// Show colored output. The value must be always (the default when omitted), never, or auto.
+    #[arg(
+        long,
+        value_name = "when",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always",
+        overrides_with = "no_color"
+    )]
+    pub color: Option<ColorWhen>,
+
+    /// Turn off colored output, even when the configuration file gives the default to color output.
+    #[arg(long, overrides_with = "color")]
+    pub no_color: bool,
+}
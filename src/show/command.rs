@@ -0,0 +1,120 @@
+use crate::{
+    color::{ColorWhen, DiffColor, Palette},
+    command::GitCommand,
+    date::{format_timestamp, DateFormat},
+    notes_tree::{load_display_notes, GitNotes},
+    object::{GitObjectId, GitObjectType},
+    pretty::{format_log_entry, log_palette, CommitFormat},
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
+
+use super::cli::ShowArgs;
+
+pub(crate) struct ShowCommand {
+    objects: Vec<String>,
+    format: CommitFormat,
+    notes: Vec<String>,
+    no_notes: bool,
+    color: Option<ColorWhen>,
+}
+
+impl ShowCommand {
+    pub fn new(args: ShowArgs) -> ShowCommand {
+        let objects = if args.objects.is_empty() {
+            vec![String::from("HEAD")]
+        } else {
+            args.objects
+        };
+
+        ShowCommand {
+            objects,
+            format: if args.oneline {
+                CommitFormat::Oneline
+            } else {
+                CommitFormat::Medium
+            },
+            notes: args.notes,
+            no_notes: args.no_notes,
+            color: match args.no_color {
+                true => Some(ColorWhen::Never),
+                false => args.color,
+            },
+        }
+    }
+
+    /// Formats an object the way `git show` does, following tags to the objects they point at.
+    /// The headers of commits, tags and trees are in the commit color of the palette.
+    fn format_object(
+        &self,
+        repo: &GitRepo,
+        name: &str,
+        id: &GitObjectId,
+        notes: &[GitNotes],
+        palette: &Palette<DiffColor>,
+    ) -> Result<String, RustGitError> {
+        match repo.object_type(id)? {
+            GitObjectType::Commit => {
+                let commit = repo.read_commit(id)?;
+                format_log_entry(repo, id, &commit, self.format, notes, palette)
+            }
+            GitObjectType::Tag => {
+                let tag = repo.read_tag_object(id)?;
+                let mut formatted = format!(
+                    "{}\nTagger: {} <{}>\nDate:   {}\n\n{}",
+                    palette.paint(DiffColor::Commit, &format!("tag {}", tag.tag_name)),
+                    tag.tagger_name,
+                    tag.tagger_email,
+                    format_timestamp(tag.timestamp, DateFormat::Default),
+                    tag.message.trim_end()
+                );
+                formatted.push_str("\n\n");
+                formatted.push_str(&self.format_object(
+                    repo,
+                    &tag.object_id.to_string(),
+                    &tag.object_id,
+                    notes,
+                    palette,
+                )?);
+                Ok(formatted)
+            }
+            GitObjectType::Tree => {
+                let mut formatted = format!(
+                    "{}\n\n",
+                    palette.paint(DiffColor::Commit, &format!("tree {name}"))
+                );
+                for entry in repo.read_tree(id)?.entries {
+                    let suffix = if entry.entry_type == "tree" { "/" } else { "" };
+                    formatted.push_str(&format!("{}{suffix}\n", entry.name));
+                }
+                Ok(formatted)
+            }
+            GitObjectType::Blob => GitNotes::read_note(repo, id),
+        }
+    }
+}
+
+impl GitCommand for ShowCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+        let notes = load_display_notes(&repo, &self.notes, self.no_notes)?;
+        let palette = log_palette(&repo.config, self.color)?;
+
+        for (i, name) in self.objects.iter().enumerate() {
+            let id = repo.resolve_revision(name)?;
+            if i > 0 && self.format == CommitFormat::Medium {
+                println!();
+            }
+            print!(
+                "{}",
+                self.format_object(&repo, name, &id, &notes, &palette)?
+            );
+        }
+
+        Ok(())
+    }
+
+    fn pages_by_default(&self) -> bool {
+        true
+    }
+}
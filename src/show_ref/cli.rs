@@ -0,0 +1,63 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "List references in a local repository")]
+#[command(long_about = "
+Displays references available in a local repository along with the associated commit IDs. Results can be
+filtered using a pattern and tags can be dereferenced into object IDs. Additionally, it can be used to test
+whether a particular ref exists.
+
+By default, shows the tags, heads, and remote refs.
+
+The --exists form can be used to check for the existence of a single references. This form does not verify
+whether the reference resolves to an actual object.
+
+Use of this utility is encouraged in favor of directly accessing files under the .git directory.
+")]
+pub(crate) struct ShowRefArgs {
+    /// Show references matching one or more patterns. Patterns are matched from the end of the full name, and
+    /// only complete parts are matched, e.g. master would match refs/heads/master, refs/remotes/origin/master,
+    /// refs/tags/jedi/master but not refs/heads/mymaster or refs/remotes/master/jedi.
+    #[clap(value_name("pattern"))]
+    pub patterns: Vec<String>,
+
+    /// Show the HEAD reference, even if it would normally be filtered out.
+    #[arg(long)]
+    pub head: bool,
+
+    /// Limit to local branches. Can be combined with --tags.
+    #[arg(long, alias = "branches")]
+    pub heads: bool,
+
+    /// Limit to local tags. Can be combined with --heads.
+    #[arg(long)]
+    pub tags: bool,
+
+    /// Dereference tags into object IDs as well. They will be shown with ^{} appended.
+    #[arg(short, long)]
+    pub dereference: bool,
+
+    /// Only show the OID, not the reference name. When combined with --dereference, the dereferenced tag will
+    /// still be shown after the OID.
+    #[arg(short = 's', long = "hash", value_name = "n", num_args = 0..=1, require_equals = true, default_missing_value = "40")]
+    pub hash: Option<usize>,
+
+    /// Enable stricter reference checking by requiring an exact ref path. Aside from returning an error code of
+    /// 1, it will also print an error message if --quiet was not specified.
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Check whether the given reference exists. Returns an exit code of 0 if it does, 2 if it is missing, and
+    /// 1 in case looking up the reference failed with an error other than the reference being missing.
+    #[arg(long)]
+    pub exists: bool,
+
+    /// Abbreviate the object name. When using --hash, you do not have to say --hash --abbrev; --hash=n would
+    /// do.
+    #[arg(long, value_name = "n", num_args = 0..=1, require_equals = true, default_missing_value = "7")]
+    pub abbrev: Option<usize>,
+
+    /// Do not print any results to stdout. Can be used with --verify to silently check if a reference exists.
+    #[arg(short, long)]
+    pub quiet: bool,
+}
@@ -0,0 +1,230 @@
+use crate::{
+    command::GitCommand,
+    object::{GitObjectId, GitObjectType},
+    refs::GitRef,
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
+
+use super::cli::ShowRefArgs;
+
+// The abbreviated length can't be shorter than this:
+// https://github.com/git/git/blob/master/builtin/show-ref.c#L313
+const MIN_ABBREV_LEN: usize = 4;
+
+pub(crate) struct ShowRefOutput {
+    dereference: bool,
+    hash_only: bool,
+    abbrev: Option<usize>,
+    quiet: bool,
+}
+
+pub(crate) struct ListRefs {
+    patterns: Vec<String>,
+    head: bool,
+    heads: bool,
+    tags: bool,
+    output: ShowRefOutput,
+}
+
+pub(crate) struct VerifyRefs {
+    ref_names: Vec<String>,
+    output: ShowRefOutput,
+}
+
+pub(crate) struct RefExists {
+    ref_name: String,
+}
+
+pub(crate) enum ShowRefCommand {
+    ListRefs(ListRefs),
+    VerifyRefs(VerifyRefs),
+    RefExists(RefExists),
+}
+
+impl ShowRefCommand {
+    pub fn new(args: ShowRefArgs) -> Result<ShowRefCommand, RustGitError> {
+        if args.verify && args.exists {
+            return Err(RustGitError::new(
+                "fatal: options '--verify' and '--exists' cannot be used together",
+            ));
+        }
+
+        let output = ShowRefOutput {
+            dereference: args.dereference,
+            hash_only: args.hash.is_some(),
+            abbrev: args.abbrev.or(args.hash.filter(|len| *len != 40)),
+            quiet: args.quiet,
+        };
+
+        if args.exists {
+            return match args.patterns.as_slice() {
+                [ref_name] => Ok(ShowRefCommand::RefExists(RefExists {
+                    ref_name: ref_name.clone(),
+                })),
+                _ => Err(RustGitError::new(
+                    "fatal: --exists requires exactly one reference",
+                )),
+            };
+        }
+
+        if args.verify {
+            if args.patterns.is_empty() {
+                return Err(RustGitError::new("fatal: --verify requires a reference"));
+            }
+            return Ok(ShowRefCommand::VerifyRefs(VerifyRefs {
+                ref_names: args.patterns,
+                output,
+            }));
+        }
+
+        Ok(ShowRefCommand::ListRefs(ListRefs {
+            patterns: args.patterns,
+            head: args.head,
+            heads: args.heads,
+            tags: args.tags,
+            output,
+        }))
+    }
+}
+
+/// Returns true if the pattern matches the end of the ref name on a path component boundary.
+fn matches_pattern(ref_name: &str, pattern: &str) -> bool {
+    match ref_name.strip_suffix(pattern) {
+        Some(prefix) => prefix.is_empty() || prefix.ends_with('/'),
+        None => false,
+    }
+}
+
+impl ShowRefOutput {
+    fn format_id(&self, id: &GitObjectId) -> String {
+        let id = id.to_string();
+        match self.abbrev {
+            Some(len) => id[..len.clamp(MIN_ABBREV_LEN, id.len())].to_string(),
+            None => id,
+        }
+    }
+
+    fn show(&self, repo: &GitRepo, git_ref: &GitRef) -> Result<(), RustGitError> {
+        if self.quiet {
+            return Ok(());
+        }
+
+        if self.hash_only {
+            println!("{}", self.format_id(&git_ref.id));
+        } else {
+            println!("{} {}", self.format_id(&git_ref.id), git_ref.name);
+        }
+
+        if self.dereference && repo.object_type(&git_ref.id)? == GitObjectType::Tag {
+            let (peeled_id, _) = repo.peel(&git_ref.id)?;
+            if self.hash_only {
+                println!("{}", self.format_id(&peeled_id));
+            } else {
+                println!("{} {}^{{}}", self.format_id(&peeled_id), git_ref.name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GitCommand for ShowRefCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+
+        match self {
+            ShowRefCommand::ListRefs(list_cmd) => {
+                let mut refs = Vec::new();
+                if list_cmd.head {
+                    if let Some(id) = repo.refs.resolve_ref("HEAD")? {
+                        refs.push(GitRef {
+                            name: String::from("HEAD"),
+                            id,
+                        });
+                    }
+                }
+                refs.extend(repo.refs.list_refs()?);
+
+                let mut found = false;
+                for git_ref in refs {
+                    let is_head = git_ref.name == "HEAD";
+                    if !is_head && (list_cmd.heads || list_cmd.tags) {
+                        let allowed = (list_cmd.heads && git_ref.name.starts_with("refs/heads/"))
+                            || (list_cmd.tags && git_ref.name.starts_with("refs/tags/"));
+                        if !allowed {
+                            continue;
+                        }
+                    }
+
+                    if !is_head
+                        && !list_cmd.patterns.is_empty()
+                        && !list_cmd
+                            .patterns
+                            .iter()
+                            .any(|pattern| matches_pattern(&git_ref.name, pattern))
+                    {
+                        continue;
+                    }
+
+                    found = true;
+                    list_cmd.output.show(&repo, &git_ref)?;
+                }
+
+                if !found {
+                    return Err(RustGitError::new(""));
+                }
+            }
+            ShowRefCommand::VerifyRefs(verify_cmd) => {
+                for ref_name in &verify_cmd.ref_names {
+                    let id = if ref_name == "HEAD" || ref_name.starts_with("refs/") {
+                        repo.refs.resolve_ref(ref_name)?
+                    } else {
+                        None
+                    };
+
+                    match id {
+                        Some(id) => verify_cmd.output.show(
+                            &repo,
+                            &GitRef {
+                                name: ref_name.clone(),
+                                id,
+                            },
+                        )?,
+                        None if verify_cmd.output.quiet => return Err(RustGitError::new("")),
+                        None => {
+                            return Err(RustGitError::new(format!(
+                                "fatal: '{ref_name}' - not a valid ref"
+                            )))
+                        }
+                    }
+                }
+            }
+            ShowRefCommand::RefExists(exists_cmd) => {
+                // Missing refs exit with 2, to tell them apart from failing to look them up.
+                if !repo.refs.ref_exists(&exists_cmd.ref_name)? {
+                    return Err(
+                        RustGitError::new("error: reference does not exist").with_exit_code(2)
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_patterns_on_component_boundaries() {
+        assert!(matches_pattern("refs/heads/master", "master"));
+        assert!(matches_pattern("refs/remotes/origin/master", "master"));
+        assert!(matches_pattern("refs/heads/master", "heads/master"));
+        assert!(matches_pattern("refs/heads/master", "refs/heads/master"));
+        assert!(!matches_pattern("refs/heads/mymaster", "master"));
+        assert!(!matches_pattern("refs/remotes/master/jedi", "master"));
+    }
+}
@@ -0,0 +1,110 @@
+use clap::{Args, ValueEnum};
+
+use crate::{
+    color::ColorWhen,
+    diffcore_rename::parse_rename_score,
+    wt_status::{IgnoredMode, UntrackedFilesMode},
+};
+
+/// The versions of the porcelain format.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum PorcelainVersion {
+    V1,
+    V2,
+}
+
+#[derive(Args, Debug)]
+#[command(about = "Show the working tree status")]
+#[command(long_about = "
+Displays paths that have differences between the index file and the current HEAD commit, paths that have differences
+between the working tree and the index file, and paths in the working tree that are not tracked by Git (and are not
+ignored by gitignore(5)). The first are what you would commit by running git commit; the second and third are what you
+could commit by running git add before running git commit.
+")]
+pub(crate) struct StatusArgs {
+    /// Give the output in the short-format.
+    #[arg(short, long)]
+    pub short: bool,
+
+    /// Show the branch and tracking info even in short-format.
+    #[arg(short, long)]
+    pub branch: bool,
+
+    /// Give the output in an easy-to-parse format for scripts. This is similar to the short output, but will remain
+    /// stable across Git versions and regardless of user configuration. The version parameter is used to specify the
+    /// format version. This is optional and defaults to the original version v1 format.
+    #[arg(
+        long,
+        value_name = "version",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "v1"
+    )]
+    pub porcelain: Option<PorcelainVersion>,
+
+    /// Give the output in the long-format. This is the default.
+    #[arg(long)]
+    pub long: bool,
+
+    /// Terminate entries with NUL, instead of LF. This implies the --porcelain=v1 output format if no other format is
+    /// given.
+    #[arg(short = 'z')]
+    pub nul_terminated: bool,
+
+    /// Show untracked files. The mode parameter is used to specify the handling of untracked files. It is optional: it
+    /// defaults to all.
+    #[arg(
+        short = 'u',
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        default_missing_value = "all"
+    )]
+    pub untracked_files: Option<UntrackedFilesMode>,
+
+    /// Show ignored files as well. The mode parameter is used to specify the handling of ignored files. It is optional:
+    /// it defaults to traditional.
+    #[arg(
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "traditional"
+    )]
+    pub ignored: Option<IgnoredMode>,
+
+    /// Turn off rename detection regardless of user configuration.
+    #[arg(long, overrides_with = "find_renames")]
+    pub no_renames: bool,
+
+    /// Turn on rename detection regardless of user configuration, optionally setting the similarity threshold. If
+    /// specified, n must be given with an equals sign, e.g. --find-renames=90%.
+    #[arg(
+        long,
+        value_name = "n",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_parser = parse_rename_score,
+        overrides_with = "no_renames"
+    )]
+    pub find_renames: Option<u32>,
+
+    /// Show colored output. The value must be always (the default when omitted), never, or auto.
+    #[arg(
+        long,
+        value_name = "when",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always",
+        overrides_with = "no_color"
+    )]
+    pub color: Option<ColorWhen>,
+
+    /// Turn off colored output, even when the configuration file gives the default to color output.
+    #[arg(long, overrides_with = "color")]
+    pub no_color: bool,
+
+    /// Limits the paths shown to those matching the pathspec.
+    pub pathspec: Vec<String>,
+}
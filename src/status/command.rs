@@ -0,0 +1,700 @@
+use crate::{
+    color::{ColorWhen, Palette, StatusColor},
+    command::GitCommand,
+    config::{CopiesKeyword, RenamesConfig},
+    diffcore::GitChangeType,
+    diffcore_rename::{RenameOptions, DEFAULT_RENAME_LIMIT, DEFAULT_RENAME_SCORE},
+    index::GitIndexMode,
+    object::GitObjectId,
+    pathspec::{Pathspec, PathspecOptions},
+    ref_filter::{shorten_ref_name, AheadBehind},
+    refs::GitHead,
+    repo::{GitRepo, RepoState},
+    wt_status::{
+        GitFileVersion, GitStatusChange, GitUnmergedPath, GitWtStatus, IgnoredMode,
+        UntrackedFilesMode,
+    },
+    RustGitError,
+};
+
+use super::cli::{PorcelainVersion, StatusArgs};
+
+const ABBREV_LEN: usize = 7;
+
+/// The width labels of changes are padded to in the long format.
+const CHANGE_LABEL_WIDTH: usize = 12;
+const UNMERGED_LABEL_WIDTH: usize = 17;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StatusFormat {
+    Long,
+    Short,
+    Porcelain(PorcelainVersion),
+}
+
+pub(crate) struct StatusCommand {
+    args: StatusArgs,
+    format: StatusFormat,
+    pathspec_options: PathspecOptions,
+}
+
+impl StatusCommand {
+    pub fn new(
+        args: StatusArgs,
+        pathspec_options: PathspecOptions,
+    ) -> Result<StatusCommand, RustGitError> {
+        let format = match (args.porcelain, args.short, args.long) {
+            (Some(_), _, true) | (_, true, true) => return Err(RustGitError::new(
+                "fatal: options '--long' and '--short' or '--porcelain' cannot be used together",
+            )),
+            (Some(version), _, _) => StatusFormat::Porcelain(version),
+            (None, true, _) => StatusFormat::Short,
+            (None, false, true) if args.nul_terminated => {
+                return Err(RustGitError::new(
+                    "fatal: options '--long' and '-z' cannot be used together",
+                ))
+            }
+            (None, false, true) => StatusFormat::Long,
+            (None, false, false) if args.nul_terminated => {
+                StatusFormat::Porcelain(PorcelainVersion::V1)
+            }
+            (None, false, false) => StatusFormat::Long,
+        };
+
+        Ok(StatusCommand {
+            args,
+            format,
+            pathspec_options,
+        })
+    }
+
+    fn eol(&self) -> char {
+        if self.args.nul_terminated {
+            '\0'
+        } else {
+            '\n'
+        }
+    }
+
+    /// Returns how staged renames are detected, if they are. The command line overrides
+    /// status.renames, which overrides diff.renames, and renames are detected by default.
+    fn rename_options(&self, repo: &GitRepo) -> Option<RenameOptions> {
+        let config = &repo.config;
+        let renames = config
+            .status
+            .renames
+            .or(config.diff.renames)
+            .unwrap_or(RenamesConfig::Enabled(true));
+        if self.args.no_renames
+            || (self.args.find_renames.is_none() && renames == RenamesConfig::Enabled(false))
+        {
+            return None;
+        }
+        Some(RenameOptions {
+            copies: renames == RenamesConfig::Copies(CopiesKeyword::Copies),
+            min_score: self.args.find_renames.unwrap_or(DEFAULT_RENAME_SCORE),
+            limit: config
+                .status
+                .renamelimit
+                .or(config.diff.renamelimit)
+                .unwrap_or(DEFAULT_RENAME_LIMIT),
+            ..RenameOptions::default()
+        })
+    }
+}
+
+fn abbrev(id: &GitObjectId) -> String {
+    id.to_string()[..ABBREV_LEN].to_string()
+}
+
+fn zero_id() -> GitObjectId {
+    GitObjectId::new("0".repeat(40))
+}
+
+/// Returns the path, relative to the root of the repo, relative to the provided directory
+/// instead, which is itself relative to the root.
+fn relative_path(path: &str, dir: &str) -> String {
+    let dir_components = dir
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>();
+    let mut rest = path;
+    let mut parents = 0;
+    for (idx, component) in dir_components.iter().enumerate() {
+        match rest
+            .strip_prefix(component)
+            .and_then(|rest| rest.strip_prefix('/'))
+        {
+            Some(stripped) => rest = stripped,
+            None => {
+                parents = dir_components.len() - idx;
+                break;
+            }
+        }
+    }
+    let relative = format!("{}{rest}", "../".repeat(parents));
+    if relative.is_empty() {
+        String::from("./")
+    } else {
+        relative
+    }
+}
+
+/// The branch HEAD is on and how it compares to its upstream.
+struct BranchStatus {
+    head: Option<GitHead>,
+    /// The full name of the upstream ref, and how far the branch is ahead and behind it. The
+    /// counts are missing when the upstream ref is gone.
+    upstream: Option<(String, Option<AheadBehind>)>,
+}
+
+impl BranchStatus {
+    fn read(repo: &GitRepo) -> Result<BranchStatus, RustGitError> {
+        let head = repo.read_head()?;
+        let upstream_ref = head
+            .as_ref()
+            .and_then(|head| head.branch())
+            .and_then(|branch| branch.strip_prefix("refs/heads/"))
+            .and_then(|branch| repo.config.branch.get(branch))
+            .and_then(|config| config.upstream_ref());
+        let upstream = match upstream_ref {
+            Some(upstream_ref) => {
+                let tracking = match (
+                    head.as_ref().and_then(|head| head.id()),
+                    repo.refs.resolve_ref(&upstream_ref)?,
+                ) {
+                    (Some(local), Some(upstream_id)) => {
+                        Some(repo.ahead_behind(local, &upstream_id)?)
+                    }
+                    (None, Some(_)) => Some(AheadBehind {
+                        ahead: 0,
+                        behind: 0,
+                    }),
+                    (_, None) => None,
+                };
+                Some((upstream_ref, tracking))
+            }
+            None => None,
+        };
+        Ok(BranchStatus { head, upstream })
+    }
+
+    fn branch_name(&self) -> Option<&str> {
+        self.head
+            .as_ref()
+            .and_then(|head| head.branch())
+            .map(shorten_ref_name)
+    }
+
+    fn is_unborn(&self) -> bool {
+        self.head.as_ref().is_none_or(|head| head.id().is_none())
+    }
+
+    /// Returns the header of the short format, e.g. `main...origin/main [ahead 1]`, where the
+    /// branches and the counts of commits are colored.
+    fn short_header(&self, palette: &Palette<StatusColor>) -> String {
+        let Some(branch) = self.branch_name() else {
+            return palette.paint(StatusColor::NoBranch, "HEAD (no branch)");
+        };
+        let text = |text: &str| palette.paint(StatusColor::Header, text);
+        let local = |text: &str| palette.paint(StatusColor::LocalBranch, text);
+        let remote = |text: &str| palette.paint(StatusColor::RemoteBranch, text);
+        let mut header = if self.is_unborn() {
+            text("No commits yet on ")
+        } else {
+            String::new()
+        };
+        header.push_str(&local(branch));
+        if let Some((upstream, tracking)) = &self.upstream {
+            header.push_str(&text("..."));
+            header.push_str(&remote(shorten_ref_name(upstream)));
+            let counts = match tracking {
+                None => text("gone"),
+                Some(AheadBehind {
+                    ahead: 0,
+                    behind: 0,
+                }) => return header,
+                Some(AheadBehind { ahead, behind: 0 }) => {
+                    format!("{}{}", text("ahead "), local(&ahead.to_string()))
+                }
+                Some(AheadBehind { ahead: 0, behind }) => {
+                    format!("{}{}", text("behind "), remote(&behind.to_string()))
+                }
+                Some(AheadBehind { ahead, behind }) => format!(
+                    "{}{}{}{}",
+                    text("ahead "),
+                    local(&ahead.to_string()),
+                    text(", behind "),
+                    remote(&behind.to_string())
+                ),
+            };
+            header.push_str(&format!("{}{counts}{}", text(" ["), text("]")));
+        }
+        header
+    }
+
+    /// Returns the lines of the long format describing how the branch compares to its upstream.
+    fn tracking_info(&self) -> Option<String> {
+        let (upstream, tracking) = self.upstream.as_ref()?;
+        let upstream = shorten_ref_name(upstream);
+        let commits = |count: usize| if count == 1 { "commit" } else { "commits" };
+        Some(match tracking {
+            None => format!(
+                "Your branch is based on '{upstream}', but the upstream is gone.\n  (use \"git branch --unset-upstream\" to fixup)\n"
+            ),
+            Some(AheadBehind {
+                ahead: 0,
+                behind: 0,
+            }) => format!("Your branch is up to date with '{upstream}'.\n"),
+            Some(AheadBehind { ahead, behind: 0 }) => format!(
+                "Your branch is ahead of '{upstream}' by {ahead} {}.\n  (use \"git push\" to publish your local commits)\n",
+                commits(*ahead)
+            ),
+            Some(AheadBehind { ahead: 0, behind }) => format!(
+                "Your branch is behind '{upstream}' by {behind} {}, and can be fast-forwarded.\n  (use \"git pull\" to update your local branch)\n",
+                commits(*behind)
+            ),
+            Some(AheadBehind { ahead, behind }) => format!(
+                "Your branch and '{upstream}' have diverged,\nand have {ahead} and {behind} different commits each, respectively.\n  (use \"git pull\" if you want to integrate the remote branch with yours)\n"
+            ),
+        })
+    }
+}
+
+/// A path with changes or conflicts, which formats other than the long one show in path order.
+enum StatusEntry<'a> {
+    Change(&'a GitStatusChange),
+    Unmerged(&'a GitUnmergedPath),
+}
+
+impl StatusEntry<'_> {
+    fn path(&self) -> &str {
+        match self {
+            StatusEntry::Change(change) => &change.path,
+            StatusEntry::Unmerged(unmerged) => &unmerged.path,
+        }
+    }
+}
+
+fn sorted_entries(status: &GitWtStatus) -> Vec<StatusEntry<'_>> {
+    let mut entries = status
+        .changes
+        .iter()
+        .map(StatusEntry::Change)
+        .chain(status.unmerged.iter().map(StatusEntry::Unmerged))
+        .collect::<Vec<_>>();
+    entries.sort_by(|one, other| one.path().cmp(other.path()));
+    entries
+}
+
+fn change_letter(change: Option<GitChangeType>, unchanged: char) -> char {
+    change.map_or(unchanged, |change| change.letter())
+}
+
+fn format_mode(mode: Option<&GitIndexMode>) -> String {
+    format!("{:06o}", mode.map_or(0, GitIndexMode::as_octal))
+}
+
+fn format_version(version: Option<&GitFileVersion>) -> (String, GitObjectId) {
+    (
+        format_mode(version.map(|(mode, _)| mode)),
+        version.map_or_else(zero_id, |(_, id)| id.clone()),
+    )
+}
+
+impl StatusCommand {
+    fn display_path(&self, repo: &GitRepo, path: &str) -> String {
+        match self.format {
+            StatusFormat::Porcelain(_) => String::from(path),
+            _ => relative_path(path, &repo.working_dir.to_string_lossy()),
+        }
+    }
+
+    /// Prints the short format, which porcelain v1 shares without colors.
+    fn print_short(
+        &self,
+        repo: &GitRepo,
+        branch: &BranchStatus,
+        status: &GitWtStatus,
+        palette: &Palette<StatusColor>,
+    ) {
+        let eol = self.eol();
+        let mut out = String::new();
+        if self.args.branch {
+            out.push_str(&format!(
+                "{}{}{eol}",
+                palette.paint(StatusColor::Header, "## "),
+                branch.short_header(palette)
+            ));
+        }
+
+        for entry in sorted_entries(status) {
+            match entry {
+                StatusEntry::Unmerged(unmerged) => out.push_str(&format!(
+                    "{} {}{eol}",
+                    palette.paint(StatusColor::Unmerged, unmerged.code()),
+                    self.display_path(repo, &unmerged.path)
+                )),
+                StatusEntry::Change(change) => {
+                    let letter = |slot, change: Option<GitChangeType>| match change {
+                        Some(change) => palette.paint(slot, &change.letter().to_string()),
+                        None => String::from(" "),
+                    };
+                    let code = format!(
+                        "{}{}",
+                        letter(StatusColor::Added, change.staged),
+                        letter(StatusColor::Changed, change.unstaged)
+                    );
+                    let path = self.display_path(repo, &change.path);
+                    match &change.rename_source {
+                        Some((source, _)) if self.args.nul_terminated => out.push_str(&format!(
+                            "{code} {path}{eol}{}{eol}",
+                            self.display_path(repo, source)
+                        )),
+                        Some((source, _)) => out.push_str(&format!(
+                            "{code} {} -> {path}{eol}",
+                            self.display_path(repo, source)
+                        )),
+                        None => out.push_str(&format!("{code} {path}{eol}")),
+                    }
+                }
+            }
+        }
+        for path in &status.untracked {
+            out.push_str(&format!(
+                "{} {}{eol}",
+                palette.paint(StatusColor::Untracked, "??"),
+                self.display_path(repo, path)
+            ));
+        }
+        for path in &status.ignored {
+            out.push_str(&format!(
+                "{} {}{eol}",
+                palette.paint(StatusColor::Ignored, "!!"),
+                self.display_path(repo, path)
+            ));
+        }
+        print!("{out}");
+    }
+
+    fn print_porcelain_v2(&self, branch: &BranchStatus, status: &GitWtStatus) {
+        let eol = self.eol();
+        let mut out = String::new();
+        if self.args.branch {
+            let oid = match branch.head.as_ref().and_then(|head| head.id()) {
+                Some(id) => id.to_string(),
+                None => String::from("(initial)"),
+            };
+            out.push_str(&format!("# branch.oid {oid}{eol}"));
+            out.push_str(&format!(
+                "# branch.head {}{eol}",
+                branch.branch_name().unwrap_or("(detached)")
+            ));
+            if let Some((upstream, tracking)) = &branch.upstream {
+                out.push_str(&format!(
+                    "# branch.upstream {}{eol}",
+                    shorten_ref_name(upstream)
+                ));
+                if let Some(AheadBehind { ahead, behind }) = tracking {
+                    out.push_str(&format!("# branch.ab +{ahead} -{behind}{eol}"));
+                }
+            }
+        }
+
+        for entry in sorted_entries(status) {
+            match entry {
+                StatusEntry::Change(change) => {
+                    let code = format!(
+                        "{}{}",
+                        change_letter(change.staged, '.'),
+                        change_letter(change.unstaged, '.')
+                    );
+                    let (head_mode, head_id) = format_version(change.head.as_ref());
+                    let (index_mode, index_id) = format_version(change.index.as_ref());
+                    let worktree_mode = format_mode(change.worktree_mode.as_ref());
+                    let fields = format!(
+                        "{code} N... {head_mode} {index_mode} {worktree_mode} {head_id} {index_id}"
+                    );
+                    match &change.rename_source {
+                        Some((source, score)) => {
+                            let separator = if self.args.nul_terminated { '\0' } else { '\t' };
+                            out.push_str(&format!(
+                                "2 {fields} {}{score} {}{separator}{source}{eol}",
+                                change_letter(change.staged, 'R'),
+                                change.path
+                            ))
+                        }
+                        None => out.push_str(&format!("1 {fields} {}{eol}", change.path)),
+                    }
+                }
+                StatusEntry::Unmerged(unmerged) => {
+                    let [base, ours, theirs] = unmerged
+                        .stages
+                        .each_ref()
+                        .map(|stage| format_version(stage.as_ref()));
+                    out.push_str(&format!(
+                        "u {} N... {} {} {} {} {} {} {} {}{eol}",
+                        unmerged.code(),
+                        base.0,
+                        ours.0,
+                        theirs.0,
+                        format_mode(unmerged.worktree_mode.as_ref()),
+                        base.1,
+                        ours.1,
+                        theirs.1,
+                        unmerged.path
+                    ));
+                }
+            }
+        }
+        for path in &status.untracked {
+            out.push_str(&format!("? {path}{eol}"));
+        }
+        for path in &status.ignored {
+            out.push_str(&format!("! {path}{eol}"));
+        }
+        print!("{out}");
+    }
+
+    fn print_long(
+        &self,
+        repo: &GitRepo,
+        branch: &BranchStatus,
+        status: &GitWtStatus,
+        untracked_mode: UntrackedFilesMode,
+        palette: &Palette<StatusColor>,
+    ) {
+        // Headers and hints are colored line by line.
+        let header = |text: &str| {
+            text.lines()
+                .map(|line| format!("{}\n", palette.paint(StatusColor::Header, line)))
+                .collect::<String>()
+        };
+        let mut out = String::new();
+        match (&branch.head, branch.branch_name()) {
+            (_, Some(name)) => out.push_str(&format!(
+                "{}{}\n",
+                palette.paint(StatusColor::Header, "On branch "),
+                palette.paint(StatusColor::Branch, name)
+            )),
+            (Some(GitHead::Detached(id)), None) => out.push_str(&format!(
+                "{}{}\n",
+                palette.paint(StatusColor::NoBranch, "HEAD detached at "),
+                palette.paint(StatusColor::Branch, &abbrev(id))
+            )),
+            _ => out.push_str(&format!(
+                "{}\n",
+                palette.paint(StatusColor::NoBranch, "Not currently on any branch.")
+            )),
+        }
+        if let Some(tracking_info) = branch.tracking_info() {
+            out.push_str(&header(&tracking_info));
+            out.push('\n');
+        }
+        if branch.is_unborn() {
+            out.push_str(&format!("\n{}\n", header("No commits yet")));
+        }
+
+        if !status.unmerged.is_empty() {
+            out.push_str(&header("Unmerged paths:"));
+            if branch.is_unborn() {
+                out.push_str(&header("  (use \"git rm --cached <file>...\" to unstage)"));
+            } else {
+                out.push_str(&header(
+                    "  (use \"git restore --staged <file>...\" to unstage)",
+                ));
+            }
+            let codes = status.unmerged.iter().map(|unmerged| unmerged.code());
+            let both_deleted = codes.clone().any(|code| code == "DD");
+            let deleted_modified = codes.clone().any(|code| code == "UD" || code == "DU");
+            let not_deleted = codes.clone().any(|code| code != "DD");
+            if deleted_modified || (both_deleted && not_deleted) {
+                out.push_str(&header(
+                    "  (use \"git add/rm <file>...\" as appropriate to mark resolution)",
+                ));
+            } else if both_deleted {
+                out.push_str(&header("  (use \"git rm <file>...\" to mark resolution)"));
+            } else {
+                out.push_str(&header("  (use \"git add <file>...\" to mark resolution)"));
+            }
+            for unmerged in &status.unmerged {
+                let line = format!(
+                    "{:<UNMERGED_LABEL_WIDTH$}{}",
+                    unmerged.description(),
+                    self.display_path(repo, &unmerged.path)
+                );
+                out.push_str(&format!(
+                    "\t{}\n",
+                    palette.paint(StatusColor::Unmerged, &line)
+                ));
+            }
+            out.push('\n');
+        }
+
+        if status.has_staged_changes() {
+            out.push_str(&header("Changes to be committed:"));
+            if branch.is_unborn() {
+                out.push_str(&header("  (use \"git rm --cached <file>...\" to unstage)"));
+            } else {
+                out.push_str(&header(
+                    "  (use \"git restore --staged <file>...\" to unstage)",
+                ));
+            }
+            for change in &status.changes {
+                let Some(staged) = change.staged else {
+                    continue;
+                };
+                let label = match staged {
+                    GitChangeType::Added => "new file:",
+                    GitChangeType::Deleted => "deleted:",
+                    GitChangeType::Modified => "modified:",
+                    GitChangeType::TypeChanged => "typechange:",
+                    GitChangeType::Renamed => "renamed:",
+                    GitChangeType::Copied => "copied:",
+                    GitChangeType::Unmerged => "unmerged:",
+                };
+                let path = match &change.rename_source {
+                    Some((source, _)) => format!(
+                        "{} -> {}",
+                        self.display_path(repo, source),
+                        self.display_path(repo, &change.path)
+                    ),
+                    None => self.display_path(repo, &change.path),
+                };
+                let line = format!("{label:<CHANGE_LABEL_WIDTH$}{path}");
+                out.push_str(&format!("\t{}\n", palette.paint(StatusColor::Added, &line)));
+            }
+            out.push('\n');
+        }
+
+        if status.has_unstaged_changes() {
+            out.push_str(&header("Changes not staged for commit:"));
+            let has_deleted = status
+                .changes
+                .iter()
+                .any(|change| change.unstaged == Some(GitChangeType::Deleted));
+            if has_deleted {
+                out.push_str(&header(
+                    "  (use \"git add/rm <file>...\" to update what will be committed)",
+                ));
+            } else {
+                out.push_str(&header(
+                    "  (use \"git add <file>...\" to update what will be committed)",
+                ));
+            }
+            out.push_str(&header(
+                "  (use \"git restore <file>...\" to discard changes in working directory)",
+            ));
+            for change in &status.changes {
+                let label = match change.unstaged {
+                    Some(GitChangeType::Added) => "new file:",
+                    Some(GitChangeType::Deleted) => "deleted:",
+                    Some(GitChangeType::TypeChanged) => "typechange:",
+                    Some(_) => "modified:",
+                    None => continue,
+                };
+                let line = format!(
+                    "{label:<CHANGE_LABEL_WIDTH$}{}",
+                    self.display_path(repo, &change.path)
+                );
+                out.push_str(&format!(
+                    "\t{}\n",
+                    palette.paint(StatusColor::Changed, &line)
+                ));
+            }
+            out.push('\n');
+        }
+
+        for (title, hint, paths, slot) in [
+            (
+                "Untracked files:",
+                "  (use \"git add <file>...\" to include in what will be committed)",
+                &status.untracked,
+                StatusColor::Untracked,
+            ),
+            (
+                "Ignored files:",
+                "  (use \"git add -f <file>...\" to include in what will be committed)",
+                &status.ignored,
+                StatusColor::Ignored,
+            ),
+        ] {
+            if paths.is_empty() {
+                continue;
+            }
+            out.push_str(&header(title));
+            out.push_str(&header(hint));
+            for path in paths {
+                out.push_str(&format!(
+                    "\t{}\n",
+                    palette.paint(slot, &self.display_path(repo, path))
+                ));
+            }
+            out.push('\n');
+        }
+
+        let committable = status.has_staged_changes();
+        let worktree_dirty = status.has_unstaged_changes() || !status.unmerged.is_empty();
+        if untracked_mode == UntrackedFilesMode::No && committable {
+            out.push_str("Untracked files not listed (use -u option to show untracked files)\n");
+        } else if !committable {
+            out.push_str(if worktree_dirty {
+                "no changes added to commit (use \"git add\" and/or \"git commit -a\")\n"
+            } else if !status.untracked.is_empty() {
+                "nothing added to commit but untracked files present (use \"git add\" to track)\n"
+            } else if branch.is_unborn() {
+                "nothing to commit (create/copy files and use \"git add\" to track)\n"
+            } else if untracked_mode == UntrackedFilesMode::No {
+                "nothing to commit (use -u to show untracked files)\n"
+            } else {
+                "nothing to commit, working tree clean\n"
+            });
+        }
+        print!("{out}");
+    }
+}
+
+impl GitCommand for StatusCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let mut repo = repo_state.try_get()?;
+
+        let mut pathspec = Pathspec::parse(&repo, &self.args.pathspec, self.pathspec_options)?;
+        let untracked_mode = self
+            .args
+            .untracked_files
+            .unwrap_or(UntrackedFilesMode::Normal);
+        let ignored_mode = self.args.ignored.unwrap_or(IgnoredMode::No);
+        let status = repo.wt_status(
+            &mut pathspec,
+            untracked_mode,
+            ignored_mode,
+            self.rename_options(&repo).as_ref(),
+        )?;
+        let branch = BranchStatus::read(&repo)?;
+
+        // Porcelain formats are never colored.
+        let config = &repo.config;
+        let when = match self.format {
+            StatusFormat::Porcelain(_) => ColorWhen::Never,
+            _ if self.args.no_color => ColorWhen::Never,
+            _ => self
+                .args
+                .color
+                .unwrap_or_else(|| config.color.when(&config.color.status)),
+        };
+        let palette = Palette::new(when, config.color.status.slots())?;
+
+        match self.format {
+            StatusFormat::Long => {
+                self.print_long(&repo, &branch, &status, untracked_mode, &palette)
+            }
+            StatusFormat::Short | StatusFormat::Porcelain(PorcelainVersion::V1) => {
+                self.print_short(&repo, &branch, &status, &palette)
+            }
+            StatusFormat::Porcelain(PorcelainVersion::V2) => {
+                self.print_porcelain_v2(&branch, &status)
+            }
+        }
+        Ok(())
+    }
+}
@@ -58,8 +58,10 @@ impl GitCommand for SymbolicRefCommand {
                         if read_cmd.quiet {
                             return Err(RustGitError::new(""));
                         } else {
-                            println!("HEAD is in detatched state");
-                            return Ok(());
+                            return Err(RustGitError::new(format!(
+                                "fatal: ref {} is not a symbolic ref",
+                                read_cmd.ref_name
+                            )));
                         }
                     }
 
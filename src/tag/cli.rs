@@ -1,5 +1,3 @@
-use std::path::PathBuf;
-
 use clap::Args;
 
 #[derive(Args, Debug)]
@@ -30,13 +28,27 @@ default.
 ")]
 pub(crate) struct TagArgs {
     /// The name of the tag to create, delete, or describe. The new tag name must pass all checks defined by git-
-    /// check-ref-format(1). Some of these checks may restrict the characters allowed in a tag name.
+    /// check-ref-format(1). Some of these checks may restrict the characters allowed in a tag name. When
+    /// listing, the names are patterns to filter tags by.
     #[clap(value_name("tagname"))]
-    pub tag_name: Option<String>,
+    pub names: Vec<String>,
+
+    /// Make an unsigned, annotated tag object
+    #[arg(short, long)]
+    pub annotate: bool,
+
+    /// Make a cryptographically signed tag, using the default signing key. The default behavior of tag GPG-
+    /// signing is controlled by tag.gpgSign configuration variable if it exists, or disabled otherwise.
+    #[arg(short, long, overrides_with = "no_sign")]
+    pub sign: bool,
+
+    /// Override tag.gpgSign configuration variable that is set to force each and every tag to be signed.
+    #[arg(long, overrides_with = "sign")]
+    pub no_sign: bool,
 
-    /// The object that the new tag will refer to, usually a commit. Defaults to HEAD.
-    #[clap(value_name("object"))]
-    pub object: Option<String>,
+    /// Make a cryptographically signed tag using the given key.
+    #[arg(short = 'u', long, value_name = "key-id")]
+    pub local_user: Option<String>,
 
     /// Replace an existing tag with the given name (instead of failing)
     #[arg(short, long)]
@@ -46,8 +58,64 @@ pub(crate) struct TagArgs {
     #[arg(short, long)]
     pub delete: bool,
 
+    /// Verify the cryptographic signature of the given tags.
+    #[arg(short, long)]
+    pub verify: bool,
+
     /// Use the given tag message (instead of prompting). If multiple -m options are given, their values are
     /// concatenated as separate paragraphs. Implies -a if none of -a, -s, or -u <keyid> is given.
     #[arg(short, long, value_name = "msg")]
-    pub message: Option<String>,
+    pub message: Vec<String>,
+
+    /// Take the tag message from the given file. Use - to read the message from the standard input. Implies -a
+    /// if none of -a, -s, or -u <keyid> is given.
+    #[arg(short = 'F', long, value_name = "file")]
+    pub file: Option<String>,
+
+    /// List tags. With optional <pattern>..., e.g. git tag --list 'v-*', list only the tags that match the
+    /// pattern(s).
+    #[arg(short, long)]
+    pub list: bool,
+
+    /// <num> specifies how many lines from the annotation, if any, are printed when using -l. Implies --list.
+    ///
+    /// The default is not to print any annotation lines. If no number is given to -n, only the first line is
+    /// printed. If the tag is not annotated, the commit message is displayed instead.
+    #[arg(short = 'n', value_name = "num", num_args = 0..=1, default_missing_value = "1")]
+    pub lines: Option<usize>,
+
+    /// Sort based on the key given. Prefix - to sort in descending order of the value. You may use the
+    /// --sort=<key> option multiple times, in which case the last key becomes the primary key. Also supports
+    /// "version:refname" or "v:refname" (tag names are treated as versions).
+    #[arg(long, value_name = "key")]
+    pub sort: Vec<String>,
+
+    /// Only list tags which contain the specified commit (HEAD if not specified). Implies --list.
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub contains: Vec<String>,
+
+    /// Only list tags which don't contain the specified commit (HEAD if not specified). Implies --list.
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub no_contains: Vec<String>,
+
+    /// Only list tags whose commits are reachable from the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub merged: Option<String>,
+
+    /// Only list tags whose commits are not reachable from the specified commit (HEAD if not specified).
+    #[arg(long, value_name = "commit", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub no_merged: Option<String>,
+
+    /// Only list tags of the given object (HEAD if not specified). Implies --list.
+    #[arg(long, value_name = "object", num_args = 0..=1, default_missing_value = "HEAD")]
+    pub points_at: Vec<String>,
+
+    /// A string that interpolates %(fieldname) from a tag ref being shown and the object it points at. The
+    /// format is the same as that of git-for-each-ref(1). When unspecified, defaults to %(refname:strip=2).
+    #[arg(long, value_name = "format")]
+    pub format: Option<String>,
+
+    /// Sorting and filtering tags are case insensitive.
+    #[arg(short, long)]
+    pub ignore_case: bool,
 }
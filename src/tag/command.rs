@@ -1,52 +1,229 @@
-use crate::{command::GitCommand, object::GitObjectId, repo::RepoState, RustGitError};
+use std::{fs, io::Read};
+
+use crate::{
+    command::GitCommand,
+    gpg::resolve_signing_key,
+    object::{GitObjectId, GitObjectType},
+    ref_filter::{sort_details, sort_refs, PatternMatch, RefFilter, RefFormat, SortKey},
+    refs::is_valid_ref_name,
+    repo::{GitRepo, RepoState},
+    RustGitError,
+};
 
 use super::cli::TagArgs;
 
+const TAGS_PREFIX: &str = "refs/tags/";
+const DEFAULT_FORMAT: &str = "%(refname:strip=2)";
+
+// Tag names are padded to this width when listing annotation lines with -n, as in C Git:
+// https://github.com/git/git/blob/master/builtin/tag.c#L50
+const ANNOTATION_NAME_WIDTH: usize = 15;
+
 pub(crate) struct CreateTag {
     tag_name: String,
-    object_id: Option<GitObjectId>,
+    object: Option<String>,
     force: bool,
+    annotate: bool,
     message: Option<String>,
+    // The key to sign with, where an empty key means the default key.
+    signing_key: Option<String>,
+    no_sign: bool,
 }
 
-pub(crate) struct DeleteTag {
-    tag_name: String,
+pub(crate) struct DeleteTags {
+    tag_names: Vec<String>,
+}
+
+pub(crate) struct VerifyTags {
+    tag_names: Vec<String>,
+}
+
+pub(crate) struct ListTags {
+    patterns: Vec<String>,
+    lines: Option<usize>,
+    sort_keys: Vec<SortKey>,
+    format: RefFormat,
+    contains: Vec<String>,
+    no_contains: Vec<String>,
+    merged: Option<String>,
+    no_merged: Option<String>,
+    points_at: Vec<String>,
+    ignore_case: bool,
 }
 
 pub(crate) enum TagCommand {
-    ListTags(),
+    ListTags(ListTags),
     CreateTag(CreateTag),
-    DeleteTag(DeleteTag),
+    DeleteTags(DeleteTags),
+    VerifyTags(VerifyTags),
+}
+
+fn abbrev(id: &GitObjectId) -> String {
+    id.to_string()[..7].to_string()
+}
+
+fn read_message_file(file: &str) -> Result<String, RustGitError> {
+    let message = if file == "-" {
+        let mut message = String::new();
+        std::io::stdin().read_to_string(&mut message)?;
+        message
+    } else {
+        fs::read_to_string(file)
+            .map_err(|_| RustGitError::new(format!("fatal: could not open or read '{file}'")))?
+    };
+
+    Ok(message.trim_end().to_string())
 }
 
 impl TagCommand {
     pub fn new(args: TagArgs) -> Result<TagCommand, RustGitError> {
-        match (args.tag_name, args.object) {
-            (Some(tag_name), Some(object_id)) => {
-                let object_id = GitObjectId::new(object_id);
-
-                Ok(TagCommand::CreateTag(CreateTag {
-                    tag_name,
-                    object_id: Some(object_id),
-                    force: args.force,
-                    message: args.message,
-                }))
+        if args.delete {
+            if args.names.is_empty() {
+                return Err(RustGitError::new("fatal: tag name required"));
             }
-            (Some(tag_name), None) => {
-                if args.delete {
-                    Ok(TagCommand::DeleteTag(DeleteTag { tag_name }))
-                } else {
-                    Ok(TagCommand::CreateTag(CreateTag {
-                        tag_name,
-                        object_id: None,
-                        force: args.force,
-                        message: args.message,
-                    }))
-                }
+            return Ok(TagCommand::DeleteTags(DeleteTags {
+                tag_names: args.names,
+            }));
+        }
+
+        if args.verify {
+            if args.names.is_empty() {
+                return Err(RustGitError::new("fatal: tag name required"));
+            }
+            return Ok(TagCommand::VerifyTags(VerifyTags {
+                tag_names: args.names,
+            }));
+        }
+
+        let list = args.list
+            || args.lines.is_some()
+            || !args.contains.is_empty()
+            || !args.no_contains.is_empty()
+            || args.merged.is_some()
+            || args.no_merged.is_some()
+            || !args.points_at.is_empty()
+            || args.names.is_empty();
+
+        if list {
+            if args.lines.is_some() && args.format.is_some() {
+                return Err(RustGitError::new(
+                    "fatal: options '--format' and '-n' cannot be used together",
+                ));
+            }
+
+            let sort_keys = if args.sort.is_empty() {
+                vec!["refname".parse()?]
+            } else {
+                args.sort
+                    .iter()
+                    .map(|key| key.parse())
+                    .collect::<Result<Vec<SortKey>, RustGitError>>()?
+            };
+
+            let format = match (&args.format, args.lines) {
+                (Some(format), _) => format.parse()?,
+                (None, Some(lines)) => format!("%(contents:lines={lines})").parse()?,
+                (None, None) => DEFAULT_FORMAT.parse()?,
+            };
+
+            return Ok(TagCommand::ListTags(ListTags {
+                patterns: args.names,
+                lines: args.lines,
+                sort_keys,
+                format,
+                contains: args.contains,
+                no_contains: args.no_contains,
+                merged: args.merged,
+                no_merged: args.no_merged,
+                points_at: args.points_at,
+                ignore_case: args.ignore_case,
+            }));
+        }
+
+        let message = match (args.message.is_empty(), &args.file) {
+            (true, None) => None,
+            (false, None) => Some(args.message.join("\n\n")),
+            (true, Some(file)) => Some(read_message_file(file)?),
+            (false, Some(_)) => {
+                return Err(RustGitError::new(
+                    "fatal: only one -F or -m option is allowed.",
+                ))
+            }
+        };
+
+        let signing_key = match (args.local_user, args.sign) {
+            (Some(key), _) => Some(key),
+            (None, true) => Some(String::new()),
+            (None, false) => None,
+        };
+
+        let mut names = args.names.into_iter();
+        match (names.next(), names.next(), names.next()) {
+            (Some(tag_name), object, None) => Ok(TagCommand::CreateTag(CreateTag {
+                tag_name,
+                object,
+                force: args.force,
+                annotate: args.annotate,
+                message,
+                signing_key,
+                no_sign: args.no_sign,
+            })),
+            _ => Err(RustGitError::new("fatal: too many arguments")),
+        }
+    }
+}
+
+impl ListTags {
+    fn execute(&self, repo: &GitRepo) -> Result<(), RustGitError> {
+        let mut filter = RefFilter::new(self.patterns.clone(), PatternMatch::ShortName);
+        filter.ignore_case = self.ignore_case;
+        filter.details = self.format.details().union(sort_details(&self.sort_keys));
+        filter.points_at = self
+            .points_at
+            .iter()
+            .map(|revision| repo.resolve_revision(revision))
+            .collect::<Result<_, _>>()?;
+        filter.merged = self
+            .merged
+            .as_deref()
+            .map(|revision| repo.resolve_commit(revision))
+            .transpose()?;
+        filter.no_merged = self
+            .no_merged
+            .as_deref()
+            .map(|revision| repo.resolve_commit(revision))
+            .transpose()?;
+        filter.contains = self
+            .contains
+            .iter()
+            .map(|revision| repo.resolve_commit(revision))
+            .collect::<Result<_, _>>()?;
+        filter.no_contains = self
+            .no_contains
+            .iter()
+            .map(|revision| repo.resolve_commit(revision))
+            .collect::<Result<_, _>>()?;
+
+        let tag_refs = repo
+            .refs
+            .list_refs()?
+            .into_iter()
+            .filter(|git_ref| git_ref.name.starts_with(TAGS_PREFIX))
+            .collect();
+        let mut items = filter.apply(repo, tag_refs)?;
+        sort_refs(&mut items, &self.sort_keys)?;
+
+        for item in &items {
+            let formatted = self.format.format(item)?;
+            if self.lines.is_some() {
+                let tag_name = item.name.trim_start_matches(TAGS_PREFIX);
+                println!("{tag_name:<ANNOTATION_NAME_WIDTH$} {formatted}");
+            } else {
+                println!("{formatted}");
             }
-            (None, None) => Ok(TagCommand::ListTags()),
-            (None, Some(_)) => Err(RustGitError::new("tag_name required when object provided")),
         }
+
+        Ok(())
     }
 }
 
@@ -56,38 +233,89 @@ impl GitCommand for TagCommand {
 
         match self {
             TagCommand::CreateTag(create_cmd) => {
-                if !create_cmd.force && repo.read_tag(&create_cmd.tag_name)?.is_some() {
+                let tag_name = &create_cmd.tag_name;
+                if !is_valid_ref_name(&format!("{TAGS_PREFIX}{tag_name}")) {
                     return Err(RustGitError::new(format!(
-                        "cannot overwrite existing tag {}",
-                        create_cmd.tag_name
+                        "fatal: '{tag_name}' is not a valid tag name."
                     )));
                 }
 
-                let object_id = if let Some(object_id) = &create_cmd.object_id {
-                    object_id
-                } else {
-                    if let (_, Some(head_ref)) = repo.get_head_ref()? {
-                        &head_ref.clone()
-                    } else {
-                        return Err(RustGitError::new("no HEAD ref"));
-                    }
-                };
+                let existing_id = repo.read_tag(tag_name)?;
+                if !create_cmd.force && existing_id.is_some() {
+                    return Err(RustGitError::new(format!(
+                        "cannot overwrite existing tag {tag_name}"
+                    )));
+                }
 
-                if let Some(message) = &create_cmd.message {
-                    return repo.create_annotated_tag(&create_cmd.tag_name, &object_id, &message);
+                let object = create_cmd.object.as_deref().unwrap_or("HEAD");
+                let object_id = repo.resolve_revision(object).map_err(|_| {
+                    RustGitError::new(format!(
+                        "fatal: Failed to resolve '{object}' as a valid ref."
+                    ))
+                })?;
+
+                let signing_key = resolve_signing_key(
+                    &repo.config,
+                    create_cmd.signing_key.as_deref(),
+                    repo.config.tag.gpgsign && !create_cmd.no_sign,
+                )?;
+
+                let annotated =
+                    create_cmd.annotate || create_cmd.message.is_some() || signing_key.is_some();
+                match (&create_cmd.message, annotated) {
+                    (Some(message), _) => repo.create_annotated_tag(
+                        tag_name,
+                        &object_id,
+                        message,
+                        signing_key.as_deref(),
+                    )?,
+                    // There's no editor to write the message in, so it must be given up front.
+                    (None, true) => return Err(RustGitError::new("fatal: no tag message?")),
+                    (None, false) => repo.create_lightweight_tag(tag_name, &object_id)?,
+                }
+
+                if let Some(existing_id) = existing_id {
+                    if repo.read_tag(tag_name)?.as_ref() != Some(&existing_id) {
+                        println!("Updated tag '{tag_name}' (was {})", abbrev(&existing_id));
+                    }
+                }
+            }
+            TagCommand::ListTags(list_cmd) => list_cmd.execute(&repo)?,
+            TagCommand::DeleteTags(delete_cmd) => {
+                // As in C Git, failing to delete one tag doesn't stop the others from being deleted.
+                let mut errors = Vec::new();
+                for tag_name in &delete_cmd.tag_names {
+                    match repo.read_tag(tag_name)? {
+                        Some(id) => {
+                            repo.delete_tag(tag_name)?;
+                            println!("Deleted tag '{tag_name}' (was {})", abbrev(&id));
+                        }
+                        None => errors.push(format!("error: tag '{tag_name}' not found.")),
+                    }
                 }
 
-                return repo.create_lightweight_tag(&create_cmd.tag_name, &object_id);
+                if !errors.is_empty() {
+                    return Err(RustGitError::new(errors.join("\n")));
+                }
             }
-            TagCommand::ListTags() => {
-                let tags = repo.list_tags()?;
-                for tag in tags {
-                    println!("{}", tag)
+            TagCommand::VerifyTags(verify_cmd) => {
+                let mut all_good = true;
+                for tag_name in &verify_cmd.tag_names {
+                    all_good &=
+                        repo.verify_object_signature(tag_name, GitObjectType::Tag, true, false)?;
+                }
+
+                if !all_good {
+                    return Err(RustGitError::new(""));
                 }
             }
-            TagCommand::DeleteTag(delete_cmd) => repo.delete_tag(&delete_cmd.tag_name)?,
         }
 
         Ok(())
     }
+
+    /// Only listing pages its output.
+    fn pages_by_default(&self) -> bool {
+        matches!(self, TagCommand::ListTags(_))
+    }
 }
@@ -0,0 +1,90 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::ErrorKind,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::RustGitError;
+
+const TEMP_NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const TEMP_NAME_LENGTH: usize = 6;
+
+// Same number of attempts as C Git, one for each possible name of three characters:
+// https://github.com/git/git/blob/master/wrapper.c#L470
+const TEMP_FILE_ATTEMPTS: usize = 62 * 62 * 62;
+
+/// Returns six random characters to end the name of a temporary file with.
+fn random_suffix() -> String {
+    // Every RandomState is seeded differently, which is mixed with the time and process for good
+    // measure, so that names can't be guessed in advance.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos()),
+    );
+    hasher.write_u32(process::id());
+
+    let mut value = hasher.finish();
+    (0..TEMP_NAME_LENGTH)
+        .map(|_| {
+            let c = TEMP_NAME_CHARS[(value % TEMP_NAME_CHARS.len() as u64) as usize] as char;
+            value /= TEMP_NAME_CHARS.len() as u64;
+            c
+        })
+        .collect()
+}
+
+/// Creates a new file with the provided permissions in `dir`, named from the prefix followed by
+/// six random characters, and returns it along with its path. Like C Git's `mks_tempfile_mode`,
+/// the file is only ever created, never opened, and other names are tried while the picked ones
+/// already exist.
+pub(crate) fn create_temp_file(
+    dir: &Path,
+    prefix: &str,
+    mode: u32,
+) -> Result<(File, PathBuf), RustGitError> {
+    for _ in 0..TEMP_FILE_ATTEMPTS {
+        let path = dir.join(format!("{prefix}{}", random_suffix()));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&path)
+        {
+            Ok(file) => return Ok((file, path)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Err(RustGitError::new(format!(
+        "fatal: unable to create temporary file in '{}'",
+        dir.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn should_create_distinct_temp_files() {
+        let (_, first) = create_temp_file(&env::temp_dir(), ".rust_git_test_", 0o600).unwrap();
+        let (_, second) = create_temp_file(&env::temp_dir(), ".rust_git_test_", 0o600).unwrap();
+
+        assert_ne!(first, second);
+        for path in [&first, &second] {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            assert_eq!(name.len(), ".rust_git_test_".len() + TEMP_NAME_LENGTH);
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
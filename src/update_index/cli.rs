@@ -0,0 +1,116 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Register file contents in the working tree to the index")]
+#[command(long_about = "
+Modifies the index. Each file mentioned is updated into the index and any unmerged or needs updating state is
+cleared.
+
+See also git-add(1) for a more user-friendly way to do some of the most common operations on the index.
+
+The way git update-index handles files it is told about can be modified using the various options.
+")]
+pub(crate) struct UpdateIndexArgs {
+    /// If a specified file isn't in the index already then it's added. Default behaviour is to ignore new files.
+    #[arg(long)]
+    pub add: bool,
+
+    /// If a specified file is in the index but is missing then it's removed. Default behavior is to ignore
+    /// removed files.
+    #[arg(long)]
+    pub remove: bool,
+
+    /// Remove the file from the index even when the working directory still has such a file. (Implies
+    /// --remove.)
+    #[arg(long)]
+    pub force_remove: bool,
+
+    /// By default, when a file path exists in the index, git update-index refuses an attempt to add path/file.
+    /// Similarly if a file path/file exists, a file path cannot be added. With --replace flag, existing entries
+    /// that conflict with the entry being added are automatically removed with warning messages.
+    #[arg(long)]
+    pub replace: bool,
+
+    /// Directly insert the specified info into the index. The object doesn't have to exist in the object
+    /// database, and the path doesn't have to exist in the working tree.
+    #[arg(long, value_name = "mode>,<object>,<path")]
+    pub cacheinfo: Vec<String>,
+
+    /// Read index information from stdin.
+    #[arg(long)]
+    pub index_info: bool,
+
+    /// Set the execute permissions on the updated files.
+    #[arg(long, value_name = "(+|-)x", value_parser = ["+x", "-x"], allow_hyphen_values = true)]
+    pub chmod: Option<String>,
+
+    /// When this flag is specified, the object names recorded for the paths are not updated. Instead, this
+    /// option sets the "assume unchanged" bit for the paths. When the "assume unchanged" bit is on, the user
+    /// promises not to change the file and allows Git to assume that the working tree file matches what is
+    /// recorded in the index.
+    #[arg(long, overrides_with = "no_assume_unchanged")]
+    pub assume_unchanged: bool,
+
+    /// Unsets the "assume unchanged" bit for the paths.
+    #[arg(long, overrides_with = "assume_unchanged")]
+    pub no_assume_unchanged: bool,
+
+    /// When one of these flags is specified, the object names recorded for the paths are not updated.
+    /// Instead, these options set the "skip-worktree" bit for the paths.
+    #[arg(long, overrides_with = "no_skip_worktree")]
+    pub skip_worktree: bool,
+
+    /// Unsets the "skip-worktree" bit for the paths.
+    #[arg(long, overrides_with = "skip_worktree")]
+    pub no_skip_worktree: bool,
+
+    /// Do not create objects in the object database for all <file> arguments that follow this flag; just
+    /// insert their object IDs into the index.
+    #[arg(long)]
+    pub info_only: bool,
+
+    /// Instead of taking a list of paths from the command line, read a list of paths from the standard input.
+    /// Paths are separated by LF (i.e. one path per line) by default.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// Only meaningful with --stdin or --index-info; paths are separated with NUL character instead of LF.
+    #[arg(short = 'z')]
+    pub nul_terminated: bool,
+
+    /// Looks at the current index and checks to see if merges or updates are needed by checking stat()
+    /// information.
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// Like --refresh, but checks stat information unconditionally, without regard to the "assume
+    /// unchanged" setting.
+    #[arg(long)]
+    pub really_refresh: bool,
+
+    /// Quiet. If --refresh finds that the index needs an update, the default behavior is to error out. This
+    /// option makes git update-index continue anyway.
+    #[arg(short = 'q')]
+    pub quiet: bool,
+
+    /// If --refresh finds unmerged changes in the index, the default behavior is to error out. This option
+    /// makes git update-index continue anyway.
+    #[arg(long)]
+    pub unmerged: bool,
+
+    /// Ignores missing files during a --refresh
+    #[arg(long)]
+    pub ignore_missing: bool,
+
+    /// Write the resulting index out in the named on-disk format version. Supported versions are 2, 3 and 4.
+    /// The current default version is 2 or 3, depending on whether extra features are used, such as git add
+    /// -N.
+    ///
+    /// Version 4 performs a simple pathname compression that reduces index size by 30%-50% on large
+    /// repositories, which results in faster load time.
+    #[arg(long, value_name = "n")]
+    pub index_version: Option<u32>,
+
+    /// Files to act on.
+    pub files: Vec<String>,
+}
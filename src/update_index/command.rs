@@ -0,0 +1,423 @@
+use std::{
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{
+    command::GitCommand,
+    index::{GitIndexEntry, GitIndexMode, GitIndexStageFlag, GitIndexVersion},
+    object::GitObjectId,
+    repo::{GitRefreshFailure, GitRepo, GitRepoPath, RepoState},
+    RustGitError,
+};
+
+use super::cli::UpdateIndexArgs;
+
+/// An entry to insert directly into the index, read from --cacheinfo or --index-info.
+struct CacheInfo {
+    mode: u32,
+    id: GitObjectId,
+    path: String,
+    stage: GitIndexStageFlag,
+}
+
+impl CacheInfo {
+    /// Parses the `<mode>,<object>,<path>` argument of --cacheinfo.
+    fn parse_argument(argument: &str) -> Option<CacheInfo> {
+        let mut parts = argument.splitn(3, ',');
+        let mode = u32::from_str_radix(parts.next()?, 8).ok()?;
+        let id = parse_object_id(parts.next()?)?;
+        let path = parts.next()?.to_string();
+        Some(CacheInfo {
+            mode,
+            id,
+            path,
+            stage: GitIndexStageFlag::RegularFileNoConflict,
+        })
+    }
+
+    /// Parses a line of --index-info input, which can be in any of these formats:
+    /// - `<mode> SP <object> TAB <path>`
+    /// - `<mode> SP <type> SP <object> TAB <path>`, as printed by ls-tree
+    /// - `<mode> SP <object> SP <stage> TAB <path>`, as printed by ls-files --stage
+    fn parse_index_info(line: &str) -> Option<CacheInfo> {
+        let (info, path) = line.split_once('\t')?;
+        let (mode, info) = info.split_once(' ')?;
+        let mode = u32::from_str_radix(mode, 8).ok()?;
+
+        let fields = info.split(' ').collect::<Vec<_>>();
+        let (id, stage) = match fields.as_slice() {
+            [id] | [_, id] if id.len() == 40 => (*id, 0),
+            [id, stage] => (*id, stage.parse().ok()?),
+            _ => return None,
+        };
+
+        Some(CacheInfo {
+            mode,
+            id: parse_object_id(id)?,
+            path: path.to_string(),
+            stage: GitIndexStageFlag::from_number(stage)?,
+        })
+    }
+}
+
+fn parse_object_id(id: &str) -> Option<GitObjectId> {
+    (id.len() == 40 && id.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| GitObjectId::new(id.to_lowercase()))
+}
+
+/// Returns the value to set a flag of the paths to, if only their flags are updated.
+fn marked_flag(set: bool, unset: bool) -> Option<bool> {
+    match (set, unset) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns whether the provided path, relative to the root of the repo, can be stored in the
+/// index: it must not be absolute, end with a slash, or have ".", ".." or ".git" components.
+fn verify_path(path: &str) -> bool {
+    !path.is_empty()
+        && path
+            .split('/')
+            .all(|component| !matches!(component, "" | "." | ".." | ".git"))
+}
+
+pub(crate) struct UpdateIndexCommand {
+    args: UpdateIndexArgs,
+    index_version: Option<GitIndexVersion>,
+    cacheinfo: Vec<CacheInfo>,
+}
+
+impl UpdateIndexCommand {
+    pub fn new(args: UpdateIndexArgs) -> Result<UpdateIndexCommand, RustGitError> {
+        let index_version = args
+            .index_version
+            .map(|number| {
+                GitIndexVersion::from_number(number).ok_or_else(|| {
+                    RustGitError::new(format!("fatal: index-version {number} not in range: 2..4"))
+                })
+            })
+            .transpose()?;
+
+        let cacheinfo = args
+            .cacheinfo
+            .iter()
+            .map(|argument| {
+                CacheInfo::parse_argument(argument).ok_or_else(|| {
+                    RustGitError::new("error: option 'cacheinfo' expects <mode>,<sha1>,<path>")
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(UpdateIndexCommand {
+            args,
+            index_version,
+            cacheinfo,
+        })
+    }
+
+    /// Inserts an entry which isn't read from the worktree. With `force`, as for --index-info,
+    /// the entry is added even if it's new or conflicts with other entries.
+    fn add_cacheinfo(
+        &self,
+        repo: &mut GitRepo,
+        cacheinfo: &CacheInfo,
+        force: bool,
+    ) -> Result<(), String> {
+        let path = GitRepoPath::deserialize(cacheinfo.path.as_bytes()).unwrap();
+        if !verify_path(&cacheinfo.path) {
+            return Err(format!("error: Invalid path '{}'", cacheinfo.path));
+        }
+
+        let mut entry = GitIndexEntry::from_object(
+            &path,
+            GitIndexMode::from_octal(cacheinfo.mode),
+            cacheinfo.id.clone(),
+            cacheinfo.stage,
+        );
+        entry.flags.assume_valid = self.args.assume_unchanged;
+        self.add_entry(repo, entry, force)
+    }
+
+    /// Adds the provided entry, which is only allowed to be new with --add, and to replace
+    /// entries conflicting with its path with --replace.
+    fn add_entry(
+        &self,
+        repo: &mut GitRepo,
+        entry: GitIndexEntry,
+        force: bool,
+    ) -> Result<(), String> {
+        let path = entry.path_name.clone();
+        let cannot_add = format!(
+            "error: {}: cannot add to the index - missing --add option?",
+            path.as_string()
+        );
+
+        // Resolving an unmerged path doesn't require --add.
+        let exists = repo.index.entry_by_path(&path).is_some();
+        if !exists && !self.args.add && !force {
+            return Err(cannot_add);
+        }
+        if repo.index.has_file_directory_conflict(&path) {
+            if !self.args.replace && !force {
+                return Err(format!(
+                    "error: '{}' appears as both a file and as a directory\n{cannot_add}",
+                    path.as_string()
+                ));
+            }
+            repo.index.remove_file_directory_conflicts(&path);
+        }
+
+        repo.index.add(entry);
+        Ok(())
+    }
+
+    fn remove_path(&self, repo: &mut GitRepo, path: &GitRepoPath) {
+        repo.index.filter_entries(|entry| entry.path_name != *path);
+    }
+
+    /// Updates the index entry of the provided path from the worktree, following the flags.
+    fn update_path(&self, repo: &mut GitRepo, file: &str) -> Result<(), RustGitError> {
+        let path = repo.path_to_git_repo_path(Path::new(file))?;
+        let stage_0_position = repo
+            .index
+            .entry_position(&path, GitIndexStageFlag::RegularFileNoConflict);
+
+        let assume_unchanged =
+            marked_flag(self.args.assume_unchanged, self.args.no_assume_unchanged);
+        let skip_worktree = marked_flag(self.args.skip_worktree, self.args.no_skip_worktree);
+        if assume_unchanged.is_some() || skip_worktree.is_some() {
+            let Some(position) = stage_0_position else {
+                return Err(RustGitError::new(format!(
+                    "fatal: Unable to mark file {}",
+                    path.as_string()
+                )));
+            };
+            repo.index.update_entry_at(position, |entry| {
+                if let Some(assume_unchanged) = assume_unchanged {
+                    entry.flags.assume_valid = assume_unchanged;
+                }
+                if let Some(skip_worktree) = skip_worktree {
+                    entry.extended_flags.skip_worktree = skip_worktree;
+                }
+            });
+            return Ok(());
+        }
+
+        if self.args.force_remove {
+            self.remove_path(repo, &path);
+            return Ok(());
+        }
+
+        self.update_from_worktree(repo, &path, stage_0_position)
+            .map_err(|message| {
+                RustGitError::new(format!(
+                    "{message}\nfatal: Unable to process path {}",
+                    path.as_string()
+                ))
+            })?;
+
+        if let Some(chmod) = &self.args.chmod {
+            let position = repo
+                .index
+                .entry_position(&path, GitIndexStageFlag::RegularFileNoConflict)
+                .filter(|&position| {
+                    matches!(
+                        repo.index.entry_at(position).mode,
+                        GitIndexMode::RegularFile0644 | GitIndexMode::RegularFile0755
+                    )
+                })
+                .ok_or_else(|| {
+                    RustGitError::new(format!(
+                        "fatal: git update-index: cannot chmod {chmod} '{}'",
+                        path.as_string()
+                    ))
+                })?;
+            repo.index.update_entry_at(position, |entry| {
+                entry.mode = if chmod == "+x" {
+                    GitIndexMode::RegularFile0755
+                } else {
+                    GitIndexMode::RegularFile0644
+                };
+            });
+        }
+
+        Ok(())
+    }
+
+    fn update_from_worktree(
+        &self,
+        repo: &mut GitRepo,
+        path: &GitRepoPath,
+        stage_0_position: Option<usize>,
+    ) -> Result<(), String> {
+        let old_entry = stage_0_position.map(|position| repo.index.entry_at(position));
+
+        // The worktree file of entries which aren't checked out is never looked at, so they can
+        // only be removed.
+        if old_entry.is_some_and(|entry| entry.extended_flags.skip_worktree) {
+            if self.args.remove {
+                self.remove_path(repo, path);
+            }
+            return Ok(());
+        }
+
+        let path_name = path.as_string();
+        let metadata = match repo
+            .worktree_metadata(path)
+            .map_err(|err| err.to_string())?
+        {
+            Some(metadata) => metadata,
+            None if self.args.remove => {
+                self.remove_path(repo, path);
+                return Ok(());
+            }
+            None => {
+                return Err(format!(
+                    "error: {path_name}: does not exist and --remove not passed"
+                ))
+            }
+        };
+        if metadata.is_dir() {
+            return Err(format!(
+                "error: {path_name}: is a directory - add individual files instead"
+            ));
+        }
+
+        if let Some(old_entry) = old_entry {
+            if old_entry.matches_stat(&metadata, &repo.config.core)
+                && !repo.index.is_racy(old_entry)
+            {
+                return Ok(());
+            }
+        }
+        let mode = GitIndexMode::from_worktree(
+            &metadata,
+            old_entry.map(|entry| &entry.mode),
+            &repo.config.core,
+        );
+
+        let id = repo
+            .hash_worktree_file(path, !self.args.info_only)
+            .map_err(|err| err.to_string())?;
+        let mut entry = GitIndexEntry::new(path, &metadata, id);
+        entry.mode = mode;
+        self.add_entry(repo, entry, false)
+    }
+
+    /// Reads the entries to insert from the provided --index-info input. Entries with a mode of
+    /// 0 remove their path instead.
+    fn read_index_info(&self, repo: &mut GitRepo, input: &str) -> Result<(), RustGitError> {
+        for line in self.split_input(input) {
+            let cacheinfo = CacheInfo::parse_index_info(line)
+                .ok_or_else(|| RustGitError::new(format!("fatal: malformed index info {line}")))?;
+            if !verify_path(&cacheinfo.path) {
+                eprintln!("Ignoring path {}", cacheinfo.path);
+                continue;
+            }
+
+            if cacheinfo.mode == 0 {
+                let path = GitRepoPath::deserialize(cacheinfo.path.as_bytes())?;
+                self.remove_path(repo, &path);
+            } else {
+                self.add_cacheinfo(repo, &cacheinfo, true)
+                    .map_err(|message| {
+                        RustGitError::new(format!(
+                            "{message}\nfatal: git update-index: unable to update {}",
+                            cacheinfo.path
+                        ))
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits input read from stdin into records, terminated by NUL with -z and by newlines
+    /// otherwise.
+    fn split_input<'a>(&self, input: &'a str) -> impl Iterator<Item = &'a str> {
+        let terminator = if self.args.nul_terminated { '\0' } else { '\n' };
+        input.split(terminator).filter(|record| !record.is_empty())
+    }
+}
+
+impl GitCommand for UpdateIndexCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let mut repo = repo_state.try_get()?;
+        repo.lock_index()?;
+        let mut index_changed = false;
+        let mut has_errors = false;
+
+        if let Some(index_version) = self.index_version {
+            repo.index.set_version(index_version);
+            index_changed = true;
+        }
+
+        if self.args.refresh || self.args.really_refresh {
+            for failure in repo.refresh_index(self.args.really_refresh, |_| true)? {
+                let message = match failure {
+                    GitRefreshFailure::NeedsMerge(_) if self.args.unmerged => continue,
+                    GitRefreshFailure::Missing(_) if self.args.ignore_missing => continue,
+                    GitRefreshFailure::NeedsMerge(path) => {
+                        format!("{}: needs merge", path.as_string())
+                    }
+                    GitRefreshFailure::NeedsUpdate(path) | GitRefreshFailure::Missing(path) => {
+                        format!("{}: needs update", path.as_string())
+                    }
+                };
+                if !self.args.quiet {
+                    println!("{message}");
+                    has_errors = true;
+                }
+            }
+            index_changed = true;
+        }
+
+        for cacheinfo in &self.cacheinfo {
+            self.add_cacheinfo(&mut repo, cacheinfo, false)
+                .map_err(|message| {
+                    RustGitError::new(format!(
+                        "{message}\nfatal: git update-index: --cacheinfo cannot add {}",
+                        cacheinfo.path
+                    ))
+                })?;
+            index_changed = true;
+        }
+
+        let mut stdin = String::new();
+        if self.args.index_info || self.args.stdin {
+            io::stdin().read_to_string(&mut stdin)?;
+        }
+        if self.args.index_info {
+            self.read_index_info(&mut repo, &stdin)?;
+            index_changed = true;
+        }
+
+        let stdin_files = if self.args.stdin {
+            self.split_input(&stdin).collect()
+        } else {
+            vec![]
+        };
+        for file in self
+            .args
+            .files
+            .iter()
+            .map(String::as_str)
+            .chain(stdin_files)
+        {
+            self.update_path(&mut repo, file)?;
+            index_changed = true;
+        }
+
+        if index_changed {
+            repo.write_index()?;
+        }
+
+        // Like C Git, the refreshed entries are saved even if some needed an update.
+        if has_errors {
+            return Err(RustGitError::new(""));
+        }
+        Ok(())
+    }
+}
@@ -33,4 +33,9 @@ pub(crate) struct UpdateRefArgs {
 
     #[clap(value_name("oldvalue"))]
     pub old_value: Option<String>,
+
+    /// Overwrite <ref> itself, rather than the result of following the symbolic pointers. Updating HEAD with
+    /// this option detaches it from the current branch.
+    #[arg(long)]
+    pub no_deref: bool,
 }
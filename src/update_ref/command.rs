@@ -16,10 +16,15 @@ impl GitCommand for UpdateRefCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
         let repo = repo_state.try_get()?;
 
+        let new_value = repo.resolve_revision(&self.args.new_value).map_err(|_| {
+            RustGitError::new(format!("fatal: {}: not a valid SHA1", self.args.new_value))
+        })?;
+
         repo.update_ref(
             &self.args.git_ref,
-            &self.args.new_value,
+            &new_value.to_string(),
             self.args.old_value.as_deref(),
+            self.args.no_deref,
         )
     }
 }
@@ -0,0 +1,20 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Check the GPG signature of commits")]
+#[command(long_about = "
+Validates the GPG signature created by git commit -S.
+")]
+pub(crate) struct VerifyCommitArgs {
+    /// SHA-1 identifiers of Git commit objects.
+    #[arg(value_name = "commit", required = true)]
+    pub commits: Vec<String>,
+
+    /// Print the raw gpg status output to standard error instead of the normal human-readable output.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Print the contents of the commit object before validating it.
+    #[arg(short, long)]
+    pub verbose: bool,
+}
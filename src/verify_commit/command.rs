@@ -0,0 +1,42 @@
+use crate::{command::GitCommand, object::GitObjectType, repo::RepoState, RustGitError};
+
+use super::cli::VerifyCommitArgs;
+
+pub(crate) struct VerifyCommitCommand {
+    commits: Vec<String>,
+    raw: bool,
+    verbose: bool,
+}
+
+impl VerifyCommitCommand {
+    pub fn new(args: VerifyCommitArgs) -> VerifyCommitCommand {
+        VerifyCommitCommand {
+            commits: args.commits,
+            raw: args.raw,
+            verbose: args.verbose,
+        }
+    }
+}
+
+impl GitCommand for VerifyCommitCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+
+        // Every commit is verified, even once one has failed verification.
+        let mut all_good = true;
+        for commit in &self.commits {
+            all_good &= repo.verify_object_signature(
+                commit,
+                GitObjectType::Commit,
+                self.verbose,
+                self.raw,
+            )?;
+        }
+
+        if !all_good {
+            return Err(RustGitError::new(""));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "Check the GPG signature of tags")]
+#[command(long_about = "
+Validates the gpg signature created by git tag.
+")]
+pub(crate) struct VerifyTagArgs {
+    /// SHA-1 identifiers of Git tag objects.
+    #[arg(value_name = "tag", required = true)]
+    pub tags: Vec<String>,
+
+    /// Print the raw gpg status output to standard error instead of the normal human-readable output.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Print the contents of the tag object before validating it.
+    #[arg(short, long)]
+    pub verbose: bool,
+}
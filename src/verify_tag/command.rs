@@ -0,0 +1,38 @@
+use crate::{command::GitCommand, object::GitObjectType, repo::RepoState, RustGitError};
+
+use super::cli::VerifyTagArgs;
+
+pub(crate) struct VerifyTagCommand {
+    tags: Vec<String>,
+    raw: bool,
+    verbose: bool,
+}
+
+impl VerifyTagCommand {
+    pub fn new(args: VerifyTagArgs) -> VerifyTagCommand {
+        VerifyTagCommand {
+            tags: args.tags,
+            raw: args.raw,
+            verbose: args.verbose,
+        }
+    }
+}
+
+impl GitCommand for VerifyTagCommand {
+    fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
+        let repo = repo_state.try_get()?;
+
+        // Every tag is verified, even once one has failed verification.
+        let mut all_good = true;
+        for tag in &self.tags {
+            all_good &=
+                repo.verify_object_signature(tag, GitObjectType::Tag, self.verbose, self.raw)?;
+        }
+
+        if !all_good {
+            return Err(RustGitError::new(""));
+        }
+
+        Ok(())
+    }
+}
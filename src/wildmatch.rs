@@ -0,0 +1,320 @@
+// Port of C Git's wildmatch, which implements the glob semantics used for
+// ref patterns, pathspecs and ignore rules:
+// https://github.com/git/git/blob/master/wildmatch.c
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct WildmatchFlags {
+    /// When set, wildcards other than `**` do not match a '/'.
+    pub(crate) pathname: bool,
+    /// When set, matching is ASCII case-insensitive.
+    pub(crate) casefold: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum MatchResult {
+    Match,
+    NoMatch,
+    AbortAll,
+    AbortToStarStar,
+}
+
+/// Returns true if the text matches the glob pattern.
+pub(crate) fn wildmatch(pattern: &str, text: &str, flags: WildmatchFlags) -> bool {
+    dowild(pattern.as_bytes(), text.as_bytes(), flags) == MatchResult::Match
+}
+
+fn fold(ch: u8, flags: WildmatchFlags) -> u8 {
+    if flags.casefold {
+        ch.to_ascii_lowercase()
+    } else {
+        ch
+    }
+}
+
+fn matches_class(class: &[u8], ch: u8) -> Option<bool> {
+    let matched = match class {
+        b"alnum" => ch.is_ascii_alphanumeric(),
+        b"alpha" => ch.is_ascii_alphabetic(),
+        b"blank" => ch == b' ' || ch == b'\t',
+        b"cntrl" => ch.is_ascii_control(),
+        b"digit" => ch.is_ascii_digit(),
+        b"graph" => ch.is_ascii_graphic(),
+        b"lower" => ch.is_ascii_lowercase(),
+        b"print" => ch.is_ascii_graphic() || ch == b' ',
+        b"punct" => ch.is_ascii_punctuation(),
+        b"space" => ch.is_ascii_whitespace(),
+        b"upper" => ch.is_ascii_uppercase(),
+        b"xdigit" => ch.is_ascii_hexdigit(),
+        _ => return None,
+    };
+    Some(matched)
+}
+
+fn dowild(pattern: &[u8], text: &[u8], flags: WildmatchFlags) -> MatchResult {
+    let at = |s: &[u8], i: usize| -> u8 { s.get(i).copied().unwrap_or(0) };
+
+    let mut p = 0;
+    let mut t = 0;
+
+    while p < pattern.len() {
+        let mut p_ch = fold(pattern[p], flags);
+        let t_ch = fold(at(text, t), flags);
+
+        if t >= text.len() && p_ch != b'*' {
+            return MatchResult::AbortAll;
+        }
+
+        match p_ch {
+            b'?' => {
+                if flags.pathname && t_ch == b'/' {
+                    return MatchResult::NoMatch;
+                }
+            }
+            b'*' => {
+                let match_slash;
+                p += 1;
+                if at(pattern, p) == b'*' {
+                    let prev_is_boundary = p < 2 || pattern[p - 2] == b'/';
+                    while at(pattern, p) == b'*' {
+                        p += 1;
+                    }
+                    let next = at(pattern, p);
+                    if prev_is_boundary
+                        && (next == 0
+                            || next == b'/'
+                            || (next == b'\\' && at(pattern, p + 1) == b'/'))
+                    {
+                        if next == b'/'
+                            && dowild(&pattern[p + 1..], &text[t..], flags) == MatchResult::Match
+                        {
+                            return MatchResult::Match;
+                        }
+                        match_slash = true;
+                    } else {
+                        match_slash = !flags.pathname;
+                    }
+                } else {
+                    match_slash = !flags.pathname;
+                }
+
+                if p >= pattern.len() {
+                    // Trailing "**" matches everything. Trailing "*" matches only if there
+                    // are no more slash characters.
+                    if !match_slash && text[t..].contains(&b'/') {
+                        return MatchResult::NoMatch;
+                    }
+                    return MatchResult::Match;
+                } else if !match_slash && pattern[p] == b'/' {
+                    // One asterisk followed by a slash matches the next directory.
+                    match text[t..].iter().position(|&b| b == b'/') {
+                        Some(slash) => {
+                            t += slash + 1;
+                            p += 1;
+                            continue;
+                        }
+                        None => return MatchResult::NoMatch,
+                    }
+                }
+
+                while t < text.len() {
+                    match dowild(&pattern[p..], &text[t..], flags) {
+                        MatchResult::NoMatch => {
+                            if !match_slash && text[t] == b'/' {
+                                return MatchResult::AbortToStarStar;
+                            }
+                        }
+                        MatchResult::AbortToStarStar if match_slash => (),
+                        other => return other,
+                    }
+                    t += 1;
+                }
+                return MatchResult::AbortAll;
+            }
+            b'[' => {
+                p += 1;
+                p_ch = at(pattern, p);
+                let negated = p_ch == b'!' || p_ch == b'^';
+                if negated {
+                    p += 1;
+                    p_ch = at(pattern, p);
+                }
+                let mut prev_ch: u8 = 0;
+                let mut matched = false;
+                loop {
+                    if p_ch == 0 {
+                        return MatchResult::AbortAll;
+                    }
+                    if p_ch == b'\\' {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == 0 {
+                            return MatchResult::AbortAll;
+                        }
+                        if t_ch == fold(p_ch, flags) {
+                            matched = true;
+                        }
+                    } else if p_ch == b'-'
+                        && prev_ch != 0
+                        && at(pattern, p + 1) != 0
+                        && at(pattern, p + 1) != b']'
+                    {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == b'\\' {
+                            p += 1;
+                            p_ch = at(pattern, p);
+                            if p_ch == 0 {
+                                return MatchResult::AbortAll;
+                            }
+                        }
+                        let raw_t_ch = at(text, t);
+                        if (prev_ch..=p_ch).contains(&raw_t_ch)
+                            || (flags.casefold
+                                && ((prev_ch..=p_ch).contains(&raw_t_ch.to_ascii_lowercase())
+                                    || (prev_ch..=p_ch).contains(&raw_t_ch.to_ascii_uppercase())))
+                        {
+                            matched = true;
+                        }
+                        p_ch = 0;
+                    } else if p_ch == b'[' && at(pattern, p + 1) == b':' {
+                        let class_start = p + 2;
+                        let mut class_end = class_start;
+                        while class_end < pattern.len() && pattern[class_end] != b']' {
+                            class_end += 1;
+                        }
+                        if class_end >= pattern.len() {
+                            return MatchResult::AbortAll;
+                        }
+                        if class_end == class_start || pattern[class_end - 1] != b':' {
+                            // Didn't find ":]", so treat like a normal set.
+                            if t_ch == b'[' {
+                                matched = true;
+                            }
+                        } else {
+                            let class = &pattern[class_start..class_end - 1];
+                            match matches_class(class, at(text, t)) {
+                                Some(true) => matched = true,
+                                Some(false) => {
+                                    if flags.casefold
+                                        && class == b"upper"
+                                        && at(text, t).is_ascii_lowercase()
+                                    {
+                                        matched = true;
+                                    }
+                                }
+                                None => return MatchResult::AbortAll,
+                            }
+                            p = class_end;
+                            p_ch = 0;
+                        }
+                    } else if t_ch == fold(p_ch, flags) {
+                        matched = true;
+                    }
+
+                    prev_ch = p_ch;
+                    p += 1;
+                    p_ch = at(pattern, p);
+                    if p_ch == b']' {
+                        break;
+                    }
+                }
+                if matched == negated || (flags.pathname && t_ch == b'/') {
+                    return MatchResult::NoMatch;
+                }
+            }
+            _ => {
+                if p_ch == b'\\' {
+                    p += 1;
+                    p_ch = fold(at(pattern, p), flags);
+                }
+                if t_ch != p_ch {
+                    return MatchResult::NoMatch;
+                }
+            }
+        }
+
+        p += 1;
+        t += 1;
+    }
+
+    if t < text.len() {
+        MatchResult::NoMatch
+    } else {
+        MatchResult::Match
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATHNAME: WildmatchFlags = WildmatchFlags {
+        pathname: true,
+        casefold: false,
+    };
+
+    const NONE: WildmatchFlags = WildmatchFlags {
+        pathname: false,
+        casefold: false,
+    };
+
+    #[test]
+    fn should_match_literals_and_basic_wildcards() {
+        assert!(wildmatch("foo", "foo", PATHNAME));
+        assert!(!wildmatch("foo", "bar", PATHNAME));
+        assert!(wildmatch("???", "foo", PATHNAME));
+        assert!(!wildmatch("??", "foo", PATHNAME));
+        assert!(wildmatch("*", "foo", PATHNAME));
+        assert!(wildmatch("f*", "foo", PATHNAME));
+        assert!(!wildmatch("*f", "foo", PATHNAME));
+        assert!(wildmatch("*foo*", "foo", PATHNAME));
+        assert!(wildmatch("\\*", "*", PATHNAME));
+        assert!(!wildmatch("\\*", "foo", PATHNAME));
+    }
+
+    #[test]
+    fn should_respect_slashes_in_pathname_mode() {
+        assert!(!wildmatch("foo*", "foo/bar", PATHNAME));
+        assert!(wildmatch("foo*", "foo/bar", NONE));
+        assert!(wildmatch("foo/*", "foo/bar", PATHNAME));
+        assert!(!wildmatch("foo/*", "foo/bar/baz", PATHNAME));
+        assert!(wildmatch("*/bar", "foo/bar", PATHNAME));
+        assert!(!wildmatch("foo?bar", "foo/bar", PATHNAME));
+    }
+
+    #[test]
+    fn should_match_double_asterisk_across_directories() {
+        assert!(wildmatch("**/foo", "foo", PATHNAME));
+        assert!(wildmatch("**/foo", "a/b/foo", PATHNAME));
+        assert!(wildmatch("foo/**", "foo/a/b", PATHNAME));
+        assert!(wildmatch("a/**/b", "a/b", PATHNAME));
+        assert!(wildmatch("a/**/b", "a/x/y/b", PATHNAME));
+        assert!(!wildmatch("a/**/b", "a/x/y/c", PATHNAME));
+        assert!(wildmatch("**", "a/b/c", PATHNAME));
+    }
+
+    #[test]
+    fn should_match_character_classes() {
+        assert!(wildmatch("[ab]", "a", PATHNAME));
+        assert!(!wildmatch("[ab]", "c", PATHNAME));
+        assert!(wildmatch("[!ab]", "c", PATHNAME));
+        assert!(wildmatch("[^ab]", "c", PATHNAME));
+        assert!(wildmatch("[a-c]", "b", PATHNAME));
+        assert!(!wildmatch("[a-c]", "d", PATHNAME));
+        assert!(wildmatch("[[:digit:]]x", "1x", PATHNAME));
+        assert!(!wildmatch("[[:digit:]]x", "ax", PATHNAME));
+        assert!(!wildmatch("[a/]", "/", PATHNAME));
+    }
+
+    #[test]
+    fn should_match_case_insensitively_with_casefold() {
+        let casefold = WildmatchFlags {
+            pathname: true,
+            casefold: true,
+        };
+        assert!(wildmatch("FOO", "foo", casefold));
+        assert!(wildmatch("f*", "FOO", casefold));
+        assert!(wildmatch("[a-c]x", "BX", casefold));
+        assert!(!wildmatch("FOO", "foo", PATHNAME));
+    }
+}
@@ -12,7 +12,7 @@ impl WriteTreeCommand {
 
 impl GitCommand for WriteTreeCommand {
     fn execute(&self, repo_state: RepoState) -> Result<(), RustGitError> {
-        let repo = repo_state.try_get()?;
+        let mut repo = repo_state.try_get()?;
 
         let object_id = repo.write_index_as_tree()?;
 
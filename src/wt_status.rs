@@ -0,0 +1,424 @@
+// Collects the differences between HEAD, the index and the worktree which `status` reports,
+// based on C Git's implementation:
+// https://github.com/git/git/blob/master/wt-status.c
+
+use std::collections::{BTreeMap, HashSet};
+
+use clap::ValueEnum;
+
+use crate::{
+    diffcore::{same_kind, GitChangeType, GitDiffPair, GitDiffSide},
+    diffcore_rename::RenameOptions,
+    index::{GitIndexMode, GitIndexStageFlag},
+    object::GitObjectId,
+    pathspec::Pathspec,
+    repo::{GitRepo, GitRepoPath},
+    RustGitError,
+};
+
+/// Which untracked files are shown.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum UntrackedFilesMode {
+    /// Show no untracked files.
+    No,
+    /// Show untracked files and directories, without listing the files of untracked directories.
+    Normal,
+    /// Show every untracked file.
+    All,
+}
+
+/// Which ignored files are shown.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum IgnoredMode {
+    /// Show ignored files and directories, unless every untracked file is shown, in which case
+    /// the files of ignored directories are listed.
+    Traditional,
+    /// Show ignored files and directories matching an ignore pattern.
+    Matching,
+    /// Show no ignored files.
+    No,
+}
+
+/// The mode and object of a path in HEAD or the index.
+pub(crate) type GitFileVersion = (GitIndexMode, GitObjectId);
+
+/// The changes of a path which isn't unmerged.
+#[derive(Debug)]
+pub(crate) struct GitStatusChange {
+    /// The path, relative to the root of the repo.
+    pub(crate) path: String,
+    /// The change between HEAD and the index.
+    pub(crate) staged: Option<GitChangeType>,
+    /// The change between the index and the worktree.
+    pub(crate) unstaged: Option<GitChangeType>,
+    pub(crate) head: Option<GitFileVersion>,
+    pub(crate) index: Option<GitFileVersion>,
+    /// The mode of the file in the worktree, if it exists.
+    pub(crate) worktree_mode: Option<GitIndexMode>,
+    /// For staged renames and copies, the path in HEAD and the similarity score of the files.
+    pub(crate) rename_source: Option<(String, u32)>,
+}
+
+/// A path with unmerged entries in the index.
+#[derive(Debug)]
+pub(crate) struct GitUnmergedPath {
+    pub(crate) path: String,
+    /// The versions at the base, ours and theirs stages.
+    pub(crate) stages: [Option<GitFileVersion>; 3],
+    pub(crate) worktree_mode: Option<GitIndexMode>,
+}
+
+impl GitUnmergedPath {
+    /// Returns the two letter code of the conflict, describing which sides added, deleted or
+    /// modified the path.
+    pub(crate) fn code(&self) -> &'static str {
+        match self.stages.each_ref().map(Option::is_some) {
+            [true, false, false] => "DD",
+            [false, true, false] => "AU",
+            [true, true, false] => "UD",
+            [false, false, true] => "UA",
+            [true, false, true] => "DU",
+            [false, true, true] => "AA",
+            _ => "UU",
+        }
+    }
+
+    /// Returns the description of the conflict used by the long format.
+    pub(crate) fn description(&self) -> &'static str {
+        match self.code() {
+            "DD" => "both deleted:",
+            "AU" => "added by us:",
+            "UD" => "deleted by them:",
+            "UA" => "added by them:",
+            "DU" => "deleted by us:",
+            "AA" => "both added:",
+            _ => "both modified:",
+        }
+    }
+}
+
+/// The state of the worktree compared to the index and HEAD.
+#[derive(Debug, Default)]
+pub(crate) struct GitWtStatus {
+    /// The changed paths, sorted by path.
+    pub(crate) changes: Vec<GitStatusChange>,
+    pub(crate) unmerged: Vec<GitUnmergedPath>,
+    /// Untracked paths, with a trailing '/' for directories shown as a whole.
+    pub(crate) untracked: Vec<String>,
+    /// Ignored paths, with a trailing '/' for directories shown as a whole.
+    pub(crate) ignored: Vec<String>,
+}
+
+impl GitWtStatus {
+    pub(crate) fn has_staged_changes(&self) -> bool {
+        self.changes.iter().any(|change| change.staged.is_some())
+    }
+
+    pub(crate) fn has_unstaged_changes(&self) -> bool {
+        self.changes.iter().any(|change| change.unstaged.is_some())
+    }
+}
+
+/// Returns the directories containing the path, outermost first, without trailing slashes.
+fn leading_dirs(path: &str) -> impl Iterator<Item = &str> {
+    path.match_indices('/').map(move |(idx, _)| &path[..idx])
+}
+
+impl GitRepo {
+    /// Pairs staged additions with the staged deletions they were renamed from, or the changed
+    /// files they were copied from, turning them into renames and copies.
+    fn detect_staged_renames(
+        &self,
+        changes: &mut Vec<GitStatusChange>,
+        options: &RenameOptions,
+    ) -> Result<(), RustGitError> {
+        let side = |path: &str, version: &Option<GitFileVersion>| {
+            version.as_ref().map(|(mode, id)| GitDiffSide {
+                path: path.to_string(),
+                mode: mode.as_octal(),
+                id: Some(id.clone()),
+            })
+        };
+        let pairs = changes
+            .iter()
+            .filter(|change| change.staged.is_some())
+            .filter_map(|change| {
+                GitDiffPair::between(
+                    side(&change.path, &change.head),
+                    side(&change.path, &change.index),
+                )
+            })
+            .collect();
+        let pairs = self.detect_renames(pairs, vec![], Some(options))?;
+
+        let remaining = pairs
+            .iter()
+            .map(|pair| pair.path().to_string())
+            .collect::<HashSet<_>>();
+        let mut sources = pairs
+            .into_iter()
+            .filter(|pair| matches!(pair.status, GitChangeType::Renamed | GitChangeType::Copied))
+            .filter_map(|pair| {
+                let path = pair.path().to_string();
+                pair.old.map(|old| (path, (old, pair.status, pair.score)))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        // Renamed files are only shown at their new path.
+        changes.retain(|change| {
+            change.staged != Some(GitChangeType::Deleted) || remaining.contains(&change.path)
+        });
+        for change in changes.iter_mut() {
+            if let Some((source, status, score)) = sources.remove(&change.path) {
+                change.staged = Some(status);
+                change.head = source
+                    .id
+                    .map(|id| (GitIndexMode::from_octal(source.mode), id));
+                change.rename_source = Some((source.path, score));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares HEAD with the index and the index with the worktree for the paths matching the
+    /// pathspec, detecting staged renames if `renames` is set. The stat data of unchanged files
+    /// is refreshed in the index along the way, which is saved if possible.
+    pub(crate) fn wt_status(
+        &mut self,
+        pathspec: &mut Pathspec,
+        untracked_mode: UntrackedFilesMode,
+        ignored_mode: IgnoredMode,
+        renames: Option<&RenameOptions>,
+    ) -> Result<GitWtStatus, RustGitError> {
+        let head_entries = match self.read_head()?.as_ref().and_then(|head| head.id()) {
+            Some(id) => {
+                let tree = self.read_commit(id)?.tree;
+                self.read_tree_entries(&tree, "", GitIndexStageFlag::RegularFileNoConflict)?
+            }
+            None => vec![],
+        };
+
+        self.try_lock_index()?;
+        self.refresh_index(false, |_| true)?;
+        self.write_index_if_able()?;
+
+        let mut status = GitWtStatus::default();
+
+        // The versions of each path in HEAD and the index.
+        let mut versions = BTreeMap::<String, (Option<GitFileVersion>, Option<usize>)>::new();
+        for entry in head_entries {
+            versions.entry(entry.path_name.as_string()).or_default().0 =
+                Some((entry.mode, entry.name));
+        }
+        let mut unmerged = BTreeMap::<String, [Option<GitFileVersion>; 3]>::new();
+        for (idx, entry) in self.index.iter_entries().enumerate() {
+            let path = entry.path_name.as_string();
+            let version = (entry.mode.clone(), entry.name.clone());
+            match entry.flags.stage {
+                GitIndexStageFlag::RegularFileNoConflict => {
+                    versions.entry(path).or_default().1 = Some(idx)
+                }
+                GitIndexStageFlag::Base => unmerged.entry(path).or_default()[0] = Some(version),
+                GitIndexStageFlag::Ours => unmerged.entry(path).or_default()[1] = Some(version),
+                GitIndexStageFlag::Theirs => unmerged.entry(path).or_default()[2] = Some(version),
+            }
+        }
+
+        for (path, stages) in unmerged {
+            if pathspec.matches(&path).is_none() {
+                continue;
+            }
+            versions.remove(&path);
+            let worktree_mode = self
+                .worktree_metadata(&GitRepoPath::deserialize(path.as_bytes())?)?
+                .map(|metadata| GitIndexMode::from_worktree(&metadata, None, &self.config.core));
+            status.unmerged.push(GitUnmergedPath {
+                path,
+                stages,
+                worktree_mode,
+            });
+        }
+
+        for (path, (head, index_position)) in versions {
+            if pathspec.matches(&path).is_none() {
+                continue;
+            }
+            let index_entry = index_position.map(|idx| self.index.entry_at(idx));
+            // Paths added with --intent-to-add aren't in the index yet, only in the worktree.
+            let intent_to_add = index_entry.is_some_and(|entry| entry.extended_flags.intent_to_add);
+            let index = index_entry
+                .filter(|_| !intent_to_add)
+                .map(|entry| (entry.mode.clone(), entry.name.clone()));
+
+            let staged = match (&head, &index) {
+                (None, Some(_)) => Some(GitChangeType::Added),
+                (Some(_), None) => Some(GitChangeType::Deleted),
+                (Some((head_mode, _)), Some((index_mode, _)))
+                    if !same_kind(head_mode.as_octal(), index_mode.as_octal()) =>
+                {
+                    Some(GitChangeType::TypeChanged)
+                }
+                (Some(head), Some(index)) if head != index => Some(GitChangeType::Modified),
+                _ => None,
+            };
+
+            let (unstaged, worktree_mode) = match index_entry {
+                Some(entry) if entry.extended_flags.skip_worktree => {
+                    (None, Some(entry.mode.clone()))
+                }
+                Some(entry) => match self.worktree_metadata(&entry.path_name)? {
+                    None => (Some(GitChangeType::Deleted), None),
+                    Some(metadata) => {
+                        let mode = GitIndexMode::from_worktree(
+                            &metadata,
+                            Some(&entry.mode),
+                            &self.config.core,
+                        );
+                        let unstaged = if intent_to_add {
+                            Some(GitChangeType::Added)
+                        } else if !same_kind(mode.as_octal(), entry.mode.as_octal()) {
+                            Some(GitChangeType::TypeChanged)
+                        } else if self.is_entry_modified(entry, &metadata)? {
+                            Some(GitChangeType::Modified)
+                        } else {
+                            None
+                        };
+                        (unstaged, Some(mode))
+                    }
+                },
+                None => (None, None),
+            };
+
+            if staged.is_some() || unstaged.is_some() {
+                status.changes.push(GitStatusChange {
+                    path,
+                    staged,
+                    unstaged,
+                    head,
+                    index,
+                    worktree_mode,
+                    rename_source: None,
+                });
+            }
+        }
+        if let Some(renames) = renames {
+            self.detect_staged_renames(&mut status.changes, renames)?;
+        }
+
+        if untracked_mode != UntrackedFilesMode::No {
+            self.collect_untracked(&mut status, pathspec, untracked_mode, ignored_mode)?;
+        }
+
+        Ok(status)
+    }
+
+    fn collect_untracked(
+        &self,
+        status: &mut GitWtStatus,
+        pathspec: &mut Pathspec,
+        untracked_mode: UntrackedFilesMode,
+        ignored_mode: IgnoredMode,
+    ) -> Result<(), RustGitError> {
+        let mut ignore = self.standard_ignore()?;
+        let files = self.untracked_files(&mut ignore, ignored_mode != IgnoredMode::No)?;
+        let others = files
+            .others
+            .iter()
+            .map(|path| path.as_string())
+            .filter(|path| pathspec.matches(path).is_some())
+            .collect::<Vec<_>>();
+        let ignored = files
+            .ignored
+            .iter()
+            .map(|path| path.as_string())
+            .filter(|path| pathspec.matches(path).is_some())
+            .collect::<Vec<_>>();
+
+        // Directories without tracked files are shown as a whole, unless every file is shown.
+        let tracked_dirs = self
+            .index
+            .iter_entries()
+            .flat_map(|entry| {
+                let path = entry.path_name.as_string();
+                leading_dirs(&path).map(String::from).collect::<Vec<_>>()
+            })
+            .collect::<HashSet<_>>();
+        let untracked_dirs = others
+            .iter()
+            .flat_map(|path| leading_dirs(path).map(String::from).collect::<Vec<_>>())
+            .collect::<HashSet<_>>();
+
+        for path in &others {
+            let shown = match untracked_mode {
+                UntrackedFilesMode::All => path.clone(),
+                _ => leading_dirs(path)
+                    .find(|dir| !tracked_dirs.contains(*dir))
+                    .map(|dir| format!("{dir}/"))
+                    .unwrap_or_else(|| path.clone()),
+            };
+            if status.untracked.last() != Some(&shown) {
+                status.untracked.push(shown);
+            }
+        }
+
+        for path in &ignored {
+            let collapsed_dir = match ignored_mode {
+                IgnoredMode::Traditional if untracked_mode == UntrackedFilesMode::All => None,
+                // Directories holding only ignored files are shown as a whole.
+                IgnoredMode::Traditional => leading_dirs(path)
+                    .find(|dir| !tracked_dirs.contains(*dir) && !untracked_dirs.contains(*dir)),
+                IgnoredMode::Matching => {
+                    let mut dirs = leading_dirs(path);
+                    let mut ignored_dir = None;
+                    for dir in dirs.by_ref() {
+                        if ignore.is_ignored(dir, true)? {
+                            ignored_dir = Some(dir);
+                            break;
+                        }
+                    }
+                    ignored_dir
+                }
+                IgnoredMode::No => None,
+            };
+            let shown = collapsed_dir
+                .map(|dir| format!("{dir}/"))
+                .unwrap_or_else(|| path.clone());
+            if status.ignored.last() != Some(&shown) {
+                status.ignored.push(shown);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unmerged(stages: [bool; 3]) -> GitUnmergedPath {
+        let version = || {
+            (
+                GitIndexMode::RegularFile0644,
+                GitObjectId::new("a".repeat(40)),
+            )
+        };
+        GitUnmergedPath {
+            path: String::from("a"),
+            stages: stages.map(|present| present.then(version)),
+            worktree_mode: None,
+        }
+    }
+
+    #[test]
+    fn should_describe_conflicts() {
+        assert_eq!(unmerged([true, false, false]).code(), "DD");
+        assert_eq!(unmerged([false, true, false]).code(), "AU");
+        assert_eq!(unmerged([true, true, false]).code(), "UD");
+        assert_eq!(unmerged([false, false, true]).code(), "UA");
+        assert_eq!(unmerged([true, false, true]).code(), "DU");
+        assert_eq!(unmerged([false, true, true]).code(), "AA");
+        assert_eq!(unmerged([true, true, true]).code(), "UU");
+        assert_eq!(unmerged([true, true, true]).description(), "both modified:");
+    }
+}
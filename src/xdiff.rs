@@ -0,0 +1,1381 @@
+// Line diffs between two files, based on C Git's xdiff library:
+// https://github.com/git/git/tree/master/xdiff
+
+use std::{borrow::Cow, collections::HashMap};
+
+use clap::ValueEnum;
+
+/// Lines of a common region are only looked up in the histogram algorithm if they appear at
+/// most this many times, otherwise the region is diffed with Myers' algorithm.
+const HISTOGRAM_MAX_CHAIN_LENGTH: usize = 64;
+
+/// The minimum number of edits after which Myers' algorithm looks for a good enough split
+/// instead of a minimal one, unless a minimal diff is requested.
+const MYERS_MIN_MAX_COST: usize = 256;
+
+/// The number of bytes of a line kept in hunk headers.
+const FUNCTION_NAME_MAX_LEN: usize = 80;
+
+// Weights of the indent heuristic, tuned in C Git against a corpus of human-made diffs:
+// https://github.com/git/git/blob/master/xdiff/xdiffi.c#L430
+const MAX_INDENT: usize = 200;
+const MAX_BLANKS: usize = 20;
+const START_OF_FILE_PENALTY: i32 = 1;
+const END_OF_FILE_PENALTY: i32 = 21;
+const TOTAL_BLANK_WEIGHT: i32 = -30;
+const POST_BLANK_WEIGHT: i32 = 6;
+const RELATIVE_INDENT_PENALTY: i32 = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: i32 = 10;
+const RELATIVE_OUTDENT_PENALTY: i32 = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: i32 = 17;
+const RELATIVE_DEDENT_PENALTY: i32 = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: i32 = 17;
+const INDENT_WEIGHT: i32 = 60;
+const INDENT_HEURISTIC_MAX_SLIDING: usize = 100;
+
+/// The algorithm used to find the lines which changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub(crate) enum DiffAlgorithm {
+    /// The basic greedy diff algorithm.
+    #[default]
+    #[value(alias = "default")]
+    Myers,
+    /// Spend extra time to make sure the smallest possible diff is produced.
+    Minimal,
+    /// Use "patience diff" algorithm when generating patches.
+    Patience,
+    /// This algorithm extends the patience algorithm to "support low-occurrence common elements".
+    Histogram,
+}
+
+/// How lines are compared and how hunks are built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct XdiffOptions {
+    pub(crate) algorithm: DiffAlgorithm,
+    /// Ignore whitespace when comparing lines.
+    pub(crate) ignore_all_space: bool,
+    /// Ignore changes in amount of whitespace, including whitespace at the end of lines.
+    pub(crate) ignore_space_change: bool,
+    /// Ignore changes in whitespace at the end of lines.
+    pub(crate) ignore_space_at_eol: bool,
+    /// Ignore changes whose lines are all blank.
+    pub(crate) ignore_blank_lines: bool,
+    /// The number of unchanged lines shown around changes.
+    pub(crate) context: usize,
+    /// Hunks separated by at most this many lines in addition to their context are merged.
+    pub(crate) interhunk_context: usize,
+    /// Show whole functions as context lines for each change.
+    pub(crate) function_context: bool,
+    /// Shift the boundaries of changes to where they're easiest to read, judging by the
+    /// indentation of the lines around them.
+    pub(crate) indent_heuristic: bool,
+}
+
+impl Default for XdiffOptions {
+    fn default() -> XdiffOptions {
+        XdiffOptions {
+            algorithm: DiffAlgorithm::Myers,
+            ignore_all_space: false,
+            ignore_space_change: false,
+            ignore_space_at_eol: false,
+            ignore_blank_lines: false,
+            context: 3,
+            interhunk_context: 0,
+            function_context: false,
+            indent_heuristic: true,
+        }
+    }
+}
+
+impl XdiffOptions {
+    fn ignores_whitespace(&self) -> bool {
+        self.ignore_all_space || self.ignore_space_change || self.ignore_space_at_eol
+    }
+
+    /// Returns the form of the line which is compared, according to the whitespace options.
+    fn comparable_line<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if self.ignore_all_space {
+            Cow::Owned(line.chars().filter(|c| !c.is_ascii_whitespace()).collect())
+        } else if self.ignore_space_change {
+            let mut normalized = String::with_capacity(line.len());
+            for word in line.split_ascii_whitespace() {
+                if !normalized.is_empty() {
+                    normalized.push(' ');
+                }
+                normalized.push_str(word);
+            }
+            // Whitespace before the first word is only a change in amount of whitespace if
+            // there was some on both sides.
+            if line.starts_with(|c: char| c.is_ascii_whitespace()) && !normalized.is_empty() {
+                normalized.insert(0, ' ');
+            }
+            Cow::Owned(normalized)
+        } else if self.ignore_space_at_eol {
+            Cow::Borrowed(line.trim_end_matches(|c: char| c.is_ascii_whitespace()))
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+
+    fn is_blank_line(&self, line: &str) -> bool {
+        line.trim_ascii().is_empty()
+    }
+}
+
+/// Splits the text into lines, each keeping its line feed. Only the last line may lack one.
+pub(crate) fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Returns whether the line can start a function, used for hunk headers and function context:
+/// like C Git without a diff driver, these are lines starting with a letter, '_' or '$'.
+fn is_function_line(line: &str) -> bool {
+    line.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+}
+
+/// Returns the text shown for a function line in hunk headers.
+fn function_name(line: &str) -> String {
+    let mut len = line.len().min(FUNCTION_NAME_MAX_LEN);
+    while !line.is_char_boundary(len) {
+        len -= 1;
+    }
+    line[..len].trim_end().to_string()
+}
+
+/// A run of changed lines, with the lines removed from the old file and added to the new one.
+/// Positions are 0-based.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct XdiffChange {
+    pub(crate) old_start: usize,
+    pub(crate) old_len: usize,
+    pub(crate) new_start: usize,
+    pub(crate) new_len: usize,
+    /// The change only has blank lines, and --ignore-blank-lines is set.
+    ignorable: bool,
+}
+
+/// A line of a hunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum HunkLine<'a> {
+    Context(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl<'a> HunkLine<'a> {
+    /// Returns the line, with its line feed if it has one.
+    pub(crate) fn text(&self) -> &'a str {
+        match self {
+            HunkLine::Context(line) | HunkLine::Removed(line) | HunkLine::Added(line) => line,
+        }
+    }
+
+    /// Returns the character the line is prefixed with in unified diffs.
+    pub(crate) fn prefix(&self) -> char {
+        match self {
+            HunkLine::Context(_) => ' ',
+            HunkLine::Removed(_) => '-',
+            HunkLine::Added(_) => '+',
+        }
+    }
+}
+
+/// A group of nearby changes shown with their context. Starts are 1-based, as shown in hunk
+/// headers, except that an empty range starts at the line before it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Hunk<'a> {
+    pub(crate) old_start: usize,
+    pub(crate) old_len: usize,
+    pub(crate) new_start: usize,
+    pub(crate) new_len: usize,
+    /// The function the hunk is in, shown after the hunk header.
+    pub(crate) function_name: Option<String>,
+    pub(crate) lines: Vec<HunkLine<'a>>,
+}
+
+impl Hunk<'_> {
+    /// Returns the hunk header, e.g. `@@ -1,3 +1,4 @@ fn main() {`.
+    pub(crate) fn header(&self) -> String {
+        let range = |start: usize, len: usize| match len {
+            1 => format!("{start}"),
+            _ => format!("{start},{len}"),
+        };
+        let mut header = format!(
+            "@@ -{} +{} @@",
+            range(self.old_start, self.old_len),
+            range(self.new_start, self.new_len)
+        );
+        if let Some(function_name) = &self.function_name {
+            header.push(' ');
+            header.push_str(function_name);
+        }
+        header
+    }
+
+    /// Formats the hunk as in unified diffs, marking lines without a line feed.
+    pub(crate) fn format_unified(&self) -> String {
+        let mut out = format!("{}\n", self.header());
+        for line in &self.lines {
+            out.push(line.prefix());
+            out.push_str(line.text());
+            if !line.text().ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        out
+    }
+}
+
+/// The changed lines between two files.
+pub(crate) struct LineDiff<'a> {
+    old_lines: Vec<&'a str>,
+    new_lines: Vec<&'a str>,
+    changes: Vec<XdiffChange>,
+}
+
+impl<'a> LineDiff<'a> {
+    pub(crate) fn new(old: &'a str, new: &'a str, options: &XdiffOptions) -> LineDiff<'a> {
+        let old_lines = split_lines(old);
+        let new_lines = split_lines(new);
+
+        // Lines are compared by the id of their comparable form.
+        let mut ids = HashMap::new();
+        let mut line_ids = |lines: &[&'a str]| {
+            lines
+                .iter()
+                .map(|line| {
+                    let next_id = ids.len();
+                    *ids.entry(options.comparable_line(line)).or_insert(next_id)
+                })
+                .collect::<Vec<_>>()
+        };
+        let old_ids = line_ids(&old_lines);
+        let new_ids = line_ids(&new_lines);
+
+        let mut state = DiffState {
+            old: &old_ids,
+            new: &new_ids,
+            old_changed: vec![false; old_ids.len()],
+            new_changed: vec![false; new_ids.len()],
+            minimal: options.algorithm == DiffAlgorithm::Minimal,
+        };
+        let (old_len, new_len) = (old_ids.len(), new_ids.len());
+        match options.algorithm {
+            DiffAlgorithm::Myers | DiffAlgorithm::Minimal => state.myers(0, old_len, 0, new_len),
+            DiffAlgorithm::Patience => state.patience(0, old_len, 0, new_len),
+            DiffAlgorithm::Histogram => state.histogram(0, old_len, 0, new_len),
+        }
+        let indent_heuristic = options.indent_heuristic;
+        let mut old_file = CompactFile {
+            ids: &old_ids,
+            lines: &old_lines,
+            changed: &mut state.old_changed,
+        };
+        let mut new_file = CompactFile {
+            ids: &new_ids,
+            lines: &new_lines,
+            changed: &mut state.new_changed,
+        };
+        compact_changes(&mut old_file, &new_file, indent_heuristic);
+        compact_changes(&mut new_file, &old_file, indent_heuristic);
+
+        let mut changes = vec![];
+        let (mut old_idx, mut new_idx) = (0, 0);
+        while old_idx < old_len || new_idx < new_len {
+            let old_changed = old_idx < old_len && state.old_changed[old_idx];
+            let new_changed = new_idx < new_len && state.new_changed[new_idx];
+            if !old_changed && !new_changed {
+                old_idx += 1;
+                new_idx += 1;
+                continue;
+            }
+            let (old_start, new_start) = (old_idx, new_idx);
+            while old_idx < old_len && state.old_changed[old_idx] {
+                old_idx += 1;
+            }
+            while new_idx < new_len && state.new_changed[new_idx] {
+                new_idx += 1;
+            }
+            let ignorable = options.ignore_blank_lines
+                && old_lines[old_start..old_idx]
+                    .iter()
+                    .chain(&new_lines[new_start..new_idx])
+                    .all(|line| options.is_blank_line(line));
+            changes.push(XdiffChange {
+                old_start,
+                old_len: old_idx - old_start,
+                new_start,
+                new_len: new_idx - new_start,
+                ignorable,
+            });
+        }
+
+        LineDiff {
+            old_lines,
+            new_lines,
+            changes,
+        }
+    }
+
+    /// Returns the runs of changed lines, in order.
+    pub(crate) fn changes(&self) -> &[XdiffChange] {
+        &self.changes
+    }
+
+    /// Returns the number of lines removed from the old file and added to the new one.
+    pub(crate) fn line_counts(&self) -> (usize, usize) {
+        self.changes
+            .iter()
+            .fold((0, 0), |(removed, added), change| {
+                (removed + change.old_len, added + change.new_len)
+            })
+    }
+
+    /// Returns the end of the change group starting at the provided change, as C Git's
+    /// xdl_get_hunk: changes are grouped when the unchanged lines between them would be shown
+    /// as context anyway. Ignorable changes are only kept when they're close to others.
+    fn group_end(&self, first: usize, options: &XdiffOptions) -> usize {
+        let max_common = 2 * options.context + options.interhunk_context;
+        let max_ignorable = options.context;
+        let mut last = first;
+        let mut ignored = 0;
+        for idx in first + 1..self.changes.len() {
+            let previous = &self.changes[idx - 1];
+            let change = &self.changes[idx];
+            let distance = change.old_start - (previous.old_start + previous.old_len);
+            if distance > max_common {
+                break;
+            }
+            let last_change = &self.changes[last];
+            if distance < max_ignorable && (!change.ignorable || last == idx - 1) {
+                last = idx;
+                ignored = 0;
+            } else if distance < max_ignorable && change.ignorable {
+                ignored += change.new_len;
+            } else if last != idx - 1
+                && change.old_start + ignored - (last_change.old_start + last_change.old_len)
+                    > max_common
+            {
+                break;
+            } else if !change.ignorable {
+                last = idx;
+                ignored = 0;
+            } else {
+                ignored += change.new_len;
+            }
+        }
+        last
+    }
+
+    /// Returns the line of the old file starting the function containing the provided line,
+    /// searching backwards from it.
+    fn function_start(&self, line: usize) -> Option<usize> {
+        (0..=line.min(self.old_lines.len().saturating_sub(1)))
+            .rev()
+            .find(|&idx| idx < self.old_lines.len() && is_function_line(self.old_lines[idx]))
+    }
+
+    /// Groups the changes into hunks with the provided context.
+    pub(crate) fn hunks(&self, options: &XdiffOptions) -> Vec<Hunk<'a>> {
+        // The ranges of each hunk, in lines of the old and new files, and its changes.
+        let mut ranges: Vec<(usize, usize, usize, usize, usize, usize)> = vec![];
+        let mut idx = 0;
+        while idx < self.changes.len() {
+            // Leading ignorable changes are dropped, unless they're close to the next change.
+            let mut first = idx;
+            let mut current = idx;
+            while current < self.changes.len() && self.changes[current].ignorable {
+                let change_end = self.changes[current].old_start + self.changes[current].old_len;
+                current += 1;
+                if self
+                    .changes
+                    .get(current)
+                    .is_none_or(|next| next.old_start - change_end >= options.context)
+                {
+                    first = current;
+                }
+            }
+            if first >= self.changes.len() {
+                break;
+            }
+            let last = self.group_end(first, options);
+            let first_change = &self.changes[first];
+            let last_change = &self.changes[last];
+            idx = last + 1;
+
+            let mut old_start = first_change.old_start.saturating_sub(options.context);
+            let mut old_end = (last_change.old_start + last_change.old_len + options.context)
+                .min(self.old_lines.len());
+            if options.function_context {
+                let start = self.function_start(first_change.old_start).unwrap_or(0);
+                old_start = old_start.min(start);
+                // The function ends before the next one, without the blank lines before it.
+                let change_end = last_change.old_start + last_change.old_len;
+                let mut end = (change_end..self.old_lines.len())
+                    .find(|&line| is_function_line(self.old_lines[line]))
+                    .unwrap_or(self.old_lines.len());
+                while end > change_end && options.is_blank_line(self.old_lines[end - 1]) {
+                    end -= 1;
+                }
+                old_end = old_end.max(end);
+            }
+            let new_start = first_change.new_start - (first_change.old_start - old_start);
+            let new_end = last_change.new_start
+                + last_change.new_len
+                + (old_end - (last_change.old_start + last_change.old_len));
+
+            match ranges.last_mut() {
+                // With function context, hunks may overlap, in which case they're merged.
+                Some(range) if old_start <= range.1 => {
+                    range.1 = old_end;
+                    range.3 = new_end;
+                    range.5 = last;
+                }
+                _ => ranges.push((old_start, old_end, new_start, new_end, first, last)),
+            }
+        }
+
+        ranges
+            .into_iter()
+            .map(|(old_start, old_end, new_start, new_end, first, last)| {
+                let mut lines = vec![];
+                let (mut old_idx, mut new_idx) = (old_start, new_start);
+                for change in &self.changes[first..=last] {
+                    while old_idx < change.old_start {
+                        lines.push(HunkLine::Context(self.new_lines[new_idx]));
+                        old_idx += 1;
+                        new_idx += 1;
+                    }
+                    lines.extend(
+                        self.old_lines[change.old_start..change.old_start + change.old_len]
+                            .iter()
+                            .map(|line| HunkLine::Removed(line)),
+                    );
+                    lines.extend(
+                        self.new_lines[change.new_start..change.new_start + change.new_len]
+                            .iter()
+                            .map(|line| HunkLine::Added(line)),
+                    );
+                    old_idx += change.old_len;
+                    new_idx += change.new_len;
+                }
+                while old_idx < old_end {
+                    lines.push(HunkLine::Context(self.new_lines[new_idx]));
+                    old_idx += 1;
+                    new_idx += 1;
+                }
+
+                let function_name = old_start
+                    .checked_sub(1)
+                    .and_then(|line| self.function_start(line))
+                    .map(|line| function_name(self.old_lines[line]))
+                    .filter(|name| !name.is_empty());
+                let old_len = old_end - old_start;
+                let new_len = new_end - new_start;
+                Hunk {
+                    old_start: if old_len == 0 {
+                        old_start
+                    } else {
+                        old_start + 1
+                    },
+                    old_len,
+                    new_start: if new_len == 0 {
+                        new_start
+                    } else {
+                        new_start + 1
+                    },
+                    new_len,
+                    function_name,
+                    lines,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns whether the old and new files have the same lines, ignoring the changes the
+    /// options ignore.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.changes.iter().all(|change| change.ignorable)
+    }
+
+    /// Returns whether the comparison ignored some differences, so that files which differ may
+    /// have no changes.
+    pub(crate) fn ignores_changes(options: &XdiffOptions) -> bool {
+        options.ignores_whitespace() || options.ignore_blank_lines
+    }
+}
+
+/// The lines being diffed, as ids of their comparable form, and which of them changed.
+struct DiffState<'a> {
+    old: &'a [usize],
+    new: &'a [usize],
+    old_changed: Vec<bool>,
+    new_changed: Vec<bool>,
+    minimal: bool,
+}
+
+impl DiffState<'_> {
+    /// Narrows the ranges to exclude their common prefix and suffix.
+    fn trim(&self, old_lo: &mut usize, old_hi: &mut usize, new_lo: &mut usize, new_hi: &mut usize) {
+        while *old_lo < *old_hi && *new_lo < *new_hi && self.old[*old_lo] == self.new[*new_lo] {
+            *old_lo += 1;
+            *new_lo += 1;
+        }
+        while *old_lo < *old_hi
+            && *new_lo < *new_hi
+            && self.old[*old_hi - 1] == self.new[*new_hi - 1]
+        {
+            *old_hi -= 1;
+            *new_hi -= 1;
+        }
+    }
+
+    /// Marks the lines of the ranges as changed, returning whether one range is empty, in which
+    /// case every line of the other one changed.
+    fn mark_if_one_sided(
+        &mut self,
+        old_lo: usize,
+        old_hi: usize,
+        new_lo: usize,
+        new_hi: usize,
+    ) -> bool {
+        if old_lo == old_hi {
+            self.new_changed[new_lo..new_hi].fill(true);
+            true
+        } else if new_lo == new_hi {
+            self.old_changed[old_lo..old_hi].fill(true);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Myers' O(ND) algorithm in linear space, as described in "An O(ND) Difference Algorithm
+    /// and Its Variations": the ranges are split at the middle of an edit path, and each half
+    /// is diffed recursively.
+    fn myers(
+        &mut self,
+        mut old_lo: usize,
+        mut old_hi: usize,
+        mut new_lo: usize,
+        mut new_hi: usize,
+    ) {
+        self.trim(&mut old_lo, &mut old_hi, &mut new_lo, &mut new_hi);
+        if self.mark_if_one_sided(old_lo, old_hi, new_lo, new_hi) {
+            return;
+        }
+        let (old_split, new_split) = self.split(old_lo, old_hi, new_lo, new_hi);
+        self.myers(old_lo, old_split, new_lo, new_split);
+        self.myers(old_split, old_hi, new_split, new_hi);
+    }
+
+    /// Returns a point on a shortest edit path between the ranges, where forward and backward
+    /// searches meet. Unless a minimal diff is required, a point as far as possible from both
+    /// ends is used instead once the edit path gets too long, as C Git does.
+    fn split(&self, old_lo: usize, old_hi: usize, new_lo: usize, new_hi: usize) -> (usize, usize) {
+        let n = (old_hi - old_lo) as isize;
+        let m = (new_hi - new_lo) as isize;
+        let delta = n - m;
+        let odd = delta & 1 != 0;
+        // Diagonals k = x - y range from -m to n, with an unreachable one on each side.
+        let offset = m + 1;
+        // The furthest x reached on each diagonal, forward from the start and backward from the
+        // end, in coordinates relative to the end. -1 marks unreachable diagonals.
+        let mut forward = vec![-1isize; (n + m + 3) as usize];
+        let mut backward = vec![-1isize; (n + m + 3) as usize];
+        let max_cost = (((n + m) as f64).sqrt() as isize).max(MYERS_MIN_MAX_COST as isize);
+
+        let old_at = |x: isize| self.old[old_lo + x as usize];
+        let new_at = |y: isize| self.new[new_lo + y as usize];
+        let point = |x: isize, y: isize| (old_lo + x as usize, new_lo + y as usize);
+        // Returns the furthest x on the diagonal reachable with one more edit.
+        let furthest = |values: &[isize], k: isize| {
+            let down = values[(k + 1 + offset) as usize];
+            let down = (down >= 0 && down - (k + 1) < m).then_some(down);
+            let right = values[(k - 1 + offset) as usize];
+            let right = (right >= 0 && right < n).then_some(right + 1);
+            down.max(right)
+        };
+
+        for d in 0..=n + m {
+            let diagonals = (-d..=d).step_by(2).filter(|&k| -m <= k && k <= n);
+
+            for k in diagonals.clone() {
+                let Some(mut x) = (if d == 0 {
+                    Some(0)
+                } else {
+                    furthest(&forward, k)
+                }) else {
+                    continue;
+                };
+                let mut y = x - k;
+                while x < n && y < m && old_at(x) == new_at(y) {
+                    x += 1;
+                    y += 1;
+                }
+                forward[(k + offset) as usize] = x;
+                let backward_x = backward[(delta - k + offset) as usize];
+                if odd && (delta - k).abs() < d && backward_x >= 0 && x + backward_x >= n {
+                    return point(x, y);
+                }
+            }
+
+            for k in diagonals.clone() {
+                let Some(mut x) = (if d == 0 {
+                    Some(0)
+                } else {
+                    furthest(&backward, k)
+                }) else {
+                    continue;
+                };
+                let mut y = x - k;
+                while x < n && y < m && old_at(n - 1 - x) == new_at(m - 1 - y) {
+                    x += 1;
+                    y += 1;
+                }
+                backward[(k + offset) as usize] = x;
+                let forward_x = forward[(delta - k + offset) as usize];
+                if !odd && (delta - k).abs() <= d && forward_x >= 0 && x + forward_x >= n {
+                    return point(n - x, m - y);
+                }
+            }
+
+            if !self.minimal && d >= max_cost {
+                // The progress of the furthest point of each search, as x + y.
+                let best = |values: &[isize]| {
+                    diagonals
+                        .clone()
+                        .map(|k| (values[(k + offset) as usize], k))
+                        .filter(|(x, _)| *x >= 0)
+                        .max_by_key(|(x, k)| 2 * x - k)
+                };
+                match (best(&forward), best(&backward)) {
+                    (Some((fx, fk)), Some((bx, bk))) if 2 * fx - fk >= 2 * bx - bk => {
+                        return point(fx, fx - fk)
+                    }
+                    (_, Some((bx, bk))) => return point(n - bx, m - (bx - bk)),
+                    (Some((fx, fk)), None) => return point(fx, fx - fk),
+                    (None, None) => (),
+                }
+            }
+        }
+        unreachable!("the forward and backward searches always meet")
+    }
+
+    /// The patience algorithm, as C Git's: lines appearing exactly once on both sides are
+    /// matched when they appear in the same order, along with the equal lines around them, and
+    /// the regions between them are diffed recursively. Regions without such lines are diffed
+    /// with Myers' algorithm, and regions without any common line are changed as a whole.
+    fn patience(&mut self, old_lo: usize, old_hi: usize, new_lo: usize, new_hi: usize) {
+        if self.mark_if_one_sided(old_lo, old_hi, new_lo, new_hi) {
+            return;
+        }
+
+        // For each line, its number of occurrences and last position on each side.
+        let mut occurrences = HashMap::<usize, (usize, usize, usize, usize)>::new();
+        for idx in old_lo..old_hi {
+            let entry = occurrences.entry(self.old[idx]).or_default();
+            entry.0 += 1;
+            entry.1 = idx;
+        }
+        let mut has_common = false;
+        for idx in new_lo..new_hi {
+            if let Some(entry) = occurrences.get_mut(&self.new[idx]) {
+                has_common = true;
+                entry.2 += 1;
+                entry.3 = idx;
+            }
+        }
+        if !has_common {
+            self.old_changed[old_lo..old_hi].fill(true);
+            self.new_changed[new_lo..new_hi].fill(true);
+            return;
+        }
+
+        let unique = (old_lo..old_hi)
+            .filter_map(|idx| match occurrences[&self.old[idx]] {
+                (1, old_idx, 1, new_idx) => Some((old_idx, new_idx)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if unique.is_empty() {
+            self.myers(old_lo, old_hi, new_lo, new_hi);
+            return;
+        }
+
+        let mut matches = longest_increasing_subsequence(&unique).into_iter();
+        let (mut old_idx, mut new_idx) = (old_lo, new_lo);
+        loop {
+            let next_match = matches.next();
+            let (mut old_next, mut new_next) = next_match.unwrap_or((old_hi, new_hi));
+            // Equal lines before the next match and after the previous one are matched too.
+            if next_match.is_some() {
+                while old_next > old_idx
+                    && new_next > new_idx
+                    && self.old[old_next - 1] == self.new[new_next - 1]
+                {
+                    old_next -= 1;
+                    new_next -= 1;
+                }
+            }
+            while old_idx < old_next && new_idx < new_next && self.old[old_idx] == self.new[new_idx]
+            {
+                old_idx += 1;
+                new_idx += 1;
+            }
+            if old_idx < old_next || new_idx < new_next {
+                self.patience(old_idx, old_next, new_idx, new_next);
+            }
+
+            let Some((old_match, new_match)) = next_match else {
+                return;
+            };
+            old_idx = old_match + 1;
+            new_idx = new_match + 1;
+        }
+    }
+
+    /// The histogram algorithm, as C Git's: the longest common region whose lines appear the
+    /// fewest times in the old range is matched, and the regions around it are diffed
+    /// recursively.
+    fn histogram(
+        &mut self,
+        mut old_lo: usize,
+        mut old_hi: usize,
+        mut new_lo: usize,
+        mut new_hi: usize,
+    ) {
+        self.trim(&mut old_lo, &mut old_hi, &mut new_lo, &mut new_hi);
+        if self.mark_if_one_sided(old_lo, old_hi, new_lo, new_hi) {
+            return;
+        }
+
+        let mut positions = HashMap::<usize, Vec<usize>>::new();
+        for idx in old_lo..old_hi {
+            positions.entry(self.old[idx]).or_default().push(idx);
+        }
+
+        // The best region so far, as its start on each side, its length and the fewest
+        // occurrences of its lines in the old range.
+        let mut best: Option<(usize, usize, usize, usize)> = None;
+        let mut has_common = false;
+        let mut new_idx = new_lo;
+        while new_idx < new_hi {
+            let mut next_new_idx = new_idx + 1;
+            if let Some(old_positions) = positions.get(&self.new[new_idx]) {
+                has_common = true;
+                if old_positions.len() <= HISTOGRAM_MAX_CHAIN_LENGTH {
+                    for &old_idx in old_positions {
+                        let (mut old_start, mut new_start) = (old_idx, new_idx);
+                        while old_start > old_lo
+                            && new_start > new_lo
+                            && self.old[old_start - 1] == self.new[new_start - 1]
+                        {
+                            old_start -= 1;
+                            new_start -= 1;
+                        }
+                        let mut len = new_idx - new_start;
+                        while old_start + len < old_hi
+                            && new_start + len < new_hi
+                            && self.old[old_start + len] == self.new[new_start + len]
+                        {
+                            len += 1;
+                        }
+                        let count = (0..len)
+                            .map(|offset| positions[&self.old[old_start + offset]].len())
+                            .min()
+                            .unwrap_or(usize::MAX);
+                        let better = match best {
+                            None => true,
+                            Some((_, _, best_len, best_count)) => {
+                                count < best_count || (count == best_count && len > best_len)
+                            }
+                        };
+                        if better {
+                            best = Some((old_start, new_start, len, count));
+                        }
+                        next_new_idx = next_new_idx.max(new_start + len);
+                    }
+                }
+            }
+            new_idx = next_new_idx;
+        }
+
+        match best {
+            Some((old_start, new_start, len, _)) => {
+                self.histogram(old_lo, old_start, new_lo, new_start);
+                self.histogram(old_start + len, old_hi, new_start + len, new_hi);
+            }
+            None if has_common => self.myers(old_lo, old_hi, new_lo, new_hi),
+            None => {
+                self.old_changed[old_lo..old_hi].fill(true);
+                self.new_changed[new_lo..new_hi].fill(true);
+            }
+        }
+    }
+}
+
+/// Returns the longest subsequence of the pairs, which are sorted by their first element, whose
+/// second elements are increasing.
+fn longest_increasing_subsequence(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // The index of the last pair of the best subsequence of each length, and the previous pair
+    // of each pair in its subsequence.
+    let mut tails: Vec<usize> = vec![];
+    let mut previous = vec![None; pairs.len()];
+    for (idx, (_, value)) in pairs.iter().enumerate() {
+        let position = tails.partition_point(|&tail| pairs[tail].1 < *value);
+        if position > 0 {
+            previous[idx] = Some(tails[position - 1]);
+        }
+        if position == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[position] = idx;
+        }
+    }
+
+    let mut subsequence = vec![];
+    let mut current = tails.last().copied();
+    while let Some(idx) = current {
+        subsequence.push(pairs[idx]);
+        current = previous[idx];
+    }
+    subsequence.reverse();
+    subsequence
+}
+
+/// The lines of one side of a diff, as used when compacting its changes.
+struct CompactFile<'a, 'b> {
+    ids: &'a [usize],
+    lines: &'a [&'b str],
+    changed: &'a mut [bool],
+}
+
+impl CompactFile<'_, '_> {
+    fn is_changed(&self, idx: usize) -> bool {
+        self.changed.get(idx).copied().unwrap_or(false)
+    }
+
+    /// Returns the indentation of the line, with tabs as 8 columns, or None if it's blank.
+    fn indent(&self, idx: usize) -> Option<usize> {
+        let mut indent = 0;
+        for byte in self.lines[idx].bytes() {
+            match byte {
+                b' ' => indent += 1,
+                b'\t' => indent += 8 - indent % 8,
+                b'\n' | b'\r' | b'\x0b' | b'\x0c' => (),
+                _ => return Some(indent),
+            }
+            if indent >= MAX_INDENT {
+                return Some(MAX_INDENT);
+            }
+        }
+        None
+    }
+
+    /// Measures the lines around a split before the line at `split`.
+    fn measure_split(&self, split: usize) -> SplitMeasurement {
+        let end_of_file = split >= self.lines.len();
+        let indent = if end_of_file {
+            None
+        } else {
+            self.indent(split)
+        };
+
+        let mut pre_blank = 0;
+        let mut pre_indent = None;
+        for idx in (0..split).rev() {
+            pre_indent = self.indent(idx);
+            if pre_indent.is_some() {
+                break;
+            }
+            pre_blank += 1;
+            if pre_blank == MAX_BLANKS {
+                pre_indent = Some(0);
+                break;
+            }
+        }
+
+        let mut post_blank = 0;
+        let mut post_indent = None;
+        for idx in split + 1..self.lines.len() {
+            post_indent = self.indent(idx);
+            if post_indent.is_some() {
+                break;
+            }
+            post_blank += 1;
+            if post_blank == MAX_BLANKS {
+                post_indent = Some(0);
+                break;
+            }
+        }
+
+        SplitMeasurement {
+            end_of_file,
+            indent,
+            pre_blank,
+            pre_indent,
+            post_blank,
+            post_indent,
+        }
+    }
+}
+
+/// A run of changed lines, which may be empty. Groups of both sides of a diff are separated by
+/// the same unchanged lines, so the nth group of one side lines up with the nth of the other.
+struct Group {
+    start: usize,
+    end: usize,
+}
+
+impl Group {
+    fn first(file: &CompactFile) -> Group {
+        let mut end = 0;
+        while file.is_changed(end) {
+            end += 1;
+        }
+        Group { start: 0, end }
+    }
+
+    /// Moves to the following group, returning false if this is the last one.
+    fn next(&mut self, file: &CompactFile) -> bool {
+        if self.end == file.ids.len() {
+            return false;
+        }
+        self.start = self.end + 1;
+        self.end = self.start;
+        while file.is_changed(self.end) {
+            self.end += 1;
+        }
+        true
+    }
+
+    /// Moves to the preceding group, returning false if this is the first one.
+    fn previous(&mut self, file: &CompactFile) -> bool {
+        if self.start == 0 {
+            return false;
+        }
+        self.end = self.start - 1;
+        self.start = self.end;
+        while self.start > 0 && file.changed[self.start - 1] {
+            self.start -= 1;
+        }
+        true
+    }
+
+    /// Shifts the group down by a line if the lines allow it, merging it with the following
+    /// group if they meet.
+    fn slide_down(&mut self, file: &mut CompactFile) -> bool {
+        if self.end == file.ids.len() || file.ids[self.start] != file.ids[self.end] {
+            return false;
+        }
+        file.changed[self.start] = false;
+        file.changed[self.end] = true;
+        self.start += 1;
+        self.end += 1;
+        while file.is_changed(self.end) {
+            self.end += 1;
+        }
+        true
+    }
+
+    /// Shifts the group up by a line if the lines allow it, merging it with the preceding
+    /// group if they meet.
+    fn slide_up(&mut self, file: &mut CompactFile) -> bool {
+        if self.start == 0 || file.ids[self.start - 1] != file.ids[self.end - 1] {
+            return false;
+        }
+        self.start -= 1;
+        self.end -= 1;
+        file.changed[self.start] = true;
+        file.changed[self.end] = false;
+        while self.start > 0 && file.changed[self.start - 1] {
+            self.start -= 1;
+        }
+        true
+    }
+}
+
+/// The lines around a possible split between a group of changes and unchanged lines.
+struct SplitMeasurement {
+    end_of_file: bool,
+    /// The indent of the line after the split, or None if it's blank.
+    indent: Option<usize>,
+    /// The number of blank lines before the split, and the indent of the line before them.
+    pre_blank: usize,
+    pre_indent: Option<usize>,
+    /// The number of blank lines after the line following the split, and the indent of the
+    /// line after them.
+    post_blank: usize,
+    post_indent: Option<usize>,
+}
+
+/// How bad a position of a group of changes is, the lowest being the best.
+#[derive(Clone, Copy, Default)]
+struct SplitScore {
+    effective_indent: i32,
+    penalty: i32,
+}
+
+impl SplitScore {
+    fn add_split(&mut self, m: &SplitMeasurement) {
+        if m.pre_indent.is_none() && m.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if m.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+
+        let post_blank = if m.indent.is_none() {
+            1 + m.post_blank
+        } else {
+            0
+        };
+        let total_blank = m.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank as i32;
+        self.penalty += POST_BLANK_WEIGHT * post_blank as i32;
+
+        let indent = m.indent.or(m.post_indent);
+        let any_blanks = total_blank != 0;
+        // As in C Git, a missing indent counts as -1.
+        self.effective_indent += indent.map_or(-1, |indent| indent as i32);
+
+        let (Some(indent), Some(pre_indent)) = (indent, m.pre_indent) else {
+            return;
+        };
+        if indent > pre_indent {
+            self.penalty += match any_blanks {
+                true => RELATIVE_INDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_INDENT_PENALTY,
+            };
+        } else if indent < pre_indent {
+            self.penalty += match (m.post_indent.is_some_and(|post| post > indent), any_blanks) {
+                (true, true) => RELATIVE_OUTDENT_WITH_BLANK_PENALTY,
+                (true, false) => RELATIVE_OUTDENT_PENALTY,
+                (false, true) => RELATIVE_DEDENT_WITH_BLANK_PENALTY,
+                (false, false) => RELATIVE_DEDENT_PENALTY,
+            };
+        }
+    }
+
+    fn compare(&self, other: &SplitScore) -> i32 {
+        let indents = self.effective_indent.cmp(&other.effective_indent) as i32;
+        INDENT_WEIGHT * indents + (self.penalty - other.penalty)
+    }
+}
+
+/// Shifts each group of changed lines of a file to where it reads best, merging groups which
+/// meet, as C Git's `xdl_change_compact` does. Groups which can slide are lined up with a
+/// group of changes of the other file where possible, and otherwise placed by the indent
+/// heuristic, or as far down as possible without it.
+fn compact_changes(file: &mut CompactFile, other: &CompactFile, indent_heuristic: bool) {
+    let mut group = Group::first(file);
+    let mut other_group = Group::first(other);
+
+    loop {
+        if group.end != group.start {
+            // Slide up as far as possible, then down, merging with adjacent groups. Positions
+            // where the group lines up with changed lines of the other file are kept track of.
+            let mut earliest_end;
+            let mut end_matching_other;
+            loop {
+                let group_len = group.end - group.start;
+                end_matching_other = None;
+
+                while group.slide_up(file) {
+                    assert!(other_group.previous(other), "group sync broken sliding up");
+                }
+                earliest_end = group.end;
+                if other_group.end > other_group.start {
+                    end_matching_other = Some(group.end);
+                }
+
+                while group.slide_down(file) {
+                    assert!(other_group.next(other), "group sync broken sliding down");
+                    if other_group.end > other_group.start {
+                        end_matching_other = Some(group.end);
+                    }
+                }
+
+                if group.end - group.start == group_len {
+                    break;
+                }
+            }
+
+            // The group is now as far down as it goes, so it only ever moves back up.
+            let group_len = group.end - group.start;
+            let target_end = if group.end == earliest_end {
+                None
+            } else if let Some(end_matching_other) = end_matching_other {
+                Some(end_matching_other)
+            } else if indent_heuristic {
+                // Each position of the group splits it from the lines before and after it, and
+                // the position whose splits score best wins.
+                let mut best: Option<(usize, SplitScore)> = None;
+                let first_shift = earliest_end
+                    .max((group.end - group_len).saturating_sub(1))
+                    .max(group.end.saturating_sub(INDENT_HEURISTIC_MAX_SLIDING));
+                for shift in first_shift..=group.end {
+                    let mut score = SplitScore::default();
+                    score.add_split(&file.measure_split(shift));
+                    score.add_split(&file.measure_split(shift - group_len));
+                    if best.is_none_or(|(_, best_score)| score.compare(&best_score) <= 0) {
+                        best = Some((shift, score));
+                    }
+                }
+                best.map(|(shift, _)| shift)
+            } else {
+                None
+            };
+
+            if let Some(target_end) = target_end {
+                while group.end > target_end {
+                    assert!(group.slide_up(file), "group sync broken sliding to target");
+                    assert!(
+                        other_group.previous(other),
+                        "group sync broken sliding to target"
+                    );
+                }
+            }
+        }
+
+        if !group.next(file) {
+            break;
+        }
+        assert!(
+            other_group.next(other),
+            "group sync broken moving to next group"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unified(old: &str, new: &str, options: &XdiffOptions) -> String {
+        LineDiff::new(old, new, options)
+            .hunks(options)
+            .iter()
+            .map(Hunk::format_unified)
+            .collect()
+    }
+
+    fn with_algorithm(algorithm: DiffAlgorithm) -> XdiffOptions {
+        XdiffOptions {
+            algorithm,
+            ..XdiffOptions::default()
+        }
+    }
+
+    #[test]
+    fn should_diff_lines_with_each_algorithm() {
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "a\nb\nx\nd\ne\nf\ng\ny\n";
+        for algorithm in [
+            DiffAlgorithm::Myers,
+            DiffAlgorithm::Minimal,
+            DiffAlgorithm::Patience,
+            DiffAlgorithm::Histogram,
+        ] {
+            assert_eq!(
+                unified(old, new, &with_algorithm(algorithm)),
+                "@@ -1,7 +1,8 @@\n a\n b\n-c\n+x\n d\n e\n f\n g\n+y\n",
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_split_distant_changes_into_hunks() {
+        let old = (1..=20).map(|n| format!("{n}\n")).collect::<String>();
+        let new = (1..=20)
+            .map(|n| match n {
+                2 => String::from("two\n"),
+                18 => String::from("eighteen\n"),
+                n => format!("{n}\n"),
+            })
+            .collect::<String>();
+        assert_eq!(
+            unified(&old, &new, &XdiffOptions::default()),
+            "@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n@@ -15,6 +15,6 @@\n 15\n 16\n 17\n-18\n+eighteen\n 19\n 20\n"
+        );
+        let options = XdiffOptions {
+            context: 0,
+            ..XdiffOptions::default()
+        };
+        assert_eq!(
+            unified(&old, "", &options),
+            format!(
+                "@@ -1,20 +0,0 @@\n{}",
+                old.lines()
+                    .map(|line| format!("-{line}\n"))
+                    .collect::<String>()
+            )
+        );
+    }
+
+    #[test]
+    fn should_match_unique_lines_with_patience() {
+        let old = "}\nfn a() {\n  x\n}\n\nfn b() {\n  y\n}\n";
+        let new = "}\nfn b() {\n  y\n}\n\nfn a() {\n  x\n}\n";
+        let diff = LineDiff::new(old, new, &with_algorithm(DiffAlgorithm::Patience));
+        assert_eq!(diff.line_counts(), (4, 4));
+        let diff = LineDiff::new(old, new, &with_algorithm(DiffAlgorithm::Histogram));
+        assert_eq!(diff.line_counts(), (4, 4));
+    }
+
+    #[test]
+    fn should_find_unique_lines_before_trimming_with_patience() {
+        // With the equal last lines trimmed first, "a" would be unique too and matched instead.
+        assert_eq!(
+            unified(
+                "b\na\na\n",
+                "a\nb\nc\na\n",
+                &with_algorithm(DiffAlgorithm::Patience)
+            ),
+            "@@ -1,3 +1,4 @@\n+a\n b\n-a\n+c\n a\n"
+        );
+    }
+
+    #[test]
+    fn should_ignore_whitespace_and_blank_lines() {
+        let old = "a b\nc\n";
+        let new = "a   b  \n\nc\n";
+        let options = XdiffOptions {
+            ignore_space_change: true,
+            ignore_blank_lines: true,
+            ..XdiffOptions::default()
+        };
+        assert!(LineDiff::new(old, new, &options).is_empty());
+        let options = XdiffOptions {
+            ignore_all_space: true,
+            ..XdiffOptions::default()
+        };
+        assert_eq!(
+            unified("ab\nc\n", "a b\n\nc\n", &options),
+            "@@ -1,2 +1,3 @@\n a b\n+\n c\n"
+        );
+    }
+
+    #[test]
+    fn should_show_function_names_and_missing_line_feeds() {
+        let old = "fn main() {\n  1\n  2\n  3\n  4\n  5\n}";
+        let new = "fn main() {\n  1\n  2\n  3\n  4\n  5\n}\n";
+        assert_eq!(
+            unified(old, new, &XdiffOptions::default()),
+            "@@ -4,4 +4,4 @@ fn main() {\n   3\n   4\n   5\n-}\n\\ No newline at end of file\n+}\n"
+        );
+        let options = XdiffOptions {
+            function_context: true,
+            ..XdiffOptions::default()
+        };
+        assert_eq!(
+            unified(
+                "x\nfn a() {\n  1\n  2\n  3\n  4\n}\nfn b() {\n}\n",
+                "x\nfn a() {\n  1\n  2\n  3\n  5\n}\nfn b() {\n}\n",
+                &options
+            ),
+            "@@ -2,8 +2,8 @@ x\n fn a() {\n   1\n   2\n   3\n-  4\n+  5\n }\n fn b() {\n }\n"
+        );
+    }
+
+    /// Returns the number of lines of the longest common subsequence of the files.
+    fn lcs_len(old: &[&str], new: &[&str]) -> usize {
+        let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lengths[i][j] = if old[i] == new[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        lengths[0][0]
+    }
+
+    #[test]
+    fn should_produce_valid_diffs_for_random_files() {
+        // A linear congruential generator keeps the files the same across runs.
+        let mut seed = 42u64;
+        let mut random = |max: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        for _ in 0..200 {
+            let mut file = || {
+                (0..random(30))
+                    .map(|_| format!("{}\n", random(6)))
+                    .collect::<String>()
+            };
+            let (old, new) = (file(), file());
+            for algorithm in [
+                DiffAlgorithm::Myers,
+                DiffAlgorithm::Minimal,
+                DiffAlgorithm::Patience,
+                DiffAlgorithm::Histogram,
+            ] {
+                let diff = LineDiff::new(&old, &new, &with_algorithm(algorithm));
+                // Applying the changes to the old file gives the new one.
+                let mut patched = String::new();
+                let mut old_idx = 0;
+                for change in diff.changes() {
+                    patched.extend(diff.old_lines[old_idx..change.old_start].iter().copied());
+                    patched.extend(
+                        diff.new_lines[change.new_start..change.new_start + change.new_len]
+                            .iter()
+                            .copied(),
+                    );
+                    old_idx = change.old_start + change.old_len;
+                }
+                patched.extend(diff.old_lines[old_idx..].iter().copied());
+                assert_eq!(patched, new, "{algorithm:?}");
+
+                if algorithm == DiffAlgorithm::Minimal {
+                    let common = lcs_len(&diff.old_lines, &diff.new_lines);
+                    let (removed, added) = diff.line_counts();
+                    assert_eq!(removed, diff.old_lines.len() - common);
+                    assert_eq!(added, diff.new_lines.len() - common);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_slide_changes_down() {
+        assert_eq!(
+            unified("a\nb\n", "a\nb\na\nb\n", &XdiffOptions::default()),
+            "@@ -1,2 +1,4 @@\n a\n b\n+a\n+b\n"
+        );
+    }
+
+    #[test]
+    fn should_line_up_changes_with_the_other_side() {
+        assert_eq!(
+            unified("beta\nbeta\n", "{\nbeta\n", &XdiffOptions::default()),
+            "@@ -1,2 +1,2 @@\n-beta\n+{\n beta\n"
+        );
+    }
+
+    #[test]
+    fn should_place_changes_by_indent() {
+        let old = "  x\n}\n}\na\n";
+        let new = "  x\n}\n}\n  x\n}\n}\na\n";
+        assert_eq!(
+            unified(old, new, &XdiffOptions::default()),
+            "@@ -1,4 +1,7 @@\n   x\n }\n+}\n+  x\n+}\n }\n a\n"
+        );
+
+        let options = XdiffOptions {
+            indent_heuristic: false,
+            ..XdiffOptions::default()
+        };
+        assert_eq!(
+            unified(old, new, &options),
+            "@@ -1,4 +1,7 @@\n   x\n }\n }\n+  x\n+}\n+}\n a\n"
+        );
+    }
+}
@@ -113,6 +113,17 @@ impl TestGitRepo {
         String::from(from_utf8(&cmd.stdout).unwrap().trim())
     }
 
+    /// Runs rust-git in the repo with the provided arguments. The temp dir stands in for the
+    /// user's config dir, so that only the repo's own config applies.
+    pub fn git(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(args)
+            .env("XDG_CONFIG_HOME", self.temp_dir.path())
+            .current_dir(self.temp_dir.path())
+            .assert()
+    }
+
     pub fn write_tree(&self) -> String {
         let cmd = Command::cargo_bin("rust-git")
             .unwrap()
@@ -136,6 +147,26 @@ impl TestGitRepo {
         String::from(from_utf8(&cmd.stdout).unwrap().trim())
     }
 
+    /// Commits the index and returns the new commit's id.
+    pub fn commit(&self, message: &str) -> String {
+        let assert = self.git(&["commit", "-m", message]).success();
+        String::from(from_utf8(&assert.get_output().stdout).unwrap().trim())
+    }
+
+    /// Adds a `<name>.txt` file for each name and commits it with the name as message, returning
+    /// the commit ids oldest first.
+    pub fn commit_files(&self, names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .map(|name| {
+                self.temp_dir
+                    .create_test_file(&format!("{name}.txt"), name.as_bytes());
+                self.add(&format!("{name}.txt"));
+                self.commit(name)
+            })
+            .collect()
+    }
+
     pub fn update_ref(&self, ref_name: &str, ref_value: &str) {
         Command::cargo_bin("rust-git")
             .unwrap()
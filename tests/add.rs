@@ -1,5 +1,15 @@
 mod integration_tests {
+    use std::{
+        fs,
+        os::unix::fs::{symlink, PermissionsExt},
+    };
+
     use assert_cmd::{assert::OutputAssertExt, Command};
+    use assert_fs::{
+        assert::PathAssert,
+        fixture::{FileTouch, PathChild},
+    };
+    use predicates::prelude::*;
     use test_helpers::{TempDirExt, TestGitRepo};
 
     #[test]
@@ -91,6 +101,302 @@ test_dir/test_in_dir.txt")
         .failure()
         .stderr("path \"../test.txt\" is outside of repo");
     }
+
+    #[test]
+    fn should_fail_when_index_is_locked() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.git_dir().child("index.lock").touch().unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "test.txt"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "fatal: Unable to create '{}': File exists.\n\nAnother git process seems to be running in this repository",
+                test_git_repo.git_dir().child("index.lock").path().display()
+            )));
+
+        test_git_repo
+            .git_dir()
+            .child("index")
+            .assert(predicate::path::missing());
+        // The lock is taken before anything gets added.
+        test_git_repo
+            .git_dir()
+            .child("objects/30/d74d258442c7c65512eafab474568dd706c430")
+            .assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn should_add_files_to_alternate_index() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_dir("test_dir");
+        test_git_repo
+            .temp_dir
+            .create_test_file("test_dir/test.txt", b"test");
+        test_git_repo.init();
+
+        // A relative GIT_INDEX_FILE is relative to the root of the repo.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_INDEX_FILE", "alternate_index")
+            .args(["add", "test.txt"])
+            .current_dir(test_git_repo.temp_dir.child("test_dir").path())
+            .assert()
+            .success();
+
+        test_git_repo
+            .temp_dir
+            .child("alternate_index")
+            .assert(predicate::path::exists());
+        test_git_repo
+            .temp_dir
+            .child("alternate_index.lock")
+            .assert(predicate::path::missing());
+        test_git_repo
+            .git_dir()
+            .child("index")
+            .assert(predicate::path::missing());
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_INDEX_FILE", "alternate_index")
+            .arg("ls-files")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("test_dir/test.txt\n");
+    }
+
+    fn ls_files_stage(test_git_repo: &TestGitRepo) -> String {
+        let output = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "-s"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn should_add_symlinks_and_executable_files() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("test_dir");
+        test_git_repo.temp_dir.create_test_file("run.sh", b"run");
+        fs::set_permissions(
+            test_git_repo.temp_dir.child("run.sh"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        symlink("test_dir", test_git_repo.temp_dir.child("link")).unwrap();
+
+        test_git_repo.add("run.sh link");
+
+        // The blob of a symbolic link holds its target.
+        assert_eq!(
+            ls_files_stage(&test_git_repo),
+            format!(
+                "120000 {} 0\tlink\n100755 {} 0\trun.sh\n",
+                test_git_repo.hash_object("test_dir"),
+                test_git_repo.hash_object("run")
+            )
+        );
+    }
+
+    #[test]
+    fn should_keep_recorded_executable_bit_without_filemode() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[core]
+filemode = false",
+        );
+        test_git_repo.temp_dir.create_test_file("run.sh", b"run");
+        fs::set_permissions(
+            test_git_repo.temp_dir.child("run.sh"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+
+        test_git_repo.add("run.sh");
+        assert!(ls_files_stage(&test_git_repo).starts_with("100644 "));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "--chmod=+x", "run.sh"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert!(ls_files_stage(&test_git_repo).starts_with("100755 "));
+
+        // Without filemode, re-adding the file keeps the executable bit of the index.
+        test_git_repo.temp_dir.create_test_file("run.sh", b"changed");
+        test_git_repo.add("run.sh");
+        assert!(ls_files_stage(&test_git_repo).starts_with("100755 "));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "--chmod", "-x", "run.sh"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert!(ls_files_stage(&test_git_repo).starts_with("100644 "));
+    }
+
+    #[test]
+    fn should_fail_to_chmod_symlinks() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        symlink("test.txt", test_git_repo.temp_dir.child("link")).unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "--chmod=+x", "link"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("error: cannot chmod +x 'link'\n");
+
+        // The link is still added.
+        assert_eq!(test_git_repo.ls_files(), "link");
+    }
+
+    #[test]
+    fn should_skip_ignored_files_and_git_dir() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("target/debug");
+        test_git_repo.temp_dir.create_test_dir("src");
+        test_git_repo
+            .temp_dir
+            .create_test_file(".gitignore", b"target/\n*.log\n!keep.log\n");
+        test_git_repo.temp_dir.create_test_file("src/main.rs", b"main");
+        test_git_repo.temp_dir.create_test_file("src/debug.log", b"log");
+        test_git_repo.temp_dir.create_test_file("keep.log", b"keep");
+        test_git_repo.temp_dir.create_test_file("target/debug/out", b"out");
+
+        test_git_repo.add(".");
+
+        assert_eq!(test_git_repo.ls_files(), ".gitignore\nkeep.log\nsrc/main.rs");
+    }
+
+    #[test]
+    fn should_refuse_explicit_ignored_files_unless_forced() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_file(".gitignore", b"*.log\n");
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a");
+        test_git_repo.temp_dir.create_test_file("debug.log", b"log");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "a.txt", "debug.log"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(
+                "The following paths are ignored by one of your .gitignore files:\ndebug.log\n",
+            ));
+        assert_eq!(test_git_repo.ls_files(), "a.txt");
+
+        test_git_repo.add("-f debug.log");
+        assert_eq!(test_git_repo.ls_files(), "a.txt\ndebug.log");
+
+        // Tracked files are updated even if they are ignored.
+        test_git_repo.temp_dir.create_test_file("debug.log", b"changed");
+        test_git_repo.add(".");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "-s"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!(
+                "{} 0\tdebug.log",
+                test_git_repo.hash_object("changed")
+            )));
+    }
+
+    #[test]
+    fn should_add_files_matching_pathspec_magic() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("src/nested");
+        test_git_repo.temp_dir.create_test_file("a.rs", b"a");
+        test_git_repo.temp_dir.create_test_file("README.md", b"readme");
+        test_git_repo.temp_dir.create_test_file("src/b.rs", b"b");
+        test_git_repo.temp_dir.create_test_file("src/nested/c.rs", b"c");
+        test_git_repo.temp_dir.create_test_file("src/skip.rs", b"skip");
+
+        let add = |args: &[&str]| {
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .arg("add")
+                .args(args)
+                .current_dir(test_git_repo.temp_dir.child("src").path())
+                .assert()
+        };
+
+        // Wildcards match across directories, unless the glob magic is used.
+        add(&["*.rs", ":!skip.rs"]).success();
+        assert_eq!(test_git_repo.ls_files(), "src/b.rs\nsrc/nested/c.rs");
+        add(&[":(top,glob)*.rs"]).success();
+        assert_eq!(test_git_repo.ls_files(), "a.rs\nsrc/b.rs\nsrc/nested/c.rs");
+        add(&[":(top,icase)readme.MD"]).success();
+        assert_eq!(
+            test_git_repo.ls_files(),
+            "README.md\na.rs\nsrc/b.rs\nsrc/nested/c.rs"
+        );
+
+        add(&["missing.rs"])
+            .failure()
+            .stderr("fatal: pathspec 'missing.rs' did not match any files");
+        add(&[":(foo)a.rs"])
+            .failure()
+            .stderr("fatal: Invalid pathspec magic 'foo' in ':(foo)a.rs'");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["--literal-pathspecs", "add", "*.rs"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: pathspec '*.rs' did not match any files");
+    }
+
+    #[test]
+    fn should_read_pathspec_from_file() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_file("a b.txt", b"a");
+        test_git_repo.temp_dir.create_test_file("c.txt", b"c");
+        test_git_repo.temp_dir.create_test_file("d.txt", b"d");
+        test_git_repo
+            .temp_dir
+            .create_test_file("paths", b"\"a b.txt\"\r\nc.txt\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "--pathspec-from-file=paths"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert_eq!(test_git_repo.ls_files(), "a b.txt\nc.txt");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "--pathspec-from-file=-", "--pathspec-file-nul"])
+            .write_stdin("d.txt\0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert_eq!(test_git_repo.ls_files(), "a b.txt\nc.txt\nd.txt");
+    }
 }
 
 mod compatibility_tests {
@@ -122,4 +428,4 @@ mod compatibility_tests {
 
         test_git_repo.assert_compatibility(commands, get_index_bytes);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,339 @@
+mod integration_tests {
+    use std::str::from_utf8;
+
+    use assert_cmd::Command;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> (TestGitRepo, String, String) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let first_commit = test_git_repo.commit("First commit");
+        test_git_repo
+            .temp_dir
+            .create_test_file("test2.txt", b"test2");
+        test_git_repo.add("test2.txt");
+        let second_commit = test_git_repo.commit("Second commit");
+
+        (test_git_repo, first_commit, second_commit)
+    }
+
+    fn branch(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("branch")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    #[test]
+    fn should_create_and_list_branches() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+
+        branch(&test_git_repo, &["feature", "HEAD~1"]).success();
+        test_git_repo.assert_ref_file("refs/heads/feature", &first_commit);
+
+        branch(&test_git_repo, &["topic"]).success();
+        test_git_repo.assert_ref_file("refs/heads/topic", &second_commit);
+
+        branch(&test_git_repo, &[])
+            .success()
+            .stdout("  feature\n* main\n  topic\n");
+
+        branch(&test_git_repo, &["--list", "f*"])
+            .success()
+            .stdout("  feature\n");
+
+        branch(&test_git_repo, &["-v"]).success().stdout(format!(
+            "  feature {} First commit
+* main    {} Second commit
+  topic   {} Second commit
+",
+            &first_commit[..7],
+            &second_commit[..7],
+            &second_commit[..7]
+        ));
+
+        branch(&test_git_repo, &["--contains", &second_commit])
+            .success()
+            .stdout("* main\n  topic\n");
+
+        branch(&test_git_repo, &["--merged", "feature"])
+            .success()
+            .stdout("  feature\n");
+    }
+
+    #[test]
+    fn should_fail_to_create_existing_or_invalid_branch() {
+        let (test_git_repo, first_commit, _) = setup_repo();
+
+        branch(&test_git_repo, &["feature"]).success();
+        branch(&test_git_repo, &["feature"])
+            .failure()
+            .stderr("fatal: a branch named 'feature' already exists");
+
+        branch(&test_git_repo, &["-f", "feature", &first_commit]).success();
+        test_git_repo.assert_ref_file("refs/heads/feature", &first_commit);
+
+        branch(&test_git_repo, &["bad..name"])
+            .failure()
+            .stderr("fatal: 'bad..name' is not a valid branch name");
+
+        branch(&test_git_repo, &["other", "missing"])
+            .failure()
+            .stderr("fatal: not a valid object name: 'missing'");
+    }
+
+    #[test]
+    fn should_delete_branches() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+
+        branch(&test_git_repo, &["merged", &first_commit]).success();
+        branch(&test_git_repo, &["-d", "merged"])
+            .success()
+            .stdout(format!(
+                "Deleted branch merged (was {}).\n",
+                &first_commit[..7]
+            ));
+        test_git_repo.assert_no_ref_file("refs/heads/merged");
+
+        // Move HEAD back so that the second commit isn't merged.
+        branch(&test_git_repo, &["unmerged", &second_commit]).success();
+        test_git_repo.update_ref("refs/heads/main", &first_commit);
+
+        branch(&test_git_repo, &["-d", "unmerged"])
+            .failure()
+            .stderr("error: the branch 'unmerged' is not fully merged.\nIf you are sure you want to delete it, run 'git branch -D unmerged'.");
+
+        branch(&test_git_repo, &["-D", "unmerged"]).success();
+        test_git_repo.assert_no_ref_file("refs/heads/unmerged");
+
+        branch(&test_git_repo, &["-d", "main"])
+            .failure()
+            .stderr(format!(
+                "error: cannot delete branch 'main' used by worktree at '{}'",
+                test_git_repo
+                    .temp_dir
+                    .path()
+                    .canonicalize()
+                    .unwrap()
+                    .display()
+            ));
+    }
+
+    #[test]
+    fn should_rename_and_copy_branches_with_config() {
+        let (test_git_repo, _, second_commit) = setup_repo();
+
+        branch(&test_git_repo, &["feature"]).success();
+        branch(&test_git_repo, &["--set-upstream-to=main", "feature"])
+            .success()
+            .stdout("branch 'feature' set up to track 'main'.\n");
+
+        branch(&test_git_repo, &["-m", "feature", "renamed"]).success();
+        test_git_repo.assert_no_ref_file("refs/heads/feature");
+        test_git_repo.assert_ref_file("refs/heads/renamed", &second_commit);
+
+        branch(&test_git_repo, &["-c", "renamed", "copied"]).success();
+        test_git_repo.assert_ref_file("refs/heads/renamed", &second_commit);
+        test_git_repo.assert_ref_file("refs/heads/copied", &second_commit);
+
+        branch(&test_git_repo, &["-vv", "--list", "copied", "renamed"])
+            .success()
+            .stdout(format!(
+                "  copied  {0} [main] Second commit\n  renamed {0} [main] Second commit\n",
+                &second_commit[..7]
+            ));
+
+        // Renaming the current branch updates HEAD.
+        branch(&test_git_repo, &["-m", "trunk"]).success();
+        test_git_repo.assert_ref_file("HEAD", "ref: refs/heads/trunk");
+    }
+
+    #[test]
+    fn should_keep_other_config_sections() {
+        let (test_git_repo, _, _) = setup_repo();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"
+
+[alias]
+st = \"status\"
+
+[remote.origin]
+url = \"https://example.com/repo.git\"
+
+[branch.feature]
+description = \"A feature\"",
+        );
+
+        branch(&test_git_repo, &["feature"]).success();
+        branch(&test_git_repo, &["--set-upstream-to=main", "feature"]).success();
+        branch(&test_git_repo, &["-m", "feature", "renamed"]).success();
+        branch(&test_git_repo, &["-c", "renamed", "copied"]).success();
+
+        let config = std::fs::read_to_string(test_git_repo.git_dir().join("config")).unwrap();
+        assert!(config.contains("[alias]\nst = \"status\"\n"));
+        assert!(config.contains("[remote.origin]\nurl = \"https://example.com/repo.git\"\n"));
+        assert!(!config.contains("[branch.feature]"));
+        for name in ["renamed", "copied"] {
+            assert!(config.contains(&format!(
+                "[branch.{name}]\ndescription = \"A feature\"\nmerge = \"refs/heads/main\"\nremote = \".\"\n"
+            )));
+        }
+    }
+
+    #[test]
+    fn should_track_remote_branches() {
+        let (test_git_repo, first_commit, _) = setup_repo();
+        test_git_repo.update_ref("refs/remotes/origin/main", &first_commit);
+
+        branch(&test_git_repo, &["-u", "origin/main"])
+            .success()
+            .stdout("branch 'main' set up to track 'origin/main'.\n");
+
+        branch(&test_git_repo, &["-a", "-vv"])
+            .success()
+            .stdout(format!(
+                "* main                {} [origin/main: ahead 1] Second commit
+  remotes/origin/main {} First commit
+",
+                &test_git_repo.assert_ref_file_read("refs/heads/main")[..7],
+                &first_commit[..7]
+            ));
+
+        branch(&test_git_repo, &["-r"])
+            .success()
+            .stdout("  origin/main\n");
+
+        branch(&test_git_repo, &["--unset-upstream"]).success();
+        branch(&test_git_repo, &["--unset-upstream"])
+            .failure()
+            .stderr("fatal: branch 'main' has no upstream information");
+    }
+
+    #[test]
+    fn should_list_detached_head() {
+        let (test_git_repo, first_commit, _) = setup_repo();
+        test_git_repo
+            .temp_dir
+            .create_test_file(".git/HEAD", first_commit.as_bytes());
+
+        branch(&test_git_repo, &[]).success().stdout(format!(
+            "* (HEAD detached at {})\n  main\n",
+            &first_commit[..7]
+        ));
+
+        branch(&test_git_repo, &["--show-current"])
+            .success()
+            .stdout("");
+    }
+
+    #[test]
+    fn should_manage_branches_in_reftable_repo() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("init")
+            .arg("--ref-format=reftable")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        test_git_repo.write_config(
+            b"
+[extensions]
+refstorage = \"reftable\"
+
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo.add("test.txt");
+        let commit_id = test_git_repo.commit("First commit");
+
+        // Enough refs to span several blocks, each update adding a table to the stack.
+        for n in 0..150 {
+            test_git_repo.update_ref(&format!("refs/heads/topic-{n:03}"), &commit_id);
+        }
+
+        branch(&test_git_repo, &["-m", "topic-000", "renamed"]).success();
+        branch(&test_git_repo, &["-d", "topic-001"]).success();
+        branch(&test_git_repo, &["--list", "topic-00*", "renamed"])
+            .success()
+            .stdout(
+                "  renamed
+  topic-002
+  topic-003
+  topic-004
+  topic-005
+  topic-006
+  topic-007
+  topic-008
+  topic-009
+",
+            );
+
+        let for_each_ref = Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        let refs = from_utf8(&for_each_ref.stdout).unwrap();
+        assert_eq!(refs.lines().count(), 150);
+        assert!(refs.contains(&format!("{commit_id} commit\trefs/heads/topic-149\n")));
+        assert!(!refs.contains("refs/heads/topic-001\n"));
+
+        // Auto-compaction keeps the stack short.
+        let tables_list = test_git_repo.git_dir().join("reftable").join("tables.list");
+        let tables = std::fs::read_to_string(tables_list).unwrap();
+        assert!(tables.lines().count() <= 8);
+    }
+
+    #[test]
+    fn should_color_branches() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+        test_git_repo.update_ref("refs/remotes/origin/main", &first_commit);
+        branch(&test_git_repo, &["-u", "origin/main"]).success();
+        branch(&test_git_repo, &["feature", "HEAD~1"]).success();
+
+        branch(&test_git_repo, &["-a", "--color"])
+            .success()
+            .stdout("  feature\x1b[m\n* \x1b[32mmain\x1b[m\n  \x1b[31mremotes/origin/main\x1b[m\n");
+        branch(&test_git_repo, &["-vv", "--color=always"])
+            .success()
+            .stdout(format!(
+                "  feature\x1b[m {} First commit
+* \x1b[32mmain   \x1b[m {} [\x1b[34morigin/main\x1b[m: ahead 1] Second commit
+",
+                &first_commit[..7],
+                &second_commit[..7]
+            ));
+
+        test_git_repo.write_config(
+            b"
+[color]
+ui = \"always\"
+
+[color.branch]
+current = \"yellow reverse\"",
+        );
+        branch(&test_git_repo, &[])
+            .success()
+            .stdout("  feature\x1b[m\n* \x1b[7;33mmain\x1b[m\n");
+        branch(&test_git_repo, &["--no-color"])
+            .success()
+            .stdout("  feature\n* main\n");
+    }
+}
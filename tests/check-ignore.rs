@@ -0,0 +1,140 @@
+mod integration_tests {
+    use assert_cmd::Command;
+    use assert_fs::fixture::PathChild;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> TestGitRepo {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("build");
+        test_git_repo.temp_dir.create_test_dir("src/gen");
+        test_git_repo.temp_dir.create_test_file(
+            ".gitignore",
+            b"# build output\n*.o\n!keep.o\nbuild/\n/root.txt\n",
+        );
+        test_git_repo
+            .temp_dir
+            .create_test_file("src/.gitignore", b"!*.o\ngen/**/*.rs\n");
+        test_git_repo
+    }
+
+    fn check_ignore(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("check-ignore")
+            .args(args)
+            .env("XDG_CONFIG_HOME", test_git_repo.temp_dir.path())
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    #[test]
+    fn should_print_ignored_paths() {
+        let test_git_repo = setup_repo();
+
+        check_ignore(
+            &test_git_repo,
+            &[
+                "a.o",
+                "keep.o",
+                "build",
+                "build/a.txt",
+                "root.txt",
+                "src/root.txt",
+                "src/a.o",
+                "src/gen/x/y.rs",
+            ],
+        )
+        .success()
+        .stdout("a.o\nbuild\nbuild/a.txt\nroot.txt\nsrc/gen/x/y.rs\n");
+
+        check_ignore(&test_git_repo, &["keep.o", "src/a.o"])
+            .failure()
+            .stdout("");
+        check_ignore(&test_git_repo, &["-q", "a.o"])
+            .success()
+            .stdout("");
+    }
+
+    #[test]
+    fn should_print_matching_patterns_with_verbose() {
+        let test_git_repo = setup_repo();
+
+        check_ignore(
+            &test_git_repo,
+            &["-v", "a.o", "keep.o", "src/a.o", "build/a.txt"],
+        )
+        .success()
+        .stdout(
+            ".gitignore:2:*.o\ta.o
+.gitignore:3:!keep.o\tkeep.o
+src/.gitignore:1:!*.o\tsrc/a.o
+.gitignore:4:build/\tbuild/a.txt
+",
+        );
+        check_ignore(&test_git_repo, &["-v", "-n", "a.txt"])
+            .failure()
+            .stdout("::\ta.txt\n");
+    }
+
+    #[test]
+    fn should_read_paths_from_stdin() {
+        let test_git_repo = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["check-ignore", "--stdin", "-z", "-v"])
+            .write_stdin("a.o\0a.txt\0")
+            .env("XDG_CONFIG_HOME", test_git_repo.temp_dir.path())
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(".gitignore\x002\x00*.o\x00a.o\x00");
+    }
+
+    #[test]
+    fn should_read_exclude_files() {
+        let test_git_repo = setup_repo();
+        test_git_repo.temp_dir.create_test_dir(".git/info");
+        test_git_repo
+            .temp_dir
+            .create_test_file(".git/info/exclude", b"*.info\n*.both\n");
+        test_git_repo
+            .temp_dir
+            .create_test_file("global-ignore", b"*.global\n!*.both\n");
+        test_git_repo.write_config(
+            format!(
+                "[core]\nexcludesFile = \"{}\"\n",
+                test_git_repo.temp_dir.child("global-ignore").display()
+            )
+            .as_bytes(),
+        );
+
+        check_ignore(&test_git_repo, &["-v", "a.info", "a.both"])
+            .success()
+            .stdout(".git/info/exclude:1:*.info\ta.info\n.git/info/exclude:2:*.both\ta.both\n");
+        check_ignore(&test_git_repo, &["a.global"])
+            .success()
+            .stdout("a.global\n");
+    }
+
+    #[test]
+    fn should_skip_tracked_paths_unless_no_index() {
+        let test_git_repo = setup_repo();
+        test_git_repo.temp_dir.create_test_file("a.o", b"a");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "-f", "a.o"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        check_ignore(&test_git_repo, &["a.o"]).failure().stdout("");
+        check_ignore(&test_git_repo, &["--no-index", "a.o"])
+            .success()
+            .stdout("a.o\n");
+        check_ignore(&test_git_repo, &["-n", "a.o"])
+            .failure()
+            .stderr("fatal: --non-matching is only valid with --verbose");
+    }
+}
@@ -0,0 +1,212 @@
+mod integration_tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use assert_cmd::Command;
+    use assert_fs::{assert::PathAssert, fixture::PathChild};
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> TestGitRepo {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("dir");
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a");
+        test_git_repo.temp_dir.create_test_file("dir/b.txt", b"b");
+        test_git_repo.add("a.txt dir");
+        test_git_repo
+    }
+
+    fn checkout_index(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("checkout-index")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    fn update_index(test_git_repo: &TestGitRepo, args: &[&str]) {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("update-index")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+    }
+
+    #[test]
+    fn should_restore_missing_files_and_refuse_to_overwrite_changes() {
+        let test_git_repo = setup_repo();
+        fs::remove_file(test_git_repo.temp_dir.child("a.txt")).unwrap();
+        fs::remove_dir_all(test_git_repo.temp_dir.child("dir")).unwrap();
+
+        checkout_index(&test_git_repo, &["-a"]).success();
+        test_git_repo.temp_dir.child("a.txt").assert("a");
+        test_git_repo.temp_dir.child("dir/b.txt").assert("b");
+
+        // Files matching the index are skipped silently.
+        checkout_index(&test_git_repo, &["a.txt"])
+            .success()
+            .stderr("");
+
+        test_git_repo.temp_dir.create_test_file("a.txt", b"changed");
+        checkout_index(&test_git_repo, &["a.txt"])
+            .failure()
+            .stderr("a.txt already exists, no checkout\n");
+        checkout_index(&test_git_repo, &["-q", "a.txt"])
+            .failure()
+            .stderr("");
+        test_git_repo.temp_dir.child("a.txt").assert("changed");
+
+        checkout_index(&test_git_repo, &["-f", "a.txt"]).success();
+        test_git_repo.temp_dir.child("a.txt").assert("a");
+    }
+
+    #[test]
+    fn should_report_files_not_in_index() {
+        let test_git_repo = setup_repo();
+
+        checkout_index(&test_git_repo, &["missing.txt"])
+            .failure()
+            .stderr("git checkout-index: missing.txt is not in the cache\n");
+        checkout_index(&test_git_repo, &["--stage=2", "a.txt"])
+            .failure()
+            .stderr("git checkout-index: a.txt does not exist at stage 2\n");
+        checkout_index(&test_git_repo, &["-a", "a.txt"])
+            .failure()
+            .stderr("fatal: git checkout-index: don't mix '--all' and explicit filenames");
+    }
+
+    #[test]
+    fn should_export_index_under_prefix() {
+        let test_git_repo = setup_repo();
+
+        checkout_index(&test_git_repo, &["-a", "--prefix=build/"]).success();
+
+        test_git_repo.temp_dir.child("build/a.txt").assert("a");
+        test_git_repo.temp_dir.child("build/dir/b.txt").assert("b");
+    }
+
+    #[test]
+    fn should_preserve_executable_bit_and_symlinks() {
+        let test_git_repo = setup_repo();
+        let a_id = test_git_repo.hash_object("a");
+        let target_id = test_git_repo.hash_object("a.txt");
+        update_index(
+            &test_git_repo,
+            &[
+                "--add",
+                "--cacheinfo",
+                &format!("100755,{a_id},run.sh"),
+                "--cacheinfo",
+                &format!("120000,{target_id},link"),
+            ],
+        );
+
+        checkout_index(&test_git_repo, &["run.sh", "link"]).success();
+
+        let mode = fs::metadata(test_git_repo.temp_dir.child("run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_ne!(mode & 0o100, 0);
+        assert_eq!(
+            fs::read_link(test_git_repo.temp_dir.child("link")).unwrap(),
+            std::path::PathBuf::from("a.txt")
+        );
+    }
+
+    #[test]
+    fn should_refresh_stat_data_with_index_flag() {
+        let test_git_repo = setup_repo();
+        let a_id = test_git_repo.hash_object("a");
+        update_index(
+            &test_git_repo,
+            &["--cacheinfo", &format!("100644,{a_id},a.txt")],
+        );
+        fs::remove_file(test_git_repo.temp_dir.child("a.txt")).unwrap();
+
+        checkout_index(&test_git_repo, &["-u", "a.txt"]).success();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "-c", "--debug"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("size: 0").not());
+    }
+
+    #[test]
+    fn should_write_unmerged_stages_to_temp_files() {
+        let test_git_repo = setup_repo();
+        let base_id = test_git_repo.hash_object("base");
+        let theirs_id = test_git_repo.hash_object("theirs");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--index-info"])
+            .write_stdin(format!(
+                "100644 {base_id} 1\tc.txt\n100644 {theirs_id} 3\tc.txt\n"
+            ))
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        let output = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["checkout-index", "--stage=all", "c.txt"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        let output = String::from_utf8(output.stdout).unwrap();
+
+        let (names, path) = output.trim_end().split_once('\t').unwrap();
+        assert_eq!(path, "c.txt");
+        let names = names.split(' ').collect::<Vec<_>>();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[1], ".");
+        test_git_repo.temp_dir.child(names[0]).assert("base");
+        test_git_repo.temp_dir.child(names[2]).assert("theirs");
+        test_git_repo
+            .temp_dir
+            .child("c.txt")
+            .assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn should_write_symlinks_to_plain_temp_files() {
+        let test_git_repo = setup_repo();
+        let target_id = test_git_repo.hash_object("a.txt");
+        // Submodule commits aren't in the repository, so any id will do.
+        let commit_id = "1".repeat(40);
+        update_index(
+            &test_git_repo,
+            &[
+                "--add",
+                "--cacheinfo",
+                &format!("120000,{target_id},link"),
+                "--cacheinfo",
+                &format!("160000,{commit_id},sub"),
+            ],
+        );
+
+        let output = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["checkout-index", "--temp", "link"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        let output = String::from_utf8(output.stdout).unwrap();
+
+        let (name, path) = output.trim_end().split_once('\t').unwrap();
+        assert_eq!(path, "link");
+        assert!(name.starts_with(".merge_link_"));
+        let metadata = fs::symlink_metadata(test_git_repo.temp_dir.child(name)).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        test_git_repo.temp_dir.child(name).assert("a.txt");
+
+        checkout_index(&test_git_repo, &["--temp", "sub"])
+            .failure()
+            .stderr("error: cannot create temporary submodule sub");
+    }
+}
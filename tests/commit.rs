@@ -143,4 +143,44 @@ email = \"test@user.com\"",
         assert_eq!(line5, "");
         assert_eq!(line6, "Another commit");
     }
+
+    #[test]
+    fn should_commit_on_detached_head() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let first_commit = test_git_repo.commit_tree(&tree_obj_id, "First commit");
+        test_git_repo.update_ref("refs/heads/main", &first_commit);
+        test_git_repo.temp_dir.create_test_file(".git/HEAD", first_commit.as_bytes());
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("test2.txt", b"test2");
+        test_git_repo.add("test2.txt");
+
+        let cmd = Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("commit")
+            .arg("-m")
+            .arg("Detached commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        let commit_obj_id = String::from(from_utf8(&cmd.stdout).unwrap().trim());
+
+        // The detached HEAD moves to the new commit, and the branch is left alone.
+        test_git_repo.assert_ref_file("HEAD", &commit_obj_id);
+        test_git_repo.assert_ref_file("refs/heads/main", &first_commit);
+
+        let cat_file_contents = test_git_repo.cat_file("-p", &commit_obj_id);
+        assert!(cat_file_contents.contains(&format!("parent {first_commit}")));
+    }
 }
@@ -0,0 +1,37 @@
+mod integration_tests {
+    use std::fs;
+
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    #[test]
+    fn should_compare_index_with_worktree() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a\n");
+        test_git_repo.temp_dir.create_test_file("b.txt", b"b\n");
+        test_git_repo.add("a.txt b.txt");
+
+        test_git_repo.git(&["diff-files"]).success().stdout("");
+
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a\nb\n");
+        fs::remove_file(test_git_repo.temp_dir.path().join("b.txt")).unwrap();
+        test_git_repo.git(&["diff-files"]).success().stdout(
+            ":100644 100644 78981922613b2afb6025042ff6bd878ac1994e85 0000000000000000000000000000000000000000 M\ta.txt
+:100644 000000 61780798228d17af2d34fce4cfbdf35556832472 0000000000000000000000000000000000000000 D\tb.txt
+",
+        );
+        test_git_repo
+            .git(&["diff-files", "-p", "a.txt"])
+            .success()
+            .stdout(
+                "diff --git a/a.txt b/a.txt
+index 7898192..422c2b7 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1,2 @@
+ a
++b
+",
+            );
+    }
+}
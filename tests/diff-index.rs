@@ -0,0 +1,71 @@
+mod integration_tests {
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    #[test]
+    fn should_compare_tree_with_index_or_worktree() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a\n");
+        test_git_repo.add("a.txt");
+        test_git_repo
+            .git(&["commit", "-m", "First commit"])
+            .success();
+
+        test_git_repo.temp_dir.create_test_file("b.txt", b"b\n");
+        test_git_repo.add("b.txt");
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a\nb\n");
+
+        test_git_repo.git(&["diff-index", "--cached", "HEAD"])
+            .success()
+            .stdout(
+                ":000000 100644 0000000000000000000000000000000000000000 61780798228d17af2d34fce4cfbdf35556832472 A\tb.txt
+",
+            );
+        test_git_repo.git(&["diff-index", "HEAD"]).success().stdout(
+            ":100644 100644 78981922613b2afb6025042ff6bd878ac1994e85 0000000000000000000000000000000000000000 M\ta.txt
+:000000 100644 0000000000000000000000000000000000000000 61780798228d17af2d34fce4cfbdf35556832472 A\tb.txt
+",
+        );
+        test_git_repo
+            .git(&["diff-index", "--name-only", "HEAD", "--", "a.txt"])
+            .success()
+            .stdout("a.txt\n");
+    }
+
+    #[test]
+    fn should_leave_out_intent_to_add_paths_when_cached() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a\n");
+        test_git_repo.add("a.txt");
+        test_git_repo.commit("First commit");
+
+        test_git_repo.temp_dir.create_test_file("new.txt", b"new\n");
+        test_git_repo.git(&["add", "-N", "new.txt"]).success();
+
+        test_git_repo
+            .git(&["diff-index", "--cached", "HEAD"])
+            .success()
+            .stdout("");
+        test_git_repo
+            .git(&["diff", "--cached", "--name-status"])
+            .success()
+            .stdout("");
+        test_git_repo
+            .git(&["diff-index", "--name-status", "HEAD"])
+            .success()
+            .stdout("A\tnew.txt\n");
+    }
+}
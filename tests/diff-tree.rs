@@ -0,0 +1,105 @@
+mod integration_tests {
+    use assert_cmd::Command;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> (TestGitRepo, String, String) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo
+            .temp_dir
+            .create_test_file("a.txt", b"a\nb\nc\n");
+        test_git_repo.temp_dir.create_test_file("b.txt", b"b\n");
+        test_git_repo.add("a.txt b.txt");
+        let first_commit = test_git_repo.commit("First commit");
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("a.txt", b"a\nB\nc\nd\n");
+        test_git_repo.temp_dir.create_test_dir("dir");
+        test_git_repo
+            .temp_dir
+            .create_test_file("dir/c.txt", b"new\n");
+        test_git_repo.add("a.txt dir/c.txt");
+        test_git_repo
+            .git(&["rm", "--cached", "-q", "b.txt"])
+            .success();
+        let second_commit = test_git_repo.commit("Second commit");
+        (test_git_repo, first_commit, second_commit)
+    }
+
+    #[test]
+    fn should_compare_commit_with_its_parent() {
+        let (test_git_repo, _, second_commit) = setup_repo();
+
+        test_git_repo.git(&["diff-tree", "-r", "HEAD"])
+            .success()
+            .stdout(format!(
+                "{second_commit}
+:100644 100644 de980441c3ab03a8c07dda1ad27b8a11f39deb1e a7bc997ebe8cf84988b83d2e83f1d193124fe593 M\ta.txt
+:100644 000000 61780798228d17af2d34fce4cfbdf35556832472 0000000000000000000000000000000000000000 D\tb.txt
+:000000 100644 0000000000000000000000000000000000000000 3e757656cf36eca53338e520d134963a44f793f8 A\tdir/c.txt
+"
+            ));
+        test_git_repo
+            .git(&[
+                "diff-tree",
+                "--no-commit-id",
+                "-r",
+                "--name-status",
+                "HEAD",
+                "--",
+                "dir",
+            ])
+            .success()
+            .stdout("A\tdir/c.txt\n");
+        test_git_repo
+            .git(&["diff-tree", "--name-only", "HEAD~", "HEAD"])
+            .success()
+            .stdout("a.txt\nb.txt\ndir\n");
+    }
+
+    #[test]
+    fn should_show_root_commit_only_with_root() {
+        let (test_git_repo, first_commit, _) = setup_repo();
+
+        test_git_repo
+            .git(&["diff-tree", "HEAD~"])
+            .success()
+            .stdout("");
+        test_git_repo
+            .git(&["diff-tree", "--root", "--name-status", "HEAD~"])
+            .success()
+            .stdout(format!("{first_commit}\nA\ta.txt\nA\tb.txt\n"));
+    }
+
+    #[test]
+    fn should_read_commits_from_stdin() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["diff-tree", "--stdin", "-r", "--name-only"])
+            .env("XDG_CONFIG_HOME", test_git_repo.temp_dir.path())
+            .current_dir(test_git_repo.temp_dir.path())
+            .write_stdin(format!("{second_commit}\n{second_commit} {first_commit}\n"))
+            .assert()
+            .success()
+            .stdout(format!(
+                "{second_commit}
+a.txt
+b.txt
+dir/c.txt
+{second_commit}
+a.txt
+b.txt
+dir/c.txt
+"
+            ));
+    }
+}
@@ -0,0 +1,444 @@
+mod integration_tests {
+    use std::{fs, str::from_utf8};
+
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> TestGitRepo {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo
+            .temp_dir
+            .create_test_file("a.txt", b"a\nb\nc\n");
+        test_git_repo.temp_dir.create_test_file("b.txt", b"b\n");
+        test_git_repo.add("a.txt b.txt");
+        test_git_repo
+            .git(&["commit", "-m", "First commit"])
+            .success();
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("a.txt", b"a\nB\nc\nd\n");
+        test_git_repo.temp_dir.create_test_file("c.txt", b"new\n");
+        test_git_repo.add("c.txt");
+        fs::remove_file(test_git_repo.temp_dir.path().join("b.txt")).unwrap();
+        test_git_repo
+    }
+
+    #[test]
+    fn should_diff_worktree_against_index() {
+        let test_git_repo = setup_repo();
+
+        test_git_repo.git(&["diff"]).success().stdout(
+            "diff --git a/a.txt b/a.txt
+index de98044..a7bc997 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,4 @@
+ a
+-b
++B
+ c
++d
+diff --git a/b.txt b/b.txt
+deleted file mode 100644
+index 6178079..0000000
+--- a/b.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-b
+",
+        );
+        test_git_repo
+            .git(&["diff", "--name-only", "--", "a.txt"])
+            .success()
+            .stdout("a.txt\n");
+    }
+
+    #[test]
+    fn should_diff_index_against_head() {
+        let test_git_repo = setup_repo();
+
+        for flag in ["--cached", "--staged"] {
+            test_git_repo.git(&["diff", flag]).success().stdout(
+                "diff --git a/c.txt b/c.txt
+new file mode 100644
+index 0000000..3e75765
+--- /dev/null
++++ b/c.txt
+@@ -0,0 +1 @@
++new
+",
+            );
+        }
+    }
+
+    #[test]
+    fn should_show_stats_of_diff_against_commit() {
+        let test_git_repo = setup_repo();
+
+        test_git_repo
+            .git(&["diff", "HEAD", "--stat"])
+            .success()
+            .stdout(
+                " a.txt | 3 ++-
+ b.txt | 1 -
+ c.txt | 1 +
+ 3 files changed, 3 insertions(+), 2 deletions(-)
+",
+            );
+        test_git_repo
+            .git(&["diff", "HEAD", "--numstat"])
+            .success()
+            .stdout("2\t1\ta.txt\n0\t1\tb.txt\n1\t0\tc.txt\n");
+        test_git_repo
+            .git(&["diff", "HEAD", "--name-status"])
+            .success()
+            .stdout("M\ta.txt\nD\tb.txt\nA\tc.txt\n");
+        test_git_repo
+            .git(&["diff", "HEAD", "--name-only"])
+            .success()
+            .stdout("a.txt\nb.txt\nc.txt\n");
+        test_git_repo
+            .git(&["diff", "HEAD", "--shortstat", "--summary"])
+            .success()
+            .stdout(
+                " 3 files changed, 3 insertions(+), 2 deletions(-)
+ delete mode 100644 b.txt
+ create mode 100644 c.txt
+",
+            );
+    }
+
+    #[test]
+    fn should_diff_two_commits() {
+        let test_git_repo = setup_repo();
+        test_git_repo.add("a.txt");
+        test_git_repo
+            .git(&["rm", "--cached", "-q", "b.txt"])
+            .success();
+        test_git_repo
+            .git(&["commit", "-m", "Second commit"])
+            .success();
+
+        let expected = ":100644 100644 de98044 a7bc997 M\ta.txt
+:100644 000000 6178079 0000000 D\tb.txt
+:000000 100644 0000000 3e75765 A\tc.txt
+";
+        test_git_repo
+            .git(&["diff", "--raw", "HEAD~", "HEAD"])
+            .success()
+            .stdout(expected);
+        test_git_repo
+            .git(&["diff", "--raw", "HEAD~..HEAD"])
+            .success()
+            .stdout(expected);
+        test_git_repo
+            .git(&["diff", "--raw", "HEAD~...HEAD"])
+            .success()
+            .stdout(expected);
+        test_git_repo
+            .git(&["diff", "HEAD", "HEAD"])
+            .success()
+            .stdout("");
+    }
+
+    #[test]
+    fn should_fail_on_ambiguous_argument() {
+        let test_git_repo = setup_repo();
+
+        test_git_repo.git(&["diff", "missing"]).failure().stderr(
+            "fatal: ambiguous argument 'missing': unknown revision or path not in the working tree.
+Use '--' to separate paths from revisions, like this:
+'git <command> [<revision>...] -- [<file>...]'",
+        );
+    }
+
+    #[test]
+    fn should_diff_files_outside_of_repo() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("old", b"a\nb\n");
+        test_git_repo.temp_dir.create_test_file("new", b"a\nc\n");
+        test_git_repo.temp_dir.create_test_file("same", b"a\nb\n");
+
+        let assert = test_git_repo
+            .git(&["diff", "--no-index", "old", "new"])
+            .code(1);
+        let stdout = from_utf8(&assert.get_output().stdout).unwrap();
+        assert!(stdout.starts_with("diff --git a/old b/new\n"));
+        assert!(stdout.ends_with("@@ -1,2 +1,2 @@\n a\n-b\n+c\n"));
+
+        test_git_repo
+            .git(&["diff", "--no-index", "old", "same"])
+            .success()
+            .stdout("");
+
+        // The leading slash of absolute paths isn't doubled up with the prefix.
+        let old = test_git_repo.temp_dir.path().join("old");
+        let new = test_git_repo.temp_dir.path().join("new");
+        let (old, new) = (old.to_str().unwrap(), new.to_str().unwrap());
+        let assert = test_git_repo.git(&["diff", "--no-index", old, new]).code(1);
+        let stdout = from_utf8(&assert.get_output().stdout).unwrap();
+        assert!(stdout.starts_with(&format!("diff --git a{old} b{new}\n")));
+        assert!(stdout.contains(&format!("\n--- a{old}\n+++ b{new}\n")));
+    }
+
+    #[test]
+    fn should_detect_renames_and_copies() {
+        let test_git_repo = setup_repo();
+        let numbers = (1..=20).map(|n| format!("{n}\n")).collect::<String>();
+        test_git_repo
+            .temp_dir
+            .create_test_file("n.txt", numbers.as_bytes());
+        test_git_repo.add("n.txt");
+        test_git_repo
+            .git(&["commit", "-m", "Second commit"])
+            .success();
+
+        test_git_repo.git(&["mv", "n.txt", "m.txt"]).success();
+        test_git_repo
+            .temp_dir
+            .create_test_file("m.txt", numbers.replace("20\n", "").as_bytes());
+        test_git_repo.add("m.txt");
+
+        test_git_repo
+            .git(&["diff", "--cached", "--", "m.txt", "n.txt"])
+            .success()
+            .stdout(
+                "diff --git a/n.txt b/m.txt
+similarity index 94%
+rename from n.txt
+rename to m.txt
+index 0ff3bbb..ac30dc2 100644
+--- a/n.txt
++++ b/m.txt
+@@ -17,4 +17,3 @@
+ 17
+ 18
+ 19
+-20
+",
+            );
+        test_git_repo
+            .git(&["diff", "--cached", "--stat", "--", "m.txt", "n.txt"])
+            .success()
+            .stdout(" n.txt => m.txt | 1 -\n 1 file changed, 1 deletion(-)\n");
+        test_git_repo
+            .git(&[
+                "diff",
+                "--cached",
+                "--name-status",
+                "-M=95%",
+                "--",
+                "m.txt",
+                "n.txt",
+            ])
+            .success()
+            .stdout("A\tm.txt\nD\tn.txt\n");
+        test_git_repo
+            .git(&[
+                "diff",
+                "--cached",
+                "--name-status",
+                "--no-renames",
+                "--",
+                "m.txt",
+                "n.txt",
+            ])
+            .success()
+            .stdout("A\tm.txt\nD\tn.txt\n");
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("copy.txt", numbers.as_bytes());
+        test_git_repo.add("copy.txt");
+        test_git_repo
+            .git(&[
+                "diff",
+                "--cached",
+                "--name-status",
+                "-C",
+                "--find-copies-harder",
+                "--",
+                "*.txt",
+            ])
+            .success()
+            .stdout("C100\tn.txt\tcopy.txt\nR094\tn.txt\tm.txt\n");
+    }
+
+    #[test]
+    fn should_color_diffs() {
+        let test_git_repo = setup_repo();
+        let colored_patch = "\x1b[1mdiff --git a/a.txt b/a.txt\x1b[m
+\x1b[1mindex de98044..a7bc997 100644\x1b[m
+\x1b[1m--- a/a.txt\x1b[m
+\x1b[1m+++ b/a.txt\x1b[m
+\x1b[36m@@ -1,3 +1,4 @@\x1b[m
+ a\x1b[m
+\x1b[31m-b\x1b[m
+\x1b[32m+\x1b[m\x1b[32mB\x1b[m
+ c\x1b[m
+\x1b[32m+\x1b[m\x1b[32md\x1b[m
+";
+
+        test_git_repo
+            .git(&["diff", "--color", "--", "a.txt"])
+            .success()
+            .stdout(colored_patch);
+        test_git_repo
+            .git(&["diff", "--color=auto", "--", "a.txt"])
+            .success()
+            .stdout(predicates::str::contains("\x1b").not());
+        test_git_repo.git(&["diff", "--color", "--stat", "--", "a.txt"])
+            .success()
+            .stdout(" a.txt | 3 \x1b[32m++\x1b[m\x1b[31m-\x1b[m\n 1 file changed, 2 insertions(+), 1 deletion(-)\n");
+
+        // Output going to the pager is colored automatically.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["diff", "--", "a.txt"])
+            .env("GIT_PAGER_IN_USE", "true")
+            .env("TERM", "xterm")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(colored_patch);
+
+        test_git_repo.write_config(
+            b"
+[color]
+ui = \"always\"
+
+[color.diff]
+old = \"blue bold\"
+whitespace = \"reverse red\"",
+        );
+        test_git_repo
+            .temp_dir
+            .create_test_file("a.txt", b"a\nB\nc\nd \n");
+        test_git_repo
+            .git(&["diff", "--", "a.txt"])
+            .success()
+            .stdout(predicates::str::contains(
+                "\x1b[32m+\x1b[m\x1b[32md\x1b[m\x1b[7;31m \x1b[m\n",
+            ));
+        test_git_repo
+            .git(&["diff-files", "-p", "--", "a.txt"])
+            .success()
+            .stdout(predicates::str::contains("\x1b").not());
+        test_git_repo
+            .git(&["diff-files", "-p", "--color", "--", "a.txt"])
+            .success()
+            .stdout(predicates::str::contains("\x1b[1;34m-"));
+        test_git_repo
+            .git(&["diff", "--no-color", "--", "a.txt"])
+            .success()
+            .stdout(predicates::str::contains("\x1b").not());
+
+        test_git_repo.write_config(
+            b"
+[color.diff]
+old = \"bogus\"",
+        );
+        test_git_repo.git(&["diff", "--color"]).failure().stderr(
+            "error: invalid color value: bogus\nfatal: bad config variable 'color.diff.old'",
+        );
+    }
+
+    #[test]
+    fn should_show_word_diffs() {
+        let test_git_repo = setup_repo();
+        test_git_repo
+            .temp_dir
+            .create_test_file("w.txt", b"hello world\nlast one here\n");
+        test_git_repo.add("w.txt");
+        test_git_repo
+            .temp_dir
+            .create_test_file("w.txt", b"hello there world\nlast two here\n");
+        let header = "diff --git a/w.txt b/w.txt
+index c4026bf..578a462 100644
+--- a/w.txt
++++ b/w.txt
+@@ -1,2 +1,2 @@
+";
+
+        test_git_repo
+            .git(&["diff", "--word-diff", "--", "w.txt"])
+            .success()
+            .stdout(format!(
+                "{header}hello {{+there+}} world\nlast [-one-]{{+two+}} here\n"
+            ));
+        test_git_repo
+            .git(&["diff", "--word-diff=porcelain", "--", "w.txt"])
+            .success()
+            .stdout(format!(
+                "{header} hello \n+there\n  world\n~\n last \n-one\n+two\n  here\n~\n"
+            ));
+        test_git_repo
+            .git(&["diff", "--word-diff=color", "--no-color", "--", "w.txt"])
+            .success()
+            .stdout(format!("{header}hello there world\nlast onetwo here\n"));
+        test_git_repo
+            .git(&["diff", "--word-diff=color", "--", "w.txt"])
+            .success()
+            .stdout(predicates::str::ends_with(
+                "\x1b[36m@@ -1,2 +1,2 @@\x1b[m
+hello \x1b[32mthere\x1b[m world
+last \x1b[31mone\x1b[m\x1b[32mtwo\x1b[m here
+",
+            ));
+    }
+
+    #[test]
+    fn should_color_moved_lines() {
+        let test_git_repo = setup_repo();
+        test_git_repo.temp_dir.create_test_file(
+            "m.txt",
+            b"first line of the block\nsecond line of the block\nmiddle\nend\n",
+        );
+        test_git_repo.add("m.txt");
+        test_git_repo.temp_dir.create_test_file(
+            "m.txt",
+            b"middle\nend\nfirst line of the block\nsecond line of the block\n",
+        );
+
+        test_git_repo
+            .git(&["diff", "--color", "--color-moved", "--", "m.txt"])
+            .success()
+            .stdout(predicates::str::ends_with(
+                "\x1b[36m@@ -1,4 +1,4 @@\x1b[m
+\x1b[1;35m-first line of the block\x1b[m
+\x1b[1;35m-second line of the block\x1b[m
+ middle\x1b[m
+ end\x1b[m
+\x1b[1;36m+\x1b[m\x1b[1;36mfirst line of the block\x1b[m
+\x1b[1;36m+\x1b[m\x1b[1;36msecond line of the block\x1b[m
+",
+            ));
+
+        test_git_repo.write_config(
+            b"
+[diff]
+colorMoved = \"dimmed-zebra\"",
+        );
+        test_git_repo
+            .git(&["diff", "--color", "--", "m.txt"])
+            .success()
+            .stdout(predicates::str::contains(
+                "\x1b[2m-first line of the block\x1b[m\n",
+            ));
+        test_git_repo
+            .git(&["diff", "--color", "--no-color-moved", "--", "m.txt"])
+            .success()
+            .stdout(predicates::str::contains(
+                "\x1b[31m-first line of the block\x1b[m\n",
+            ));
+    }
+}
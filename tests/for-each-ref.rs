@@ -0,0 +1,332 @@
+mod integration_tests {
+    use assert_cmd::Command;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> (TestGitRepo, String, String) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"
+
+[branch.feature]
+remote = \".\"
+merge = \"refs/heads/main\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let first_commit = test_git_repo.commit_tree(&tree_obj_id, "First commit");
+
+        let cmd = Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("commit-tree")
+            .arg(&tree_obj_id)
+            .arg("-p")
+            .arg(&first_commit)
+            .arg("-m")
+            .arg("Second commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        let second_commit = String::from(std::str::from_utf8(&cmd.stdout).unwrap().trim());
+
+        test_git_repo.update_ref("refs/heads/main", &first_commit);
+        test_git_repo.update_ref("refs/heads/feature", &second_commit);
+        test_git_repo.symbolic_ref("HEAD", "refs/heads/main");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("v1.0")
+            .arg(&first_commit)
+            .arg("-m")
+            .arg("Release 1.0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        test_git_repo.update_ref("refs/tags/v2.0", &second_commit);
+
+        (test_git_repo, first_commit, second_commit)
+    }
+
+    #[test]
+    fn should_list_all_refs_with_default_format() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+        let tag_obj_id = test_git_repo.assert_ref_file_read("refs/tags/v1.0");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "{second_commit} commit\trefs/heads/feature
+{first_commit} commit\trefs/heads/main
+{tag_obj_id} tag\trefs/tags/v1.0
+{second_commit} commit\trefs/tags/v2.0
+"
+            ));
+    }
+
+    #[test]
+    fn should_list_refs_from_packed_refs() {
+        let (test_git_repo, first_commit, _) = setup_repo();
+        test_git_repo.temp_dir.create_test_file(
+            ".git/packed-refs",
+            format!(
+                "# pack-refs with: peeled fully-peeled sorted\n{first_commit} refs/heads/packed\n"
+            )
+            .as_bytes(),
+        );
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname)")
+            .arg("refs/heads")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/heads/feature\nrefs/heads/main\nrefs/heads/packed\n");
+    }
+
+    #[test]
+    fn should_format_atoms_and_conditionals() {
+        let (test_git_repo, first_commit, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname:short) %(objecttype) %(if)%(*objectname)%(then)%(*objectname)%(else)lightweight%(end) %(subject)")
+            .arg("refs/tags")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "v1.0 tag {first_commit} Release 1.0\nv2.0 commit lightweight Second commit\n"
+            ));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(HEAD) %(refname:short) %(authorname) %(upstream:short) %(upstream:track)")
+            .arg("refs/heads/")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("  feature Test User main [ahead 1]\n* main Test User  \n");
+    }
+
+    #[test]
+    fn should_sort_and_limit_refs() {
+        let (test_git_repo, _, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--sort=-refname")
+            .arg("--count=3")
+            .arg("--format=%(refname)")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/tags/v2.0\nrefs/tags/v1.0\nrefs/heads/main\n");
+    }
+
+    #[test]
+    fn should_sort_by_head_and_tracking() {
+        let (test_git_repo, _, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--sort=-HEAD")
+            .arg("--format=%(refname:short)")
+            .arg("refs/heads/")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("main\nfeature\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--sort=upstream:trackshort")
+            .arg("--format=%(refname:short)")
+            .arg("refs/heads/")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("main\nfeature\n");
+    }
+
+    #[test]
+    fn should_strip_ref_name_components() {
+        let (test_git_repo, _, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname:lstrip=2) %(refname:lstrip=-1) %(refname:rstrip=-1) %(refname:strip=-2) %(refname:rstrip=5) %(refname:lstrip=-5)")
+            .arg("refs/tags/v1.0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("v1.0 v1.0 refs tags/v1.0  refs/tags/v1.0\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname:lstrip=x)")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: Integer value expected refname:lstrip=x");
+    }
+
+    #[test]
+    fn should_show_symbolic_ref_targets() {
+        let (test_git_repo, _, _) = setup_repo();
+        test_git_repo.symbolic_ref("refs/remotes/origin/HEAD", "refs/heads/main");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname:short) %(symref) %(symref:short)")
+            .arg("refs/heads")
+            .arg("refs/remotes")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("feature  \nmain  \norigin/HEAD refs/heads/main main\n");
+    }
+
+    #[test]
+    fn should_filter_refs_by_reachability() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--merged")
+            .arg("--format=%(refname)")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/heads/main\nrefs/tags/v1.0\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg(format!("--contains={second_commit}"))
+            .arg("--format=%(refname)")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/heads/feature\nrefs/tags/v2.0\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg(format!("--points-at={first_commit}"))
+            .arg("--format=%(refname)")
+            .arg("refs/tags/*")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/tags/v1.0\n");
+    }
+
+    #[test]
+    fn should_fail_for_unknown_atom() {
+        let (test_git_repo, _, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(unknown)")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: unknown field name: unknown");
+    }
+
+    #[test]
+    fn should_only_list_refs_of_active_namespace() {
+        let (test_git_repo, first_commit, second_commit) = setup_repo();
+
+        // Loose and packed refs in two namespaces, one of them nested.
+        test_git_repo.temp_dir.create_test_file(
+            ".git/packed-refs",
+            format!(
+                "# pack-refs with: peeled fully-peeled sorted \n\
+                 {first_commit} refs/namespaces/foo/refs/namespaces/bar/refs/heads/main\n\
+                 {first_commit} refs/namespaces/foo/refs/tags/packed\n"
+            )
+            .as_bytes(),
+        );
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("--namespace=foo")
+            .arg("update-ref")
+            .arg("refs/heads/main")
+            .arg(&second_commit)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        test_git_repo.assert_ref_file("refs/namespaces/foo/refs/heads/main", &second_commit);
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname) %(objectname)")
+            .env("GIT_NAMESPACE", "foo")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "refs/heads/main {second_commit}\n\
+                 refs/namespaces/bar/refs/heads/main {first_commit}\n\
+                 refs/tags/packed {first_commit}\n"
+            ));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("--namespace=foo/bar")
+            .arg("for-each-ref")
+            .arg("--format=%(refname)")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/heads/main\n");
+
+        // Deleting a packed ref in a namespace leaves the other namespaces alone.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("--namespace=foo")
+            .arg("tag")
+            .arg("-d")
+            .arg("packed")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("for-each-ref")
+            .arg("--format=%(refname)")
+            .arg("refs/namespaces/")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(
+                "refs/namespaces/foo/refs/heads/main\n\
+                 refs/namespaces/foo/refs/namespaces/bar/refs/heads/main\n",
+            );
+    }
+}
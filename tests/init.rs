@@ -293,4 +293,33 @@ mod integration_tests {
         .assert()
         .failure();
     }
+
+    #[test]
+    fn should_create_reftable_repo() {
+        let test_git_repo = TestGitRepo::new();
+
+        Command::cargo_bin("rust-git")
+        .unwrap()
+        .current_dir(test_git_repo.temp_dir.path())
+        .arg("init")
+        .arg("--ref-format=reftable")
+        .assert()
+        .success();
+
+        let git_dir = test_git_repo.git_dir();
+        git_dir.child("HEAD").assert("ref: refs/heads/.invalid\n");
+        git_dir.child("refs").child("heads").assert("this repository uses the reftable format\n");
+        git_dir.child("refs").child("tags").assert(predicate::path::missing());
+        git_dir.child("reftable").child("tables.list").assert(predicate::path::exists());
+        git_dir.child("config").assert(predicate::str::contains("refstorage = \"reftable\""));
+
+        Command::cargo_bin("rust-git")
+        .unwrap()
+        .current_dir(test_git_repo.temp_dir.path())
+        .arg("symbolic-ref")
+        .arg("HEAD")
+        .assert()
+        .success()
+        .stdout("refs/heads/main\n");
+    }
 }
@@ -0,0 +1,120 @@
+mod integration_tests {
+    use predicates::prelude::*;
+    use test_helpers::TestGitRepo;
+
+    /// Creates a repo with two commits, returned oldest first.
+    fn setup_repo() -> (TestGitRepo, Vec<String>) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        let commits = test_git_repo.commit_files(&["first", "second"]);
+
+        (test_git_repo, commits)
+    }
+
+    #[test]
+    fn should_log_commits_newest_first() {
+        let (test_git_repo, commits) = setup_repo();
+
+        test_git_repo
+            .git(&["log", "--oneline"])
+            .success()
+            .stdout(format!(
+                "{} second\n{} first\n",
+                &commits[1][..7],
+                &commits[0][..7]
+            ));
+        test_git_repo
+            .git(&["log", "-n", "1"])
+            .success()
+            .stdout(predicate::str::starts_with(format!(
+                "commit {}\n",
+                commits[1]
+            )))
+            .stdout(predicate::str::contains("\n    second\n"))
+            .stdout(predicate::str::contains("first").not());
+    }
+
+    #[test]
+    fn should_log_notes() {
+        let (test_git_repo, commits) = setup_repo();
+
+        test_git_repo
+            .git(&["notes", "add", "-m", "default note"])
+            .success();
+        test_git_repo
+            .git(&["notes", "--ref", "ci", "add", "-m", "passed"])
+            .success();
+
+        test_git_repo
+            .git(&["log", "-n", "1"])
+            .success()
+            .stdout(predicate::str::ends_with(
+                "    second\n\nNotes:\n    default note\n",
+            ));
+        test_git_repo
+            .git(&["log", "--oneline", "--notes=ci", &commits[1]])
+            .success()
+            .stdout(format!(
+                "{} second\nNotes (ci):\n    passed\n{} first\n",
+                &commits[1][..7],
+                &commits[0][..7]
+            ));
+        test_git_repo
+            .git(&["log", "--no-notes"])
+            .success()
+            .stdout(predicate::str::contains("Notes").not());
+    }
+
+    #[test]
+    fn should_fail_on_unborn_branch() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+
+        test_git_repo
+            .git(&["log"])
+            .failure()
+            .stderr(predicate::str::contains("does not have any commits yet"));
+    }
+
+    #[test]
+    fn should_color_commit_ids() {
+        let (test_git_repo, commits) = setup_repo();
+
+        test_git_repo
+            .git(&["log", "--oneline", "--color"])
+            .success()
+            .stdout(format!(
+                "\x1b[33m{}\x1b[m second\n\x1b[33m{}\x1b[m first\n",
+                &commits[1][..7],
+                &commits[0][..7]
+            ));
+        test_git_repo
+            .git(&["show", "--color=always", "HEAD~1"])
+            .success()
+            .stdout(predicate::str::starts_with(format!(
+                "\x1b[33mcommit {}\x1b[m\n",
+                commits[0]
+            )));
+
+        test_git_repo.write_config(
+            b"
+[color]
+diff = \"always\"",
+        );
+        test_git_repo
+            .git(&["log", "-n", "1", "--oneline"])
+            .success()
+            .stdout(format!("\x1b[33m{}\x1b[m second\n", &commits[1][..7]));
+        test_git_repo
+            .git(&["log", "-n", "1", "--oneline", "--no-color"])
+            .success()
+            .stdout(format!("{} second\n", &commits[1][..7]));
+    }
+}
@@ -103,5 +103,77 @@ get_expected_debug_output(&test_file2),
 get_expected_debug_output(&test_file3)));
     }
 
+    #[test]
+    fn should_show_untracked_and_ignored_files() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("build");
+        test_git_repo.temp_dir.create_test_dir("src");
+        test_git_repo.temp_dir.create_test_file(".gitignore", b"build/\n*.o\n");
+        test_git_repo.temp_dir.create_test_file("src/a.c", b"a");
+        test_git_repo.temp_dir.create_test_file("src/a.o", b"a");
+        test_git_repo.temp_dir.create_test_file("build/out", b"out");
+        test_git_repo.temp_dir.create_test_file("tracked.o", b"tracked");
+        test_git_repo.add("-f tracked.o");
+
+        let ls_files = |args: &[&str]| {
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .arg("ls-files")
+                .args(args)
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+        };
+
+        ls_files(&["-o"])
+            .success()
+            .stdout(".gitignore\nbuild/out\nsrc/a.c\nsrc/a.o\n");
+        ls_files(&["-o", "--exclude-standard"])
+            .success()
+            .stdout(".gitignore\nsrc/a.c\n");
+        ls_files(&["-o", "-i", "--exclude-standard"])
+            .success()
+            .stdout("build/out\nsrc/a.o\n");
+        ls_files(&["-o", "-x", "*.c", "--exclude-per-directory=.gitignore"])
+            .success()
+            .stdout(".gitignore\n");
+        ls_files(&["-c", "-i", "--exclude-standard"])
+            .success()
+            .stdout("tracked.o\n");
+        ls_files(&["-o", "-i"])
+            .failure()
+            .stderr("ls-files: --ignored needs some exclude pattern");
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn should_filter_files_by_pathspec() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("dir");
+        test_git_repo
+            .temp_dir
+            .create_test_file(".gitattributes", b"*.bin binary\ndir/* text\n");
+        test_git_repo.temp_dir.create_test_file("a.bin", b"a");
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a");
+        test_git_repo.temp_dir.create_test_file("dir/b.bin", b"b");
+        test_git_repo.add(".");
+
+        let ls_files = |args: &[&str]| {
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .arg("ls-files")
+                .args(args)
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+        };
+
+        ls_files(&["dir"]).success().stdout("dir/b.bin\n");
+        ls_files(&["*.bin"]).success().stdout("a.bin\ndir/b.bin\n");
+        ls_files(&[":(attr:-diff)"]).success().stdout("a.bin\ndir/b.bin\n");
+        ls_files(&[":(attr:-diff text)"]).success().stdout("dir/b.bin\n");
+        ls_files(&[":(attr:!text)", ":!.gitattributes"])
+            .success()
+            .stdout("a.txt\n");
+        ls_files(&[":(literal)*.bin"]).success().stdout("");
+    }
+}
@@ -0,0 +1,304 @@
+mod integration_tests {
+    use std::str::from_utf8;
+
+    use assert_cmd::Command;
+    use assert_fs::{assert::PathAssert, fixture::PathChild};
+    use predicates::prelude::*;
+    use test_helpers::TestGitRepo;
+
+    /// Creates a repo with two commits, returned oldest first.
+    fn setup_repo() -> (TestGitRepo, Vec<String>) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        let commits = test_git_repo.commit_files(&["first", "second"]);
+
+        (test_git_repo, commits)
+    }
+
+    fn notes(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("notes")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    fn show_note(test_git_repo: &TestGitRepo, args: &[&str]) -> String {
+        let output = notes(test_git_repo, args).success().get_output().clone();
+        String::from(from_utf8(&output.stdout).unwrap())
+    }
+
+    #[test]
+    fn should_add_append_and_show_notes() {
+        let (test_git_repo, commits) = setup_repo();
+
+        notes(&test_git_repo, &["add", "-m", "first line", "-m", "second"]).success();
+        assert_eq!(
+            show_note(&test_git_repo, &["show"]),
+            "first line\n\nsecond\n"
+        );
+        assert_eq!(
+            show_note(&test_git_repo, &["show", &commits[1]]),
+            "first line\n\nsecond\n"
+        );
+
+        notes(&test_git_repo, &["add", "-m", "again"])
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "error: Cannot add notes. Found existing notes for object {}. Use '-f' to overwrite existing notes",
+                commits[1]
+            )));
+        notes(&test_git_repo, &["add", "-f", "-m", "again"])
+            .success()
+            .stderr(predicate::str::contains(format!(
+                "Overwriting existing notes for object {}",
+                commits[1]
+            )));
+
+        notes(&test_git_repo, &["append", "-m", "more"]).success();
+        assert_eq!(show_note(&test_git_repo, &["show"]), "again\n\nmore\n");
+
+        notes(&test_git_repo, &["show", &commits[0]])
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "error: no note found for object {}.",
+                commits[0]
+            )));
+    }
+
+    #[test]
+    fn should_list_copy_and_remove_notes() {
+        let (test_git_repo, commits) = setup_repo();
+        let (first, second) = (&commits[0], &commits[1]);
+
+        notes(&test_git_repo, &["add", "-m", "note", first]).success();
+        let note = test_git_repo.hash_object("note\n");
+        assert_eq!(show_note(&test_git_repo, &[]), format!("{note} {first}\n"));
+        assert_eq!(
+            show_note(&test_git_repo, &["list", first]),
+            format!("{note}\n")
+        );
+
+        notes(&test_git_repo, &["copy", second, first])
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "error: Cannot copy notes. Found existing notes for object {first}."
+            )));
+        notes(&test_git_repo, &["copy", "-f", second, first])
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "error: missing notes on source object {second}. Cannot copy."
+            )));
+        notes(&test_git_repo, &["copy", first, second]).success();
+        let mut expected = [format!("{note} {first}"), format!("{note} {second}")];
+        expected.sort();
+        assert_eq!(
+            show_note(&test_git_repo, &["list"]),
+            format!("{}\n", expected.join("\n"))
+        );
+
+        notes(&test_git_repo, &["remove", first, second])
+            .success()
+            .stderr(predicate::str::contains(format!(
+                "Removing note for object {first}"
+            )));
+        assert_eq!(show_note(&test_git_repo, &["list"]), "");
+
+        notes(&test_git_repo, &["remove", first])
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "Object {first} has no note"
+            )));
+        notes(&test_git_repo, &["remove", "--ignore-missing", first]).success();
+    }
+
+    #[test]
+    fn should_edit_notes_with_editor() {
+        let (test_git_repo, _) = setup_repo();
+
+        notes(&test_git_repo, &["add", "-m", "draft"]).success();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_EDITOR", "sed -i s/draft/final/")
+            .args(["notes", "edit"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert_eq!(show_note(&test_git_repo, &["show"]), "final\n");
+
+        // Emptying the note removes it.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_EDITOR", "sed -i 1d")
+            .args(["notes", "edit"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert_eq!(show_note(&test_git_repo, &["list"]), "");
+    }
+
+    #[test]
+    fn should_use_alternative_notes_refs() {
+        let (test_git_repo, _) = setup_repo();
+
+        notes(
+            &test_git_repo,
+            &["--ref", "review", "add", "-m", "looks good"],
+        )
+        .success();
+        test_git_repo
+            .git_dir()
+            .child("refs/notes/review")
+            .assert(predicate::path::exists());
+        test_git_repo.assert_no_ref_file("refs/notes/commits");
+        assert_eq!(
+            show_note(&test_git_repo, &["show", "--ref=notes/review"]),
+            "looks good\n"
+        );
+        assert_eq!(
+            show_note(&test_git_repo, &["--ref", "review", "get-ref"]),
+            "refs/notes/review\n"
+        );
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_NOTES_REF", "refs/notes/review")
+            .args(["notes", "show"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("looks good\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_NOTES_REF", "refs/heads/main")
+            .args(["notes", "add", "-m", "x"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "fatal: refusing to add notes in refs/heads/main (outside of refs/notes/)",
+            ));
+    }
+
+    #[test]
+    fn should_merge_notes() {
+        let (test_git_repo, commits) = setup_repo();
+        let (first, second) = (&commits[0], &commits[1]);
+
+        notes(&test_git_repo, &["add", "-m", "base", first]).success();
+        let base = test_git_repo.assert_ref_file_read("refs/notes/commits");
+        test_git_repo.update_ref("refs/notes/other", base.trim());
+
+        notes(&test_git_repo, &["merge", "other"])
+            .success()
+            .stdout(predicate::str::contains("Already up to date."));
+
+        notes(
+            &test_git_repo,
+            &["--ref", "other", "add", "-m", "theirs", second],
+        )
+        .success();
+        notes(&test_git_repo, &["merge", "other"])
+            .success()
+            .stdout(predicate::str::contains("Fast-forward"));
+        assert_eq!(show_note(&test_git_repo, &["show", second]), "theirs\n");
+
+        notes(&test_git_repo, &["add", "-f", "-m", "ours", second]).success();
+        notes(
+            &test_git_repo,
+            &["--ref", "other", "add", "-f", "-m", "remote", second],
+        )
+        .success();
+        notes(&test_git_repo, &["merge", "-s", "union", "other"]).success();
+        assert_eq!(
+            show_note(&test_git_repo, &["show", second]),
+            "ours\n\nremote\n"
+        );
+        assert_eq!(show_note(&test_git_repo, &["show", first]), "base\n");
+    }
+
+    #[test]
+    fn should_resolve_conflicting_notes_manually() {
+        let (test_git_repo, commits) = setup_repo();
+        let second = &commits[1];
+
+        notes(&test_git_repo, &["add", "-m", "base"]).success();
+        let base = test_git_repo.assert_ref_file_read("refs/notes/commits");
+        test_git_repo.update_ref("refs/notes/other", base.trim());
+        notes(&test_git_repo, &["add", "-f", "-m", "ours"]).success();
+        notes(
+            &test_git_repo,
+            &["--ref", "other", "add", "-f", "-m", "theirs"],
+        )
+        .success();
+
+        notes(&test_git_repo, &["merge", "other"])
+            .failure()
+            .stdout(predicate::str::contains(format!(
+                "CONFLICT (content): Merge conflict in notes for object {second}"
+            )))
+            .stderr(predicate::str::contains("Automatic notes merge failed."));
+
+        let worktree = test_git_repo
+            .git_dir()
+            .child(format!("NOTES_MERGE_WORKTREE/{second}"));
+        worktree.assert(predicate::str::contains("<<<<<<< refs/notes/commits"));
+        test_git_repo.assert_ref_file("NOTES_MERGE_REF", "ref: refs/notes/commits");
+
+        notes(&test_git_repo, &["merge", "other"])
+            .failure()
+            .stderr(predicate::str::contains(
+                "fatal: You have not concluded your previous notes merge",
+            ));
+
+        std::fs::write(worktree.path(), "resolved\n").unwrap();
+        notes(&test_git_repo, &["merge", "--commit"]).success();
+        assert_eq!(show_note(&test_git_repo, &["show"]), "resolved\n");
+        test_git_repo
+            .git_dir()
+            .child("NOTES_MERGE_WORKTREE")
+            .assert(predicate::path::missing());
+        test_git_repo.assert_no_ref_file("NOTES_MERGE_PARTIAL");
+    }
+
+    #[test]
+    fn should_fan_out_large_notes_trees() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        let blobs = (0..300)
+            .map(|i| test_git_repo.hash_object(&format!("blob {i}")))
+            .collect::<Vec<_>>();
+        for blob in &blobs {
+            notes(&test_git_repo, &["add", "-m", "annotated", blob]).success();
+        }
+
+        // Notes are split into 2-character directories once there are too many for one tree.
+        let tip = test_git_repo.assert_ref_file_read("refs/notes/commits");
+        let commit = test_git_repo.cat_file("-p", tip.trim());
+        let tree = commit.lines().next().unwrap().trim_start_matches("tree ");
+        let root = test_git_repo.cat_file("-p", tree);
+        assert!(root.lines().all(|line| line.contains(" tree ")));
+
+        assert_eq!(
+            show_note(&test_git_repo, &["show", &blobs[42]]),
+            "annotated\n"
+        );
+        assert_eq!(show_note(&test_git_repo, &["list"]).lines().count(), 300);
+    }
+}
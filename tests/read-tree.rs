@@ -0,0 +1,224 @@
+mod integration_tests {
+    use std::fs;
+
+    use assert_cmd::Command;
+    use assert_fs::{assert::PathAssert, fixture::PathChild};
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    /// Writes a tree holding the provided files, without touching the worktree. The index is
+    /// left holding the tree.
+    fn write_tree_of(test_git_repo: &TestGitRepo, files: &[(&str, &str)]) -> String {
+        let _ = fs::remove_file(test_git_repo.git_dir().join("index"));
+        for (path, contents) in files {
+            let id = test_git_repo.hash_object(contents);
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .args(["update-index", "--add", "--cacheinfo"])
+                .arg(format!("100644,{id},{path}"))
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+                .success();
+        }
+        test_git_repo.write_tree()
+    }
+
+    fn read_tree(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("read-tree")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    fn ls_files_stage(test_git_repo: &TestGitRepo) -> String {
+        let output = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "-s"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn should_replace_index_with_tree() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let tree = write_tree_of(&test_git_repo, &[("a.txt", "a"), ("dir/b.txt", "b")]);
+        write_tree_of(&test_git_repo, &[("c.txt", "c")]);
+
+        read_tree(&test_git_repo, &[&tree]).success();
+
+        assert_eq!(test_git_repo.ls_files(), "a.txt\ndir/b.txt");
+        assert_eq!(test_git_repo.write_tree(), tree);
+    }
+
+    #[test]
+    fn should_read_tree_under_prefix() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let tree = write_tree_of(&test_git_repo, &[("a.txt", "a")]);
+
+        read_tree(&test_git_repo, &["--prefix=sub/", &tree]).success();
+        assert_eq!(test_git_repo.ls_files(), "a.txt\nsub/a.txt");
+
+        read_tree(&test_git_repo, &["--prefix=sub", &tree])
+            .failure()
+            .stderr("error: Entry 'sub/a.txt' overlaps with 'sub/a.txt'.  Cannot bind.");
+    }
+
+    #[test]
+    fn should_fast_forward_index_and_worktree_with_two_trees() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let head = write_tree_of(&test_git_repo, &[("a.txt", "one"), ("gone.txt", "gone")]);
+        let merge = write_tree_of(&test_git_repo, &[("a.txt", "two"), ("new.txt", "new")]);
+        test_git_repo.temp_dir.create_test_file("a.txt", b"one");
+        test_git_repo.temp_dir.create_test_file("gone.txt", b"gone");
+        read_tree(&test_git_repo, &[&head]).success();
+
+        read_tree(&test_git_repo, &["-m", "-u", &head, &merge]).success();
+
+        assert_eq!(test_git_repo.ls_files(), "a.txt\nnew.txt");
+        test_git_repo.temp_dir.child("a.txt").assert("two");
+        test_git_repo.temp_dir.child("new.txt").assert("new");
+        test_git_repo
+            .temp_dir
+            .child("gone.txt")
+            .assert(predicate::path::missing());
+
+        // The stat data of the written files is recorded.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--refresh"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("");
+    }
+
+    #[test]
+    fn should_refuse_two_way_merge_losing_local_changes() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let head = write_tree_of(&test_git_repo, &[("a.txt", "one")]);
+        let merge = write_tree_of(&test_git_repo, &[("a.txt", "two")]);
+        test_git_repo.temp_dir.create_test_file("a.txt", b"one");
+        read_tree(&test_git_repo, &[&head]).success();
+        test_git_repo.temp_dir.create_test_file("a.txt", b"local");
+
+        read_tree(&test_git_repo, &["-m", "-u", &head, &merge])
+            .failure()
+            .stderr("error: Entry 'a.txt' not uptodate. Cannot merge.");
+        test_git_repo.temp_dir.child("a.txt").assert("local");
+
+        // Changes staged in the index which the merge doesn't touch are kept.
+        let staged = write_tree_of(&test_git_repo, &[("a.txt", "one"), ("b.txt", "b")]);
+        read_tree(&test_git_repo, &[&staged]).success();
+        test_git_repo.temp_dir.create_test_file("a.txt", b"one");
+        let merge = write_tree_of(&test_git_repo, &[("a.txt", "one"), ("c.txt", "c")]);
+        read_tree(&test_git_repo, &[&staged]).success();
+        read_tree(&test_git_repo, &["-m", &head, &merge]).success();
+        assert_eq!(test_git_repo.ls_files(), "a.txt\nb.txt\nc.txt");
+    }
+
+    #[test]
+    fn should_leave_conflicting_paths_unmerged_in_three_way_merge() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let base = write_tree_of(
+            &test_git_repo,
+            &[
+                ("both.txt", "base"),
+                ("theirs.txt", "base"),
+                ("same.txt", "base"),
+            ],
+        );
+        let ours = write_tree_of(
+            &test_git_repo,
+            &[
+                ("both.txt", "ours"),
+                ("theirs.txt", "base"),
+                ("same.txt", "new"),
+            ],
+        );
+        let theirs = write_tree_of(
+            &test_git_repo,
+            &[
+                ("both.txt", "theirs"),
+                ("theirs.txt", "theirs"),
+                ("same.txt", "new"),
+            ],
+        );
+        read_tree(&test_git_repo, &[&ours]).success();
+
+        read_tree(&test_git_repo, &["-m", &base, &ours, &theirs]).success();
+
+        let id = |contents| test_git_repo.hash_object(contents);
+        assert_eq!(
+            ls_files_stage(&test_git_repo),
+            format!(
+                "100644 {} 1\tboth.txt
+100644 {} 2\tboth.txt
+100644 {} 3\tboth.txt
+100644 {} 0\tsame.txt
+100644 {} 0\ttheirs.txt
+",
+                id("base"),
+                id("ours"),
+                id("theirs"),
+                id("new"),
+                id("theirs")
+            )
+        );
+
+        read_tree(&test_git_repo, &["-m", &ours])
+            .failure()
+            .stderr("fatal: You need to resolve your current index first");
+        read_tree(&test_git_repo, &["--reset", &ours]).success();
+        assert_eq!(test_git_repo.write_tree(), ours);
+    }
+
+    #[test]
+    fn should_resolve_removals_with_aggressive() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let base = write_tree_of(&test_git_repo, &[("a.txt", "a"), ("b.txt", "b")]);
+        let ours = write_tree_of(&test_git_repo, &[("a.txt", "a"), ("b.txt", "b")]);
+        let theirs = write_tree_of(&test_git_repo, &[("a.txt", "a")]);
+
+        read_tree(&test_git_repo, &[&ours]).success();
+        read_tree(&test_git_repo, &["-m", &base, &ours, &theirs]).success();
+        let b_id = test_git_repo.hash_object("b");
+        assert_eq!(
+            ls_files_stage(&test_git_repo),
+            format!(
+                "100644 {} 0\ta.txt\n100644 {b_id} 1\tb.txt\n100644 {b_id} 2\tb.txt\n",
+                test_git_repo.hash_object("a")
+            )
+        );
+
+        read_tree(&test_git_repo, &[&ours]).success();
+        read_tree(
+            &test_git_repo,
+            &["-m", "--aggressive", &base, &ours, &theirs],
+        )
+        .success();
+        assert_eq!(test_git_repo.write_tree(), theirs);
+    }
+
+    #[test]
+    fn should_require_merge_to_update_worktree() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        let tree = write_tree_of(&test_git_repo, &[("a.txt", "a")]);
+
+        read_tree(&test_git_repo, &["-u", &tree])
+            .failure()
+            .stderr("fatal: -u is meaningless without -m, --reset, or --prefix");
+        read_tree(&test_git_repo, &["does-not-exist"])
+            .failure()
+            .stderr("fatal: failed to unpack tree object does-not-exist");
+    }
+}
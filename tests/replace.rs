@@ -0,0 +1,178 @@
+mod integration_tests {
+    use std::str::from_utf8;
+
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use test_helpers::TestGitRepo;
+
+    /// Creates a repo with three commits, returned oldest first.
+    fn setup_repo() -> (TestGitRepo, Vec<String>) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        let commits = test_git_repo.commit_files(&["first", "second", "third"]);
+
+        (test_git_repo, commits)
+    }
+
+    fn replace(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("replace")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    fn cat_commit(test_git_repo: &TestGitRepo, global_args: &[&str], id: &str) -> String {
+        let cmd = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(global_args)
+            .arg("cat-file")
+            .arg("-p")
+            .arg(id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        String::from(from_utf8(&cmd.stdout).unwrap())
+    }
+
+    #[test]
+    fn should_replace_list_and_delete_objects() {
+        let (test_git_repo, commits) = setup_repo();
+        let (first, second) = (&commits[0], &commits[1]);
+
+        replace(&test_git_repo, &[first, second]).success();
+        test_git_repo.assert_ref_file(&format!("refs/replace/{first}"), second);
+
+        // Reads transparently return the replacement, unless replacements are disabled.
+        assert!(cat_commit(&test_git_repo, &[], first).ends_with("second"));
+        assert!(cat_commit(&test_git_repo, &["--no-replace-objects"], first).ends_with("first"));
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_NO_REPLACE_OBJECTS", "1")
+            .args(["cat-file", "-p", first])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("first"));
+
+        replace(&test_git_repo, &[])
+            .success()
+            .stdout(format!("{first}\n"));
+        replace(
+            &test_git_repo,
+            &["-l", "--format=medium", &format!("{}*", &first[..4])],
+        )
+        .success()
+        .stdout(format!("{first} -> {second}\n"));
+        replace(&test_git_repo, &["-l", "--format=long", "nothing*"])
+            .success()
+            .stdout("");
+        replace(&test_git_repo, &["--format=long"])
+            .success()
+            .stdout(format!("{first} (commit) -> {second} (commit)\n"));
+
+        replace(&test_git_repo, &[first, &commits[2]])
+            .failure()
+            .stderr(format!(
+                "error: replace ref 'refs/replace/{first}' already exists"
+            ));
+        replace(&test_git_repo, &["-f", first, &commits[2]]).success();
+        test_git_repo.assert_ref_file(&format!("refs/replace/{first}"), &commits[2]);
+
+        replace(&test_git_repo, &["-d", first, second])
+            .failure()
+            .stdout(format!("Deleted replace ref '{first}'\n"))
+            .stderr(format!("error: replace ref '{second}' not found"));
+        test_git_repo.assert_no_ref_file(&format!("refs/replace/{first}"));
+    }
+
+    #[test]
+    fn should_require_objects_of_the_same_type() {
+        let (test_git_repo, commits) = setup_repo();
+        let blob = test_git_repo.hash_object("first.txt");
+
+        replace(&test_git_repo, &[&commits[0], &blob])
+            .failure()
+            .stderr(format!(
+                "error: Objects must be of the same type.
+'{}' points to a replaced object of type 'commit'
+while '{blob}' points to a replacement object of type 'blob'.",
+                commits[0]
+            ));
+
+        replace(&test_git_repo, &["-f", &commits[0], &blob]).success();
+        replace(&test_git_repo, &["-l", "--format=short", "-f"])
+            .failure()
+            .stderr("fatal: -f only makes sense when writing a replacement");
+    }
+
+    #[test]
+    fn should_graft_commits_onto_new_parents() {
+        let (test_git_repo, commits) = setup_repo();
+        let (first, third) = (&commits[0], &commits[2]);
+
+        // Grafting a commit onto its current parents changes nothing.
+        replace(&test_git_repo, &["--graft", third, &commits[1]])
+            .success()
+            .stderr(format!("warning: graft for '{third}' unnecessary\n"));
+
+        replace(&test_git_repo, &["--graft", third, first]).success();
+        assert!(cat_commit(&test_git_repo, &[], third).contains(&format!("parent {first}\n")));
+
+        // History is walked through the graft, skipping the second commit.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-ref", "refs/heads/parent", "HEAD~1"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        test_git_repo.assert_ref_file("refs/heads/parent", first);
+
+        replace(&test_git_repo, &["-g", "main", "nope"])
+            .failure()
+            .stderr("error: not a valid object name: 'nope'");
+    }
+
+    #[test]
+    fn should_edit_objects() {
+        let (test_git_repo, commits) = setup_repo();
+        let third = &commits[2];
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_EDITOR", "sed -i s/third/edited/")
+            .args(["replace", "--edit", third])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert!(cat_commit(&test_git_repo, &[], third).ends_with("edited"));
+        assert!(cat_commit(&test_git_repo, &["--no-replace-objects"], third).ends_with("third"));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_EDITOR", "true")
+            .args(["replace", "-e", third, "-f"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_EDITOR", "false")
+            .args(["replace", "-e", &commits[0]])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(
+                "error: there was a problem with the editor 'false'
+error: editing object file failed",
+            );
+    }
+}
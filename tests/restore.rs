@@ -1,5 +1,10 @@
 mod integration_tests {
-    use std::io::Write;
+    use std::{
+        fs,
+        io::Write,
+        os::unix::fs::{symlink, MetadataExt, PermissionsExt},
+        path::PathBuf,
+    };
 
     use assert_cmd::{assert::OutputAssertExt, Command};
     use assert_fs::{assert::PathAssert, fixture::PathChild};
@@ -95,4 +100,90 @@ mod integration_tests {
 
         test_git_repo.temp_dir.child("test.txt").assert("original contents");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_restore_symlinks_and_executable_bits() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("run.sh", b"run");
+        fs::set_permissions(test_git_repo.temp_dir.child("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("run.sh", test_git_repo.temp_dir.child("link")).unwrap();
+
+        test_git_repo.init();
+        test_git_repo.add("run.sh link");
+        fs::remove_file(test_git_repo.temp_dir.child("run.sh")).unwrap();
+        fs::remove_file(test_git_repo.temp_dir.child("link")).unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["restore", "run.sh", "link"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        let mode = fs::metadata(test_git_repo.temp_dir.child("run.sh")).unwrap().permissions().mode();
+        assert_ne!(mode & 0o100, 0);
+        assert_eq!(fs::read_link(test_git_repo.temp_dir.child("link")).unwrap(), PathBuf::from("run.sh"));
+    }
+
+    #[test]
+    fn should_restore_symlinks_as_plain_files_without_symlinks() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        symlink("test.txt", test_git_repo.temp_dir.child("link")).unwrap();
+
+        test_git_repo.init();
+        test_git_repo.add("link");
+        test_git_repo.write_config(b"
+[core]
+symlinks = false");
+        fs::remove_file(test_git_repo.temp_dir.child("link")).unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["restore", "link"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        // The plain file holding the target still matches the index entry.
+        test_git_repo.temp_dir.child("link").assert("test.txt");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--refresh"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("");
+    }
+
+    #[test]
+    fn should_only_rewrite_modified_files_and_refresh_them() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("changed.txt", b"changed");
+        test_git_repo.temp_dir.create_test_file("same.txt", b"same");
+
+        test_git_repo.init();
+        test_git_repo.add("changed.txt same.txt");
+        test_git_repo.temp_dir.create_test_file("changed.txt", b"updated file");
+        // Files which are rewritten lose the extra link.
+        fs::hard_link(test_git_repo.temp_dir.child("same.txt"), test_git_repo.temp_dir.child("link")).unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["restore", "changed.txt", "same.txt"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        test_git_repo.temp_dir.child("changed.txt").assert("changed");
+        assert_eq!(fs::metadata(test_git_repo.temp_dir.child("same.txt")).unwrap().nlink(), 2);
+        // The stat data of the restored file is up to date, so it doesn't show up as changed.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("diff-files")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("");
+    }
+}
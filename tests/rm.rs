@@ -220,4 +220,69 @@ test2.txt");
         .failure()
         .stderr("path \"../test.txt\" is outside of repo");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_fail_to_remove_locally_modified_files_without_force_flag() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.temp_dir.create_test_file("test2.txt", b"test2");
+
+        test_git_repo.init();
+        test_git_repo.add("test.txt");
+        test_git_repo.add("test2.txt");
+        test_git_repo.temp_dir.create_test_file("test.txt", b"changed");
+
+        Command::cargo_bin("rust-git")
+        .unwrap()
+        .arg("rm")
+        .arg("test.txt")
+        .arg("test2.txt")
+        .current_dir(test_git_repo.temp_dir.path())
+        .assert()
+        .failure()
+        .stderr("error: the following file has local modifications:
+    test.txt
+(use --cached to keep the file, or -f to force removal)
+");
+
+        assert_eq!(test_git_repo.ls_files(), "test.txt\ntest2.txt");
+
+        Command::cargo_bin("rust-git")
+        .unwrap()
+        .arg("rm")
+        .arg("-f")
+        .arg("test.txt")
+        .current_dir(test_git_repo.temp_dir.path())
+        .assert()
+        .success();
+
+        test_git_repo.temp_dir.child("test.txt").assert(predicate::path::missing());
+    }
+
+    #[test]
+    fn should_rm_files_matching_pathspec() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("dir");
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a");
+        test_git_repo.temp_dir.create_test_file("b.md", b"b");
+        test_git_repo.temp_dir.create_test_file("dir/c.txt", b"c");
+        test_git_repo.add(".");
+
+        let rm = |args: &[&str]| {
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .args(["rm", "--cached"])
+                .args(args)
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+        };
+
+        rm(&["dir"])
+            .failure()
+            .stderr("fatal: not removing 'dir' recursively without -r");
+        rm(&["*.txt", ":(exclude)dir"]).success().stdout("rm 'a.txt'\n");
+        rm(&["-r", "dir"]).success().stdout("rm 'dir/c.txt'\n");
+        assert_eq!(test_git_repo.ls_files(), "b.md");
+    }
+}
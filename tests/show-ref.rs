@@ -0,0 +1,201 @@
+mod integration_tests {
+    use assert_cmd::Command;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> (TestGitRepo, String, String) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let commit_obj_id = test_git_repo.commit_tree(&tree_obj_id, "Test commit");
+        test_git_repo.update_ref("refs/heads/main", &commit_obj_id);
+        test_git_repo.update_ref("refs/remotes/origin/main", &commit_obj_id);
+        test_git_repo.symbolic_ref("HEAD", "refs/heads/main");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("v1.0")
+            .arg(&commit_obj_id)
+            .arg("-m")
+            .arg("Test tag")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        let tag_obj_id = test_git_repo.assert_ref_file_read("refs/tags/v1.0");
+
+        (test_git_repo, commit_obj_id, tag_obj_id)
+    }
+
+    #[test]
+    fn should_list_refs() {
+        let (test_git_repo, commit_obj_id, tag_obj_id) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "{commit_obj_id} refs/heads/main
+{commit_obj_id} refs/remotes/origin/main
+{tag_obj_id} refs/tags/v1.0
+"
+            ));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--head")
+            .arg("--heads")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "{commit_obj_id} HEAD
+{commit_obj_id} refs/heads/main
+"
+            ));
+    }
+
+    #[test]
+    fn should_filter_refs_by_pattern() {
+        let (test_git_repo, commit_obj_id, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("main")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "{commit_obj_id} refs/heads/main
+{commit_obj_id} refs/remotes/origin/main
+"
+            ));
+
+        // Patterns only match complete path components.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("ain")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stdout("");
+    }
+
+    #[test]
+    fn should_dereference_tags() {
+        let (test_git_repo, commit_obj_id, tag_obj_id) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--tags")
+            .arg("-d")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!(
+                "{tag_obj_id} refs/tags/v1.0
+{commit_obj_id} refs/tags/v1.0^{{}}
+"
+            ));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--tags")
+            .arg("-d")
+            .arg("--hash=7")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!("{}\n{}\n", &tag_obj_id[..7], &commit_obj_id[..7]));
+    }
+
+    #[test]
+    fn should_verify_refs() {
+        let (test_git_repo, commit_obj_id, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--verify")
+            .arg("refs/heads/main")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!("{commit_obj_id} refs/heads/main\n"));
+
+        // --verify requires the full ref name.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--verify")
+            .arg("main")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: 'main' - not a valid ref");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--verify")
+            .arg("--quiet")
+            .arg("refs/heads/main")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--verify")
+            .arg("--quiet")
+            .arg("refs/heads/missing")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stdout("")
+            .stderr("");
+    }
+
+    #[test]
+    fn should_check_ref_exists() {
+        let (test_git_repo, _, _) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--exists")
+            .arg("refs/heads/main")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show-ref")
+            .arg("--exists")
+            .arg("refs/heads/missing")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .code(2)
+            .stderr("error: reference does not exist");
+    }
+}
@@ -0,0 +1,77 @@
+mod integration_tests {
+    use std::str::from_utf8;
+
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn setup_repo() -> (TestGitRepo, String) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("file.txt", b"contents");
+        test_git_repo.add("file.txt");
+        let cmd = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["commit", "-m", "Initial commit"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .unwrap();
+        let commit = String::from(from_utf8(&cmd.stdout).unwrap().trim());
+
+        (test_git_repo, commit)
+    }
+
+    fn show(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("show")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    #[test]
+    fn should_show_commit_with_notes() {
+        let (test_git_repo, commit) = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["notes", "add", "-m", "A note"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        show(&test_git_repo, &[])
+            .success()
+            .stdout(predicate::str::starts_with(format!("commit {commit}\n")))
+            .stdout(predicate::str::contains(
+                "Author: Test User <test@user.com>\n",
+            ))
+            .stdout(predicate::str::ends_with(
+                "    Initial commit\n\nNotes:\n    A note\n",
+            ));
+        show(&test_git_repo, &["--no-notes", "--oneline"])
+            .success()
+            .stdout(format!("{} Initial commit\n", &commit[..7]));
+    }
+
+    #[test]
+    fn should_show_trees_and_blobs() {
+        let (test_git_repo, _) = setup_repo();
+        let tree = test_git_repo.write_tree();
+        let blob = test_git_repo.hash_object("contents");
+
+        show(&test_git_repo, &[&tree])
+            .success()
+            .stdout(format!("tree {tree}\n\nfile.txt\n"));
+        show(&test_git_repo, &[&blob]).success().stdout("contents");
+    }
+}
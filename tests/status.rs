@@ -0,0 +1,347 @@
+mod integration_tests {
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn status(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        test_git_repo.git(&[&["status"], args].concat())
+    }
+
+    fn setup_repo() -> (TestGitRepo, String) {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a");
+        test_git_repo.temp_dir.create_test_file("b.txt", b"b");
+        test_git_repo
+            .temp_dir
+            .create_test_file(".gitignore", b"*.log\n");
+        test_git_repo.add("a.txt b.txt .gitignore");
+        let first_commit = test_git_repo.commit("First commit");
+        (test_git_repo, first_commit)
+    }
+
+    #[test]
+    fn should_show_long_status_for_repo_without_commits() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_file("a.txt", b"a");
+        test_git_repo.temp_dir.create_test_dir("dir");
+        test_git_repo.temp_dir.create_test_file("dir/b.txt", b"b");
+
+        status(&test_git_repo, &[]).success().stdout(
+            "On branch main
+
+No commits yet
+
+Untracked files:
+  (use \"git add <file>...\" to include in what will be committed)
+\ta.txt
+\tdir/
+
+nothing added to commit but untracked files present (use \"git add\" to track)
+",
+        );
+
+        test_git_repo.add("a.txt");
+        status(&test_git_repo, &["-uno"]).success().stdout(
+            "On branch main
+
+No commits yet
+
+Changes to be committed:
+  (use \"git rm --cached <file>...\" to unstage)
+\tnew file:   a.txt
+
+Untracked files not listed (use -u option to show untracked files)
+",
+        );
+    }
+
+    #[test]
+    fn should_show_staged_unstaged_and_untracked_changes() {
+        let (test_git_repo, first_commit) = setup_repo();
+        test_git_repo.temp_dir.create_test_file("c.txt", b"c");
+        test_git_repo.add("c.txt");
+        test_git_repo.commit("Second commit");
+        test_git_repo.update_ref("refs/remotes/origin/main", &first_commit);
+        test_git_repo
+            .git(&["branch", "--set-upstream-to=origin/main"])
+            .success();
+
+        status(&test_git_repo, &[]).success().stdout(
+            "On branch main
+Your branch is ahead of 'origin/main' by 1 commit.
+  (use \"git push\" to publish your local commits)
+
+nothing to commit, working tree clean
+",
+        );
+
+        test_git_repo.git(&["mv", "a.txt", "renamed.txt"]).success();
+        test_git_repo.temp_dir.create_test_file("b.txt", b"changed");
+        test_git_repo.temp_dir.create_test_file("new.txt", b"new");
+        test_git_repo.temp_dir.create_test_file("debug.log", b"log");
+        std::fs::remove_file(test_git_repo.temp_dir.path().join("c.txt")).unwrap();
+
+        status(&test_git_repo, &[]).success().stdout(
+            "On branch main
+Your branch is ahead of 'origin/main' by 1 commit.
+  (use \"git push\" to publish your local commits)
+
+Changes to be committed:
+  (use \"git restore --staged <file>...\" to unstage)
+\trenamed:    a.txt -> renamed.txt
+
+Changes not staged for commit:
+  (use \"git add/rm <file>...\" to update what will be committed)
+  (use \"git restore <file>...\" to discard changes in working directory)
+\tmodified:   b.txt
+\tdeleted:    c.txt
+
+Untracked files:
+  (use \"git add <file>...\" to include in what will be committed)
+\tnew.txt
+
+",
+        );
+        status(&test_git_repo, &["-sb", "--ignored"])
+            .success()
+            .stdout(
+                "## main...origin/main [ahead 1]
+ M b.txt
+ D c.txt
+R  a.txt -> renamed.txt
+?? new.txt
+!! debug.log
+",
+            );
+    }
+
+    #[test]
+    fn should_show_porcelain_v2_with_nul_terminated_entries() {
+        let (test_git_repo, first_commit) = setup_repo();
+        test_git_repo.git(&["mv", "a.txt", "renamed.txt"]).success();
+        test_git_repo.temp_dir.create_test_file("b.txt", b"changed");
+        test_git_repo.temp_dir.create_test_file("new.txt", b"new");
+
+        let a_id = test_git_repo.hash_object("a");
+        let b_id = test_git_repo.hash_object("b");
+        status(&test_git_repo, &["--porcelain=v2", "-z", "--branch"])
+            .success()
+            .stdout(format!(
+                "# branch.oid {first_commit}\0# branch.head main\0\
+1 .M N... 100644 100644 100644 {b_id} {b_id} b.txt\0\
+2 R. N... 100644 100644 100644 {a_id} {a_id} R100 renamed.txt\0a.txt\0\
+? new.txt\0"
+            ));
+        status(&test_git_repo, &["-z"])
+            .success()
+            .stdout(" M b.txt\0R  renamed.txt\0a.txt\0?? new.txt\0");
+    }
+
+    #[test]
+    fn should_show_intent_to_add_paths_as_unstaged() {
+        let (test_git_repo, _) = setup_repo();
+        test_git_repo.temp_dir.create_test_file("new.txt", b"new");
+        test_git_repo.git(&["add", "-N", "new.txt"]).success();
+
+        status(&test_git_repo, &["--short"])
+            .success()
+            .stdout(" A new.txt\n");
+        status(&test_git_repo, &["--porcelain=v2"])
+            .success()
+            .stdout(format!(
+                "1 .A N... 000000 000000 100644 {0} {0} new.txt\n",
+                "0".repeat(40)
+            ));
+        status(&test_git_repo, &[]).success().stdout(
+            "On branch main
+Changes not staged for commit:
+  (use \"git add <file>...\" to update what will be committed)
+  (use \"git restore <file>...\" to discard changes in working directory)
+\tnew file:   new.txt
+
+no changes added to commit (use \"git add\" and/or \"git commit -a\")
+",
+        );
+    }
+
+    #[test]
+    fn should_show_untracked_files_by_mode() {
+        let (test_git_repo, _) = setup_repo();
+        test_git_repo.temp_dir.create_test_dir("dir/sub");
+        test_git_repo.temp_dir.create_test_dir("logs");
+        test_git_repo.temp_dir.create_test_file("dir/x.txt", b"x");
+        test_git_repo
+            .temp_dir
+            .create_test_file("dir/sub/y.txt", b"y");
+        test_git_repo.temp_dir.create_test_file("logs/z.log", b"z");
+
+        status(&test_git_repo, &["-s"])
+            .success()
+            .stdout("?? dir/\n");
+        status(&test_git_repo, &["-s", "-uall"])
+            .success()
+            .stdout("?? dir/sub/y.txt\n?? dir/x.txt\n");
+        status(&test_git_repo, &["-s", "-uno"]).success().stdout("");
+        status(&test_git_repo, &["-s", "--ignored"])
+            .success()
+            .stdout("?? dir/\n!! logs/\n");
+        status(&test_git_repo, &["-s", "--ignored", "-uall"])
+            .success()
+            .stdout("?? dir/sub/y.txt\n?? dir/x.txt\n!! logs/z.log\n");
+        status(&test_git_repo, &["-s", "dir/sub"])
+            .success()
+            .stdout("?? dir/\n");
+    }
+
+    #[test]
+    fn should_show_unmerged_paths() {
+        let (test_git_repo, _) = setup_repo();
+        let a_id = test_git_repo.hash_object("a");
+        let b_id = test_git_repo.hash_object("b");
+        let mut cmd = Command::cargo_bin("rust-git").unwrap();
+        cmd.args(["update-index", "--index-info"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .write_stdin(format!(
+                "0 {zero}\tb.txt\n100644 {a_id} 1\tb.txt\n100644 {b_id} 2\tb.txt\n100644 {a_id} 3\tb.txt\n100644 {a_id} 2\tc.txt\n",
+                zero = "0".repeat(40)
+            ))
+            .assert()
+            .success();
+
+        status(&test_git_repo, &["-s"])
+            .success()
+            .stdout("UU b.txt\nAU c.txt\n");
+        status(&test_git_repo, &["--porcelain=v2"])
+            .success()
+            .stdout(format!(
+                "u UU N... 100644 100644 100644 100644 {a_id} {b_id} {a_id} b.txt
+u AU N... 000000 100644 000000 000000 {zero} {a_id} {zero} c.txt
+",
+                zero = "0".repeat(40)
+            ));
+        status(&test_git_repo, &[]).success().stdout(
+            "On branch main
+Unmerged paths:
+  (use \"git restore --staged <file>...\" to unstage)
+  (use \"git add <file>...\" to mark resolution)
+\tboth modified:   b.txt
+\tadded by us:     c.txt
+
+no changes added to commit (use \"git add\" and/or \"git commit -a\")
+",
+        );
+    }
+
+    #[test]
+    fn should_detect_similar_and_copied_files() {
+        let (test_git_repo, _) = setup_repo();
+        let numbers = (1..=20).map(|n| format!("{n}\n")).collect::<String>();
+        test_git_repo
+            .temp_dir
+            .create_test_file("numbers.txt", numbers.as_bytes());
+        test_git_repo.add("numbers.txt");
+        test_git_repo.commit("Second commit");
+
+        test_git_repo
+            .git(&["mv", "numbers.txt", "moved.txt"])
+            .success();
+        test_git_repo
+            .temp_dir
+            .create_test_file("moved.txt", numbers.replace("20\n", "").as_bytes());
+        test_git_repo.add("moved.txt");
+        status(&test_git_repo, &["-s"])
+            .success()
+            .stdout("R  numbers.txt -> moved.txt\n");
+        status(&test_git_repo, &["-s", "--find-renames=99%"])
+            .success()
+            .stdout("A  moved.txt\nD  numbers.txt\n");
+        status(&test_git_repo, &["-s", "--no-renames"])
+            .success()
+            .stdout("A  moved.txt\nD  numbers.txt\n");
+
+        test_git_repo.temp_dir.create_test_file("a.txt", b"changed");
+        test_git_repo.temp_dir.create_test_file("copy.txt", b"a");
+        test_git_repo.add("a.txt copy.txt");
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"
+
+[status]
+renames = \"copies\"",
+        );
+        status(&test_git_repo, &["-s"])
+            .success()
+            .stdout("M  a.txt\nC  a.txt -> copy.txt\nR  numbers.txt -> moved.txt\n");
+    }
+
+    #[test]
+    fn should_color_status() {
+        let (test_git_repo, first_commit) = setup_repo();
+        test_git_repo.temp_dir.create_test_file("c.txt", b"c");
+        test_git_repo.add("c.txt");
+        test_git_repo.commit("Second commit");
+        test_git_repo.update_ref("refs/remotes/origin/main", &first_commit);
+        test_git_repo
+            .git(&["branch", "--set-upstream-to=origin/main"])
+            .success();
+        test_git_repo.temp_dir.create_test_file("b.txt", b"changed");
+        test_git_repo.temp_dir.create_test_file("d.txt", b"d");
+        test_git_repo.add("d.txt");
+        test_git_repo.temp_dir.create_test_file("new.txt", b"new");
+
+        status(&test_git_repo, &["-sb", "--color"])
+            .success()
+            .stdout(
+                "## \x1b[32mmain\x1b[m...\x1b[31morigin/main\x1b[m [ahead \x1b[32m1\x1b[m]
+ \x1b[31mM\x1b[m b.txt
+\x1b[32mA\x1b[m  d.txt
+\x1b[31m??\x1b[m new.txt
+",
+            );
+        status(&test_git_repo, &["--porcelain", "--color"])
+            .success()
+            .stdout(" M b.txt\nA  d.txt\n?? new.txt\n");
+        status(&test_git_repo, &["--color=always"])
+            .success()
+            .stdout(predicates::str::contains(
+                "\t\x1b[32mnew file:   d.txt\x1b[m
+",
+            ))
+            .stdout(predicates::str::contains(
+                "\t\x1b[31mmodified:   b.txt\x1b[m
+",
+            ))
+            .stdout(predicates::str::contains("\t\x1b[31mnew.txt\x1b[m\n"));
+
+        test_git_repo.write_config(
+            b"
+[color]
+ui = \"always\"
+
+[color.status]
+branch = \"magenta\"
+added = \"bold\"",
+        );
+        status(&test_git_repo, &[])
+            .success()
+            .stdout(predicates::str::starts_with(
+                "On branch \x1b[35mmain\x1b[m\n",
+            ))
+            .stdout(predicates::str::contains(
+                "\t\x1b[1mnew file:   d.txt\x1b[m\n",
+            ));
+        status(&test_git_repo, &["--no-color"])
+            .success()
+            .stdout(predicates::str::contains("\x1b").not());
+    }
+}
@@ -98,4 +98,108 @@ email = \"test@user.com\"",
             .success()
             .stdout("main\n");
     }
+
+    #[test]
+    fn should_fail_to_read_detached_head() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let commit_obj_id = test_git_repo.commit_tree(&tree_obj_id, "Test commit");
+        test_git_repo
+            .temp_dir
+            .create_test_file(".git/HEAD", commit_obj_id.as_bytes());
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("symbolic-ref")
+            .arg("HEAD")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: ref HEAD is not a symbolic ref");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("symbolic-ref")
+            .arg("--quiet")
+            .arg("HEAD")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("");
+    }
+
+    #[test]
+    fn should_resolve_head_within_namespace() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+        test_git_repo.add("test.txt");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("symbolic-ref")
+            .arg("HEAD")
+            .arg("refs/heads/trunk")
+            .env("GIT_NAMESPACE", "foo")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        // The target of the namespaced HEAD is stored with the namespace prefix, as in C Git.
+        test_git_repo.assert_ref_file(
+            "refs/namespaces/foo/HEAD",
+            "ref: refs/namespaces/foo/refs/heads/trunk",
+        );
+        test_git_repo.assert_ref_file("HEAD", "ref: refs/heads/main");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("symbolic-ref")
+            .arg("HEAD")
+            .env("GIT_NAMESPACE", "foo")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/heads/trunk\n");
+
+        // Committing moves the branch HEAD points at in the namespace.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("--namespace=foo")
+            .arg("commit")
+            .arg("-m")
+            .arg("Namespaced commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        let commit_id = test_git_repo.assert_ref_file_read("refs/namespaces/foo/refs/heads/trunk");
+        test_git_repo.assert_no_ref_file("refs/heads/trunk");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("--namespace=foo")
+            .arg("branch")
+            .arg("-v")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(format!("* trunk {} Namespaced commit\n", &commit_id[..7]));
+    }
 }
@@ -268,4 +268,244 @@ email = \"test@user.com\"",
 
         test_git_repo.assert_ref_file("refs/tags/v1.0", &commit_obj_id);
     }
+
+    #[test]
+    fn should_create_and_delete_hierarchical_tag() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let commit_obj_id = test_git_repo.commit_tree(&tree_obj_id, "Test commit");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("release/2024.1")
+            .arg(&commit_obj_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        test_git_repo.assert_ref_file("refs/tags/release/2024.1", &commit_obj_id);
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("bad..name")
+            .arg(&commit_obj_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: 'bad..name' is not a valid tag name.");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-d")
+            .arg("release/2024.1")
+            .arg("missing")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stdout(format!(
+                "Deleted tag 'release/2024.1' (was {})\n",
+                &commit_obj_id[..7]
+            ))
+            .stderr("error: tag 'missing' not found.");
+
+        test_git_repo.assert_no_ref_file("refs/tags/release/2024.1");
+    }
+
+    #[test]
+    fn should_list_loose_and_packed_tags_with_patterns_and_version_sort() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let commit_obj_id = test_git_repo.commit_tree(&tree_obj_id, "Test commit");
+
+        test_git_repo.temp_dir.create_test_file(
+            ".git/packed-refs",
+            format!(
+                "# pack-refs with: peeled fully-peeled sorted \n\
+                 {commit_obj_id} refs/tags/v1.10\n\
+                 {commit_obj_id} refs/tags/v1.2\n"
+            )
+            .as_bytes(),
+        );
+        test_git_repo.update_ref("refs/tags/v1.9", &commit_obj_id);
+        test_git_repo.update_ref("refs/tags/other", &commit_obj_id);
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("other\nv1.10\nv1.2\nv1.9\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-l")
+            .arg("--sort=version:refname")
+            .arg("v1.*")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("v1.2\nv1.9\nv1.10\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("--list")
+            .arg("--sort=-version:refname")
+            .arg("--format=%(refname) %(objecttype)")
+            .arg("v1.1*")
+            .arg("v1.2")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("refs/tags/v1.10 commit\nrefs/tags/v1.2 commit\n");
+
+        // Deleting a packed tag removes it from packed-refs.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-d")
+            .arg("v1.10")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-l")
+            .arg("v1*")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("v1.2\nv1.9\n");
+    }
+
+    #[test]
+    fn should_list_annotations_and_filter_by_commit() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let first_commit_id = test_git_repo.commit_tree(&tree_obj_id, "First commit");
+        test_git_repo.update_ref("refs/heads/main", &first_commit_id);
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("v1.0")
+            .arg("-m")
+            .arg("Release 1.0")
+            .arg("-m")
+            .arg("Second paragraph")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("message.txt", b"Release 2.0\n\nFrom a file\n");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("commit")
+            .arg("-m")
+            .arg("Second commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-F")
+            .arg("message.txt")
+            .arg("v2.0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        test_git_repo.update_ref("refs/tags/light", &first_commit_id);
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-n")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(
+                "light           First commit\n\
+                 v1.0            Release 1.0\n\
+                 v2.0            Release 2.0\n",
+            );
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-n3")
+            .arg("v2.0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("v2.0            Release 2.0\n    \n    From a file\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("--contains")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("v2.0\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("--no-contains")
+            .arg("HEAD")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("light\nv1.0\n");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("--points-at")
+            .arg(&first_commit_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("light\nv1.0\n");
+    }
 }
@@ -0,0 +1,418 @@
+mod integration_tests {
+    use std::{
+        fs::{self, File},
+        time::{Duration, SystemTime},
+    };
+
+    use assert_cmd::Command;
+    use assert_fs::{assert::PathAssert, fixture::PathChild};
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    fn index_version(test_git_repo: &TestGitRepo) -> u8 {
+        fs::read(test_git_repo.git_dir().join("index")).unwrap()[7]
+    }
+
+    fn setup_repo() -> TestGitRepo {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.temp_dir.create_test_dir("test_dir");
+        test_git_repo
+            .temp_dir
+            .create_test_file("test_dir/test.txt", b"test");
+        test_git_repo
+            .temp_dir
+            .create_test_file("test_dir/test2.txt", b"test2");
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.add("test.txt test_dir");
+        test_git_repo
+    }
+
+    #[test]
+    fn should_rewrite_index_in_requested_version() {
+        let test_git_repo = setup_repo();
+        let expected_files = "100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest.txt
+100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest_dir/test.txt
+100644 d606037cb232bfda7788a8322492312d55b2ae9d 0\ttest_dir/test2.txt
+";
+        assert_eq!(index_version(&test_git_repo), 2);
+
+        for (version, expected_version) in [("4", 4), ("3", 2), ("2", 2)] {
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .args(["update-index", "--index-version", version])
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+                .success();
+            // Without extended flags, version 3 indexes are written as version 2.
+            assert_eq!(index_version(&test_git_repo), expected_version);
+
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .args(["ls-files", "-s"])
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+                .success()
+                .stdout(expected_files);
+        }
+    }
+
+    #[test]
+    fn should_fail_for_unsupported_index_version() {
+        let test_git_repo = setup_repo();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--index-version", "5"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: index-version 5 not in range: 2..4");
+        assert_eq!(index_version(&test_git_repo), 2);
+    }
+
+    #[test]
+    fn should_write_new_index_in_configured_version() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        test_git_repo.write_config(b"[index]\nversion = 4");
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.add("test.txt");
+        assert_eq!(index_version(&test_git_repo), 4);
+
+        fs::remove_file(test_git_repo.git_dir().join("index")).unwrap();
+        test_git_repo.write_config(b"[index]\nversion = 9");
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["add", "test.txt"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "warning: index.version set, but the value is invalid.\nUsing version 3",
+            ));
+        assert_eq!(index_version(&test_git_repo), 2);
+
+        fs::remove_file(test_git_repo.git_dir().join("index")).unwrap();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_INDEX_VERSION", "4")
+            .args(["add", "test.txt"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert_eq!(index_version(&test_git_repo), 4);
+    }
+
+    fn update_index(test_git_repo: &TestGitRepo, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("update-index")
+            .args(args)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+    }
+
+    #[test]
+    fn should_refresh_unchanged_files() {
+        let test_git_repo = setup_repo();
+        test_git_repo
+            .temp_dir
+            .create_test_file("test_dir/test.txt", b"changed");
+        fs::remove_file(test_git_repo.temp_dir.path().join("test_dir/test2.txt")).unwrap();
+        // Touching a file changes its stat data, but not its contents.
+        File::options()
+            .write(true)
+            .open(test_git_repo.temp_dir.path().join("test.txt"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+
+        update_index(&test_git_repo, &["--refresh"])
+            .failure()
+            .stdout("test_dir/test.txt: needs update\ntest_dir/test2.txt: needs update\n");
+        update_index(&test_git_repo, &["--refresh", "--ignore-missing"])
+            .failure()
+            .stdout("test_dir/test.txt: needs update\n");
+        update_index(&test_git_repo, &["-q", "--really-refresh"])
+            .success()
+            .stdout("");
+
+        // The stat data of the touched file was refreshed.
+        let metadata = fs::metadata(test_git_repo.temp_dir.path().join("test.txt")).unwrap();
+        let mtime = metadata
+            .modified()
+            .unwrap()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "--debug"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!(
+                "  mtime: {}:{}\n",
+                mtime.as_secs(),
+                mtime.subsec_nanos()
+            )));
+    }
+
+    #[test]
+    fn should_detect_changes_to_racily_clean_entries() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.init();
+        // Without comparing ctimes, rewriting a file with the same size and mtime leaves its stat
+        // data unchanged, as happens when a file is modified right after being added.
+        test_git_repo.write_config(
+            b"
+[core]
+trustctime = false",
+        );
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        let set_mtime = |mtime| {
+            File::options()
+                .write(true)
+                .open(test_git_repo.temp_dir.path().join("test.txt"))
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap()
+        };
+
+        // Files modified no earlier than the index was written are racily clean.
+        let mtime = SystemTime::now() + Duration::from_secs(60);
+        set_mtime(mtime);
+        test_git_repo.add("test.txt");
+        test_git_repo.temp_dir.create_test_file("test.txt", b"TEST");
+        set_mtime(mtime);
+
+        update_index(&test_git_repo, &["--refresh"])
+            .failure()
+            .stdout("test.txt: needs update\n");
+
+        // Writing the index smudges the entry, so its stat data is never trusted again.
+        update_index(&test_git_repo, &["--index-version", "2"]).success();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "--debug"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("  size: 0\t"));
+        update_index(&test_git_repo, &["--refresh"])
+            .failure()
+            .stdout("test.txt: needs update\n");
+    }
+
+    fn ls_files_stage(test_git_repo: &TestGitRepo) -> String {
+        let output = Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["ls-files", "-s"])
+            .current_dir(test_git_repo.temp_dir.path())
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn should_add_and_remove_files_only_when_requested() {
+        let test_git_repo = setup_repo();
+        test_git_repo.temp_dir.create_test_file("new.txt", b"test2");
+
+        update_index(&test_git_repo, &["new.txt"]).failure().stderr(
+            "error: new.txt: cannot add to the index - missing --add option?
+fatal: Unable to process path new.txt",
+        );
+        update_index(&test_git_repo, &["--add", "new.txt"]).success();
+        assert!(ls_files_stage(&test_git_repo)
+            .contains("100644 d606037cb232bfda7788a8322492312d55b2ae9d 0\tnew.txt\n"));
+
+        fs::remove_file(test_git_repo.temp_dir.path().join("new.txt")).unwrap();
+        update_index(&test_git_repo, &["new.txt"]).failure().stderr(
+            "error: new.txt: does not exist and --remove not passed
+fatal: Unable to process path new.txt",
+        );
+        update_index(&test_git_repo, &["--remove", "new.txt"]).success();
+        // Files still in the worktree are only removed with --force-remove.
+        update_index(&test_git_repo, &["--remove", "test.txt"]).success();
+        update_index(&test_git_repo, &["--force-remove", "test_dir/test.txt"]).success();
+        assert_eq!(
+            ls_files_stage(&test_git_repo),
+            "100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest.txt
+100644 d606037cb232bfda7788a8322492312d55b2ae9d 0\ttest_dir/test2.txt
+"
+        );
+    }
+
+    #[test]
+    fn should_insert_entries_from_cacheinfo() {
+        let test_git_repo = setup_repo();
+        let id = "d606037cb232bfda7788a8322492312d55b2ae9d";
+
+        update_index(
+            &test_git_repo,
+            &["--cacheinfo", &format!("100755,{id},new.txt")],
+        )
+        .failure()
+        .stderr(
+            "error: new.txt: cannot add to the index - missing --add option?
+fatal: git update-index: --cacheinfo cannot add new.txt",
+        );
+        update_index(&test_git_repo, &["--cacheinfo", "100644,abc,new.txt"])
+            .failure()
+            .stderr("error: option 'cacheinfo' expects <mode>,<sha1>,<path>");
+
+        update_index(
+            &test_git_repo,
+            &["--add", "--cacheinfo", &format!("100755,{id},new.txt")],
+        )
+        .success();
+        // The file doesn't exist in the worktree, and can only be changed through the index.
+        test_git_repo
+            .temp_dir
+            .child("new.txt")
+            .assert(predicate::path::missing());
+        assert!(ls_files_stage(&test_git_repo).contains(&format!("100755 {id} 0\tnew.txt\n")));
+    }
+
+    #[test]
+    fn should_insert_entries_from_index_info() {
+        let test_git_repo = setup_repo();
+        let id = "d606037cb232bfda7788a8322492312d55b2ae9d";
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--index-info"])
+            .write_stdin(format!(
+                "100644 {id} 1\tconflict.txt
+100644 {id} 3\tconflict.txt
+120000 blob {id}\tlink
+0 0000000000000000000000000000000000000000\ttest.txt
+100644 {id}\t.git/config
+"
+            ))
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stderr("Ignoring path .git/config\n");
+        assert_eq!(
+            ls_files_stage(&test_git_repo),
+            format!(
+                "100644 {id} 1\tconflict.txt
+100644 {id} 3\tconflict.txt
+120000 {id} 0\tlink
+100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest_dir/test.txt
+100644 {id} 0\ttest_dir/test2.txt
+"
+            )
+        );
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--index-info"])
+            .write_stdin("100644 nothex\ttest.txt\n")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("fatal: malformed index info 100644 nothex\ttest.txt");
+    }
+
+    #[test]
+    fn should_change_executable_bit_of_entries() {
+        let test_git_repo = setup_repo();
+
+        update_index(&test_git_repo, &["--chmod=+x", "test.txt"]).success();
+        assert!(ls_files_stage(&test_git_repo)
+            .starts_with("100755 30d74d258442c7c65512eafab474568dd706c430 0\ttest.txt\n"));
+        update_index(&test_git_repo, &["--chmod=-x", "test.txt"]).success();
+        assert!(ls_files_stage(&test_git_repo)
+            .starts_with("100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest.txt\n"));
+    }
+
+    #[test]
+    fn should_mark_entries_as_unchanged_or_skipped() {
+        let test_git_repo = setup_repo();
+        let debug_flags = || {
+            let output = Command::cargo_bin("rust-git")
+                .unwrap()
+                .args(["ls-files", "--debug"])
+                .current_dir(test_git_repo.temp_dir.path())
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout)
+                .unwrap()
+                .lines()
+                .filter_map(|line| {
+                    line.split_once("flags: ")
+                        .map(|(_, flags)| flags.to_string())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        update_index(&test_git_repo, &["--assume-unchanged", "test.txt"]).success();
+        update_index(&test_git_repo, &["--skip-worktree", "test_dir/test.txt"]).success();
+        assert_eq!(debug_flags(), ["8000", "40004000", "0"]);
+
+        // Entries which aren't checked out are left alone, even if the file is missing.
+        fs::remove_file(test_git_repo.temp_dir.path().join("test_dir/test.txt")).unwrap();
+        update_index(&test_git_repo, &["test_dir/test.txt"]).success();
+
+        update_index(
+            &test_git_repo,
+            &[
+                "--no-assume-unchanged",
+                "--no-skip-worktree",
+                "test.txt",
+                "test_dir/test.txt",
+            ],
+        )
+        .success();
+        assert_eq!(debug_flags(), ["0", "0", "0"]);
+
+        update_index(&test_git_repo, &["--assume-unchanged", "missing.txt"])
+            .failure()
+            .stderr("fatal: Unable to mark file missing.txt");
+    }
+
+    #[test]
+    fn should_replace_entries_conflicting_with_directories_only_with_replace() {
+        let test_git_repo = setup_repo();
+        fs::remove_dir_all(test_git_repo.temp_dir.path().join("test_dir")).unwrap();
+        test_git_repo.temp_dir.create_test_file("test_dir", b"test");
+
+        update_index(&test_git_repo, &["--add", "test_dir"])
+            .failure()
+            .stderr(
+                "error: 'test_dir' appears as both a file and as a directory
+error: test_dir: cannot add to the index - missing --add option?
+fatal: Unable to process path test_dir",
+            );
+        update_index(&test_git_repo, &["--add", "--replace", "test_dir"]).success();
+        assert_eq!(
+            ls_files_stage(&test_git_repo),
+            "100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest.txt
+100644 30d74d258442c7c65512eafab474568dd706c430 0\ttest_dir
+"
+        );
+    }
+
+    #[test]
+    fn should_read_paths_from_stdin() {
+        let test_git_repo = setup_repo();
+        test_git_repo
+            .temp_dir
+            .create_test_file("new file.txt", b"new");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .args(["update-index", "--add", "--info-only", "-z", "--stdin"])
+            .write_stdin("new file.txt\0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        let id = "3e5126c4e761fd09582fc517918a1601b218dff0";
+        assert!(ls_files_stage(&test_git_repo).contains(&format!("100644 {id} 0\tnew file.txt\n")));
+        // With --info-only, the contents of the file aren't written to the object database.
+        test_git_repo.assert_no_obj_file(id);
+    }
+}
@@ -171,4 +171,59 @@ email = \"test@user.com\"",
 
         test_git_repo.assert_ref_file("refs/tags/v1.0", &commit_obj_id);
     }
+
+    #[test]
+    fn should_update_branch_through_head_unless_no_deref() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"",
+        );
+
+        test_git_repo.add("test.txt");
+        let tree_obj_id = test_git_repo.write_tree();
+        let commit_obj_id = test_git_repo.commit_tree(&tree_obj_id, "Test commit");
+
+        // HEAD points at refs/heads/main after init, so the branch is updated.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("update-ref")
+            .arg("HEAD")
+            .arg(&commit_obj_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        test_git_repo.assert_ref_file("HEAD", "ref: refs/heads/main");
+        test_git_repo.assert_ref_file("refs/heads/main", &commit_obj_id);
+
+        // With --no-deref HEAD itself is overwritten, detaching it. Tags can be peeled to the
+        // commit they point at.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("v1.0")
+            .arg(&commit_obj_id)
+            .arg("-m")
+            .arg("Test tag")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("update-ref")
+            .arg("--no-deref")
+            .arg("HEAD")
+            .arg("v1.0^{commit}")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        test_git_repo.assert_ref_file("HEAD", &commit_obj_id);
+    }
 }
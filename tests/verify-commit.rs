@@ -0,0 +1,202 @@
+mod integration_tests {
+    use std::process::Command as StdCommand;
+
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    /// Generates an SSH key in the test repo and configures it for signing and verification.
+    fn configure_ssh_signing(test_git_repo: &TestGitRepo) {
+        let key_path = test_git_repo.temp_dir.path().join("signing_key");
+        let status = StdCommand::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+            .arg(&key_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let public_key = std::fs::read_to_string(key_path.with_extension("pub")).unwrap();
+        test_git_repo.temp_dir.create_test_file(
+            "allowed_signers",
+            format!("test@user.com {public_key}").as_bytes(),
+        );
+
+        test_git_repo.write_config(
+            format!(
+                "
+[user]
+name = \"Test User\"
+email = \"test@user.com\"
+signingkey = \"{}\"
+
+[gpg]
+format = \"ssh\"
+
+[gpg.ssh]
+allowedSignersFile = \"{}\"",
+                key_path.display(),
+                test_git_repo
+                    .temp_dir
+                    .path()
+                    .join("allowed_signers")
+                    .display()
+            )
+            .as_bytes(),
+        );
+    }
+
+    #[test]
+    fn should_sign_and_verify_commit_with_ssh_key() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        configure_ssh_signing(&test_git_repo);
+
+        test_git_repo.add("test.txt");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("commit")
+            .arg("-S")
+            .arg("-m")
+            .arg("Signed commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        let head_id = test_git_repo.assert_ref_file_read("refs/heads/main");
+        let commit_contents = test_git_repo.cat_file("-p", &head_id);
+        assert!(commit_contents.contains("\ngpgsig -----BEGIN SSH SIGNATURE-----\n "));
+        assert!(commit_contents.ends_with(" -----END SSH SIGNATURE-----\n\nSigned commit"));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-commit")
+            .arg("HEAD")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stderr(predicate::str::starts_with(
+                "Good \"git\" signature for test@user.com with ED25519 key",
+            ));
+
+        // The verbose output shows the payload, without the signature.
+        let tree_id = test_git_repo.write_tree();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-commit")
+            .arg("-v")
+            .arg(&head_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with(format!("tree {tree_id}\n")))
+            .stdout(predicate::str::contains("gpgsig").not());
+    }
+
+    #[test]
+    fn should_fail_to_verify_unsigned_or_untrusted_commit() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        configure_ssh_signing(&test_git_repo);
+
+        test_git_repo.add("test.txt");
+        let tree_id = test_git_repo.write_tree();
+        let unsigned_id = test_git_repo.commit_tree(&tree_id, "Unsigned commit");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-commit")
+            .arg(&unsigned_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr("");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-commit")
+            .arg(&tree_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(format!(
+                "error: {tree_id}: cannot verify a non-commit object of type tree.\n"
+            ));
+
+        // Commits signed with keys which aren't in the allowed signers file don't verify.
+        let signed_id = String::from_utf8(
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .arg("commit-tree")
+                .arg("--gpg-sign")
+                .arg(&tree_id)
+                .arg("-m")
+                .arg("Signed commit")
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+                .success()
+                .get_output()
+                .stdout
+                .clone(),
+        )
+        .unwrap();
+        test_git_repo
+            .temp_dir
+            .create_test_file("allowed_signers", b"");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-commit")
+            .arg(signed_id.trim())
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::ends_with("No principal matched.\n"));
+    }
+
+    #[test]
+    fn should_fail_to_sign_with_missing_key() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.write_config(
+            b"
+[user]
+name = \"Test User\"
+email = \"test@user.com\"
+
+[commit]
+gpgsign = true
+
+[gpg]
+format = \"ssh\"",
+        );
+
+        test_git_repo.add("test.txt");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("commit")
+            .arg("-m")
+            .arg("Signed commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(
+                "fatal: either user.signingkey or gpg.ssh.defaultKeyCommand needs to be configured",
+            );
+
+        // --no-gpg-sign overrides commit.gpgSign.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("commit")
+            .arg("--no-gpg-sign")
+            .arg("-m")
+            .arg("Unsigned commit")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+    }
+}
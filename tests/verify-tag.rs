@@ -0,0 +1,191 @@
+mod integration_tests {
+    use std::process::Command as StdCommand;
+
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use test_helpers::{TempDirExt, TestGitRepo};
+
+    /// Generates an SSH key in the test repo and configures it for signing and verification.
+    fn configure_ssh_signing(test_git_repo: &TestGitRepo) {
+        let key_path = test_git_repo.temp_dir.path().join("signing_key");
+        let status = StdCommand::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+            .arg(&key_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let public_key = std::fs::read_to_string(key_path.with_extension("pub")).unwrap();
+        test_git_repo.temp_dir.create_test_file(
+            "allowed_signers",
+            format!("test@user.com {public_key}").as_bytes(),
+        );
+
+        test_git_repo.write_config(
+            format!(
+                "
+[user]
+name = \"Test User\"
+email = \"test@user.com\"
+signingkey = \"{}\"
+
+[gpg]
+format = \"ssh\"
+
+[gpg.ssh]
+allowedSignersFile = \"{}\"",
+                key_path.display(),
+                test_git_repo
+                    .temp_dir
+                    .path()
+                    .join("allowed_signers")
+                    .display()
+            )
+            .as_bytes(),
+        );
+    }
+
+    #[test]
+    fn should_sign_and_verify_tag_with_ssh_key() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        configure_ssh_signing(&test_git_repo);
+
+        test_git_repo.add("test.txt");
+        let tree_id = test_git_repo.write_tree();
+        let commit_id = test_git_repo.commit_tree(&tree_id, "Test commit");
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-s")
+            .arg("v1.0")
+            .arg("-m")
+            .arg("Release 1.0")
+            .arg(&commit_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        let tag_id = test_git_repo.assert_ref_file_read("refs/tags/v1.0");
+        let tag_contents = test_git_repo.cat_file("-p", &tag_id);
+        assert!(tag_contents.contains("\n\nRelease 1.0\n-----BEGIN SSH SIGNATURE-----\n"));
+        assert!(tag_contents.trim_end().ends_with("-----END SSH SIGNATURE-----"));
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-tag")
+            .arg("v1.0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("")
+            .stderr(predicate::str::starts_with(
+                "Good \"git\" signature for test@user.com with ED25519 key",
+            ));
+
+        // tag -v shows the tag contents, without the signature.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-v")
+            .arg("v1.0")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("\n\nRelease 1.0\n"));
+
+        // The signature isn't part of the annotation lines.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-n5")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success()
+            .stdout("v1.0            Release 1.0\n");
+    }
+
+    #[test]
+    fn should_fail_to_verify_unsigned_or_tampered_tag() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        configure_ssh_signing(&test_git_repo);
+
+        test_git_repo.add("test.txt");
+        let tree_id = test_git_repo.write_tree();
+        let commit_id = test_git_repo.commit_tree(&tree_id, "Test commit");
+        test_git_repo.update_ref("refs/tags/light", &commit_id);
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-a")
+            .arg("unsigned")
+            .arg("-m")
+            .arg("Unsigned")
+            .arg(&commit_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-tag")
+            .arg("unsigned")
+            .arg("light")
+            .arg("missing")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure()
+            .stderr(format!(
+                "error: no signature found\n\
+                 error: light: cannot verify a non-tag object of type commit.\n\
+                 error: tag 'missing' not found.\n"
+            ));
+
+        // A signature over different contents doesn't verify.
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("tag")
+            .arg("-s")
+            .arg("signed")
+            .arg("-m")
+            .arg("Signed")
+            .arg(&commit_id)
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        let tag_id = test_git_repo.assert_ref_file_read("refs/tags/signed");
+        let tampered = test_git_repo
+            .cat_file("-p", &tag_id)
+            .replace("Signed\n", "Tampered\n");
+        let tampered_id = String::from_utf8(
+            Command::cargo_bin("rust-git")
+                .unwrap()
+                .arg("hash-object")
+                .arg("-t")
+                .arg("tag")
+                .arg("-w")
+                .arg("--stdin")
+                .write_stdin(tampered)
+                .current_dir(test_git_repo.temp_dir.path())
+                .assert()
+                .success()
+                .get_output()
+                .stdout
+                .clone(),
+        )
+        .unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("verify-tag")
+            .arg(tampered_id.trim())
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .failure();
+    }
+}
@@ -1,5 +1,8 @@
 mod integration_tests {
+    use std::fs;
+
     use assert_cmd::{assert::OutputAssertExt, Command};
+    use assert_fs::fixture::{FileTouch, PathChild};
     use test_helpers::{TempDirExt, TestGitRepo};
 
     #[test]
@@ -55,4 +58,65 @@ mod integration_tests {
             "100644 blob 27961cd8c28d3fd780672e10e8c7c25d6eee10ee\ttest_in_dir.txt"
         );
     }
+
+    #[test]
+    fn should_write_changed_directories_after_cached_tree() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_dir("a");
+        test_git_repo.temp_dir.create_test_dir("b");
+        test_git_repo.temp_dir.create_test_file("a/one.txt", b"one");
+        test_git_repo.temp_dir.create_test_file("b/two.txt", b"two");
+
+        test_git_repo.init();
+        test_git_repo.add("a b");
+        let first_tree = test_git_repo.write_tree();
+        assert_eq!(test_git_repo.write_tree(), first_tree);
+
+        test_git_repo
+            .temp_dir
+            .create_test_file("b/three.txt", b"three");
+        test_git_repo.add("b/three.txt");
+        let second_tree = test_git_repo.write_tree();
+        assert_ne!(second_tree, first_tree);
+
+        // The tree matches one written from an index without any cached trees.
+        fs::remove_file(test_git_repo.git_dir().path().join("index")).unwrap();
+        test_git_repo.add("a b");
+        assert_eq!(test_git_repo.write_tree(), second_tree);
+    }
+
+    #[test]
+    fn should_not_save_cached_trees_without_optional_locks() {
+        let test_git_repo = TestGitRepo::new();
+        test_git_repo.temp_dir.create_test_file("test.txt", b"test");
+        test_git_repo.init();
+        test_git_repo.add("test.txt");
+        let index = test_git_repo.git_dir().child("index");
+        let index_bytes = fs::read(index.path()).unwrap();
+
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .arg("--no-optional-locks")
+            .arg("write-tree")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        Command::cargo_bin("rust-git")
+            .unwrap()
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .arg("write-tree")
+            .current_dir(test_git_repo.temp_dir.path())
+            .assert()
+            .success();
+        assert_eq!(fs::read(index.path()).unwrap(), index_bytes);
+
+        // A held lock doesn't prevent writing the tree.
+        test_git_repo.git_dir().child("index.lock").touch().unwrap();
+        test_git_repo.write_tree();
+        assert_eq!(fs::read(index.path()).unwrap(), index_bytes);
+
+        fs::remove_file(test_git_repo.git_dir().child("index.lock").path()).unwrap();
+        test_git_repo.write_tree();
+        assert_ne!(fs::read(index.path()).unwrap(), index_bytes);
+    }
 }